@@ -2,7 +2,7 @@ use std::{
     cmp, fmt,
     fs::File,
     future::Future,
-    io::{self, Read, Seek},
+    io,
     pin::Pin,
     task::{Context, Poll},
 };
@@ -14,85 +14,161 @@ use actix_web::{
 use bytes::Bytes;
 use futures_core::{ready, Stream};
 
+use crate::FileSource;
+
+/// Default number of bytes read from a file at once.
+pub(crate) const DEFAULT_CHUNK_SIZE: usize = 65_536;
+
 #[doc(hidden)]
-/// A helper created from a `std::fs::File` which reads the file
+/// A helper created from a `std::fs::File` or other [`FileSource`] which reads the file
 /// chunk-by-chunk on a `ThreadPool`.
-pub struct ChunkedReadFile {
+///
+/// The next chunk is read while the current one is being sent.
+pub struct ChunkedReadFile<F = File> {
     size: u64,
     offset: u64,
-    state: ChunkedReadFileState,
+    chunk_size: usize,
+    state: ChunkedReadFileState<F>,
     counter: u64,
 }
 
-enum ChunkedReadFileState {
-    File(Option<File>),
-    Future(JoinHandle<Result<(File, Bytes), io::Error>>),
+enum ChunkedReadFileState<F> {
+    File(Option<F>),
+    Future(JoinHandle<Result<(F, Bytes), io::Error>>),
 }
 
-impl ChunkedReadFile {
-    pub(crate) fn new(size: u64, offset: u64, file: File) -> Self {
+impl<F: FileSource> ChunkedReadFile<F> {
+    pub(crate) fn new(size: u64, offset: u64, file: F) -> Self {
         Self {
             size,
             offset,
+            chunk_size: DEFAULT_CHUNK_SIZE,
             state: ChunkedReadFileState::File(Some(file)),
             counter: 0,
         }
     }
+
+    /// Set the maximum number of bytes read at once.
+    pub(crate) fn chunk_size(mut self, chunk_size: usize) -> Self {
+        self.chunk_size = cmp::max(chunk_size, 1);
+        self
+    }
+
+    /// Start reading the next chunk on the thread pool.
+    fn read_next(&mut self, mut file: F) {
+        let offset = self.offset;
+        let max_bytes = cmp::min(
+            self.size.saturating_sub(self.counter),
+            self.chunk_size as u64,
+        ) as usize;
+
+        let fut = spawn_blocking(move || {
+            let bytes = file.read_at(offset, max_bytes)?;
+
+            // file was truncated while being served
+            if bytes.is_empty() {
+                return Err(io::ErrorKind::UnexpectedEof.into());
+            }
+
+            Ok((file, bytes))
+        });
+
+        self.state = ChunkedReadFileState::Future(fut);
+    }
 }
 
-impl fmt::Debug for ChunkedReadFile {
+// the file is never pinned
+impl<F> Unpin for ChunkedReadFile<F> {}
+
+impl<F> fmt::Debug for ChunkedReadFile<F> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.write_str("ChunkedReadFile")
     }
 }
 
-impl Stream for ChunkedReadFile {
+impl<F: FileSource> Stream for ChunkedReadFile<F> {
     type Item = Result<Bytes, Error>;
 
     fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
         let this = self.as_mut().get_mut();
         match this.state {
             ChunkedReadFileState::File(ref mut file) => {
-                let size = this.size;
-                let offset = this.offset;
-                let counter = this.counter;
-
-                if size == counter {
+                if this.size == this.counter {
                     Poll::Ready(None)
                 } else {
-                    let mut file = file
+                    let file = file
                         .take()
                         .expect("ChunkedReadFile polled after completion");
 
-                    let fut = spawn_blocking(move || {
-                        let max_bytes = cmp::min(size.saturating_sub(counter), 65_536) as usize;
-
-                        let mut buf = Vec::with_capacity(max_bytes);
-                        file.seek(io::SeekFrom::Start(offset))?;
-
-                        let n_bytes =
-                            file.by_ref().take(max_bytes as u64).read_to_end(&mut buf)?;
-
-                        if n_bytes == 0 {
-                            return Err(io::ErrorKind::UnexpectedEof.into());
-                        }
-
-                        Ok((file, Bytes::from(buf)))
-                    });
-                    this.state = ChunkedReadFileState::Future(fut);
+                    this.read_next(file);
                     self.poll_next(cx)
                 }
             }
             ChunkedReadFileState::Future(ref mut fut) => {
                 let (file, bytes) =
-                    ready!(Pin::new(fut).poll(cx)).map_err(|_| BlockingError)??;
-                this.state = ChunkedReadFileState::File(Some(file));
+                    ready!(Pin::new(fut).poll(cx)).map_err(|_| BlockingError::Canceled)??;
 
                 this.offset += bytes.len() as u64;
                 this.counter += bytes.len() as u64;
 
+                if this.counter < this.size {
+                    this.read_next(file);
+                } else {
+                    this.state = ChunkedReadFileState::File(Some(file));
+                }
+
                 Poll::Ready(Some(Ok(bytes)))
             }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Seek, SeekFrom, Write};
+
+    use futures_util::stream::StreamExt;
+
+    use super::*;
+
+    #[actix_rt::test]
+    async fn test_truncated_file() {
+        let mut file = tempfile::tempfile().unwrap();
+        file.write_all(&[b'x'; 1000]).unwrap();
+        file.seek(SeekFrom::Start(0)).unwrap();
+
+        let mut chunks =
+            ChunkedReadFile::new(1000, 0, file.try_clone().unwrap()).chunk_size(256);
+
+        let chunk = chunks.next().await.unwrap().unwrap();
+        assert_eq!(chunk.len(), 256);
+
+        file.set_len(300).unwrap();
+
+        let mut read = chunk.len();
+        let err = loop {
+            match chunks.next().await {
+                Some(Ok(chunk)) => read += chunk.len(),
+                Some(Err(err)) => break err,
+                None => panic!("truncated file must not end successfully"),
+            }
+        };
+
+        assert!(read <= 512);
+        assert!(err.to_string().contains("unexpected end of file"));
+    }
+
+    #[actix_rt::test]
+    async fn test_chunk_size() {
+        let mut file = tempfile::tempfile().unwrap();
+        file.write_all(&[b'x'; 1000]).unwrap();
+
+        let chunks = ChunkedReadFile::new(900, 50, file)
+            .chunk_size(400)
+            .map(|chunk| chunk.unwrap().len())
+            .collect::<Vec<_>>()
+            .await;
+
+        assert_eq!(chunks, vec![400, 400, 100]);
+    }
+}
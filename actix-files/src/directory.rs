@@ -1,8 +1,13 @@
-use std::{fmt::Write, fs::DirEntry, io, path::Path, path::PathBuf};
+use std::{fmt::Write, fs::DirEntry, io, path::Path, path::PathBuf, time::UNIX_EPOCH};
 
-use actix_web::{dev::ServiceResponse, HttpRequest, HttpResponse};
+use actix_web::{
+    dev::ServiceResponse,
+    http::header::{self, VARY},
+    HttpMessage, HttpRequest, HttpResponse,
+};
 use askama_escape::{escape as escape_html_entity, Html};
-use percent_encoding::{utf8_percent_encode, CONTROLS};
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use serde::Serialize;
 
 /// A directory; responds with the generated directory listing.
 #[derive(Debug)]
@@ -12,19 +17,26 @@ pub struct Directory {
 
     /// Path of subdirectory to generate listing for.
     pub path: PathBuf,
+
+    /// Whether hidden entries (names starting with `.`) are visible.
+    pub show_hidden: bool,
 }
 
 impl Directory {
     /// Create a new directory
     pub fn new(base: PathBuf, path: PathBuf) -> Directory {
-        Directory { base, path }
+        Directory {
+            base,
+            path,
+            show_hidden: false,
+        }
     }
 
     /// Is this entry visible from this directory?
     pub fn is_visible(&self, entry: &io::Result<DirEntry>) -> bool {
         if let Ok(ref entry) = *entry {
             if let Some(name) = entry.file_name().to_str() {
-                if name.starts_with('.') {
+                if !self.show_hidden && name.starts_with('.') {
                     return false;
                 }
             }
@@ -35,63 +47,149 @@ impl Directory {
         }
         false
     }
+
+    /// Returns the visible entries of this directory, sorted by name.
+    ///
+    /// `req` is the request for the directory; it is used to build the URL of each entry.
+    pub fn entries(&self, req: &HttpRequest) -> io::Result<Vec<DirectoryEntry>> {
+        let base = Path::new(req.path());
+        let mut entries = Vec::new();
+
+        for entry in self.path.read_dir()? {
+            if !self.is_visible(&entry) {
+                continue;
+            }
+
+            let entry = entry?;
+            let path = match entry.path().strip_prefix(&self.path) {
+                Ok(p) if cfg!(windows) => base.join(p).to_string_lossy().replace("\\", "/"),
+                Ok(p) => base.join(p).to_string_lossy().into_owned(),
+                Err(_) => continue,
+            };
+
+            let metadata = match entry.metadata() {
+                Ok(metadata) => metadata,
+                Err(_) => continue,
+            };
+
+            entries.push(DirectoryEntry {
+                name: entry.file_name().to_string_lossy().into_owned(),
+                href: utf8_percent_encode(&path, PATH).to_string(),
+                size: metadata.len(),
+                modified: metadata
+                    .modified()
+                    .ok()
+                    .and_then(|mtime| mtime.duration_since(UNIX_EPOCH).ok())
+                    .map(|dur| dur.as_secs()),
+                is_dir: metadata.is_dir(),
+            });
+        }
+
+        entries.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(entries)
+    }
 }
 
-pub(crate) type DirectoryRenderer =
-    dyn Fn(&Directory, &HttpRequest) -> Result<ServiceResponse, io::Error>;
+/// An entry of a directory listing.
+///
+/// Serialized as-is by the JSON directory listing.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DirectoryEntry {
+    /// File name of the entry.
+    pub name: String,
+
+    /// Percent-encoded URL path of the entry.
+    pub href: String,
+
+    /// Size in bytes.
+    pub size: u64,
 
-// show file url as relative to static path
-macro_rules! encode_file_url {
-    ($path:ident) => {
-        utf8_percent_encode(&$path, CONTROLS)
-    };
+    /// Last modification time in seconds since the Unix epoch, if available.
+    pub modified: Option<u64>,
+
+    /// Whether the entry is a directory.
+    pub is_dir: bool,
+}
+
+/// Built-in directory listing renderers.
+///
+/// Used with [`Files::directory_renderer`](crate::Files::directory_renderer). Custom renderers
+/// can be set with [`Files::files_listing_renderer`](crate::Files::files_listing_renderer).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DirectoryRenderer {
+    /// An HTML page with a link to each entry. This is the default.
+    Html,
+
+    /// A JSON array of [`DirectoryEntry`] objects.
+    Json,
+
+    /// JSON if the request's `Accept` header prefers `application/json` over `text/html`,
+    /// HTML otherwise.
+    Negotiated,
 }
 
-// " -- &quot;  & -- &amp;  ' -- &#x27;  < -- &lt;  > -- &gt;  / -- &#x2f;
-macro_rules! encode_file_name {
-    ($entry:ident) => {
-        escape_html_entity(&$entry.file_name().to_string_lossy(), Html)
-    };
+impl DirectoryRenderer {
+    /// Render the listing of `dir` in response to `req`.
+    pub fn render(
+        self,
+        dir: &Directory,
+        req: &HttpRequest,
+    ) -> Result<ServiceResponse, io::Error> {
+        match self {
+            DirectoryRenderer::Html => directory_listing(dir, req),
+            DirectoryRenderer::Json => json_directory_listing(dir, req),
+            DirectoryRenderer::Negotiated => {
+                let prefers_json = req
+                    .get_header::<header::Accept>()
+                    .and_then(|accept| {
+                        accept.negotiate(&[mime::TEXT_HTML, mime::APPLICATION_JSON])
+                    })
+                    .map_or(false, |mime| mime == mime::APPLICATION_JSON);
+
+                let mut res = if prefers_json {
+                    json_directory_listing(dir, req)?
+                } else {
+                    directory_listing(dir, req)?
+                };
+
+                res.headers_mut()
+                    .append(VARY, header::HeaderValue::from_static("accept"));
+                Ok(res)
+            }
+        }
+    }
 }
 
+pub(crate) type DirectoryRenderFn =
+    dyn Fn(&Directory, &HttpRequest) -> Result<ServiceResponse, io::Error>;
+
+/// Characters escaped in entry URLs; everything but unreserved characters and `/`.
+const PATH: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'.')
+    .remove(b'_')
+    .remove(b'~')
+    .remove(b'/');
+
 pub(crate) fn directory_listing(
     dir: &Directory,
     req: &HttpRequest,
 ) -> Result<ServiceResponse, io::Error> {
     let index_of = format!("Index of {}", req.path());
     let mut body = String::new();
-    let base = Path::new(req.path());
 
-    for entry in dir.path.read_dir()? {
-        if dir.is_visible(&entry) {
-            let entry = entry.unwrap();
-            let p = match entry.path().strip_prefix(&dir.path) {
-                Ok(p) if cfg!(windows) => base.join(p).to_string_lossy().replace("\\", "/"),
-                Ok(p) => base.join(p).to_string_lossy().into_owned(),
-                Err(_) => continue,
-            };
+    for entry in dir.entries(req)? {
+        // " -- &quot;  & -- &amp;  ' -- &#x27;  < -- &lt;  > -- &gt;  / -- &#x2f;
+        let name = escape_html_entity(&entry.name, Html);
 
-            // if file is a directory, add '/' to the end of the name
-            if let Ok(metadata) = entry.metadata() {
-                if metadata.is_dir() {
-                    let _ = write!(
-                        body,
-                        "<li><a href=\"{}\">{}/</a></li>",
-                        encode_file_url!(p),
-                        encode_file_name!(entry),
-                    );
-                } else {
-                    let _ = write!(
-                        body,
-                        "<li><a href=\"{}\">{}</a></li>",
-                        encode_file_url!(p),
-                        encode_file_name!(entry),
-                    );
-                }
-            } else {
-                continue;
-            }
-        }
+        // if file is a directory, add '/' to the end of the name
+        let _ = write!(
+            body,
+            "<li><a href=\"{}\">{}{}</a></li>",
+            escape_html_entity(&entry.href, Html),
+            name,
+            if entry.is_dir { "/" } else { "" },
+        );
     }
 
     let html = format!(
@@ -110,3 +208,17 @@ pub(crate) fn directory_listing(
             .body(html),
     ))
 }
+
+pub(crate) fn json_directory_listing(
+    dir: &Directory,
+    req: &HttpRequest,
+) -> Result<ServiceResponse, io::Error> {
+    let body = serde_json::to_string(&dir.entries(req)?)?;
+
+    Ok(ServiceResponse::new(
+        req.clone(),
+        HttpResponse::Ok()
+            .content_type(mime::APPLICATION_JSON)
+            .body(body),
+    ))
+}
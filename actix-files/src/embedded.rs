@@ -0,0 +1,375 @@
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    fmt, io,
+    path::{Component, Path, PathBuf},
+    rc::Rc,
+    task::Poll,
+    time::SystemTime,
+};
+
+use actix_service::{boxed, IntoServiceFactory, Service, ServiceFactory, ServiceFactoryExt};
+use actix_web::{
+    dev::{AppService, HttpServiceFactory, ResourceDef, ServiceRequest, ServiceResponse},
+    error::Error,
+    guard::Guard,
+    http::header::DispositionType,
+};
+use bytes::Bytes;
+use futures_util::future::{ok, Either, FutureExt, LocalBoxFuture, Ready};
+
+use crate::{
+    named,
+    service::{is_method_valid, method_not_allowed},
+    FileMetadata, FileSource, HttpNewService, HttpService, MimeOverride, NamedFile,
+    PathBufWrap,
+};
+
+/// A file compiled into the binary.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EmbeddedFile {
+    content: &'static [u8],
+    modified: SystemTime,
+}
+
+impl EmbeddedFile {
+    /// Create a new embedded file from its content and modification time.
+    pub fn new(content: &'static [u8], modified: SystemTime) -> EmbeddedFile {
+        EmbeddedFile { content, modified }
+    }
+
+    /// Returns the content of the file.
+    pub fn content(&self) -> &'static [u8] {
+        self.content
+    }
+}
+
+impl FileSource for EmbeddedFile {
+    fn metadata(&self) -> io::Result<FileMetadata> {
+        Ok(FileMetadata {
+            len: self.content.len() as u64,
+            modified: Some(self.modified),
+            ino: 0,
+        })
+    }
+
+    fn read_at(&mut self, offset: u64, max_bytes: usize) -> io::Result<Bytes> {
+        let start = offset.min(self.content.len() as u64) as usize;
+        let end = start + max_bytes.min(self.content.len() - start);
+        Ok(Bytes::from_static(&self.content[start..end]))
+    }
+}
+
+/// Static files handling service for files compiled into the binary.
+///
+/// Responses are built from [`NamedFile`]s, so conditional and range requests as well as
+/// `Content-Type` guessing work like for [`Files`](crate::Files).
+///
+/// `EmbeddedFiles` service must be registered with `App::service()` method.
+///
+/// ```rust
+/// use std::time::SystemTime;
+/// use actix_web::App;
+/// use actix_files::EmbeddedFiles;
+///
+/// let app = App::new().service(EmbeddedFiles::new(
+///     "/static",
+///     vec![("css/app.css", (&b"body {}"[..], SystemTime::UNIX_EPOCH))],
+/// ));
+/// ```
+pub struct EmbeddedFiles {
+    path: String,
+    files: Rc<HashMap<PathBuf, EmbeddedFile>>,
+    index: Option<String>,
+    default: Rc<RefCell<Option<Rc<HttpNewService>>>>,
+    mime_override: Option<Rc<MimeOverride>>,
+    file_flags: named::Flags,
+    guards: Option<Rc<dyn Guard>>,
+    hidden_files: bool,
+}
+
+impl fmt::Debug for EmbeddedFiles {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("EmbeddedFiles")
+    }
+}
+
+impl Clone for EmbeddedFiles {
+    fn clone(&self) -> Self {
+        Self {
+            path: self.path.clone(),
+            files: self.files.clone(),
+            index: self.index.clone(),
+            default: self.default.clone(),
+            mime_override: self.mime_override.clone(),
+            file_flags: self.file_flags,
+            guards: self.guards.clone(),
+            hidden_files: self.hidden_files,
+        }
+    }
+}
+
+impl EmbeddedFiles {
+    /// Create new `EmbeddedFiles` instance serving `files` at `mount_path`.
+    ///
+    /// `files` maps the path of each file, relative to `mount_path`, to its content and
+    /// modification time.
+    pub fn new<I, P>(mount_path: &str, files: I) -> EmbeddedFiles
+    where
+        I: IntoIterator<Item = (P, (&'static [u8], SystemTime))>,
+        P: AsRef<Path>,
+    {
+        let files = files
+            .into_iter()
+            .map(|(path, (content, modified))| {
+                (
+                    normalize(path.as_ref()),
+                    EmbeddedFile::new(content, modified),
+                )
+            })
+            .collect();
+
+        EmbeddedFiles {
+            path: mount_path.to_owned(),
+            files: Rc::new(files),
+            index: None,
+            default: Rc::new(RefCell::new(None)),
+            mime_override: None,
+            file_flags: named::Flags::default(),
+            guards: None,
+            hidden_files: false,
+        }
+    }
+
+    /// Specifies mime override callback
+    pub fn mime_override<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&mime::Name<'_>) -> DispositionType + 'static,
+    {
+        self.mime_override = Some(Rc::new(f));
+        self
+    }
+
+    /// Set index file
+    ///
+    /// Serves specific index file for directory paths, e.g. `index.html` for `/`.
+    pub fn index_file<T: Into<String>>(mut self, index: T) -> Self {
+        self.index = Some(index.into());
+        self
+    }
+
+    /// Specifies whether to use ETag or not.
+    ///
+    /// Default is true.
+    #[inline]
+    pub fn use_etag(mut self, value: bool) -> Self {
+        self.file_flags.set(named::Flags::ETAG, value);
+        self
+    }
+
+    /// Specifies whether to use Last-Modified or not.
+    ///
+    /// Default is true.
+    #[inline]
+    pub fn use_last_modified(mut self, value: bool) -> Self {
+        self.file_flags.set(named::Flags::LAST_MD, value);
+        self
+    }
+
+    /// Specifies whether text responses should signal a UTF-8 encoding.
+    ///
+    /// Default is false (but will default to true in a future version).
+    #[inline]
+    pub fn prefer_utf8(mut self, value: bool) -> Self {
+        self.file_flags.set(named::Flags::PREFER_UTF8, value);
+        self
+    }
+
+    /// Specifies custom guards to use for files.
+    ///
+    /// Default behaviour allows GET and HEAD.
+    #[inline]
+    pub fn use_guards<G: Guard + 'static>(mut self, guards: G) -> Self {
+        self.guards = Some(Rc::new(guards));
+        self
+    }
+
+    /// Disable `Content-Disposition` header.
+    ///
+    /// By default Content-Disposition` header is enabled.
+    #[inline]
+    pub fn disable_content_disposition(mut self) -> Self {
+        self.file_flags.remove(named::Flags::CONTENT_DISPOSITION);
+        self
+    }
+
+    /// Sets default handler which is used when no matched file could be found.
+    pub fn default_handler<F, U>(mut self, f: F) -> Self
+    where
+        F: IntoServiceFactory<U, ServiceRequest>,
+        U: ServiceFactory<
+                ServiceRequest,
+                Config = (),
+                Response = ServiceResponse,
+                Error = Error,
+            > + 'static,
+    {
+        // create and configure default resource
+        self.default = Rc::new(RefCell::new(Some(Rc::new(boxed::factory(
+            f.into_factory().map_init_err(|_| ()),
+        )))));
+
+        self
+    }
+
+    /// Enables serving hidden files and directories, allowing a leading dots in url fragments.
+    #[inline]
+    pub fn use_hidden_files(mut self) -> Self {
+        self.hidden_files = true;
+        self
+    }
+}
+
+/// Strips root and `.` components so paths compare equal to parsed request paths.
+fn normalize(path: &Path) -> PathBuf {
+    path.components()
+        .filter(|comp| matches!(comp, Component::Normal(_)))
+        .collect()
+}
+
+impl HttpServiceFactory for EmbeddedFiles {
+    fn register(self, config: &mut AppService) {
+        if self.default.borrow().is_none() {
+            *self.default.borrow_mut() = Some(config.default_service());
+        }
+
+        let rdef = if config.is_root() {
+            ResourceDef::root_prefix(&self.path)
+        } else {
+            ResourceDef::prefix(&self.path)
+        };
+
+        config.register_service(rdef, None, self, None)
+    }
+}
+
+impl ServiceFactory<ServiceRequest> for EmbeddedFiles {
+    type Response = ServiceResponse;
+    type Error = Error;
+    type Config = ();
+    type Service = EmbeddedFilesService;
+    type InitError = ();
+    type Future = LocalBoxFuture<'static, Result<Self::Service, Self::InitError>>;
+
+    fn new_service(&self, _: ()) -> Self::Future {
+        let mut srv = EmbeddedFilesService {
+            files: self.files.clone(),
+            index: self.index.clone(),
+            default: None,
+            mime_override: self.mime_override.clone(),
+            file_flags: self.file_flags,
+            guards: self.guards.clone(),
+            hidden_files: self.hidden_files,
+        };
+
+        if let Some(ref default) = *self.default.borrow() {
+            default
+                .new_service(())
+                .map(move |result| match result {
+                    Ok(default) => {
+                        srv.default = Some(default);
+                        Ok(srv)
+                    }
+                    Err(_) => Err(()),
+                })
+                .boxed_local()
+        } else {
+            ok(srv).boxed_local()
+        }
+    }
+}
+
+/// Assembled embedded file serving service.
+pub struct EmbeddedFilesService {
+    files: Rc<HashMap<PathBuf, EmbeddedFile>>,
+    index: Option<String>,
+    default: Option<HttpService>,
+    mime_override: Option<Rc<MimeOverride>>,
+    file_flags: named::Flags,
+    guards: Option<Rc<dyn Guard>>,
+    hidden_files: bool,
+}
+
+type EmbeddedFilesServiceFuture = Either<
+    Ready<Result<ServiceResponse, Error>>,
+    LocalBoxFuture<'static, Result<ServiceResponse, Error>>,
+>;
+
+impl EmbeddedFilesService {
+    fn handle_err(&self, e: io::Error, req: ServiceRequest) -> EmbeddedFilesServiceFuture {
+        log::debug!("Failed to handle {}: {}", req.path(), e);
+
+        if let Some(ref default) = self.default {
+            Either::Right(default.call(req))
+        } else {
+            Either::Left(ok(req.error_response(e)))
+        }
+    }
+
+    /// Looks up the file at `path`, or the index file if `path` is a directory.
+    fn find(&self, path: &Path) -> Option<(PathBuf, EmbeddedFile)> {
+        if let Some(file) = self.files.get(path) {
+            return Some((path.to_owned(), *file));
+        }
+
+        let index = path.join(self.index.as_ref()?);
+        let file = *self.files.get(&index)?;
+        Some((index, file))
+    }
+}
+
+impl fmt::Debug for EmbeddedFilesService {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("EmbeddedFilesService")
+    }
+}
+
+impl Service<ServiceRequest> for EmbeddedFilesService {
+    type Response = ServiceResponse;
+    type Error = Error;
+    type Future = EmbeddedFilesServiceFuture;
+
+    actix_service::always_ready!();
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        if !is_method_valid(self.guards.as_deref(), &req) {
+            return Either::Left(ok(method_not_allowed(req)));
+        }
+
+        let real_path =
+            match PathBufWrap::parse_path(req.match_info().path(), self.hidden_files) {
+                Ok(item) => item,
+                Err(e) => return Either::Left(ok(req.error_response(e))),
+            };
+
+        let (path, file) = match self.find(real_path.as_ref()) {
+            Some(found) => found,
+            None => return self.handle_err(io::ErrorKind::NotFound.into(), req),
+        };
+
+        let mut named_file = match NamedFile::from_source(file, path) {
+            Ok(named_file) => named_file,
+            Err(e) => return self.handle_err(e, req),
+        };
+
+        if let Some(ref mime_override) = self.mime_override {
+            let new_disposition = mime_override(&named_file.content_type.type_());
+            named_file.content_disposition.disposition = new_disposition;
+        }
+        named_file.flags = self.file_flags;
+
+        let (req, _) = req.into_parts();
+        let res = named_file.into_response(&req);
+        Either::Left(ok(ServiceResponse::new(req, res)))
+    }
+}
@@ -1,3 +1,7 @@
+use actix_web::http::{
+    header::{HeaderMap, ACCEPT_ENCODING},
+    ContentEncoding,
+};
 use mime::Mime;
 
 /// Transforms MIME `text/*` types into their UTF-8 equivalent, if supported.
@@ -39,6 +43,52 @@ pub(crate) fn equiv_utf8_text(ct: Mime) -> Mime {
     ct
 }
 
+/// Returns the file extension used for files precompressed with `encoding`, if supported.
+pub(crate) fn precompressed_ext(encoding: ContentEncoding) -> Option<&'static str> {
+    match encoding {
+        ContentEncoding::Br => Some("br"),
+        ContentEncoding::Gzip => Some("gz"),
+        _ => None,
+    }
+}
+
+/// Returns true if the `Accept-Encoding` headers allow `encoding`, either by name or through `*`.
+///
+/// A missing `Accept-Encoding` header does not allow any encoding.
+pub(crate) fn accepts_encoding(headers: &HeaderMap, encoding: ContentEncoding) -> bool {
+    let mut wildcard = false;
+
+    let items = headers
+        .get_all(ACCEPT_ENCODING)
+        .filter_map(|val| val.to_str().ok())
+        .flat_map(|val| val.split(','));
+
+    for item in items {
+        let mut params = item.split(';');
+        let name = params.next().unwrap_or("").trim();
+
+        let quality = params
+            .filter_map(|param| {
+                let (key, val) = param.split_at(param.find('=')?);
+                if key.trim().eq_ignore_ascii_case("q") {
+                    val[1..].trim().parse::<f32>().ok()
+                } else {
+                    None
+                }
+            })
+            .next()
+            .unwrap_or(1.0);
+
+        if name.eq_ignore_ascii_case(encoding.as_str()) {
+            return quality > 0.0;
+        } else if name == "*" {
+            wildcard = quality > 0.0;
+        }
+    }
+
+    wildcard
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -49,4 +99,24 @@ mod tests {
         assert_eq!(equiv_utf8_text(mime::TEXT_XML), mime::TEXT_XML);
         assert_eq!(equiv_utf8_text(mime::IMAGE_PNG), mime::IMAGE_PNG);
     }
+
+    #[test]
+    fn test_accepts_encoding() {
+        use actix_web::http::header::HeaderValue;
+
+        let accepts = |val: &'static str, encoding| {
+            let mut headers = HeaderMap::new();
+            headers.insert(ACCEPT_ENCODING, HeaderValue::from_static(val));
+            accepts_encoding(&headers, encoding)
+        };
+
+        assert!(!accepts_encoding(&HeaderMap::new(), ContentEncoding::Gzip));
+        assert!(accepts("gzip, br", ContentEncoding::Br));
+        assert!(accepts("GZIP;q=0.5", ContentEncoding::Gzip));
+        assert!(!accepts("gzip;q=0", ContentEncoding::Gzip));
+        assert!(!accepts("deflate", ContentEncoding::Gzip));
+        assert!(accepts("*", ContentEncoding::Br));
+        assert!(!accepts("br;q=0, *", ContentEncoding::Br));
+        assert!(!accepts("*;q=0", ContentEncoding::Gzip));
+    }
 }
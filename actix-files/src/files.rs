@@ -1,18 +1,28 @@
-use std::{cell::RefCell, fmt, io, path::PathBuf, rc::Rc};
+use std::{
+    cell::RefCell,
+    fmt, io,
+    path::{Path, PathBuf},
+    rc::Rc,
+};
 
 use actix_service::{boxed, IntoServiceFactory, ServiceFactory, ServiceFactoryExt};
 use actix_web::{
     dev::{AppService, HttpServiceFactory, ResourceDef, ServiceRequest, ServiceResponse},
     error::Error,
     guard::Guard,
-    http::header::DispositionType,
+    http::{
+        header::{
+            CacheControl, ContentDisposition, DispositionType, HeaderValue, IntoHeaderValue,
+        },
+        ContentEncoding,
+    },
     HttpRequest,
 };
 use futures_util::future::{ok, FutureExt, LocalBoxFuture};
 
 use crate::{
-    directory_listing, named, Directory, DirectoryRenderer, FilesService, HttpNewService,
-    MimeOverride,
+    directory_listing, named, ContentDispositionFn, Directory, DirectoryRenderFn,
+    DirectoryRenderer, FilesService, Fingerprinted, HttpNewService, MimeOverride,
 };
 
 /// Static files handling service.
@@ -33,11 +43,18 @@ pub struct Files {
     show_index: bool,
     redirect_to_slash: bool,
     default: Rc<RefCell<Option<Rc<HttpNewService>>>>,
-    renderer: Rc<DirectoryRenderer>,
+    renderer: Rc<DirectoryRenderFn>,
     mime_override: Option<Rc<MimeOverride>>,
+    content_disposition_fn: Option<Rc<ContentDispositionFn>>,
     file_flags: named::Flags,
     guards: Option<Rc<dyn Guard>>,
     hidden_files: bool,
+    list_hidden_files: bool,
+    precompressed: Vec<ContentEncoding>,
+    cache_control: Option<HeaderValue>,
+    fingerprinted: Option<Rc<Fingerprinted>>,
+    spa: bool,
+    spa_excluded: Vec<String>,
 }
 
 impl fmt::Debug for Files {
@@ -58,8 +75,15 @@ impl Clone for Files {
             file_flags: self.file_flags,
             path: self.path.clone(),
             mime_override: self.mime_override.clone(),
+            content_disposition_fn: self.content_disposition_fn.clone(),
             guards: self.guards.clone(),
             hidden_files: self.hidden_files,
+            list_hidden_files: self.list_hidden_files,
+            precompressed: self.precompressed.clone(),
+            cache_control: self.cache_control.clone(),
+            fingerprinted: self.fingerprinted.clone(),
+            spa: self.spa,
+            spa_excluded: self.spa_excluded.clone(),
         }
     }
 }
@@ -102,9 +126,16 @@ impl Files {
             default: Rc::new(RefCell::new(None)),
             renderer: Rc::new(directory_listing),
             mime_override: None,
+            content_disposition_fn: None,
             file_flags: named::Flags::default(),
             guards: None,
             hidden_files: false,
+            list_hidden_files: false,
+            precompressed: Vec::new(),
+            cache_control: None,
+            fingerprinted: None,
+            spa: false,
+            spa_excluded: Vec::new(),
         }
     }
 
@@ -124,7 +155,30 @@ impl Files {
         self
     }
 
-    /// Set custom directory renderer
+    /// Set custom directory renderer.
+    ///
+    /// The renderer is called for every directory listing. Use [`Directory::entries`] to list
+    /// the visible entries of the directory.
+    ///
+    /// ```rust
+    /// use actix_web::{dev::ServiceResponse, HttpResponse};
+    /// use actix_files::Files;
+    ///
+    /// let files = Files::new("/static", ".")
+    ///     .show_files_listing()
+    ///     .files_listing_renderer(|dir, req| {
+    ///         let names = dir
+    ///             .entries(req)?
+    ///             .into_iter()
+    ///             .map(|entry| entry.name)
+    ///             .collect::<Vec<_>>();
+    ///
+    ///         Ok(ServiceResponse::new(
+    ///             req.clone(),
+    ///             HttpResponse::Ok().body(names.join("\n")),
+    ///         ))
+    ///     });
+    /// ```
     pub fn files_listing_renderer<F>(mut self, f: F) -> Self
     where
         for<'r, 's> F:
@@ -134,6 +188,25 @@ impl Files {
         self
     }
 
+    /// Set built-in directory renderer.
+    ///
+    /// By default [`DirectoryRenderer::Html`] is used.
+    pub fn directory_renderer(mut self, renderer: DirectoryRenderer) -> Self {
+        self.renderer =
+            Rc::new(move |dir: &Directory, req: &HttpRequest| renderer.render(dir, req));
+        self
+    }
+
+    /// Show hidden files and directories (names starting with `.`) in directory listings.
+    ///
+    /// This does not allow serving them; see [`Files::use_hidden_files`].
+    ///
+    /// By default hidden files are not listed.
+    pub fn list_hidden_files(mut self, value: bool) -> Self {
+        self.list_hidden_files = value;
+        self
+    }
+
     /// Specifies mime override callback
     pub fn mime_override<F>(mut self, f: F) -> Self
     where
@@ -143,6 +216,39 @@ impl Files {
         self
     }
 
+    /// Sets a callback that chooses the `Content-Disposition` of each served file from its path
+    /// and content type.
+    ///
+    /// Takes precedence over [`mime_override`](Self::mime_override). A non-ASCII `filename`
+    /// parameter is encoded like in [`NamedFile::set_content_disposition`](crate::NamedFile).
+    ///
+    /// ```
+    /// use actix_files::Files;
+    /// use actix_web::http::header::{ContentDisposition, DispositionParam, DispositionType};
+    ///
+    /// // show PDFs in the browser instead of downloading them
+    /// let files = Files::new("/docs", ".").content_disposition_fn(|path, mime| {
+    ///     let disposition = if mime.subtype() == mime::PDF {
+    ///         DispositionType::Inline
+    ///     } else {
+    ///         DispositionType::Attachment
+    ///     };
+    ///     let name = path.file_name().unwrap().to_string_lossy().into_owned();
+    ///
+    ///     ContentDisposition {
+    ///         disposition,
+    ///         parameters: vec![DispositionParam::Filename(name)],
+    ///     }
+    /// });
+    /// ```
+    pub fn content_disposition_fn<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&Path, &mime::Mime) -> ContentDisposition + 'static,
+    {
+        self.content_disposition_fn = Some(Rc::new(f));
+        self
+    }
+
     /// Set index file
     ///
     /// Shows specific index file for directory "/" instead of
@@ -222,6 +328,109 @@ impl Files {
         self.hidden_files = true;
         self
     }
+
+    /// Serve precompressed variants of files when the client accepts their encoding.
+    ///
+    /// For a request to `app.js`, the sidecar files `app.js.br` and `app.js.gz` are probed for
+    /// [`ContentEncoding::Br`] and [`ContentEncoding::Gzip`] respectively, in the given order,
+    /// falling back to the file itself. Other encodings are ignored.
+    ///
+    /// A sidecar file is served with the `Content-Type` and `Content-Disposition` of the original
+    /// file, while `ETag`, `Last-Modified` and byte ranges refer to the encoded content. All
+    /// responses include a `Vary: Accept-Encoding` header.
+    ///
+    /// By default precompressed files are not served.
+    ///
+    /// ```rust
+    /// use actix_web::{http::ContentEncoding, App};
+    /// use actix_files::Files;
+    ///
+    /// let app = App::new().service(
+    ///     Files::new("/static", ".")
+    ///         .use_precompressed(&[ContentEncoding::Br, ContentEncoding::Gzip]),
+    /// );
+    /// ```
+    pub fn use_precompressed(mut self, encodings: &[ContentEncoding]) -> Self {
+        self.precompressed = encodings.to_vec();
+        self
+    }
+
+    /// Sets the `Cache-Control` header of file and directory listing responses.
+    ///
+    /// Files matched by [`Files::fingerprinted`] use their own value instead. The header is also
+    /// sent with `304 Not Modified` responses. `ETag` and `Last-Modified` are not affected.
+    ///
+    /// By default no `Cache-Control` header is set.
+    ///
+    /// ```rust
+    /// use actix_web::http::header::{CacheControl, CacheDirective};
+    /// use actix_files::Files;
+    ///
+    /// let files = Files::new("/static", ".")
+    ///     .cache_control(CacheControl(vec![CacheDirective::MaxAge(3600)]));
+    /// ```
+    pub fn cache_control(mut self, cache_control: CacheControl) -> Self {
+        match cache_control.try_into_value() {
+            Ok(value) => self.cache_control = Some(value),
+            Err(e) => log::error!("Invalid Cache-Control header: {}", e),
+        }
+        self
+    }
+
+    /// Marks files whose names contain a content hash, such as `app.3f9a2c.js`.
+    ///
+    /// The callback is called with the file name. Matching files never change, so they are
+    /// served with `Cache-Control: public, max-age=31536000, immutable` instead of the value set
+    /// by [`Files::cache_control`].
+    ///
+    /// ```rust
+    /// use actix_files::Files;
+    ///
+    /// let files = Files::new("/static", ".").fingerprinted(|name| {
+    ///     // `<name>.<hash>.<ext>`
+    ///     let mut parts = name.rsplit('.');
+    ///     parts.nth(1).map_or(false, |hash| {
+    ///         hash.len() >= 6 && hash.bytes().all(|b| b.is_ascii_hexdigit())
+    ///     })
+    /// });
+    /// ```
+    pub fn fingerprinted<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&str) -> bool + 'static,
+    {
+        self.fingerprinted = Some(Rc::new(f));
+        self
+    }
+
+    /// Serve the index file for paths that do not exist, for single-page applications.
+    ///
+    /// The index file set by [`Files::index_file`] is served with `200 OK`, or `index.html` if
+    /// none is set. The request is not redirected, so client-side routing sees the original URL.
+    /// Invalid paths and files which can not be read are still rejected.
+    ///
+    /// By default unknown paths are handled by the default handler.
+    ///
+    /// ```rust
+    /// use actix_files::Files;
+    ///
+    /// let files = Files::new("/", "./dist")
+    ///     .index_file("index.html")
+    ///     .spa()
+    ///     .spa_exclude("/api");
+    /// ```
+    pub fn spa(mut self) -> Self {
+        self.spa = true;
+        self
+    }
+
+    /// Excludes paths under `prefix` from the [`Files::spa`] fallback.
+    ///
+    /// The prefix is relative to the mount path and matches whole path segments, so `/api`
+    /// matches `/api` and `/api/users` but not `/apis`.
+    pub fn spa_exclude<T: Into<String>>(mut self, prefix: T) -> Self {
+        self.spa_excluded.push(prefix.into());
+        self
+    }
 }
 
 impl HttpServiceFactory for Files {
@@ -257,9 +466,16 @@ impl ServiceFactory<ServiceRequest> for Files {
             default: None,
             renderer: self.renderer.clone(),
             mime_override: self.mime_override.clone(),
+            content_disposition_fn: self.content_disposition_fn.clone(),
             file_flags: self.file_flags,
             guards: self.guards.clone(),
             hidden_files: self.hidden_files,
+            list_hidden_files: self.list_hidden_files,
+            precompressed: self.precompressed.clone(),
+            cache_control: self.cache_control.clone(),
+            fingerprinted: self.fingerprinted.clone(),
+            spa: self.spa,
+            spa_excluded: self.spa_excluded.clone(),
         };
 
         if let Some(ref default) = *self.default.borrow() {
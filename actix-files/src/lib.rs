@@ -14,16 +14,19 @@
 #![deny(rust_2018_idioms)]
 #![warn(missing_docs, missing_debug_implementations)]
 
+use std::path::Path;
+
 use actix_service::boxed::{BoxService, BoxServiceFactory};
 use actix_web::{
     dev::{ServiceRequest, ServiceResponse},
     error::Error,
-    http::header::DispositionType,
+    http::header::{ContentDisposition, DispositionType},
 };
 use mime_guess::from_ext;
 
 mod chunked;
 mod directory;
+mod embedded;
 mod encoding;
 mod error;
 mod files;
@@ -31,15 +34,18 @@ mod named;
 mod path_buf;
 mod range;
 mod service;
+mod source;
 
 pub use crate::chunked::ChunkedReadFile;
-pub use crate::directory::Directory;
+pub use crate::directory::{Directory, DirectoryEntry, DirectoryRenderer};
+pub use crate::embedded::{EmbeddedFile, EmbeddedFiles, EmbeddedFilesService};
 pub use crate::files::Files;
 pub use crate::named::NamedFile;
 pub use crate::range::HttpRange;
 pub use crate::service::FilesService;
+pub use crate::source::{FileMetadata, FileSource};
 
-use self::directory::{directory_listing, DirectoryRenderer};
+use self::directory::{directory_listing, DirectoryRenderFn};
 use self::error::FilesError;
 use self::path_buf::PathBufWrap;
 
@@ -56,6 +62,10 @@ pub fn file_extension_to_mime(ext: &str) -> mime::Mime {
 
 type MimeOverride = dyn Fn(&mime::Name<'_>) -> DispositionType;
 
+type ContentDispositionFn = dyn Fn(&Path, &mime::Mime) -> ContentDisposition;
+
+type Fingerprinted = dyn Fn(&str) -> bool;
+
 #[cfg(test)]
 mod tests {
     use std::{
@@ -227,7 +237,36 @@ mod tests {
         );
         assert_eq!(
             resp.headers().get(header::CONTENT_DISPOSITION).unwrap(),
-            "inline; filename=\"貨物.toml\"; filename*=UTF-8''%E8%B2%A8%E7%89%A9.toml"
+            "inline; filename=\"__.toml\"; filename*=UTF-8''%E8%B2%A8%E7%89%A9.toml"
+        );
+    }
+
+    #[actix_rt::test]
+    async fn test_named_file_utf8_filename_inline() {
+        let file =
+            NamedFile::from_file(File::open("Cargo.toml").unwrap(), "résumé.pdf").unwrap();
+        let req = TestRequest::default().to_http_request();
+        let resp = file.respond_to(&req).await.unwrap();
+        assert_eq!(
+            resp.headers().get(header::CONTENT_DISPOSITION).unwrap(),
+            "attachment; filename=\"r_sum_.pdf\"; filename*=UTF-8''r%C3%A9sum%C3%A9.pdf"
+        );
+
+        let cd = ContentDisposition {
+            disposition: DispositionType::Inline,
+            parameters: vec![DispositionParam::Filename(String::from("résumé.pdf"))],
+        };
+        let file = NamedFile::from_file(File::open("Cargo.toml").unwrap(), "résumé.pdf")
+            .unwrap()
+            .set_content_disposition(cd);
+        let resp = file.respond_to(&req).await.unwrap();
+        assert_eq!(
+            resp.headers().get(header::CONTENT_TYPE).unwrap(),
+            "application/pdf"
+        );
+        assert_eq!(
+            resp.headers().get(header::CONTENT_DISPOSITION).unwrap(),
+            "inline; filename=\"r_sum_.pdf\"; filename*=UTF-8''r%C3%A9sum%C3%A9.pdf"
         );
     }
 
@@ -386,6 +425,36 @@ mod tests {
         assert_eq!(content_disposition, "attachment; filename=\"Cargo.toml\"");
     }
 
+    #[actix_rt::test]
+    async fn test_content_disposition_fn() {
+        let srv = test::init_service(
+            App::new().service(
+                Files::new("/", ".")
+                    .mime_override(|_| DispositionType::Attachment)
+                    .content_disposition_fn(|path, mime| {
+                        assert!(path.ends_with("tests/test.binary"));
+                        assert_eq!(*mime, mime::APPLICATION_OCTET_STREAM);
+
+                        ContentDisposition {
+                            disposition: DispositionType::Inline,
+                            parameters: vec![DispositionParam::Filename(String::from(
+                                "données.bin",
+                            ))],
+                        }
+                    }),
+            ),
+        )
+        .await;
+
+        let req = TestRequest::get().uri("/tests/test.binary").to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(
+            res.headers().get(header::CONTENT_DISPOSITION).unwrap(),
+            "inline; filename=\"donn_es.bin\"; filename*=UTF-8''donn%C3%A9es.bin"
+        );
+    }
+
     #[actix_rt::test]
     async fn test_named_file_ranges_status_code() {
         let srv = test::init_service(
@@ -630,6 +699,355 @@ mod tests {
         assert!(format!("{:?}", bytes).contains("/tests/test.png"));
     }
 
+    #[actix_rt::test]
+    async fn test_precompressed_files() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("app.js"), "identity").unwrap();
+        fs::write(dir.path().join("app.js.br"), "brotli").unwrap();
+        fs::write(dir.path().join("app.js.gz"), "gzip").unwrap();
+
+        let srv = test::init_service(App::new().wrap(Compress::default()).service(
+            Files::new("/", dir.path()).use_precompressed(&[
+                header::ContentEncoding::Br,
+                header::ContentEncoding::Gzip,
+            ]),
+        ))
+        .await;
+
+        for (accept, encoding, body) in &[
+            ("gzip, br", Some("br"), "brotli"),
+            ("gzip, br;q=0", Some("gzip"), "gzip"),
+            ("identity", None, "identity"),
+        ] {
+            let req = TestRequest::with_uri("/app.js")
+                .insert_header((header::ACCEPT_ENCODING, *accept))
+                .to_request();
+            let res = test::call_service(&srv, req).await;
+
+            assert_eq!(res.status(), StatusCode::OK);
+            assert_eq!(
+                res.headers().get(header::CONTENT_TYPE).unwrap(),
+                "text/javascript"
+            );
+            assert_eq!(
+                res.headers()
+                    .get(header::CONTENT_ENCODING)
+                    .map(|enc| enc.to_str().unwrap()),
+                *encoding
+            );
+            assert!(res
+                .headers()
+                .get_all(header::VARY)
+                .any(|vary| vary == "accept-encoding"));
+            assert_eq!(test::read_body(res).await, *body);
+        }
+
+        // etag and ranges refer to the encoded file
+        let req = TestRequest::with_uri("/app.js")
+            .insert_header((header::ACCEPT_ENCODING, "br"))
+            .insert_header((header::RANGE, "bytes=1-3"))
+            .to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::PARTIAL_CONTENT);
+        assert_eq!(res.headers().get(header::CONTENT_ENCODING).unwrap(), "br");
+        assert_eq!(
+            res.headers().get(header::CONTENT_RANGE).unwrap(),
+            "bytes 1-3/6"
+        );
+        assert_eq!(test::read_body(res).await, "rot");
+
+        let req = TestRequest::with_uri("/app.js")
+            .insert_header((header::ACCEPT_ENCODING, "br"))
+            .to_request();
+        let br = test::call_service(&srv, req).await;
+        let req = TestRequest::with_uri("/app.js").to_request();
+        let identity = test::call_service(&srv, req).await;
+        assert_ne!(
+            br.headers().get(header::ETAG),
+            identity.headers().get(header::ETAG)
+        );
+    }
+
+    #[actix_rt::test]
+    async fn test_large_file_chunks() {
+        let content = (0..1_500_000u32)
+            .map(|i| (i % 251) as u8)
+            .collect::<Vec<_>>();
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("large.bin");
+        fs::write(&path, &content).unwrap();
+
+        let srv = test::init_service(App::new().service(web::resource("/").to(move || {
+            let file = NamedFile::open(&path).unwrap();
+            async { file.chunk_size(256 * 1024) }
+        })))
+        .await;
+
+        let req = TestRequest::get().to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        let bytes = test::read_body(res).await;
+        assert_eq!(bytes.len(), content.len());
+        assert!(bytes == content[..]);
+
+        let req = TestRequest::get()
+            .insert_header((header::RANGE, "bytes=100000-1299999"))
+            .to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::PARTIAL_CONTENT);
+        let bytes = test::read_body(res).await;
+        assert!(bytes == content[100_000..1_300_000]);
+    }
+
+    #[actix_rt::test]
+    async fn test_spa_fallback() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("index.html"), "<h1>app</h1>").unwrap();
+        fs::write(dir.path().join("app.js"), "app();").unwrap();
+        fs::create_dir(dir.path().join("docs")).unwrap();
+        fs::write(dir.path().join("docs").join("index.html"), "<h1>docs</h1>").unwrap();
+
+        let srv = test::init_service(
+            App::new().service(
+                Files::new("/", dir.path())
+                    .index_file("index.html")
+                    .spa()
+                    .spa_exclude("/api"),
+            ),
+        )
+        .await;
+
+        for (uri, status, body) in &[
+            ("/app.js", StatusCode::OK, "app();"),
+            ("/docs", StatusCode::OK, "<h1>docs</h1>"),
+            ("/some/route", StatusCode::OK, "<h1>app</h1>"),
+            ("/apis", StatusCode::OK, "<h1>app</h1>"),
+            ("/api/missing.png", StatusCode::NOT_FOUND, ""),
+            ("/api", StatusCode::NOT_FOUND, ""),
+            ("/.hidden", StatusCode::BAD_REQUEST, ""),
+        ] {
+            let req = TestRequest::with_uri(uri).to_request();
+            let res = test::call_service(&srv, req).await;
+            assert_eq!(res.status(), *status, "{}", uri);
+            if status.is_success() {
+                assert_eq!(test::read_body(res).await, body.as_bytes(), "{}", uri);
+            }
+        }
+    }
+
+    #[actix_rt::test]
+    async fn test_cache_control() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("app.3f9a2c.js"), "app();").unwrap();
+        fs::write(dir.path().join("app.js"), "app();").unwrap();
+
+        let srv = test::init_service(
+            App::new().service(
+                Files::new("/", dir.path())
+                    .cache_control(header::CacheControl(vec![
+                        header::CacheDirective::Public,
+                        header::CacheDirective::MaxAge(60),
+                    ]))
+                    .fingerprinted(|name| name.split('.').any(|part| part == "3f9a2c")),
+            ),
+        )
+        .await;
+
+        for (uri, cache_control) in &[
+            ("/app.3f9a2c.js", "public, max-age=31536000, immutable"),
+            ("/app.js", "public, max-age=60"),
+        ] {
+            let req = TestRequest::with_uri(uri).to_request();
+            let res = test::call_service(&srv, req).await;
+            assert_eq!(res.status(), StatusCode::OK);
+            assert_eq!(
+                res.headers().get(header::CACHE_CONTROL).unwrap(),
+                cache_control
+            );
+            assert!(res.headers().contains_key(header::LAST_MODIFIED));
+            let etag = res.headers().get(header::ETAG).unwrap().clone();
+
+            let req = TestRequest::with_uri(uri)
+                .insert_header((header::IF_NONE_MATCH, etag))
+                .to_request();
+            let res = test::call_service(&srv, req).await;
+            assert_eq!(res.status(), StatusCode::NOT_MODIFIED);
+            assert_eq!(
+                res.headers().get(header::CACHE_CONTROL).unwrap(),
+                cache_control
+            );
+        }
+
+        let req = TestRequest::with_uri("/missing.js").to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+        assert!(!res.headers().contains_key(header::CACHE_CONTROL));
+    }
+
+    #[actix_rt::test]
+    async fn test_embedded_files() {
+        let mtime = SystemTime::UNIX_EPOCH + Duration::from_secs(1_600_000_000);
+        let srv = test::init_service(
+            App::new().service(
+                EmbeddedFiles::new(
+                    "/assets",
+                    vec![
+                        ("/index.html", (&b"<h1>home</h1>"[..], mtime)),
+                        ("css/hello.txt", (&b"hello world"[..], mtime)),
+                    ],
+                )
+                .index_file("index.html"),
+            ),
+        )
+        .await;
+
+        let req = TestRequest::with_uri("/assets/css/hello.txt").to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(
+            res.headers().get(header::CONTENT_TYPE).unwrap(),
+            "text/plain"
+        );
+        let etag = res.headers().get(header::ETAG).unwrap().clone();
+        let last_modified = res.headers().get(header::LAST_MODIFIED).unwrap().clone();
+        assert_eq!(test::read_body(res).await, &b"hello world"[..]);
+
+        // range request
+        let req = TestRequest::with_uri("/assets/css/hello.txt")
+            .insert_header((header::RANGE, "bytes=6-"))
+            .to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::PARTIAL_CONTENT);
+        assert_eq!(
+            res.headers().get(header::CONTENT_RANGE).unwrap(),
+            "bytes 6-10/11"
+        );
+        assert_eq!(test::read_body(res).await, &b"world"[..]);
+
+        // conditional requests
+        let req = TestRequest::with_uri("/assets/css/hello.txt")
+            .insert_header((header::IF_NONE_MATCH, etag))
+            .to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::NOT_MODIFIED);
+
+        let req = TestRequest::with_uri("/assets/css/hello.txt")
+            .insert_header((header::IF_MODIFIED_SINCE, last_modified))
+            .to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::NOT_MODIFIED);
+
+        let req = TestRequest::with_uri("/assets/")
+            .insert_header((header::IF_MODIFIED_SINCE, "Sun, 13 Sep 2020 12:26:39 GMT"))
+            .to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(
+            res.headers().get(header::CONTENT_TYPE).unwrap(),
+            "text/html"
+        );
+        assert_eq!(test::read_body(res).await, &b"<h1>home</h1>"[..]);
+
+        // path traversal and hidden segments
+        let req = TestRequest::with_uri("/assets/../../css/hello.txt").to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+
+        let req = TestRequest::with_uri("/assets/css/.hello.txt").to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+
+        let req = TestRequest::with_uri("/assets/css/missing.txt").to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+
+        let req = TestRequest::post()
+            .uri("/assets/css/hello.txt")
+            .to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::METHOD_NOT_ALLOWED);
+    }
+
+    #[actix_rt::test]
+    async fn test_directory_listing_formats() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("a.txt"), "abc").unwrap();
+        fs::write(dir.path().join("with space.txt"), "").unwrap();
+        fs::write(dir.path().join(".hidden"), "").unwrap();
+        fs::create_dir(dir.path().join("sub")).unwrap();
+
+        let files = Files::new("/files", dir.path()).show_files_listing();
+
+        let srv = test::init_service(App::new().service(files.clone())).await;
+        let req = TestRequest::with_uri("/files/").to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(
+            res.headers().get(header::CONTENT_TYPE).unwrap(),
+            "text/html; charset=utf-8"
+        );
+        let body = test::read_body(res).await;
+        let body = std::str::from_utf8(&body).unwrap();
+        assert!(body.contains(
+            "<ul><li><a href=\"/files/a.txt\">a.txt</a></li>\
+             <li><a href=\"/files/sub\">sub/</a></li>\
+             <li><a href=\"/files/with%20space.txt\">with space.txt</a></li></ul>"
+        ));
+
+        let srv = test::init_service(
+            App::new().service(files.clone().directory_renderer(DirectoryRenderer::Json)),
+        )
+        .await;
+        let req = TestRequest::with_uri("/files/").to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(
+            res.headers().get(header::CONTENT_TYPE).unwrap(),
+            "application/json"
+        );
+        let body = test::read_body(res).await;
+        let entries: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        let entries = entries.as_array().unwrap();
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[0]["name"], "a.txt");
+        assert_eq!(entries[0]["href"], "/files/a.txt");
+        assert_eq!(entries[0]["size"], 3);
+        assert_eq!(entries[0]["is_dir"], false);
+        assert!(entries[0]["modified"].is_u64());
+        assert_eq!(entries[1]["name"], "sub");
+        assert_eq!(entries[1]["is_dir"], true);
+        assert_eq!(entries[2]["href"], "/files/with%20space.txt");
+
+        let srv = test::init_service(
+            App::new().service(
+                files
+                    .directory_renderer(DirectoryRenderer::Negotiated)
+                    .list_hidden_files(true),
+            ),
+        )
+        .await;
+        let req = TestRequest::with_uri("/files/")
+            .insert_header((header::ACCEPT, "text/html;q=0.5, application/json"))
+            .to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(
+            res.headers().get(header::CONTENT_TYPE).unwrap(),
+            "application/json"
+        );
+        assert_eq!(res.headers().get(header::VARY).unwrap(), "accept");
+        let body = test::read_body(res).await;
+        let entries: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(entries[0]["name"], ".hidden");
+
+        let req = TestRequest::with_uri("/files/")
+            .insert_header((header::ACCEPT, "text/html"))
+            .to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(
+            res.headers().get(header::CONTENT_TYPE).unwrap(),
+            "text/html; charset=utf-8"
+        );
+    }
+
     #[actix_rt::test]
     async fn test_redirect_to_slash_directory() {
         // should not redirect if no index
@@ -1,12 +1,9 @@
-use std::fs::{File, Metadata};
+use std::fs::File;
 use std::io;
 use std::ops::{Deref, DerefMut};
 use std::path::{Path, PathBuf};
 use std::time::{SystemTime, UNIX_EPOCH};
 
-#[cfg(unix)]
-use std::os::unix::fs::MetadataExt;
-
 use actix_web::{
     dev::{BodyEncoding, SizedStream},
     http::{
@@ -20,7 +17,7 @@ use actix_web::{
 use bitflags::bitflags;
 use mime_guess::from_path;
 
-use crate::ChunkedReadFile;
+use crate::{chunked::DEFAULT_CHUNK_SIZE, ChunkedReadFile, FileMetadata, FileSource};
 use crate::{encoding::equiv_utf8_text, range::HttpRange};
 
 bitflags! {
@@ -39,17 +36,19 @@ impl Default for Flags {
 }
 
 /// A file with an associated name.
+///
+/// Files are read from disk by default; other storage can be used through [`FileSource`].
 #[derive(Debug)]
-pub struct NamedFile {
+pub struct NamedFile<F = File> {
     path: PathBuf,
-    file: File,
-    modified: Option<SystemTime>,
-    pub(crate) md: Metadata,
+    file: F,
+    pub(crate) md: FileMetadata,
     pub(crate) flags: Flags,
     pub(crate) status_code: StatusCode,
     pub(crate) content_type: mime::Mime,
     pub(crate) content_disposition: header::ContentDisposition,
     pub(crate) encoding: Option<ContentEncoding>,
+    chunk_size: usize,
 }
 
 impl NamedFile {
@@ -75,6 +74,29 @@ impl NamedFile {
     /// }
     /// ```
     pub fn from_file<P: AsRef<Path>>(file: File, path: P) -> io::Result<NamedFile> {
+        Self::from_source(file, path)
+    }
+
+    /// Attempts to open a file in read-only mode.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use actix_files::NamedFile;
+    ///
+    /// let file = NamedFile::open("foo.txt");
+    /// ```
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<NamedFile> {
+        Self::from_file(File::open(&path)?, path)
+    }
+}
+
+impl<F: FileSource> NamedFile<F> {
+    /// Creates an instance from a [`FileSource`].
+    ///
+    /// The given `path` is only used to determine the `ContentType` and `ContentDisposition`
+    /// headers, exactly like for [`NamedFile::from_file`].
+    pub fn from_source<P: AsRef<Path>>(file: F, path: P) -> io::Result<NamedFile<F>> {
         let path = path.as_ref().to_path_buf();
 
         // Get the name of the file and use it to construct default Content-Type
@@ -97,27 +119,15 @@ impl NamedFile {
                 _ => DispositionType::Attachment,
             };
 
-            let mut parameters =
-                vec![DispositionParam::Filename(String::from(filename.as_ref()))];
-
-            if !filename.is_ascii() {
-                parameters.push(DispositionParam::FilenameExt(ExtendedValue {
-                    charset: Charset::Ext(String::from("UTF-8")),
-                    language_tag: None,
-                    value: filename.into_owned().into_bytes(),
-                }))
-            }
-
-            let cd = ContentDisposition {
+            let cd = encode_filename(ContentDisposition {
                 disposition,
-                parameters,
-            };
+                parameters: vec![DispositionParam::Filename(filename.into_owned())],
+            });
 
             (ct, cd)
         };
 
         let md = file.metadata()?;
-        let modified = md.modified().ok();
         let encoding = None;
 
         Ok(NamedFile {
@@ -126,29 +136,16 @@ impl NamedFile {
             content_type,
             content_disposition,
             md,
-            modified,
             encoding,
+            chunk_size: DEFAULT_CHUNK_SIZE,
             status_code: StatusCode::OK,
             flags: Flags::default(),
         })
     }
 
-    /// Attempts to open a file in read-only mode.
-    ///
-    /// # Examples
-    ///
-    /// ```rust
-    /// use actix_files::NamedFile;
-    ///
-    /// let file = NamedFile::open("foo.txt");
-    /// ```
-    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<NamedFile> {
-        Self::from_file(File::open(&path)?, path)
-    }
-
     /// Returns reference to the underlying `File` object.
     #[inline]
-    pub fn file(&self) -> &File {
+    pub fn file(&self) -> &F {
         &self.file
     }
 
@@ -192,9 +189,12 @@ impl NamedFile {
     /// the filename is taken from the path provided in the `open` method
     /// after converting it to UTF-8 using.
     /// [`std::ffi::OsStr::to_string_lossy`]
+    ///
+    /// A non-ASCII `filename` parameter is sent as a `filename*` parameter, with an ASCII
+    /// fallback `filename` for clients that do not support it.
     #[inline]
     pub fn set_content_disposition(mut self, cd: header::ContentDisposition) -> Self {
-        self.content_disposition = cd;
+        self.content_disposition = encode_filename(cd);
         self.flags.insert(Flags::CONTENT_DISPOSITION);
         self
     }
@@ -242,28 +242,29 @@ impl NamedFile {
         self
     }
 
+    /// Set the maximum number of bytes read from the file at once.
+    ///
+    /// Larger chunks need fewer reads at the cost of more memory per response. Values below 1 are
+    /// treated as 1.
+    ///
+    /// Default is 64 KiB.
+    #[inline]
+    pub fn chunk_size(mut self, size: usize) -> Self {
+        self.chunk_size = size;
+        self
+    }
+
     pub(crate) fn etag(&self) -> Option<header::EntityTag> {
         // This etag format is similar to Apache's.
-        self.modified.as_ref().map(|mtime| {
-            let ino = {
-                #[cfg(unix)]
-                {
-                    self.md.ino()
-                }
-                #[cfg(not(unix))]
-                {
-                    0
-                }
-            };
-
+        self.md.modified.as_ref().map(|mtime| {
             let dur = mtime
                 .duration_since(UNIX_EPOCH)
                 .expect("modification time must be after epoch");
 
             header::EntityTag::strong(format!(
                 "{:x}:{:x}:{:x}:{:x}",
-                ino,
-                self.md.len(),
+                self.md.ino,
+                self.md.len,
                 dur.as_secs(),
                 dur.subsec_nanos()
             ))
@@ -271,7 +272,7 @@ impl NamedFile {
     }
 
     pub(crate) fn last_modified(&self) -> Option<header::HttpDate> {
-        self.modified.map(|mtime| mtime.into())
+        self.md.modified.map(|mtime| mtime.into())
     }
 
     /// Creates an `HttpResponse` with file as a streaming body.
@@ -297,7 +298,8 @@ impl NamedFile {
                 res.encoding(current_encoding);
             }
 
-            let reader = ChunkedReadFile::new(self.md.len(), 0, self.file);
+            let reader =
+                ChunkedReadFile::new(self.md.len, 0, self.file).chunk_size(self.chunk_size);
 
             return res.streaming(reader);
         }
@@ -381,7 +383,7 @@ impl NamedFile {
 
         resp.insert_header((header::ACCEPT_RANGES, "bytes"));
 
-        let mut length = self.md.len();
+        let mut length = self.md.len;
         let mut offset = 0;
 
         // check for range header
@@ -394,7 +396,7 @@ impl NamedFile {
                     resp.encoding(ContentEncoding::Identity);
                     resp.insert_header((
                         header::CONTENT_RANGE,
-                        format!("bytes {}-{}/{}", offset, offset + length - 1, self.md.len()),
+                        format!("bytes {}-{}/{}", offset, offset + length - 1, self.md.len),
                     ));
                 } else {
                     resp.insert_header((header::CONTENT_RANGE, format!("bytes */{}", length)));
@@ -411,9 +413,10 @@ impl NamedFile {
             return resp.status(StatusCode::NOT_MODIFIED).finish();
         }
 
-        let reader = ChunkedReadFile::new(length, offset, self.file);
+        let reader =
+            ChunkedReadFile::new(length, offset, self.file).chunk_size(self.chunk_size);
 
-        if offset != 0 || length != self.md.len() {
+        if offset != 0 || length != self.md.len {
             resp.status(StatusCode::PARTIAL_CONTENT);
         }
 
@@ -421,16 +424,47 @@ impl NamedFile {
     }
 }
 
-impl Deref for NamedFile {
-    type Target = File;
+/// Moves a non-ASCII `filename` parameter to an RFC 5987 encoded `filename*` parameter, keeping
+/// an ASCII approximation as `filename` for clients that do not understand `filename*`.
+pub(crate) fn encode_filename(mut cd: ContentDisposition) -> ContentDisposition {
+    let has_ext = cd.parameters.iter().any(DispositionParam::is_filename_ext);
+    let mut parameters = Vec::with_capacity(cd.parameters.len() + 1);
+
+    for param in cd.parameters {
+        match param {
+            DispositionParam::Filename(name) if !name.is_ascii() => {
+                let fallback = name
+                    .chars()
+                    .map(|c| if c.is_ascii() { c } else { '_' })
+                    .collect();
+                parameters.push(DispositionParam::Filename(fallback));
+
+                if !has_ext {
+                    parameters.push(DispositionParam::FilenameExt(ExtendedValue {
+                        charset: Charset::Ext(String::from("UTF-8")),
+                        language_tag: None,
+                        value: name.into_bytes(),
+                    }));
+                }
+            }
+            param => parameters.push(param),
+        }
+    }
+
+    cd.parameters = parameters;
+    cd
+}
+
+impl<F> Deref for NamedFile<F> {
+    type Target = F;
 
-    fn deref(&self) -> &File {
+    fn deref(&self) -> &F {
         &self.file
     }
 }
 
-impl DerefMut for NamedFile {
-    fn deref_mut(&mut self) -> &mut File {
+impl<F> DerefMut for NamedFile<F> {
+    fn deref_mut(&mut self) -> &mut F {
         &mut self.file
     }
 }
@@ -475,7 +509,7 @@ fn none_match(etag: Option<&header::EntityTag>, req: &HttpRequest) -> bool {
     }
 }
 
-impl Responder for NamedFile {
+impl<F: FileSource> Responder for NamedFile<F> {
     fn respond_to(self, req: &HttpRequest) -> HttpResponse {
         self.into_response(req)
     }
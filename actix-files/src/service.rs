@@ -1,18 +1,29 @@
-use std::{fmt, io, path::PathBuf, rc::Rc, task::Poll};
+use std::{
+    fmt,
+    fs::File,
+    io,
+    path::{Path, PathBuf},
+    rc::Rc,
+    task::Poll,
+};
 
 use actix_service::Service;
 use actix_web::{
     dev::{ServiceRequest, ServiceResponse},
     error::Error,
     guard::Guard,
-    http::{header, Method},
+    http::{
+        header::{self, HeaderValue},
+        ContentEncoding, Method, StatusCode,
+    },
     HttpResponse,
 };
 use futures_util::future::{ok, Either, LocalBoxFuture, Ready};
 
 use crate::{
-    named, Directory, DirectoryRenderer, FilesError, HttpService, MimeOverride, NamedFile,
-    PathBufWrap,
+    encoding::{accepts_encoding, precompressed_ext},
+    named, ContentDispositionFn, Directory, DirectoryRenderFn, FilesError, Fingerprinted,
+    HttpService, MimeOverride, NamedFile, PathBufWrap,
 };
 
 /// Assembled file serving service.
@@ -22,11 +33,18 @@ pub struct FilesService {
     pub(crate) show_index: bool,
     pub(crate) redirect_to_slash: bool,
     pub(crate) default: Option<HttpService>,
-    pub(crate) renderer: Rc<DirectoryRenderer>,
+    pub(crate) renderer: Rc<DirectoryRenderFn>,
     pub(crate) mime_override: Option<Rc<MimeOverride>>,
+    pub(crate) content_disposition_fn: Option<Rc<ContentDispositionFn>>,
     pub(crate) file_flags: named::Flags,
     pub(crate) guards: Option<Rc<dyn Guard>>,
     pub(crate) hidden_files: bool,
+    pub(crate) list_hidden_files: bool,
+    pub(crate) precompressed: Vec<ContentEncoding>,
+    pub(crate) cache_control: Option<HeaderValue>,
+    pub(crate) fingerprinted: Option<Rc<Fingerprinted>>,
+    pub(crate) spa: bool,
+    pub(crate) spa_excluded: Vec<String>,
 }
 
 type FilesServiceFuture = Either<
@@ -44,6 +62,133 @@ impl FilesService {
             Either::Left(ok(req.error_response(e)))
         }
     }
+
+    /// Returns the index file to serve for unknown paths in single-page application mode.
+    fn spa_fallback(&self, e: &io::Error, req: &ServiceRequest) -> Option<PathBuf> {
+        if !self.spa || e.kind() != io::ErrorKind::NotFound {
+            return None;
+        }
+
+        let path = req.match_info().path();
+        let excluded = self.spa_excluded.iter().any(|prefix| {
+            let prefix = prefix.trim_end_matches('/');
+            path.starts_with(prefix)
+                && matches!(path.as_bytes().get(prefix.len()), None | Some(b'/'))
+        });
+
+        if excluded {
+            return None;
+        }
+
+        let index = self.index.as_deref().unwrap_or("index.html");
+        Some(self.directory.join(index))
+    }
+
+    /// Opens the file at `path`, or its first precompressed variant accepted by the client.
+    fn open_file(
+        &self,
+        path: &Path,
+        req: &ServiceRequest,
+    ) -> io::Result<(NamedFile, Option<ContentEncoding>)> {
+        for &encoding in &self.precompressed {
+            let ext = match precompressed_ext(encoding) {
+                Some(ext) => ext,
+                None => continue,
+            };
+
+            if !accepts_encoding(req.headers(), encoding) {
+                continue;
+            }
+
+            let mut sidecar = path.as_os_str().to_owned();
+            sidecar.push(".");
+            sidecar.push(ext);
+
+            if let Ok(file) = File::open(&sidecar) {
+                if file.metadata().map_or(false, |md| md.is_file()) {
+                    // named after the original file to keep its content type and disposition
+                    return Ok((NamedFile::from_file(file, path)?, Some(encoding)));
+                }
+            }
+        }
+
+        Ok((NamedFile::open(path)?, None))
+    }
+
+    fn serve_named_file(&self, path: PathBuf, req: ServiceRequest) -> FilesServiceFuture {
+        let (mut named_file, encoding) = match self.open_file(&path, &req) {
+            Ok(file) => file,
+            Err(e) => return self.handle_err(e, req),
+        };
+
+        if let Some(ref mime_override) = self.mime_override {
+            let new_disposition = mime_override(&named_file.content_type.type_());
+            named_file.content_disposition.disposition = new_disposition;
+        }
+        if let Some(ref content_disposition_fn) = self.content_disposition_fn {
+            let cd = content_disposition_fn(&path, &named_file.content_type);
+            named_file.content_disposition = named::encode_filename(cd);
+        }
+        named_file.flags = self.file_flags;
+
+        let (req, _) = req.into_parts();
+        let mut res = named_file.into_response(&req);
+
+        if !self.precompressed.is_empty() {
+            let headers = res.headers_mut();
+
+            headers.append(header::VARY, HeaderValue::from_static("accept-encoding"));
+
+            // content is already encoded; also prevents `Compress` middleware from re-encoding
+            if let Some(encoding) = encoding {
+                headers.insert(
+                    header::CONTENT_ENCODING,
+                    HeaderValue::from_static(encoding.as_str()),
+                );
+            }
+        }
+
+        let is_fingerprinted = match (&self.fingerprinted, path.file_name()) {
+            (Some(fingerprinted), Some(name)) => fingerprinted(&name.to_string_lossy()),
+            _ => false,
+        };
+
+        if is_fingerprinted {
+            set_cache_control(&mut res, HeaderValue::from_static(IMMUTABLE));
+        } else if let Some(ref cache_control) = self.cache_control {
+            set_cache_control(&mut res, cache_control.clone());
+        }
+
+        Either::Left(ok(ServiceResponse::new(req, res)))
+    }
+}
+
+const IMMUTABLE: &str = "public, max-age=31536000, immutable";
+
+/// Sets `Cache-Control` on successful and `304 Not Modified` responses.
+fn set_cache_control<B>(res: &mut HttpResponse<B>, value: HeaderValue) {
+    if res.status().is_success() || res.status() == StatusCode::NOT_MODIFIED {
+        res.headers_mut().insert(header::CACHE_CONTROL, value);
+    }
+}
+
+/// Checks `req` against user defined guards, or allows GET and HEAD by default.
+pub(crate) fn is_method_valid(guards: Option<&dyn Guard>, req: &ServiceRequest) -> bool {
+    if let Some(guard) = guards {
+        // execute user defined guards
+        guard.check(req.head())
+    } else {
+        // default behavior
+        matches!(*req.method(), Method::HEAD | Method::GET)
+    }
+}
+
+pub(crate) fn method_not_allowed(req: ServiceRequest) -> ServiceResponse {
+    req.into_response(
+        HttpResponse::MethodNotAllowed()
+            .insert_header(header::ContentType(mime::TEXT_PLAIN_UTF_8))
+            .body("Request did not meet this resource's requirements."),
+    )
 }
 
 impl fmt::Debug for FilesService {
@@ -60,20 +205,8 @@ impl Service<ServiceRequest> for FilesService {
     actix_service::always_ready!();
 
     fn call(&self, req: ServiceRequest) -> Self::Future {
-        let is_method_valid = if let Some(guard) = &self.guards {
-            // execute user defined guards
-            (**guard).check(req.head())
-        } else {
-            // default behavior
-            matches!(*req.method(), Method::HEAD | Method::GET)
-        };
-
-        if !is_method_valid {
-            return Either::Left(ok(req.into_response(
-                actix_web::HttpResponse::MethodNotAllowed()
-                    .insert_header(header::ContentType(mime::TEXT_PLAIN_UTF_8))
-                    .body("Request did not meet this resource's requirements."),
-            )));
+        if !is_method_valid(self.guards.as_deref(), &req) {
+            return Either::Left(ok(method_not_allowed(req)));
         }
 
         let real_path =
@@ -85,7 +218,15 @@ impl Service<ServiceRequest> for FilesService {
         // full file path
         let path = match self.directory.join(&real_path).canonicalize() {
             Ok(path) => path,
-            Err(e) => return self.handle_err(e, req),
+            Err(e) => {
+                return match self.spa_fallback(&e, &req) {
+                    Some(index) => {
+                        log::debug!("Serving index file for {}", req.path());
+                        self.serve_named_file(index, req)
+                    }
+                    None => self.handle_err(e, req),
+                }
+            }
         };
 
         if path.is_dir() {
@@ -101,31 +242,21 @@ impl Service<ServiceRequest> for FilesService {
                     )));
                 }
 
-                let path = path.join(redir_index);
-
-                match NamedFile::open(path) {
-                    Ok(mut named_file) => {
-                        if let Some(ref mime_override) = self.mime_override {
-                            let new_disposition =
-                                mime_override(&named_file.content_type.type_());
-                            named_file.content_disposition.disposition = new_disposition;
-                        }
-                        named_file.flags = self.file_flags;
-
-                        let (req, _) = req.into_parts();
-                        let res = named_file.into_response(&req);
-                        Either::Left(ok(ServiceResponse::new(req, res)))
-                    }
-                    Err(e) => self.handle_err(e, req),
-                }
+                self.serve_named_file(path.join(redir_index), req)
             } else if self.show_index {
-                let dir = Directory::new(self.directory.clone(), path);
+                let mut dir = Directory::new(self.directory.clone(), path);
+                dir.show_hidden = self.list_hidden_files;
 
                 let (req, _) = req.into_parts();
                 let x = (self.renderer)(&dir, &req);
 
                 match x {
-                    Ok(resp) => Either::Left(ok(resp)),
+                    Ok(mut resp) => {
+                        if let Some(ref cache_control) = self.cache_control {
+                            set_cache_control(resp.response_mut(), cache_control.clone());
+                        }
+                        Either::Left(ok(resp))
+                    }
                     Err(e) => Either::Left(ok(ServiceResponse::from_err(e, req))),
                 }
             } else {
@@ -135,20 +266,7 @@ impl Service<ServiceRequest> for FilesService {
                 )))
             }
         } else {
-            match NamedFile::open(path) {
-                Ok(mut named_file) => {
-                    if let Some(ref mime_override) = self.mime_override {
-                        let new_disposition = mime_override(&named_file.content_type.type_());
-                        named_file.content_disposition.disposition = new_disposition;
-                    }
-                    named_file.flags = self.file_flags;
-
-                    let (req, _) = req.into_parts();
-                    let res = named_file.into_response(&req);
-                    Either::Left(ok(ServiceResponse::new(req, res)))
-                }
-                Err(e) => self.handle_err(e, req),
-            }
+            self.serve_named_file(path, req)
         }
     }
 }
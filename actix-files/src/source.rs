@@ -0,0 +1,61 @@
+use std::{
+    fs::File,
+    io::{self, Read, Seek},
+    time::SystemTime,
+};
+
+#[cfg(unix)]
+use std::os::unix::fs::MetadataExt;
+
+use bytes::Bytes;
+
+/// Storage backend of a [`NamedFile`](crate::NamedFile).
+///
+/// Implemented for [`std::fs::File`] and for [`EmbeddedFile`](crate::EmbeddedFile). Reads are
+/// performed on a thread pool, so implementations are allowed to block.
+pub trait FileSource: Send + 'static {
+    /// Returns the metadata of the file.
+    fn metadata(&self) -> io::Result<FileMetadata>;
+
+    /// Reads up to `max_bytes` bytes starting at `offset`.
+    ///
+    /// Returns an empty chunk when `offset` is at or past the end of the file.
+    fn read_at(&mut self, offset: u64, max_bytes: usize) -> io::Result<Bytes>;
+}
+
+/// Metadata of a [`FileSource`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FileMetadata {
+    /// Size of the file in bytes.
+    pub len: u64,
+
+    /// Last modification time, if known.
+    pub modified: Option<SystemTime>,
+
+    /// Number identifying the file within its storage, used in the `ETag`; zero if unknown.
+    pub ino: u64,
+}
+
+impl FileSource for File {
+    fn metadata(&self) -> io::Result<FileMetadata> {
+        let md = File::metadata(self)?;
+
+        #[cfg(unix)]
+        let ino = md.ino();
+        #[cfg(not(unix))]
+        let ino = 0;
+
+        Ok(FileMetadata {
+            len: md.len(),
+            modified: md.modified().ok(),
+            ino,
+        })
+    }
+
+    fn read_at(&mut self, offset: u64, max_bytes: usize) -> io::Result<Bytes> {
+        let mut buf = Vec::with_capacity(max_bytes);
+        self.seek(io::SeekFrom::Start(offset))?;
+        self.by_ref().take(max_bytes as u64).read_to_end(&mut buf)?;
+        Ok(Bytes::from(buf))
+    }
+}
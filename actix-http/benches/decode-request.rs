@@ -0,0 +1,81 @@
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use actix_codec::Decoder;
+use actix_http::h1::Codec;
+use bytes::BytesMut;
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+/// Counts heap allocations, to report the number made per decoded request.
+struct CountingAlloc;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+const REQUEST: &[u8] = b"\
+GET /api/v1/items?page=2 HTTP/1.1\r\n\
+Host: example.com\r\n\
+User-Agent: Mozilla/5.0 (X11; Linux x86_64; rv:85.0) Gecko/20100101 Firefox/85.0\r\n\
+Accept: application/json\r\n\
+Accept-Encoding: gzip, deflate, br\r\n\
+Connection: keep-alive\r\n\
+X-Request-Id: 3a4b7c9d\r\n\
+X-Forwarded-For: 10.0.0.1\r\n\r\n";
+
+/// Decodes one request from the connection buffer, like the dispatcher does.
+fn decode_request(codec: &mut Codec, buf: &mut BytesMut) {
+    buf.reserve(REQUEST.len());
+    buf.extend_from_slice(REQUEST);
+    let msg = codec.decode(buf).unwrap().unwrap();
+    black_box(msg);
+}
+
+fn bench_decode_request(c: &mut Criterion) {
+    // the codec's date service runs on the system
+    let sys = actix_rt::System::new();
+
+    sys.block_on(async {
+        let mut codec = Codec::default();
+        let mut buf = BytesMut::with_capacity(1024);
+
+        // warm up the connection buffer and the message pool
+        for _ in 0..16 {
+            decode_request(&mut codec, &mut buf);
+        }
+
+        let before = ALLOCATIONS.load(Ordering::Relaxed);
+        for _ in 0..1000 {
+            decode_request(&mut codec, &mut buf);
+        }
+        let allocations = ALLOCATIONS.load(Ordering::Relaxed) - before;
+        println!(
+            "allocations per request: {:.2}",
+            allocations as f64 / 1000.0
+        );
+
+        c.bench_function("decode_request", |b| {
+            b.iter(|| decode_request(&mut codec, &mut buf))
+        });
+    });
+}
+
+criterion_group!(benches, bench_decode_request);
+criterion_main!(benches);
@@ -0,0 +1,72 @@
+use std::io::{IoSlice, Write as _};
+
+use bytes::{Bytes, BytesMut};
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+
+fn bench_write_large_body(c: &mut Criterion) {
+    let mut group = c.benchmark_group("write_large_body");
+
+    for &size in &[4 * 1024, 64 * 1024, 1024 * 1024, 4 * 1024 * 1024] {
+        let chunk = Bytes::from(vec![b'x'; size]);
+
+        // the socket is simulated by a vector, which copies the written bytes like the kernel
+        group.bench_with_input(
+            BenchmarkId::new("Original", size),
+            &chunk,
+            |b, chunk| {
+                let mut buf = BytesMut::with_capacity(8 * 1024);
+                let mut socket = Vec::with_capacity(size + 64);
+
+                b.iter(|| {
+                    socket.clear();
+                    _original::write_chunk(black_box(chunk), &mut buf, &mut socket)
+                });
+            },
+        );
+
+        group.bench_with_input(BenchmarkId::new("New", size), &chunk, |b, chunk| {
+            let mut buf = BytesMut::with_capacity(8 * 1024);
+            let mut socket = Vec::with_capacity(size + 64);
+
+            b.iter(|| {
+                socket.clear();
+                _new::write_chunk(black_box(chunk.clone()), &mut buf, &mut socket)
+            });
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_write_large_body);
+criterion_main!(benches);
+
+mod _original {
+    use super::*;
+
+    /// Copies the chunk into the write buffer, then writes the buffer.
+    pub fn write_chunk(chunk: &Bytes, buf: &mut BytesMut, socket: &mut Vec<u8>) {
+        buf.extend_from_slice(format!("{:X}\r\n", chunk.len()).as_bytes());
+        buf.extend_from_slice(chunk);
+        buf.extend_from_slice(b"\r\n");
+
+        socket.write_all(buf).unwrap();
+        buf.clear();
+    }
+}
+
+mod _new {
+    use super::*;
+
+    /// Writes the framing from the write buffer and the chunk from its own buffer.
+    pub fn write_chunk(chunk: Bytes, buf: &mut BytesMut, socket: &mut Vec<u8>) {
+        buf.extend_from_slice(format!("{:X}\r\n", chunk.len()).as_bytes());
+        let head = buf.split().freeze();
+        buf.extend_from_slice(b"\r\n");
+
+        let slices = [IoSlice::new(&head), IoSlice::new(&chunk), IoSlice::new(buf)];
+        let n = socket.write_vectored(&slices).unwrap();
+        assert_eq!(n, head.len() + chunk.len() + buf.len());
+        buf.clear();
+    }
+}
@@ -1,4 +1,5 @@
 use std::{
+    error::Error as StdError,
     fmt, mem,
     pin::Pin,
     task::{Context, Poll},
@@ -150,7 +151,7 @@ where
 impl<S, E> From<BodyStream<S>> for Body
 where
     S: Stream<Item = Result<Bytes, E>> + Unpin + 'static,
-    E: Into<Error> + 'static,
+    E: Into<Box<dyn StdError>> + 'static,
 {
     fn from(s: BodyStream<S>) -> Body {
         Body::from_message(s)
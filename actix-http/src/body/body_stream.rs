@@ -1,36 +1,122 @@
 use std::{
+    error::Error as StdError,
+    fmt,
     pin::Pin,
     task::{Context, Poll},
 };
 
 use bytes::Bytes;
 use futures_core::{ready, Stream};
+use http::header::{HeaderName, HeaderValue};
 
-use crate::error::Error;
+use crate::{
+    error::{Error, ResponseError},
+    header::HeaderMap,
+};
 
 use super::{BodySize, MessageBody};
 
+/// Name of the trailer sent by [`StreamErrorPolicy::Trailer`].
+pub(crate) const STREAM_ERROR_TRAILER: &str = "x-stream-error";
+
+/// Value of the trailer sent by [`StreamErrorPolicy::Trailer`]. The error itself is only logged.
+const STREAM_ERROR_VALUE: &str = "error";
+
+/// Determines what happens when a streaming body yields an error.
+pub enum StreamErrorPolicy {
+    /// Abort the connection (HTTP/1) or reset the stream (HTTP/2) without finishing the body.
+    ///
+    /// For chunked responses the terminating chunk is not written, so clients are able to detect
+    /// the truncation. This is the default.
+    Abort,
+
+    /// Finish the body cleanly and signal the failure with an `X-Stream-Error: error` trailer.
+    ///
+    /// The error is logged but not sent to the client. Clients only read trailers that are
+    /// announced by a `Trailer: x-stream-error` response header, which
+    /// [`ResponseBuilder::streaming_with`](crate::ResponseBuilder::streaming_with) adds.
+    ///
+    /// Trailers require chunked transfer encoding on HTTP/1; when the response is not chunked
+    /// this behaves like [`StreamErrorPolicy::Abort`].
+    Trailer,
+
+    /// Write a final chunk produced from the error, then finish the body cleanly.
+    ///
+    /// The error is logged, and the chunk is sent to the client as is.
+    ErrorChunk(Box<dyn Fn(&Error) -> Bytes>),
+}
+
+impl Default for StreamErrorPolicy {
+    fn default() -> Self {
+        StreamErrorPolicy::Abort
+    }
+}
+
+impl fmt::Debug for StreamErrorPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StreamErrorPolicy::Abort => write!(f, "Abort"),
+            StreamErrorPolicy::Trailer => write!(f, "Trailer"),
+            StreamErrorPolicy::ErrorChunk(_) => write!(f, "ErrorChunk(..)"),
+        }
+    }
+}
+
+/// Error used to signal the dispatcher that a body should be finished with trailers.
+///
+/// Displays as the original stream error, which is reported when the trailers can not be sent.
+#[derive(Debug)]
+pub(crate) struct BodyTrailers {
+    pub(crate) trailers: HeaderMap,
+    pub(crate) error: Error,
+}
+
+impl fmt::Display for BodyTrailers {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.error, f)
+    }
+}
+
+impl ResponseError for BodyTrailers {}
+
 /// Streaming response wrapper.
 ///
 /// Response does not contain `Content-Length` header and appropriate transfer encoding is used.
+///
+/// The stream may fail with any error convertible into `Box<dyn std::error::Error>`. Streams with
+/// errors that only convert into [`Error`] can be adapted with [`IntoErrorStream`].
 pub struct BodyStream<S: Unpin> {
     stream: S,
+    policy: StreamErrorPolicy,
+    finished: bool,
 }
 
 impl<S, E> BodyStream<S>
 where
     S: Stream<Item = Result<Bytes, E>> + Unpin,
-    E: Into<Error>,
+    E: Into<Box<dyn StdError>>,
 {
     pub fn new(stream: S) -> Self {
-        BodyStream { stream }
+        Self::with_error_policy(stream, StreamErrorPolicy::Abort)
+    }
+
+    /// Create streaming body that handles stream errors according to `policy`.
+    ///
+    /// With [`StreamErrorPolicy::Trailer`], the response should announce the trailer with a
+    /// `Trailer: x-stream-error` header.
+    pub fn with_error_policy(stream: S, policy: StreamErrorPolicy) -> Self {
+        BodyStream {
+            stream,
+            policy,
+            finished: false,
+        }
     }
 }
 
 impl<S, E> MessageBody for BodyStream<S>
 where
     S: Stream<Item = Result<Bytes, E>> + Unpin,
-    E: Into<Error>,
+    E: Into<Box<dyn StdError>>,
 {
     fn size(&self) -> BodySize {
         BodySize::Stream
@@ -45,15 +131,217 @@ where
         mut self: Pin<&mut Self>,
         cx: &mut Context<'_>,
     ) -> Poll<Option<Result<Bytes, Error>>> {
+        if self.finished {
+            return Poll::Ready(None);
+        }
+
         loop {
             let stream = &mut self.as_mut().stream;
 
             let chunk = match ready!(Pin::new(stream).poll_next(cx)) {
                 Some(Ok(ref bytes)) if bytes.is_empty() => continue,
-                opt => opt.map(|res| res.map_err(Into::into)),
+                Some(Err(err)) => {
+                    let err = Error::from(err.into());
+                    Some(self.as_mut().handle_error(err))
+                }
+                opt => opt.map(|res| res.map_err(|err| Error::from(err.into()))),
             };
 
             return Poll::Ready(chunk);
         }
     }
 }
+
+impl<S: Unpin> BodyStream<S> {
+    fn handle_error(&mut self, err: Error) -> Result<Bytes, Error> {
+        match self.policy {
+            StreamErrorPolicy::Abort => Err(err),
+
+            StreamErrorPolicy::Trailer => {
+                self.finished = true;
+                log::error!("Response payload stream error: {:?}", err);
+
+                let mut trailers = HeaderMap::new();
+                trailers.insert(
+                    HeaderName::from_static(STREAM_ERROR_TRAILER),
+                    HeaderValue::from_static(STREAM_ERROR_VALUE),
+                );
+
+                Err(BodyTrailers {
+                    trailers,
+                    error: err,
+                }
+                .into())
+            }
+
+            StreamErrorPolicy::ErrorChunk(ref chunk) => {
+                self.finished = true;
+                log::error!("Response payload stream error: {:?}", err);
+                Ok(chunk(&err))
+            }
+        }
+    }
+}
+
+/// Stream adapter converting the errors of a stream into [`Error`].
+///
+/// Allows streams failing with errors that convert into [`Error`] but do not implement
+/// `std::error::Error`, such as custom [`ResponseError`] types, to be used with [`BodyStream`].
+pub struct IntoErrorStream<S> {
+    stream: S,
+}
+
+impl<S> IntoErrorStream<S> {
+    pub fn new(stream: S) -> Self {
+        IntoErrorStream { stream }
+    }
+}
+
+impl<S, T, E> Stream for IntoErrorStream<S>
+where
+    S: Stream<Item = Result<T, E>> + Unpin,
+    E: Into<Error>,
+{
+    type Item = Result<T, Error>;
+
+    fn poll_next(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Self::Item>> {
+        Pin::new(&mut self.stream)
+            .poll_next(cx)
+            .map(|opt| opt.map(|res| res.map_err(Into::into)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_rt::pin;
+    use futures_util::{future::poll_fn, stream};
+
+    use super::*;
+    use crate::error::ErrorBadRequest;
+
+    fn failing_stream() -> impl Stream<Item = Result<Bytes, Error>> + Unpin {
+        stream::iter(vec![
+            Ok(Bytes::from_static(b"1")),
+            Err(ErrorBadRequest("boom")),
+            Ok(Bytes::from_static(b"2")),
+        ])
+    }
+
+    #[actix_rt::test]
+    async fn test_error_policy_abort() {
+        let body = BodyStream::new(failing_stream());
+        pin!(body);
+
+        let chunk = poll_fn(|cx| body.as_mut().poll_next(cx)).await;
+        assert_eq!(chunk.unwrap().ok(), Some(Bytes::from_static(b"1")));
+        let chunk = poll_fn(|cx| body.as_mut().poll_next(cx)).await;
+        assert!(chunk.unwrap().is_err());
+    }
+
+    #[actix_rt::test]
+    async fn test_actix_error_kept() {
+        let body = BodyStream::new(failing_stream());
+        pin!(body);
+
+        poll_fn(|cx| body.as_mut().poll_next(cx)).await;
+        let err = poll_fn(|cx| body.as_mut().poll_next(cx))
+            .await
+            .unwrap()
+            .unwrap_err();
+        assert_eq!(
+            err.as_response_error().status_code(),
+            http::StatusCode::BAD_REQUEST
+        );
+    }
+
+    #[actix_rt::test]
+    async fn test_io_error() {
+        let body = BodyStream::new(stream::iter(vec![
+            Ok(Bytes::from_static(b"1")),
+            Err(std::io::Error::new(std::io::ErrorKind::Other, "disk gone")),
+        ]));
+        pin!(body);
+
+        let chunk = poll_fn(|cx| body.as_mut().poll_next(cx)).await;
+        assert_eq!(chunk.unwrap().ok(), Some(Bytes::from_static(b"1")));
+        let err = poll_fn(|cx| body.as_mut().poll_next(cx))
+            .await
+            .unwrap()
+            .unwrap_err();
+        assert_eq!(err.to_string(), "disk gone");
+    }
+
+    #[actix_rt::test]
+    async fn test_custom_error() {
+        #[derive(Debug)]
+        struct StoreError;
+
+        impl fmt::Display for StoreError {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str("object store failure")
+            }
+        }
+
+        impl StdError for StoreError {}
+
+        let body = BodyStream::with_error_policy(
+            stream::iter(vec![Err::<Bytes, _>(StoreError)]),
+            StreamErrorPolicy::Trailer,
+        );
+        pin!(body);
+
+        let err = poll_fn(|cx| body.as_mut().poll_next(cx))
+            .await
+            .unwrap()
+            .unwrap_err();
+        // the error is kept for logging, but not sent to the client
+        let trailers = err.as_error::<BodyTrailers>().unwrap();
+        assert_eq!(trailers.to_string(), "object store failure");
+        assert_eq!(
+            trailers.trailers.get(STREAM_ERROR_TRAILER).unwrap(),
+            STREAM_ERROR_VALUE
+        );
+    }
+
+    #[actix_rt::test]
+    async fn test_error_policy_trailer() {
+        let body =
+            BodyStream::with_error_policy(failing_stream(), StreamErrorPolicy::Trailer);
+        pin!(body);
+
+        let chunk = poll_fn(|cx| body.as_mut().poll_next(cx)).await;
+        assert_eq!(chunk.unwrap().ok(), Some(Bytes::from_static(b"1")));
+
+        let err = poll_fn(|cx| body.as_mut().poll_next(cx))
+            .await
+            .unwrap()
+            .unwrap_err();
+        let trailers = &err.as_error::<BodyTrailers>().unwrap().trailers;
+        assert_eq!(trailers.get(STREAM_ERROR_TRAILER).unwrap(), "error");
+
+        assert!(poll_fn(|cx| body.as_mut().poll_next(cx)).await.is_none());
+    }
+
+    #[actix_rt::test]
+    async fn test_error_policy_error_chunk() {
+        let body = BodyStream::with_error_policy(
+            failing_stream(),
+            StreamErrorPolicy::ErrorChunk(Box::new(|err| {
+                Bytes::from(format!("error: {}", err))
+            })),
+        );
+        pin!(body);
+
+        let chunk = poll_fn(|cx| body.as_mut().poll_next(cx)).await;
+        assert_eq!(chunk.unwrap().ok(), Some(Bytes::from_static(b"1")));
+        let chunk = poll_fn(|cx| body.as_mut().poll_next(cx)).await;
+        assert_eq!(
+            chunk.unwrap().ok(),
+            Some(Bytes::from_static(b"error: boom"))
+        );
+        assert!(poll_fn(|cx| body.as_mut().poll_next(cx)).await.is_none());
+    }
+}
@@ -0,0 +1,78 @@
+use std::{
+    fmt,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use bytes::Bytes;
+
+use crate::error::Error;
+
+use super::{BodySize, MessageBody};
+
+/// A boxed message body with the concrete type erased.
+///
+/// Unlike [`Body::Message`](super::Body::Message), the wrapped body does not need to be `Unpin`.
+/// Create one with [`MessageBody::boxed`].
+pub struct BoxBody(Pin<Box<dyn MessageBody>>);
+
+impl BoxBody {
+    /// Boxes a message body.
+    pub fn new<B: MessageBody + 'static>(body: B) -> Self {
+        BoxBody(Box::pin(body))
+    }
+}
+
+impl fmt::Debug for BoxBody {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("BoxBody(dyn MessageBody)")
+    }
+}
+
+impl MessageBody for BoxBody {
+    fn size(&self) -> BodySize {
+        self.0.size()
+    }
+
+    fn poll_next(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Bytes, Error>>> {
+        self.0.as_mut().poll_next(cx)
+    }
+
+    fn boxed(self) -> BoxBody {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures_util::future::poll_fn;
+
+    use super::*;
+    use crate::body::BodyStream;
+
+    #[actix_rt::test]
+    async fn test_boxed() {
+        let mut body = Bytes::from_static(b"boxed").boxed();
+        assert_eq!(body.size(), BodySize::Sized(5));
+        assert_eq!(
+            poll_fn(|cx| Pin::new(&mut body).poll_next(cx))
+                .await
+                .unwrap()
+                .ok(),
+            Some(Bytes::from_static(b"boxed"))
+        );
+        assert!(poll_fn(|cx| Pin::new(&mut body).poll_next(cx))
+            .await
+            .is_none());
+
+        // boxing again does not add another layer of indirection
+        let body = body.boxed();
+        assert_eq!(body.size(), BodySize::Sized(0));
+
+        let stream = futures_util::stream::empty::<Result<Bytes, Error>>();
+        assert_eq!(BodyStream::new(stream).boxed().size(), BodySize::Stream);
+    }
+}
@@ -0,0 +1,104 @@
+use std::{
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use bytes::Bytes;
+use pin_project::pin_project;
+
+use crate::error::Error;
+
+use super::{BodySize, BoxBody, MessageBody};
+
+/// A message body that is one of two body types.
+///
+/// Useful in middleware that sometimes replaces the response body: the original body is kept as
+/// `Left` and the replacement becomes `Right`, so neither needs to be buffered. The size hint and
+/// chunks are those of the active variant.
+#[pin_project(project = EitherBodyProj)]
+#[derive(Debug, Clone)]
+pub enum EitherBody<L, R = BoxBody> {
+    Left(#[pin] L),
+    Right(#[pin] R),
+}
+
+impl<L, R> MessageBody for EitherBody<L, R>
+where
+    L: MessageBody,
+    R: MessageBody,
+{
+    fn size(&self) -> BodySize {
+        match self {
+            EitherBody::Left(body) => body.size(),
+            EitherBody::Right(body) => body.size(),
+        }
+    }
+
+    fn poll_next(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Bytes, Error>>> {
+        match self.project() {
+            EitherBodyProj::Left(body) => body.poll_next(cx),
+            EitherBodyProj::Right(body) => body.poll_next(cx),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_rt::pin;
+    use futures_util::{future::poll_fn, stream};
+
+    use super::*;
+    use crate::body::{Body, BodyStream};
+
+    #[actix_rt::test]
+    async fn test_size() {
+        let body: EitherBody<Body, Bytes> = EitherBody::Left(Body::None);
+        assert_eq!(body.size(), BodySize::None);
+
+        let body: EitherBody<Body, Bytes> = EitherBody::Left(Body::Empty);
+        assert_eq!(body.size(), BodySize::Empty);
+
+        let body: EitherBody<Body, Bytes> =
+            EitherBody::Right(Bytes::from_static(b"abc"));
+        assert_eq!(body.size(), BodySize::Sized(3));
+
+        let body: EitherBody<Bytes> = EitherBody::Right(
+            BodyStream::new(stream::empty::<Result<_, Error>>()).boxed(),
+        );
+        assert_eq!(body.size(), BodySize::Stream);
+    }
+
+    #[actix_rt::test]
+    async fn test_poll_next() {
+        let body = EitherBody::<Bytes, _>::Right(BodyStream::new(stream::iter(vec![
+            Ok::<_, Error>(Bytes::from_static(b"1")),
+            Ok(Bytes::from_static(b"2")),
+        ])));
+        pin!(body);
+
+        for chunk in &["1", "2"] {
+            assert_eq!(
+                poll_fn(|cx| body.as_mut().poll_next(cx))
+                    .await
+                    .unwrap()
+                    .ok(),
+                Some(Bytes::from(*chunk))
+            );
+        }
+        assert!(poll_fn(|cx| body.as_mut().poll_next(cx)).await.is_none());
+
+        let body = EitherBody::<_, Bytes>::Left("left");
+        pin!(body);
+        assert_eq!(
+            poll_fn(|cx| body.as_mut().poll_next(cx))
+                .await
+                .unwrap()
+                .ok(),
+            Some(Bytes::from_static(b"left"))
+        );
+        assert!(poll_fn(|cx| body.as_mut().poll_next(cx)).await.is_none());
+    }
+}
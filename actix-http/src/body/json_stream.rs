@@ -0,0 +1,229 @@
+use std::{
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use bytes::{BufMut, Bytes, BytesMut};
+use futures_core::{ready, Stream};
+use serde::Serialize;
+
+use crate::error::Error;
+
+use super::{BodySize, MessageBody};
+
+/// Determines what happens when an item of a [`JsonStream`] fails to serialize.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SerializeErrorPolicy {
+    /// Stop the body with the serialization error, which aborts the connection (HTTP/1) or resets
+    /// the stream (HTTP/2). This is the default.
+    Abort,
+
+    /// Leave the item out of the array and continue with the next one.
+    Skip,
+}
+
+impl Default for SerializeErrorPolicy {
+    fn default() -> Self {
+        SerializeErrorPolicy::Abort
+    }
+}
+
+/// Streaming body that serializes the items of a stream as a JSON array.
+///
+/// Each item is serialized as soon as the stream yields it, so the complete array is never held in
+/// memory. Response does not contain `Content-Length` header and chunked transfer encoding is
+/// used. Stream errors always stop the body; serialization errors are handled according to the
+/// [`SerializeErrorPolicy`].
+pub struct JsonStream<S: Unpin> {
+    stream: S,
+    policy: SerializeErrorPolicy,
+    opened: bool,
+    finished: bool,
+}
+
+impl<S, T, E> JsonStream<S>
+where
+    S: Stream<Item = Result<T, E>> + Unpin,
+    T: Serialize,
+    E: Into<Error>,
+{
+    pub fn new(stream: S) -> Self {
+        Self::with_error_policy(stream, SerializeErrorPolicy::Abort)
+    }
+
+    /// Create JSON array body that handles serialization errors according to `policy`.
+    pub fn with_error_policy(stream: S, policy: SerializeErrorPolicy) -> Self {
+        JsonStream {
+            stream,
+            policy,
+            opened: false,
+            finished: false,
+        }
+    }
+}
+
+impl<S, T, E> MessageBody for JsonStream<S>
+where
+    S: Stream<Item = Result<T, E>> + Unpin,
+    T: Serialize,
+    E: Into<Error>,
+{
+    fn size(&self) -> BodySize {
+        BodySize::Stream
+    }
+
+    fn poll_next(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Bytes, Error>>> {
+        if self.finished {
+            return Poll::Ready(None);
+        }
+
+        loop {
+            let this = self.as_mut().get_mut();
+
+            let item = match ready!(Pin::new(&mut this.stream).poll_next(cx)) {
+                Some(Ok(item)) => item,
+
+                Some(Err(err)) => {
+                    this.finished = true;
+                    return Poll::Ready(Some(Err(err.into())));
+                }
+
+                None => {
+                    this.finished = true;
+                    let end: &[u8] = if this.opened { b"]" } else { b"[]" };
+                    return Poll::Ready(Some(Ok(Bytes::from_static(end))));
+                }
+            };
+
+            // each item is serialized on its own so that a failed item leaves no partial output
+            match serde_json::to_vec(&item) {
+                Ok(json) => {
+                    let mut buf = BytesMut::with_capacity(json.len() + 1);
+                    buf.put_u8(if this.opened { b',' } else { b'[' });
+                    buf.extend_from_slice(&json);
+
+                    this.opened = true;
+                    return Poll::Ready(Some(Ok(buf.freeze())));
+                }
+
+                Err(err) => match this.policy {
+                    SerializeErrorPolicy::Abort => {
+                        this.finished = true;
+                        return Poll::Ready(Some(Err(err.into())));
+                    }
+
+                    SerializeErrorPolicy::Skip => {
+                        log::debug!("Skipping JSON stream item: {}", err);
+                        continue;
+                    }
+                },
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_rt::pin;
+    use futures_util::{future::poll_fn, stream};
+    use serde::ser::{Error as _, Serializer};
+
+    use super::*;
+    use crate::error::ErrorBadRequest;
+
+    /// Item that fails to serialize when it is `Bad`.
+    enum Item {
+        Good(u32),
+        Bad,
+    }
+
+    impl Serialize for Item {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            match self {
+                Item::Good(n) => serializer.serialize_u32(*n),
+                Item::Bad => Err(S::Error::custom("bad item")),
+            }
+        }
+    }
+
+    fn items(items: Vec<Item>) -> impl Stream<Item = Result<Item, Error>> + Unpin {
+        stream::iter(items.into_iter().map(Ok))
+    }
+
+    async fn collect<B: MessageBody>(body: B) -> (Vec<u8>, Option<Error>) {
+        pin!(body);
+
+        let mut buf = Vec::new();
+        while let Some(chunk) = poll_fn(|cx| body.as_mut().poll_next(cx)).await {
+            match chunk {
+                Ok(chunk) => buf.extend_from_slice(&chunk),
+                Err(err) => return (buf, Some(err)),
+            }
+        }
+
+        (buf, None)
+    }
+
+    #[actix_rt::test]
+    async fn test_many_items() {
+        let body = JsonStream::new(stream::iter((0..10_000u32).map(Ok::<_, Error>)));
+        let (buf, err) = collect(body).await;
+        assert!(err.is_none());
+
+        let array: Vec<u32> = serde_json::from_slice(&buf).unwrap();
+        assert_eq!(array, (0..10_000).collect::<Vec<_>>());
+    }
+
+    #[actix_rt::test]
+    async fn test_empty() {
+        let (buf, err) = collect(JsonStream::new(items(vec![]))).await;
+        assert!(err.is_none());
+        assert_eq!(buf, b"[]");
+    }
+
+    #[actix_rt::test]
+    async fn test_serialize_error_abort() {
+        let body = JsonStream::new(items(vec![Item::Good(1), Item::Bad, Item::Good(2)]));
+        let (buf, err) = collect(body).await;
+        assert_eq!(buf, b"[1");
+        assert!(err.unwrap().as_error::<serde_json::Error>().is_some());
+    }
+
+    #[actix_rt::test]
+    async fn test_serialize_error_skip() {
+        let body = JsonStream::with_error_policy(
+            items(vec![
+                Item::Bad,
+                Item::Good(1),
+                Item::Bad,
+                Item::Good(2),
+                Item::Bad,
+            ]),
+            SerializeErrorPolicy::Skip,
+        );
+        let (buf, err) = collect(body).await;
+        assert!(err.is_none());
+        assert_eq!(buf, b"[1,2]");
+
+        let body = JsonStream::with_error_policy(
+            items(vec![Item::Bad]),
+            SerializeErrorPolicy::Skip,
+        );
+        let (buf, _) = collect(body).await;
+        assert_eq!(buf, b"[]");
+    }
+
+    #[actix_rt::test]
+    async fn test_stream_error() {
+        let body = JsonStream::with_error_policy(
+            stream::iter(vec![Ok(1), Err(ErrorBadRequest("boom")), Ok(2)]),
+            SerializeErrorPolicy::Skip,
+        );
+        let (buf, err) = collect(body).await;
+        assert_eq!(buf, b"[1");
+        assert!(err.is_some());
+    }
+}
@@ -7,10 +7,11 @@ use std::{
 };
 
 use bytes::{Bytes, BytesMut};
+use pin_project::pin_project;
 
 use crate::error::Error;
 
-use super::BodySize;
+use super::{BodySize, BoxBody};
 
 /// Type that implement this trait can be streamed to a peer.
 pub trait MessageBody {
@@ -21,6 +22,30 @@ pub trait MessageBody {
         cx: &mut Context<'_>,
     ) -> Poll<Option<Result<Bytes, Error>>>;
 
+    /// Boxes this body, erasing its type.
+    fn boxed(self) -> BoxBody
+    where
+        Self: Sized + 'static,
+    {
+        BoxBody::new(self)
+    }
+
+    /// Maps the error of this body, e.g. to give it a different status code in logs and error
+    /// handlers.
+    ///
+    /// The body ends after yielding the mapped error.
+    fn map_err<F, E>(self, f: F) -> MapErrBody<Self, F>
+    where
+        Self: Sized,
+        F: FnOnce(Error) -> E,
+        E: Into<Error>,
+    {
+        MapErrBody {
+            body: self,
+            mapper: Some(f),
+        }
+    }
+
     downcast_get_type_id!();
 }
 
@@ -140,3 +165,93 @@ impl MessageBody for String {
         }
     }
 }
+
+/// Message body that maps the error of another body, see [`MessageBody::map_err`].
+#[pin_project]
+pub struct MapErrBody<B, F> {
+    #[pin]
+    body: B,
+    mapper: Option<F>,
+}
+
+impl<B, F, E> MessageBody for MapErrBody<B, F>
+where
+    B: MessageBody,
+    F: FnOnce(Error) -> E,
+    E: Into<Error>,
+{
+    fn size(&self) -> BodySize {
+        self.body.size()
+    }
+
+    fn poll_next(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Bytes, Error>>> {
+        let this = self.project();
+
+        if this.mapper.is_none() {
+            return Poll::Ready(None);
+        }
+
+        match this.body.poll_next(cx) {
+            Poll::Ready(Some(Err(err))) => {
+                let mapper = this.mapper.take().unwrap();
+                Poll::Ready(Some(Err(mapper(err).into())))
+            }
+            res => res,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_rt::pin;
+    use futures_util::{future::poll_fn, stream};
+
+    use super::*;
+    use crate::body::BodyStream;
+    use crate::error::{ErrorBadRequest, ErrorServiceUnavailable};
+    use crate::http::StatusCode;
+
+    #[actix_rt::test]
+    async fn test_map_err() {
+        let body = BodyStream::new(stream::iter(vec![
+            Ok(Bytes::from_static(b"1")),
+            Err(ErrorBadRequest("first")),
+            Err(ErrorBadRequest("second")),
+        ]))
+        .map_err(|err| {
+            assert_eq!(err.to_string(), "first");
+            ErrorServiceUnavailable(err)
+        });
+        assert_eq!(body.size(), BodySize::Stream);
+        pin!(body);
+
+        assert_eq!(
+            poll_fn(|cx| body.as_mut().poll_next(cx))
+                .await
+                .unwrap()
+                .ok(),
+            Some(Bytes::from_static(b"1"))
+        );
+
+        let err = poll_fn(|cx| body.as_mut().poll_next(cx))
+            .await
+            .unwrap()
+            .unwrap_err();
+        assert_eq!(
+            err.as_response_error().status_code(),
+            StatusCode::SERVICE_UNAVAILABLE
+        );
+
+        assert!(poll_fn(|cx| body.as_mut().poll_next(cx)).await.is_none());
+    }
+
+    #[actix_rt::test]
+    async fn test_map_err_size() {
+        let body = Bytes::from_static(b"abc").map_err(|err| err);
+        assert_eq!(body.size(), BodySize::Sized(3));
+        assert_eq!(().map_err(|err| err).size(), BodySize::Empty);
+    }
+}
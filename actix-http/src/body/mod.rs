@@ -3,14 +3,21 @@
 #[allow(clippy::module_inception)]
 mod body;
 mod body_stream;
+mod boxed;
+mod either;
+mod json_stream;
 mod message_body;
 mod response_body;
 mod size;
 mod sized_stream;
 
 pub use self::body::Body;
-pub use self::body_stream::BodyStream;
-pub use self::message_body::MessageBody;
+pub use self::body_stream::{BodyStream, IntoErrorStream, StreamErrorPolicy};
+pub(crate) use self::body_stream::{BodyTrailers, STREAM_ERROR_TRAILER};
+pub use self::boxed::BoxBody;
+pub use self::either::EitherBody;
+pub use self::json_stream::{JsonStream, SerializeErrorPolicy};
+pub use self::message_body::{MapErrBody, MessageBody};
 pub use self::response_body::ResponseBody;
 pub use self::size::BodySize;
 pub use self::sized_stream::SizedStream;
@@ -21,9 +28,11 @@ mod tests {
 
     use actix_rt::pin;
     use bytes::{Bytes, BytesMut};
+    use futures_core::Stream;
     use futures_util::{future::poll_fn, stream};
 
     use super::*;
+    use crate::error::Error;
 
     impl Body {
         pub(crate) fn get_ref(&self) -> &[u8] {
@@ -186,11 +195,11 @@ mod tests {
 
     #[actix_rt::test]
     async fn body_stream_skips_empty_chunks() {
-        let body = BodyStream::new(stream::iter(
+        let body = BodyStream::new(IntoErrorStream::new(stream::iter(
             ["1", "", "2"]
                 .iter()
                 .map(|&v| Ok(Bytes::from(v)) as Result<Bytes, ()>),
-        ));
+        )));
         pin!(body);
 
         assert_eq!(
@@ -211,6 +220,89 @@ mod tests {
 
     mod sized_stream {
         use super::*;
+        use crate::error::BodyLengthError;
+
+        fn chunks(
+            chunks: &[&'static str],
+        ) -> SizedStream<impl Stream<Item = Result<Bytes, Error>> + Unpin> {
+            let len = 6;
+            SizedStream::new(
+                len,
+                stream::iter(
+                    chunks
+                        .iter()
+                        .map(|&v| Ok(Bytes::from(v)))
+                        .collect::<Vec<_>>(),
+                ),
+            )
+        }
+
+        #[actix_rt::test]
+        async fn exact_length() {
+            let body = chunks(&["abc", "def"]);
+            assert_eq!(body.size(), BodySize::Sized(6));
+            pin!(body);
+
+            for chunk in &["abc", "def"] {
+                assert_eq!(
+                    poll_fn(|cx| body.as_mut().poll_next(cx))
+                        .await
+                        .unwrap()
+                        .ok(),
+                    Some(Bytes::from(*chunk)),
+                );
+            }
+            assert!(poll_fn(|cx| body.as_mut().poll_next(cx)).await.is_none());
+        }
+
+        #[actix_rt::test]
+        async fn short_stream() {
+            let body = chunks(&["abc", "d"]);
+            pin!(body);
+
+            assert!(poll_fn(|cx| body.as_mut().poll_next(cx))
+                .await
+                .unwrap()
+                .is_ok());
+            assert!(poll_fn(|cx| body.as_mut().poll_next(cx))
+                .await
+                .unwrap()
+                .is_ok());
+
+            let err = poll_fn(|cx| body.as_mut().poll_next(cx))
+                .await
+                .unwrap()
+                .unwrap_err();
+            assert_eq!(
+                err.as_error::<BodyLengthError>(),
+                Some(&BodyLengthError::TooShort {
+                    expected: 6,
+                    received: 4
+                })
+            );
+            assert!(poll_fn(|cx| body.as_mut().poll_next(cx)).await.is_none());
+        }
+
+        #[actix_rt::test]
+        async fn long_stream() {
+            let body = chunks(&["abc", "defg", "h"]);
+            pin!(body);
+
+            assert!(poll_fn(|cx| body.as_mut().poll_next(cx))
+                .await
+                .unwrap()
+                .is_ok());
+
+            let err = poll_fn(|cx| body.as_mut().poll_next(cx))
+                .await
+                .unwrap()
+                .unwrap_err();
+            assert_eq!(
+                err.as_error::<BodyLengthError>(),
+                Some(&BodyLengthError::TooLong(6))
+            );
+            assert!(poll_fn(|cx| body.as_mut().poll_next(cx)).await.is_none());
+        }
 
         #[actix_rt::test]
         async fn skips_empty_chunks() {
@@ -6,16 +6,23 @@ use std::{
 use bytes::Bytes;
 use futures_core::{ready, Stream};
 
-use crate::error::Error;
+use crate::error::{BodyLengthError, Error};
 
 use super::{BodySize, MessageBody};
 
 /// Known sized streaming response wrapper.
 ///
 /// This body implementation should be used if total size of stream is known. Data get sent as is
-/// without using transfer encoding.
+/// without using transfer encoding: HTTP/1.x responses get a `Content-Length` header and HTTP/2
+/// responses a `content-length` field.
+///
+/// The stream must produce exactly `size` bytes. If it produces more, or ends early, the error is
+/// logged and the body yields a [`BodyLengthError`], which closes the HTTP/1.x connection or
+/// resets the HTTP/2 stream so the client does not receive a corrupt response.
 pub struct SizedStream<S: Unpin> {
     size: u64,
+    sent: u64,
+    finished: bool,
     stream: S,
 }
 
@@ -24,7 +31,12 @@ where
     S: Stream<Item = Result<Bytes, Error>> + Unpin,
 {
     pub fn new(size: u64, stream: S) -> Self {
-        SizedStream { size, stream }
+        SizedStream {
+            size,
+            sent: 0,
+            finished: false,
+            stream,
+        }
     }
 }
 
@@ -45,11 +57,36 @@ where
         mut self: Pin<&mut Self>,
         cx: &mut Context<'_>,
     ) -> Poll<Option<Result<Bytes, Error>>> {
+        if self.finished {
+            return Poll::Ready(None);
+        }
+
         loop {
             let stream = &mut self.as_mut().stream;
 
             let chunk = match ready!(Pin::new(stream).poll_next(cx)) {
                 Some(Ok(ref bytes)) if bytes.is_empty() => continue,
+
+                Some(Ok(bytes)) => {
+                    let sent = self.sent + bytes.len() as u64;
+
+                    if sent > self.size {
+                        let err = BodyLengthError::TooLong(self.size);
+                        Some(Err(self.length_error(err)))
+                    } else {
+                        self.sent = sent;
+                        Some(Ok(bytes))
+                    }
+                }
+
+                None if self.sent < self.size => {
+                    let err = BodyLengthError::TooShort {
+                        expected: self.size,
+                        received: self.sent,
+                    };
+                    Some(Err(self.length_error(err)))
+                }
+
                 val => val,
             };
 
@@ -57,3 +94,11 @@ where
         }
     }
 }
+
+impl<S: Unpin> SizedStream<S> {
+    fn length_error(&mut self, err: BodyLengthError) -> Error {
+        log::error!("{}", err);
+        self.finished = true;
+        err.into()
+    }
+}
@@ -1,30 +1,26 @@
 use std::marker::PhantomData;
 use std::rc::Rc;
-use std::{fmt, net};
+use std::{fmt, net, time::Duration};
 
 use actix_codec::Framed;
 use actix_service::{IntoServiceFactory, Service, ServiceFactory};
 
 use crate::body::MessageBody;
-use crate::config::{KeepAlive, ServiceConfig};
+use crate::config::{KeepAlive, ServiceConfig, ServiceConfigOptions};
 use crate::error::Error;
 use crate::h1::{Codec, ExpectHandler, H1Service, UpgradeHandler};
-use crate::h2::H2Service;
+use crate::h2::{H2Service, H2Settings};
 use crate::request::Request;
 use crate::response::Response;
 use crate::service::HttpService;
-use crate::{ConnectCallback, Extensions};
+use crate::{ConnectCallback, ConnectionStats, Extensions, GracefulShutdown};
 
 /// A HTTP service builder
 ///
 /// This type can be used to construct an instance of [`HttpService`] through a
 /// builder-like pattern.
 pub struct HttpServiceBuilder<T, S, X = ExpectHandler, U = UpgradeHandler> {
-    keep_alive: KeepAlive,
-    client_timeout: u64,
-    client_disconnect: u64,
-    secure: bool,
-    local_addr: Option<net::SocketAddr>,
+    config: ServiceConfigOptions,
     expect: X,
     upgrade: Option<U>,
     on_connect_ext: Option<Rc<ConnectCallback<T>>>,
@@ -41,11 +37,10 @@ where
     /// Create instance of `ServiceConfigBuilder`
     pub fn new() -> Self {
         HttpServiceBuilder {
-            keep_alive: KeepAlive::Timeout(5),
-            client_timeout: 5000,
-            client_disconnect: 0,
-            secure: false,
-            local_addr: None,
+            config: ServiceConfigOptions {
+                client_timeout: 5000,
+                ..ServiceConfigOptions::default()
+            },
             expect: ExpectHandler,
             upgrade: None,
             on_connect_ext: None,
@@ -73,19 +68,19 @@ where
     ///
     /// By default keep alive is set to a 5 seconds.
     pub fn keep_alive<W: Into<KeepAlive>>(mut self, val: W) -> Self {
-        self.keep_alive = val.into();
+        self.config.keep_alive = val.into();
         self
     }
 
     /// Set connection secure state
     pub fn secure(mut self) -> Self {
-        self.secure = true;
+        self.config.secure = true;
         self
     }
 
     /// Set the local address that this service is bound to.
     pub fn local_addr(mut self, addr: net::SocketAddr) -> Self {
-        self.local_addr = Some(addr);
+        self.config.local_addr = Some(addr);
         self
     }
 
@@ -99,7 +94,7 @@ where
     ///
     /// By default client timeout is set to 5000 milliseconds.
     pub fn client_timeout(mut self, val: u64) -> Self {
-        self.client_timeout = val;
+        self.config.client_timeout = val;
         self
     }
 
@@ -112,7 +107,138 @@ where
     ///
     /// By default disconnect timeout is set to 0.
     pub fn client_disconnect(mut self, val: u64) -> Self {
-        self.client_disconnect = val;
+        self.config.client_disconnect = val;
+        self
+    }
+
+    /// Serve HTTP/2 over cleartext TCP (h2c) to clients with prior knowledge of HTTP/2.
+    ///
+    /// Plain TCP services created with [`HttpService::tcp`] read the start of each connection and
+    /// dispatch those beginning with the HTTP/2 connection preface to the HTTP/2 dispatcher; other
+    /// connections are served with HTTP/1.x. The preface is awaited for at most the client timeout,
+    /// or 5 seconds if it is disabled. Only prior knowledge is supported, not `Upgrade: h2c`.
+    ///
+    /// By default h2c is not allowed.
+    pub fn allow_h2c(mut self) -> Self {
+        self.config.allow_h2c = true;
+        self
+    }
+
+    /// Accept HTTP/1 header fields using obsolete line folding, as sent by some old clients.
+    ///
+    /// Each folded line is joined to the header line it continues, separated by a single space.
+    /// Folding onto the request line is never accepted. When not allowed, requests with folded
+    /// header fields are answered with `400 Bad Request`; the connection is kept open unless the
+    /// request has a body.
+    ///
+    /// By default obsolete line folding is not allowed.
+    pub fn allow_obsolete_header_folding(mut self, allow: bool) -> Self {
+        self.config.allow_obsolete_header_folding = allow;
+        self
+    }
+
+    /// Keep responding after a client shuts down its write side following a complete request.
+    ///
+    /// Clients may half-close the connection right after sending a request and still read the
+    /// response. When enabled, the HTTP/1 dispatcher finishes writing responses in flight before
+    /// closing the connection, while a request cut off by the half-close is dropped. When
+    /// disabled, reaching the end of the read side closes the connection right away, as for a
+    /// client that has gone away.
+    ///
+    /// By default responses are finished after a half-close.
+    pub fn respond_after_half_close(mut self, enabled: bool) -> Self {
+        self.config.respond_after_half_close = enabled;
+        self
+    }
+
+    /// Advertise the keep-alive timeout to HTTP/1 clients.
+    ///
+    /// When enabled, responses that keep the connection open include a `Keep-Alive: timeout=<secs>`
+    /// header, so that clients can avoid sending a request on a connection that is about to be
+    /// closed. The timeout is the configured [keep-alive](Self::keep_alive) duration, or the
+    /// shorter timeout requested by the client with its own `Keep-Alive` header.
+    ///
+    /// Nothing is advertised when keep-alive has no timeout. By default the timeout is not
+    /// advertised.
+    pub fn advertise_keep_alive(mut self, enabled: bool) -> Self {
+        self.config.advertise_keep_alive = enabled;
+        self
+    }
+
+    /// Set the counters updated by connections of the service.
+    ///
+    /// Pass a clone of the same [`ConnectionStats`] to read them while the service is running.
+    pub fn stats(mut self, stats: ConnectionStats) -> Self {
+        self.config.stats = stats;
+        self
+    }
+
+    /// Set the age after which connections are gracefully closed.
+    ///
+    /// HTTP/2 connections send `GOAWAY`, finish their in-flight streams and close. HTTP/1.x
+    /// connections stop keep-alive, closing after the next response. Useful for spreading
+    /// long-lived connections across instances behind a load balancer.
+    ///
+    /// By default connections have no maximum age.
+    pub fn max_connection_age<A: Into<Option<Duration>>>(mut self, age: A) -> Self {
+        self.config.max_connection_age = age.into();
+        self
+    }
+
+    /// Set the handle used to gracefully shut down the connections of the service.
+    ///
+    /// Call [`GracefulShutdown::shutdown`] on a clone of the handle to close connections the same
+    /// way as when they reach their [maximum age](Self::max_connection_age).
+    pub fn graceful_shutdown(mut self, shutdown: GracefulShutdown) -> Self {
+        self.config.shutdown = shutdown;
+        self
+    }
+
+    /// Set the HTTP/2 connection settings, replacing any set before.
+    pub fn h2_settings(mut self, settings: H2Settings) -> Self {
+        self.config.h2_settings = settings;
+        self
+    }
+
+    /// Set the maximum number of concurrent streams per HTTP/2 connection.
+    ///
+    /// By default the `h2` crate's default is used.
+    pub fn h2_max_concurrent_streams(mut self, max: u32) -> Self {
+        self.config.h2_settings = self.config.h2_settings.max_concurrent_streams(max);
+        self
+    }
+
+    /// Set the initial HTTP/2 flow control window size of each stream in bytes.
+    ///
+    /// By default the protocol default of 65,535 bytes is used.
+    ///
+    /// # Panics
+    /// Panics if `size` is larger than 2^31 - 1.
+    pub fn h2_initial_window_size(mut self, size: u32) -> Self {
+        self.config.h2_settings = self.config.h2_settings.initial_window_size(size);
+        self
+    }
+
+    /// Set the initial HTTP/2 flow control window size of each connection in bytes.
+    ///
+    /// By default the protocol default of 65,535 bytes is used.
+    ///
+    /// # Panics
+    /// Panics if `size` is larger than 2^31 - 1.
+    pub fn h2_initial_connection_window_size(mut self, size: u32) -> Self {
+        self.config.h2_settings =
+            self.config.h2_settings.initial_connection_window_size(size);
+        self
+    }
+
+    /// Set the largest HTTP/2 frame payload accepted from clients in bytes.
+    ///
+    /// By default the protocol default of 16,384 bytes is used.
+    ///
+    /// # Panics
+    /// Panics if `size` is not between 2^14 and 2^24 - 1.
+    pub fn h2_max_frame_size(mut self, size: u32) -> Self {
+        self.config.h2_settings = self.config.h2_settings.max_frame_size(size);
         self
     }
 
@@ -130,11 +256,7 @@ where
         <X1::Service as Service<Request>>::Future: 'static,
     {
         HttpServiceBuilder {
-            keep_alive: self.keep_alive,
-            client_timeout: self.client_timeout,
-            client_disconnect: self.client_disconnect,
-            secure: self.secure,
-            local_addr: self.local_addr,
+            config: self.config,
             expect: expect.into_factory(),
             upgrade: self.upgrade,
             on_connect_ext: self.on_connect_ext,
@@ -155,11 +277,7 @@ where
         <U1::Service as Service<(Request, Framed<T, Codec>)>>::Future: 'static,
     {
         HttpServiceBuilder {
-            keep_alive: self.keep_alive,
-            client_timeout: self.client_timeout,
-            client_disconnect: self.client_disconnect,
-            secure: self.secure,
-            local_addr: self.local_addr,
+            config: self.config,
             expect: self.expect,
             upgrade: Some(upgrade.into_factory()),
             on_connect_ext: self.on_connect_ext,
@@ -189,13 +307,7 @@ where
         S::InitError: fmt::Debug,
         S::Response: Into<Response<B>>,
     {
-        let cfg = ServiceConfig::new(
-            self.keep_alive,
-            self.client_timeout,
-            self.client_disconnect,
-            self.secure,
-            self.local_addr,
-        );
+        let cfg = ServiceConfig::from_options(self.config);
 
         H1Service::with_config(cfg, service.into_factory())
             .expect(self.expect)
@@ -213,13 +325,7 @@ where
         S::Response: Into<Response<B>> + 'static,
         <S::Service as Service<Request>>::Future: 'static,
     {
-        let cfg = ServiceConfig::new(
-            self.keep_alive,
-            self.client_timeout,
-            self.client_disconnect,
-            self.secure,
-            self.local_addr,
-        );
+        let cfg = ServiceConfig::from_options(self.config);
 
         H2Service::with_config(cfg, service.into_factory())
             .on_connect_ext(self.on_connect_ext)
@@ -235,13 +341,7 @@ where
         S::Response: Into<Response<B>> + 'static,
         <S::Service as Service<Request>>::Future: 'static,
     {
-        let cfg = ServiceConfig::new(
-            self.keep_alive,
-            self.client_timeout,
-            self.client_disconnect,
-            self.secure,
-            self.local_addr,
-        );
+        let cfg = ServiceConfig::from_options(self.config);
 
         HttpService::with_config(cfg, service.into_factory())
             .expect(self.expect)
@@ -9,6 +9,7 @@ pub(crate) struct ConnectorConfig {
     pub(crate) timeout: Duration,
     pub(crate) conn_lifetime: Duration,
     pub(crate) conn_keep_alive: Duration,
+    pub(crate) max_idle_age: Option<Duration>,
     pub(crate) disconnect_timeout: Option<Duration>,
     pub(crate) limit: usize,
     pub(crate) conn_window_size: u32,
@@ -21,6 +22,7 @@ impl Default for ConnectorConfig {
             timeout: Duration::from_secs(5),
             conn_lifetime: Duration::from_secs(75),
             conn_keep_alive: Duration::from_secs(15),
+            max_idle_age: None,
             disconnect_timeout: Some(Duration::from_millis(3000)),
             limit: 100,
             conn_window_size: DEFAULT_H2_CONN_WINDOW,
@@ -217,6 +217,17 @@ where
         self
     }
 
+    /// Set max idle period for pooled connections and close them in the background.
+    ///
+    /// Idle connections are checked at an interval of `dur` and closed once they have been idle
+    /// for longer than `dur`, instead of waiting in the pool until the next request finds them
+    /// expired. This keeps connections from lingering after the server has likely given up on
+    /// them. Disabled by default.
+    pub fn max_idle_age(mut self, dur: Duration) -> Self {
+        self.config.max_idle_age = Some(dur);
+        self
+    }
+
     /// Set max lifetime period for connection.
     ///
     /// Connection lifetime is max lifetime of any opened connection
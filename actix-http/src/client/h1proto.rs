@@ -1,6 +1,7 @@
 use std::io::Write;
 use std::pin::Pin;
 use std::task::{Context, Poll};
+use std::time::Duration;
 use std::{io, time};
 
 use actix_codec::{AsyncRead, AsyncWrite, Framed, ReadBuf};
@@ -13,7 +14,8 @@ use futures_util::{SinkExt, StreamExt};
 use crate::error::PayloadError;
 use crate::h1;
 use crate::header::HeaderMap;
-use crate::http::header::{IntoHeaderValue, HOST};
+use crate::http::header::{IntoHeaderValue, EXPECT, HOST};
+use crate::http::StatusCode;
 use crate::message::{RequestHeadType, ResponseHead};
 use crate::payload::{Payload, PayloadStream};
 
@@ -22,6 +24,19 @@ use super::error::{ConnectError, SendRequestError};
 use super::pool::Acquired;
 use crate::body::{BodySize, MessageBody};
 
+/// How long a request with an `Expect: 100-continue` header waits for an interim response by
+/// default.
+const DEFAULT_EXPECT_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Request extension that sets how long an HTTP/1 request with an `Expect: 100-continue` header
+/// waits for the server before sending its body.
+///
+/// When the server answers with `100 Continue` the body is sent. When it answers with a final
+/// response instead, that response is returned and the body is not sent. When the timeout elapses
+/// first, the body is sent anyway. Without this extension the timeout is 1 second.
+#[derive(Debug, Clone, Copy)]
+pub struct ExpectContinue(pub Duration);
+
 pub(crate) async fn send_request<T, B>(
     io: T,
     mut head: RequestHeadType,
@@ -60,6 +75,8 @@ where
         }
     }
 
+    let expect = expect_continue(&head);
+
     let io = H1Connection {
         created,
         pool,
@@ -70,29 +87,107 @@ where
     let mut framed_inner = Framed::new(io, h1::ClientCodec::default());
     framed_inner.send((head, body.size()).into()).await?;
 
+    let has_body = !matches!(
+        body.size(),
+        BodySize::None | BodySize::Empty | BodySize::Sized(0)
+    );
+
+    // wait for the server to accept the body before sending it
+    if let Some(timeout) = expect.filter(|_| has_body) {
+        match actix_rt::time::timeout(timeout, read_head(&mut framed_inner, false)).await
+        {
+            Ok(Ok(head)) if head.status == StatusCode::CONTINUE => {}
+
+            // final response; the body is not sent and the connection can not be reused
+            Ok(Ok(head)) => {
+                framed_inner.codec_mut().force_close();
+                return read_response(head, framed_inner);
+            }
+
+            Ok(Err(err)) => return Err(err),
+
+            // the server did not respond in time, send the body anyway
+            Err(_) => {}
+        }
+    }
+
     // send request body
-    match body.size() {
-        BodySize::None | BodySize::Empty | BodySize::Sized(0) => {}
-        _ => send_body(body, Pin::new(&mut framed_inner)).await?,
-    };
+    if has_body {
+        send_body(body, Pin::new(&mut framed_inner)).await?;
+    }
 
-    // read response and init read body
-    let res = Pin::new(&mut framed_inner).into_future().await;
-    let (head, framed) = if let (Some(result), framed) = res {
-        let item = result.map_err(SendRequestError::from)?;
-        (item, framed)
-    } else {
-        return Err(SendRequestError::from(ConnectError::Disconnected));
+    // read response and init read body; a `100 Continue` that arrived too late is skipped
+    let head = read_head(&mut framed_inner, expect.is_some() && has_body).await?;
+    read_response(head, framed_inner)
+}
+
+/// Returns how long to wait for `100 Continue` if the request has an `Expect: 100-continue`
+/// header.
+fn expect_continue(head: &RequestHeadType) -> Option<Duration> {
+    let expects = |headers: &HeaderMap| {
+        headers.get_all(EXPECT).any(|val| {
+            val.to_str()
+                .map_or(false, |val| val.trim().eq_ignore_ascii_case("100-continue"))
+        })
     };
 
+    if !expects(&head.as_ref().headers) && !head.extra_headers().map_or(false, expects) {
+        return None;
+    }
+
+    let timeout = head
+        .as_ref()
+        .extensions()
+        .get::<ExpectContinue>()
+        .map_or(DEFAULT_EXPECT_TIMEOUT, |expect| expect.0);
+
+    Some(timeout)
+}
+
+/// Reads the next response head, skipping `103 Early Hints`, and `100 Continue` when
+/// `skip_continue` is set.
+async fn read_head<T>(
+    framed: &mut Framed<T, h1::ClientCodec>,
+    skip_continue: bool,
+) -> Result<ResponseHead, SendRequestError>
+where
+    T: AsyncRead + AsyncWrite + Unpin,
+{
+    loop {
+        match framed.next().await {
+            Some(Ok(head)) if is_interim(head.status, skip_continue) => continue,
+            Some(res) => return res.map_err(SendRequestError::from),
+            None => return Err(SendRequestError::from(ConnectError::Disconnected)),
+        }
+    }
+}
+
+/// Returns true for interim responses that are not handed to the caller: `103 Early Hints`, and
+/// `100 Continue` when `skip_continue` is set.
+fn is_interim(status: StatusCode, skip_continue: bool) -> bool {
+    match status.as_u16() {
+        100 => skip_continue,
+        103 => true,
+        _ => false,
+    }
+}
+
+/// Sets up the response payload for a response head read from `framed`.
+fn read_response<T>(
+    head: ResponseHead,
+    mut framed: Framed<T, h1::ClientCodec>,
+) -> Result<(ResponseHead, Payload), SendRequestError>
+where
+    T: ConnectionLifetime + Unpin,
+{
     match framed.codec_ref().message_type() {
         h1::MessageType::None => {
             let force_close = !framed.codec_ref().keepalive();
-            release_connection(framed, force_close);
+            release_connection(Pin::new(&mut framed), force_close);
             Ok((head, Payload::None))
         }
         _ => {
-            let pl: PayloadStream = PlStream::new(framed_inner).boxed_local();
+            let pl: PayloadStream = PlStream::new(framed).boxed_local();
             Ok((head, pl.into()))
         }
     }
@@ -17,6 +17,7 @@ pub use actix_tls::connect::{
 pub use self::connection::Connection;
 pub use self::connector::Connector;
 pub use self::error::{ConnectError, FreezeRequestError, InvalidUrl, SendRequestError};
+pub use self::h1proto::ExpectContinue;
 pub use self::pool::Protocol;
 
 #[derive(Clone)]
@@ -4,11 +4,14 @@ use std::collections::VecDeque;
 use std::future::Future;
 use std::ops::Deref;
 use std::pin::Pin;
-use std::rc::Rc;
+use std::rc::{Rc, Weak};
 use std::sync::Arc;
 use std::task::{Context, Poll};
 use std::time::{Duration, Instant};
-use std::{cell::RefCell, io};
+use std::{
+    cell::{Cell, RefCell},
+    io,
+};
 
 use actix_codec::{AsyncRead, AsyncWrite};
 use actix_rt::time::{sleep, Sleep};
@@ -70,6 +73,45 @@ where
             }
         }
     }
+
+    /// spawn a async task that closes connections idle for longer than `max_idle_age`.
+    ///
+    /// The task is spawned once and ends when the pool is dropped.
+    fn spawn_sweep(&self) {
+        let max_idle_age = match self.config.max_idle_age {
+            Some(max_idle_age) if !self.sweeping.replace(true) => max_idle_age,
+            _ => return,
+        };
+
+        let pool = Rc::downgrade(&self.0);
+
+        actix_rt::spawn(async move {
+            loop {
+                sleep(max_idle_age).await;
+
+                match Weak::upgrade(&pool) {
+                    Some(inner) => ConnectionPoolInner(inner).close_idle(max_idle_age),
+                    None => return,
+                }
+            }
+        });
+    }
+
+    /// close the pooled connections that have been idle for longer than `max_idle_age`.
+    fn close_idle(&self, max_idle_age: Duration) {
+        let now = Instant::now();
+
+        self.available.borrow_mut().retain(|_, conns| {
+            let (idle, used) = conns
+                .drain(..)
+                .partition::<VecDeque<_>, _>(|c| now - c.used > max_idle_age);
+
+            idle.into_iter().for_each(|c| self.close(c.conn));
+            *conns = used;
+
+            !conns.is_empty()
+        });
+    }
 }
 
 impl<Io> Clone for ConnectionPoolInner<Io>
@@ -117,6 +159,7 @@ where
     config: ConnectorConfig,
     available: RefCell<AHashMap<Key, VecDeque<PooledConnection<Io>>>>,
     permits: Arc<Semaphore>,
+    sweeping: Cell<bool>,
 }
 
 impl<S, Io> ConnectionPool<S, Io>
@@ -142,6 +185,7 @@ where
             config,
             available,
             permits,
+            sweeping: Cell::new(false),
         }));
 
         Self { connector, inner }
@@ -204,7 +248,8 @@ where
                         let idle_dur = now - c.used;
                         let age = now - c.created;
                         let conn_ineligible = idle_dur > config.conn_keep_alive
-                            || age > config.conn_lifetime;
+                            || age > config.conn_lifetime
+                            || config.max_idle_age.map_or(false, |max| idle_dur > max);
 
                         if conn_ineligible {
                             // drop connections that are too old
@@ -382,6 +427,8 @@ where
                 used: Instant::now(),
             });
 
+        inner.spawn_sweep();
+
         let _ = &mut self.permit;
     }
 }
@@ -589,6 +636,42 @@ mod test {
         release(conn);
     }
 
+    #[actix_rt::test]
+    async fn test_pool_max_idle_age() {
+        let generated = Rc::new(Cell::new(0));
+        let generated_clone = generated.clone();
+
+        let connector = TestPoolConnector { generated };
+
+        let config = ConnectorConfig {
+            max_idle_age: Some(Duration::from_millis(100)),
+            ..Default::default()
+        };
+
+        let pool = super::ConnectionPool::new(connector, config);
+
+        let req = Connect {
+            uri: Uri::from_static("http://localhost"),
+            addr: None,
+        };
+
+        let conn = pool.call(req.clone()).await.unwrap();
+        assert_eq!(1, generated_clone.get());
+        release(conn);
+
+        // idle connection is closed by the sweep without another request
+        actix_rt::time::sleep(Duration::from_millis(350)).await;
+        for _ in 0..2 {
+            actix_rt::task::yield_now().await;
+        }
+        assert_eq!(0, generated_clone.get());
+        assert!(pool.inner.available.borrow().is_empty());
+
+        let conn = pool.call(req).await.unwrap();
+        assert_eq!(1, generated_clone.get());
+        release(conn);
+    }
+
     #[actix_rt::test]
     async fn test_pool_authority_key() {
         let generated = Rc::new(Cell::new(0));
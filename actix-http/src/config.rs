@@ -11,6 +11,10 @@ use actix_rt::{
 use bytes::BytesMut;
 use time::OffsetDateTime;
 
+use crate::h2::H2Settings;
+use crate::shutdown::GracefulShutdown;
+use crate::stats::ConnectionStats;
+
 /// "Sun, 06 Nov 1994 08:49:37 GMT".len()
 const DATE_VALUE_LENGTH: usize = 29;
 
@@ -51,6 +55,14 @@ struct Inner {
     ka_enabled: bool,
     secure: bool,
     local_addr: Option<std::net::SocketAddr>,
+    h2_settings: H2Settings,
+    allow_h2c: bool,
+    allow_obsolete_header_folding: bool,
+    respond_after_half_close: bool,
+    advertise_keep_alive: bool,
+    stats: ConnectionStats,
+    max_connection_age: Option<Duration>,
+    shutdown: GracefulShutdown,
     date_service: DateService,
 }
 
@@ -62,7 +74,44 @@ impl Clone for ServiceConfig {
 
 impl Default for ServiceConfig {
     fn default() -> Self {
-        Self::new(KeepAlive::Timeout(5), 0, 0, false, None)
+        Self::from_options(ServiceConfigOptions::default())
+    }
+}
+
+/// Settings that a [`ServiceConfig`] is created from.
+pub(crate) struct ServiceConfigOptions {
+    pub(crate) keep_alive: KeepAlive,
+    pub(crate) client_timeout: u64,
+    pub(crate) client_disconnect: u64,
+    pub(crate) secure: bool,
+    pub(crate) local_addr: Option<net::SocketAddr>,
+    pub(crate) h2_settings: H2Settings,
+    pub(crate) allow_h2c: bool,
+    pub(crate) allow_obsolete_header_folding: bool,
+    pub(crate) respond_after_half_close: bool,
+    pub(crate) advertise_keep_alive: bool,
+    pub(crate) stats: ConnectionStats,
+    pub(crate) max_connection_age: Option<Duration>,
+    pub(crate) shutdown: GracefulShutdown,
+}
+
+impl Default for ServiceConfigOptions {
+    fn default() -> Self {
+        ServiceConfigOptions {
+            keep_alive: KeepAlive::Timeout(5),
+            client_timeout: 0,
+            client_disconnect: 0,
+            secure: false,
+            local_addr: None,
+            h2_settings: H2Settings::default(),
+            allow_h2c: false,
+            allow_obsolete_header_folding: false,
+            respond_after_half_close: true,
+            advertise_keep_alive: false,
+            stats: ConnectionStats::default(),
+            max_connection_age: None,
+            shutdown: GracefulShutdown::default(),
+        }
     }
 }
 
@@ -75,6 +124,33 @@ impl ServiceConfig {
         secure: bool,
         local_addr: Option<net::SocketAddr>,
     ) -> ServiceConfig {
+        Self::from_options(ServiceConfigOptions {
+            keep_alive,
+            client_timeout,
+            client_disconnect,
+            secure,
+            local_addr,
+            ..ServiceConfigOptions::default()
+        })
+    }
+
+    pub(crate) fn from_options(options: ServiceConfigOptions) -> ServiceConfig {
+        let ServiceConfigOptions {
+            keep_alive,
+            client_timeout,
+            client_disconnect,
+            secure,
+            local_addr,
+            h2_settings,
+            allow_h2c,
+            allow_obsolete_header_folding,
+            respond_after_half_close,
+            advertise_keep_alive,
+            stats,
+            max_connection_age,
+            shutdown,
+        } = options;
+
         let (keep_alive, ka_enabled) = match keep_alive {
             KeepAlive::Timeout(val) => (val as u64, true),
             KeepAlive::Os => (0, true),
@@ -93,6 +169,14 @@ impl ServiceConfig {
             client_disconnect,
             secure,
             local_addr,
+            h2_settings,
+            allow_h2c,
+            allow_obsolete_header_folding,
+            respond_after_half_close,
+            advertise_keep_alive,
+            stats,
+            max_connection_age,
+            shutdown,
             date_service: DateService::new(),
         }))
     }
@@ -109,6 +193,60 @@ impl ServiceConfig {
         self.0.local_addr
     }
 
+    /// Returns the HTTP/2 connection settings.
+    #[inline]
+    pub fn h2_settings(&self) -> &H2Settings {
+        &self.0.h2_settings
+    }
+
+    /// Returns true if HTTP/2 over cleartext TCP (h2c) is detected on plain connections.
+    #[inline]
+    pub fn allow_h2c(&self) -> bool {
+        self.0.allow_h2c
+    }
+
+    /// Returns true if HTTP/1 header fields using obsolete line folding are accepted.
+    ///
+    /// Otherwise requests with folded header fields are rejected with `400 Bad Request`.
+    #[inline]
+    pub fn allow_obsolete_header_folding(&self) -> bool {
+        self.0.allow_obsolete_header_folding
+    }
+
+    /// Returns true if HTTP/1 responses in flight are finished after the client half-closes the
+    /// connection.
+    ///
+    /// Otherwise the end of the read side closes the connection right away.
+    #[inline]
+    pub fn respond_after_half_close(&self) -> bool {
+        self.0.respond_after_half_close
+    }
+
+    /// Returns true if HTTP/1 keep-alive responses advertise the keep-alive timeout in a
+    /// `Keep-Alive` header.
+    #[inline]
+    pub fn advertise_keep_alive(&self) -> bool {
+        self.0.advertise_keep_alive
+    }
+
+    /// Returns the counters updated by connections using this configuration.
+    #[inline]
+    pub fn stats(&self) -> &ConnectionStats {
+        &self.0.stats
+    }
+
+    /// Returns the age after which connections are gracefully closed, if configured.
+    #[inline]
+    pub fn max_connection_age(&self) -> Option<Duration> {
+        self.0.max_connection_age
+    }
+
+    /// Returns the handle used to gracefully shut down connections.
+    #[inline]
+    pub fn shutdown(&self) -> &GracefulShutdown {
+        &self.0.shutdown
+    }
+
     /// Keep alive duration if configured.
     #[inline]
     pub fn keep_alive(&self) -> Option<Duration> {
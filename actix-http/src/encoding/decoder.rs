@@ -82,8 +82,8 @@ where
     ) -> Poll<Option<Self::Item>> {
         loop {
             if let Some(ref mut fut) = self.fut {
-                let (chunk, decoder) =
-                    ready!(Pin::new(fut).poll(cx)).map_err(|_| BlockingError)??;
+                let (chunk, decoder) = ready!(Pin::new(fut).poll(cx))
+                    .map_err(|_| BlockingError::Canceled)??;
 
                 self.decoder = Some(decoder);
                 self.fut.take();
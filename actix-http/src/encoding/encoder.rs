@@ -15,7 +15,7 @@ use futures_core::ready;
 use pin_project::pin_project;
 
 use crate::{
-    body::{Body, BodySize, MessageBody, ResponseBody},
+    body::{Body, BodySize, EitherBody, MessageBody, ResponseBody},
     http::{
         header::{ContentEncoding, CONTENT_ENCODING},
         HeaderValue, StatusCode,
@@ -32,7 +32,7 @@ const MAX_CHUNK_SIZE_ENCODE_IN_PLACE: usize = 1024;
 pub struct Encoder<B> {
     eof: bool,
     #[pin]
-    body: EncoderBody<B>,
+    body: EitherBody<B, Body>,
     encoder: Option<ContentEncoder>,
     fut: Option<JoinHandle<Result<ContentEncoder, io::Error>>>,
 }
@@ -53,16 +53,12 @@ impl<B: MessageBody> Encoder<B> {
             ResponseBody::Other(b) => match b {
                 Body::None => return ResponseBody::Other(Body::None),
                 Body::Empty => return ResponseBody::Other(Body::Empty),
-                Body::Bytes(buf) => {
-                    if can_encode {
-                        EncoderBody::Bytes(buf)
-                    } else {
-                        return ResponseBody::Other(Body::Bytes(buf));
-                    }
+                Body::Bytes(buf) if !can_encode => {
+                    return ResponseBody::Other(Body::Bytes(buf));
                 }
-                Body::Message(stream) => EncoderBody::BoxedStream(stream),
+                body => EitherBody::Right(body),
             },
-            ResponseBody::Body(stream) => EncoderBody::Stream(stream),
+            ResponseBody::Body(stream) => EitherBody::Left(stream),
         };
 
         if can_encode {
@@ -88,42 +84,6 @@ impl<B: MessageBody> Encoder<B> {
     }
 }
 
-#[pin_project(project = EncoderBodyProj)]
-enum EncoderBody<B> {
-    Bytes(Bytes),
-    Stream(#[pin] B),
-    BoxedStream(Box<dyn MessageBody + Unpin>),
-}
-
-impl<B: MessageBody> MessageBody for EncoderBody<B> {
-    fn size(&self) -> BodySize {
-        match self {
-            EncoderBody::Bytes(ref b) => b.size(),
-            EncoderBody::Stream(ref b) => b.size(),
-            EncoderBody::BoxedStream(ref b) => b.size(),
-        }
-    }
-
-    fn poll_next(
-        self: Pin<&mut Self>,
-        cx: &mut Context<'_>,
-    ) -> Poll<Option<Result<Bytes, Error>>> {
-        match self.project() {
-            EncoderBodyProj::Bytes(b) => {
-                if b.is_empty() {
-                    Poll::Ready(None)
-                } else {
-                    Poll::Ready(Some(Ok(std::mem::take(b))))
-                }
-            }
-            EncoderBodyProj::Stream(b) => b.poll_next(cx),
-            EncoderBodyProj::BoxedStream(ref mut b) => {
-                Pin::new(b.as_mut()).poll_next(cx)
-            }
-        }
-    }
-}
-
 impl<B: MessageBody> MessageBody for Encoder<B> {
     fn size(&self) -> BodySize {
         if self.encoder.is_none() {
@@ -144,8 +104,8 @@ impl<B: MessageBody> MessageBody for Encoder<B> {
             }
 
             if let Some(ref mut fut) = this.fut {
-                let mut encoder =
-                    ready!(Pin::new(fut).poll(cx)).map_err(|_| BlockingError)??;
+                let mut encoder = ready!(Pin::new(fut).poll(cx))
+                    .map_err(|_| BlockingError::Canceled)??;
 
                 let chunk = encoder.take();
                 *this.encoder = Some(encoder);
@@ -2,6 +2,7 @@
 
 use std::cell::RefCell;
 use std::io::Write;
+use std::net::SocketAddr;
 use std::str::Utf8Error;
 use std::string::FromUtf8Error;
 use std::{fmt, io, result};
@@ -12,13 +13,14 @@ use actix_utils::timeout::TimeoutError;
 use bytes::BytesMut;
 use derive_more::{Display, From};
 use http::uri::InvalidUri;
-use http::{header, Error as HttpError, StatusCode};
+use http::{header, Error as HttpError, Method, StatusCode};
 use serde::de::value::Error as DeError;
 use serde_json::error::Error as JsonError;
 use serde_urlencoded::ser::Error as FormError;
 
 use crate::body::Body;
 use crate::helpers::Writer;
+use crate::message::RequestHead;
 use crate::response::{Response, ResponseBuilder};
 
 #[cfg(feature = "cookies")]
@@ -54,7 +56,7 @@ impl Error {
 
     /// Similar to `as_response_error` but downcasts.
     pub fn as_error<T: ResponseError + 'static>(&self) -> Option<&T> {
-        ResponseError::downcast_ref(self.cause.as_ref())
+        <dyn ResponseError>::downcast_ref(self.cause.as_ref())
     }
 }
 
@@ -110,6 +112,18 @@ impl From<()> for Error {
     }
 }
 
+/// Converts a boxed error, unwrapping it when it is an `Error` itself.
+///
+/// Other errors generate an *INTERNAL SERVER ERROR* response.
+impl From<Box<dyn std::error::Error + 'static>> for Error {
+    fn from(err: Box<dyn std::error::Error + 'static>) -> Self {
+        match err.downcast::<Error>() {
+            Ok(err) => *err,
+            Err(err) => Error::from(BoxedError(err)),
+        }
+    }
+}
+
 impl From<std::convert::Infallible> for Error {
     fn from(_: std::convert::Infallible) -> Self {
         // `std::convert::Infallible` indicates an error
@@ -168,6 +182,13 @@ struct UnitError;
 /// Returns [`StatusCode::INTERNAL_SERVER_ERROR`] for [`UnitError`].
 impl ResponseError for UnitError {}
 
+/// Boxed error converted into an [`Error`].
+#[derive(Debug, Display)]
+struct BoxedError(Box<dyn std::error::Error + 'static>);
+
+/// Returns [`StatusCode::INTERNAL_SERVER_ERROR`] for [`BoxedError`].
+impl ResponseError for BoxedError {}
+
 /// Returns [`StatusCode::INTERNAL_SERVER_ERROR`] for [`JsonError`].
 impl ResponseError for JsonError {}
 
@@ -252,6 +273,9 @@ pub enum ParseError {
     /// Parsing a field as string failed
     #[display(fmt = "UTF8 error: {}", _0)]
     Utf8(Utf8Error),
+    /// A malformed chunked request payload, such as an invalid or overlong chunk size line.
+    #[display(fmt = "Chunked encoding error: {}", _0)]
+    Chunked(io::Error),
 }
 
 /// Return `BadRequest` for `ParseError`
@@ -301,13 +325,27 @@ impl From<httparse::Error> for ParseError {
 
 /// A set of errors that can occur running blocking tasks in thread pool.
 #[derive(Debug, Display)]
-#[display(fmt = "Blocking thread pool is gone")]
-pub struct BlockingError;
+pub enum BlockingError {
+    /// The blocking task was canceled or the thread pool is gone.
+    #[display(fmt = "Blocking thread pool is gone")]
+    Canceled,
+
+    /// The blocking task could not be queued because the thread pool queue is full.
+    #[display(fmt = "Blocking thread pool queue is full")]
+    QueueFull,
+}
 
 impl std::error::Error for BlockingError {}
 
-/// `InternalServerError` for `BlockingError`
-impl ResponseError for BlockingError {}
+/// Return `ServiceUnavailable` for `BlockingError::QueueFull` and `InternalServerError` otherwise.
+impl ResponseError for BlockingError {
+    fn status_code(&self) -> StatusCode {
+        match *self {
+            BlockingError::QueueFull => StatusCode::SERVICE_UNAVAILABLE,
+            BlockingError::Canceled => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
 
 #[derive(Display, Debug)]
 /// A set of errors that can occur during payload parsing
@@ -404,6 +442,11 @@ impl ResponseError for crate::cookie::ParseError {
 
 #[derive(Debug, Display, From)]
 /// A set of errors that can occur during dispatching HTTP requests
+///
+/// Use [`is_disconnect`](Self::is_disconnect), [`is_parse`](Self::is_parse) and
+/// [`is_timeout`](Self::is_timeout) to tell clients going away or misbehaving apart from errors
+/// of the server.
+#[non_exhaustive]
 pub enum DispatchError {
     /// Service error
     Service(Error),
@@ -421,7 +464,10 @@ pub enum DispatchError {
     Parse(ParseError),
 
     /// Http/2 error
+    ///
+    /// Errors of the underlying io stream are reported as [`DispatchError::Io`] instead.
     #[display(fmt = "{}", _0)]
+    #[from(ignore)]
     H2(h2::Error),
 
     /// The first request did not complete within the specified timeout.
@@ -447,8 +493,212 @@ pub enum DispatchError {
     /// Unknown error
     #[display(fmt = "Unknown error")]
     Unknown,
+
+    /// Error that occurred while handling a request, along with a description of that request.
+    #[display(fmt = "{} ({})", _0, _1)]
+    #[from(ignore)]
+    WithContext(Box<DispatchError>, RequestContext),
+}
+
+impl DispatchError {
+    /// Attaches a description of the request that was being handled when the error occurred.
+    ///
+    /// A context already attached to the error is replaced.
+    pub fn with_context(self, ctx: RequestContext) -> Self {
+        match self {
+            DispatchError::WithContext(err, _) => DispatchError::WithContext(err, ctx),
+            err => DispatchError::WithContext(Box::new(err), ctx),
+        }
+    }
+
+    /// Returns the description of the request the error occurred for, if known.
+    pub fn context(&self) -> Option<&RequestContext> {
+        match self {
+            DispatchError::WithContext(_, ctx) => Some(ctx),
+            _ => None,
+        }
+    }
+
+    /// Returns the error without its request context.
+    pub fn without_context(self) -> Self {
+        match self {
+            DispatchError::WithContext(err, _) => *err,
+            err => err,
+        }
+    }
+
+    /// Returns true if the error was caused by the client closing or resetting the connection.
+    ///
+    /// These are io errors of kind `BrokenPipe`, `ConnectionReset`, `ConnectionAborted`,
+    /// `NotConnected`, `UnexpectedEof` and `WriteZero`, and are usually not worth alerting on.
+    pub fn is_disconnect(&self) -> bool {
+        match self {
+            DispatchError::Io(err) | DispatchError::Parse(ParseError::Io(err)) => {
+                is_disconnect_kind(err.kind())
+            }
+            DispatchError::WithContext(err, _) => err.is_disconnect(),
+            _ => false,
+        }
+    }
+
+    /// Returns true if the client sent a request that could not be parsed.
+    pub fn is_parse(&self) -> bool {
+        match self {
+            DispatchError::Parse(ParseError::Io(_)) => false,
+            DispatchError::Parse(_) | DispatchError::MalformedRequest => true,
+            DispatchError::WithContext(err, _) => err.is_parse(),
+            _ => false,
+        }
+    }
+
+    /// Returns true if the connection was closed because a timeout elapsed.
+    pub fn is_timeout(&self) -> bool {
+        match self {
+            DispatchError::SlowRequestTimeout | DispatchError::DisconnectTimeout => true,
+            DispatchError::Io(err) => err.kind() == io::ErrorKind::TimedOut,
+            DispatchError::WithContext(err, _) => err.is_timeout(),
+            _ => false,
+        }
+    }
+}
+
+fn is_disconnect_kind(kind: io::ErrorKind) -> bool {
+    matches!(
+        kind,
+        io::ErrorKind::BrokenPipe
+            | io::ErrorKind::ConnectionReset
+            | io::ErrorKind::ConnectionAborted
+            | io::ErrorKind::NotConnected
+            | io::ErrorKind::UnexpectedEof
+            | io::ErrorKind::WriteZero
+    )
+}
+
+/// Reports errors of the io stream as [`DispatchError::Io`], so that they are classified the same
+/// way for HTTP/1 and HTTP/2 connections.
+impl From<h2::Error> for DispatchError {
+    fn from(err: h2::Error) -> Self {
+        if err.is_io() {
+            DispatchError::Io(err.into_io().unwrap())
+        } else {
+            DispatchError::H2(err)
+        }
+    }
+}
+
+/// Lightweight description of a request, used to correlate errors logged by the dispatchers
+/// with access logs.
+///
+/// Holds the method, path (without the query string), peer address and, when the client sent
+/// one, the `X-Request-Id` header of the request.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RequestContext {
+    method: Method,
+    path: String,
+    peer_addr: Option<SocketAddr>,
+    request_id: Option<String>,
+}
+
+impl RequestContext {
+    /// Describes the request with the given head.
+    pub fn new(head: &RequestHead) -> Self {
+        let request_id = head
+            .headers
+            .get("x-request-id")
+            .and_then(|id| id.to_str().ok())
+            .map(str::to_owned);
+
+        RequestContext {
+            method: head.method.clone(),
+            path: head.uri.path().to_owned(),
+            peer_addr: head.peer_addr,
+            request_id,
+        }
+    }
+
+    /// Returns the request method.
+    pub fn method(&self) -> &Method {
+        &self.method
+    }
+
+    /// Returns the request path, without the query string.
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// Returns the peer address of the connection the request was received on, if known.
+    pub fn peer_addr(&self) -> Option<SocketAddr> {
+        self.peer_addr
+    }
+
+    /// Returns the value of the request's `X-Request-Id` header, if present.
+    pub fn request_id(&self) -> Option<&str> {
+        self.request_id.as_deref()
+    }
+}
+
+impl fmt::Display for RequestContext {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.method, self.path)?;
+
+        if let Some(addr) = self.peer_addr {
+            write!(f, " from {}", addr)?;
+        }
+
+        if let Some(ref id) = self.request_id {
+            write!(f, ", request id {}", id)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// A set of errors that can occur when pushing a resource with HTTP/2 server push.
+#[derive(Debug, Display)]
+pub enum PushError {
+    /// Server push is not available, e.g. on HTTP/1.x connections or after the response to the
+    /// request has been sent.
+    #[display(fmt = "Server push is not available for this request")]
+    Unavailable,
+
+    /// The pushed resource's URI is invalid.
+    #[display(fmt = "Invalid push URI: {}", _0)]
+    InvalidUri(HttpError),
+
+    /// The client's `SETTINGS_MAX_CONCURRENT_STREAMS` limit has been reached.
+    #[display(fmt = "Too many concurrent pushed streams")]
+    TooManyStreams,
+
+    /// The push promise was rejected, e.g. because the client disabled server push.
+    #[display(fmt = "{}", _0)]
+    H2(h2::Error),
+}
+
+impl std::error::Error for PushError {}
+
+impl ResponseError for PushError {}
+
+/// Error of a response body that produced a different number of bytes than its declared size,
+/// see [`SizedStream`](crate::body::SizedStream).
+#[derive(Debug, Display, PartialEq)]
+pub enum BodyLengthError {
+    /// The body produced more bytes than declared.
+    #[display(fmt = "Response body is longer than its declared size of {} bytes", _0)]
+    TooLong(u64),
+
+    /// The body ended before producing the declared number of bytes.
+    #[display(
+        fmt = "Response body ended after {} of its declared {} bytes",
+        received,
+        expected
+    )]
+    TooShort { expected: u64, received: u64 },
 }
 
+impl std::error::Error for BodyLengthError {}
+
+impl ResponseError for BodyLengthError {}
+
 /// A set of error that can occur during parsing content type
 #[derive(PartialEq, Debug, Display)]
 pub enum ContentTypeError {
@@ -1021,6 +1271,17 @@ mod tests {
         assert_eq!(resp.status(), StatusCode::INTERNAL_SERVER_ERROR);
     }
 
+    #[test]
+    fn test_response_error_source() {
+        let resp = Response::from_error(ErrorBadRequest("nope"));
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+        let source = resp.error().unwrap();
+        assert_eq!(source.to_string(), "nope");
+        assert!(source.as_error::<InternalError<&str>>().is_some());
+        assert!(source.as_error::<ParseError>().is_none());
+    }
+
     #[test]
     fn test_payload_error() {
         let err: PayloadError =
@@ -1070,6 +1331,79 @@ mod tests {
         from!(httparse::Error::Version => ParseError::Version);
     }
 
+    #[test]
+    fn test_dispatch_error_context() {
+        let mut req = crate::test::TestRequest::with_uri("/upload?name=a")
+            .method(http::Method::POST)
+            .insert_header(("x-request-id", "abc"))
+            .finish();
+        req.head_mut().peer_addr = Some("127.0.0.1:8080".parse().unwrap());
+
+        let ctx = RequestContext::new(req.head());
+        assert_eq!(ctx.path(), "/upload");
+        assert_eq!(ctx.request_id(), Some("abc"));
+
+        let err = DispatchError::InternalError.with_context(ctx.clone());
+        assert_eq!(
+            err.to_string(),
+            "Internal error (POST /upload from 127.0.0.1:8080, request id abc)"
+        );
+        assert_eq!(err.context(), Some(&ctx));
+
+        // contexts are replaced instead of nested
+        let other =
+            RequestContext::new(crate::test::TestRequest::default().finish().head());
+        let err = err.with_context(other.clone());
+        assert_eq!(err.context(), Some(&other));
+        assert!(matches!(
+            err.without_context(),
+            DispatchError::InternalError
+        ));
+    }
+
+    #[test]
+    fn test_dispatch_error_classification() {
+        for kind in &[
+            io::ErrorKind::BrokenPipe,
+            io::ErrorKind::ConnectionReset,
+            io::ErrorKind::UnexpectedEof,
+        ] {
+            let err = DispatchError::Io(io::Error::from(*kind));
+            assert!(err.is_disconnect());
+            assert!(!err.is_parse());
+            assert!(!err.is_timeout());
+        }
+
+        let err = DispatchError::Io(io::ErrorKind::PermissionDenied.into());
+        assert!(!err.is_disconnect());
+
+        let err = DispatchError::Io(io::ErrorKind::TimedOut.into());
+        assert!(err.is_timeout());
+        assert!(DispatchError::SlowRequestTimeout.is_timeout());
+
+        let err = DispatchError::from(ParseError::Method);
+        assert!(err.is_parse());
+        assert!(!err.is_disconnect());
+        assert!(DispatchError::MalformedRequest.is_parse());
+
+        // io errors surfacing through the parser are disconnects, not malformed requests
+        let err =
+            DispatchError::from(ParseError::Io(io::ErrorKind::ConnectionReset.into()));
+        assert!(err.is_disconnect());
+        assert!(!err.is_parse());
+
+        let err = DispatchError::from(h2::Error::from(h2::Reason::PROTOCOL_ERROR));
+        assert!(matches!(err, DispatchError::H2(_)));
+        assert!(!err.is_disconnect());
+
+        // the request context does not hide the classification
+        let ctx =
+            RequestContext::new(crate::test::TestRequest::default().finish().head());
+        let err = DispatchError::Io(io::ErrorKind::BrokenPipe.into()).with_context(ctx);
+        assert!(err.is_disconnect());
+        assert!(!DispatchError::InternalError.is_disconnect());
+    }
+
     #[test]
     fn test_internal_error() {
         let err =
@@ -1,6 +1,6 @@
 use std::{
-    any::{Any, TypeId},
-    fmt, mem,
+    any::{type_name, Any, TypeId},
+    fmt,
 };
 
 use ahash::AHashMap;
@@ -12,7 +12,22 @@ use ahash::AHashMap;
 pub struct Extensions {
     /// Use FxHasher with a std HashMap with for faster
     /// lookups on the small `TypeId` (u64 equivalent) keys.
-    map: AHashMap<TypeId, Box<dyn Any>>,
+    map: AHashMap<TypeId, Entry>,
+}
+
+/// A stored item along with the name of its type, for debugging.
+struct Entry {
+    type_name: &'static str,
+    value: Box<dyn Any>,
+}
+
+impl Entry {
+    fn new<T: 'static>(val: T) -> Self {
+        Entry {
+            type_name: type_name::<T>(),
+            value: Box::new(val),
+        }
+    }
 }
 
 impl Extensions {
@@ -38,10 +53,34 @@ impl Extensions {
     /// ```
     pub fn insert<T: 'static>(&mut self, val: T) -> Option<T> {
         self.map
-            .insert(TypeId::of::<T>(), Box::new(val))
+            .insert(TypeId::of::<T>(), Entry::new(val))
             .and_then(downcast_owned)
     }
 
+    /// Get a mutable reference to an item of a given type, inserting the result of `default` if
+    /// the map does not contain one.
+    ///
+    /// Needs a single lookup, which is convenient while holding a `RefCell` borrow of the map.
+    ///
+    /// ```
+    /// # use actix_http::Extensions;
+    /// let mut map = Extensions::new();
+    /// map.get_or_insert_with(Vec::new).push(1u32);
+    /// map.get_or_insert_with(Vec::new).push(2u32);
+    /// assert_eq!(map.get::<Vec<u32>>(), Some(&vec![1, 2]));
+    /// ```
+    pub fn get_or_insert_with<T: 'static, F: FnOnce() -> T>(
+        &mut self,
+        default: F,
+    ) -> &mut T {
+        self.map
+            .entry(TypeId::of::<T>())
+            .or_insert_with(|| Entry::new(default()))
+            .value
+            .downcast_mut()
+            .expect("extensions are keyed by the type id of their value")
+    }
+
     /// Check if map contains an item of a given type.
     ///
     /// ```
@@ -67,7 +106,7 @@ impl Extensions {
     pub fn get<T: 'static>(&self) -> Option<&T> {
         self.map
             .get(&TypeId::of::<T>())
-            .and_then(|boxed| boxed.downcast_ref())
+            .and_then(|entry| entry.value.downcast_ref())
     }
 
     /// Get a mutable reference to an item of a given type.
@@ -81,7 +120,7 @@ impl Extensions {
     pub fn get_mut<T: 'static>(&mut self) -> Option<&mut T> {
         self.map
             .get_mut(&TypeId::of::<T>())
-            .and_then(|boxed| boxed.downcast_mut())
+            .and_then(|entry| entry.value.downcast_mut())
     }
 
     /// Remove an item from the map of a given type.
@@ -119,25 +158,64 @@ impl Extensions {
         self.map.clear();
     }
 
+    /// Returns the number of items in the map.
+    ///
+    /// ```
+    /// # use actix_http::Extensions;
+    /// let mut map = Extensions::new();
+    /// assert_eq!(map.len(), 0);
+    ///
+    /// map.insert(1u32);
+    /// map.insert(2u32);
+    /// map.insert("");
+    /// assert_eq!(map.len(), 2);
+    /// ```
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    /// Returns true if the map contains no items.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// Iterates over the type ids and type names of the stored items, in arbitrary order.
+    ///
+    /// Type names are those returned by [`std::any::type_name`] and are only meant for
+    /// debugging, such as finding out which middleware inserted what.
+    ///
+    /// ```
+    /// # use actix_http::Extensions;
+    /// # use std::any::TypeId;
+    /// let mut map = Extensions::new();
+    /// map.insert(1u32);
+    ///
+    /// let types = map.types().collect::<Vec<_>>();
+    /// assert_eq!(types, vec![(TypeId::of::<u32>(), "u32")]);
+    /// ```
+    pub fn types(&self) -> impl Iterator<Item = (TypeId, &'static str)> + '_ {
+        self.map.iter().map(|(id, entry)| (*id, entry.type_name))
+    }
+
     /// Extends self with the items from another `Extensions`.
     pub fn extend(&mut self, other: Extensions) {
         self.map.extend(other.map);
     }
-
-    /// Sets (or overrides) items from `other` into this map.
-    pub(crate) fn drain_from(&mut self, other: &mut Self) {
-        self.map.extend(mem::take(&mut other.map));
-    }
 }
 
 impl fmt::Debug for Extensions {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        f.debug_struct("Extensions").finish()
+        let mut types = self.types().map(|(_, name)| name).collect::<Vec<_>>();
+        types.sort_unstable();
+
+        f.debug_struct("Extensions").field("types", &types).finish()
     }
 }
 
-fn downcast_owned<T: 'static>(boxed: Box<dyn Any>) -> Option<T> {
-    boxed.downcast().ok().map(|boxed| *boxed)
+fn downcast_owned<T: 'static>(entry: Entry) -> Option<T> {
+    entry.value.downcast().ok().map(|boxed| *boxed)
 }
 
 #[cfg(test)]
@@ -281,25 +359,77 @@ mod tests {
     }
 
     #[test]
-    fn test_drain_from() {
-        let mut ext = Extensions::new();
-        ext.insert(2isize);
+    fn test_get_or_insert_with() {
+        use std::cell::RefCell;
+
+        let map = RefCell::new(Extensions::new());
+
+        let mut calls = 0;
+        for i in 0..3u32 {
+            map.borrow_mut()
+                .get_or_insert_with(|| {
+                    calls += 1;
+                    Vec::new()
+                })
+                .push(i);
+        }
 
-        let mut more_ext = Extensions::new();
+        assert_eq!(calls, 1);
+        assert_eq!(map.borrow().get::<Vec<u32>>(), Some(&vec![0, 1, 2]));
 
-        more_ext.insert(5isize);
-        more_ext.insert(5usize);
+        let mut map = map.into_inner();
+        map.insert(5u8);
+        assert_eq!(*map.get_or_insert_with(|| 10u8), 5);
+    }
 
-        assert_eq!(ext.get::<isize>(), Some(&2isize));
-        assert_eq!(ext.get::<usize>(), None);
-        assert_eq!(more_ext.get::<isize>(), Some(&5isize));
-        assert_eq!(more_ext.get::<usize>(), Some(&5usize));
+    #[test]
+    fn test_remove_returns_value() {
+        #[derive(Debug, PartialEq)]
+        struct MyType(String);
 
-        ext.drain_from(&mut more_ext);
+        let mut map = Extensions::new();
+        map.insert(MyType("value".to_owned()));
 
-        assert_eq!(ext.get::<isize>(), Some(&5isize));
-        assert_eq!(ext.get::<usize>(), Some(&5usize));
-        assert_eq!(more_ext.get::<isize>(), None);
-        assert_eq!(more_ext.get::<usize>(), None);
+        assert_eq!(map.remove::<MyType>(), Some(MyType("value".to_owned())));
+        assert_eq!(map.remove::<MyType>(), None);
+    }
+
+    #[test]
+    fn test_len() {
+        let mut map = Extensions::new();
+        assert!(map.is_empty());
+
+        map.insert::<i8>(8);
+        map.insert::<i16>(16);
+        map.insert::<i8>(9);
+        assert_eq!(map.len(), 2);
+        assert!(!map.is_empty());
+
+        map.remove::<i8>();
+        assert_eq!(map.len(), 1);
+
+        map.clear();
+        assert!(map.is_empty());
+    }
+
+    #[test]
+    fn test_types() {
+        struct MyType;
+
+        let mut map = Extensions::new();
+        map.insert(8u8);
+        map.insert(MyType);
+
+        let types = map.types().collect::<Vec<_>>();
+        assert_eq!(types.len(), 2);
+        assert!(types.contains(&(TypeId::of::<u8>(), "u8")));
+        assert!(types.iter().any(
+            |(id, name)| *id == TypeId::of::<MyType>() && name.ends_with("::MyType")
+        ));
+
+        let dbg = format!("{:?}", map);
+        assert!(dbg.starts_with("Extensions { types: ["));
+        assert!(dbg.contains("\"u8\""));
+        assert!(dbg.contains("::MyType\""));
     }
 }
@@ -0,0 +1,63 @@
+use std::{cell::Cell, fmt, rc::Rc};
+
+use super::payload::MAX_BUFFER_SIZE;
+
+/// Write buffer state of an upgraded HTTP/1 connection.
+///
+/// Inserted into the extensions of requests asking for a protocol upgrade (such as WebSocket
+/// handshakes) which are answered with a streaming response. Response bodies can use it to see
+/// how much data is waiting to be written to the client.
+///
+/// By default the dispatcher stops polling the response body while its write buffer is full.
+/// After [`subscribe`](Self::subscribe) is called the body keeps being polled, so it can keep
+/// processing its own work while the client is not reading. A subscribed body must not yield
+/// data while [`is_blocked`](Self::is_blocked) returns true; yielding an error drops the
+/// connection without flushing the write buffer.
+#[derive(Clone)]
+pub struct WriteBackpressure(Rc<Inner>);
+
+struct Inner {
+    buffered: Cell<usize>,
+    subscribed: Cell<bool>,
+}
+
+impl WriteBackpressure {
+    pub(crate) fn new() -> Self {
+        WriteBackpressure(Rc::new(Inner {
+            buffered: Cell::new(0),
+            subscribed: Cell::new(false),
+        }))
+    }
+
+    pub(crate) fn set_buffered(&self, len: usize) {
+        self.0.buffered.set(len);
+    }
+
+    pub(crate) fn is_subscribed(&self) -> bool {
+        self.0.subscribed.get()
+    }
+
+    /// Number of bytes in the connection's write buffer.
+    pub fn buffered(&self) -> usize {
+        self.0.buffered.get()
+    }
+
+    /// Returns true if the write buffer is full and no more data should be produced.
+    pub fn is_blocked(&self) -> bool {
+        self.buffered() >= MAX_BUFFER_SIZE
+    }
+
+    /// Keep polling the response body while the write buffer is full.
+    pub fn subscribe(&self) {
+        self.0.subscribed.set(true);
+    }
+}
+
+impl fmt::Debug for WriteBackpressure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WriteBackpressure")
+            .field("buffered", &self.buffered())
+            .field("subscribed", &self.is_subscribed())
+            .finish()
+    }
+}
@@ -83,6 +83,11 @@ impl ClientCodec {
         self.inner.ctype == ConnectionType::KeepAlive
     }
 
+    /// Close the connection after the current response, even if it is keep-alive.
+    pub(crate) fn force_close(&mut self) {
+        self.inner.ctype = ConnectionType::Close;
+    }
+
     /// Check last request's message type
     pub fn message_type(&self) -> MessageType {
         if self.inner.flags.contains(Flags::STREAM) {
@@ -1,16 +1,18 @@
-use std::{fmt, io};
+use std::{collections::VecDeque, fmt, io};
 
 use actix_codec::{Decoder, Encoder};
 use bitflags::bitflags;
-use bytes::BytesMut;
-use http::{Method, Version};
+use bytes::{Bytes, BytesMut};
+use http::{Method, StatusCode, Version};
 
-use super::decoder::{PayloadDecoder, PayloadItem, PayloadType};
+use super::decoder::{PayloadDecoder, PayloadItem, PayloadType, Unfold};
 use super::{decoder, encoder};
 use super::{Message, MessageType};
 use crate::body::BodySize;
 use crate::config::ServiceConfig;
 use crate::error::ParseError;
+use crate::header::{self, HeaderMap};
+use crate::helpers;
 use crate::message::ConnectionType;
 use crate::request::Request;
 use crate::response::Response;
@@ -20,6 +22,7 @@ bitflags! {
         const HEAD              = 0b0000_0001;
         const KEEPALIVE_ENABLED = 0b0000_0010;
         const STREAM            = 0b0000_0100;
+        const FOLDED            = 0b0000_1000;
     }
 }
 
@@ -104,6 +107,85 @@ impl Codec {
     pub fn config(&self) -> &ServiceConfig {
         &self.config
     }
+
+    /// Finish the response payload with trailer fields.
+    ///
+    /// Return `false` if the current payload encoding can not carry trailers.
+    pub(crate) fn encode_trailers(
+        &mut self,
+        trailers: &HeaderMap,
+        dst: &mut BytesMut,
+    ) -> io::Result<bool> {
+        self.encoder.encode_trailers(trailers, dst)
+    }
+
+    /// Encode the head of an informational (`1xx`) response, sent before the final response.
+    pub(crate) fn encode_informational(
+        &self,
+        status: StatusCode,
+        headers: &HeaderMap,
+        dst: &mut BytesMut,
+    ) {
+        helpers::write_status_line(Version::HTTP_11, status.as_u16(), dst);
+        let reason = match status.as_u16() {
+            // not known to the http crate
+            103 => "Early Hints",
+            _ => status.canonical_reason().unwrap_or(""),
+        };
+        dst.extend_from_slice(reason.as_bytes());
+        dst.extend_from_slice(b"\r\n");
+
+        for (name, value) in headers {
+            dst.extend_from_slice(name.as_str().as_bytes());
+            dst.extend_from_slice(b": ");
+            dst.extend_from_slice(value.as_bytes());
+            dst.extend_from_slice(b"\r\n");
+        }
+
+        dst.extend_from_slice(b"\r\n");
+    }
+
+    /// Returns true if header fields of the last decoded request used obsolete line folding.
+    pub(crate) fn obsolete_folding(&self) -> bool {
+        self.flags.contains(Flags::FOLDED)
+    }
+
+    /// Decode request head, joining header lines that use obsolete line folding.
+    fn decode_head(
+        &mut self,
+        src: &mut BytesMut,
+    ) -> Result<Option<(Request, PayloadType)>, ParseError> {
+        match self.decoder.decode(src) {
+            Err(ParseError::Header) => match decoder::unfold_headers(src)? {
+                Unfold::Unfolded => {
+                    self.flags.insert(Flags::FOLDED);
+                    self.decoder.decode(src)
+                }
+                Unfold::Partial if src.len() >= decoder::MAX_BUFFER_SIZE => {
+                    Err(ParseError::TooLarge)
+                }
+                Unfold::Partial => Ok(None),
+                Unfold::None => Err(ParseError::Header),
+            },
+            res => {
+                self.flags.remove(Flags::FOLDED);
+                res
+            }
+        }
+    }
+
+    /// Encode a chunk of the response payload, queueing large chunks instead of copying them.
+    ///
+    /// The queue is written before `dst`.
+    pub(crate) fn encode_chunk_queued(
+        &mut self,
+        chunk: Bytes,
+        dst: &mut BytesMut,
+        queue: &mut VecDeque<Bytes>,
+    ) -> io::Result<()> {
+        self.encoder.encode_chunk_queued(chunk, dst, queue)?;
+        Ok(())
+    }
 }
 
 impl Decoder for Codec {
@@ -112,7 +194,7 @@ impl Decoder for Codec {
 
     fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
         if let Some(ref mut payload) = self.payload {
-            Ok(match payload.decode(src)? {
+            Ok(match payload.decode(src).map_err(ParseError::Chunked)? {
                 Some(PayloadItem::Chunk(chunk)) => Some(Message::Chunk(Some(chunk))),
                 Some(PayloadItem::Eof) => {
                     self.payload.take();
@@ -120,7 +202,7 @@ impl Decoder for Codec {
                 }
                 None => None,
             })
-        } else if let Some((req, payload)) = self.decoder.decode(src)? {
+        } else if let Some((req, payload)) = self.decode_head(src)? {
             let head = req.head();
             self.flags.set(Flags::HEAD, head.method == Method::HEAD);
             self.version = head.version;
@@ -158,6 +240,9 @@ impl Encoder<Message<(Response<()>, BodySize)>> for Codec {
                 // set response version
                 res.head_mut().version = self.version;
 
+                // merge accumulated vary names
+                header::vary::finalize(res.head_mut());
+
                 // connection status
                 self.ctype = if let Some(ct) = res.head().ctype() {
                     if ct == ConnectionType::KeepAlive {
@@ -199,7 +284,7 @@ mod tests {
     use http::Method;
 
     use super::*;
-    use crate::HttpMessage;
+    use crate::{error::ResponseError as _, HttpMessage};
 
     #[actix_rt::test]
     async fn test_http_request_chunked_payload_and_next_message() {
@@ -237,4 +322,43 @@ mod tests {
         assert_eq!(*req.method(), Method::POST);
         assert!(req.chunked().unwrap());
     }
+
+    #[actix_rt::test]
+    async fn test_obsolete_folding() {
+        let mut codec = Codec::default();
+
+        let mut buf = BytesMut::from("GET /test HTTP/1.1\r\nUser-Agent: Device/1.0\r\n");
+        assert!(codec.decode(&mut buf).unwrap().is_none());
+
+        buf.extend_from_slice(b" (embedded)\r\n\r\n");
+        let item = codec.decode(&mut buf).unwrap().unwrap();
+        let req = item.message();
+        assert_eq!(
+            req.headers().get("user-agent").unwrap(),
+            "Device/1.0 (embedded)"
+        );
+        assert!(codec.obsolete_folding());
+
+        // flag is reset by the next request
+        buf.extend_from_slice(b"GET /test HTTP/1.1\r\nUser-Agent: Device/1.0\r\n\r\n");
+        codec.decode(&mut buf).unwrap().unwrap();
+        assert!(!codec.obsolete_folding());
+
+        // request line can not be folded
+        buf.extend_from_slice(b"GET /test HTTP/1.1\r\n User-Agent: Device/1.0\r\n\r\n");
+        assert!(matches!(codec.decode(&mut buf), Err(ParseError::Header)));
+    }
+
+    #[actix_rt::test]
+    async fn test_chunked_error() {
+        let mut codec = Codec::default();
+        let mut buf =
+            BytesMut::from("POST /test HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n");
+        codec.decode(&mut buf).unwrap().unwrap();
+
+        buf.extend_from_slice(b"zz\r\n");
+        let err = codec.decode(&mut buf).unwrap_err();
+        assert!(matches!(err, ParseError::Chunked(_)));
+        assert_eq!(err.status_code(), http::StatusCode::BAD_REQUEST);
+    }
 }
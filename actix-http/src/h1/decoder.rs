@@ -1,4 +1,3 @@
-use std::convert::TryFrom;
 use std::io;
 use std::marker::PhantomData;
 use std::task::Poll;
@@ -16,9 +15,20 @@ use crate::request::Request;
 
 pub(crate) const MAX_BUFFER_SIZE: usize = 131_072;
 const MAX_HEADERS: usize = 96;
+const MAX_CACHED_NAMES: usize = 32;
+
+/// Maximum number of hex digits in a chunk size, enough for any `u64`.
+const MAX_CHUNK_SIZE_DIGITS: usize = 16;
+
+/// Maximum length of the whitespace and chunk extensions following the size of a single chunk,
+/// which are discarded.
+const MAX_CHUNK_EXTENSION_LEN: usize = 1024;
 
 /// Incoming message decoder
-pub(crate) struct MessageDecoder<T: MessageType>(PhantomData<T>);
+pub(crate) struct MessageDecoder<T: MessageType> {
+    names: HeaderNameCache,
+    _t: PhantomData<T>,
+}
 
 #[derive(Debug)]
 /// Incoming request type
@@ -30,7 +40,10 @@ pub(crate) enum PayloadType {
 
 impl<T: MessageType> Default for MessageDecoder<T> {
     fn default() -> Self {
-        MessageDecoder(PhantomData)
+        MessageDecoder {
+            names: HeaderNameCache::default(),
+            _t: PhantomData,
+        }
     }
 }
 
@@ -39,7 +52,7 @@ impl<T: MessageType> Decoder for MessageDecoder<T> {
     type Error = ParseError;
 
     fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
-        T::decode(src)
+        T::decode(src, &mut self.names)
     }
 }
 
@@ -56,12 +69,16 @@ pub(crate) trait MessageType: Sized {
 
     fn headers_mut(&mut self) -> &mut HeaderMap;
 
-    fn decode(src: &mut BytesMut) -> Result<Option<(Self, PayloadType)>, ParseError>;
+    fn decode(
+        src: &mut BytesMut,
+        names: &mut HeaderNameCache,
+    ) -> Result<Option<(Self, PayloadType)>, ParseError>;
 
     fn set_headers(
         &mut self,
         slice: &Bytes,
         raw_headers: &[HeaderIndex],
+        names: &mut HeaderNameCache,
     ) -> Result<PayloadLength, ParseError> {
         let mut ka = None;
         let mut has_upgrade_websocket = false;
@@ -72,9 +89,8 @@ pub(crate) trait MessageType: Sized {
         {
             let headers = self.headers_mut();
 
-            for idx in raw_headers.iter() {
-                let name =
-                    HeaderName::from_bytes(&slice[idx.name.0..idx.name.1]).unwrap();
+            for (pos, idx) in raw_headers.iter().enumerate() {
+                let name = names.get(pos, &slice[idx.name.0..idx.name.1]);
 
                 // SAFETY: httparse already checks header value is only visible ASCII bytes
                 // from_maybe_shared_unchecked contains debug assertions so they are omitted here
@@ -182,10 +198,13 @@ impl MessageType for Request {
         &mut self.head_mut().headers
     }
 
-    fn decode(src: &mut BytesMut) -> Result<Option<(Self, PayloadType)>, ParseError> {
+    fn decode(
+        src: &mut BytesMut,
+        names: &mut HeaderNameCache,
+    ) -> Result<Option<(Self, PayloadType)>, ParseError> {
         let mut headers: [HeaderIndex; MAX_HEADERS] = EMPTY_HEADER_INDEX_ARRAY;
 
-        let (len, method, uri, ver, h_len) = {
+        let (len, method, path, ver, h_len) = {
             let mut parsed: [httparse::Header<'_>; MAX_HEADERS] = EMPTY_HEADER_ARRAY;
 
             let mut req = httparse::Request::new(&mut parsed);
@@ -193,7 +212,8 @@ impl MessageType for Request {
                 httparse::Status::Complete(len) => {
                     let method = Method::from_bytes(req.method.unwrap().as_bytes())
                         .map_err(|_| ParseError::Method)?;
-                    let uri = Uri::try_from(req.path.unwrap())?;
+                    let path = req.path.unwrap();
+                    let path_start = path.as_ptr() as usize - src.as_ptr() as usize;
                     let version = if req.version.unwrap() == 1 {
                         Version::HTTP_11
                     } else {
@@ -201,7 +221,9 @@ impl MessageType for Request {
                     };
                     HeaderIndex::record(src, req.headers, &mut headers);
 
-                    (len, method, uri, version, req.headers.len())
+                    let path = (path_start, path_start + path.len());
+
+                    (len, method, path, version, req.headers.len())
                 }
                 httparse::Status::Partial => {
                     return if src.len() >= MAX_BUFFER_SIZE {
@@ -215,10 +237,15 @@ impl MessageType for Request {
             }
         };
 
+        let slice = src.split_to(len).freeze();
+
+        // uri shares the message head buffer instead of copying the path
+        let uri = Uri::from_maybe_shared(slice.slice(path.0..path.1))?;
+
         let mut msg = Request::new();
 
         // convert headers
-        let length = msg.set_headers(&src.split_to(len).freeze(), &headers[..h_len])?;
+        let length = msg.set_headers(&slice, &headers[..h_len], names)?;
 
         // payload decoder
         let decoder = match length {
@@ -258,7 +285,10 @@ impl MessageType for ResponseHead {
         &mut self.headers
     }
 
-    fn decode(src: &mut BytesMut) -> Result<Option<(Self, PayloadType)>, ParseError> {
+    fn decode(
+        src: &mut BytesMut,
+        names: &mut HeaderNameCache,
+    ) -> Result<Option<(Self, PayloadType)>, ParseError> {
         let mut headers: [HeaderIndex; MAX_HEADERS] = EMPTY_HEADER_INDEX_ARRAY;
 
         let (len, ver, status, h_len) = {
@@ -293,7 +323,8 @@ impl MessageType for ResponseHead {
         msg.version = ver;
 
         // convert headers
-        let length = msg.set_headers(&src.split_to(len).freeze(), &headers[..h_len])?;
+        let length =
+            msg.set_headers(&src.split_to(len).freeze(), &headers[..h_len], names)?;
 
         // message payload
         let decoder = if let PayloadLength::Payload(pl) = length {
@@ -350,6 +381,120 @@ impl HeaderIndex {
     }
 }
 
+/// Header names seen on a connection.
+///
+/// Clients tend to send the same set of headers, in the same order, with every request. Reusing
+/// names seen earlier on the connection avoids allocating custom (non-standard) names again for
+/// each message.
+#[derive(Default)]
+pub(crate) struct HeaderNameCache {
+    names: Vec<HeaderName>,
+}
+
+impl HeaderNameCache {
+    /// Get header name for raw bytes of the header at position `pos` of the message.
+    ///
+    /// Raw name must be a valid header name token, as checked by httparse.
+    fn get(&mut self, pos: usize, raw: &[u8]) -> HeaderName {
+        let matches =
+            |name: &HeaderName| name.as_str().as_bytes().eq_ignore_ascii_case(raw);
+
+        // same header at the same position as in a previous message is the common case
+        if let Some(name) = self.names.get(pos).filter(|name| matches(name)) {
+            return name.clone();
+        }
+
+        if let Some(name) = self.names.iter().find(|name| matches(name)) {
+            return name.clone();
+        }
+
+        let name = HeaderName::from_bytes(raw).unwrap();
+        if self.names.len() < MAX_CACHED_NAMES {
+            self.names.push(name.clone());
+        }
+        name
+    }
+}
+
+/// Result of [`unfold_headers`].
+#[derive(Debug, PartialEq)]
+pub(crate) enum Unfold {
+    /// Folded header lines were joined.
+    Unfolded,
+    /// Header lines are folded but the message head is not complete yet.
+    Partial,
+    /// No header line is folded.
+    None,
+}
+
+/// Joins header lines folded with obsolete line folding (obs-fold) to the line they continue,
+/// replacing the line break and leading whitespace with a single space.
+///
+/// Only complete message heads are changed. Folding a line onto the start line is never allowed
+/// and is an error.
+pub(crate) fn unfold_headers(src: &mut BytesMut) -> Result<Unfold, ParseError> {
+    let is_folded = |line: &[u8]| matches!(line.first(), Some(b' ') | Some(b'\t'));
+
+    let mut lines = Vec::new();
+    let mut start = 0;
+    let mut folded = false;
+
+    loop {
+        let end = match src[start..].iter().position(|&b| b == b'\n') {
+            Some(pos) => start + pos + 1,
+            None if folded => return Ok(Unfold::Partial),
+            None => return Ok(Unfold::None),
+        };
+        let line = &src[start..end];
+
+        // empty line ends the head
+        if line == b"\r\n" || line == b"\n" {
+            break;
+        }
+
+        if is_folded(line) {
+            if lines.len() < 2 {
+                debug!("start line is folded");
+                return Err(ParseError::Header);
+            }
+            folded = true;
+        }
+
+        lines.push((start, end));
+        start = end;
+    }
+
+    if !folded {
+        return Ok(Unfold::None);
+    }
+
+    // only the header lines are copied; the unfolded head is written back in front of the
+    // empty line ending it, so the rest of the buffer stays in place
+    let mut unfolded = Vec::with_capacity(start);
+    for &(line_start, line_end) in &lines {
+        let line = &src[line_start..line_end];
+
+        if is_folded(line) {
+            // replace the line break and whitespace around it with a single space
+            while let Some(b'\r') | Some(b'\n') | Some(b' ') | Some(b'\t') =
+                unfolded.last()
+            {
+                unfolded.truncate(unfolded.len() - 1);
+            }
+            unfolded.push(b' ');
+            let content = line.iter().position(|b| !matches!(b, b' ' | b'\t'));
+            unfolded.extend_from_slice(&line[content.unwrap_or(line.len())..]);
+        } else {
+            unfolded.extend_from_slice(line);
+        }
+    }
+
+    let offset = start - unfolded.len();
+    src[offset..start].copy_from_slice(&unfolded);
+    src.advance(offset);
+    Ok(Unfold::Unfolded)
+}
+
 #[derive(Debug, Clone, PartialEq)]
 /// Http payload item
 pub enum PayloadItem {
@@ -375,7 +520,7 @@ impl PayloadDecoder {
 
     pub fn chunked() -> PayloadDecoder {
         PayloadDecoder {
-            kind: Kind::Chunked(ChunkedState::Size, 0),
+            kind: Kind::Chunked(ChunkedState::Size, 0, 0),
         }
     }
 
@@ -390,7 +535,10 @@ enum Kind {
     /// integer.
     Length(u64),
     /// A Reader used when Transfer-Encoding is `chunked`.
-    Chunked(ChunkedState, u64),
+    ///
+    /// Holds the chunk size or remaining chunk bytes, and the length of the current part of the
+    /// chunk size line.
+    Chunked(ChunkedState, u64, usize),
     /// A Reader used for responses that don't indicate a length or chunked.
     ///
     /// Note: This should only used for `Response`s. It is illegal for a
@@ -448,15 +596,22 @@ impl Decoder for PayloadDecoder {
                     Ok(Some(PayloadItem::Chunk(buf)))
                 }
             }
-            Kind::Chunked(ref mut state, ref mut size) => {
+            Kind::Chunked(ref mut state, ref mut size, ref mut line) => {
                 loop {
                     let mut buf = None;
                     // advances the chunked state
-                    *state = match state.step(src, size, &mut buf) {
+                    let next = match state.step(src, size, line, &mut buf) {
                         Poll::Pending => return Ok(None),
                         Poll::Ready(Ok(state)) => state,
                         Poll::Ready(Err(e)) => return Err(e),
                     };
+                    // whitespace and extensions after the size share one length limit
+                    let same_part = *state == ChunkedState::SizeLws
+                        && next == ChunkedState::Extension;
+                    if next != *state && !same_part {
+                        *line = 0;
+                    }
+                    *state = next;
                     if *state == ChunkedState::End {
                         trace!("End of chunked stream");
                         return Ok(Some(PayloadItem::Eof));
@@ -497,13 +652,14 @@ impl ChunkedState {
         &self,
         body: &mut BytesMut,
         size: &mut u64,
+        line: &mut usize,
         buf: &mut Option<Bytes>,
     ) -> Poll<Result<ChunkedState, io::Error>> {
         use self::ChunkedState::*;
         match *self {
-            Size => ChunkedState::read_size(body, size),
-            SizeLws => ChunkedState::read_size_lws(body),
-            Extension => ChunkedState::read_extension(body),
+            Size => ChunkedState::read_size(body, size, line),
+            SizeLws => ChunkedState::read_size_lws(body, line),
+            Extension => ChunkedState::read_extension(body, line),
             SizeLf => ChunkedState::read_size_lf(body, size),
             Body => ChunkedState::read_body(body, size, buf),
             BodyCr => ChunkedState::read_body_cr(body),
@@ -517,20 +673,18 @@ impl ChunkedState {
     fn read_size(
         rdr: &mut BytesMut,
         size: &mut u64,
+        digits: &mut usize,
     ) -> Poll<Result<ChunkedState, io::Error>> {
         let radix = 16;
-        match byte!(rdr) {
-            b @ b'0'..=b'9' => {
-                *size *= radix;
-                *size += u64::from(b - b'0');
-            }
-            b @ b'a'..=b'f' => {
-                *size *= radix;
-                *size += u64::from(b + 10 - b'a');
-            }
-            b @ b'A'..=b'F' => {
-                *size *= radix;
-                *size += u64::from(b + 10 - b'A');
+        let digit = match byte!(rdr) {
+            b @ b'0'..=b'9' => b - b'0',
+            b @ b'a'..=b'f' => b + 10 - b'a',
+            b @ b'A'..=b'F' => b + 10 - b'A',
+            _ if *digits == 0 => {
+                return Poll::Ready(Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "Invalid chunk size line: Missing size",
+                )));
             }
             b'\t' | b' ' => return Poll::Ready(Ok(ChunkedState::SizeLws)),
             b';' => return Poll::Ready(Ok(ChunkedState::Extension)),
@@ -541,15 +695,47 @@ impl ChunkedState {
                     "Invalid chunk size line: Invalid Size",
                 )));
             }
+        };
+
+        *digits += 1;
+        if *digits > MAX_CHUNK_SIZE_DIGITS {
+            return Poll::Ready(Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Invalid chunk size line: Size is too long",
+            )));
+        }
+
+        match size
+            .checked_mul(radix)
+            .and_then(|size| size.checked_add(u64::from(digit)))
+        {
+            Some(new_size) => *size = new_size,
+            None => {
+                return Poll::Ready(Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "Invalid chunk size line: Size overflows u64",
+                )));
+            }
         }
+
         Poll::Ready(Ok(ChunkedState::Size))
     }
 
-    fn read_size_lws(rdr: &mut BytesMut) -> Poll<Result<ChunkedState, io::Error>> {
+    fn read_size_lws(
+        rdr: &mut BytesMut,
+        len: &mut usize,
+    ) -> Poll<Result<ChunkedState, io::Error>> {
         trace!("read_size_lws");
         match byte!(rdr) {
             // LWS can follow the chunk size, but no more digits can come
-            b'\t' | b' ' => Poll::Ready(Ok(ChunkedState::SizeLws)),
+            b'\t' | b' ' if *len < MAX_CHUNK_EXTENSION_LEN => {
+                *len += 1;
+                Poll::Ready(Ok(ChunkedState::SizeLws))
+            }
+            b'\t' | b' ' => Poll::Ready(Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Invalid chunk size line: Extensions are too long",
+            ))),
             b';' => Poll::Ready(Ok(ChunkedState::Extension)),
             b'\r' => Poll::Ready(Ok(ChunkedState::SizeLf)),
             _ => Poll::Ready(Err(io::Error::new(
@@ -558,10 +744,21 @@ impl ChunkedState {
             ))),
         }
     }
-    fn read_extension(rdr: &mut BytesMut) -> Poll<Result<ChunkedState, io::Error>> {
+    fn read_extension(
+        rdr: &mut BytesMut,
+        len: &mut usize,
+    ) -> Poll<Result<ChunkedState, io::Error>> {
         match byte!(rdr) {
             b'\r' => Poll::Ready(Ok(ChunkedState::SizeLf)),
-            _ => Poll::Ready(Ok(ChunkedState::Extension)), // no supported extensions
+            // no supported extensions; they are skipped up to a limit
+            _ if *len < MAX_CHUNK_EXTENSION_LEN => {
+                *len += 1;
+                Poll::Ready(Ok(ChunkedState::Extension))
+            }
+            _ => Poll::Ready(Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Invalid chunk size line: Extensions are too long",
+            ))),
         }
     }
     fn read_size_lf(
@@ -646,6 +843,8 @@ impl ChunkedState {
 
 #[cfg(test)]
 mod tests {
+    use std::convert::TryFrom;
+
     use bytes::{Bytes, BytesMut};
     use http::{Method, Version};
 
@@ -815,6 +1014,47 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_unfold_headers() {
+        let mut buf = BytesMut::from(
+            "GET /test HTTP/1.1\r\n\
+             User-Agent: Device/1.0 \r\n \t (embedded;\r\n\tfirmware 2)\r\n\
+             Host: example.com\r\n\r\nbody",
+        );
+        expect_parse_err!(&mut buf.clone());
+
+        // the rest of the buffer is not moved
+        let body = buf[buf.len() - 4..].as_ptr();
+        assert_eq!(unfold_headers(&mut buf).unwrap(), Unfold::Unfolded);
+        assert_eq!(buf[buf.len() - 4..].as_ptr(), body);
+        assert_eq!(
+            &buf[..],
+            &b"GET /test HTTP/1.1\r\n\
+               User-Agent: Device/1.0 (embedded; firmware 2)\r\n\
+               Host: example.com\r\n\r\nbody"[..]
+        );
+
+        let req = parse_ready!(&mut buf);
+        assert_eq!(
+            req.headers().get(header::USER_AGENT).unwrap(),
+            "Device/1.0 (embedded; firmware 2)"
+        );
+        assert_eq!(&buf[..], b"body");
+
+        // head is not complete yet
+        let mut buf =
+            BytesMut::from("GET /test HTTP/1.1\r\nUser-Agent: Device\r\n 1.0\r\n");
+        assert_eq!(unfold_headers(&mut buf).unwrap(), Unfold::Partial);
+
+        let mut buf = BytesMut::from("GET /test HTTP/1.1\r\nUser-Agent: Device\r\n\r\n");
+        assert_eq!(unfold_headers(&mut buf).unwrap(), Unfold::None);
+
+        // request line can not be folded
+        let mut buf =
+            BytesMut::from("GET /test\r\n HTTP/1.1\r\nUser-Agent: Device\r\n\r\n");
+        assert!(unfold_headers(&mut buf).is_err());
+    }
+
     #[test]
     fn test_headers_multi_value() {
         let mut buf = BytesMut::from(
@@ -1098,6 +1338,43 @@ mod tests {
         assert_eq!(req.path(), "//path");
     }
 
+    #[test]
+    fn test_http_request_pipelined_headers() {
+        let mut buf = BytesMut::from(
+            "GET /first?a=1 HTTP/1.1\r\n\
+             x-request-id: 1\r\n\
+             x-forwarded-for: 10.0.0.1\r\n\r\n\
+             GET /second HTTP/1.1\r\n\
+             X-Forwarded-For: 10.0.0.2\r\n\
+             X-Request-Id: 2\r\n\r\n",
+        );
+        let mut reader = MessageDecoder::<Request>::default();
+        let (first, _) = reader.decode(&mut buf).unwrap().unwrap();
+        let (second, _) = reader.decode(&mut buf).unwrap().unwrap();
+
+        // grow the read buffer while earlier requests are still alive
+        buf.reserve(64 * 1024);
+        buf.extend_from_slice(b"GET /third HTTP/1.1\r\nx-request-id: 3\r\n\r\n");
+        let (third, _) = reader.decode(&mut buf).unwrap().unwrap();
+        assert!(buf.is_empty());
+
+        assert_eq!(first.uri(), "/first?a=1");
+        assert_eq!(first.uri().query(), Some("a=1"));
+        assert_eq!(first.headers().get("x-request-id").unwrap(), "1");
+        assert_eq!(first.headers().get("x-forwarded-for").unwrap(), "10.0.0.1");
+
+        assert_eq!(second.path(), "/second");
+        assert_eq!(second.headers().get("x-request-id").unwrap(), "2");
+        assert_eq!(second.headers().get("x-forwarded-for").unwrap(), "10.0.0.2");
+
+        assert_eq!(third.path(), "/third");
+        assert_eq!(third.headers().get("x-request-id").unwrap(), "3");
+        assert_eq!(third.headers().len(), 1);
+
+        // names differing only in case or position are reused
+        assert_eq!(reader.names.names.len(), 2);
+    }
+
     #[test]
     fn test_http_request_parser_bad_method() {
         let mut buf = BytesMut::from("!12%()+=~$ /get HTTP/1.1\r\n\r\n");
@@ -1231,6 +1508,94 @@ mod tests {
         assert!(msg.eof());
     }
 
+    /// Feeds `input` to a chunked payload decoder byte by byte, asserting that every byte is
+    /// consumed, and returns the first error.
+    fn chunked_error(input: &[u8]) -> io::Error {
+        let mut pl = PayloadDecoder::chunked();
+        let mut buf = BytesMut::new();
+
+        for &b in input {
+            buf.extend_from_slice(&[b]);
+            match pl.decode(&mut buf) {
+                Ok(None) => assert!(buf.is_empty()),
+                Ok(Some(item)) => panic!("unexpected item: {:?}", item),
+                Err(err) => return err,
+            }
+        }
+
+        panic!("no error for {:?}", String::from_utf8_lossy(input));
+    }
+
+    #[test]
+    fn test_chunked_size_limits() {
+        let err = chunked_error(&[b'0'; 64]);
+        assert!(err.to_string().contains("too long"));
+
+        let err = chunked_error(b"1ffffffffffffffff\r\n");
+        assert!(err.to_string().contains("too long"));
+
+        let err = chunked_error(b"-1\r\n");
+        assert!(err.to_string().contains("Missing size"));
+
+        let err = chunked_error(b"\r\n");
+        assert!(err.to_string().contains("Missing size"));
+
+        let err = chunked_error(b"4g\r\n");
+        assert!(err.to_string().contains("Invalid Size"));
+
+        // the largest size still fits
+        let mut pl = PayloadDecoder::chunked();
+        let mut buf = BytesMut::from(&b"ffffffffffffffff\r\ndata"[..]);
+        let chunk = pl.decode(&mut buf).unwrap().unwrap().chunk();
+        assert_eq!(chunk.as_ref(), b"data");
+    }
+
+    #[test]
+    fn test_chunked_extension_limit() {
+        let mut input = b"4;".to_vec();
+        input.extend_from_slice(&[b'x'; 64 * 1024]);
+        let err = chunked_error(&input);
+        assert!(err.to_string().contains("Extensions are too long"));
+
+        // extensions up to the limit are discarded, also on every chunk
+        let ext = "x".repeat(MAX_CHUNK_EXTENSION_LEN - 1);
+        let mut pl = PayloadDecoder::chunked();
+        let mut buf = BytesMut::from(
+            format!("4;{}\r\ndata\r\n4;{}\r\nline\r\n0\r\n\r\n", ext, ext).as_bytes(),
+        );
+        assert_eq!(
+            pl.decode(&mut buf).unwrap().unwrap().chunk().as_ref(),
+            b"data"
+        );
+        assert_eq!(
+            pl.decode(&mut buf).unwrap().unwrap().chunk().as_ref(),
+            b"line"
+        );
+        assert!(pl.decode(&mut buf).unwrap().unwrap().eof());
+    }
+
+    #[test]
+    fn test_chunked_lws_limit() {
+        let mut input = b"4".to_vec();
+        input.extend_from_slice(&[b' '; 10 * 1024]);
+        input.extend_from_slice(b"\r\ndata\r\n");
+        let err = chunked_error(&input);
+        assert!(err.to_string().contains("too long"));
+
+        // whitespace and extensions share the limit
+        let lws = " ".repeat(MAX_CHUNK_EXTENSION_LEN / 2);
+        let ext = "x".repeat(MAX_CHUNK_EXTENSION_LEN / 2 + 16);
+        let err = chunked_error(format!("4{};{}\r\n", lws, ext).as_bytes());
+        assert!(err.to_string().contains("too long"));
+
+        let mut pl = PayloadDecoder::chunked();
+        let mut buf = BytesMut::from(format!("4{}\r\ndata\r\n", lws).as_bytes());
+        assert_eq!(
+            pl.decode(&mut buf).unwrap().unwrap().chunk().as_ref(),
+            b"data"
+        );
+    }
+
     #[test]
     fn test_response_http10_read_until_eof() {
         let mut buf = BytesMut::from(&"HTTP/1.0 200 Ok\r\n\r\ntest data"[..]);
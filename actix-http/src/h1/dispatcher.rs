@@ -2,37 +2,46 @@ use std::{
     collections::VecDeque,
     fmt,
     future::Future,
-    io, mem, net,
+    io::{self, IoSlice},
+    mem, net,
     pin::Pin,
     rc::Rc,
     task::{Context, Poll},
+    time::Duration,
 };
 
 use actix_codec::{AsyncRead, AsyncWrite, Decoder, Encoder, Framed, FramedParts};
 use actix_rt::time::{sleep_until, Instant, Sleep};
 use actix_service::Service;
 use bitflags::bitflags;
-use bytes::{Buf, BytesMut};
+use bytes::{Buf, Bytes, BytesMut};
 use futures_core::ready;
-use log::{error, trace};
+use http::Version;
+use log::{debug, error, trace};
 use pin_project::pin_project;
 
-use crate::body::{Body, BodySize, MessageBody, ResponseBody};
+use crate::body::{Body, BodySize, BodyTrailers, MessageBody, ResponseBody};
 use crate::config::ServiceConfig;
 use crate::error::{DispatchError, Error};
-use crate::error::{ParseError, PayloadError};
+use crate::error::{ParseError, PayloadError, RequestContext};
+use crate::header::{HeaderMap, HeaderName, HeaderValue};
+use crate::message::ConnectionType;
 use crate::request::Request;
 use crate::response::Response;
 use crate::service::HttpFlow;
+use crate::span::{self, ConnectionSpan};
 use crate::OnConnectData;
 
+use super::backpressure::WriteBackpressure;
 use super::codec::Codec;
+use super::informational::Informational;
 use super::payload::{Payload, PayloadSender, PayloadStatus};
 use super::{Message, MessageType};
 
 const LW_BUFFER_SIZE: usize = 1024;
 const HW_BUFFER_SIZE: usize = 1024 * 8;
 const MAX_PIPELINED_MESSAGES: usize = 16;
+const MAX_IO_SLICES: usize = 16;
 
 bitflags! {
     pub struct Flags: u8 {
@@ -58,6 +67,7 @@ where
 {
     #[pin]
     inner: DispatcherState<T, S, B, X, U>,
+    span: ConnectionSpan,
 
     #[cfg(test)]
     poll_count: u64,
@@ -98,15 +108,26 @@ where
     #[pin]
     state: State<S, B, X>,
     payload: Option<PayloadSender>,
+    /// Description of the request whose payload is being read, kept after a payload error so
+    /// the error returned by the dispatcher can refer to it.
+    request_ctx: Option<RequestContext>,
     messages: VecDeque<DispatcherMessage>,
 
     ka_expire: Instant,
     #[pin]
     ka_timer: Option<Sleep>,
+    /// Keep-alive timeout requested by the client in the `Keep-Alive` header of its last request.
+    peer_keep_alive: Option<Duration>,
+    age_expire: Option<Instant>,
+    backpressure: Option<WriteBackpressure>,
+    informational: Option<Informational>,
 
     io: Option<T>,
     read_buf: BytesMut,
     write_buf: BytesMut,
+    /// Large body chunks, and the output preceding them, waiting to be written before
+    /// `write_buf` without being copied into it.
+    write_queue: VecDeque<Bytes>,
     codec: Codec,
 }
 
@@ -146,6 +167,54 @@ enum PollResponse {
     DrainWriteBuf,
 }
 
+const KEEP_ALIVE: &str = "keep-alive";
+
+/// Keep-alive timeout of the connection: the configured timeout, or the timeout requested by the
+/// client when it is shorter.
+fn keep_alive_timeout(
+    config: &ServiceConfig,
+    peer: Option<Duration>,
+) -> Option<Duration> {
+    match (config.keep_alive(), peer) {
+        (Some(ka), Some(peer)) => Some(ka.min(peer)),
+        (ka, _) => ka,
+    }
+}
+
+/// Parses the `timeout` parameter of a `Keep-Alive` request header, e.g. `timeout=5, max=100`.
+fn peer_keep_alive(headers: &HeaderMap) -> Option<Duration> {
+    headers
+        .get_all(KEEP_ALIVE)
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .find_map(|param| {
+            let mut parts = param.splitn(2, '=');
+            let name = parts.next()?.trim();
+            let value = parts.next()?.trim();
+
+            if name.eq_ignore_ascii_case("timeout") {
+                value.parse().ok().map(Duration::from_secs)
+            } else {
+                None
+            }
+        })
+}
+
+/// Fails the payload being read, if any, logging the error along with the request it belongs to.
+fn set_payload_error(
+    payload: &mut Option<PayloadSender>,
+    ctx: &Option<RequestContext>,
+    err: PayloadError,
+) {
+    if let Some(mut payload) = payload.take() {
+        match ctx {
+            Some(ctx) => debug!("Request payload error: {}; request: {}", err, ctx),
+            None => debug!("Request payload error: {}", err),
+        }
+        payload.set_error(err);
+    }
+}
+
 impl<T, S, B, X, U> Dispatcher<T, S, B, X, U>
 where
     T: AsyncRead + AsyncWrite + Unpin,
@@ -165,6 +234,26 @@ where
         flow: Rc<HttpFlow<S, X, U>>,
         on_connect_data: OnConnectData,
         peer_addr: Option<net::SocketAddr>,
+    ) -> Self {
+        Self::with_read_buf(
+            io,
+            BytesMut::with_capacity(HW_BUFFER_SIZE),
+            config,
+            flow,
+            on_connect_data,
+            peer_addr,
+        )
+    }
+
+    /// Create HTTP/1 dispatcher for a connection whose first bytes were already read into
+    /// `read_buf`.
+    pub(crate) fn with_read_buf(
+        io: T,
+        read_buf: BytesMut,
+        config: ServiceConfig,
+        flow: Rc<HttpFlow<S, X, U>>,
+        on_connect_data: OnConnectData,
+        peer_addr: Option<net::SocketAddr>,
     ) -> Self {
         let flags = if config.keep_alive_enabled() {
             Flags::KEEPALIVE
@@ -178,11 +267,16 @@ where
             None => (config.now(), None),
         };
 
+        // connection age limit
+        let age_expire = config.max_connection_age().map(|age| config.now() + age);
+
         Dispatcher {
             inner: DispatcherState::Normal(InnerDispatcher {
-                read_buf: BytesMut::with_capacity(HW_BUFFER_SIZE),
+                read_buf,
                 write_buf: BytesMut::with_capacity(HW_BUFFER_SIZE),
+                write_queue: VecDeque::new(),
                 payload: None,
+                request_ctx: None,
                 state: State::None,
                 error: None,
                 messages: VecDeque::new(),
@@ -194,7 +288,12 @@ where
                 peer_addr,
                 ka_expire,
                 ka_timer,
+                peer_keep_alive: None,
+                age_expire,
+                backpressure: None,
+                informational: None,
             }),
+            span: ConnectionSpan::new("h1", peer_addr),
 
             #[cfg(test)]
             poll_count: 0,
@@ -229,8 +328,48 @@ where
         let this = self.project();
         this.flags
             .insert(Flags::READ_DISCONNECT | Flags::WRITE_DISCONNECT);
-        if let Some(mut payload) = this.payload.take() {
-            payload.set_error(PayloadError::Incomplete(None));
+        set_payload_error(
+            this.payload,
+            this.request_ctx,
+            PayloadError::Incomplete(None),
+        );
+    }
+
+    /// Handle the end of the read half of the io stream.
+    ///
+    /// Requests read completely are still responded to before the connection is closed, unless
+    /// responding after a half-close is disabled. A request cut off by the end of the stream is
+    /// dropped.
+    fn read_eof(self: Pin<&mut Self>) {
+        if !self.codec.config().respond_after_half_close() {
+            trace!("Read half closed, close connection");
+            self.client_disconnected();
+            return;
+        }
+
+        let this = self.project();
+        this.flags.insert(Flags::READ_DISCONNECT);
+
+        if this.payload.is_some() {
+            // only streams without a length are delimited by the end of the io stream
+            if this.codec.message_type() == MessageType::Stream {
+                this.payload.take().unwrap().feed_eof();
+                *this.request_ctx = None;
+            } else {
+                set_payload_error(
+                    this.payload,
+                    this.request_ctx,
+                    PayloadError::Incomplete(None),
+                );
+            }
+        } else if !this.read_buf.is_empty()
+            && this.messages.len() < MAX_PIPELINED_MESSAGES
+            && this.error.is_none()
+        {
+            // the rest of the read buffer is the head of a request that never completes
+            trace!("Read half closed in the middle of a request head");
+            this.read_buf.clear();
+            *this.error = Some(DispatchError::Io(io::ErrorKind::UnexpectedEof.into()));
         }
     }
 
@@ -238,9 +377,40 @@ where
         self: Pin<&mut Self>,
         cx: &mut Context<'_>,
     ) -> Poll<Result<(), io::Error>> {
-        let InnerDispatcherProj { io, write_buf, .. } = self.project();
+        let InnerDispatcherProj {
+            io,
+            write_buf,
+            write_queue,
+            ..
+        } = self.project();
         let mut io = Pin::new(io.as_mut().unwrap());
 
+        // queued chunks go first, using vectored writes when supported so they are not copied
+        while !write_queue.is_empty() {
+            let n = if io.is_write_vectored() {
+                let mut slices = [IoSlice::new(&[]); MAX_IO_SLICES];
+                let mut count = 0;
+                for bytes in write_queue.iter().take(MAX_IO_SLICES - 1) {
+                    slices[count] = IoSlice::new(bytes);
+                    count += 1;
+                }
+                // the write buffer follows only once the whole queue is included
+                if write_queue.len() < MAX_IO_SLICES {
+                    slices[count] = IoSlice::new(write_buf);
+                    count += 1;
+                }
+                ready!(io.as_mut().poll_write_vectored(cx, &slices[..count]))?
+            } else {
+                ready!(io.as_mut().poll_write(cx, &write_queue[0]))?
+            };
+
+            if n == 0 {
+                return Poll::Ready(Err(io::Error::new(io::ErrorKind::WriteZero, "")));
+            }
+
+            advance_queue(write_queue, write_buf, n);
+        }
+
         let len = write_buf.len();
         let mut written = 0;
 
@@ -269,17 +439,57 @@ where
 
     fn send_response(
         self: Pin<&mut Self>,
-        message: Response<()>,
+        mut message: Response<()>,
         body: ResponseBody<B>,
     ) -> Result<(), DispatchError> {
         let size = body.size();
         let mut this = self.project();
+
+        // interim responses go before the final one, later ones are refused
+        if let Some(informational) = this.informational.take() {
+            for (status, headers) in informational.close() {
+                this.codec
+                    .encode_informational(status, &headers, this.write_buf);
+            }
+        }
+
+        // stop keep-alive once the connection is too old or the server is shutting down
+        let config = this.codec.config();
+        let expired = this
+            .age_expire
+            .map_or(false, |expire| expire <= config.now());
+        if expired || config.shutdown().is_shutdown() {
+            message
+                .head_mut()
+                .set_connection_type(ConnectionType::Close);
+        }
+
+        // tell the client how long the connection stays open once the response is sent
+        if config.advertise_keep_alive()
+            && this.codec.keepalive()
+            && matches!(
+                message.head().ctype(),
+                None | Some(ConnectionType::KeepAlive)
+            )
+            && !message.headers().contains_key(KEEP_ALIVE)
+        {
+            if let Some(ka) = keep_alive_timeout(config, *this.peer_keep_alive) {
+                let value = HeaderValue::from_str(&format!("timeout={}", ka.as_secs()))
+                    .expect("keep-alive timeout is a valid header value");
+                message
+                    .headers_mut()
+                    .insert(HeaderName::from_static(KEEP_ALIVE), value);
+            }
+        }
+
         this.codec
             .encode(Message::Item((message, size)), &mut this.write_buf)
             .map_err(|err| {
-                if let Some(mut payload) = this.payload.take() {
-                    payload.set_error(PayloadError::Incomplete(None));
-                }
+                set_payload_error(
+                    this.payload,
+                    this.request_ctx,
+                    PayloadError::Incomplete(None),
+                );
                 DispatchError::Io(err)
             })?;
 
@@ -291,6 +501,18 @@ where
         Ok(())
     }
 
+    /// Writes informational responses sent by the pending service call.
+    fn send_informational(self: Pin<&mut Self>, cx: &mut Context<'_>) {
+        let this = self.project();
+
+        if let Some(ref informational) = this.informational {
+            for (status, headers) in informational.poll_take(cx.waker()) {
+                this.codec
+                    .encode_informational(status, &headers, this.write_buf);
+            }
+        }
+    }
+
     fn send_continue(self: Pin<&mut Self>) {
         self.project()
             .write_buf
@@ -308,6 +530,11 @@ where
                 StateProj::None => match this.messages.pop_front() {
                     // handle request message.
                     Some(DispatcherMessage::Item(req)) => {
+                        *this.backpressure =
+                            req.head().extensions().get::<WriteBackpressure>().cloned();
+                        *this.informational =
+                            req.head().extensions().get::<Informational>().cloned();
+
                         // Handle `EXPECT: 100-Continue` header
                         if req.head().expect() {
                             // set InnerDispatcher state and continue loop to poll it.
@@ -354,6 +581,8 @@ where
                     // service call pending and could be waiting for more chunk messages.
                     // (pipeline message limit and/or payload can_read limit)
                     Poll::Pending => {
+                        self.as_mut().send_informational(cx);
+
                         // no new message is decoded and no new payload is feed.
                         // nothing to do except waiting for new incoming data from client.
                         if !self.as_mut().poll_request(cx)? {
@@ -366,13 +595,30 @@ where
                 StateProj::SendPayload(mut stream) => {
                     // keep populate writer buffer until buffer size limit hit,
                     // get blocked or finished.
-                    while this.write_buf.len() < super::payload::MAX_BUFFER_SIZE {
+                    loop {
+                        let buffered =
+                            this.write_buf.len() + queued_len(this.write_queue);
+                        let blocked = buffered >= super::payload::MAX_BUFFER_SIZE;
+
+                        // upgraded streams can ask to be polled while the buffer is full
+                        // so they can react to a client that is not reading.
+                        match this.backpressure {
+                            Some(bp) if bp.is_subscribed() => bp.set_buffered(buffered),
+                            _ if blocked => break,
+                            _ => {}
+                        }
+
                         match stream.as_mut().poll_next(cx) {
                             Poll::Ready(Some(Ok(item))) => {
-                                this.codec.encode(
-                                    Message::Chunk(Some(item)),
-                                    &mut this.write_buf,
+                                this.codec.encode_chunk_queued(
+                                    item,
+                                    this.write_buf,
+                                    this.write_queue,
                                 )?;
+
+                                if blocked {
+                                    break;
+                                }
                             }
 
                             Poll::Ready(None) => {
@@ -381,13 +627,31 @@ where
                                 // payload stream finished.
                                 // set state to None and handle next message
                                 this.state.set(State::None);
+                                *this.backpressure = None;
                                 continue 'res;
                             }
 
                             Poll::Ready(Some(Err(err))) => {
-                                return Err(DispatchError::Service(err))
+                                // body asked to be finished with trailers instead of
+                                // aborting the connection; without chunked encoding the
+                                // connection is aborted with the original stream error
+                                if let Some(BodyTrailers { trailers, .. }) =
+                                    err.as_error()
+                                {
+                                    if this
+                                        .codec
+                                        .encode_trailers(trailers, &mut this.write_buf)?
+                                    {
+                                        this.state.set(State::None);
+                                        *this.backpressure = None;
+                                        continue 'res;
+                                    }
+                                }
+
+                                return Err(DispatchError::Service(err));
                             }
 
+                            Poll::Pending if blocked => break,
                             Poll::Pending => return Ok(PollResponse::DoNothing),
                         }
                     }
@@ -423,6 +687,10 @@ where
         req: Request,
         cx: &mut Context<'_>,
     ) -> Result<(), DispatchError> {
+        let this = self.as_mut().project();
+        *this.backpressure = req.head().extensions().get::<WriteBackpressure>().cloned();
+        *this.informational = req.head().extensions().get::<Informational>().cloned();
+
         // Handle `EXPECT: 100-Continue` header
         if req.head().expect() {
             // set dispatcher state so the future is pinned.
@@ -474,7 +742,10 @@ where
                             self.send_response(res, body)
                         }
                         // see the comment on ExpectCall state branch's Pending.
-                        Poll::Pending => Ok(()),
+                        Poll::Pending => {
+                            self.send_informational(cx);
+                            Ok(())
+                        }
                         // see the comment on ExpectCall state branch's Ready(Err(err)).
                         Poll::Ready(Err(err)) => {
                             let res: Response = err.into().into();
@@ -510,11 +781,55 @@ where
 
                     match msg {
                         Message::Item(mut req) => {
+                            // https://tools.ietf.org/html/rfc7230#section-3.2.4
+                            if this.codec.obsolete_folding()
+                                && !this.codec.config().allow_obsolete_header_folding()
+                            {
+                                trace!("Rejecting request with folded header fields");
+                                let mut res =
+                                    Response::BadRequest().finish().drop_body();
+
+                                // the body of the request is not read, stop reading instead
+                                let has_body =
+                                    this.codec.message_type() != MessageType::None;
+                                if has_body {
+                                    res.head_mut()
+                                        .set_connection_type(ConnectionType::Close);
+                                    this.flags.insert(Flags::READ_DISCONNECT);
+                                }
+
+                                // respond early like to requests when no future is in the
+                                // InnerDispatcher state, keeping responses in order
+                                if this.state.is_empty() {
+                                    self.as_mut().send_response(
+                                        res,
+                                        ResponseBody::Other(Body::Empty),
+                                    )?;
+                                    this = self.as_mut().project();
+                                } else {
+                                    this.messages
+                                        .push_back(DispatcherMessage::Error(res));
+                                }
+
+                                if has_body {
+                                    break;
+                                }
+                                continue;
+                            }
+
                             req.head_mut().peer_addr = *this.peer_addr;
 
-                            // merge on_connect_ext data into request extensions
+                            // HTTP/1.0 clients do not expect interim responses
+                            if req.head().version >= Version::HTTP_11 {
+                                req.head().extensions_mut().insert(Informational::new());
+                            }
+
+                            // attach on_connect_ext data to request
                             this.on_connect_data.merge_into(&mut req);
 
+                            *this.request_ctx = None;
+                            *this.peer_keep_alive = peer_keep_alive(&req.head().headers);
+
                             match this.codec.message_type() {
                                 // Request is upgradable. add upgrade message and break.
                                 // everything remain in read buffer would be handed to
@@ -527,6 +842,13 @@ where
 
                                 // Request is not upgradable.
                                 MessageType::Payload | MessageType::Stream => {
+                                    // let upgraded response streams observe the write buffer
+                                    if this.codec.message_type() == MessageType::Stream {
+                                        req.head()
+                                            .extensions_mut()
+                                            .insert(WriteBackpressure::new());
+                                    }
+
                                     /*
                                     PayloadSender and Payload are smart pointers share the
                                     same state.
@@ -540,6 +862,8 @@ where
                                         req.replace_payload(crate::Payload::H1(pl));
                                     req = req1;
                                     *this.payload = Some(ps);
+                                    *this.request_ctx =
+                                        Some(RequestContext::new(req.head()));
                                 }
 
                                 // Request has no payload.
@@ -572,6 +896,7 @@ where
                         Message::Chunk(None) => {
                             if let Some(mut payload) = this.payload.take() {
                                 payload.feed_eof();
+                                *this.request_ctx = None;
                             } else {
                                 error!("Internal server error: unexpected eof");
                                 this.flags.insert(Flags::READ_DISCONNECT);
@@ -594,9 +919,11 @@ where
                     break;
                 }
                 Err(ParseError::TooLarge) => {
-                    if let Some(mut payload) = this.payload.take() {
-                        payload.set_error(PayloadError::Overflow);
-                    }
+                    set_payload_error(
+                        this.payload,
+                        this.request_ctx,
+                        PayloadError::Overflow,
+                    );
                     // Requests overflow buffer size should be responded with 431
                     this.messages.push_back(DispatcherMessage::Error(
                         Response::RequestHeaderFieldsTooLarge().finish().drop_body(),
@@ -606,14 +933,19 @@ where
                     break;
                 }
                 Err(err) => {
-                    if let Some(mut payload) = this.payload.take() {
-                        payload.set_error(PayloadError::EncodingCorrupted);
+                    if this.payload.is_some() {
+                        // the request is already dispatched; its service sees the error
+                        set_payload_error(
+                            this.payload,
+                            this.request_ctx,
+                            PayloadError::EncodingCorrupted,
+                        );
+                    } else {
+                        // Malformed requests should be responded with 400
+                        this.messages.push_back(DispatcherMessage::Error(
+                            Response::BadRequest().finish().drop_body(),
+                        ));
                     }
-
-                    // Malformed requests should be responded with 400
-                    this.messages.push_back(DispatcherMessage::Error(
-                        Response::BadRequest().finish().drop_body(),
-                    ));
                     this.flags.insert(Flags::READ_DISCONNECT);
                     *this.error = Some(err.into());
                     break;
@@ -622,8 +954,18 @@ where
         }
 
         if updated && this.ka_timer.is_some() {
-            if let Some(expire) = this.codec.config().keep_alive_expire() {
-                *this.ka_expire = expire;
+            let config = this.codec.config();
+            if let Some(ka) = keep_alive_timeout(config, *this.peer_keep_alive) {
+                *this.ka_expire = config.now() + ka;
+
+                // wake up earlier when the client asked for a shorter timeout
+                if let Some(timer) = this.ka_timer.as_mut().as_pin_mut() {
+                    if !this.flags.contains(Flags::SHUTDOWN)
+                        && timer.deadline() > *this.ka_expire
+                    {
+                        timer.reset(*this.ka_expire);
+                    }
+                }
             }
         }
         Ok(updated)
@@ -650,9 +992,11 @@ where
                         return self.poll_keepalive(cx);
                     } else {
                         this.flags.insert(Flags::READ_DISCONNECT);
-                        if let Some(mut payload) = this.payload.take() {
-                            payload.set_error(PayloadError::Incomplete(None));
-                        }
+                        set_payload_error(
+                            this.payload,
+                            this.request_ctx,
+                            PayloadError::Incomplete(None),
+                        );
                     }
                 }
             }
@@ -665,7 +1009,10 @@ where
                     // exceed deadline. check for any outstanding tasks
                     } else if timer.deadline() >= *this.ka_expire {
                         // have no task at hand.
-                        if this.state.is_empty() && this.write_buf.is_empty() {
+                        if this.state.is_empty()
+                            && this.write_buf.is_empty()
+                            && this.write_queue.is_empty()
+                        {
                             if this.flags.contains(Flags::STARTED) {
                                 trace!("Keep-alive timeout, close connection");
                                 this.flags.insert(Flags::SHUTDOWN);
@@ -696,10 +1043,11 @@ where
                                 this.state.set(State::None);
                             }
                         // still have unfinished task. try to reset and register keep-alive.
-                        } else if let Some(deadline) =
-                            this.codec.config().keep_alive_expire()
-                        {
-                            timer.as_mut().reset(deadline);
+                        } else if let Some(ka) = keep_alive_timeout(
+                            this.codec.config(),
+                            *this.peer_keep_alive,
+                        ) {
+                            timer.as_mut().reset(this.codec.config().now() + ka);
                             let _ = timer.poll(cx);
                         }
                     // timer resolved but still have not met the keep-alive expire deadline.
@@ -816,12 +1164,46 @@ where
             mem::take(this.codec),
             mem::take(this.read_buf),
         );
-        parts.write_buf = mem::take(this.write_buf);
+        parts.write_buf = if this.write_queue.is_empty() {
+            mem::take(this.write_buf)
+        } else {
+            let mut write_buf = BytesMut::new();
+            for bytes in this.write_queue.drain(..) {
+                write_buf.extend_from_slice(&bytes);
+            }
+            write_buf.extend_from_slice(this.write_buf);
+            write_buf
+        };
         let framed = Framed::from_parts(parts);
         this.flow.upgrade.as_ref().unwrap().call((req, framed))
     }
 }
 
+/// Returns the number of bytes waiting in the write queue.
+fn queued_len(queue: &VecDeque<Bytes>) -> usize {
+    queue.iter().map(Bytes::len).sum()
+}
+
+/// Removes `n` written bytes from the front of the write queue, followed by the write buffer.
+fn advance_queue(queue: &mut VecDeque<Bytes>, buf: &mut BytesMut, mut n: usize) {
+    while n > 0 {
+        match queue.front_mut() {
+            Some(bytes) if n >= bytes.len() => {
+                n -= bytes.len();
+                queue.pop_front();
+            }
+            Some(bytes) => {
+                bytes.advance(n);
+                return;
+            }
+            None => {
+                buf.advance(n);
+                return;
+            }
+        }
+    }
+}
+
 impl<T, S, B, X, U> Future for Dispatcher<T, S, B, X, U>
 where
     T: AsyncRead + AsyncWrite + Unpin,
@@ -838,6 +1220,44 @@ where
 
     #[inline]
     fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let _span = self.span.enter();
+
+        match ready!(self.as_mut().poll_dispatch(cx)) {
+            Ok(()) => Poll::Ready(Ok(())),
+            Err(err) => {
+                // refer to the request whose payload was being read when the error occurred
+                let ctx = match self.project().inner.project() {
+                    DispatcherStateProj::Normal(inner) => {
+                        inner.project().request_ctx.take()
+                    }
+                    DispatcherStateProj::Upgrade(_) => None,
+                };
+
+                Poll::Ready(Err(match ctx {
+                    Some(ctx) => err.with_context(ctx),
+                    None => err,
+                }))
+            }
+        }
+    }
+}
+
+impl<T, S, B, X, U> Dispatcher<T, S, B, X, U>
+where
+    T: AsyncRead + AsyncWrite + Unpin,
+    S: Service<Request>,
+    S::Error: Into<Error>,
+    S::Response: Into<Response<B>>,
+    B: MessageBody,
+    X: Service<Request, Response = Request>,
+    X::Error: Into<Error>,
+    U: Service<(Request, Framed<T, Codec>), Response = ()>,
+    U::Error: fmt::Display,
+{
+    fn poll_dispatch(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<(), DispatchError>> {
         let this = self.as_mut().project();
 
         #[cfg(test)]
@@ -854,6 +1274,7 @@ where
                         Poll::Ready(Ok(()))
                     } else {
                         // flush buffer and wait on blocked.
+                        let _write = span::write();
                         ready!(inner.as_mut().poll_flush(cx))?;
                         Pin::new(inner.project().io.as_mut().unwrap())
                             .poll_shutdown(cx)
@@ -861,17 +1282,16 @@ where
                     }
                 } else {
                     // read from io stream and fill read buffer.
-                    let should_disconnect = inner.as_mut().read_available(cx)?;
+                    let should_disconnect = {
+                        let _read = span::read();
+                        inner.as_mut().read_available(cx)?
+                    };
 
                     inner.as_mut().poll_request(cx)?;
 
-                    // io stream should to be closed.
+                    // read half of io stream is closed.
                     if should_disconnect {
-                        let inner = inner.as_mut().project();
-                        inner.flags.insert(Flags::READ_DISCONNECT);
-                        if let Some(mut payload) = inner.payload.take() {
-                            payload.feed_eof();
-                        }
+                        inner.as_mut().read_eof();
                     };
 
                     loop {
@@ -887,7 +1307,7 @@ where
                                     .project()
                                     .inner
                                     .set(DispatcherState::Upgrade(upgrade));
-                                return self.poll(cx);
+                                return self.poll_dispatch(cx);
                             }
                         };
 
@@ -897,7 +1317,12 @@ where
                         //
                         // TODO: what? is WouldBlock good or bad?
                         // want to find a reference for this macOS behavior
-                        if inner.as_mut().poll_flush(cx)?.is_pending() || !drain {
+                        let flushed = {
+                            let _write = span::write();
+                            inner.as_mut().poll_flush(cx)?
+                        };
+
+                        if flushed.is_pending() || !drain {
                             break;
                         }
                     }
@@ -916,7 +1341,10 @@ where
                     }
 
                     // keep-alive and stream errors
-                    if is_empty && inner_p.write_buf.is_empty() {
+                    if is_empty
+                        && inner_p.write_buf.is_empty()
+                        && inner_p.write_queue.is_empty()
+                    {
                         if let Some(err) = inner_p.error.take() {
                             Poll::Ready(Err(err))
                         }
@@ -925,11 +1353,11 @@ where
                             && !inner_p.flags.intersects(Flags::KEEPALIVE)
                         {
                             inner_p.flags.insert(Flags::SHUTDOWN);
-                            self.poll(cx)
+                            self.poll_dispatch(cx)
                         }
                         // disconnect if shutdown
                         else if inner_p.flags.contains(Flags::SHUTDOWN) {
-                            self.poll(cx)
+                            self.poll_dispatch(cx)
                         } else {
                             Poll::Pending
                         }
@@ -953,7 +1381,11 @@ mod tests {
     use actix_service::fn_service;
     use futures_util::future::{lazy, ready};
 
+    use bytes::Bytes;
+
     use super::*;
+    use crate::body::StreamErrorPolicy;
+    use crate::config::ServiceConfigOptions;
     use crate::test::TestBuffer;
     use crate::{error::Error, KeepAlive};
     use crate::{
@@ -1024,7 +1456,11 @@ mod tests {
 
             match h1.as_mut().poll(cx) {
                 Poll::Pending => panic!(),
-                Poll::Ready(res) => assert!(res.is_err()),
+                Poll::Ready(res) => {
+                    let err = res.unwrap_err();
+                    assert!(err.is_parse());
+                    assert!(!err.is_disconnect());
+                }
             }
 
             if let DispatcherStateProj::Normal(inner) = h1.project().inner.project() {
@@ -1038,6 +1474,74 @@ mod tests {
         .await;
     }
 
+    #[actix_rt::test]
+    async fn test_connection_reset() {
+        lazy(|cx| {
+            let mut buf = TestBuffer::empty();
+            buf.err = Some(io::ErrorKind::ConnectionReset.into());
+
+            let services = HttpFlow::new(ok_service(), ExpectHandler, None);
+
+            let h1 = Dispatcher::<_, _, _, _, UpgradeHandler>::new(
+                buf,
+                ServiceConfig::default(),
+                services,
+                OnConnectData::default(),
+                None,
+            );
+
+            actix_rt::pin!(h1);
+
+            match h1.as_mut().poll(cx) {
+                Poll::Ready(Err(err)) => {
+                    assert!(err.is_disconnect());
+                    assert!(!err.is_parse());
+                    assert!(!err.is_timeout());
+                }
+                _ => panic!("connection reset should be reported"),
+            }
+        })
+        .await;
+    }
+
+    #[actix_rt::test]
+    async fn test_peer_keep_alive() {
+        let headers = |value: &'static str| {
+            let mut headers = HeaderMap::new();
+            headers.insert(
+                HeaderName::from_static(KEEP_ALIVE),
+                HeaderValue::from_static(value),
+            );
+            headers
+        };
+
+        assert_eq!(peer_keep_alive(&HeaderMap::new()), None);
+        assert_eq!(
+            peer_keep_alive(&headers("timeout=5")),
+            Some(Duration::from_secs(5))
+        );
+        assert_eq!(
+            peer_keep_alive(&headers("max=100, Timeout = 2")),
+            Some(Duration::from_secs(2))
+        );
+        assert_eq!(peer_keep_alive(&headers("timeout=soon")), None);
+        assert_eq!(peer_keep_alive(&headers("max=100")), None);
+
+        let cfg = ServiceConfig::new(KeepAlive::Timeout(5), 0, 0, false, None);
+        assert_eq!(keep_alive_timeout(&cfg, None), Some(Duration::from_secs(5)));
+        assert_eq!(
+            keep_alive_timeout(&cfg, Some(Duration::from_secs(1))),
+            Some(Duration::from_secs(1))
+        );
+        assert_eq!(
+            keep_alive_timeout(&cfg, Some(Duration::from_secs(60))),
+            Some(Duration::from_secs(5))
+        );
+
+        let cfg = ServiceConfig::new(KeepAlive::Os, 0, 0, false, None);
+        assert_eq!(keep_alive_timeout(&cfg, Some(Duration::from_secs(1))), None);
+    }
+
     #[actix_rt::test]
     async fn test_pipelining() {
         lazy(|cx| {
@@ -1316,4 +1820,688 @@ mod tests {
         })
         .await;
     }
+
+    fn stream_error_service(
+        policy: fn() -> StreamErrorPolicy,
+    ) -> impl Service<Request, Response = Response, Error = Error> {
+        fn_service(move |_req: Request| {
+            let stream = futures_util::stream::iter(vec![
+                Ok(Bytes::from_static(b"data")),
+                Err(crate::error::ErrorBadRequest("boom")),
+            ]);
+
+            ready(Ok::<_, Error>(
+                Response::Ok().streaming_with(stream, policy()),
+            ))
+        })
+    }
+
+    async fn stream_error_response(
+        policy: fn() -> StreamErrorPolicy,
+    ) -> (bool, Vec<u8>) {
+        lazy(|cx| {
+            let buf = TestBuffer::new("GET /test HTTP/1.1\r\n\r\n");
+            let cfg = ServiceConfig::new(KeepAlive::Disabled, 0, 0, false, None);
+            let services =
+                HttpFlow::new(stream_error_service(policy), ExpectHandler, None);
+
+            let h1 = Dispatcher::<_, _, _, _, UpgradeHandler>::new(
+                buf,
+                cfg,
+                services,
+                OnConnectData::default(),
+                None,
+            );
+
+            actix_rt::pin!(h1);
+
+            let is_ok = match h1.as_mut().poll(cx) {
+                Poll::Pending => panic!("first poll should not be pending"),
+                Poll::Ready(res) => res.is_ok(),
+            };
+
+            match h1.project().inner.project() {
+                DispatcherStateProj::Normal(inner) => {
+                    let mut res = inner.project().io.take().unwrap().write_buf.to_vec();
+                    stabilize_date_header(&mut res);
+                    (is_ok, res)
+                }
+                _ => panic!("dispatcher should not be upgraded"),
+            }
+        })
+        .await
+    }
+
+    #[actix_rt::test]
+    async fn test_stream_error_abort() {
+        let (is_ok, res) = stream_error_response(|| StreamErrorPolicy::Abort).await;
+
+        assert!(!is_ok);
+        // terminating chunk must not be written
+        assert!(find_slice(&res, b"0\r\n\r\n", 0).is_none());
+    }
+
+    #[actix_rt::test]
+    async fn test_stream_error_trailer() {
+        let (is_ok, res) = stream_error_response(|| StreamErrorPolicy::Trailer).await;
+
+        assert!(is_ok);
+        assert_eq!(
+            str::from_utf8(&res).unwrap(),
+            "\
+            HTTP/1.1 200 OK\r\n\
+            transfer-encoding: chunked\r\n\
+            connection: close\r\n\
+            trailer: x-stream-error\r\n\
+            date: Thu, 01 Jan 1970 12:34:56 UTC\r\n\r\n\
+            4\r\ndata\r\n\
+            0\r\n\
+            x-stream-error: error\r\n\
+            \r\n\
+            "
+        );
+    }
+
+    #[actix_rt::test]
+    async fn test_stream_error_chunk() {
+        let (is_ok, res) = stream_error_response(|| {
+            StreamErrorPolicy::ErrorChunk(Box::new(|err| {
+                Bytes::from(format!("!{}", err))
+            }))
+        })
+        .await;
+
+        assert!(is_ok);
+        assert_eq!(
+            str::from_utf8(&res).unwrap(),
+            "\
+            HTTP/1.1 200 OK\r\n\
+            transfer-encoding: chunked\r\n\
+            connection: close\r\n\
+            date: Thu, 01 Jan 1970 12:34:56 UTC\r\n\r\n\
+            4\r\ndata\r\n\
+            5\r\n!boom\r\n\
+            0\r\n\r\n\
+            "
+        );
+    }
+
+    fn large_chunks_service() -> impl Service<Request, Response = Response, Error = Error>
+    {
+        fn_service(|req: Request| {
+            let res = if req.path() == "/chunked" {
+                let stream = futures_util::stream::iter(vec![
+                    Ok::<_, Error>(Bytes::from_static(b"abc")),
+                    Ok(Bytes::from(vec![b'a'; 10_000])),
+                    Ok(Bytes::from(vec![b'b'; 5_000])),
+                    Ok(Bytes::from_static(b"de")),
+                ]);
+                Response::Ok().streaming(stream)
+            } else {
+                Response::Ok().body(vec![b'c'; 20_000])
+            };
+
+            ready(Ok::<_, Error>(res))
+        })
+    }
+
+    /// Test buffer that supports vectored writes and writes at most 4000 bytes at a time.
+    struct VectoredBuffer(TestBuffer);
+
+    impl AsyncRead for VectoredBuffer {
+        fn poll_read(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            buf: &mut actix_codec::ReadBuf<'_>,
+        ) -> Poll<io::Result<()>> {
+            Pin::new(&mut self.0).poll_read(cx, buf)
+        }
+    }
+
+    impl AsyncWrite for VectoredBuffer {
+        fn poll_write(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            buf: &[u8],
+        ) -> Poll<io::Result<usize>> {
+            let len = std::cmp::min(buf.len(), 4000);
+            Pin::new(&mut self.0).poll_write(cx, &buf[..len])
+        }
+
+        fn poll_write_vectored(
+            mut self: Pin<&mut Self>,
+            _: &mut Context<'_>,
+            bufs: &[IoSlice<'_>],
+        ) -> Poll<io::Result<usize>> {
+            let mut written = 0;
+            for buf in bufs {
+                let len = std::cmp::min(buf.len(), 4000 - written);
+                self.0.write_buf.extend_from_slice(&buf[..len]);
+                written += len;
+            }
+            Poll::Ready(Ok(written))
+        }
+
+        fn is_write_vectored(&self) -> bool {
+            true
+        }
+
+        fn poll_flush(
+            self: Pin<&mut Self>,
+            _: &mut Context<'_>,
+        ) -> Poll<io::Result<()>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn poll_shutdown(
+            self: Pin<&mut Self>,
+            _: &mut Context<'_>,
+        ) -> Poll<io::Result<()>> {
+            Poll::Ready(Ok(()))
+        }
+    }
+
+    async fn large_chunks_response<T>(io: T) -> T
+    where
+        T: AsyncRead + AsyncWrite + Unpin,
+    {
+        lazy(|cx| {
+            let cfg = ServiceConfig::new(KeepAlive::Disabled, 0, 0, false, None);
+            let services = HttpFlow::new(large_chunks_service(), ExpectHandler, None);
+
+            let h1 = Dispatcher::<_, _, _, _, UpgradeHandler>::new(
+                io,
+                cfg,
+                services,
+                OnConnectData::default(),
+                None,
+            );
+
+            actix_rt::pin!(h1);
+
+            match h1.as_mut().poll(cx) {
+                Poll::Pending => panic!("first poll should not be pending"),
+                Poll::Ready(res) => assert!(res.is_ok()),
+            }
+
+            match h1.project().inner.project() {
+                DispatcherStateProj::Normal(inner) => inner.project().io.take().unwrap(),
+                _ => panic!("dispatcher should not be upgraded"),
+            }
+        })
+        .await
+    }
+
+    #[actix_rt::test]
+    async fn test_large_chunks() {
+        let req = "\
+            GET /chunked HTTP/1.1\r\n\r\n\
+            GET /sized HTTP/1.1\r\n\r\n\
+            ";
+
+        let mut exp = Vec::new();
+        exp.extend_from_slice(
+            b"\
+            HTTP/1.1 200 OK\r\n\
+            transfer-encoding: chunked\r\n\
+            connection: close\r\n\
+            date: Thu, 01 Jan 1970 12:34:56 UTC\r\n\r\n\
+            3\r\nabc\r\n\
+            2710\r\n",
+        );
+        exp.extend_from_slice(&[b'a'; 10_000]);
+        exp.extend_from_slice(b"\r\n1388\r\n");
+        exp.extend_from_slice(&[b'b'; 5_000]);
+        exp.extend_from_slice(
+            b"\r\n\
+            2\r\nde\r\n\
+            0\r\n\r\n\
+            HTTP/1.1 200 OK\r\n\
+            content-length: 20000\r\n\
+            connection: close\r\n\
+            date: Thu, 01 Jan 1970 12:34:56 UTC\r\n\r\n",
+        );
+        exp.extend_from_slice(&[b'c'; 20_000]);
+
+        let mut res = large_chunks_response(TestBuffer::new(req))
+            .await
+            .write_buf
+            .to_vec();
+        stabilize_date_header(&mut res);
+        assert!(res == exp);
+
+        let mut res = large_chunks_response(VectoredBuffer(TestBuffer::new(req)))
+            .await
+            .0
+            .write_buf
+            .to_vec();
+        stabilize_date_header(&mut res);
+        assert!(res == exp);
+    }
+
+    async fn folded_headers_response(allow: bool) -> Vec<u8> {
+        lazy(|cx| {
+            let buf = TestBuffer::new(
+                "\
+                GET /one HTTP/1.1\r\n\
+                User-Agent: Device/1.0\r\n (embedded)\r\n\r\n\
+                GET /two HTTP/1.1\r\n\
+                User-Agent: Device/1.0\r\n\r\n\
+                ",
+            );
+
+            let cfg = ServiceConfig::from_options(ServiceConfigOptions {
+                keep_alive: KeepAlive::Disabled,
+                allow_obsolete_header_folding: allow,
+                ..Default::default()
+            });
+
+            let service = fn_service(|req: Request| {
+                let ua = req.head().headers.get("user-agent").unwrap().as_bytes();
+                ready(Ok::<_, Error>(Response::Ok().body(Body::from_slice(ua))))
+            });
+            let services = HttpFlow::new(service, ExpectHandler, None);
+
+            let h1 = Dispatcher::<_, _, _, _, UpgradeHandler>::new(
+                buf,
+                cfg,
+                services,
+                OnConnectData::default(),
+                None,
+            );
+
+            actix_rt::pin!(h1);
+
+            match h1.as_mut().poll(cx) {
+                Poll::Pending => panic!("first poll should not be pending"),
+                Poll::Ready(res) => assert!(res.is_ok()),
+            }
+
+            match h1.project().inner.project() {
+                DispatcherStateProj::Normal(inner) => {
+                    let mut res = inner.project().io.take().unwrap().write_buf.to_vec();
+                    stabilize_date_header(&mut res);
+                    res
+                }
+                _ => panic!("dispatcher should not be upgraded"),
+            }
+        })
+        .await
+    }
+
+    #[actix_rt::test]
+    async fn test_obsolete_folding() {
+        // rejected without closing the connection
+        let res = folded_headers_response(false).await;
+        assert_eq!(
+            str::from_utf8(&res).unwrap(),
+            "\
+            HTTP/1.1 400 Bad Request\r\n\
+            content-length: 0\r\n\
+            connection: close\r\n\
+            date: Thu, 01 Jan 1970 12:34:56 UTC\r\n\r\n\
+            HTTP/1.1 200 OK\r\n\
+            content-length: 10\r\n\
+            connection: close\r\n\
+            date: Thu, 01 Jan 1970 12:34:56 UTC\r\n\r\n\
+            Device/1.0\
+            "
+        );
+
+        let res = folded_headers_response(true).await;
+        assert_eq!(
+            str::from_utf8(&res).unwrap(),
+            "\
+            HTTP/1.1 200 OK\r\n\
+            content-length: 21\r\n\
+            connection: close\r\n\
+            date: Thu, 01 Jan 1970 12:34:56 UTC\r\n\r\n\
+            Device/1.0 (embedded)\
+            HTTP/1.1 200 OK\r\n\
+            content-length: 10\r\n\
+            connection: close\r\n\
+            date: Thu, 01 Jan 1970 12:34:56 UTC\r\n\r\n\
+            Device/1.0\
+            "
+        );
+    }
+
+    #[actix_rt::test]
+    async fn test_obsolete_folding_with_body() {
+        lazy(|cx| {
+            let buf = TestBuffer::new(
+                "\
+                POST /one HTTP/1.1\r\n\
+                User-Agent: Device/1.0\r\n (embedded)\r\n\
+                Content-Length: 4\r\n\r\n\
+                dataGET /two HTTP/1.1\r\n\r\n\
+                ",
+            );
+
+            let services = HttpFlow::new(ok_service(), ExpectHandler, None);
+
+            let h1 = Dispatcher::<_, _, _, _, UpgradeHandler>::new(
+                buf,
+                ServiceConfig::default(),
+                services,
+                OnConnectData::default(),
+                None,
+            );
+
+            actix_rt::pin!(h1);
+
+            match h1.as_mut().poll(cx) {
+                Poll::Pending => panic!("first poll should not be pending"),
+                Poll::Ready(res) => assert!(res.is_ok()),
+            }
+
+            if let DispatcherStateProj::Normal(inner) = h1.project().inner.project() {
+                let mut res = inner.project().io.take().unwrap().write_buf.to_vec();
+                stabilize_date_header(&mut res);
+
+                // the connection is closed since the body is not read
+                assert_eq!(
+                    str::from_utf8(&res).unwrap(),
+                    "\
+                    HTTP/1.1 400 Bad Request\r\n\
+                    content-length: 0\r\n\
+                    connection: close\r\n\
+                    date: Thu, 01 Jan 1970 12:34:56 UTC\r\n\r\n\
+                    "
+                );
+            }
+        })
+        .await;
+    }
+
+    #[actix_rt::test]
+    async fn test_chunked_size_line_too_long() {
+        lazy(|cx| {
+            let mut req = b"\
+                POST /one HTTP/1.1\r\n\
+                Transfer-Encoding: chunked\r\n\r\n\
+                "
+            .to_vec();
+            req.extend_from_slice(&[b'0'; 1024]);
+            let buf = TestBuffer::new(&req[..]);
+
+            let services = HttpFlow::new(
+                fn_service(|mut req: Request| async move {
+                    use futures_util::stream::StreamExt as _;
+
+                    let mut pl = req.take_payload();
+                    while let Some(chunk) = pl.next().await {
+                        chunk?;
+                    }
+
+                    Ok::<_, Error>(Response::Ok().finish())
+                }),
+                ExpectHandler,
+                None,
+            );
+
+            let h1 = Dispatcher::<_, _, _, _, UpgradeHandler>::new(
+                buf,
+                ServiceConfig::default(),
+                services,
+                OnConnectData::default(),
+                None,
+            );
+
+            actix_rt::pin!(h1);
+
+            match h1.as_mut().poll(cx) {
+                Poll::Pending => panic!("first poll should not be pending"),
+                Poll::Ready(res) => {
+                    let err = res.unwrap_err();
+                    assert_eq!(err.context().unwrap().path(), "/one");
+                    assert!(matches!(err.without_context(), DispatchError::Parse(_)));
+                }
+            }
+
+            if let DispatcherStateProj::Normal(inner) = h1.project().inner.project() {
+                let mut res = inner.project().io.take().unwrap().write_buf.to_vec();
+                stabilize_date_header(&mut res);
+
+                assert_eq!(
+                    str::from_utf8(&res).unwrap(),
+                    "\
+                    HTTP/1.1 400 Bad Request\r\n\
+                    content-length: 32\r\n\
+                    content-type: text/plain; charset=utf-8\r\n\
+                    date: Thu, 01 Jan 1970 12:34:56 UTC\r\n\r\n\
+                    Can not decode content-encoding.\
+                    "
+                );
+            }
+        })
+        .await;
+    }
+
+    fn half_close_dispatcher(
+        buf: TestSeqBuffer,
+        respond_after_half_close: bool,
+        rx: tokio::sync::oneshot::Receiver<()>,
+    ) -> Dispatcher<
+        TestSeqBuffer,
+        impl Service<Request, Response = Response, Error = Error>,
+        Body,
+        ExpectHandler,
+        UpgradeHandler,
+    > {
+        use futures_util::stream;
+
+        let rx = Rc::new(std::cell::RefCell::new(Some(rx)));
+
+        // responds once signalled, streaming the body in chunks
+        let service = fn_service(move |_req: Request| {
+            let rx = rx.borrow_mut().take().unwrap();
+            async move {
+                rx.await.unwrap();
+                let body = stream::iter(vec![
+                    Ok::<_, Error>(Bytes::from_static(b"hello ")),
+                    Ok(Bytes::from_static(b"world")),
+                ]);
+                Ok::<_, Error>(Response::Ok().streaming(body))
+            }
+        });
+
+        let cfg = ServiceConfig::from_options(ServiceConfigOptions {
+            respond_after_half_close,
+            ..Default::default()
+        });
+
+        Dispatcher::new(
+            buf,
+            cfg,
+            HttpFlow::new(service, ExpectHandler, None),
+            OnConnectData::default(),
+            None,
+        )
+    }
+
+    #[actix_rt::test]
+    async fn test_half_close_after_request() {
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        let mut buf = TestSeqBuffer::empty();
+        let h1 = half_close_dispatcher(buf.clone(), true, rx);
+        actix_rt::pin!(h1);
+
+        buf.extend_read_buf("GET /slow HTTP/1.1\r\n\r\n");
+        buf.close_read();
+
+        assert!(lazy(|cx| h1.as_mut().poll(cx)).await.is_pending());
+        assert!(buf.write_buf().is_empty());
+
+        tx.send(()).unwrap();
+        assert!(matches!(
+            lazy(|cx| h1.as_mut().poll(cx)).await,
+            Poll::Ready(Ok(()))
+        ));
+
+        let mut res = buf.write_buf()[..].to_owned();
+        stabilize_date_header(&mut res);
+        assert_eq!(
+            str::from_utf8(&res).unwrap(),
+            "HTTP/1.1 200 OK\r\n\
+            transfer-encoding: chunked\r\n\
+            date: Thu, 01 Jan 1970 12:34:56 UTC\r\n\r\n\
+            6\r\nhello \r\n5\r\nworld\r\n0\r\n\r\n"
+        );
+    }
+
+    #[actix_rt::test]
+    async fn test_half_close_mid_head() {
+        let (_tx, rx) = tokio::sync::oneshot::channel();
+        let mut buf = TestSeqBuffer::empty();
+        let h1 = half_close_dispatcher(buf.clone(), true, rx);
+        actix_rt::pin!(h1);
+
+        buf.extend_read_buf("GET /slow HTTP/1.1\r\nHost: loc");
+        buf.close_read();
+
+        match lazy(|cx| h1.as_mut().poll(cx)).await {
+            Poll::Ready(Err(DispatchError::Io(err))) => {
+                assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof)
+            }
+            _ => panic!("connection should be aborted"),
+        }
+        assert!(buf.write_buf().is_empty());
+    }
+
+    #[actix_rt::test]
+    async fn test_half_close_incomplete_payload() {
+        let mut buf = TestSeqBuffer::empty();
+        let cfg = ServiceConfig::new(KeepAlive::Disabled, 0, 0, false, None);
+
+        let service = fn_service(|mut req: Request| async move {
+            use futures_util::stream::StreamExt as _;
+
+            let mut pl = req.take_payload();
+            while let Some(chunk) = pl.next().await {
+                if chunk.is_err() {
+                    return Ok::<_, Error>(Response::BadRequest().finish());
+                }
+            }
+            Ok(Response::Ok().finish())
+        });
+
+        let h1 = Dispatcher::<_, _, _, _, UpgradeHandler>::new(
+            buf.clone(),
+            cfg,
+            HttpFlow::new(service, ExpectHandler, None),
+            OnConnectData::default(),
+            None,
+        );
+        actix_rt::pin!(h1);
+
+        buf.extend_read_buf("POST /upload HTTP/1.1\r\nContent-Length: 10\r\n\r\n12345");
+        buf.close_read();
+
+        assert!(lazy(|cx| h1.as_mut().poll(cx)).await.is_ready());
+        assert!(buf.write_buf().starts_with(b"HTTP/1.1 400 Bad Request\r\n"));
+    }
+
+    thread_local! {
+        static LOG_RECORDS: std::cell::RefCell<Option<Vec<String>>> =
+            std::cell::RefCell::new(None);
+    }
+
+    /// Logger keeping the messages logged on threads that run [`capture_logs`].
+    struct CaptureLogger;
+
+    impl log::Log for CaptureLogger {
+        fn enabled(&self, _: &log::Metadata<'_>) -> bool {
+            true
+        }
+
+        fn log(&self, record: &log::Record<'_>) {
+            LOG_RECORDS.with(|records| {
+                if let Some(ref mut records) = *records.borrow_mut() {
+                    records.push(record.args().to_string());
+                }
+            });
+        }
+
+        fn flush(&self) {}
+    }
+
+    /// Returns the messages logged on the current thread while `fut` runs.
+    async fn capture_logs(fut: impl Future<Output = ()>) -> Vec<String> {
+        static INIT: std::sync::Once = std::sync::Once::new();
+        INIT.call_once(|| {
+            log::set_logger(&CaptureLogger).unwrap();
+            log::set_max_level(log::LevelFilter::Debug);
+        });
+
+        LOG_RECORDS.with(|records| *records.borrow_mut() = Some(Vec::new()));
+        fut.await;
+        LOG_RECORDS.with(|records| records.borrow_mut().take().unwrap())
+    }
+
+    #[actix_rt::test]
+    async fn test_payload_error_request_context() {
+        let mut buf = TestSeqBuffer::empty();
+        let cfg = ServiceConfig::new(KeepAlive::Disabled, 0, 0, false, None);
+
+        let service = fn_service(|mut req: Request| async move {
+            use futures_util::stream::StreamExt as _;
+
+            let mut pl = req.take_payload();
+            while let Some(chunk) = pl.next().await {
+                chunk?;
+            }
+            Ok::<_, Error>(Response::Ok().finish())
+        });
+
+        let h1 = Dispatcher::<_, _, _, _, UpgradeHandler>::new(
+            buf.clone(),
+            cfg,
+            HttpFlow::new(service, ExpectHandler, None),
+            OnConnectData::default(),
+            Some("127.0.0.1:8080".parse().unwrap()),
+        );
+        actix_rt::pin!(h1);
+
+        buf.extend_read_buf(
+            "POST /upload?name=a HTTP/1.1\r\n\
+            Content-Length: 10\r\n\
+            X-Request-Id: abc\r\n\r\n\
+            12345",
+        );
+        assert!(lazy(|cx| h1.as_mut().poll(cx)).await.is_pending());
+
+        // the client goes away in the middle of the body
+        buf.close_read();
+        let logs = capture_logs(async {
+            assert!(lazy(|cx| h1.as_mut().poll(cx)).await.is_ready());
+        })
+        .await;
+
+        assert!(
+            logs.iter().any(|msg| msg.contains("Request payload error")
+                && msg.contains("POST /upload from 127.0.0.1:8080, request id abc")),
+            "{:?}",
+            logs
+        );
+    }
+
+    #[actix_rt::test]
+    async fn test_half_close_disabled() {
+        let (_tx, rx) = tokio::sync::oneshot::channel();
+        let mut buf = TestSeqBuffer::empty();
+        let h1 = half_close_dispatcher(buf.clone(), false, rx);
+        actix_rt::pin!(h1);
+
+        buf.extend_read_buf("GET /slow HTTP/1.1\r\n\r\n");
+        buf.close_read();
+
+        // the response in flight is dropped with the connection
+
+        assert!(matches!(
+            lazy(|cx| h1.as_mut().poll(cx)).await,
+            Poll::Ready(Ok(()))
+        ));
+        assert!(buf.write_buf().is_empty());
+    }
 }
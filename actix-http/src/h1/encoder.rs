@@ -1,14 +1,17 @@
+use std::cell::Ref;
+use std::collections::VecDeque;
 use std::io::Write;
 use std::marker::PhantomData;
 use std::ptr::copy_nonoverlapping;
 use std::slice::from_raw_parts_mut;
 use std::{cmp, io};
 
-use bytes::{BufMut, BytesMut};
+use bytes::{BufMut, Bytes, BytesMut};
 
 use crate::body::BodySize;
 use crate::config::ServiceConfig;
-use crate::header::{map::Value, HeaderName};
+use crate::extensions::Extensions;
+use crate::header::{map::Value, HeaderCaseMap, HeaderName};
 use crate::helpers;
 use crate::http::header::{CONNECTION, CONTENT_LENGTH, DATE, TRANSFER_ENCODING};
 use crate::http::{HeaderMap, StatusCode, Version};
@@ -17,6 +20,9 @@ use crate::response::Response;
 
 const AVERAGE_HEADER_SIZE: usize = 30;
 
+/// Body chunks of at least this size are queued for writing instead of being copied.
+pub(crate) const MIN_QUEUED_CHUNK: usize = 4 * 1024;
+
 #[derive(Debug)]
 pub(crate) struct MessageEncoder<T: MessageType> {
     pub length: BodySize,
@@ -45,6 +51,11 @@ pub(crate) trait MessageType: Sized {
         false
     }
 
+    /// Extensions that may hold the original casing of header names as a [`HeaderCaseMap`].
+    fn extensions(&self) -> Option<Ref<'_, Extensions>> {
+        None
+    }
+
     fn chunked(&self) -> bool;
 
     fn encode_status(&mut self, dst: &mut BytesMut) -> io::Result<()>;
@@ -60,6 +71,10 @@ pub(crate) trait MessageType: Sized {
         let chunked = self.chunked();
         let mut skip_len = length != BodySize::Stream;
         let camel_case = self.camel_case();
+        let extensions = self.extensions();
+        let header_case = extensions
+            .as_ref()
+            .and_then(|ext| ext.get::<HeaderCaseMap>());
 
         // Content length
         if let Some(status) = self.status() {
@@ -171,7 +186,10 @@ pub(crate) trait MessageType: Sized {
                 // SAFETY: on each write, it is enough to ensure that the advancement of
                 // the cursor matches the number of bytes written
                 unsafe {
-                    if camel_case {
+                    if let Some(name) = header_case.and_then(|case| case.get(key)) {
+                        // use original casing, which has the same length
+                        write_data(name.as_bytes(), buf, k_len);
+                    } else if camel_case {
                         // use Camel-Case headers
                         write_camel_case(k, from_raw_parts_mut(buf, k_len));
                     } else {
@@ -214,7 +232,7 @@ pub(crate) trait MessageType: Sized {
         Ok(())
     }
 
-    fn write_headers<F>(&mut self, mut f: F)
+    fn write_headers<F>(&self, mut f: F)
     where
         F: FnMut(&HeaderName, &Value),
     {
@@ -255,9 +273,21 @@ impl MessageType for Response<()> {
         let reason = head.reason().as_bytes();
         dst.reserve(256 + head.headers.len() * AVERAGE_HEADER_SIZE + reason.len());
 
-        // status line
-        helpers::write_status_line(head.version, head.status.as_u16(), dst);
-        dst.put_slice(reason);
+        // status line; precomputed unless the reason phrase is custom
+        match head.reason {
+            None => match standard_status_line(head.version, head.status) {
+                Some(line) => dst.put_slice(line),
+                None => {
+                    helpers::write_status_line(head.version, head.status.as_u16(), dst);
+                    dst.put_slice(reason);
+                }
+            },
+            Some(_) => {
+                helpers::write_status_line(head.version, head.status.as_u16(), dst);
+                dst.put_slice(reason);
+            }
+        }
+
         Ok(())
     }
 }
@@ -275,6 +305,10 @@ impl MessageType for RequestHeadType {
         self.as_ref().camel_case_headers()
     }
 
+    fn extensions(&self) -> Option<Ref<'_, Extensions>> {
+        Some(self.as_ref().extensions())
+    }
+
     fn headers(&self) -> &HeaderMap {
         self.as_ref().headers()
     }
@@ -314,11 +348,30 @@ impl<T: MessageType> MessageEncoder<T> {
         self.te.encode(msg, buf)
     }
 
+    /// Encode message, queueing large messages instead of copying them
+    pub fn encode_chunk_queued(
+        &mut self,
+        msg: Bytes,
+        buf: &mut BytesMut,
+        queue: &mut VecDeque<Bytes>,
+    ) -> io::Result<bool> {
+        self.te.encode_queued(msg, buf, queue)
+    }
+
     /// Encode eof
     pub fn encode_eof(&mut self, buf: &mut BytesMut) -> io::Result<()> {
         self.te.encode_eof(buf)
     }
 
+    /// Encode eof with trailers. Return `false` if trailers are not supported.
+    pub fn encode_trailers(
+        &mut self,
+        trailers: &HeaderMap,
+        buf: &mut BytesMut,
+    ) -> io::Result<bool> {
+        self.te.encode_trailers(trailers, buf)
+    }
+
     pub fn encode(
         &mut self,
         dst: &mut BytesMut,
@@ -447,6 +500,53 @@ impl TransferEncoding {
         }
     }
 
+    /// Encode message like [`encode`](Self::encode), but queue messages of at least
+    /// [`MIN_QUEUED_CHUNK`] bytes instead of copying them to `buf`.
+    ///
+    /// Queued messages are preceded by the contents of `buf`, which are moved to the queue first.
+    /// Writing the queue followed by `buf` produces the same output as `encode`.
+    pub fn encode_queued(
+        &mut self,
+        msg: Bytes,
+        buf: &mut BytesMut,
+        queue: &mut VecDeque<Bytes>,
+    ) -> io::Result<bool> {
+        if msg.len() < MIN_QUEUED_CHUNK {
+            return self.encode(&msg, buf);
+        }
+
+        match self.kind {
+            TransferEncodingKind::Eof => {
+                queue_bytes(msg, buf, queue);
+                Ok(false)
+            }
+            TransferEncodingKind::Chunked(eof) => {
+                if eof {
+                    return Ok(true);
+                }
+
+                writeln!(helpers::Writer(buf), "{:X}\r", msg.len())
+                    .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+
+                queue_bytes(msg, buf, queue);
+                buf.extend_from_slice(b"\r\n");
+                Ok(false)
+            }
+            TransferEncodingKind::Length(ref mut remaining) => {
+                if *remaining > 0 {
+                    let len = cmp::min(*remaining, msg.len() as u64);
+
+                    queue_bytes(msg.slice(..len as usize), buf, queue);
+
+                    *remaining -= len;
+                    Ok(*remaining == 0)
+                } else {
+                    Ok(true)
+                }
+            }
+        }
+    }
+
     /// Encode eof. Return `EOF` state of encoder
     #[inline]
     pub fn encode_eof(&mut self, buf: &mut BytesMut) -> io::Result<()> {
@@ -468,6 +568,34 @@ impl TransferEncoding {
             }
         }
     }
+
+    /// Encode last chunk followed by trailer fields.
+    ///
+    /// Only chunked transfer encoding can carry trailers. Return `false`, without writing
+    /// anything, for other encodings or when eof was already written.
+    pub fn encode_trailers(
+        &mut self,
+        trailers: &HeaderMap,
+        buf: &mut BytesMut,
+    ) -> io::Result<bool> {
+        match self.kind {
+            TransferEncodingKind::Chunked(ref mut eof) if !*eof => {
+                *eof = true;
+                buf.extend_from_slice(b"0\r\n");
+
+                for (name, value) in trailers {
+                    buf.extend_from_slice(name.as_str().as_bytes());
+                    buf.extend_from_slice(b": ");
+                    buf.extend_from_slice(value.as_bytes());
+                    buf.extend_from_slice(b"\r\n");
+                }
+
+                buf.extend_from_slice(b"\r\n");
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
 }
 
 /// # Safety
@@ -504,6 +632,100 @@ fn write_camel_case(value: &[u8], buffer: &mut [u8]) {
     }
 }
 
+macro_rules! status_lines {
+    ($($code:literal $reason:literal,)*) => {
+        /// Returns the complete status line of a standard status code with its canonical reason
+        /// phrase, for HTTP/1.1 and HTTP/1.0.
+        fn standard_status_line(version: Version, status: StatusCode) -> Option<&'static [u8]> {
+            let line = match version {
+                Version::HTTP_11 => match status.as_u16() {
+                    $($code => concat!("HTTP/1.1 ", $code, " ", $reason),)*
+                    _ => return None,
+                },
+                Version::HTTP_10 => match status.as_u16() {
+                    $($code => concat!("HTTP/1.0 ", $code, " ", $reason),)*
+                    _ => return None,
+                },
+                _ => return None,
+            };
+
+            Some(line.as_bytes())
+        }
+    };
+}
+
+// keep in sync with `StatusCode::canonical_reason`
+status_lines! {
+    100 "Continue",
+    101 "Switching Protocols",
+    102 "Processing",
+    200 "OK",
+    201 "Created",
+    202 "Accepted",
+    203 "Non Authoritative Information",
+    204 "No Content",
+    205 "Reset Content",
+    206 "Partial Content",
+    207 "Multi-Status",
+    208 "Already Reported",
+    226 "IM Used",
+    300 "Multiple Choices",
+    301 "Moved Permanently",
+    302 "Found",
+    303 "See Other",
+    304 "Not Modified",
+    305 "Use Proxy",
+    307 "Temporary Redirect",
+    308 "Permanent Redirect",
+    400 "Bad Request",
+    401 "Unauthorized",
+    402 "Payment Required",
+    403 "Forbidden",
+    404 "Not Found",
+    405 "Method Not Allowed",
+    406 "Not Acceptable",
+    407 "Proxy Authentication Required",
+    408 "Request Timeout",
+    409 "Conflict",
+    410 "Gone",
+    411 "Length Required",
+    412 "Precondition Failed",
+    413 "Payload Too Large",
+    414 "URI Too Long",
+    415 "Unsupported Media Type",
+    416 "Range Not Satisfiable",
+    417 "Expectation Failed",
+    418 "I'm a teapot",
+    421 "Misdirected Request",
+    422 "Unprocessable Entity",
+    423 "Locked",
+    424 "Failed Dependency",
+    426 "Upgrade Required",
+    428 "Precondition Required",
+    429 "Too Many Requests",
+    431 "Request Header Fields Too Large",
+    451 "Unavailable For Legal Reasons",
+    500 "Internal Server Error",
+    501 "Not Implemented",
+    502 "Bad Gateway",
+    503 "Service Unavailable",
+    504 "Gateway Timeout",
+    505 "HTTP Version Not Supported",
+    506 "Variant Also Negotiates",
+    507 "Insufficient Storage",
+    508 "Loop Detected",
+    510 "Not Extended",
+    511 "Network Authentication Required",
+}
+
+/// Queues `msg` after the contents of `buf`.
+fn queue_bytes(msg: Bytes, buf: &mut BytesMut, queue: &mut VecDeque<Bytes>) {
+    if !buf.is_empty() {
+        queue.push_back(buf.split().freeze());
+    }
+    queue.push_back(msg);
+}
+
 #[cfg(test)]
 mod tests {
     use std::rc::Rc;
@@ -515,6 +737,46 @@ mod tests {
     use crate::http::header::{HeaderValue, CONTENT_TYPE};
     use crate::RequestHead;
 
+    #[test]
+    fn test_status_line_table() {
+        let mut head: Response<()> = Response::new(StatusCode::OK).into_body::<()>();
+        let versions = [Version::HTTP_09, Version::HTTP_10, Version::HTTP_11];
+
+        for &version in versions.iter() {
+            for code in 100..1000 {
+                let status = StatusCode::from_u16(code).unwrap();
+                let reason =
+                    status.canonical_reason().unwrap_or("<unknown status code>");
+                let mut expected = BytesMut::new();
+                helpers::write_status_line(version, code, &mut expected);
+                expected.extend_from_slice(reason.as_bytes());
+
+                head.head_mut().version = version;
+                head.head_mut().status = status;
+                let mut bytes = BytesMut::new();
+                head.encode_status(&mut bytes).unwrap();
+                assert_eq!(bytes, expected, "{:?} {}", version, code);
+
+                let precomputed = standard_status_line(version, status).is_some();
+                assert_eq!(
+                    precomputed,
+                    version != Version::HTTP_09 && status.canonical_reason().is_some(),
+                    "{:?} {}",
+                    version,
+                    code
+                );
+            }
+        }
+
+        // custom reason phrases are kept
+        head.head_mut().version = Version::HTTP_11;
+        head.head_mut().status = StatusCode::OK;
+        head.head_mut().reason = Some("Fine");
+        let mut bytes = BytesMut::new();
+        head.encode_status(&mut bytes).unwrap();
+        assert_eq!(bytes, Bytes::from_static(b"HTTP/1.1 200 Fine"));
+    }
+
     #[test]
     fn test_chunked_te() {
         let mut bytes = BytesMut::new();
@@ -529,6 +791,84 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_encode_queued() {
+        let msgs = [
+            Bytes::from_static(b"test"),
+            Bytes::from(vec![b'a'; MIN_QUEUED_CHUNK]),
+            Bytes::from(vec![b'b'; MIN_QUEUED_CHUNK * 2]),
+            Bytes::from_static(b"tail"),
+            Bytes::new(),
+        ];
+
+        for &(make, eof) in &[
+            (TransferEncoding::chunked as fn() -> TransferEncoding, true),
+            (TransferEncoding::eof, true),
+            (
+                || TransferEncoding::length(MIN_QUEUED_CHUNK as u64 + 10),
+                false,
+            ),
+        ] {
+            let mut copied = BytesMut::from(&b"head"[..]);
+            let mut enc = make();
+            let copied_eof = msgs
+                .iter()
+                .map(|msg| enc.encode(msg, &mut copied).unwrap())
+                .collect::<Vec<_>>();
+
+            let mut buf = BytesMut::from(&b"head"[..]);
+            let mut queue = VecDeque::new();
+            let mut enc = make();
+            let queued_eof = msgs
+                .iter()
+                .map(|msg| {
+                    enc.encode_queued(msg.clone(), &mut buf, &mut queue)
+                        .unwrap()
+                })
+                .collect::<Vec<_>>();
+
+            // large messages are not copied
+            if eof {
+                assert!(queue.iter().any(|bytes| bytes.as_ptr() == msgs[2].as_ptr()));
+            }
+
+            let mut written = BytesMut::new();
+            for bytes in queue {
+                written.extend_from_slice(&bytes);
+            }
+            written.extend_from_slice(&buf);
+
+            assert_eq!(written, copied);
+            assert_eq!(queued_eof, copied_eof);
+        }
+    }
+
+    #[test]
+    fn test_chunked_te_trailers() {
+        let mut trailers = HeaderMap::new();
+        trailers.insert(
+            HeaderName::from_static("x-stream-error"),
+            HeaderValue::from_static("boom"),
+        );
+
+        let mut bytes = BytesMut::new();
+        let mut enc = TransferEncoding::chunked();
+        assert!(!enc.encode(b"test", &mut bytes).unwrap());
+        assert!(enc.encode_trailers(&trailers, &mut bytes).unwrap());
+        assert_eq!(
+            bytes.split().freeze(),
+            Bytes::from_static(b"4\r\ntest\r\n0\r\nx-stream-error: boom\r\n\r\n")
+        );
+
+        // eof already written
+        assert!(!enc.encode_trailers(&trailers, &mut bytes).unwrap());
+        assert!(bytes.is_empty());
+
+        let mut enc = TransferEncoding::length(4);
+        assert!(enc.encode(b"te", &mut bytes).is_ok());
+        assert!(!enc.encode_trailers(&trailers, &mut bytes).unwrap());
+    }
+
     #[actix_rt::test]
     async fn test_camel_case() {
         let mut bytes = BytesMut::with_capacity(2048);
@@ -592,6 +932,57 @@ mod tests {
         assert!(data.contains("date: date\r\n"));
     }
 
+    #[actix_rt::test]
+    async fn test_header_case() {
+        let soap_action = HeaderName::from_static("soapaction");
+        let api_key = HeaderName::from_static("x-api-key");
+
+        let mut bytes = BytesMut::with_capacity(2048);
+        let mut head = RequestHead::default();
+        head.headers
+            .insert(soap_action.clone(), HeaderValue::from_static("urn:get"));
+        head.headers
+            .insert(api_key.clone(), HeaderValue::from_static("key"));
+        head.headers
+            .insert(CONTENT_TYPE, HeaderValue::from_static("plain/text"));
+
+        let mut case = HeaderCaseMap::new();
+        case.insert(&soap_action, "SOAPAction");
+        case.insert(&api_key, "X-API-Key");
+        head.extensions_mut().insert(case);
+
+        let mut head = RequestHeadType::Owned(head);
+        let _ = head.encode_headers(
+            &mut bytes,
+            Version::HTTP_11,
+            BodySize::Empty,
+            ConnectionType::Close,
+            &ServiceConfig::default(),
+        );
+        let data =
+            String::from_utf8(Vec::from(bytes.split().freeze().as_ref())).unwrap();
+        assert!(data.contains("\r\nSOAPAction: urn:get\r\n"));
+        assert!(data.contains("\r\nX-API-Key: key\r\n"));
+        assert!(data.contains("\r\ncontent-type: plain/text\r\n"));
+        assert!(data.contains("\r\ncontent-length: 0\r\n"));
+
+        // headers without recorded casing follow the camel case setting
+        if let RequestHeadType::Owned(ref mut head) = head {
+            head.set_camel_case_headers(true);
+        }
+        let _ = head.encode_headers(
+            &mut bytes,
+            Version::HTTP_11,
+            BodySize::Empty,
+            ConnectionType::Close,
+            &ServiceConfig::default(),
+        );
+        let data =
+            String::from_utf8(Vec::from(bytes.split().freeze().as_ref())).unwrap();
+        assert!(data.contains("\r\nX-API-Key: key\r\n"));
+        assert!(data.contains("\r\nContent-Type: plain/text\r\n"));
+    }
+
     #[actix_rt::test]
     async fn test_extra_headers() {
         let mut bytes = BytesMut::with_capacity(2048);
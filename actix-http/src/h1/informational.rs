@@ -0,0 +1,89 @@
+use std::{
+    cell::{Cell, RefCell},
+    fmt, mem,
+    rc::Rc,
+    task::Waker,
+};
+
+use http::StatusCode;
+
+use crate::header::HeaderMap;
+
+/// Sender of informational (`1xx`) responses, such as `103 Early Hints`, on an HTTP/1.1
+/// connection.
+///
+/// Inserted into the extensions of HTTP/1.1 requests by the HTTP/1 dispatcher. Interim responses
+/// are written to the connection as soon as the dispatcher is polled, before the final response
+/// of the service. HTTP/1.0 clients do not understand them, so their requests have no sender;
+/// neither have HTTP/2 requests.
+#[derive(Clone)]
+pub struct Informational(Rc<Inner>);
+
+struct Inner {
+    queue: RefCell<Vec<(StatusCode, HeaderMap)>>,
+    closed: Cell<bool>,
+    waker: RefCell<Option<Waker>>,
+}
+
+impl Informational {
+    pub(crate) fn new() -> Self {
+        Informational(Rc::new(Inner {
+            queue: RefCell::new(Vec::new()),
+            closed: Cell::new(false),
+            waker: RefCell::new(None),
+        }))
+    }
+
+    /// Queues an informational response to be written before the final response.
+    ///
+    /// Several interim responses can be sent. Returns `false`, without sending anything, once the
+    /// final response has been written.
+    ///
+    /// # Panics
+    /// Panics if `status` is not a `1xx` status or is `101 Switching Protocols`, which can only
+    /// be sent as a final response.
+    pub fn send(&self, status: StatusCode, headers: HeaderMap) -> bool {
+        assert!(
+            status.is_informational() && status != StatusCode::SWITCHING_PROTOCOLS,
+            "Informational responses must have a 1xx status other than 101"
+        );
+
+        if self.0.closed.get() {
+            return false;
+        }
+
+        self.0.queue.borrow_mut().push((status, headers));
+
+        if let Some(waker) = self.0.waker.borrow_mut().take() {
+            waker.wake();
+        }
+
+        true
+    }
+
+    /// Takes the queued responses and wakes `waker` when more are sent.
+    pub(crate) fn poll_take(&self, waker: &Waker) -> Vec<(StatusCode, HeaderMap)> {
+        let mut slot = self.0.waker.borrow_mut();
+        if !slot.as_ref().map_or(false, |w| w.will_wake(waker)) {
+            *slot = Some(waker.clone());
+        }
+
+        mem::take(&mut *self.0.queue.borrow_mut())
+    }
+
+    /// Takes the queued responses; later ones are refused.
+    pub(crate) fn close(&self) -> Vec<(StatusCode, HeaderMap)> {
+        self.0.closed.set(true);
+        self.0.waker.borrow_mut().take();
+        mem::take(&mut *self.0.queue.borrow_mut())
+    }
+}
+
+impl fmt::Debug for Informational {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Informational")
+            .field("queued", &self.0.queue.borrow().len())
+            .field("closed", &self.0.closed.get())
+            .finish()
+    }
+}
@@ -1,21 +1,25 @@
 //! HTTP/1 protocol implementation.
 use bytes::{Bytes, BytesMut};
 
+mod backpressure;
 mod client;
 mod codec;
 mod decoder;
 mod dispatcher;
 mod encoder;
 mod expect;
+mod informational;
 mod payload;
 mod service;
 mod upgrade;
 mod utils;
 
+pub use self::backpressure::WriteBackpressure;
 pub use self::client::{ClientCodec, ClientPayloadCodec};
 pub use self::codec::Codec;
 pub use self::dispatcher::Dispatcher;
 pub use self::expect::ExpectHandler;
+pub use self::informational::Informational;
 pub use self::payload::Payload;
 pub use self::service::{H1Service, H1ServiceHandler};
 pub use self::upgrade::UpgradeHandler;
@@ -1,3 +1,4 @@
+use std::cell::RefCell;
 use std::future::Future;
 use std::marker::PhantomData;
 use std::net;
@@ -7,23 +8,27 @@ use std::task::{Context, Poll};
 use std::{cmp, convert::TryFrom};
 
 use actix_codec::{AsyncRead, AsyncWrite};
-use actix_rt::time::{Instant, Sleep};
+use actix_rt::time::{sleep_until, Instant, Sleep};
 use actix_service::Service;
 use bytes::{Bytes, BytesMut};
 use futures_core::ready;
-use h2::server::{Connection, SendResponse};
+use futures_util::future::{select, LocalBoxFuture};
+use h2::server::{Connection, SendPushedResponse, SendResponse};
 use h2::SendStream;
 use http::header::{HeaderValue, CONNECTION, CONTENT_LENGTH, DATE, TRANSFER_ENCODING};
 use log::{error, trace};
 
-use crate::body::{BodySize, MessageBody, ResponseBody};
+use crate::body::{BodySize, BodyTrailers, MessageBody, ResponseBody};
 use crate::config::ServiceConfig;
 use crate::error::{DispatchError, Error};
+use crate::h2::push::{ActivePush, PushQueue, ServerPush};
+use crate::header::vary;
 use crate::message::ResponseHead;
 use crate::payload::Payload;
 use crate::request::Request;
 use crate::response::Response;
 use crate::service::HttpFlow;
+use crate::span::ConnectionSpan;
 use crate::OnConnectData;
 
 const CHUNK_SIZE: usize = 16_384;
@@ -43,6 +48,9 @@ where
     peer_addr: Option<net::SocketAddr>,
     ka_expire: Instant,
     ka_timer: Option<Sleep>,
+    pushes: Rc<PushQueue>,
+    goaway: Option<LocalBoxFuture<'static, ()>>,
+    span: ConnectionSpan,
     _phantom: PhantomData<B>,
 }
 
@@ -78,6 +86,18 @@ where
             (config.now(), None)
         };
 
+        // resolves when the connection should stop accepting streams
+        let shutdown = config.shutdown().wait();
+        let goaway: LocalBoxFuture<'static, ()> = match config.max_connection_age() {
+            Some(age) => {
+                let age = sleep_until(config.now() + age);
+                Box::pin(async move {
+                    select(Box::pin(age), Box::pin(shutdown)).await;
+                })
+            }
+            None => Box::pin(shutdown),
+        };
+
         Dispatcher {
             flow,
             config,
@@ -86,6 +106,9 @@ where
             on_connect_data,
             ka_expire,
             ka_timer,
+            pushes: PushQueue::new(),
+            goaway: Some(goaway),
+            span: ConnectionSpan::new("h2", peer_addr),
             _phantom: PhantomData,
         }
     }
@@ -105,14 +128,58 @@ where
     #[inline]
     fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
         let this = self.get_mut();
+        let _span = this.span.enter();
+
+        this.pushes.register(cx.waker());
+
+        if let Some(ref mut goaway) = this.goaway {
+            if goaway.as_mut().poll(cx).is_ready() {
+                // send GOAWAY and close once in-flight streams are done
+                trace!("Gracefully shutting down HTTP/2 connection");
+                this.connection.graceful_shutdown();
+                this.goaway = None;
+            }
+        }
 
         loop {
+            // dispatch requests promised to the client
+            while let Some(push) = this.pushes.pop() {
+                let mut req = Request::new();
+
+                let head = req.head_mut();
+                head.uri = push.uri;
+                head.method = http::Method::GET;
+                head.version = http::Version::HTTP_2;
+                head.headers = push.headers.into();
+                head.peer_addr = this.peer_addr;
+
+                this.on_connect_data.merge_into(&mut req);
+
+                let svc = ServiceResponse::<S::Future, S::Response, S::Error, B> {
+                    state: ServiceResponseState::ServiceCall(
+                        this.flow.service.call(req),
+                        Some(ResponseSender::Pushed(push.send)),
+                    ),
+                    config: this.config.clone(),
+                    buffer: None,
+                    push: Some(push.guard),
+                    stalled: None,
+                    _phantom: PhantomData,
+                };
+
+                actix_rt::spawn(this.span.instrument(svc));
+            }
+
             match ready!(Pin::new(&mut this.connection).poll_accept(cx)) {
                 None => return Poll::Ready(Ok(())),
 
                 Some(Err(err)) => return Poll::Ready(Err(err.into())),
 
                 Some(Ok((req, res))) => {
+                    // the peer's settings are known once a stream was accepted
+                    this.pushes
+                        .set_max_streams(this.connection.max_concurrent_send_streams());
+
                     // update keep-alive expire
                     if this.ka_timer.is_some() {
                         if let Some(expire) = this.config.keep_alive_expire() {
@@ -126,26 +193,36 @@ where
                     let mut req = Request::with_payload(pl);
 
                     let head = &mut req.head_mut();
-                    head.uri = parts.uri;
+                    head.uri = parts.uri.clone();
                     head.method = parts.method;
                     head.version = parts.version;
                     head.headers = parts.headers.into();
                     head.peer_addr = this.peer_addr;
 
-                    // merge on_connect_ext data into request extensions
+                    // attach on_connect_ext data to request
                     this.on_connect_data.merge_into(&mut req);
 
+                    // share the response stream with the request's server push handle
+                    let res = Rc::new(RefCell::new(Some(res)));
+                    req.head().extensions_mut().insert(ServerPush::new(
+                        Rc::clone(&res),
+                        Rc::clone(&this.pushes),
+                        parts.uri,
+                    ));
+
                     let svc = ServiceResponse::<S::Future, S::Response, S::Error, B> {
                         state: ServiceResponseState::ServiceCall(
                             this.flow.service.call(req),
-                            Some(res),
+                            Some(ResponseSender::Stream(res)),
                         ),
                         config: this.config.clone(),
                         buffer: None,
+                        push: None,
+                        stalled: None,
                         _phantom: PhantomData,
                     };
 
-                    actix_rt::spawn(svc);
+                    actix_rt::spawn(this.span.instrument(svc));
                 }
             }
         }
@@ -158,15 +235,42 @@ struct ServiceResponse<F, I, E, B> {
     state: ServiceResponseState<F, B>,
     config: ServiceConfig,
     buffer: Option<Bytes>,
+    push: Option<ActivePush>,
+    stalled: Option<Instant>,
     _phantom: PhantomData<(I, E)>,
 }
 
 #[pin_project::pin_project(project = ServiceResponseStateProj)]
 enum ServiceResponseState<F, B> {
-    ServiceCall(#[pin] F, Option<SendResponse<Bytes>>),
+    ServiceCall(#[pin] F, Option<ResponseSender>),
     SendPayload(SendStream<Bytes>, #[pin] ResponseBody<B>),
 }
 
+/// Stream the response of a service call is sent on.
+enum ResponseSender {
+    /// Stream of a client request, shared with the request's [`ServerPush`] handle.
+    Stream(Rc<RefCell<Option<SendResponse<Bytes>>>>),
+
+    /// Stream promised to the client by a server push.
+    Pushed(SendPushedResponse<Bytes>),
+}
+
+impl ResponseSender {
+    fn send_response(
+        self,
+        res: http::Response<()>,
+        end_of_stream: bool,
+    ) -> Result<SendStream<Bytes>, h2::Error> {
+        match self {
+            ResponseSender::Stream(send) => match send.borrow_mut().take() {
+                Some(mut send) => send.send_response(res, end_of_stream),
+                None => unreachable!("HTTP/2 response sent twice"),
+            },
+            ResponseSender::Pushed(mut send) => send.send_response(res, end_of_stream),
+        }
+    }
+}
+
 impl<F, I, E, B> ServiceResponse<F, I, E, B>
 where
     F: Future<Output = Result<I, E>>,
@@ -253,10 +357,11 @@ where
             ServiceResponseStateProj::ServiceCall(call, send) => {
                 match ready!(call.poll(cx)) {
                     Ok(res) => {
-                        let (res, body) = res.into().replace_body(());
+                        let (mut res, body) = res.into().replace_body(());
 
-                        let mut send = send.take().unwrap();
+                        let send = send.take().unwrap();
                         let mut size = body.size();
+                        vary::finalize(res.head_mut());
                         let h2_res =
                             self.as_mut().prepare_response(res.head(), &mut size);
                         this = self.as_mut().project();
@@ -280,10 +385,11 @@ where
 
                     Err(e) => {
                         let res: Response = e.into().into();
-                        let (res, body) = res.replace_body(());
+                        let (mut res, body) = res.replace_body(());
 
-                        let mut send = send.take().unwrap();
+                        let send = send.take().unwrap();
                         let mut size = body.size();
+                        vary::finalize(res.head_mut());
                         let h2_res =
                             self.as_mut().prepare_response(res.head(), &mut size);
                         this = self.as_mut().project();
@@ -311,57 +417,85 @@ where
 
             ServiceResponseStateProj::SendPayload(ref mut stream, ref mut body) => {
                 loop {
-                    loop {
-                        match this.buffer {
-                            Some(ref mut buffer) => {
-                                match ready!(stream.poll_capacity(cx)) {
-                                    None => return Poll::Ready(()),
-
-                                    Some(Ok(cap)) => {
-                                        let len = buffer.len();
-                                        let bytes = buffer.split_to(cmp::min(cap, len));
-
-                                        if let Err(e) = stream.send_data(bytes, false) {
-                                            warn!("{:?}", e);
-                                            return Poll::Ready(());
-                                        } else if !buffer.is_empty() {
-                                            let cap = cmp::min(buffer.len(), CHUNK_SIZE);
-                                            stream.reserve_capacity(cap);
-                                        } else {
-                                            this.buffer.take();
-                                        }
-                                    }
+                    // wait for flow control window before producing or sending more data
+                    if stream.capacity() == 0 {
+                        let wanted = match this.buffer {
+                            Some(ref buffer) => cmp::min(buffer.len(), CHUNK_SIZE),
+                            None => CHUNK_SIZE,
+                        };
+                        stream.reserve_capacity(wanted);
 
-                                    Some(Err(e)) => {
-                                        warn!("{:?}", e);
-                                        return Poll::Ready(());
-                                    }
+                        match stream.poll_capacity(cx) {
+                            Poll::Pending => {
+                                if this.stalled.is_none() {
+                                    *this.stalled = Some(Instant::now());
                                 }
+                                return Poll::Pending;
                             }
 
-                            None => match ready!(body.as_mut().poll_next(cx)) {
-                                None => {
-                                    if let Err(e) = stream.send_data(Bytes::new(), true)
-                                    {
-                                        warn!("{:?}", e);
-                                    }
-                                    return Poll::Ready(());
+                            Poll::Ready(None) => return Poll::Ready(()),
+
+                            Poll::Ready(Some(Ok(_))) => {
+                                if let Some(since) = this.stalled.take() {
+                                    this.config
+                                        .stats()
+                                        .add_h2_window_stall(since.elapsed());
+                                }
+                                continue;
+                            }
+
+                            Poll::Ready(Some(Err(e))) => {
+                                warn!("{:?}", e);
+                                return Poll::Ready(());
+                            }
+                        }
+                    }
+
+                    match this.buffer {
+                        Some(ref mut buffer) => {
+                            let cap = cmp::min(stream.capacity(), buffer.len());
+                            let bytes = buffer.split_to(cap);
+
+                            if let Err(e) = stream.send_data(bytes, false) {
+                                warn!("{:?}", e);
+                                return Poll::Ready(());
+                            } else if buffer.is_empty() {
+                                this.buffer.take();
+                            }
+                        }
+
+                        None => match ready!(body.as_mut().poll_next(cx)) {
+                            None => {
+                                if let Err(e) = stream.send_data(Bytes::new(), true) {
+                                    warn!("{:?}", e);
                                 }
+                                return Poll::Ready(());
+                            }
 
-                                Some(Ok(chunk)) => {
-                                    stream.reserve_capacity(cmp::min(
-                                        chunk.len(),
-                                        CHUNK_SIZE,
-                                    ));
+                            Some(Ok(chunk)) => {
+                                if !chunk.is_empty() {
                                     *this.buffer = Some(chunk);
                                 }
+                            }
+
+                            Some(Err(e)) => {
+                                if let Some(BodyTrailers { trailers, .. }) = e.as_error()
+                                {
+                                    let trailers = trailers
+                                        .iter()
+                                        .map(|(k, v)| (k.clone(), v.clone()))
+                                        .collect();
 
-                                Some(Err(e)) => {
+                                    if let Err(e) = stream.send_trailers(trailers) {
+                                        warn!("{:?}", e);
+                                    }
+                                } else {
                                     error!("Response payload stream error: {:?}", e);
-                                    return Poll::Ready(());
                                 }
-                            },
-                        }
+
+                                return Poll::Ready(());
+                            }
+                        },
                     }
                 }
             }
@@ -10,10 +10,14 @@ use futures_core::{ready, Stream};
 use h2::RecvStream;
 
 mod dispatcher;
+mod push;
 mod service;
+mod settings;
 
 pub use self::dispatcher::Dispatcher;
+pub use self::push::ServerPush;
 pub use self::service::H2Service;
+pub use self::settings::H2Settings;
 use crate::error::PayloadError;
 
 /// HTTP/2 peer stream.
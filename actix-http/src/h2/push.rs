@@ -0,0 +1,157 @@
+use std::{
+    cell::{Cell, RefCell},
+    collections::VecDeque,
+    fmt,
+    rc::Rc,
+};
+
+use actix_utils::task::LocalWaker;
+use bytes::Bytes;
+use h2::server::{SendPushedResponse, SendResponse};
+use http::{uri::PathAndQuery, Method, Uri};
+
+use crate::error::PushError;
+use crate::header::HeaderMap;
+
+/// Pushed requests of a connection waiting to be dispatched to the service.
+pub(crate) struct PushQueue {
+    pending: RefCell<VecDeque<PendingPush>>,
+    task: LocalWaker,
+    active: Cell<usize>,
+    max_streams: Cell<usize>,
+}
+
+/// A promised request together with the stream its response is sent on.
+pub(crate) struct PendingPush {
+    pub(crate) uri: Uri,
+    pub(crate) headers: http::HeaderMap,
+    pub(crate) send: SendPushedResponse<Bytes>,
+    pub(crate) guard: ActivePush,
+}
+
+/// Counts a pushed stream as active until dropped.
+pub(crate) struct ActivePush(Rc<PushQueue>);
+
+impl Drop for ActivePush {
+    fn drop(&mut self) {
+        self.0.active.set(self.0.active.get() - 1);
+    }
+}
+
+impl PushQueue {
+    pub(crate) fn new() -> Rc<Self> {
+        Rc::new(PushQueue {
+            pending: RefCell::new(VecDeque::new()),
+            task: LocalWaker::new(),
+            active: Cell::new(0),
+            max_streams: Cell::new(usize::MAX),
+        })
+    }
+
+    /// Update the peer's `SETTINGS_MAX_CONCURRENT_STREAMS` limit.
+    pub(crate) fn set_max_streams(&self, max: usize) {
+        self.max_streams.set(max);
+    }
+
+    /// Register the dispatcher task to be woken when a push is queued.
+    pub(crate) fn register(&self, waker: &std::task::Waker) {
+        self.task.register(waker);
+    }
+
+    pub(crate) fn pop(&self) -> Option<PendingPush> {
+        self.pending.borrow_mut().pop_front()
+    }
+}
+
+/// HTTP/2 server push handle of a request.
+///
+/// Inserted into the extensions of requests received over HTTP/2. Pushed requests are sent
+/// to the client as `PUSH_PROMISE` frames and are dispatched to the same service as
+/// regular requests, so routing and middleware apply to them.
+#[derive(Clone)]
+pub struct ServerPush {
+    send: Rc<RefCell<Option<SendResponse<Bytes>>>>,
+    queue: Rc<PushQueue>,
+    base: Uri,
+}
+
+impl ServerPush {
+    pub(crate) fn new(
+        send: Rc<RefCell<Option<SendResponse<Bytes>>>>,
+        queue: Rc<PushQueue>,
+        base: Uri,
+    ) -> Self {
+        ServerPush { send, queue, base }
+    }
+
+    /// Promise a `GET` request for `uri` with `headers` to the client.
+    ///
+    /// Relative URIs are resolved against the scheme and authority of the current request.
+    /// Pushes must happen before the response to the current request is sent.
+    ///
+    /// The number of pushed streams is limited by the client's `SETTINGS_MAX_CONCURRENT_STREAMS`;
+    /// clients which do not advertise a limit do not receive pushes.
+    pub fn push(&self, uri: Uri, headers: HeaderMap) -> Result<(), PushError> {
+        let uri = self.resolve(uri)?;
+
+        let mut send = self.send.borrow_mut();
+        let send = send.as_mut().ok_or(PushError::Unavailable)?;
+
+        if self.queue.active.get() >= self.queue.max_streams.get() {
+            return Err(PushError::TooManyStreams);
+        }
+
+        let mut req = http::Request::new(());
+        *req.method_mut() = Method::GET;
+        *req.uri_mut() = uri.clone();
+        for (name, value) in headers.iter() {
+            req.headers_mut().append(name, value.clone());
+        }
+        let headers = req.headers().clone();
+
+        let pushed = send.push_request(req).map_err(PushError::H2)?;
+
+        self.queue.active.set(self.queue.active.get() + 1);
+        self.queue.pending.borrow_mut().push_back(PendingPush {
+            uri,
+            headers,
+            send: pushed,
+            guard: ActivePush(self.queue.clone()),
+        });
+        self.queue.task.wake();
+
+        Ok(())
+    }
+
+    fn resolve(&self, uri: Uri) -> Result<Uri, PushError> {
+        if uri.authority().is_some() {
+            return Ok(uri);
+        }
+
+        let mut builder = Uri::builder();
+        if let Some(scheme) = self.base.scheme() {
+            builder = builder.scheme(scheme.clone());
+        }
+        if let Some(authority) = self.base.authority() {
+            builder = builder.authority(authority.clone());
+        }
+
+        let path = match uri.path_and_query() {
+            Some(path) => path.clone(),
+            None => PathAndQuery::from_static("/"),
+        };
+
+        builder
+            .path_and_query(path)
+            .build()
+            .map_err(PushError::InvalidUri)
+    }
+}
+
+impl fmt::Debug for ServerPush {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ServerPush")
+            .field("base", &self.base)
+            .finish()
+    }
+}
@@ -0,0 +1,102 @@
+use actix_codec::{AsyncRead, AsyncWrite};
+use bytes::Bytes;
+use h2::server::{Builder, Handshake};
+
+/// Largest flow control window allowed by the protocol.
+const MAX_WINDOW_SIZE: u32 = (1 << 31) - 1;
+
+/// Smallest `SETTINGS_MAX_FRAME_SIZE` allowed by the protocol.
+const MIN_FRAME_SIZE: u32 = 1 << 14;
+
+/// Largest `SETTINGS_MAX_FRAME_SIZE` allowed by the protocol.
+const MAX_FRAME_SIZE: u32 = (1 << 24) - 1;
+
+/// HTTP/2 connection settings advertised by the server.
+///
+/// Settings which are not set use the defaults of the `h2` crate.
+///
+/// ```rust
+/// use actix_http::h2::H2Settings;
+///
+/// let settings = H2Settings::default()
+///     .max_concurrent_streams(256)
+///     .initial_window_size(1024 * 1024)
+///     .max_frame_size(64 * 1024);
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct H2Settings {
+    max_concurrent_streams: Option<u32>,
+    initial_window_size: Option<u32>,
+    initial_connection_window_size: Option<u32>,
+    max_frame_size: Option<u32>,
+}
+
+impl H2Settings {
+    /// Set `SETTINGS_MAX_CONCURRENT_STREAMS`, the number of streams a client may open at once.
+    pub fn max_concurrent_streams(mut self, max: u32) -> Self {
+        self.max_concurrent_streams = Some(max);
+        self
+    }
+
+    /// Set `SETTINGS_INITIAL_WINDOW_SIZE`, the flow control window of each stream in bytes.
+    ///
+    /// # Panics
+    /// Panics if `size` is larger than 2^31 - 1.
+    pub fn initial_window_size(mut self, size: u32) -> Self {
+        assert!(
+            size <= MAX_WINDOW_SIZE,
+            "HTTP/2 initial window size must be at most 2^31 - 1"
+        );
+        self.initial_window_size = Some(size);
+        self
+    }
+
+    /// Set the flow control window of the whole connection in bytes.
+    ///
+    /// # Panics
+    /// Panics if `size` is larger than 2^31 - 1.
+    pub fn initial_connection_window_size(mut self, size: u32) -> Self {
+        assert!(
+            size <= MAX_WINDOW_SIZE,
+            "HTTP/2 initial connection window size must be at most 2^31 - 1"
+        );
+        self.initial_connection_window_size = Some(size);
+        self
+    }
+
+    /// Set `SETTINGS_MAX_FRAME_SIZE`, the largest frame payload the server accepts in bytes.
+    ///
+    /// # Panics
+    /// Panics if `size` is not between 2^14 and 2^24 - 1.
+    pub fn max_frame_size(mut self, size: u32) -> Self {
+        assert!(
+            (MIN_FRAME_SIZE..=MAX_FRAME_SIZE).contains(&size),
+            "HTTP/2 max frame size must be between 2^14 and 2^24 - 1"
+        );
+        self.max_frame_size = Some(size);
+        self
+    }
+
+    /// Start the server handshake on `io` with these settings.
+    pub(crate) fn handshake<T>(&self, io: T) -> Handshake<T, Bytes>
+    where
+        T: AsyncRead + AsyncWrite + Unpin,
+    {
+        let mut builder = Builder::new();
+
+        if let Some(max) = self.max_concurrent_streams {
+            builder.max_concurrent_streams(max);
+        }
+        if let Some(size) = self.initial_window_size {
+            builder.initial_window_size(size);
+        }
+        if let Some(size) = self.initial_connection_window_size {
+            builder.initial_connection_window_size(size);
+        }
+        if let Some(size) = self.max_frame_size {
+            builder.max_frame_size(size);
+        }
+
+        builder.handshake(io)
+    }
+}
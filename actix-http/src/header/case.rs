@@ -0,0 +1,70 @@
+use std::collections::HashMap;
+
+use http::header::HeaderName;
+
+/// Original casing of header names.
+///
+/// `HeaderName`s are always lowercase. When a `HeaderCaseMap` is present in the extensions of a
+/// client request, the HTTP/1 encoder writes the names it contains with their recorded casing
+/// instead. Headers without a recorded casing, and those generated by the encoder itself, are
+/// written as usual.
+#[derive(Debug, Clone, Default)]
+pub struct HeaderCaseMap {
+    names: HashMap<HeaderName, Box<str>>,
+}
+
+impl HeaderCaseMap {
+    /// Create an empty map.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record `original` as the casing of header `name`.
+    ///
+    /// Does nothing if `original` is not a differently cased spelling of `name`.
+    pub fn insert(&mut self, name: &HeaderName, original: &str) {
+        if name.as_str().eq_ignore_ascii_case(original) {
+            self.names.insert(name.clone(), original.into());
+        }
+    }
+
+    /// Returns the recorded casing of header `name`.
+    pub fn get(&self, name: &HeaderName) -> Option<&str> {
+        self.names.get(name).map(AsRef::as_ref)
+    }
+
+    /// Forget the recorded casing of header `name`.
+    pub fn remove(&mut self, name: &HeaderName) {
+        self.names.remove(name);
+    }
+
+    /// Returns true if no casing is recorded.
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_insert() {
+        let name = HeaderName::from_static("x-api-key");
+
+        let mut map = HeaderCaseMap::new();
+        assert!(map.is_empty());
+
+        map.insert(&name, "X-Other");
+        assert_eq!(map.get(&name), None);
+
+        map.insert(&name, "X-Api-Key");
+        assert_eq!(map.get(&name), Some("X-Api-Key"));
+
+        map.insert(&name, "X-API-KEY");
+        assert_eq!(map.get(&name), Some("X-API-KEY"));
+
+        map.remove(&name);
+        assert!(map.is_empty());
+    }
+}
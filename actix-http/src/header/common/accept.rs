@@ -2,7 +2,7 @@ use std::cmp::Ordering;
 
 use mime::Mime;
 
-use crate::header::{qitem, QualityItem};
+use crate::header::{q, qitem, QualityItem};
 use crate::http::header;
 
 header! {
@@ -208,6 +208,87 @@ impl Accept {
         let types = self.mime_precedence();
         types.first().cloned()
     }
+
+    /// Selects the most acceptable of the `available` mime types, following [RFC 7231]
+    /// precedence rules.
+    ///
+    /// Each available type is weighted by the q-factor of the most specific media range matching
+    /// it, where `text/html;level=1` is more specific than `text/html`, which is more specific
+    /// than `text/*` and then `*/*`. Types with a q-factor of zero, or that match no media range,
+    /// are not acceptable. When several types are equally acceptable, the first one in
+    /// `available` is chosen. An empty `Accept` list accepts any type.
+    ///
+    /// Returns `None` if none of the available types are acceptable.
+    ///
+    /// ```
+    /// use actix_http::http::header::{qitem, q, Accept, QualityItem};
+    ///
+    /// // Accept: text/*;q=0.5, application/json
+    /// let accept = Accept(vec![
+    ///     QualityItem::new(mime::TEXT_STAR, q(0.5)),
+    ///     qitem(mime::APPLICATION_JSON),
+    /// ]);
+    /// let available = [mime::TEXT_HTML, mime::APPLICATION_JSON];
+    /// assert_eq!(accept.negotiate(&available), Some(mime::APPLICATION_JSON));
+    /// ```
+    ///
+    /// [RFC 7231]: https://tools.ietf.org/html/rfc7231#section-5.3.2
+    pub fn negotiate(&self, available: &[Mime]) -> Option<Mime> {
+        if self.0.is_empty() {
+            return available.first().cloned();
+        }
+
+        let mut best = None;
+
+        for mime in available {
+            let quality = self
+                .0
+                .iter()
+                .filter_map(|range| {
+                    media_range_specificity(&range.item, mime).map(|spec| (spec, range.quality))
+                })
+                .max_by_key(|(spec, _)| *spec)
+                .map(|(_, quality)| quality);
+
+            match (quality, best) {
+                (Some(quality), _) if quality == q(0u16) => {}
+                (Some(quality), Some((best_quality, _))) if quality <= best_quality => {}
+                (Some(quality), _) => best = Some((quality, mime)),
+                (None, _) => {}
+            }
+        }
+
+        best.map(|(_, mime)| mime.clone())
+    }
+}
+
+/// Returns the specificity of `range` if it matches `mime`, or `None` if it does not.
+fn media_range_specificity(range: &Mime, mime: &Mime) -> Option<usize> {
+    if range.type_() == mime::STAR {
+        return Some(0);
+    }
+
+    if range.type_() != mime.type_() {
+        return None;
+    }
+
+    if range.subtype() == mime::STAR {
+        return Some(1);
+    }
+
+    if range.essence_str() != mime.essence_str() {
+        return None;
+    }
+
+    let mut params = 0;
+    for (name, value) in range.params() {
+        if mime.get_param(name) != Some(value) {
+            return None;
+        }
+        params += 1;
+    }
+
+    Some(2 + params)
 }
 
 #[cfg(test)]
@@ -250,6 +331,60 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_negotiate() {
+        let available = [mime::APPLICATION_JSON, mime::TEXT_HTML];
+
+        let test = Accept::star();
+        assert_eq!(test.negotiate(&available), Some(mime::APPLICATION_JSON));
+
+        let test = Accept(vec![]);
+        assert_eq!(test.negotiate(&available), Some(mime::APPLICATION_JSON));
+
+        let test = Accept(vec![
+            QualityItem::new(mime::TEXT_STAR, q(0.5)),
+            qitem(mime::APPLICATION_JSON),
+        ]);
+        assert_eq!(test.negotiate(&available), Some(mime::APPLICATION_JSON));
+        assert_eq!(test.negotiate(&[mime::TEXT_HTML]), Some(mime::TEXT_HTML));
+        assert_eq!(test.negotiate(&[mime::IMAGE_PNG]), None);
+
+        // more specific ranges take precedence over less specific ones
+        let test = Accept(vec![
+            qitem(mime::TEXT_STAR),
+            QualityItem::new(mime::TEXT_HTML, q(0.2)),
+            QualityItem::new(mime::STAR_STAR, q(0.1)),
+        ]);
+        assert_eq!(test.negotiate(&available), Some(mime::TEXT_HTML));
+        assert_eq!(
+            test.negotiate(&[mime::TEXT_HTML, mime::TEXT_PLAIN]),
+            Some(mime::TEXT_PLAIN)
+        );
+
+        // q=0 is not acceptable
+        let test = Accept(vec![
+            qitem(mime::STAR_STAR),
+            QualityItem::new(mime::APPLICATION_JSON, q(0u16)),
+        ]);
+        assert_eq!(test.negotiate(&[mime::APPLICATION_JSON]), None);
+        assert_eq!(test.negotiate(&available), Some(mime::TEXT_HTML));
+
+        // media range parameters must match
+        let test = Accept(vec![
+            qitem("text/html;level=1".parse().unwrap()),
+            QualityItem::new(mime::TEXT_HTML, q(0.7)),
+            QualityItem::new(mime::TEXT_PLAIN, q(0.8)),
+        ]);
+        assert_eq!(
+            test.negotiate(&[mime::TEXT_HTML, mime::TEXT_PLAIN]),
+            Some(mime::TEXT_PLAIN)
+        );
+        assert_eq!(
+            test.negotiate(&[mime::TEXT_PLAIN, "text/html;level=1".parse().unwrap()]),
+            Some("text/html;level=1".parse().unwrap())
+        );
+    }
+
     #[test]
     fn test_mime_preference() {
         let test = Accept(vec![
@@ -120,8 +120,14 @@ pub enum CacheDirective {
     ProxyRevalidate,
     /// "s-maxage=delta"
     SMaxAge(u32),
+    /// "stale-while-revalidate=delta"
+    StaleWhileRevalidate(u32),
+    /// "immutable"
+    Immutable,
 
     /// Extension directives. Optionally include an argument.
+    ///
+    /// Arguments are kept verbatim, including any surrounding quotes.
     Extension(String, Option<String>),
 }
 
@@ -144,6 +150,10 @@ impl fmt::Display for CacheDirective {
                 Private => "private",
                 ProxyRevalidate => "proxy-revalidate",
                 SMaxAge(secs) => return write!(f, "s-maxage={}", secs),
+                StaleWhileRevalidate(secs) => {
+                    return write!(f, "stale-while-revalidate={}", secs)
+                }
+                Immutable => "immutable",
 
                 Extension(ref name, None) => &name[..],
                 Extension(ref name, Some(ref arg)) => {
@@ -168,17 +178,22 @@ impl FromStr for CacheDirective {
             "public" => Ok(Public),
             "private" => Ok(Private),
             "proxy-revalidate" => Ok(ProxyRevalidate),
+            "immutable" => Ok(Immutable),
             "" => Err(None),
             _ => match s.find('=') {
                 Some(idx) if idx + 1 < s.len() => {
-                    match (&s[..idx], (&s[idx + 1..]).trim_matches('"')) {
-                        ("max-age", secs) => secs.parse().map(MaxAge).map_err(Some),
-                        ("max-stale", secs) => secs.parse().map(MaxStale).map_err(Some),
-                        ("min-fresh", secs) => secs.parse().map(MinFresh).map_err(Some),
-                        ("s-maxage", secs) => secs.parse().map(SMaxAge).map_err(Some),
-                        (left, right) => {
-                            Ok(Extension(left.to_owned(), Some(right.to_owned())))
+                    let (name, arg) = (&s[..idx], &s[idx + 1..]);
+                    let secs = arg.trim_matches('"');
+
+                    match name {
+                        "max-age" => secs.parse().map(MaxAge).map_err(Some),
+                        "max-stale" => secs.parse().map(MaxStale).map_err(Some),
+                        "min-fresh" => secs.parse().map(MinFresh).map_err(Some),
+                        "s-maxage" => secs.parse().map(SMaxAge).map_err(Some),
+                        "stale-while-revalidate" => {
+                            secs.parse().map(StaleWhileRevalidate).map_err(Some)
                         }
+                        _ => Ok(Extension(name.to_owned(), Some(arg.to_owned()))),
                     }
                 }
                 Some(_) => Err(None),
@@ -251,6 +266,52 @@ mod tests {
         )
     }
 
+    #[test]
+    fn test_parse_extension_verbatim() {
+        let req = TestRequest::default()
+            .insert_header((header::CACHE_CONTROL, "private, community=\"UCI\""))
+            .finish();
+        let cache: CacheControl = Header::parse(&req).unwrap();
+        assert_eq!(
+            cache,
+            CacheControl(vec![
+                CacheDirective::Private,
+                CacheDirective::Extension(
+                    "community".to_owned(),
+                    Some("\"UCI\"".to_owned())
+                ),
+            ])
+        );
+        assert_eq!(cache.to_string(), "private, community=\"UCI\"");
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let value = "public, max-age=60, s-maxage=120, stale-while-revalidate=30, \
+            must-revalidate, immutable, no-store, x-custom";
+
+        let req = TestRequest::default()
+            .insert_header((header::CACHE_CONTROL, value))
+            .finish();
+        let cache: CacheControl = Header::parse(&req).unwrap();
+        assert_eq!(
+            cache,
+            CacheControl(vec![
+                CacheDirective::Public,
+                CacheDirective::MaxAge(60),
+                CacheDirective::SMaxAge(120),
+                CacheDirective::StaleWhileRevalidate(30),
+                CacheDirective::MustRevalidate,
+                CacheDirective::Immutable,
+                CacheDirective::NoStore,
+                CacheDirective::Extension("x-custom".to_owned(), None),
+            ])
+        );
+
+        let header = cache.try_into_value().unwrap();
+        assert_eq!(header, value);
+    }
+
     #[test]
     fn test_parse_bad_syntax() {
         let req = TestRequest::default()
@@ -0,0 +1,506 @@
+use std::fmt::{self, Write};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::str::FromStr;
+
+use http::header;
+
+use crate::error::ParseError;
+use crate::header::{fmt_comma_delimited, Header, IntoHeaderValue, Writer};
+
+/// `Forwarded` header, defined in [RFC7239](https://tools.ietf.org/html/rfc7239)
+///
+/// The `Forwarded` header field contains information from the client-facing side of proxy servers
+/// that is altered or lost when a proxy is involved in the path of the request. Each proxy appends
+/// an element to the list, so the first element describes the hop closest to the client.
+///
+/// Parameters other than `for`, `by`, `host` and `proto` are ignored.
+///
+/// # ABNF
+///
+/// ```text
+/// Forwarded         = 1#forwarded-element
+/// forwarded-element = [ forwarded-pair ] *( ";" [ forwarded-pair ] )
+/// forwarded-pair    = token "=" value
+/// value             = token / quoted-string
+/// ```
+///
+/// # Example values
+///
+/// * `for="_gazonk"`
+/// * `For="[2001:db8:cafe::17]:4711"`
+/// * `for=192.0.2.60;proto=http;by=203.0.113.43`
+/// * `for=192.0.2.43, for=198.51.100.17`
+///
+/// # Examples
+/// ```
+/// use std::net::{IpAddr, Ipv4Addr};
+/// use actix_http::http::header::{Forwarded, ForwardedElement, ForwardedNode};
+///
+/// let forwarded: Forwarded = "for=192.0.2.60;proto=https, for=198.51.100.17".parse().unwrap();
+/// let client = forwarded.first().unwrap();
+/// assert_eq!(
+///     client.for_node.as_ref().and_then(ForwardedNode::ip),
+///     Some(IpAddr::V4(Ipv4Addr::new(192, 0, 2, 60)))
+/// );
+/// assert_eq!(client.proto.as_deref(), Some("https"));
+/// ```
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct Forwarded(pub Vec<ForwardedElement>);
+
+__hyper__deref!(Forwarded => Vec<ForwardedElement>);
+
+impl Header for Forwarded {
+    fn name() -> header::HeaderName {
+        header::FORWARDED
+    }
+
+    #[inline]
+    fn parse<T>(msg: &T) -> Result<Self, ParseError>
+    where
+        T: crate::HttpMessage,
+    {
+        let mut elements = Vec::new();
+
+        for hdr in msg.headers().get_all(Self::name()) {
+            let val = hdr.to_str().map_err(|_| ParseError::Header)?;
+            parse_elements(val, &mut elements)?;
+        }
+
+        if !elements.is_empty() {
+            Ok(Forwarded(elements))
+        } else {
+            Err(ParseError::Header)
+        }
+    }
+}
+
+impl FromStr for Forwarded {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut elements = Vec::new();
+        parse_elements(s, &mut elements)?;
+
+        if !elements.is_empty() {
+            Ok(Forwarded(elements))
+        } else {
+            Err(ParseError::Header)
+        }
+    }
+}
+
+impl fmt::Display for Forwarded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt_comma_delimited(f, &self[..])
+    }
+}
+
+impl IntoHeaderValue for Forwarded {
+    type Error = header::InvalidHeaderValue;
+
+    fn try_into_value(self) -> Result<header::HeaderValue, Self::Error> {
+        let mut writer = Writer::new();
+        let _ = write!(&mut writer, "{}", self);
+        header::HeaderValue::from_maybe_shared(writer.take())
+    }
+}
+
+/// A single hop in a [`Forwarded`] header.
+#[derive(PartialEq, Eq, Clone, Debug, Default)]
+pub struct ForwardedElement {
+    /// Node making the request to the proxy (`for` parameter).
+    pub for_node: Option<ForwardedNode>,
+    /// Interface where the request came in to the proxy (`by` parameter).
+    pub by: Option<ForwardedNode>,
+    /// Host request header field as received by the proxy (`host` parameter).
+    pub host: Option<String>,
+    /// Protocol used to make the request (`proto` parameter).
+    pub proto: Option<String>,
+}
+
+impl fmt::Display for ForwardedElement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut sep = "";
+
+        if let Some(ref node) = self.for_node {
+            f.write_str("for=")?;
+            fmt_value(f, &node.to_string())?;
+            sep = ";";
+        }
+
+        if let Some(ref node) = self.by {
+            write!(f, "{}by=", sep)?;
+            fmt_value(f, &node.to_string())?;
+            sep = ";";
+        }
+
+        if let Some(ref host) = self.host {
+            write!(f, "{}host=", sep)?;
+            fmt_value(f, host)?;
+            sep = ";";
+        }
+
+        if let Some(ref proto) = self.proto {
+            write!(f, "{}proto=", sep)?;
+            fmt_value(f, proto)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Node identifier used in the `for` and `by` parameters of a [`Forwarded`] element.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct ForwardedNode {
+    /// Node name.
+    pub name: ForwardedNodeName,
+    /// Optional node port.
+    pub port: Option<ForwardedNodePort>,
+}
+
+impl ForwardedNode {
+    /// Returns the IP address of the node, if it is not hidden.
+    pub fn ip(&self) -> Option<IpAddr> {
+        match self.name {
+            ForwardedNodeName::Ip(ip) => Some(ip),
+            _ => None,
+        }
+    }
+
+    /// Returns the socket address of the node, if both its IP address and port are known.
+    pub fn socket_addr(&self) -> Option<SocketAddr> {
+        match (&self.name, &self.port) {
+            (ForwardedNodeName::Ip(ip), Some(ForwardedNodePort::Port(port))) => {
+                Some(SocketAddr::new(*ip, *port))
+            }
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for ForwardedNode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.name {
+            ForwardedNodeName::Ip(IpAddr::V4(ip)) => write!(f, "{}", ip)?,
+            ForwardedNodeName::Ip(IpAddr::V6(ip)) => write!(f, "[{}]", ip)?,
+            ForwardedNodeName::Unknown => f.write_str("unknown")?,
+            ForwardedNodeName::Obfuscated(ref name) => f.write_str(name)?,
+        }
+
+        match self.port {
+            Some(ForwardedNodePort::Port(port)) => write!(f, ":{}", port),
+            Some(ForwardedNodePort::Obfuscated(ref port)) => write!(f, ":{}", port),
+            None => Ok(()),
+        }
+    }
+}
+
+impl FromStr for ForwardedNode {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, port) = if let Some(rest) = s.strip_prefix('[') {
+            let end = rest.find(']').ok_or(ParseError::Header)?;
+            let ip = rest[..end]
+                .parse::<Ipv6Addr>()
+                .map_err(|_| ParseError::Header)?;
+
+            let port = match &rest[end + 1..] {
+                "" => None,
+                port => Some(port.strip_prefix(':').ok_or(ParseError::Header)?),
+            };
+
+            (ForwardedNodeName::Ip(IpAddr::V6(ip)), port)
+        } else {
+            let mut parts = s.splitn(2, ':');
+            let name = match parts.next().unwrap_or("") {
+                "unknown" => ForwardedNodeName::Unknown,
+                name if is_obfuscated(name) => ForwardedNodeName::Obfuscated(name.to_owned()),
+                name => ForwardedNodeName::Ip(IpAddr::V4(
+                    name.parse::<Ipv4Addr>().map_err(|_| ParseError::Header)?,
+                )),
+            };
+
+            (name, parts.next())
+        };
+
+        let port = match port {
+            None => None,
+            Some(port) if is_obfuscated(port) => {
+                Some(ForwardedNodePort::Obfuscated(port.to_owned()))
+            }
+            Some(port) if !port.is_empty() && port.bytes().all(|b| b.is_ascii_digit()) => {
+                Some(ForwardedNodePort::Port(
+                    port.parse().map_err(|_| ParseError::Header)?,
+                ))
+            }
+            Some(_) => return Err(ParseError::Header),
+        };
+
+        Ok(ForwardedNode { name, port })
+    }
+}
+
+/// Name part of a [`ForwardedNode`].
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub enum ForwardedNodeName {
+    /// An IPv4 or IPv6 address.
+    Ip(IpAddr),
+    /// "unknown"; the proxy does not know the identity of the node.
+    Unknown,
+    /// An obfuscated identifier starting with an underscore, e.g. "_hidden".
+    Obfuscated(String),
+}
+
+/// Port part of a [`ForwardedNode`].
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub enum ForwardedNodePort {
+    /// A port number.
+    Port(u16),
+    /// An obfuscated port starting with an underscore, e.g. "_8080".
+    Obfuscated(String),
+}
+
+fn is_obfuscated(s: &str) -> bool {
+    s.len() > 1
+        && s.starts_with('_')
+        && s.bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b == b'.' || b == b'_' || b == b'-')
+}
+
+fn is_tchar(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b"!#$%&'*+-.^_`|~".contains(&b)
+}
+
+fn is_ows(c: char) -> bool {
+    c == ' ' || c == '\t'
+}
+
+/// Writes a value as a token, or as a quoted-string if it contains other characters.
+fn fmt_value(f: &mut fmt::Formatter<'_>, val: &str) -> fmt::Result {
+    if !val.is_empty() && val.bytes().all(is_tchar) {
+        return f.write_str(val);
+    }
+
+    f.write_char('"')?;
+    for c in val.chars() {
+        if c == '"' || c == '\\' {
+            f.write_char('\\')?;
+        }
+        f.write_char(c)?;
+    }
+    f.write_char('"')
+}
+
+/// Parses a comma-separated list of elements, appending them to `elements`.
+///
+/// Empty list items are skipped. Any malformed element fails the whole value.
+fn parse_elements(s: &str, elements: &mut Vec<ForwardedElement>) -> Result<(), ParseError> {
+    let mut rest = s;
+
+    loop {
+        let mut el = ForwardedElement::default();
+        let mut has_pairs = false;
+
+        // parse `;` separated pairs of one element
+        loop {
+            rest = rest.trim_start_matches(is_ows);
+
+            if !rest.is_empty() && !rest.starts_with(',') && !rest.starts_with(';') {
+                let (name, value, remaining) = parse_pair(rest)?;
+                rest = remaining;
+                has_pairs = true;
+
+                match name.to_ascii_lowercase().as_str() {
+                    "for" if el.for_node.is_none() => el.for_node = Some(value.parse()?),
+                    "by" if el.by.is_none() => el.by = Some(value.parse()?),
+                    "host" if el.host.is_none() => el.host = Some(value),
+                    "proto" if el.proto.is_none() => el.proto = Some(value),
+
+                    // parameters must not occur more than once in an element
+                    "for" | "by" | "host" | "proto" => return Err(ParseError::Header),
+
+                    _ => {}
+                }
+
+                rest = rest.trim_start_matches(is_ows);
+            }
+
+            match rest.chars().next() {
+                Some(';') => rest = &rest[1..],
+                Some(',') | None => break,
+                Some(_) => return Err(ParseError::Header),
+            }
+        }
+
+        if has_pairs {
+            elements.push(el);
+        }
+
+        match rest.chars().next() {
+            Some(',') => rest = &rest[1..],
+            None => return Ok(()),
+            Some(_) => return Err(ParseError::Header),
+        }
+    }
+}
+
+/// Parses a `token "=" value` pair, returning the name, unquoted value and remaining input.
+fn parse_pair(s: &str) -> Result<(&str, String, &str), ParseError> {
+    let name_len = s.bytes().take_while(|b| is_tchar(*b)).count();
+    if name_len == 0 || !s[name_len..].starts_with('=') {
+        return Err(ParseError::Header);
+    }
+
+    let name = &s[..name_len];
+    let s = &s[name_len + 1..];
+
+    if let Some(quoted) = s.strip_prefix('"') {
+        let mut value = String::new();
+        let mut chars = quoted.char_indices();
+
+        while let Some((idx, c)) = chars.next() {
+            match c {
+                '"' => return Ok((name, value, &quoted[idx + 1..])),
+                '\\' => match chars.next() {
+                    Some((_, c)) => value.push(c),
+                    None => return Err(ParseError::Header),
+                },
+                c => value.push(c),
+            }
+        }
+
+        // unterminated quoted-string
+        Err(ParseError::Header)
+    } else {
+        // be lenient with unquoted values, which commonly contain `:` for ports
+        let len = s
+            .find(|c: char| c == ',' || c == ';' || c == '"' || is_ows(c))
+            .unwrap_or(s.len());
+
+        if len == 0 {
+            return Err(ParseError::Header);
+        }
+
+        Ok((name, s[..len].to_owned(), &s[len..]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::TestRequest;
+
+    fn node(s: &str) -> ForwardedNode {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_rfc_examples() {
+        let fwd: Forwarded = "for=\"_gazonk\"".parse().unwrap();
+        assert_eq!(
+            fwd.0,
+            vec![ForwardedElement {
+                for_node: Some(ForwardedNode {
+                    name: ForwardedNodeName::Obfuscated("_gazonk".to_owned()),
+                    port: None,
+                }),
+                ..Default::default()
+            }]
+        );
+
+        let fwd: Forwarded = "For=\"[2001:db8:cafe::17]:4711\"".parse().unwrap();
+        let for_node = fwd[0].for_node.as_ref().unwrap();
+        assert_eq!(
+            for_node.socket_addr(),
+            Some("[2001:db8:cafe::17]:4711".parse().unwrap())
+        );
+        assert_eq!(for_node.to_string(), "[2001:db8:cafe::17]:4711");
+
+        let fwd: Forwarded = "for=192.0.2.60;proto=http;by=203.0.113.43".parse().unwrap();
+        assert_eq!(
+            fwd.0,
+            vec![ForwardedElement {
+                for_node: Some(node("192.0.2.60")),
+                by: Some(node("203.0.113.43")),
+                host: None,
+                proto: Some("http".to_owned()),
+            }]
+        );
+
+        let fwd: Forwarded = "for=192.0.2.43, for=198.51.100.17".parse().unwrap();
+        assert_eq!(fwd.len(), 2);
+        assert_eq!(fwd[0].for_node, Some(node("192.0.2.43")));
+        assert_eq!(fwd[1].for_node, Some(node("198.51.100.17")));
+
+        let fwd: Forwarded = "for=unknown, for=\"_hidden:_8080\"".parse().unwrap();
+        assert_eq!(fwd[0].for_node.as_ref().unwrap().name, ForwardedNodeName::Unknown);
+        assert_eq!(
+            fwd[1].for_node,
+            Some(ForwardedNode {
+                name: ForwardedNodeName::Obfuscated("_hidden".to_owned()),
+                port: Some(ForwardedNodePort::Obfuscated("_8080".to_owned())),
+            })
+        );
+    }
+
+    #[test]
+    fn test_quoted_values() {
+        let fwd: Forwarded = "host=\"ex\\\"ample.com:8080\" ; proto=https ,, for=\"10.0.0.1\""
+            .parse()
+            .unwrap();
+        assert_eq!(fwd.len(), 2);
+        assert_eq!(fwd[0].host.as_deref(), Some("ex\"ample.com:8080"));
+        assert_eq!(fwd[0].proto.as_deref(), Some("https"));
+        assert_eq!(fwd[1].for_node, Some(node("10.0.0.1")));
+    }
+
+    #[test]
+    fn test_multiple_headers() {
+        let req = TestRequest::default()
+            .append_header((header::FORWARDED, "for=192.0.2.43"))
+            .append_header((header::FORWARDED, "for=198.51.100.17;secret=foo"))
+            .finish();
+
+        let fwd: Forwarded = Header::parse(&req).unwrap();
+        assert_eq!(fwd.len(), 2);
+        assert_eq!(fwd[1].for_node, Some(node("198.51.100.17")));
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let value = "for=192.0.2.60;by=\"[2001:db8::1]:80\";host=rust-lang.org;proto=https, \
+            for=_hidden";
+
+        let fwd: Forwarded = value.parse().unwrap();
+        assert_eq!(fwd.to_string(), value);
+        assert_eq!(fwd.try_into_value().unwrap(), value);
+    }
+
+    #[test]
+    fn test_malformed() {
+        for value in &[
+            "",
+            ",",
+            "for",
+            "for=",
+            "=192.0.2.60",
+            "for=\"192.0.2.60",
+            "for=\"192.0.2.60\\",
+            "for=192.0.2.60 proto=http",
+            "for=192.0.2.60;for=192.0.2.61",
+            "for=\"[2001:db8:cafe::17\"",
+            "for=\"[2001:db8:cafe::17]4711\"",
+            "for=\"[2001:db8:cafe::17]:\"",
+            "for=\"2001:db8:cafe::17\"",
+            "for=192.0.2.60:99999",
+            "for=192.0.2.60:http",
+            "for=_",
+            "for=_hid!den",
+            "for=example.com",
+            "for=\"\u{1F600}\"",
+        ] {
+            assert!(value.parse::<Forwarded>().is_err(), "{:?} should fail", value);
+        }
+    }
+}
@@ -23,6 +23,9 @@ pub use self::content_type::ContentType;
 pub use self::date::Date;
 pub use self::etag::ETag;
 pub use self::expires::Expires;
+pub use self::forwarded::{
+    Forwarded, ForwardedElement, ForwardedNode, ForwardedNodeName, ForwardedNodePort,
+};
 pub use self::if_match::IfMatch;
 pub use self::if_modified_since::IfModifiedSince;
 pub use self::if_none_match::IfNoneMatch;
@@ -347,6 +350,7 @@ mod content_type;
 mod date;
 mod etag;
 mod expires;
+mod forwarded;
 mod if_match;
 mod if_modified_since;
 mod if_none_match;
@@ -12,6 +12,13 @@ pub trait IntoHeaderPair: Sized {
     type Error: Into<HttpError>;
 
     fn try_into_header_pair(self) -> Result<(HeaderName, HeaderValue), Self::Error>;
+
+    /// Returns the header name as it was given, before it is normalized to lowercase.
+    ///
+    /// Returns `None` if the original casing is not known.
+    fn original_name(&self) -> Option<&str> {
+        None
+    }
 }
 
 #[derive(Debug)]
@@ -76,6 +83,9 @@ where
             .map_err(|err| InvalidHeaderPart::Value(err.into()))?;
         Ok((name, value))
     }
+    fn original_name(&self) -> Option<&str> {
+        std::str::from_utf8(self.0).ok()
+    }
 }
 
 impl<V> IntoHeaderPair for (&str, V)
@@ -93,6 +103,9 @@ where
             .map_err(|err| InvalidHeaderPart::Value(err.into()))?;
         Ok((name, value))
     }
+    fn original_name(&self) -> Option<&str> {
+        Some(self.0)
+    }
 }
 
 impl<V> IntoHeaderPair for (String, V)
@@ -106,6 +119,9 @@ where
         let (name, value) = self;
         (name.as_str(), value).try_into_header_pair()
     }
+    fn original_name(&self) -> Option<&str> {
+        Some(&self.0)
+    }
 }
 
 impl<T: Header> IntoHeaderPair for T {
@@ -12,9 +12,11 @@ use crate::error::ParseError;
 use crate::HttpMessage;
 
 mod as_name;
+mod case;
 mod into_pair;
 mod into_value;
 mod utils;
+pub(crate) mod vary;
 
 mod common;
 pub(crate) mod map;
@@ -25,12 +27,14 @@ pub use self::common::*;
 pub use self::shared::*;
 
 pub use self::as_name::AsHeaderName;
+pub use self::case::HeaderCaseMap;
 pub use self::into_pair::IntoHeaderPair;
 pub use self::into_value::IntoHeaderValue;
 #[doc(hidden)]
 pub use self::map::GetAll;
 pub use self::map::HeaderMap;
 pub use self::utils::*;
+pub use self::vary::VaryHeaders;
 
 /// A trait for any object that already represents a valid header field and value.
 pub trait Header: IntoHeaderValue {
@@ -76,6 +80,17 @@ impl From<http::HeaderMap> for HeaderMap {
     }
 }
 
+/// Convert our `HeaderMap` to `http::HeaderMap`.
+impl From<HeaderMap> for http::HeaderMap {
+    fn from(map: HeaderMap) -> http::HeaderMap {
+        let mut res = http::HeaderMap::with_capacity(map.len());
+        for (name, val) in map {
+            res.append(name, val);
+        }
+        res
+    }
+}
+
 /// This encode set is used for HTTP header values and is defined at
 /// https://tools.ietf.org/html/rfc5987#section-3.2.
 pub(crate) const HTTP_VALUE: &AsciiSet = &CONTROLS
@@ -0,0 +1,158 @@
+use http::header::{HeaderName, HeaderValue, VARY};
+
+use crate::message::ResponseHead;
+
+/// Request headers a response varies on.
+///
+/// Middleware that makes a response depend on a request header should record it here, in the
+/// response extensions, instead of setting `Vary` directly. When the response head is encoded,
+/// the recorded names are merged with any `Vary` header already on the response, without
+/// duplicates. Recording `*` (or a `Vary: *` header set by the handler) replaces the whole list.
+#[derive(Debug, Clone, Default)]
+pub struct VaryHeaders {
+    names: Vec<HeaderName>,
+    any: bool,
+}
+
+impl VaryHeaders {
+    /// Create an empty list.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record that the response varies on request header `name`.
+    pub fn add(&mut self, name: HeaderName) {
+        if !self.names.contains(&name) {
+            self.names.push(name);
+        }
+    }
+
+    /// Record that the response varies on more than request headers, i.e. `Vary: *`.
+    pub fn add_any(&mut self) {
+        self.any = true;
+    }
+
+    /// Returns the recorded header names.
+    pub fn names(&self) -> &[HeaderName] {
+        &self.names
+    }
+
+    /// Returns true if `*` was recorded.
+    pub fn is_any(&self) -> bool {
+        self.any
+    }
+}
+
+/// Merges the `VaryHeaders` in the extensions of `head`, if any, into its `Vary` header.
+pub(crate) fn finalize(head: &mut ResponseHead) {
+    let vary = match head.extensions_mut().remove::<VaryHeaders>() {
+        Some(vary) => vary,
+        None => return,
+    };
+
+    let mut any = vary.any;
+    let mut names = Vec::<String>::new();
+
+    let existing = head
+        .headers
+        .get_all(VARY)
+        .filter_map(|val| val.to_str().ok())
+        .flat_map(|val| val.split(','))
+        .map(str::trim)
+        .filter(|name| !name.is_empty());
+
+    for name in existing.chain(vary.names.iter().map(HeaderName::as_str)) {
+        if name == "*" {
+            any = true;
+            break;
+        }
+
+        if !names.iter().any(|n| n.eq_ignore_ascii_case(name)) {
+            names.push(name.to_owned());
+        }
+    }
+
+    let value = if any {
+        HeaderValue::from_static("*")
+    } else if names.is_empty() {
+        return;
+    } else {
+        match HeaderValue::from_str(&names.join(", ")) {
+            Ok(value) => value,
+            Err(_) => return,
+        }
+    };
+
+    head.headers.insert(VARY, value);
+}
+
+#[cfg(test)]
+mod tests {
+    use http::{header::ACCEPT_ENCODING, StatusCode};
+
+    use super::*;
+
+    fn vary_of(head: &ResponseHead) -> Vec<&str> {
+        head.headers
+            .get_all(VARY)
+            .map(|val| val.to_str().unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_finalize_merges() {
+        let mut head = ResponseHead::new(StatusCode::OK);
+        head.headers
+            .append(VARY, HeaderValue::from_static("Origin, accept-encoding"));
+        head.headers
+            .append(VARY, HeaderValue::from_static("Accept"));
+
+        let mut vary = VaryHeaders::new();
+        vary.add(ACCEPT_ENCODING);
+        vary.add(HeaderName::from_static("origin"));
+        vary.add(HeaderName::from_static("x-tenant"));
+        vary.add(HeaderName::from_static("x-tenant"));
+        head.extensions_mut().insert(vary);
+
+        finalize(&mut head);
+        assert_eq!(
+            vary_of(&head),
+            ["Origin, accept-encoding, Accept, x-tenant"]
+        );
+        assert!(!head.extensions().contains::<VaryHeaders>());
+    }
+
+    #[test]
+    fn test_finalize_any() {
+        let mut head = ResponseHead::new(StatusCode::OK);
+        head.headers.insert(VARY, HeaderValue::from_static("*"));
+
+        let mut vary = VaryHeaders::new();
+        vary.add(ACCEPT_ENCODING);
+        head.extensions_mut().insert(vary);
+
+        finalize(&mut head);
+        assert_eq!(vary_of(&head), ["*"]);
+
+        let mut head = ResponseHead::new(StatusCode::OK);
+        head.headers
+            .insert(VARY, HeaderValue::from_static("Origin"));
+
+        let mut vary = VaryHeaders::new();
+        vary.add_any();
+        head.extensions_mut().insert(vary);
+
+        finalize(&mut head);
+        assert_eq!(vary_of(&head), ["*"]);
+    }
+
+    #[test]
+    fn test_finalize_without_extension() {
+        let mut head = ResponseHead::new(StatusCode::OK);
+        head.headers
+            .insert(VARY, HeaderValue::from_static("Origin"));
+
+        finalize(&mut head);
+        assert_eq!(vary_of(&head), ["Origin"]);
+    }
+}
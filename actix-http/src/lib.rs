@@ -9,11 +9,13 @@
 //! | `cookies`        | Support for cookies backed by the [cookie] crate.     |
 //! | `secure-cookies` | Adds for secure cookies. Enables `cookies` feature.   |
 //! | `trust-dns`      | Use [trust-dns] as the client DNS resolver.           |
+//! | `tracing`        | Connection spans for the [tracing] crate.             |
 //!
 //! [OpenSSL]: https://crates.io/crates/openssl
 //! [rustls]: https://crates.io/crates/rustls
 //! [cookie]: https://crates.io/crates/cookie
 //! [trust-dns]: https://crates.io/crates/trust-dns
+//! [tracing]: https://crates.io/crates/tracing
 
 #![deny(rust_2018_idioms, nonstandard_style)]
 #![allow(
@@ -47,6 +49,10 @@ mod payload;
 mod request;
 mod response;
 mod service;
+mod shutdown;
+mod sniff;
+mod span;
+mod stats;
 mod time_parser;
 
 pub mod error;
@@ -55,6 +61,8 @@ pub mod h2;
 pub mod test;
 pub mod ws;
 
+use std::rc::Rc;
+
 #[cfg(feature = "cookies")]
 pub use cookie;
 
@@ -68,6 +76,8 @@ pub use self::payload::{Payload, PayloadStream};
 pub use self::request::Request;
 pub use self::response::{Response, ResponseBuilder};
 pub use self::service::HttpService;
+pub use self::shutdown::GracefulShutdown;
+pub use self::stats::ConnectionStats;
 
 pub mod http {
     //! Various HTTP related types.
@@ -81,11 +91,18 @@ pub mod http {
     #[cfg(feature = "cookies")]
     pub use crate::cookie::{Cookie, CookieBuilder};
     pub use crate::header::HeaderMap;
+    #[cfg(feature = "cookies")]
+    pub use crate::response::{CookieDefaults, CookieDeletion, IntoRemovalCookie};
 
     /// A collection of HTTP headers and helpers.
     pub mod header {
         pub use crate::header::*;
     }
+
+    /// Content type detection from the first bytes of a body.
+    pub mod sniff {
+        pub use crate::sniff::*;
+    }
     pub use crate::header::ContentEncoding;
     pub use crate::message::ConnectionType;
 }
@@ -104,14 +121,10 @@ type ConnectCallback<IO> = dyn Fn(&IO, &mut Extensions);
 /// Container for data that extract with ConnectCallback.
 ///
 /// # Implementation Details
-/// Uses Option to reduce necessary allocations when merging with request extensions.
-pub(crate) struct OnConnectData(Option<Extensions>);
-
-impl Default for OnConnectData {
-    fn default() -> Self {
-        Self(None)
-    }
-}
+/// Uses Option to reduce necessary allocations when no callback is registered. The container is
+/// reference counted so that it can be shared by all requests on a connection.
+#[derive(Default)]
+pub(crate) struct OnConnectData(Option<Rc<Extensions>>);
 
 impl OnConnectData {
     /// Construct by calling the on-connect callback with the underlying transport I/O.
@@ -122,17 +135,15 @@ impl OnConnectData {
         let ext = on_connect_ext.map(|handler| {
             let mut extensions = Extensions::new();
             handler(io, &mut extensions);
-            extensions
+            Rc::new(extensions)
         });
 
         Self(ext)
     }
 
-    /// Merge self into given request's extensions.
+    /// Attach connection data to the given request.
     #[inline]
-    pub(crate) fn merge_into(&mut self, req: &mut Request) {
-        if let Some(ref mut ext) = self.0 {
-            req.head.extensions.get_mut().drain_from(ext);
-        }
+    pub(crate) fn merge_into(&self, req: &mut Request) {
+        req.conn_data = self.0.as_ref().map(Rc::clone);
     }
 }
@@ -69,6 +69,7 @@ impl Default for RequestHead {
 impl Head for RequestHead {
     fn clear(&mut self) {
         self.flags = Flags::empty();
+        self.uri = Uri::default();
         self.headers.clear();
         self.extensions.get_mut().clear();
     }
@@ -373,6 +374,11 @@ impl<T: Head> std::ops::DerefMut for Message<T> {
 
 impl<T: Head> Drop for Message<T> {
     fn drop(&mut self) {
+        // clear head before pooling so it does not keep parts of the connection's read
+        // buffer alive, which would prevent the buffer from reusing its memory
+        if let Some(head) = Rc::get_mut(&mut self.head) {
+            head.clear();
+        }
         T::with_pool(|p| p.release(self.head.clone()))
     }
 }
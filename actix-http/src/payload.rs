@@ -3,6 +3,7 @@ use std::task::{Context, Poll};
 
 use bytes::Bytes;
 use futures_core::Stream;
+use futures_util::{future, stream};
 use h2::RecvStream;
 
 use crate::error::PayloadError;
@@ -42,6 +43,15 @@ impl From<PayloadStream> for Payload {
     }
 }
 
+impl Payload {
+    /// Creates a payload that yields `bytes` as a single chunk.
+    ///
+    /// Useful for replacing a request payload that has already been read into memory.
+    pub fn from_bytes(bytes: Bytes) -> Self {
+        Payload::Stream(Box::pin(stream::once(future::ready(Ok(bytes)))))
+    }
+}
+
 impl<S> Payload<S> {
     /// Takes current payload and replaces it with `None` value
     pub fn take(&mut self) -> Payload<S> {
@@ -68,3 +78,17 @@ where
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use futures_util::StreamExt;
+
+    use super::*;
+
+    #[actix_rt::test]
+    async fn test_from_bytes() {
+        let mut pl = Payload::from_bytes(Bytes::from_static(b"data"));
+        assert_eq!(pl.next().await.unwrap().unwrap(), "data");
+        assert!(pl.next().await.is_none());
+    }
+}
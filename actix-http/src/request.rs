@@ -2,7 +2,8 @@
 
 use std::{
     cell::{Ref, RefMut},
-    fmt, net,
+    fmt, mem, net,
+    rc::Rc,
 };
 
 use http::{header, Method, Uri, Version};
@@ -17,6 +18,7 @@ use crate::HttpMessage;
 pub struct Request<P = PayloadStream> {
     pub(crate) payload: Payload<P>,
     pub(crate) head: Message<RequestHead>,
+    pub(crate) conn_data: Option<Rc<Extensions>>,
 }
 
 impl<P> HttpMessage for Request<P> {
@@ -49,6 +51,7 @@ impl From<Message<RequestHead>> for Request<PayloadStream> {
         Request {
             head,
             payload: Payload::None,
+            conn_data: None,
         }
     }
 }
@@ -59,6 +62,7 @@ impl Request<PayloadStream> {
         Request {
             head: Message::new(),
             payload: Payload::None,
+            conn_data: None,
         }
     }
 }
@@ -69,6 +73,7 @@ impl<P> Request<P> {
         Request {
             payload,
             head: Message::new(),
+            conn_data: None,
         }
     }
 
@@ -79,6 +84,7 @@ impl<P> Request<P> {
             Request {
                 payload,
                 head: self.head,
+                conn_data: self.conn_data,
             },
             pl,
         )
@@ -168,6 +174,68 @@ impl<P> Request<P> {
     pub fn peer_addr(&self) -> Option<net::SocketAddr> {
         self.head().peer_addr
     }
+
+    /// Returns a reference to a piece of connection data set in an [on-connect] callback.
+    ///
+    /// Connection data is shared by all requests on the same connection and is kept separately
+    /// from the request extensions.
+    ///
+    /// [on-connect]: crate::HttpServiceBuilder::on_connect_ext
+    pub fn conn_data<T: 'static>(&self) -> Option<&T> {
+        self.conn_data
+            .as_deref()
+            .and_then(|container| container.get::<T>())
+    }
+
+    /// Returns the connection data container if an [on-connect] callback was registered.
+    ///
+    /// [on-connect]: crate::HttpServiceBuilder::on_connect_ext
+    pub fn take_conn_data(&mut self) -> Option<Rc<Extensions>> {
+        self.conn_data.take()
+    }
+}
+
+/// Converts an `http::Request`, using its body as the payload stream and keeping its extensions as
+/// an `http::Extensions` item in the request extensions.
+impl<P> From<http::Request<P>> for Request<P> {
+    fn from(req: http::Request<P>) -> Self {
+        let (parts, body) = req.into_parts();
+
+        let mut req = Request::with_payload(Payload::Stream(body));
+        let head = req.head_mut();
+        head.method = parts.method;
+        head.uri = parts.uri;
+        head.version = parts.version;
+        head.headers = parts.headers.into();
+        head.extensions.get_mut().insert(parts.extensions);
+        req
+    }
+}
+
+/// Converts to an `http::Request` with the payload as its body.
+///
+/// The `http::Extensions` item of the request extensions, as stored when converting from an
+/// `http::Request`, becomes the extensions of the `http::Request`. Other extensions, the peer
+/// address and connection data are not carried over.
+impl<P> From<Request<P>> for http::Request<Payload<P>> {
+    fn from(mut req: Request<P>) -> Self {
+        let payload = req.take_payload();
+        let head = req.head_mut();
+        let extensions = head
+            .extensions
+            .get_mut()
+            .remove::<http::Extensions>()
+            .unwrap_or_default();
+
+        let mut http_req = http::Request::new(payload);
+        *http_req.method_mut() = mem::replace(&mut head.method, Method::GET);
+        *http_req.uri_mut() = mem::take(&mut head.uri);
+        *http_req.version_mut() = head.version;
+        *http_req.headers_mut() =
+            mem::replace(&mut head.headers, HeaderMap::new()).into();
+        *http_req.extensions_mut() = extensions;
+        http_req
+    }
 }
 
 impl<P> fmt::Debug for Request<P> {
@@ -199,6 +267,52 @@ mod tests {
     use super::*;
     use std::convert::TryFrom;
 
+    #[test]
+    fn test_http_request_round_trip() {
+        let req = http::Request::builder()
+            .method(Method::PUT)
+            .uri("/upload?name=a")
+            .version(Version::HTTP_2)
+            .header("x-multi", "a")
+            .header("x-multi", "b")
+            .header(header::CONTENT_TYPE, "text/plain")
+            .extension("tag")
+            .body("body")
+            .unwrap();
+
+        let req = Request::from(req);
+        assert_eq!(req.method(), Method::PUT);
+        assert_eq!(req.uri(), "/upload?name=a");
+        assert_eq!(req.version(), Version::HTTP_2);
+        assert_eq!(
+            req.headers().get(header::CONTENT_TYPE).unwrap(),
+            "text/plain"
+        );
+        assert_eq!(
+            req.extensions().get::<http::Extensions>().unwrap().get(),
+            Some(&"tag")
+        );
+        req.extensions_mut().insert(7u32);
+
+        let req = http::Request::from(req);
+        assert_eq!(req.method(), Method::PUT);
+        assert_eq!(req.uri(), "/upload?name=a");
+        assert_eq!(req.version(), Version::HTTP_2);
+        assert_eq!(req.extensions().get(), Some(&"tag"));
+        // only extensions converted from an `http::Request` are carried back
+        assert!(req.extensions().get::<u32>().is_none());
+        assert_eq!(req.headers().len(), 3);
+        let mut multi = req
+            .headers()
+            .get_all("x-multi")
+            .iter()
+            .map(|val| val.to_str().unwrap())
+            .collect::<Vec<_>>();
+        multi.sort_unstable();
+        assert_eq!(multi, ["a", "b"]);
+        assert!(matches!(req.body(), Payload::Stream("body")));
+    }
+
     #[test]
     fn test_basics() {
         let msg = Message::new();
@@ -3,9 +3,10 @@
 use std::{
     cell::{Ref, RefMut},
     convert::TryInto,
+    error::Error as StdError,
     fmt,
     future::Future,
-    ops,
+    mem, ops,
     pin::Pin,
     str,
     task::{Context, Poll},
@@ -13,9 +14,13 @@ use std::{
 
 use bytes::{Bytes, BytesMut};
 use futures_core::Stream;
+use futures_util::TryStreamExt as _;
 use serde::Serialize;
 
-use crate::body::{Body, BodyStream, MessageBody, ResponseBody};
+use crate::body::{
+    Body, BodyStream, BoxBody, JsonStream, MessageBody, ResponseBody,
+    SerializeErrorPolicy, SizedStream, StreamErrorPolicy, STREAM_ERROR_TRAILER,
+};
 use crate::error::Error;
 use crate::extensions::Extensions;
 use crate::header::{IntoHeaderPair, IntoHeaderValue};
@@ -24,7 +29,7 @@ use crate::http::{Error as HttpError, HeaderMap, StatusCode};
 use crate::message::{BoxedResponseHead, ConnectionType, ResponseHead};
 #[cfg(feature = "cookies")]
 use crate::{
-    cookie::{Cookie, CookieJar},
+    cookie::{Cookie, CookieJar, SameSite},
     http::header::HeaderValue,
 };
 
@@ -273,6 +278,55 @@ impl<B> Response<B> {
     pub fn take_body(&mut self) -> ResponseBody<B> {
         self.body.take_body()
     }
+
+    /// Boxes the response body, erasing its type.
+    pub fn map_into_boxed_body(self) -> Response<BoxBody>
+    where
+        B: MessageBody + 'static,
+    {
+        self.map_body(|_, body| match body {
+            ResponseBody::Body(body) => ResponseBody::Body(body.boxed()),
+            ResponseBody::Other(body) => ResponseBody::Other(body),
+        })
+    }
+}
+
+/// Converts an `http::Response`, keeping its extensions as an `http::Extensions` item in the
+/// response extensions.
+impl<B> From<http::Response<B>> for Response<B> {
+    fn from(res: http::Response<B>) -> Self {
+        let (parts, body) = res.into_parts();
+
+        let mut res = Response::with_body(parts.status, body);
+        res.head.version = parts.version;
+        res.head.headers = parts.headers.into();
+        res.head.extensions.get_mut().insert(parts.extensions);
+        res
+    }
+}
+
+/// Converts to an `http::Response`.
+///
+/// The `http::Extensions` item of the response extensions, as stored when converting from an
+/// `http::Response`, becomes the extensions of the `http::Response`. Other extensions, the reason
+/// phrase and the response error are not carried over.
+impl<B> From<Response<B>> for http::Response<ResponseBody<B>> {
+    fn from(mut res: Response<B>) -> Self {
+        let head = &mut *res.head;
+        let extensions = head
+            .extensions
+            .get_mut()
+            .remove::<http::Extensions>()
+            .unwrap_or_default();
+        let headers = mem::replace(&mut head.headers, HeaderMap::new());
+
+        let mut http_res = http::Response::new(res.body);
+        *http_res.status_mut() = head.status;
+        *http_res.version_mut() = head.version;
+        *http_res.headers_mut() = headers.into();
+        *http_res.extensions_mut() = extensions;
+        http_res
+    }
 }
 
 impl<B: MessageBody> fmt::Debug for Response<B> {
@@ -325,6 +379,96 @@ impl<'a> Iterator for CookieIter<'a> {
     }
 }
 
+/// Path and domain of a cookie to be removed.
+///
+/// Browsers only delete a cookie when the removal cookie matches the path and domain the
+/// cookie was set with. Used together with a cookie name in [`ResponseBuilder::del_cookie`].
+#[cfg(feature = "cookies")]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CookieDeletion {
+    /// Path the cookie was set with.
+    pub path: Option<String>,
+
+    /// Domain the cookie was set with.
+    pub domain: Option<String>,
+}
+
+/// Types that identify a cookie to be removed by [`ResponseBuilder::del_cookie`].
+///
+/// Implemented for `&Cookie`, which uses the cookie's own path and domain, and for
+/// `(&str, CookieDeletion)` pairs of cookie name and explicit path and domain.
+#[cfg(feature = "cookies")]
+pub trait IntoRemovalCookie {
+    /// Convert into the cookie whose removal should be sent to the client.
+    fn into_removal_cookie(self) -> Cookie<'static>;
+}
+
+#[cfg(feature = "cookies")]
+impl IntoRemovalCookie for &Cookie<'_> {
+    fn into_removal_cookie(self) -> Cookie<'static> {
+        let mut cookie = Cookie::named(self.name().to_owned());
+
+        if let Some(path) = self.path() {
+            cookie.set_path(path.to_owned());
+        }
+
+        if let Some(domain) = self.domain() {
+            cookie.set_domain(domain.to_owned());
+        }
+
+        cookie
+    }
+}
+
+#[cfg(feature = "cookies")]
+impl IntoRemovalCookie for (&str, CookieDeletion) {
+    fn into_removal_cookie(self) -> Cookie<'static> {
+        let (name, deletion) = self;
+        let mut cookie = Cookie::named(name.to_owned());
+
+        if let Some(path) = deletion.path {
+            cookie.set_path(path);
+        }
+
+        if let Some(domain) = deletion.domain {
+            cookie.set_domain(domain);
+        }
+
+        cookie
+    }
+}
+
+/// Application-wide defaults applied by [`ResponseBuilder::cookie_with_defaults`].
+///
+/// Usually registered as app data and retrieved from the request when building a response.
+#[cfg(feature = "cookies")]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CookieDefaults {
+    same_site: Option<SameSite>,
+}
+
+#[cfg(feature = "cookies")]
+impl CookieDefaults {
+    /// Create empty cookie defaults.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the `SameSite` attribute used for cookies that do not set one explicitly.
+    pub fn same_site(mut self, same_site: SameSite) -> Self {
+        self.same_site = Some(same_site);
+        self
+    }
+
+    fn apply(&self, cookie: &mut Cookie<'_>) {
+        if cookie.same_site().is_none() {
+            if let Some(same_site) = self.same_site {
+                cookie.set_same_site(same_site);
+            }
+        }
+    }
+}
+
 /// An HTTP response builder.
 ///
 /// This type can be used to construct an instance of `Response` through a builder-like pattern.
@@ -358,6 +502,9 @@ impl ResponseBuilder {
 
     /// Insert a header, replacing any that were set with an equivalent field name.
     ///
+    /// Invalid header names or values do not panic; the error is stored and an
+    /// *Internal Server Error* response is produced when the response is built.
+    ///
     /// ```rust
     /// # use actix_http::Response;
     /// use actix_http::http::header::ContentType;
@@ -553,10 +700,43 @@ impl ResponseBuilder {
         self
     }
 
+    /// Set a cookie, filling in attributes it does not set from `defaults`.
+    ///
+    /// ```rust
+    /// use actix_http::{http, Response};
+    /// use actix_http::cookie::SameSite;
+    /// use actix_http::http::CookieDefaults;
+    ///
+    /// let defaults = CookieDefaults::new().same_site(SameSite::Lax);
+    ///
+    /// let res = Response::Ok()
+    ///     .cookie_with_defaults(http::Cookie::new("name", "value"), Some(&defaults))
+    ///     .finish();
+    ///
+    /// assert_eq!(res.cookies().next().unwrap().same_site(), Some(SameSite::Lax));
+    /// ```
+    #[cfg(feature = "cookies")]
+    pub fn cookie_with_defaults<'c>(
+        &mut self,
+        mut cookie: Cookie<'c>,
+        defaults: Option<&CookieDefaults>,
+    ) -> &mut Self {
+        if let Some(defaults) = defaults {
+            defaults.apply(&mut cookie);
+        }
+
+        self.cookie(cookie)
+    }
+
     /// Remove cookie
     ///
+    /// Sends an expired, empty cookie with `Max-Age=0`. The removal cookie keeps the path and
+    /// domain of the given cookie, or uses those from a [`CookieDeletion`], so that the client
+    /// matches it against the cookie it stores.
+    ///
     /// ```rust
     /// use actix_http::{http, Request, Response, HttpMessage};
+    /// use actix_http::http::CookieDeletion;
     ///
     /// fn index(req: Request) -> Response {
     ///     let mut builder = Response::Ok();
@@ -565,16 +745,24 @@ impl ResponseBuilder {
     ///         builder.del_cookie(cookie);
     ///     }
     ///
+    ///     builder.del_cookie((
+    ///         "session",
+    ///         CookieDeletion {
+    ///             path: Some("/app".to_owned()),
+    ///             domain: None,
+    ///         },
+    ///     ));
+    ///
     ///     builder.finish()
     /// }
     /// ```
     #[cfg(feature = "cookies")]
-    pub fn del_cookie<'a>(&mut self, cookie: &Cookie<'a>) -> &mut Self {
+    pub fn del_cookie(&mut self, cookie: impl IntoRemovalCookie) -> &mut Self {
         if self.cookies.is_none() {
             self.cookies = Some(CookieJar::new())
         }
         let jar = self.cookies.as_mut().unwrap();
-        let cookie = cookie.clone().into_owned();
+        let cookie = cookie.into_removal_cookie();
         jar.add_original(cookie.clone());
         jar.remove(cookie);
         self
@@ -664,11 +852,54 @@ impl ResponseBuilder {
     pub fn streaming<S, E>(&mut self, stream: S) -> Response
     where
         S: Stream<Item = Result<Bytes, E>> + Unpin + 'static,
-        E: Into<Error> + 'static,
+        E: Into<Box<dyn StdError>> + 'static,
     {
         self.body(Body::from_message(BodyStream::new(stream)))
     }
 
+    /// Set a streaming body of a known size and generate `Response`.
+    ///
+    /// Unlike [`streaming`](Self::streaming), the response is sent with a `Content-Length` instead
+    /// of chunked encoding. The stream must produce exactly `size` bytes, see [`SizedStream`].
+    ///
+    /// `ResponseBuilder` can not be used after this call.
+    pub fn streaming_sized<S, E>(&mut self, size: u64, stream: S) -> Response
+    where
+        S: Stream<Item = Result<Bytes, E>> + Unpin + 'static,
+        E: Into<Box<dyn StdError>> + 'static,
+    {
+        self.body(Body::from_message(SizedStream::new(
+            size,
+            stream.map_err(|err| Error::from(err.into())),
+        )))
+    }
+
+    /// Set a streaming body that handles stream errors according to `policy` and generate
+    /// `Response`.
+    ///
+    /// See [`StreamErrorPolicy`] for the available behaviors. [`streaming`](Self::streaming)
+    /// uses [`StreamErrorPolicy::Abort`]. With [`StreamErrorPolicy::Trailer`], the trailer is
+    /// announced in a `Trailer` header.
+    ///
+    /// `ResponseBuilder` can not be used after this call.
+    pub fn streaming_with<S, E>(
+        &mut self,
+        stream: S,
+        policy: StreamErrorPolicy,
+    ) -> Response
+    where
+        S: Stream<Item = Result<Bytes, E>> + Unpin + 'static,
+        E: Into<Box<dyn StdError>> + 'static,
+    {
+        if let StreamErrorPolicy::Trailer = policy {
+            self.append_header((header::TRAILER, STREAM_ERROR_TRAILER));
+        }
+
+        self.body(Body::from_message(BodyStream::with_error_policy(
+            stream, policy,
+        )))
+    }
+
     /// Set a json body and generate `Response`
     ///
     /// `ResponseBuilder` can not be used after this call.
@@ -679,22 +910,50 @@ impl ResponseBuilder {
     {
         match serde_json::to_string(&*value) {
             Ok(body) => {
-                let contains = if let Some(parts) = parts(&mut self.head, &self.err) {
-                    parts.headers.contains_key(header::CONTENT_TYPE)
-                } else {
-                    true
-                };
-
-                if !contains {
-                    self.insert_header(header::ContentType(mime::APPLICATION_JSON));
-                }
-
+                self.json_content_type();
                 self.body(Body::from(body))
             }
             Err(e) => Error::from(e).into(),
         }
     }
 
+    /// Set a streaming body that serializes each item of `stream` as an element of a JSON array
+    /// and generate `Response`.
+    ///
+    /// Items are written as they arrive using chunked transfer encoding, see [`JsonStream`]. An
+    /// item that fails to serialize aborts the response; use
+    /// [`json_stream_with`](Self::json_stream_with) to skip such items instead.
+    ///
+    /// `ResponseBuilder` can not be used after this call.
+    pub fn json_stream<S, T, E>(&mut self, stream: S) -> Response
+    where
+        S: Stream<Item = Result<T, E>> + Unpin + 'static,
+        T: Serialize + 'static,
+        E: Into<Error> + 'static,
+    {
+        self.json_stream_with(stream, SerializeErrorPolicy::Abort)
+    }
+
+    /// Set a streaming JSON array body that handles item serialization errors according to
+    /// `policy` and generate `Response`.
+    ///
+    /// `ResponseBuilder` can not be used after this call.
+    pub fn json_stream_with<S, T, E>(
+        &mut self,
+        stream: S,
+        policy: SerializeErrorPolicy,
+    ) -> Response
+    where
+        S: Stream<Item = Result<T, E>> + Unpin + 'static,
+        T: Serialize + 'static,
+        E: Into<Error> + 'static,
+    {
+        self.json_content_type();
+        self.body(Body::from_message(JsonStream::with_error_policy(
+            stream, policy,
+        )))
+    }
+
     #[inline]
     /// Set an empty body and generate `Response`
     ///
@@ -703,6 +962,19 @@ impl ResponseBuilder {
         self.body(Body::Empty)
     }
 
+    /// Sets the `Content-Type` to `application/json` unless one was already set.
+    fn json_content_type(&mut self) {
+        let contains = if let Some(parts) = parts(&mut self.head, &self.err) {
+            parts.headers.contains_key(header::CONTENT_TYPE)
+        } else {
+            true
+        };
+
+        if !contains {
+            self.insert_header(header::ContentType(mime::APPLICATION_JSON));
+        }
+    }
+
     /// This method construct new `ResponseBuilder`
     pub fn take(&mut self) -> ResponseBuilder {
         ResponseBuilder {
@@ -895,7 +1167,7 @@ mod tests {
     use serde_json::json;
 
     use super::*;
-    use crate::body::Body;
+    use crate::body::{Body, BodySize};
     use crate::http::header::{HeaderValue, CONTENT_TYPE, COOKIE};
     #[cfg(feature = "cookies")]
     use crate::{http::header::SET_COOKIE, HttpMessage};
@@ -948,6 +1220,64 @@ mod tests {
         );
     }
 
+    #[cfg(feature = "cookies")]
+    #[test]
+    fn test_del_cookie_path_domain() {
+        let cookie = crate::http::Cookie::build("name", "value")
+            .path("/app")
+            .domain("example.com")
+            .finish();
+
+        let resp = Response::Ok().del_cookie(&cookie).finish();
+        let val = resp.headers().get(SET_COOKIE).unwrap().to_str().unwrap();
+        assert!(
+            val.starts_with("name=; Path=/app; Domain=example.com; Max-Age=0; Expires=")
+        );
+
+        let resp = Response::Ok()
+            .del_cookie((
+                "session",
+                CookieDeletion {
+                    path: Some("/app".to_owned()),
+                    domain: None,
+                },
+            ))
+            .finish();
+        let val = resp.headers().get(SET_COOKIE).unwrap().to_str().unwrap();
+        assert!(val.starts_with("session=; Path=/app; Max-Age=0; Expires="));
+    }
+
+    #[cfg(feature = "cookies")]
+    #[test]
+    fn test_cookie_with_defaults() {
+        use crate::cookie::SameSite;
+
+        let defaults = CookieDefaults::new().same_site(SameSite::Strict);
+
+        let resp = Response::Ok()
+            .cookie_with_defaults(crate::http::Cookie::new("a", "1"), Some(&defaults))
+            .cookie_with_defaults(
+                crate::http::Cookie::build("b", "2")
+                    .same_site(SameSite::Lax)
+                    .finish(),
+                Some(&defaults),
+            )
+            .cookie_with_defaults(crate::http::Cookie::new("c", "3"), None)
+            .finish();
+
+        let mut val = resp
+            .headers()
+            .get_all(SET_COOKIE)
+            .map(|v| v.to_str().unwrap().to_owned())
+            .collect::<Vec<_>>();
+        val.sort();
+
+        assert_eq!(
+            val,
+            vec!["a=1; SameSite=Strict", "b=2; SameSite=Lax", "c=3"]
+        );
+    }
+
     #[cfg(feature = "cookies")]
     #[test]
     fn test_update_response_cookies() {
@@ -972,6 +1302,65 @@ mod tests {
         assert_eq!((v.name(), v.value()), ("cookie3", "val300"));
     }
 
+    #[test]
+    fn test_http_response_round_trip() {
+        let res = http::Response::builder()
+            .status(StatusCode::CREATED)
+            .version(http::Version::HTTP_10)
+            .header("x-multi", "a")
+            .header("x-multi", "b")
+            .header(CONTENT_TYPE, "text/plain")
+            .extension(7u32)
+            .body(Bytes::from_static(b"body"))
+            .unwrap();
+
+        let res = Response::from(res);
+        assert_eq!(res.status(), StatusCode::CREATED);
+        assert_eq!(res.head().version, http::Version::HTTP_10);
+        assert_eq!(res.headers().get(CONTENT_TYPE).unwrap(), "text/plain");
+        let ext = res.extensions();
+        assert_eq!(
+            ext.get::<http::Extensions>().unwrap().get::<u32>(),
+            Some(&7)
+        );
+        drop(ext);
+
+        let res = http::Response::from(res);
+        assert_eq!(res.status(), StatusCode::CREATED);
+        assert_eq!(res.version(), http::Version::HTTP_10);
+        assert_eq!(res.extensions().get::<u32>(), Some(&7));
+        assert_eq!(res.headers().len(), 3);
+        let mut multi = res
+            .headers()
+            .get_all("x-multi")
+            .iter()
+            .map(|val| val.to_str().unwrap())
+            .collect::<Vec<_>>();
+        multi.sort_unstable();
+        assert_eq!(multi, ["a", "b"]);
+        assert_eq!(res.body().as_ref().unwrap(), &Bytes::from_static(b"body"));
+    }
+
+    #[test]
+    fn test_streaming_io_error() {
+        let stream = futures_util::stream::iter(vec![
+            Ok(Bytes::from_static(b"chunk")),
+            Err(std::io::Error::new(
+                std::io::ErrorKind::Other,
+                "read failed",
+            )),
+        ]);
+        let res = Response::Ok().streaming(stream);
+        assert_eq!(res.body().size(), BodySize::Stream);
+    }
+
+    #[test]
+    fn test_map_into_boxed_body() {
+        let res = Response::with_body(StatusCode::OK, Bytes::from_static(b"boxed"));
+        let res = res.map_into_boxed_body();
+        assert_eq!(res.body().size(), BodySize::Sized(5));
+    }
+
     #[test]
     fn test_basic_builder() {
         let resp = Response::Ok().insert_header(("X-TEST", "value")).finish();
@@ -1022,6 +1411,22 @@ mod tests {
         assert_eq!(resp.body().get_ref(), b"[\"v1\",\"v2\",\"v3\"]");
     }
 
+    #[test]
+    fn test_json_stream() {
+        let items = futures_util::stream::iter(vec![Ok::<_, Error>(1), Ok(2)]);
+        let resp = Response::Ok().json_stream(items);
+        let ct = resp.headers().get(CONTENT_TYPE).unwrap();
+        assert_eq!(ct, HeaderValue::from_static("application/json"));
+        assert_eq!(resp.body().size(), BodySize::Stream);
+
+        let items = futures_util::stream::iter(vec![Ok::<_, Error>(1)]);
+        let resp = Response::Ok()
+            .insert_header((CONTENT_TYPE, "application/x-ndjson"))
+            .json_stream_with(items, SerializeErrorPolicy::Skip);
+        let ct = resp.headers().get(CONTENT_TYPE).unwrap();
+        assert_eq!(ct, HeaderValue::from_static("application/x-ndjson"));
+    }
+
     #[test]
     fn test_serde_json_in_body() {
         use serde_json::json;
@@ -1166,4 +1571,52 @@ mod tests {
         assert!(headers.contains(&HeaderValue::from_static("application/octet-stream")));
         assert!(headers.contains(&HeaderValue::from_static("application/json")));
     }
+
+    #[test]
+    fn response_builder_header_insert_replaces() {
+        let mut res = Response::Ok();
+        res.insert_header(("Content-Type", "application/octet-stream"));
+        res.insert_header(header::ContentType(mime::APPLICATION_JSON));
+        let res = res.finish();
+
+        let headers: Vec<_> = res.headers().get_all("Content-Type").cloned().collect();
+        assert_eq!(headers, vec![HeaderValue::from_static("application/json")]);
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn response_builder_header_deprecated_methods() {
+        let res = Response::Ok()
+            .header("X-TEST", "value1")
+            .header("X-TEST", "value2")
+            .set_header("X-SET", "value1")
+            .set_header("X-SET", "value2")
+            .finish();
+
+        assert_eq!(res.headers().get_all("X-TEST").count(), 2);
+
+        let headers: Vec<_> = res.headers().get_all("X-SET").cloned().collect();
+        assert_eq!(headers, vec![HeaderValue::from_static("value2")]);
+    }
+
+    #[test]
+    fn response_builder_invalid_header() {
+        let res = Response::Ok()
+            .insert_header(("Invalid Name", "value"))
+            .finish();
+        assert_eq!(res.status(), StatusCode::INTERNAL_SERVER_ERROR);
+
+        let res = Response::Ok()
+            .append_header(("X-TEST", "invalid\nvalue"))
+            .body("body");
+        assert_eq!(res.status(), StatusCode::INTERNAL_SERVER_ERROR);
+
+        // headers added after an error are ignored
+        let res = Response::Ok()
+            .insert_header(("Invalid Name", "value"))
+            .insert_header(("X-TEST", "value"))
+            .finish();
+        assert_eq!(res.status(), StatusCode::INTERNAL_SERVER_ERROR);
+        assert!(res.headers().get("X-TEST").is_none());
+    }
 }
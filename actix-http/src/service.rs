@@ -1,14 +1,16 @@
 use std::marker::PhantomData;
 use std::pin::Pin;
 use std::task::{Context, Poll};
-use std::{fmt, net, rc::Rc};
+use std::time::Duration;
+use std::{cmp, fmt, io, net, rc::Rc};
 
-use actix_codec::{AsyncRead, AsyncWrite, Framed};
+use actix_codec::{poll_read_buf, AsyncRead, AsyncWrite, Framed, ReadBuf};
 use actix_rt::net::TcpStream;
+use actix_rt::time::{sleep_until, Sleep};
 use actix_service::{pipeline_factory, IntoServiceFactory, Service, ServiceFactory};
-use bytes::Bytes;
+use bytes::{Buf as _, BufMut as _, Bytes, BytesMut};
 use futures_core::{ready, Future};
-use h2::server::{self, Handshake};
+use h2::server::Handshake;
 use pin_project::pin_project;
 
 use crate::body::MessageBody;
@@ -26,6 +28,7 @@ pub struct HttpService<T, S, B, X = h1::ExpectHandler, U = h1::UpgradeHandler> {
     expect: X,
     upgrade: Option<U>,
     on_connect_ext: Option<Rc<ConnectCallback<T>>>,
+    detect_h2c: bool,
     _phantom: PhantomData<B>,
 }
 
@@ -63,6 +66,7 @@ where
             expect: h1::ExpectHandler,
             upgrade: None,
             on_connect_ext: None,
+            detect_h2c: false,
             _phantom: PhantomData,
         }
     }
@@ -78,6 +82,7 @@ where
             expect: h1::ExpectHandler,
             upgrade: None,
             on_connect_ext: None,
+            detect_h2c: false,
             _phantom: PhantomData,
         }
     }
@@ -110,6 +115,7 @@ where
             srv: self.srv,
             upgrade: self.upgrade,
             on_connect_ext: self.on_connect_ext,
+            detect_h2c: self.detect_h2c,
             _phantom: PhantomData,
         }
     }
@@ -131,6 +137,7 @@ where
             srv: self.srv,
             expect: self.expect,
             on_connect_ext: self.on_connect_ext,
+            detect_h2c: self.detect_h2c,
             _phantom: PhantomData,
         }
     }
@@ -164,6 +171,9 @@ where
     <U::Service as Service<(Request, Framed<TcpStream, h1::Codec>)>>::Future: 'static,
 {
     /// Create simple tcp stream service
+    ///
+    /// Connections use HTTP/1.x, or HTTP/2 if h2c is allowed by
+    /// [`HttpServiceBuilder::allow_h2c`] and the client starts with the HTTP/2 preface.
     pub fn tcp(
         self,
     ) -> impl ServiceFactory<
@@ -173,14 +183,25 @@ where
         Error = DispatchError,
         InitError = (),
     > {
+        let this = HttpService {
+            detect_h2c: self.cfg.allow_h2c(),
+            ..self
+        };
+
         pipeline_factory(|io: TcpStream| async {
             let peer_addr = io.peer_addr().ok();
             Ok((io, Protocol::Http1, peer_addr))
         })
-        .and_then(self)
+        .and_then(this)
     }
 }
 
+/// Connection preface sent first by HTTP/2 clients.
+const H2_PREFACE: &[u8] = b"PRI * HTTP/2.0\r\n\r\nSM\r\n\r\n";
+
+/// Time to wait for the HTTP/2 connection preface when no client timeout is configured.
+const H2C_DETECT_TIMEOUT: Duration = Duration::from_secs(5);
+
 #[cfg(feature = "openssl")]
 mod openssl {
     use super::*;
@@ -346,6 +367,7 @@ where
             upgrade: None,
             on_connect_ext: self.on_connect_ext.clone(),
             cfg: self.cfg.clone(),
+            detect_h2c: self.detect_h2c,
             _phantom: PhantomData,
         }
     }
@@ -369,6 +391,7 @@ where
     upgrade: Option<U::Service>,
     on_connect_ext: Option<Rc<ConnectCallback<T>>>,
     cfg: ServiceConfig,
+    detect_h2c: bool,
     _phantom: PhantomData<B>,
 }
 
@@ -427,6 +450,7 @@ where
                 this.expect.take().unwrap(),
                 this.upgrade.take(),
                 this.on_connect_ext.clone(),
+                *this.detect_h2c,
             )
         }))
     }
@@ -442,6 +466,7 @@ where
     flow: Rc<HttpFlow<S, X, U>>,
     cfg: ServiceConfig,
     on_connect_ext: Option<Rc<ConnectCallback<T>>>,
+    detect_h2c: bool,
     _phantom: PhantomData<B>,
 }
 
@@ -480,10 +505,12 @@ where
         expect: X,
         upgrade: Option<U>,
         on_connect_ext: Option<Rc<ConnectCallback<T>>>,
+        detect_h2c: bool,
     ) -> HttpServiceHandler<T, S, B, X, U> {
         HttpServiceHandler {
             cfg,
             on_connect_ext,
+            detect_h2c,
             flow: HttpFlow::new(service, expect, upgrade),
             _phantom: PhantomData,
         }
@@ -560,9 +587,31 @@ where
             OnConnectData::from_io(&io, self.on_connect_ext.as_deref());
 
         match proto {
+            Protocol::Http1 if self.detect_h2c => {
+                // h2c detection stops waiting for the preface with the client timeout
+                let deadline = self
+                    .cfg
+                    .client_timer_expire()
+                    .unwrap_or_else(|| self.cfg.now() + H2C_DETECT_TIMEOUT);
+
+                HttpServiceHandlerResponse {
+                    state: State::DetectH2c(Some(DetectH2c {
+                        io,
+                        buf: BytesMut::with_capacity(H2_PREFACE.len()),
+                        timer: Box::pin(sleep_until(deadline)),
+                        cfg: self.cfg.clone(),
+                        flow: self.flow.clone(),
+                        on_connect_data,
+                        peer_addr,
+                    })),
+                }
+            }
+
             Protocol::Http2 => HttpServiceHandlerResponse {
                 state: State::H2Handshake(Some((
-                    server::handshake(io),
+                    self.cfg
+                        .h2_settings()
+                        .handshake(Rewind::new(io, Bytes::new())),
                     self.cfg.clone(),
                     self.flow.clone(),
                     on_connect_data,
@@ -599,16 +648,125 @@ where
     U::Error: fmt::Display,
 {
     H1(#[pin] h1::Dispatcher<T, S, B, X, U>),
-    H2(#[pin] Dispatcher<T, S, B, X, U>),
+    H2(#[pin] Dispatcher<Rewind<T>, S, B, X, U>),
     H2Handshake(
         Option<(
-            Handshake<T, Bytes>,
+            Handshake<Rewind<T>, Bytes>,
             ServiceConfig,
             Rc<HttpFlow<S, X, U>>,
             OnConnectData,
             Option<net::SocketAddr>,
         )>,
     ),
+    DetectH2c(Option<DetectH2c<T, S, X, U>>),
+}
+
+/// Connection that is read until it is known whether it starts with the HTTP/2 preface.
+struct DetectH2c<T, S, X, U> {
+    io: T,
+    buf: BytesMut,
+    timer: Pin<Box<Sleep>>,
+    cfg: ServiceConfig,
+    flow: Rc<HttpFlow<S, X, U>>,
+    on_connect_data: OnConnectData,
+    peer_addr: Option<net::SocketAddr>,
+}
+
+impl<T, S, X, U> DetectH2c<T, S, X, U>
+where
+    T: AsyncRead + Unpin,
+{
+    /// Reads until the preface is complete, the data does not match it, or the timer expires.
+    ///
+    /// Bytes that were read are kept in `buf` to be handed to the selected dispatcher.
+    fn poll_detect(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<Protocol>> {
+        loop {
+            if !H2_PREFACE.starts_with(&self.buf) {
+                return Poll::Ready(Ok(Protocol::Http1));
+            } else if self.buf.len() == H2_PREFACE.len() {
+                return Poll::Ready(Ok(Protocol::Http2));
+            }
+
+            let remaining = H2_PREFACE.len() - self.buf.len();
+            let mut buf = (&mut self.buf).limit(remaining);
+
+            match poll_read_buf(Pin::new(&mut self.io), cx, &mut buf) {
+                Poll::Ready(Ok(0)) => return Poll::Ready(Ok(Protocol::Http1)),
+                Poll::Ready(Ok(_)) => {}
+                Poll::Ready(Err(err)) => return Poll::Ready(Err(err)),
+                Poll::Pending => {
+                    // the HTTP/1.x dispatcher handles slow clients with its own timers
+                    ready!(self.timer.as_mut().poll(cx));
+                    return Poll::Ready(Ok(Protocol::Http1));
+                }
+            }
+        }
+    }
+}
+
+/// IO that yields bytes read ahead of time before reading from the inner IO.
+struct Rewind<T> {
+    pre: Bytes,
+    io: T,
+}
+
+impl<T> Rewind<T> {
+    fn new(io: T, pre: Bytes) -> Self {
+        Rewind { pre, io }
+    }
+}
+
+impl<T: AsyncRead + Unpin> AsyncRead for Rewind<T> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        if self.pre.is_empty() {
+            return Pin::new(&mut self.io).poll_read(cx, buf);
+        }
+
+        let n = cmp::min(self.pre.len(), buf.remaining());
+        buf.put_slice(&self.pre[..n]);
+        self.pre.advance(n);
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<T: AsyncWrite + Unpin> AsyncWrite for Rewind<T> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.io).poll_write(cx, buf)
+    }
+
+    fn poll_write_vectored(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &[io::IoSlice<'_>],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.io).poll_write_vectored(cx, bufs)
+    }
+
+    fn is_write_vectored(&self) -> bool {
+        self.io.is_write_vectored()
+    }
+
+    fn poll_flush(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.io).poll_flush(cx)
+    }
+
+    fn poll_shutdown(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.io).poll_shutdown(cx)
+    }
 }
 
 #[pin_project]
@@ -669,6 +827,40 @@ where
                     }
                 }
             }
+            StateProj::DetectH2c(data) => {
+                let proto = ready!(data.as_mut().unwrap().poll_detect(cx))?;
+
+                let DetectH2c {
+                    io,
+                    buf,
+                    cfg,
+                    flow,
+                    on_connect_data,
+                    peer_addr,
+                    ..
+                } = data.take().unwrap();
+
+                let state = match proto {
+                    Protocol::Http2 => State::H2Handshake(Some((
+                        cfg.h2_settings().handshake(Rewind::new(io, buf.freeze())),
+                        cfg,
+                        flow,
+                        on_connect_data,
+                        peer_addr,
+                    ))),
+                    _ => State::H1(h1::Dispatcher::with_read_buf(
+                        io,
+                        buf,
+                        cfg,
+                        flow,
+                        on_connect_data,
+                        peer_addr,
+                    )),
+                };
+
+                self.as_mut().project().state.set(state);
+                self.poll(cx)
+            }
         }
     }
 }
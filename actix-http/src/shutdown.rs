@@ -0,0 +1,74 @@
+use std::{fmt, future::Future, sync::Arc};
+
+use tokio::sync::watch;
+
+/// Handle for gracefully shutting down the connections of a service.
+///
+/// Once triggered, HTTP/2 connections send `GOAWAY`, finish their in-flight streams and close,
+/// and HTTP/1.x connections stop keep-alive after their current response. Clones share the same
+/// state, so one handle can be given to every worker of a server.
+///
+/// ```rust
+/// use actix_http::GracefulShutdown;
+///
+/// let shutdown = GracefulShutdown::new();
+/// assert!(!shutdown.is_shutdown());
+///
+/// shutdown.clone().shutdown();
+/// assert!(shutdown.is_shutdown());
+/// ```
+#[derive(Clone)]
+pub struct GracefulShutdown {
+    tx: Arc<watch::Sender<bool>>,
+    rx: watch::Receiver<bool>,
+}
+
+impl GracefulShutdown {
+    /// Create a new handle which has not been triggered.
+    pub fn new() -> Self {
+        let (tx, rx) = watch::channel(false);
+
+        GracefulShutdown {
+            tx: Arc::new(tx),
+            rx,
+        }
+    }
+
+    /// Ask all connections using this handle to shut down gracefully.
+    pub fn shutdown(&self) {
+        // a receiver is kept alive by `self`, so sending can not fail
+        let _ = self.tx.send(true);
+    }
+
+    /// Returns true if shutdown has been triggered.
+    pub fn is_shutdown(&self) -> bool {
+        *self.rx.borrow()
+    }
+
+    /// Resolves once shutdown has been triggered.
+    pub fn wait(&self) -> impl Future<Output = ()> + 'static {
+        let mut rx = self.rx.clone();
+
+        async move {
+            while !*rx.borrow() {
+                if rx.changed().await.is_err() {
+                    futures_util::future::pending::<()>().await;
+                }
+            }
+        }
+    }
+}
+
+impl Default for GracefulShutdown {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for GracefulShutdown {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("GracefulShutdown")
+            .field("is_shutdown", &self.is_shutdown())
+            .finish()
+    }
+}
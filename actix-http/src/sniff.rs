@@ -0,0 +1,123 @@
+//! Content type detection from the first bytes of a body.
+
+use mime::Mime;
+
+/// Number of leading bytes inspected by [`content_type`].
+pub const SNIFF_LEN: usize = 512;
+
+/// Signatures, matched against the start of the data.
+const SIGNATURES: &[(&[u8], &str)] = &[
+    (b"\x89PNG\r\n\x1a\n", "image/png"),
+    (b"\xff\xd8\xff", "image/jpeg"),
+    (b"GIF87a", "image/gif"),
+    (b"GIF89a", "image/gif"),
+    (b"%PDF-", "application/pdf"),
+    (b"PK\x03\x04", "application/zip"),
+    (b"PK\x05\x06", "application/zip"),
+    (b"PK\x07\x08", "application/zip"),
+    (
+        b"\xd0\xcf\x11\xe0\xa1\xb1\x1a\xe1",
+        "application/x-ole-storage",
+    ),
+];
+
+/// Detects the content type of `data` from its signature.
+///
+/// Recognizes PNG, JPEG, GIF, PDF, ZIP and OLE compound files (legacy Office documents). Data
+/// without a known signature is `text/plain; charset=utf-8` if it is UTF-8 text, and
+/// `application/octet-stream` otherwise. Only the first [`SNIFF_LEN`] bytes are inspected, so
+/// passing a prefix of a larger body is enough.
+///
+/// ```rust
+/// use actix_http::http::sniff;
+///
+/// assert_eq!(sniff::content_type(b"GIF89a...."), "image/gif");
+/// assert_eq!(sniff::content_type(b"hello"), mime::TEXT_PLAIN_UTF_8);
+/// assert_eq!(sniff::content_type(b"\x00\x01"), mime::APPLICATION_OCTET_STREAM);
+/// ```
+pub fn content_type(data: &[u8]) -> Mime {
+    let data = &data[..data.len().min(SNIFF_LEN)];
+
+    for (signature, mime) in SIGNATURES {
+        if data.starts_with(signature) {
+            return mime.parse().unwrap();
+        }
+    }
+
+    if is_text(data) {
+        mime::TEXT_PLAIN_UTF_8
+    } else {
+        mime::APPLICATION_OCTET_STREAM
+    }
+}
+
+/// Returns true if `data` is non-empty UTF-8 text without control characters besides
+/// whitespace, allowing a character cut off at the end.
+fn is_text(data: &[u8]) -> bool {
+    let text = match std::str::from_utf8(data) {
+        Ok(text) => text,
+        // only an incomplete sequence at the end is acceptable
+        Err(err) if err.error_len().is_none() => {
+            std::str::from_utf8(&data[..err.valid_up_to()]).unwrap()
+        }
+        Err(_) => return false,
+    };
+
+    !text.is_empty()
+        && !text.chars().any(|ch| {
+            ch.is_control() && !matches!(ch, '\t' | '\n' | '\r' | '\x0c' | '\x1b')
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_signatures() {
+        assert_eq!(
+            content_type(b"\x89PNG\r\n\x1a\n\x00\x00\x00\rIHDR"),
+            mime::IMAGE_PNG
+        );
+        assert_eq!(
+            content_type(b"\xff\xd8\xff\xe0\x00\x10JFIF"),
+            mime::IMAGE_JPEG
+        );
+        assert_eq!(content_type(b"GIF87a\x01\x00"), mime::IMAGE_GIF);
+        assert_eq!(content_type(b"GIF89a\x01\x00"), mime::IMAGE_GIF);
+        assert_eq!(content_type(b"%PDF-1.7\n"), mime::APPLICATION_PDF);
+        assert_eq!(content_type(b"PK\x03\x04\x14\x00"), "application/zip");
+        assert_eq!(content_type(b"PK\x05\x06\x00\x00"), "application/zip");
+        assert_eq!(content_type(b"PK\x07\x08\x00\x00"), "application/zip");
+        assert_eq!(
+            content_type(b"\xd0\xcf\x11\xe0\xa1\xb1\x1a\xe1\x00\x00"),
+            "application/x-ole-storage"
+        );
+    }
+
+    #[test]
+    fn test_text() {
+        assert_eq!(content_type(b"hello world\r\n"), mime::TEXT_PLAIN_UTF_8);
+        assert_eq!(
+            content_type("gr\u{fc}\u{df}e\tund so".as_bytes()),
+            mime::TEXT_PLAIN_UTF_8
+        );
+
+        // multi-byte character cut off by the prefix
+        let text = format!("a{}", "\u{e9}".repeat(SNIFF_LEN));
+        assert_eq!(content_type(text.as_bytes()), mime::TEXT_PLAIN_UTF_8);
+    }
+
+    #[test]
+    fn test_fallback() {
+        assert_eq!(content_type(b""), mime::APPLICATION_OCTET_STREAM);
+        assert_eq!(
+            content_type(b"\x00\x61\x73\x6d\x01"),
+            mime::APPLICATION_OCTET_STREAM
+        );
+        assert_eq!(content_type(b"text\x00"), mime::APPLICATION_OCTET_STREAM);
+        assert_eq!(content_type(b"\xc3\x28abc"), mime::APPLICATION_OCTET_STREAM);
+        assert_eq!(content_type(b"\xc3"), mime::APPLICATION_OCTET_STREAM);
+        assert_eq!(content_type(b"GIF8"), mime::TEXT_PLAIN_UTF_8);
+    }
+}
@@ -0,0 +1,90 @@
+//! Spans created by the dispatchers when the `tracing` feature is enabled.
+//!
+//! Without the feature, these types are empty and entering them does nothing.
+
+use std::{future::Future, net};
+
+/// Span covering a connection, from the start of dispatching until the dispatcher is done.
+///
+/// Request spans created by services are children of it, phases of the dispatcher are recorded as
+/// child spans at the trace level.
+#[derive(Clone)]
+pub(crate) struct ConnectionSpan {
+    #[cfg(feature = "tracing")]
+    span: tracing::Span,
+}
+
+impl ConnectionSpan {
+    #[cfg(feature = "tracing")]
+    pub(crate) fn new(
+        protocol: &'static str,
+        peer_addr: Option<net::SocketAddr>,
+    ) -> Self {
+        let span = tracing::info_span!(
+            "http.connection",
+            protocol,
+            peer_addr = tracing::field::Empty
+        );
+
+        if let Some(addr) = peer_addr {
+            span.record("peer_addr", tracing::field::display(addr));
+        }
+
+        ConnectionSpan { span }
+    }
+
+    #[cfg(not(feature = "tracing"))]
+    pub(crate) fn new(
+        _protocol: &'static str,
+        _peer_addr: Option<net::SocketAddr>,
+    ) -> Self {
+        ConnectionSpan {}
+    }
+
+    /// Enters the span until the returned guard is dropped.
+    pub(crate) fn enter(&self) -> Entered {
+        Entered {
+            #[cfg(feature = "tracing")]
+            _span: self.span.clone().entered(),
+        }
+    }
+
+    /// Runs `fut` within the span, for futures polled outside of the dispatcher.
+    pub(crate) fn instrument<F: Future>(
+        &self,
+        fut: F,
+    ) -> impl Future<Output = F::Output> {
+        #[cfg(feature = "tracing")]
+        {
+            tracing::Instrument::instrument(fut, self.span.clone())
+        }
+
+        #[cfg(not(feature = "tracing"))]
+        {
+            fut
+        }
+    }
+}
+
+/// Guard of an entered span.
+#[must_use]
+pub(crate) struct Entered {
+    #[cfg(feature = "tracing")]
+    _span: tracing::span::EnteredSpan,
+}
+
+/// Enters a span for reading from the connection, a child of the entered connection span.
+pub(crate) fn read() -> Entered {
+    Entered {
+        #[cfg(feature = "tracing")]
+        _span: tracing::trace_span!("http.read").entered(),
+    }
+}
+
+/// Enters a span for writing to the connection, a child of the entered connection span.
+pub(crate) fn write() -> Entered {
+    Entered {
+        #[cfg(feature = "tracing")]
+        _span: tracing::trace_span!("http.write").entered(),
+    }
+}
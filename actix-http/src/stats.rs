@@ -0,0 +1,64 @@
+use std::{
+    fmt,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+/// Counters describing how connections of a service behave, for use in metrics.
+///
+/// Clones share the same counters, so one instance can be handed to the service builder (or to
+/// every worker of a server) and read from elsewhere.
+///
+/// ```rust,no_run
+/// use actix_http::{ConnectionStats, HttpService, Request, Response};
+///
+/// let stats = ConnectionStats::new();
+///
+/// let _ = HttpService::build()
+///     .stats(stats.clone())
+///     .finish(|_: Request| async { Ok::<_, ()>(Response::Ok().finish()) })
+///     .tcp();
+///
+/// assert_eq!(stats.h2_window_stall_time().as_secs(), 0);
+/// ```
+#[derive(Clone, Default)]
+pub struct ConnectionStats(Arc<Inner>);
+
+#[derive(Default)]
+struct Inner {
+    h2_window_stall_nanos: AtomicU64,
+}
+
+impl ConnectionStats {
+    /// Create a new set of counters starting at zero.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Total time HTTP/2 response bodies spent waiting for flow control window, summed over all
+    /// streams.
+    ///
+    /// Grows when clients read responses slower than they are produced, or advertise small
+    /// windows.
+    pub fn h2_window_stall_time(&self) -> Duration {
+        Duration::from_nanos(self.0.h2_window_stall_nanos.load(Ordering::Relaxed))
+    }
+
+    pub(crate) fn add_h2_window_stall(&self, stall: Duration) {
+        let nanos = stall.as_nanos().min(u64::MAX as u128) as u64;
+        self.0
+            .h2_window_stall_nanos
+            .fetch_add(nanos, Ordering::Relaxed);
+    }
+}
+
+impl fmt::Debug for ConnectionStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ConnectionStats")
+            .field("h2_window_stall_time", &self.h2_window_stall_time())
+            .finish()
+    }
+}
@@ -135,6 +135,8 @@ impl TestRequest {
     }
 
     /// Set cookie for this request.
+    ///
+    /// All cookies, including any set with a `Cookie` header, are sent in a single `Cookie` header.
     #[cfg(feature = "cookies")]
     pub fn cookie<'a>(&mut self, cookie: Cookie<'a>) -> &mut Self {
         parts(&mut self.0).cookies.add(cookie.into_owned());
@@ -171,11 +173,19 @@ impl TestRequest {
 
         #[cfg(feature = "cookies")]
         {
-            let cookie: String = inner
-                .cookies
-                .delta()
-                // ensure only name=value is written to cookie header
-                .map(|c| Cookie::new(c.name(), c.value()).encoded().to_string())
+            // fold cookie headers and cookies from the jar into a single header
+            let cookie: String = head
+                .headers
+                .get_all(header::COOKIE)
+                .filter_map(|val| val.to_str().ok())
+                .map(ToOwned::to_owned)
+                .chain(
+                    inner
+                        .cookies
+                        .delta()
+                        // ensure only name=value is written to cookie header
+                        .map(|c| Cookie::new(c.name(), c.value()).encoded().to_string()),
+                )
                 .collect::<Vec<_>>()
                 .join("; ");
 
@@ -297,6 +307,7 @@ impl TestSeqBuffer {
             read_buf: data.into(),
             write_buf: BytesMut::new(),
             err: None,
+            eof: false,
         })))
     }
 
@@ -324,12 +335,18 @@ impl TestSeqBuffer {
             .read_buf
             .extend_from_slice(data.as_ref())
     }
+
+    /// Signal end of stream once the read buffer is drained, like a peer closing its write half.
+    pub fn close_read(&mut self) {
+        self.0.borrow_mut().eof = true;
+    }
 }
 
 pub struct TestSeqInner {
     read_buf: BytesMut,
     write_buf: BytesMut,
     err: Option<io::Error>,
+    eof: bool,
 }
 
 impl io::Read for TestSeqBuffer {
@@ -337,6 +354,8 @@ impl io::Read for TestSeqBuffer {
         if self.0.borrow().read_buf.is_empty() {
             if self.0.borrow().err.is_some() {
                 Err(self.0.borrow_mut().err.take().unwrap())
+            } else if self.0.borrow().eof {
+                Ok(0)
             } else {
                 Err(io::Error::new(io::ErrorKind::WouldBlock, ""))
             }
@@ -3,6 +3,8 @@ use bitflags::bitflags;
 use bytes::{Bytes, BytesMut};
 use bytestring::ByteString;
 
+#[cfg(feature = "compress")]
+use super::deflate::{DeflateConfig, DeflateContext};
 use super::frame::Parser;
 use super::proto::{CloseReason, OpCode};
 use super::ProtocolError;
@@ -63,11 +65,16 @@ pub enum Item {
     Last(Bytes),
 }
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug)]
 /// WebSocket protocol codec.
 pub struct Codec {
     flags: Flags,
     max_size: usize,
+    max_frame_size: Option<usize>,
+    max_message_size: Option<usize>,
+    message_size: usize,
+    #[cfg(feature = "compress")]
+    deflate: Option<Box<DeflateContext>>,
 }
 
 bitflags! {
@@ -75,6 +82,8 @@ bitflags! {
         const SERVER         = 0b0000_0001;
         const CONTINUATION   = 0b0000_0010;
         const W_CONTINUATION = 0b0000_0100;
+        const R_DEFLATE      = 0b0000_1000;
+        const W_DEFLATE      = 0b0001_0000;
     }
 }
 
@@ -83,7 +92,12 @@ impl Codec {
     pub fn new() -> Codec {
         Codec {
             max_size: 65_536,
+            max_frame_size: None,
+            max_message_size: None,
+            message_size: 0,
             flags: Flags::SERVER,
+            #[cfg(feature = "compress")]
+            deflate: None,
         }
     }
 
@@ -95,6 +109,29 @@ impl Codec {
         self
     }
 
+    /// Set max size of outgoing frames.
+    ///
+    /// Text and binary messages with a larger payload are split into an initial frame and
+    /// continuation frames. By default messages are not split.
+    ///
+    /// # Panics
+    /// Panics if `size` is zero.
+    pub fn max_frame_size(mut self, size: usize) -> Self {
+        assert!(size > 0, "max frame size must be greater than zero");
+        self.max_frame_size = Some(size);
+        self
+    }
+
+    /// Set max size of incoming messages sent as continuation frames.
+    ///
+    /// The payloads of all frames of a message are added up and decoding fails with
+    /// [`ProtocolError::Overflow`] once they exceed the limit. By default only the size of
+    /// individual frames is limited, by [`max_size`](Self::max_size).
+    pub fn max_message_size(mut self, size: usize) -> Self {
+        self.max_message_size = Some(size);
+        self
+    }
+
     /// Set decoder to client mode.
     ///
     /// By default decoder works in server mode.
@@ -102,6 +139,162 @@ impl Codec {
         self.flags.remove(Flags::SERVER);
         self
     }
+
+    /// Compress messages with the negotiated `permessage-deflate` parameters.
+    ///
+    /// The decompressed size of received messages is limited by
+    /// [`max_message_size`](Self::max_message_size) or, if that is not set, by
+    /// [`max_size`](Self::max_size).
+    #[cfg(feature = "compress")]
+    pub fn deflate(mut self, config: DeflateConfig) -> Self {
+        self.deflate = Some(Box::new(DeflateContext::new(config)));
+        self
+    }
+
+    /// Write a data frame, compressing it if `permessage-deflate` is in use.
+    fn write_data(
+        &mut self,
+        dst: &mut BytesMut,
+        data: &[u8],
+        op: OpCode,
+        fin: bool,
+    ) -> Result<(), ProtocolError> {
+        let server = self.flags.contains(Flags::SERVER);
+
+        #[cfg(feature = "compress")]
+        if let Some(ref mut deflate) = self.deflate {
+            let compress = if op == OpCode::Continue {
+                self.flags.contains(Flags::W_DEFLATE)
+            } else {
+                self.flags.set(Flags::W_DEFLATE, !fin);
+                true
+            };
+
+            if op == OpCode::Continue && fin {
+                self.flags.remove(Flags::W_DEFLATE);
+            }
+
+            if compress {
+                let payload = deflate.compress(server, data, fin)?;
+                // only the first frame of a compressed message has RSV1 set
+                let rsv1 = op != OpCode::Continue;
+                self.write_frames(dst, &payload, op, fin, rsv1);
+                return Ok(());
+            }
+        }
+
+        self.write_frames(dst, data, op, fin, false);
+        Ok(())
+    }
+
+    /// Write a data payload as frames no larger than the max frame size.
+    fn write_frames(
+        &self,
+        dst: &mut BytesMut,
+        payload: &[u8],
+        mut op: OpCode,
+        fin: bool,
+        mut rsv1: bool,
+    ) {
+        let mask = !self.flags.contains(Flags::SERVER);
+
+        let max = match self.max_frame_size {
+            Some(max) if payload.len() > max => max,
+            _ => return Parser::write_frame(dst, payload, op, fin, rsv1, mask),
+        };
+
+        let mut chunks = payload.chunks(max).peekable();
+        while let Some(chunk) = chunks.next() {
+            let last = chunks.peek().is_none();
+            Parser::write_frame(dst, chunk, op, fin && last, rsv1, mask);
+
+            op = OpCode::Continue;
+            rsv1 = false;
+        }
+    }
+
+    /// Add the payload size of a message fragment to the received message size.
+    fn add_message_size(
+        &mut self,
+        first: bool,
+        len: usize,
+    ) -> Result<(), ProtocolError> {
+        if first {
+            self.message_size = 0;
+        }
+        self.message_size += len;
+
+        match self.max_message_size {
+            Some(max) if self.message_size > max => Err(ProtocolError::Overflow),
+            _ => Ok(()),
+        }
+    }
+
+    /// Decompress the payload of a received frame, if the message is compressed.
+    fn read_data(
+        &mut self,
+        finished: bool,
+        rsv1: bool,
+        opcode: OpCode,
+        payload: Option<BytesMut>,
+    ) -> Result<Option<BytesMut>, ProtocolError> {
+        #[cfg(feature = "compress")]
+        if let Some(ref mut deflate) = self.deflate {
+            let compressed = match opcode {
+                OpCode::Text | OpCode::Binary if rsv1 => {
+                    self.flags.set(Flags::R_DEFLATE, !finished);
+                    true
+                }
+                OpCode::Continue if !rsv1 && self.flags.contains(Flags::R_DEFLATE) => {
+                    if finished {
+                        self.flags.remove(Flags::R_DEFLATE);
+                    }
+                    true
+                }
+                _ if rsv1 => return Err(ProtocolError::ReservedBits),
+                _ => false,
+            };
+
+            return if compressed {
+                let data = payload.as_deref().unwrap_or(&[]);
+                deflate
+                    .decompress(
+                        self.flags.contains(Flags::SERVER),
+                        data,
+                        finished,
+                        self.max_message_size.unwrap_or(self.max_size),
+                    )
+                    .map(Some)
+            } else {
+                Ok(payload)
+            };
+        }
+
+        let _ = (finished, opcode);
+        if rsv1 {
+            Err(ProtocolError::ReservedBits)
+        } else {
+            Ok(payload)
+        }
+    }
+}
+
+impl Clone for Codec {
+    /// Clones the codec configuration; compression state of the clone starts out empty.
+    fn clone(&self) -> Self {
+        Codec {
+            flags: self.flags,
+            max_size: self.max_size,
+            max_frame_size: self.max_frame_size,
+            max_message_size: self.max_message_size,
+            message_size: self.message_size,
+            #[cfg(feature = "compress")]
+            deflate: self
+                .deflate
+                .as_ref()
+                .map(|deflate| Box::new(DeflateContext::new(deflate.config()))),
+        }
+    }
 }
 
 impl Encoder<Message> for Codec {
@@ -109,20 +302,10 @@ impl Encoder<Message> for Codec {
 
     fn encode(&mut self, item: Message, dst: &mut BytesMut) -> Result<(), Self::Error> {
         match item {
-            Message::Text(txt) => Parser::write_message(
-                dst,
-                txt,
-                OpCode::Text,
-                true,
-                !self.flags.contains(Flags::SERVER),
-            ),
-            Message::Binary(bin) => Parser::write_message(
-                dst,
-                bin,
-                OpCode::Binary,
-                true,
-                !self.flags.contains(Flags::SERVER),
-            ),
+            Message::Text(txt) => {
+                self.write_data(dst, txt.as_bytes(), OpCode::Text, true)?
+            }
+            Message::Binary(bin) => self.write_data(dst, &bin, OpCode::Binary, true)?,
             Message::Ping(txt) => Parser::write_message(
                 dst,
                 txt,
@@ -146,13 +329,7 @@ impl Encoder<Message> for Codec {
                         return Err(ProtocolError::ContinuationStarted);
                     } else {
                         self.flags.insert(Flags::W_CONTINUATION);
-                        Parser::write_message(
-                            dst,
-                            &data[..],
-                            OpCode::Text,
-                            false,
-                            !self.flags.contains(Flags::SERVER),
-                        )
+                        self.write_data(dst, &data, OpCode::Text, false)?
                     }
                 }
                 Item::FirstBinary(data) => {
@@ -160,24 +337,12 @@ impl Encoder<Message> for Codec {
                         return Err(ProtocolError::ContinuationStarted);
                     } else {
                         self.flags.insert(Flags::W_CONTINUATION);
-                        Parser::write_message(
-                            dst,
-                            &data[..],
-                            OpCode::Binary,
-                            false,
-                            !self.flags.contains(Flags::SERVER),
-                        )
+                        self.write_data(dst, &data, OpCode::Binary, false)?
                     }
                 }
                 Item::Continue(data) => {
                     if self.flags.contains(Flags::W_CONTINUATION) {
-                        Parser::write_message(
-                            dst,
-                            &data[..],
-                            OpCode::Continue,
-                            false,
-                            !self.flags.contains(Flags::SERVER),
-                        )
+                        self.write_data(dst, &data, OpCode::Continue, false)?
                     } else {
                         return Err(ProtocolError::ContinuationNotStarted);
                     }
@@ -185,13 +350,7 @@ impl Encoder<Message> for Codec {
                 Item::Last(data) => {
                     if self.flags.contains(Flags::W_CONTINUATION) {
                         self.flags.remove(Flags::W_CONTINUATION);
-                        Parser::write_message(
-                            dst,
-                            &data[..],
-                            OpCode::Continue,
-                            true,
-                            !self.flags.contains(Flags::SERVER),
-                        )
+                        self.write_data(dst, &data, OpCode::Continue, true)?
                     } else {
                         return Err(ProtocolError::ContinuationNotStarted);
                     }
@@ -208,8 +367,20 @@ impl Decoder for Codec {
     type Error = ProtocolError;
 
     fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
-        match Parser::parse(src, self.flags.contains(Flags::SERVER), self.max_size) {
-            Ok(Some((finished, opcode, payload))) => {
+        match Parser::parse_frame(src, self.flags.contains(Flags::SERVER), self.max_size)
+        {
+            Ok(Some((finished, rsv1, opcode, payload))) => {
+                let payload = self.read_data(finished, rsv1, opcode, payload)?;
+
+                let len = payload.as_ref().map_or(0, |pl| pl.len());
+                match opcode {
+                    OpCode::Text | OpCode::Binary if !finished => {
+                        self.add_message_size(true, len)?
+                    }
+                    OpCode::Continue => self.add_message_size(false, len)?,
+                    _ => {}
+                }
+
                 // continuation is not supported
                 if !finished {
                     return match opcode {
@@ -294,3 +465,113 @@ impl Decoder for Codec {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decode_all(codec: &mut Codec, buf: &mut BytesMut) -> Vec<Frame> {
+        let mut frames = Vec::new();
+        while let Some(frame) = codec.decode(buf).unwrap() {
+            frames.push(frame);
+        }
+        frames
+    }
+
+    #[test]
+    fn test_split_frames() {
+        let mut server = Codec::new().max_frame_size(4);
+        let mut client = Codec::new().client_mode();
+
+        let mut buf = BytesMut::new();
+        server
+            .encode(Message::Text("0123456789".into()), &mut buf)
+            .unwrap();
+        server
+            .encode(Message::Binary(Bytes::from_static(b"0123")), &mut buf)
+            .unwrap();
+
+        assert_eq!(
+            decode_all(&mut client, &mut buf),
+            vec![
+                Frame::Continuation(Item::FirstText(Bytes::from_static(b"0123"))),
+                Frame::Continuation(Item::Continue(Bytes::from_static(b"4567"))),
+                Frame::Continuation(Item::Last(Bytes::from_static(b"89"))),
+                Frame::Binary(Bytes::from_static(b"0123")),
+            ]
+        );
+
+        // fragments sent by the user are split as well
+        let mut buf = BytesMut::new();
+        for item in vec![
+            Item::FirstBinary(Bytes::from_static(b"012345")),
+            Item::Last(Bytes::from_static(b"6789")),
+        ] {
+            server
+                .encode(Message::Continuation(item), &mut buf)
+                .unwrap();
+        }
+
+        assert_eq!(
+            decode_all(&mut client, &mut buf),
+            vec![
+                Frame::Continuation(Item::FirstBinary(Bytes::from_static(b"0123"))),
+                Frame::Continuation(Item::Continue(Bytes::from_static(b"45"))),
+                Frame::Continuation(Item::Last(Bytes::from_static(b"6789"))),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_interleaved_control_frames() {
+        let mut client = Codec::new().client_mode();
+        let mut server = Codec::new().max_message_size(8);
+
+        let mut buf = BytesMut::new();
+        for msg in vec![
+            Message::Continuation(Item::FirstText(Bytes::from_static(b"0123"))),
+            Message::Ping(Bytes::from_static(b"ping")),
+            Message::Continuation(Item::Last(Bytes::from_static(b"4567"))),
+            Message::Pong(Bytes::from_static(b"pong")),
+        ] {
+            client.encode(msg, &mut buf).unwrap();
+        }
+
+        assert_eq!(
+            decode_all(&mut server, &mut buf),
+            vec![
+                Frame::Continuation(Item::FirstText(Bytes::from_static(b"0123"))),
+                Frame::Ping(Bytes::from_static(b"ping")),
+                Frame::Continuation(Item::Last(Bytes::from_static(b"4567"))),
+                Frame::Pong(Bytes::from_static(b"pong")),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_max_message_size() {
+        let mut client = Codec::new().client_mode().max_frame_size(4);
+        let mut server = Codec::new().max_message_size(8);
+
+        // the limit applies per message
+        let mut buf = BytesMut::new();
+        for _ in 0..2 {
+            client
+                .encode(Message::Binary(Bytes::from_static(b"01234567")), &mut buf)
+                .unwrap();
+        }
+        assert_eq!(decode_all(&mut server, &mut buf).len(), 4);
+
+        let mut buf = BytesMut::new();
+        client
+            .encode(Message::Binary(Bytes::from_static(b"012345678")), &mut buf)
+            .unwrap();
+
+        assert!(server.decode(&mut buf).unwrap().is_some());
+        assert!(server.decode(&mut buf).unwrap().is_some());
+        match server.decode(&mut buf) {
+            Err(ProtocolError::Overflow) => {}
+            res => panic!("expected overflow, got {:?}", res),
+        }
+    }
+}
@@ -0,0 +1,717 @@
+//! `permessage-deflate` WebSocket extension (RFC 7692).
+
+use std::{fmt, io};
+
+use bytes::BytesMut;
+use derive_more::{Display, Error};
+use flate2::{
+    Compress, Compression, Decompress, FlushCompress, FlushDecompress, Status,
+};
+use http::header::{HeaderValue, SEC_WEBSOCKET_EXTENSIONS};
+
+use crate::header::HeaderMap;
+use crate::ws::ProtocolError;
+
+const EXTENSION: &str = "permessage-deflate";
+const SERVER_NO_CONTEXT_TAKEOVER: &str = "server_no_context_takeover";
+const CLIENT_NO_CONTEXT_TAKEOVER: &str = "client_no_context_takeover";
+const SERVER_MAX_WINDOW_BITS: &str = "server_max_window_bits";
+const CLIENT_MAX_WINDOW_BITS: &str = "client_max_window_bits";
+
+const MIN_WINDOW_BITS: u8 = 8;
+const MAX_WINDOW_BITS: u8 = 15;
+
+/// Trailer of a sync flush, stripped from the end of compressed messages.
+const TAIL: [u8; 4] = [0x00, 0x00, 0xff, 0xff];
+
+/// Server responded with `permessage-deflate` parameters which were not offered or are invalid.
+#[derive(Debug, Display, Error)]
+#[display(fmt = "Invalid permessage-deflate extension response.")]
+pub struct DeflateResponseError;
+
+/// Parameters of the `permessage-deflate` extension.
+///
+/// Servers accept a client's offer with [`handshake_with_deflate`](super::handshake_with_deflate)
+/// or [`accept_offer`](Self::accept_offer); clients send [`offer`](Self::offer) and check the
+/// server's answer with [`accept_response`](Self::accept_response). Both return the negotiated
+/// parameters, which are then passed to [`Codec::deflate`](super::Codec::deflate).
+///
+/// The compressor always uses a 32kB window, so only the window of the peer can be limited:
+/// servers decline offers limiting the server window and clients never offer to limit their own.
+///
+/// ```rust
+/// use actix_http::ws::DeflateConfig;
+///
+/// let config = DeflateConfig::new().client_no_context_takeover(true);
+/// assert_eq!(
+///     config.offer(),
+///     "permessage-deflate; client_no_context_takeover"
+/// );
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DeflateConfig {
+    server_max_window_bits: u8,
+    client_max_window_bits: u8,
+    server_no_context_takeover: bool,
+    client_no_context_takeover: bool,
+}
+
+impl Default for DeflateConfig {
+    fn default() -> Self {
+        DeflateConfig {
+            server_max_window_bits: MAX_WINDOW_BITS,
+            client_max_window_bits: MAX_WINDOW_BITS,
+            server_no_context_takeover: false,
+            client_no_context_takeover: false,
+        }
+    }
+}
+
+impl DeflateConfig {
+    /// Create config with the largest windows and context takeover on both sides.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the base-2 logarithm of the LZ77 window the server compresses with.
+    ///
+    /// By default it is 15, a 32kB window. Clients request a smaller window from the server;
+    /// servers can not compress with a smaller window and decline the extension instead.
+    ///
+    /// # Panics
+    /// Panics if `bits` is not between 8 and 15.
+    pub fn server_max_window_bits(mut self, bits: u8) -> Self {
+        assert!(
+            (MIN_WINDOW_BITS..=MAX_WINDOW_BITS).contains(&bits),
+            "window bits must be within 8..=15"
+        );
+        self.server_max_window_bits = bits;
+        self
+    }
+
+    /// Set the base-2 logarithm of the LZ77 window the client compresses with.
+    ///
+    /// By default it is 15, a 32kB window. Servers limit the window of clients that support it;
+    /// clients always compress with a 32kB window and ignore this setting.
+    ///
+    /// # Panics
+    /// Panics if `bits` is not between 8 and 15.
+    pub fn client_max_window_bits(mut self, bits: u8) -> Self {
+        assert!(
+            (MIN_WINDOW_BITS..=MAX_WINDOW_BITS).contains(&bits),
+            "window bits must be within 8..=15"
+        );
+        self.client_max_window_bits = bits;
+        self
+    }
+
+    /// Reset the server's compression context after every message.
+    ///
+    /// Saves memory on the client at the cost of compression ratio.
+    pub fn server_no_context_takeover(mut self, enabled: bool) -> Self {
+        self.server_no_context_takeover = enabled;
+        self
+    }
+
+    /// Reset the client's compression context after every message.
+    ///
+    /// Saves memory on the server at the cost of compression ratio.
+    pub fn client_no_context_takeover(mut self, enabled: bool) -> Self {
+        self.client_no_context_takeover = enabled;
+        self
+    }
+
+    /// Accept the first acceptable `permessage-deflate` offer of a handshake request.
+    ///
+    /// Returns `None` if the client did not offer the extension or no offer can be accepted.
+    pub fn accept_offer(&self, headers: &HeaderMap) -> Option<DeflateConfig> {
+        extensions(headers).find_map(|params| self.accept_params(&params))
+    }
+
+    fn accept_params(&self, params: &[(&str, Option<&str>)]) -> Option<DeflateConfig> {
+        let mut agreed = *self;
+        let mut client_window = false;
+        let mut seen = Vec::with_capacity(params.len());
+
+        for &(name, value) in params {
+            if seen.contains(&name) {
+                return None;
+            }
+            seen.push(name);
+
+            match (name, value) {
+                (SERVER_NO_CONTEXT_TAKEOVER, None) => {
+                    agreed.server_no_context_takeover = true;
+                }
+                (CLIENT_NO_CONTEXT_TAKEOVER, None) => {
+                    agreed.client_no_context_takeover = true;
+                }
+                (SERVER_MAX_WINDOW_BITS, Some(value)) => {
+                    let bits = parse_window_bits(value)?;
+                    agreed.server_max_window_bits =
+                        bits.min(self.server_max_window_bits);
+                }
+                (CLIENT_MAX_WINDOW_BITS, value) => {
+                    client_window = true;
+                    if let Some(value) = value {
+                        let bits = parse_window_bits(value)?;
+                        agreed.client_max_window_bits =
+                            bits.min(self.client_max_window_bits);
+                    }
+                }
+                _ => return None,
+            }
+        }
+
+        // client window can only be limited if the client supports it
+        if !client_window {
+            agreed.client_max_window_bits = MAX_WINDOW_BITS;
+        }
+
+        // the compressor can not use a smaller window
+        if agreed.server_max_window_bits < MAX_WINDOW_BITS {
+            return None;
+        }
+
+        Some(agreed)
+    }
+
+    /// Value of the `Sec-WebSocket-Extensions` header of a handshake response accepting the
+    /// negotiated parameters.
+    pub fn response_header(&self) -> HeaderValue {
+        self.header_value(false)
+    }
+
+    /// Value of the `Sec-WebSocket-Extensions` header of a handshake request offering the
+    /// extension.
+    pub fn offer(&self) -> HeaderValue {
+        self.header_value(true)
+    }
+
+    /// Check the server's handshake response to an [`offer`](Self::offer).
+    ///
+    /// Returns `None` if the server declined the extension.
+    pub fn accept_response(
+        &self,
+        headers: &HeaderMap,
+    ) -> Result<Option<DeflateConfig>, DeflateResponseError> {
+        let mut responses = extensions(headers);
+
+        let params = match responses.next() {
+            Some(params) => params,
+            None => return Ok(None),
+        };
+
+        // the extension can only be accepted once
+        if responses.next().is_some() {
+            return Err(DeflateResponseError);
+        }
+
+        let mut agreed = *self;
+        agreed.client_max_window_bits = MAX_WINDOW_BITS;
+        let mut seen = Vec::with_capacity(params.len());
+
+        for (name, value) in params {
+            if seen.contains(&name) {
+                return Err(DeflateResponseError);
+            }
+            seen.push(name);
+
+            match (name, value) {
+                (SERVER_NO_CONTEXT_TAKEOVER, None) => {
+                    agreed.server_no_context_takeover = true;
+                }
+                (CLIENT_NO_CONTEXT_TAKEOVER, None) => {
+                    agreed.client_no_context_takeover = true;
+                }
+                (SERVER_MAX_WINDOW_BITS, Some(value)) => {
+                    match parse_window_bits(value) {
+                        Some(bits) if bits <= self.server_max_window_bits => {
+                            agreed.server_max_window_bits = bits;
+                        }
+                        _ => return Err(DeflateResponseError),
+                    }
+                }
+                // the client window can not be limited, so it is never offered
+                _ => return Err(DeflateResponseError),
+            }
+        }
+
+        Ok(Some(agreed))
+    }
+
+    fn header_value(&self, offer: bool) -> HeaderValue {
+        let mut value = String::from(EXTENSION);
+
+        if self.server_no_context_takeover {
+            value.push_str("; ");
+            value.push_str(SERVER_NO_CONTEXT_TAKEOVER);
+        }
+        if self.client_no_context_takeover {
+            value.push_str("; ");
+            value.push_str(CLIENT_NO_CONTEXT_TAKEOVER);
+        }
+        if self.server_max_window_bits < MAX_WINDOW_BITS {
+            value.push_str(&format!(
+                "; {}={}",
+                SERVER_MAX_WINDOW_BITS, self.server_max_window_bits
+            ));
+        }
+        if self.client_max_window_bits < MAX_WINDOW_BITS && !offer {
+            value.push_str(&format!(
+                "; {}={}",
+                CLIENT_MAX_WINDOW_BITS, self.client_max_window_bits
+            ));
+        }
+
+        HeaderValue::from_str(&value).unwrap()
+    }
+
+    /// Check if the compression context of the messages sent by one side is reset after every
+    /// message.
+    fn no_context_takeover(&self, server: bool) -> bool {
+        if server {
+            self.server_no_context_takeover
+        } else {
+            self.client_no_context_takeover
+        }
+    }
+}
+
+/// Parameters of every `permessage-deflate` entry of the `Sec-WebSocket-Extensions` headers.
+fn extensions(headers: &HeaderMap) -> impl Iterator<Item = Vec<(&str, Option<&str>)>> {
+    headers
+        .get_all(SEC_WEBSOCKET_EXTENSIONS)
+        .filter_map(|hdr| hdr.to_str().ok())
+        .flat_map(|hdr| hdr.split(','))
+        .filter_map(|ext| {
+            let mut parts = ext.split(';').map(str::trim);
+
+            if !parts.next()?.eq_ignore_ascii_case(EXTENSION) {
+                return None;
+            }
+
+            Some(
+                parts
+                    .map(|param| match param.find('=') {
+                        Some(idx) => (
+                            param[..idx].trim(),
+                            Some(param[idx + 1..].trim().trim_matches('"')),
+                        ),
+                        None => (param, None),
+                    })
+                    .collect(),
+            )
+        })
+}
+
+fn parse_window_bits(value: &str) -> Option<u8> {
+    // leading zeros are not allowed
+    if value.starts_with('0') {
+        return None;
+    }
+
+    value
+        .parse()
+        .ok()
+        .filter(|bits| (MIN_WINDOW_BITS..=MAX_WINDOW_BITS).contains(bits))
+}
+
+/// Compression state of a codec with negotiated `permessage-deflate`.
+pub(crate) struct DeflateContext {
+    config: DeflateConfig,
+    compress: Compress,
+    decompress: Decompress,
+    inflated: usize,
+}
+
+impl DeflateContext {
+    pub(crate) fn new(config: DeflateConfig) -> Self {
+        DeflateContext {
+            config,
+            compress: Compress::new(Compression::default(), false),
+            decompress: Decompress::new(false),
+            inflated: 0,
+        }
+    }
+
+    pub(crate) fn config(&self) -> DeflateConfig {
+        self.config
+    }
+
+    /// Compress a message fragment; `fin` marks the last fragment of the message.
+    pub(crate) fn compress(
+        &mut self,
+        server: bool,
+        data: &[u8],
+        fin: bool,
+    ) -> Result<Vec<u8>, ProtocolError> {
+        let mut out = Vec::with_capacity(data.len() / 2 + 64);
+        let start = self.compress.total_in();
+
+        loop {
+            let consumed = (self.compress.total_in() - start) as usize;
+            self.compress
+                .compress_vec(&data[consumed..], &mut out, FlushCompress::Sync)
+                .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+
+            let consumed = (self.compress.total_in() - start) as usize;
+            if consumed == data.len() && out.len() < out.capacity() {
+                break;
+            }
+            out.reserve(out.capacity().max(64));
+        }
+
+        if fin {
+            if out.ends_with(&TAIL) {
+                out.truncate(out.len() - TAIL.len());
+            }
+
+            if self.config.no_context_takeover(server) {
+                self.compress.reset();
+            }
+        }
+
+        Ok(out)
+    }
+
+    /// Decompress a received message fragment; `fin` marks the last fragment of the message.
+    ///
+    /// Fails with [`ProtocolError::Overflow`] once the decompressed message grows larger than
+    /// `max_size`.
+    pub(crate) fn decompress(
+        &mut self,
+        server: bool,
+        data: &[u8],
+        fin: bool,
+        max_size: usize,
+    ) -> Result<BytesMut, ProtocolError> {
+        let mut out = BytesMut::new();
+        let mut finished = false;
+
+        let tail: &[u8] = if fin { &TAIL } else { &[] };
+        for input in [data, tail].iter() {
+            if !finished {
+                finished = self.inflate(input, &mut out, max_size)?;
+            }
+        }
+
+        if fin {
+            self.inflated = 0;
+
+            // the peer is the sender of received messages
+            if finished || self.config.no_context_takeover(!server) {
+                self.decompress.reset(false);
+            }
+        }
+
+        Ok(out)
+    }
+
+    /// Inflate all of `input` into `out`; returns true if the deflate stream ended.
+    fn inflate(
+        &mut self,
+        input: &[u8],
+        out: &mut BytesMut,
+        max_size: usize,
+    ) -> Result<bool, ProtocolError> {
+        let start = self.decompress.total_in();
+
+        loop {
+            let consumed = (self.decompress.total_in() - start) as usize;
+            let len = out.len();
+            let chunk = (input.len() - consumed).max(1024) * 2;
+            out.resize(len + chunk, 0);
+
+            let before = self.decompress.total_out();
+            let status = self
+                .decompress
+                .decompress(&input[consumed..], &mut out[len..], FlushDecompress::Sync)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+            let produced = (self.decompress.total_out() - before) as usize;
+            out.truncate(len + produced);
+
+            self.inflated += produced;
+            if self.inflated > max_size {
+                return Err(ProtocolError::Overflow);
+            }
+
+            if let Status::StreamEnd = status {
+                return Ok(true);
+            }
+
+            // stop once all input is consumed and output space is left over, or on no progress
+            let now_consumed = (self.decompress.total_in() - start) as usize;
+            if (now_consumed == input.len() && produced < chunk)
+                || (now_consumed == consumed && produced == 0)
+            {
+                return Ok(false);
+            }
+        }
+    }
+}
+
+impl fmt::Debug for DeflateContext {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DeflateContext")
+            .field("config", &self.config)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_codec::{Decoder, Encoder};
+    use bytes::Bytes;
+    use http::header::HeaderValue;
+
+    use super::*;
+    use crate::ws::{Codec, Frame, Item, Message};
+
+    fn headers(value: &'static str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(SEC_WEBSOCKET_EXTENSIONS, HeaderValue::from_static(value));
+        headers
+    }
+
+    fn pair(config: DeflateConfig) -> (Codec, Codec) {
+        (
+            Codec::new().deflate(config),
+            Codec::new().client_mode().deflate(config),
+        )
+    }
+
+    #[test]
+    fn test_accept_offer() {
+        let config = DeflateConfig::new();
+
+        assert_eq!(config.accept_offer(&HeaderMap::new()), None);
+        assert_eq!(
+            config.accept_offer(&headers("x-webkit-deflate-frame")),
+            None
+        );
+
+        let agreed = config
+            .accept_offer(&headers("permessage-deflate; client_max_window_bits"))
+            .unwrap();
+        assert_eq!(agreed, config);
+        assert_eq!(agreed.response_header(), "permessage-deflate");
+
+        let agreed = config
+            .accept_offer(&headers(
+                "permessage-deflate; server_max_window_bits=15; client_no_context_takeover",
+            ))
+            .unwrap();
+        assert_eq!(
+            agreed.response_header(),
+            "permessage-deflate; client_no_context_takeover"
+        );
+
+        // the server window can not be limited
+        assert_eq!(
+            config
+                .accept_offer(&headers("permessage-deflate; server_max_window_bits=10")),
+            None
+        );
+        assert_eq!(
+            DeflateConfig::new()
+                .server_max_window_bits(12)
+                .accept_offer(&headers("permessage-deflate")),
+            None
+        );
+
+        // first acceptable offer wins
+        let agreed = config
+            .accept_offer(&headers(
+                "permessage-deflate; unknown, permessage-deflate; server_max_window_bits=08, \
+                 permessage-deflate; server_no_context_takeover",
+            ))
+            .unwrap();
+        assert_eq!(
+            agreed.response_header(),
+            "permessage-deflate; server_no_context_takeover"
+        );
+
+        // client window is only limited when the client supports it
+        let config = DeflateConfig::new().client_max_window_bits(9);
+        let agreed = config.accept_offer(&headers("permessage-deflate")).unwrap();
+        assert_eq!(agreed.response_header(), "permessage-deflate");
+        let agreed = config
+            .accept_offer(&headers("permessage-deflate; client_max_window_bits=12"))
+            .unwrap();
+        assert_eq!(
+            agreed.response_header(),
+            "permessage-deflate; client_max_window_bits=9"
+        );
+    }
+
+    #[test]
+    fn test_accept_response() {
+        let config = DeflateConfig::new()
+            .server_max_window_bits(12)
+            .client_max_window_bits(10);
+        assert_eq!(
+            config.offer(),
+            "permessage-deflate; server_max_window_bits=12"
+        );
+
+        assert_eq!(config.accept_response(&HeaderMap::new()).unwrap(), None);
+
+        let agreed = config
+            .accept_response(&headers(
+                "permessage-deflate; server_max_window_bits=10; client_no_context_takeover",
+            ))
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            agreed,
+            config
+                .server_max_window_bits(10)
+                .client_max_window_bits(15)
+                .client_no_context_takeover(true)
+        );
+
+        for invalid in &[
+            "permessage-deflate; server_max_window_bits=15",
+            "permessage-deflate; client_max_window_bits",
+            "permessage-deflate; client_max_window_bits=10",
+            "permessage-deflate; unknown",
+            "permessage-deflate; server_no_context_takeover; server_no_context_takeover",
+            "permessage-deflate, permessage-deflate",
+        ] {
+            assert!(config.accept_response(&headers(invalid)).is_err());
+        }
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let (mut server, mut client) = pair(DeflateConfig::new());
+        let text = "compressible ".repeat(100);
+
+        for _ in 0..2 {
+            let mut buf = BytesMut::new();
+            server
+                .encode(Message::Text(text.clone().into()), &mut buf)
+                .unwrap();
+            assert_eq!(buf[0] & 0x40, 0x40);
+            assert!(buf.len() < text.len() / 4);
+            assert_eq!(
+                client.decode(&mut buf).unwrap().unwrap(),
+                Frame::Text(Bytes::from(text.clone()))
+            );
+        }
+
+        // fragmented message
+        let mut buf = BytesMut::new();
+        for item in vec![
+            Item::FirstBinary(Bytes::from(text.clone())),
+            Item::Continue(Bytes::from(text.clone())),
+            Item::Last(Bytes::new()),
+        ] {
+            client
+                .encode(Message::Continuation(item), &mut buf)
+                .unwrap();
+        }
+        let mut received = Vec::new();
+        while let Some(frame) = server.decode(&mut buf).unwrap() {
+            match frame {
+                Frame::Continuation(Item::FirstBinary(data))
+                | Frame::Continuation(Item::Continue(data))
+                | Frame::Continuation(Item::Last(data)) => {
+                    received.extend_from_slice(&data)
+                }
+                frame => panic!("unexpected frame: {:?}", frame),
+            }
+        }
+        assert_eq!(received, text.repeat(2).as_bytes());
+
+        // control frames are never compressed
+        let mut buf = BytesMut::new();
+        server
+            .encode(Message::Ping(Bytes::from_static(b"ping")), &mut buf)
+            .unwrap();
+        assert_eq!(buf[0] & 0x40, 0);
+    }
+
+    #[test]
+    fn test_split_compressed() {
+        let (server, mut client) = pair(DeflateConfig::new());
+        let mut server = server.max_frame_size(8);
+
+        let data = Bytes::from((0..200u8).collect::<Vec<_>>());
+        let mut buf = BytesMut::new();
+        server
+            .encode(Message::Binary(data.clone()), &mut buf)
+            .unwrap();
+
+        // RSV1 is only set on the first frame
+        assert_eq!(buf[0] & 0x40, 0x40);
+        assert_eq!(buf[10] & 0x40, 0);
+
+        let mut received = Vec::new();
+        while let Some(frame) = client.decode(&mut buf).unwrap() {
+            match frame {
+                Frame::Continuation(Item::FirstBinary(data))
+                | Frame::Continuation(Item::Continue(data))
+                | Frame::Continuation(Item::Last(data)) => {
+                    received.extend_from_slice(&data)
+                }
+                frame => panic!("unexpected frame: {:?}", frame),
+            }
+        }
+        assert_eq!(received, data);
+    }
+
+    #[test]
+    fn test_limited_peer_window() {
+        // the client's decompressor accepts a limited server window, and the server compresses
+        // messages larger than that window with its own 32kB window
+        let config = DeflateConfig::new().client_max_window_bits(9);
+        let (mut server, mut client) = pair(config);
+
+        let text = "a".repeat(4096);
+
+        let mut buf = BytesMut::new();
+        server
+            .encode(Message::Text(text.clone().into()), &mut buf)
+            .unwrap();
+        assert_eq!(buf[0] & 0x40, 0x40);
+        assert_eq!(
+            client.decode(&mut buf).unwrap().unwrap(),
+            Frame::Text(Bytes::from(text))
+        );
+    }
+
+    #[test]
+    fn test_decompressed_size_limit() {
+        let (mut server, client) = pair(DeflateConfig::new());
+        let mut client = client.max_size(1024);
+
+        let mut buf = BytesMut::new();
+        server
+            .encode(Message::Binary(Bytes::from(vec![0; 1025])), &mut buf)
+            .unwrap();
+        assert!(buf.len() < 1024);
+
+        match client.decode(&mut buf) {
+            Err(ProtocolError::Overflow) => {}
+            res => panic!("expected overflow, got {:?}", res),
+        }
+    }
+
+    #[test]
+    fn test_reserved_bits_without_deflate() {
+        let (mut server, _) = pair(DeflateConfig::new());
+        let mut client = Codec::new().client_mode();
+
+        let mut buf = BytesMut::new();
+        server
+            .encode(Message::Text("text".into()), &mut buf)
+            .unwrap();
+
+        match client.decode(&mut buf) {
+            Err(ProtocolError::ReservedBits) => {}
+            res => panic!("expected reserved bits error, got {:?}", res),
+        }
+    }
+}
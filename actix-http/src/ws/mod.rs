@@ -3,7 +3,7 @@
 //! To setup a WebSocket, first perform the WebSocket handshake then on success convert `Payload` into a
 //! `WsStream` stream and then use `WsWriter` to communicate with the peer.
 
-use std::io;
+use std::{fmt, io};
 
 use derive_more::{Display, Error, From};
 use http::{header, Method, StatusCode};
@@ -13,12 +13,16 @@ use crate::message::RequestHead;
 use crate::response::{Response, ResponseBuilder};
 
 mod codec;
+#[cfg(feature = "compress")]
+mod deflate;
 mod dispatcher;
 mod frame;
 mod mask;
 mod proto;
 
 pub use self::codec::{Codec, Frame, Item, Message};
+#[cfg(feature = "compress")]
+pub use self::deflate::{DeflateConfig, DeflateResponseError};
 pub use self::dispatcher::Dispatcher;
 pub use self::frame::Parser;
 pub use self::proto::{hash_key, CloseCode, CloseReason, OpCode};
@@ -58,6 +62,10 @@ pub enum ProtocolError {
     #[display(fmt = "Received new continuation but it is already started.")]
     ContinuationStarted,
 
+    /// Received a frame with a reserved bit set that no negotiated extension uses.
+    #[display(fmt = "Received a frame with reserved bits set.")]
+    ReservedBits,
+
     /// Unknown continuation fragment.
     #[display(fmt = "Unknown continuation fragment: {}.", _0)]
     ContinuationFragment(#[error(not(source))] OpCode),
@@ -95,6 +103,10 @@ pub enum HandshakeError {
     /// WebSocket key is not set or wrong.
     #[display(fmt = "Unknown websocket key.")]
     BadWebsocketKey,
+
+    /// None of the subprotocols offered by the client is supported.
+    #[display(fmt = "Unsupported WebSocket subprotocol.")]
+    UnsupportedProtocol,
 }
 
 impl ResponseError for HandshakeError {
@@ -123,6 +135,10 @@ impl ResponseError for HandshakeError {
             HandshakeError::BadWebsocketKey => {
                 Response::BadRequest().reason("Handshake error").finish()
             }
+
+            HandshakeError::UnsupportedProtocol => Response::BadRequest()
+                .reason("Unsupported WebSocket protocol")
+                .finish(),
         }
     }
 }
@@ -133,6 +149,129 @@ pub fn handshake(req: &RequestHead) -> Result<ResponseBuilder, HandshakeError> {
     Ok(handshake_response(req))
 }
 
+/// Subprotocol negotiation options of a WebSocket handshake.
+///
+/// ```rust
+/// use actix_http::ws::WsHandshakeOptions;
+///
+/// let options = WsHandshakeOptions {
+///     protocols: &["graphql-ws", "chat"],
+///     ..Default::default()
+/// };
+/// # let _ = options;
+/// ```
+#[derive(Clone, Copy)]
+pub struct WsHandshakeOptions<'a> {
+    /// Subprotocols supported by the server.
+    pub protocols: &'a [&'a str],
+
+    /// Picks the subprotocol to use from the supported ones offered by the client, which are
+    /// given in the client's order of preference.
+    ///
+    /// By default the client's most preferred protocol is used.
+    pub select: for<'p> fn(&[&'p str]) -> Option<&'p str>,
+}
+
+impl<'a> WsHandshakeOptions<'a> {
+    /// Negotiate the subprotocol of a handshake request.
+    ///
+    /// Returns `None` if the client does not offer any subprotocol or the server does not
+    /// support any. Fails with [`HandshakeError::UnsupportedProtocol`] if the server supports
+    /// subprotocols but none of the offered ones is selected.
+    pub fn negotiate(
+        &self,
+        req: &RequestHead,
+    ) -> Result<Option<&'a str>, HandshakeError> {
+        let offered = req
+            .headers()
+            .get_all(header::SEC_WEBSOCKET_PROTOCOL)
+            .filter_map(|hdr| hdr.to_str().ok())
+            .flat_map(|hdr| hdr.split(','))
+            .map(str::trim)
+            .filter(|protocol| !protocol.is_empty())
+            .collect::<Vec<_>>();
+
+        if offered.is_empty() || self.protocols.is_empty() {
+            return Ok(None);
+        }
+
+        let supported = offered
+            .iter()
+            .filter_map(|offered| self.protocols.iter().find(|p| *p == offered).copied())
+            .collect::<Vec<_>>();
+
+        match (self.select)(&supported) {
+            Some(protocol) => Ok(Some(protocol)),
+            None => Err(HandshakeError::UnsupportedProtocol),
+        }
+    }
+}
+
+impl Default for WsHandshakeOptions<'_> {
+    fn default() -> Self {
+        WsHandshakeOptions {
+            protocols: &[],
+            select: select_first,
+        }
+    }
+}
+
+impl fmt::Debug for WsHandshakeOptions<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WsHandshakeOptions")
+            .field("protocols", &self.protocols)
+            .finish()
+    }
+}
+
+fn select_first<'p>(protocols: &[&'p str]) -> Option<&'p str> {
+    protocols.first().copied()
+}
+
+/// Verify WebSocket handshake request and create handshake response with the negotiated
+/// subprotocol.
+pub fn handshake_with(
+    req: &RequestHead,
+    options: WsHandshakeOptions<'_>,
+) -> Result<ResponseBuilder, HandshakeError> {
+    verify_handshake(req)?;
+    let protocol = options.negotiate(req)?;
+
+    let mut res = handshake_response(req);
+    if let Some(protocol) = protocol {
+        res.insert_header((header::SEC_WEBSOCKET_PROTOCOL, protocol));
+    }
+
+    Ok(res)
+}
+
+/// Verify WebSocket handshake request and create handshake response, accepting the
+/// `permessage-deflate` extension if the client offers it.
+///
+/// Also returns the codec for the connection, which compresses messages if the extension was
+/// negotiated.
+#[cfg(feature = "compress")]
+pub fn handshake_with_deflate(
+    req: &RequestHead,
+    config: &DeflateConfig,
+) -> Result<(ResponseBuilder, Codec), HandshakeError> {
+    verify_handshake(req)?;
+
+    let mut res = handshake_response(req);
+    let codec = match config.accept_offer(req.headers()) {
+        Some(agreed) => {
+            res.insert_header((
+                header::SEC_WEBSOCKET_EXTENSIONS,
+                agreed.response_header(),
+            ));
+            Codec::new().deflate(agreed)
+        }
+        None => Codec::new(),
+    };
+
+    Ok((res, codec))
+}
+
 /// Verify WebSocket handshake request.
 pub fn verify_handshake(req: &RequestHead) -> Result<(), HandshakeError> {
     // WebSocket accepts only GET
@@ -201,6 +340,7 @@ pub fn handshake_response(req: &RequestHead) -> ResponseBuilder {
 mod tests {
     use super::*;
     use crate::test::TestRequest;
+    use crate::Request;
     use http::{header, Method};
 
     #[test]
@@ -327,5 +467,71 @@ mod tests {
         assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
         let resp: Response = HandshakeError::BadWebsocketKey.error_response();
         assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+        let resp: Response = HandshakeError::UnsupportedProtocol.error_response();
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[test]
+    fn test_handshake_with() {
+        fn request(protocols: Option<&'static str>) -> Request {
+            let mut req = TestRequest::default();
+            req.insert_header((header::UPGRADE, "websocket"))
+                .insert_header((header::CONNECTION, "upgrade"))
+                .insert_header((header::SEC_WEBSOCKET_VERSION, "13"))
+                .insert_header((header::SEC_WEBSOCKET_KEY, "13"));
+            if let Some(protocols) = protocols {
+                req.insert_header((header::SEC_WEBSOCKET_PROTOCOL, protocols));
+            }
+            req.finish()
+        }
+
+        fn protocol(res: Result<ResponseBuilder, HandshakeError>) -> Option<String> {
+            let res = res.unwrap().finish();
+            assert_eq!(res.status(), StatusCode::SWITCHING_PROTOCOLS);
+            res.headers()
+                .get(header::SEC_WEBSOCKET_PROTOCOL)
+                .map(|hdr| hdr.to_str().unwrap().to_owned())
+        }
+
+        let options = WsHandshakeOptions {
+            protocols: &["graphql-ws", "chat"],
+            ..Default::default()
+        };
+
+        // client's most preferred supported protocol is selected
+        let req = request(Some("superchat, chat, graphql-ws"));
+        assert_eq!(
+            protocol(handshake_with(req.head(), options)).as_deref(),
+            Some("chat")
+        );
+
+        // none offered
+        let req = request(None);
+        assert_eq!(protocol(handshake_with(req.head(), options)), None);
+
+        // none supported
+        let req = request(Some("superchat"));
+        assert_eq!(
+            handshake_with(req.head(), options).err(),
+            Some(HandshakeError::UnsupportedProtocol)
+        );
+
+        // servers without subprotocols ignore the offer
+        let req = request(Some("superchat"));
+        assert_eq!(
+            protocol(handshake_with(req.head(), WsHandshakeOptions::default())),
+            None
+        );
+
+        // custom selection
+        let options = WsHandshakeOptions {
+            select: |protocols| protocols.last().copied(),
+            ..options
+        };
+        let req = request(Some("chat, graphql-ws"));
+        assert_eq!(
+            protocol(handshake_with(req.head(), options)).as_deref(),
+            Some("graphql-ws")
+        );
     }
 }
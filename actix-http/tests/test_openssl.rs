@@ -416,7 +416,7 @@ async fn test_h2_on_connect() {
                 data.insert(20isize);
             })
             .h2(|req: Request| {
-                assert!(req.extensions().contains::<isize>());
+                assert_eq!(req.conn_data::<isize>(), Some(&20));
                 ok::<_, ()>(Response::Ok().finish())
             })
             .openssl(tls_config())
@@ -1,5 +1,5 @@
 use std::io::{Read, Write};
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use std::{net, thread};
 
 use actix_http_test::test_server;
@@ -10,9 +10,11 @@ use futures_util::future::{self, err, ok, ready, FutureExt};
 use futures_util::stream::{once, StreamExt};
 use regex::Regex;
 
+use actix_http::h2::ServerPush;
 use actix_http::HttpMessage;
 use actix_http::{
-    body, error, http, http::header, Error, HttpService, KeepAlive, Request, Response,
+    body, error, http, http::header, ConnectionStats, Error, GracefulShutdown,
+    HttpService, KeepAlive, Request, Response,
 };
 
 #[actix_rt::test]
@@ -54,6 +56,447 @@ async fn test_h1_2() {
     assert!(response.status().is_success());
 }
 
+#[actix_rt::test]
+async fn test_h2_settings() {
+    let srv = test_server(|| {
+        HttpService::build()
+            .h2_max_concurrent_streams(7)
+            .h2_initial_window_size(1_000_000)
+            .h2_initial_connection_window_size(2_000_000)
+            .h2_max_frame_size(32_768)
+            .h2(|_| future::ok::<_, ()>(Response::Ok().finish()))
+            .tcp()
+    })
+    .await;
+
+    let mut stream = net::TcpStream::connect(srv.addr()).unwrap();
+    stream
+        .set_read_timeout(Some(Duration::from_secs(5)))
+        .unwrap();
+
+    // connection preface followed by an empty SETTINGS frame
+    stream
+        .write_all(b"PRI * HTTP/2.0\r\n\r\nSM\r\n\r\n")
+        .unwrap();
+    stream.write_all(&[0, 0, 0, 4, 0, 0, 0, 0, 0]).unwrap();
+
+    let mut settings = Vec::new();
+    let mut window_update = None;
+
+    while settings.is_empty() || window_update.is_none() {
+        let mut head = [0; 9];
+        stream.read_exact(&mut head).unwrap();
+        let len = u32::from_be_bytes([0, head[0], head[1], head[2]]) as usize;
+        let stream_id = u32::from_be_bytes([head[5], head[6], head[7], head[8]]);
+        let mut payload = vec![0; len];
+        stream.read_exact(&mut payload).unwrap();
+
+        match head[3] {
+            // SETTINGS, not an acknowledgement
+            4 if head[4] & 1 == 0 => {
+                settings = payload
+                    .chunks(6)
+                    .map(|setting| {
+                        let id = u16::from_be_bytes([setting[0], setting[1]]);
+                        let value = u32::from_be_bytes([
+                            setting[2], setting[3], setting[4], setting[5],
+                        ]);
+                        (id, value)
+                    })
+                    .collect();
+            }
+
+            // WINDOW_UPDATE of the connection
+            8 if stream_id == 0 => {
+                window_update = Some(u32::from_be_bytes([
+                    payload[0], payload[1], payload[2], payload[3],
+                ]));
+            }
+
+            _ => {}
+        }
+    }
+
+    assert!(settings.contains(&(0x3, 7)));
+    assert!(settings.contains(&(0x4, 1_000_000)));
+    assert!(settings.contains(&(0x5, 32_768)));
+    assert_eq!(window_update, Some(2_000_000 - 65_535));
+}
+
+#[actix_rt::test]
+async fn test_h2c_prior_knowledge() {
+    let mut srv = test_server(|| {
+        HttpService::build()
+            .allow_h2c()
+            .finish(|req: Request| {
+                let version = format!("{:?}", req.version());
+                future::ok::<_, ()>(Response::Ok().body(version))
+            })
+            .tcp()
+    })
+    .await;
+
+    // HTTP/1.1 clients are unaffected
+    let response = srv.get("/").send().await.unwrap();
+    assert!(response.status().is_success());
+    assert_eq!(srv.load_body(response).await.unwrap(), "HTTP/1.1");
+
+    let tcp = actix_rt::net::TcpStream::connect(srv.addr()).await.unwrap();
+    let (mut client, connection) = h2::client::handshake(tcp).await.unwrap();
+    actix_rt::spawn(async move {
+        let _ = connection.await;
+    });
+
+    for _ in 0..2 {
+        let req = ::http::Request::get(format!("http://{}/", srv.addr()))
+            .body(())
+            .unwrap();
+        let (response, _) = client.send_request(req, true).unwrap();
+        let response = response.await.unwrap();
+        assert_eq!(response.status(), http::StatusCode::OK);
+
+        let mut body = response.into_body();
+        let mut bytes = Vec::new();
+        while let Some(chunk) = body.data().await {
+            bytes.extend_from_slice(&chunk.unwrap());
+        }
+        assert_eq!(bytes, b"HTTP/2.0");
+    }
+}
+
+#[actix_rt::test]
+async fn test_h2c_split_preface() {
+    let srv = test_server(|| {
+        HttpService::build()
+            .allow_h2c()
+            .finish(|_| future::ok::<_, ()>(Response::Ok().finish()))
+            .tcp()
+    })
+    .await;
+
+    let mut stream = net::TcpStream::connect(srv.addr()).unwrap();
+    stream
+        .set_read_timeout(Some(Duration::from_secs(5)))
+        .unwrap();
+
+    // bytes read while detecting the preface are handed to the HTTP/2 dispatcher
+    stream.write_all(b"PRI * HTTP/2.0\r\n").unwrap();
+    thread::sleep(Duration::from_millis(50));
+    stream.write_all(b"\r\nSM\r\n\r\n").unwrap();
+    stream.write_all(&[0, 0, 0, 4, 0, 0, 0, 0, 0]).unwrap();
+
+    let mut head = [0; 9];
+    stream.read_exact(&mut head).unwrap();
+    assert_eq!(head[3], 4, "expected a SETTINGS frame");
+
+    // and to the HTTP/1.x dispatcher
+    let mut stream = net::TcpStream::connect(srv.addr()).unwrap();
+    stream
+        .set_read_timeout(Some(Duration::from_secs(5)))
+        .unwrap();
+
+    stream.write_all(b"P").unwrap();
+    thread::sleep(Duration::from_millis(50));
+    stream
+        .write_all(b"OST / HTTP/1.1\r\ncontent-length: 0\r\n\r\n")
+        .unwrap();
+
+    let mut data = [0; 15];
+    stream.read_exact(&mut data).unwrap();
+    assert_eq!(&data, b"HTTP/1.1 200 OK");
+}
+
+#[actix_rt::test]
+async fn test_h2_server_push() {
+    let srv = test_server(|| {
+        HttpService::build()
+            .allow_h2c()
+            .finish(|req: Request| {
+                let res = match req.path() {
+                    "/" => {
+                        let push =
+                            req.extensions().get::<ServerPush>().cloned().unwrap();
+
+                        let results = ["/style.css", "/script.js"]
+                            .iter()
+                            .map(|path| {
+                                let mut headers = header::HeaderMap::new();
+                                headers.insert(
+                                    header::ACCEPT,
+                                    header::HeaderValue::from_static("text/css"),
+                                );
+
+                                match push.push(path.parse().unwrap(), headers) {
+                                    Ok(()) => "ok".to_owned(),
+                                    Err(err) => err.to_string(),
+                                }
+                            })
+                            .collect::<Vec<_>>();
+
+                        Response::Ok().body(results.join("\n"))
+                    }
+                    "/style.css" | "/script.js" => {
+                        assert_eq!(
+                            req.headers().get(header::ACCEPT).unwrap(),
+                            "text/css"
+                        );
+                        Response::Ok().body("body {}")
+                    }
+                    _ => Response::NotFound().finish(),
+                };
+
+                future::ok::<_, ()>(res)
+            })
+            .tcp()
+    })
+    .await;
+
+    async fn read_body(body: h2::RecvStream) -> Vec<u8> {
+        let mut body = body;
+        let mut bytes = Vec::new();
+        while let Some(chunk) = body.data().await {
+            bytes.extend_from_slice(&chunk.unwrap());
+        }
+        bytes
+    }
+
+    // client accepting a single pushed stream at a time
+    let tcp = actix_rt::net::TcpStream::connect(srv.addr()).await.unwrap();
+    let (mut client, connection) = h2::client::Builder::new()
+        .max_concurrent_streams(1)
+        .handshake::<_, Bytes>(tcp)
+        .await
+        .unwrap();
+    actix_rt::spawn(async move {
+        let _ = connection.await;
+    });
+
+    let req = ::http::Request::get(format!("http://{}/", srv.addr()))
+        .body(())
+        .unwrap();
+    let (mut response, _) = client.send_request(req, true).unwrap();
+    let mut promises = response.push_promises();
+
+    let response = response.await.unwrap();
+    let body = read_body(response.into_body()).await;
+    assert_eq!(
+        String::from_utf8(body).unwrap(),
+        "ok\nToo many concurrent pushed streams"
+    );
+
+    let promise = promises.push_promise().await.unwrap().unwrap();
+    let (promised, pushed) = promise.into_parts();
+    assert_eq!(promised.method(), http::Method::GET);
+    assert_eq!(promised.uri().path(), "/style.css");
+    assert_eq!(
+        promised.uri().authority(),
+        Some(&srv.addr().to_string().parse().unwrap())
+    );
+
+    let pushed = pushed.await.unwrap();
+    assert_eq!(pushed.status(), http::StatusCode::OK);
+    assert_eq!(read_body(pushed.into_body()).await, b"body {}");
+    assert!(promises.push_promise().await.is_none());
+
+    // client with server push disabled
+    let tcp = actix_rt::net::TcpStream::connect(srv.addr()).await.unwrap();
+    let (mut client, connection) = h2::client::Builder::new()
+        .max_concurrent_streams(10)
+        .enable_push(false)
+        .handshake::<_, Bytes>(tcp)
+        .await
+        .unwrap();
+    actix_rt::spawn(async move {
+        let _ = connection.await;
+    });
+
+    let req = ::http::Request::get(format!("http://{}/", srv.addr()))
+        .body(())
+        .unwrap();
+    let (response, _) = client.send_request(req, true).unwrap();
+    let response = response.await.unwrap();
+    let body = String::from_utf8(read_body(response.into_body()).await).unwrap();
+    assert!(body.lines().all(|line| line != "ok"));
+}
+
+#[actix_rt::test]
+async fn test_h2_flow_control() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    const CHUNK: usize = 1024;
+    const CHUNKS: usize = 256;
+    const WINDOW: u32 = 1024;
+
+    let produced = Arc::new(AtomicUsize::new(0));
+    let stats = ConnectionStats::new();
+
+    let srv = test_server({
+        let produced = produced.clone();
+        let stats = stats.clone();
+
+        move || {
+            let produced = produced.clone();
+
+            HttpService::build()
+                .allow_h2c()
+                .stats(stats.clone())
+                .finish(move |_: Request| {
+                    let produced = produced.clone();
+                    let body = futures_util::stream::iter(0..CHUNKS).map(move |_| {
+                        produced.fetch_add(CHUNK, Ordering::SeqCst);
+                        Ok::<_, Error>(Bytes::from_static(&[b'x'; CHUNK]))
+                    });
+
+                    future::ok::<_, ()>(Response::Ok().streaming(body))
+                })
+                .tcp()
+        }
+    })
+    .await;
+
+    let tcp = actix_rt::net::TcpStream::connect(srv.addr()).await.unwrap();
+    let (mut client, connection) = h2::client::Builder::new()
+        .initial_window_size(WINDOW)
+        .initial_connection_window_size(WINDOW)
+        .handshake::<_, Bytes>(tcp)
+        .await
+        .unwrap();
+    actix_rt::spawn(async move {
+        let _ = connection.await;
+    });
+
+    let req = ::http::Request::get(format!("http://{}/", srv.addr()))
+        .body(())
+        .unwrap();
+    let (response, _) = client.send_request(req, true).unwrap();
+    let mut body = response.await.unwrap().into_body();
+
+    let mut received = 0;
+    while let Some(chunk) = body.data().await {
+        let chunk = chunk.unwrap();
+        received += chunk.len();
+
+        // the server produces at most one chunk ahead of the granted window
+        let ahead = produced.load(Ordering::SeqCst) - received;
+        assert!(ahead <= WINDOW as usize + CHUNK, "{} bytes buffered", ahead);
+
+        body.flow_control().release_capacity(chunk.len()).unwrap();
+    }
+
+    assert_eq!(received, CHUNK * CHUNKS);
+    assert!(stats.h2_window_stall_time() > Duration::from_secs(0));
+}
+
+#[actix_rt::test]
+async fn test_h2_max_connection_age() {
+    let srv = test_server(|| {
+        HttpService::build()
+            .allow_h2c()
+            .max_connection_age(Duration::from_millis(200))
+            .finish(|req: Request| async move {
+                if req.path() == "/slow" {
+                    sleep(Duration::from_millis(800)).await;
+                }
+                Ok::<_, ()>(Response::Ok().body(req.path().to_owned()))
+            })
+            .tcp()
+    })
+    .await;
+
+    let tcp = actix_rt::net::TcpStream::connect(srv.addr()).await.unwrap();
+    let (mut client, connection) = h2::client::handshake(tcp).await.unwrap();
+    actix_rt::spawn(async move {
+        let _ = connection.await;
+    });
+
+    let req = ::http::Request::get(format!("http://{}/slow", srv.addr()))
+        .body(())
+        .unwrap();
+    let (in_flight, _) = client.send_request(req, true).unwrap();
+
+    // connection reaches its maximum age while the first stream is in-flight
+    sleep(Duration::from_millis(500)).await;
+
+    let refused = async {
+        let req = ::http::Request::get(format!("http://{}/", srv.addr()))
+            .body(())
+            .unwrap();
+        let (response, _) = client.send_request(req, true)?;
+        response.await
+    }
+    .await;
+    assert!(refused.is_err());
+
+    let response = in_flight.await.unwrap();
+    assert_eq!(response.status(), http::StatusCode::OK);
+
+    let mut body = response.into_body();
+    let mut bytes = Vec::new();
+    while let Some(chunk) = body.data().await {
+        bytes.extend_from_slice(&chunk.unwrap());
+    }
+    assert_eq!(bytes, b"/slow");
+}
+
+#[actix_rt::test]
+async fn test_graceful_shutdown() {
+    let shutdown = GracefulShutdown::new();
+
+    let srv = test_server({
+        let shutdown = shutdown.clone();
+        move || {
+            HttpService::build()
+                .graceful_shutdown(shutdown.clone())
+                .h1(|_| future::ok::<_, ()>(Response::Ok().finish()))
+                .tcp()
+        }
+    })
+    .await;
+
+    let response = srv.get("/").send().await.unwrap();
+    assert!(response.headers().get(header::CONNECTION).is_none());
+
+    shutdown.shutdown();
+
+    // HTTP/1.1 connections stop keep-alive
+    let response = srv.get("/").send().await.unwrap();
+    assert_eq!(response.headers().get(header::CONNECTION).unwrap(), "close");
+}
+
+#[actix_rt::test]
+async fn test_h2c_disabled() {
+    let srv = test_server(|| {
+        HttpService::build()
+            .finish(|_| future::ok::<_, ()>(Response::Ok().finish()))
+            .tcp()
+    })
+    .await;
+
+    let tcp = actix_rt::net::TcpStream::connect(srv.addr()).await.unwrap();
+    let result = async {
+        let (mut client, connection) = h2::client::handshake(tcp).await?;
+        actix_rt::spawn(async move {
+            let _ = connection.await;
+        });
+
+        let req = ::http::Request::get("/").body(()).unwrap();
+        let (response, _) = client.send_request(req, true)?;
+        response.await
+    }
+    .await;
+
+    assert!(result.is_err());
+}
+
+#[test]
+#[should_panic(expected = "max frame size")]
+fn test_h2_settings_validation() {
+    let _ = HttpService::<actix_rt::net::TcpStream, _, _>::build()
+        .h2_max_frame_size(1024)
+        .h2(|_| future::ok::<_, ()>(Response::Ok().finish()));
+}
+
 #[actix_rt::test]
 async fn test_expect_continue() {
     let srv = test_server(|| {
@@ -246,6 +689,85 @@ async fn test_http1_keepalive_timeout() {
     assert_eq!(res, 0);
 }
 
+#[actix_rt::test]
+async fn test_http1_keepalive_peer_timeout() {
+    let srv = test_server(|| {
+        HttpService::build()
+            .keep_alive(5)
+            .h1(|_| future::ok::<_, ()>(Response::Ok().finish()))
+            .tcp()
+    })
+    .await;
+
+    let mut stream = net::TcpStream::connect(srv.addr()).unwrap();
+    let _ = stream
+        .write_all(b"GET /test/tests/test HTTP/1.1\r\nkeep-alive: timeout=1\r\n\r\n");
+    let mut data = vec![0; 1024];
+    let _ = stream.read(&mut data);
+    assert_eq!(&data[..17], b"HTTP/1.1 200 OK\r\n");
+
+    // the connection is closed after the shorter timeout requested by the client
+    stream
+        .set_read_timeout(Some(Duration::from_millis(3000)))
+        .unwrap();
+    let start = Instant::now();
+    let mut data = vec![0; 1024];
+    let res = stream.read(&mut data).unwrap();
+    assert_eq!(res, 0);
+    assert!(start.elapsed() < Duration::from_millis(2500));
+}
+
+#[actix_rt::test]
+async fn test_http1_keepalive_advertise() {
+    let srv = test_server(|| {
+        HttpService::build()
+            .keep_alive(5)
+            .advertise_keep_alive(true)
+            .h1(|_| future::ok::<_, ()>(Response::Ok().finish()))
+            .tcp()
+    })
+    .await;
+
+    let mut stream = net::TcpStream::connect(srv.addr()).unwrap();
+    let mut request = |req: &[u8]| {
+        let _ = stream.write_all(req);
+        let mut data = vec![0; 1024];
+        let n = stream.read(&mut data).unwrap();
+        String::from_utf8(data[..n].to_vec()).unwrap()
+    };
+
+    let res = request(b"GET / HTTP/1.1\r\n\r\n");
+    assert!(res.contains("keep-alive: timeout=5\r\n"), "{}", res);
+
+    let res = request(b"GET / HTTP/1.1\r\nKeep-Alive: timeout=2, max=10\r\n\r\n");
+    assert!(res.contains("keep-alive: timeout=2\r\n"), "{}", res);
+
+    // a longer timeout than configured is ignored
+    let res = request(b"GET / HTTP/1.1\r\nKeep-Alive: timeout=60\r\n\r\n");
+    assert!(res.contains("keep-alive: timeout=5\r\n"), "{}", res);
+
+    let res = request(b"GET / HTTP/1.1\r\nconnection: close\r\n\r\n");
+    assert!(res.starts_with("HTTP/1.1 200 OK\r\n"), "{}", res);
+    assert!(!res.contains("keep-alive"), "{}", res);
+}
+
+#[actix_rt::test]
+async fn test_http1_keepalive_not_advertised() {
+    let srv = test_server(|| {
+        HttpService::build()
+            .keep_alive(5)
+            .h1(|_| future::ok::<_, ()>(Response::Ok().finish()))
+            .tcp()
+    })
+    .await;
+
+    let mut stream = net::TcpStream::connect(srv.addr()).unwrap();
+    let _ = stream.write_all(b"GET / HTTP/1.1\r\n\r\n");
+    let mut data = vec![0; 1024];
+    let n = stream.read(&mut data).unwrap();
+    assert!(!String::from_utf8_lossy(&data[..n]).contains("keep-alive"));
+}
+
 #[actix_rt::test]
 async fn test_http1_keepalive_close() {
     let srv = test_server(|| {
@@ -553,6 +1075,78 @@ async fn test_h1_body_length() {
     assert_eq!(bytes, Bytes::from_static(STR.as_ref()));
 }
 
+#[actix_rt::test]
+async fn test_h1_streaming_sized() {
+    let mut srv = test_server(|| {
+        HttpService::build()
+            .h1(|req: Request| {
+                let chunks: &[&'static str] = match req.path() {
+                    "/short" => &["abc"],
+                    "/long" => &["abc", "defg"],
+                    _ => &["abc", "def"],
+                };
+                let body = futures_util::stream::iter(
+                    chunks
+                        .iter()
+                        .map(|chunk| {
+                            Ok::<_, Error>(Bytes::from_static(chunk.as_bytes()))
+                        })
+                        .collect::<Vec<_>>(),
+                );
+                ok::<_, ()>(Response::Ok().streaming_sized(6, body))
+            })
+            .tcp()
+    })
+    .await;
+
+    let response = srv.get("/").send().await.unwrap();
+    assert!(response.status().is_success());
+    assert_eq!(response.headers().get(header::CONTENT_LENGTH).unwrap(), "6");
+    assert!(!response.headers().contains_key(header::TRANSFER_ENCODING));
+    let bytes = srv.load_body(response).await.unwrap();
+    assert_eq!(bytes, Bytes::from_static(b"abcdef"));
+
+    // a body that does not match its declared size closes the connection before a complete
+    // response is received
+    for path in &["/short", "/long"] {
+        let mut stream = net::TcpStream::connect(srv.addr()).unwrap();
+        let _ = stream.write_all(format!("GET {} HTTP/1.1\r\n\r\n", path).as_bytes());
+        let mut data = String::new();
+        let _ = stream.read_to_string(&mut data);
+        let body = data.splitn(2, "\r\n\r\n").nth(1).unwrap_or("");
+        assert!(body.len() < 6, "{:?}", data);
+    }
+}
+
+#[actix_rt::test]
+async fn test_h1_json_stream() {
+    let mut srv = test_server(|| {
+        HttpService::build()
+            .h1(|_| {
+                let items =
+                    futures_util::stream::iter((0..10_000u32).map(Ok::<_, Error>));
+                ok::<_, ()>(Response::Ok().json_stream(items))
+            })
+            .tcp()
+    })
+    .await;
+
+    let response = srv.get("/").send().await.unwrap();
+    assert!(response.status().is_success());
+    assert_eq!(
+        response.headers().get(header::CONTENT_TYPE).unwrap(),
+        "application/json"
+    );
+    assert_eq!(
+        response.headers().get(header::TRANSFER_ENCODING).unwrap(),
+        "chunked"
+    );
+
+    let bytes = srv.load_body(response).await.unwrap();
+    let items: Vec<u32> = serde_json::from_slice(&bytes).unwrap();
+    assert_eq!(items, (0..10_000).collect::<Vec<_>>());
+}
+
 #[actix_rt::test]
 async fn test_h1_body_chunked_explicit() {
     let mut srv = test_server(|| {
@@ -666,7 +1260,7 @@ async fn test_h1_on_connect() {
                 data.insert(20isize);
             })
             .h1(|req: Request| {
-                assert!(req.extensions().contains::<isize>());
+                assert_eq!(req.conn_data::<isize>(), Some(&20));
                 future::ok::<_, ()>(Response::Ok().finish())
             })
             .tcp()
@@ -31,14 +31,69 @@ pub enum MultipartError {
     /// Not consumed
     #[display(fmt = "Multipart stream is not consumed")]
     NotConsumed,
+    /// Configured multipart limit exceeded
+    #[display(fmt = "{}", _0)]
+    Limits(LimitError),
+    /// Field is not declared in the form schema
+    #[display(fmt = "Unknown multipart field: {}", _0)]
+    #[from(ignore)]
+    UnknownField(String),
+    /// Field required by the form is missing
+    #[display(fmt = "Multipart field is missing: {}", _0)]
+    #[from(ignore)]
+    MissingField(String),
+    /// Content type of file field is not allowed by the form schema
+    #[display(fmt = "Content type of multipart field is not allowed: {}", _0)]
+    #[from(ignore)]
+    ContentTypeNotAllowed(String),
+    /// Text field can not be decoded with its charset
+    #[display(fmt = "Can not decode multipart text field: {}", _0)]
+    #[from(ignore)]
+    TextDecode(String),
+    /// Content-Transfer-Encoding of field can not be decoded
+    #[display(fmt = "Can not decode multipart field content transfer encoding")]
+    TransferEncoding,
+    /// Error storing file field
+    #[display(fmt = "{}", _0)]
+    Io(std::io::Error),
 }
 
 impl std::error::Error for MultipartError {}
 
-/// Return `BadRequest` for `MultipartError`
+/// A limit configured with [`MultipartConfig`](crate::MultipartConfig) or
+/// [`MultipartSchema`](crate::MultipartSchema) that was exceeded.
+///
+/// Each variant holds the configured limit.
+#[derive(Debug, Display, Clone, Copy, PartialEq, Eq)]
+pub enum LimitError {
+    /// Too many parts in the multipart stream
+    #[display(fmt = "Multipart stream has more than {} parts", _0)]
+    Parts(usize),
+    /// Field content is too large
+    #[display(fmt = "Multipart field is larger than {} bytes", _0)]
+    FieldSize(usize),
+    /// Multipart stream is too large
+    #[display(fmt = "Multipart stream is larger than {} bytes", _0)]
+    TotalSize(usize),
+    /// Text fields of a [`MultipartForm`](crate::MultipartForm) are too large together
+    #[display(fmt = "Multipart text fields are larger than {} bytes", _0)]
+    TextSize(usize),
+}
+
+impl std::error::Error for LimitError {}
+
+/// Return `PayloadTooLarge` for size limits, `UnsupportedMediaType` for disallowed content types,
+/// `InternalServerError` for I/O errors and `BadRequest` for other `MultipartError`s
 impl ResponseError for MultipartError {
     fn status_code(&self) -> StatusCode {
-        StatusCode::BAD_REQUEST
+        match self {
+            MultipartError::Limits(LimitError::FieldSize(_))
+            | MultipartError::Limits(LimitError::TotalSize(_))
+            | MultipartError::Limits(LimitError::TextSize(_)) => StatusCode::PAYLOAD_TOO_LARGE,
+            MultipartError::ContentTypeNotAllowed(_) => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            MultipartError::Io(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            _ => StatusCode::BAD_REQUEST,
+        }
     }
 }
 
@@ -51,5 +106,11 @@ mod tests {
     fn test_multipart_error() {
         let resp: HttpResponse = MultipartError::Boundary.error_response();
         assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+        let resp = MultipartError::Limits(LimitError::Parts(2)).error_response();
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+        let resp = MultipartError::Limits(LimitError::FieldSize(2)).error_response();
+        assert_eq!(resp.status(), StatusCode::PAYLOAD_TOO_LARGE);
     }
 }
@@ -29,16 +29,93 @@ use crate::server::Multipart;
 /// }
 /// # fn main() {}
 /// ```
+///
+/// Use [`MultipartConfig`] to limit the size and number of parts.
 impl FromRequest for Multipart {
     type Error = Error;
     type Future = Ready<Result<Multipart, Error>>;
-    type Config = ();
+    type Config = MultipartConfig;
 
     #[inline]
     fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
+        let cfg = MultipartConfig::from_req(req);
+
         ok(match Multipart::boundary(req.headers()) {
-            Ok(boundary) => Multipart::from_boundary(boundary, payload.take()),
+            Ok(boundary) => Multipart::from_boundary(boundary, payload.take(), cfg),
             Err(err) => Multipart::from_error(err),
         })
     }
 }
+
+/// Configuration for the [`Multipart`] extractor.
+///
+/// Limits are enforced while the stream is read; exceeding one yields
+/// [`MultipartError::Limits`](crate::MultipartError::Limits). By default nothing is limited.
+///
+/// ```rust
+/// use actix_web::{web, App};
+/// use actix_multipart::{Multipart, MultipartConfig};
+///
+/// async fn index(payload: Multipart) -> String {
+///     "ok".to_owned()
+/// }
+///
+/// let app = App::new().service(
+///     web::resource("/upload")
+///         .app_data(
+///             MultipartConfig::default()
+///                 .max_parts(16)
+///                 .max_field_size(1024 * 1024)
+///                 .max_total_size(8 * 1024 * 1024),
+///         )
+///         .route(web::post().to(index)),
+/// );
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MultipartConfig {
+    pub(crate) max_parts: Option<usize>,
+    pub(crate) max_field_size: Option<usize>,
+    pub(crate) max_total_size: Option<usize>,
+    pub(crate) decode_transfer_encoding: bool,
+}
+
+impl MultipartConfig {
+    /// Set maximum number of parts (fields) in the multipart stream.
+    pub fn max_parts(mut self, max_parts: usize) -> Self {
+        self.max_parts = Some(max_parts);
+        self
+    }
+
+    /// Set maximum size in bytes of a single field's content.
+    pub fn max_field_size(mut self, max_field_size: usize) -> Self {
+        self.max_field_size = Some(max_field_size);
+        self
+    }
+
+    /// Set maximum size in bytes of the whole multipart stream, including headers and boundaries.
+    pub fn max_total_size(mut self, max_total_size: usize) -> Self {
+        self.max_total_size = Some(max_total_size);
+        self
+    }
+
+    /// Set whether field content sent with a `base64` or `quoted-printable`
+    /// `Content-Transfer-Encoding` is decoded. Disabled by default.
+    pub fn decode_transfer_encoding(mut self, decode: bool) -> Self {
+        self.decode_transfer_encoding = decode;
+        self
+    }
+
+    /// Extract multipart config from app data. Check both `T` and `Data<T>` at each layer, innermost first, and fall
+    /// back to the default multipart config.
+    fn from_req(req: &HttpRequest) -> &Self {
+        req.app_data_or_data::<Self>().unwrap_or(&DEFAULT_CONFIG)
+    }
+}
+
+/// Allow shared refs used as defaults.
+const DEFAULT_CONFIG: MultipartConfig = MultipartConfig {
+    max_parts: None,
+    max_field_size: None,
+    max_total_size: None,
+    decode_transfer_encoding: false,
+};
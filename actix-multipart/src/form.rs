@@ -0,0 +1,635 @@
+//! Typed multipart form extraction
+
+use std::collections::HashMap;
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::{fmt, ops};
+
+use actix_web::{dev::Payload, web, Error, FromRequest, HttpRequest};
+use encoding_rs::{Encoding, UTF_8};
+use futures_util::future::LocalBoxFuture;
+use futures_util::stream::StreamExt;
+use tempfile::NamedTempFile;
+
+use crate::error::{LimitError, MultipartError};
+use crate::extractor::MultipartConfig;
+use crate::server::{Field, Multipart};
+
+/// Default size limit of a text field.
+const DEFAULT_TEXT_SIZE: usize = 16_384; // 16 KiB
+
+/// Default size limit of all text fields of a form together.
+const DEFAULT_TOTAL_TEXT_SIZE: usize = 262_144; // 256 KiB
+
+/// Declares the fields expected in a multipart form.
+///
+/// Used by [`FromMultipartForm::schema`] to describe how each field of the form is read.
+#[derive(Debug, Clone)]
+pub struct MultipartSchema {
+    fields: HashMap<String, FieldKind>,
+    ignore_unknown: bool,
+    temp_dir: Option<PathBuf>,
+    max_text_size: usize,
+    max_total_text_size: usize,
+}
+
+impl Default for MultipartSchema {
+    fn default() -> Self {
+        MultipartSchema {
+            fields: HashMap::new(),
+            ignore_unknown: false,
+            temp_dir: None,
+            max_text_size: DEFAULT_TEXT_SIZE,
+            max_total_text_size: DEFAULT_TOTAL_TEXT_SIZE,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+enum FieldKind {
+    Text,
+    File(FileOpts),
+}
+
+impl MultipartSchema {
+    /// Create empty schema. Fields not declared in the schema are rejected.
+    pub fn new() -> Self {
+        MultipartSchema::default()
+    }
+
+    /// Declare a text field.
+    ///
+    /// The content is decoded according to the field's `charset` parameter, defaulting to UTF-8.
+    pub fn text(mut self, name: impl Into<String>) -> Self {
+        self.fields.insert(name.into(), FieldKind::Text);
+        self
+    }
+
+    /// Declare a file field, persisted to a temporary file.
+    pub fn file(mut self, name: impl Into<String>, opts: FileOpts) -> Self {
+        self.fields.insert(name.into(), FieldKind::File(opts));
+        self
+    }
+
+    /// Set whether fields not declared in the schema are skipped instead of rejected with
+    /// [`MultipartError::UnknownField`].
+    pub fn ignore_unknown_fields(mut self, ignore: bool) -> Self {
+        self.ignore_unknown = ignore;
+        self
+    }
+
+    /// Set directory in which file fields are stored. Defaults to [`std::env::temp_dir`].
+    pub fn temp_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.temp_dir = Some(dir.into());
+        self
+    }
+
+    /// Set maximum size in bytes of a text field. Defaults to 16kB.
+    ///
+    /// Larger fields fail with [`LimitError::FieldSize`].
+    pub fn max_text_size(mut self, limit: usize) -> Self {
+        self.max_text_size = limit;
+        self
+    }
+
+    /// Set maximum size in bytes of all text fields of the form together. Defaults to 256kB.
+    ///
+    /// Larger forms fail with [`LimitError::TextSize`].
+    pub fn max_total_text_size(mut self, limit: usize) -> Self {
+        self.max_total_text_size = limit;
+        self
+    }
+}
+
+/// Options for file fields of a [`MultipartSchema`].
+#[derive(Debug, Clone, Default)]
+pub struct FileOpts {
+    /// Maximum file size in bytes.
+    pub max_size: Option<usize>,
+
+    /// Allowed content types. All content types are allowed if empty.
+    pub content_types: Vec<mime::Mime>,
+}
+
+/// A file field stored in a temporary file.
+///
+/// The file is deleted when this value is dropped, unless it is persisted with
+/// [`NamedTempFile::persist`].
+pub struct UploadedFile {
+    /// The temporary file holding the field content.
+    pub file: NamedTempFile,
+
+    /// The file name sent by the client, if any.
+    pub file_name: Option<String>,
+
+    /// The content type of the field.
+    pub content_type: mime::Mime,
+
+    /// The size of the file in bytes.
+    pub size: usize,
+}
+
+impl fmt::Debug for UploadedFile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("UploadedFile")
+            .field("path", &self.file.path())
+            .field("file_name", &self.file_name)
+            .field("content_type", &self.content_type)
+            .field("size", &self.size)
+            .finish()
+    }
+}
+
+/// Fields read from a multipart form according to a [`MultipartSchema`].
+///
+/// If a field is sent more than once, the last value is kept.
+#[derive(Debug, Default)]
+pub struct FormData {
+    texts: HashMap<String, String>,
+    files: HashMap<String, UploadedFile>,
+}
+
+impl FormData {
+    /// Returns the value of a text field.
+    pub fn text(&self, name: &str) -> Option<&str> {
+        self.texts.get(name).map(String::as_str)
+    }
+
+    /// Returns a file field.
+    pub fn file(&self, name: &str) -> Option<&UploadedFile> {
+        self.files.get(name)
+    }
+
+    /// Removes and returns the value of a text field.
+    ///
+    /// Fails with [`MultipartError::MissingField`] if the field was not sent.
+    pub fn take_text(&mut self, name: &str) -> Result<String, MultipartError> {
+        self.texts
+            .remove(name)
+            .ok_or_else(|| MultipartError::MissingField(name.to_owned()))
+    }
+
+    /// Removes and returns a file field.
+    ///
+    /// Fails with [`MultipartError::MissingField`] if the field was not sent.
+    pub fn take_file(&mut self, name: &str) -> Result<UploadedFile, MultipartError> {
+        self.files
+            .remove(name)
+            .ok_or_else(|| MultipartError::MissingField(name.to_owned()))
+    }
+}
+
+/// Types that can be built from a multipart form.
+pub trait FromMultipartForm: Sized {
+    /// Returns the schema the form is read with.
+    fn schema() -> MultipartSchema;
+
+    /// Builds the value from the fields read with [`schema`](FromMultipartForm::schema).
+    fn from_form(form: FormData) -> Result<Self, MultipartError>;
+}
+
+/// Extract a typed form from a `multipart/form-data` request.
+///
+/// Text fields are decoded into strings and file fields are stored in temporary files, as declared
+/// by [`FromMultipartForm::schema`]. Limits set with [`MultipartConfig`] apply to the form.
+///
+/// # Examples
+/// ```rust
+/// use actix_multipart::{
+///     FileOpts, FormData, FromMultipartForm, MultipartError, MultipartForm, MultipartSchema,
+///     UploadedFile,
+/// };
+/// use actix_web::HttpResponse;
+///
+/// struct Profile {
+///     name: String,
+///     avatar: UploadedFile,
+/// }
+///
+/// impl FromMultipartForm for Profile {
+///     fn schema() -> MultipartSchema {
+///         MultipartSchema::new().text("name").file(
+///             "avatar",
+///             FileOpts {
+///                 max_size: Some(1024 * 1024),
+///                 content_types: vec![mime::IMAGE_PNG],
+///             },
+///         )
+///     }
+///
+///     fn from_form(mut form: FormData) -> Result<Self, MultipartError> {
+///         Ok(Profile {
+///             name: form.take_text("name")?,
+///             avatar: form.take_file("avatar")?,
+///         })
+///     }
+/// }
+///
+/// async fn index(form: MultipartForm<Profile>) -> HttpResponse {
+///     let profile = form.into_inner();
+///     HttpResponse::Ok().body(format!("{} uploaded {} bytes", profile.name, profile.avatar.size))
+/// }
+/// ```
+pub struct MultipartForm<T>(pub T);
+
+impl<T> MultipartForm<T> {
+    /// Unwrap into inner `T` value.
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> ops::Deref for MultipartForm<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T> ops::DerefMut for MultipartForm<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.0
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for MultipartForm<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl<T> FromRequest for MultipartForm<T>
+where
+    T: FromMultipartForm + 'static,
+{
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self, Error>>;
+    type Config = MultipartConfig;
+
+    #[inline]
+    fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
+        let multipart = Multipart::from_request(req, payload);
+
+        Box::pin(async move {
+            let form = read_form(multipart.await?, T::schema()).await?;
+            Ok(MultipartForm(T::from_form(form)?))
+        })
+    }
+}
+
+async fn read_form(
+    mut multipart: Multipart,
+    schema: MultipartSchema,
+) -> Result<FormData, MultipartError> {
+    let mut form = FormData::default();
+    let mut text_size = 0;
+
+    while let Some(field) = multipart.next().await {
+        let field = field?;
+        let name = field
+            .content_disposition()
+            .and_then(|cd| cd.get_name().map(ToOwned::to_owned))
+            .unwrap_or_default();
+
+        match schema.fields.get(&name) {
+            Some(FieldKind::Text) => {
+                let text = read_text(field, &name, &schema, &mut text_size).await?;
+                form.texts.insert(name, text);
+            }
+            Some(FieldKind::File(opts)) => {
+                let file = read_file(field, &name, opts, schema.temp_dir.as_ref()).await?;
+                form.files.insert(name, file);
+            }
+            None if schema.ignore_unknown => continue,
+            None => return Err(MultipartError::UnknownField(name)),
+        }
+    }
+
+    Ok(form)
+}
+
+async fn read_text(
+    mut field: Field,
+    name: &str,
+    schema: &MultipartSchema,
+    total_size: &mut usize,
+) -> Result<String, MultipartError> {
+    let encoding = match field.content_type().get_param(mime::CHARSET) {
+        Some(charset) => Encoding::for_label(charset.as_str().as_bytes())
+            .ok_or_else(|| MultipartError::TextDecode(name.to_owned()))?,
+        None => UTF_8,
+    };
+
+    let mut buf = Vec::new();
+    while let Some(chunk) = field.next().await {
+        let chunk = chunk?;
+
+        if buf.len() + chunk.len() > schema.max_text_size {
+            return Err(MultipartError::Limits(LimitError::FieldSize(
+                schema.max_text_size,
+            )));
+        }
+
+        *total_size += chunk.len();
+        if *total_size > schema.max_total_text_size {
+            return Err(MultipartError::Limits(LimitError::TextSize(
+                schema.max_total_text_size,
+            )));
+        }
+
+        buf.extend_from_slice(&chunk);
+    }
+
+    encoding
+        .decode_without_bom_handling_and_without_replacement(&buf)
+        .map(|text| text.into_owned())
+        .ok_or_else(|| MultipartError::TextDecode(name.to_owned()))
+}
+
+async fn read_file(
+    mut field: Field,
+    name: &str,
+    opts: &FileOpts,
+    temp_dir: Option<&PathBuf>,
+) -> Result<UploadedFile, MultipartError> {
+    let content_type = field.content_type().clone();
+    if !opts.content_types.is_empty()
+        && !opts
+            .content_types
+            .iter()
+            .any(|ct| ct.essence_str() == content_type.essence_str())
+    {
+        return Err(MultipartError::ContentTypeNotAllowed(name.to_owned()));
+    }
+
+    let file_name = field
+        .content_disposition()
+        .and_then(|cd| cd.get_filename().map(ToOwned::to_owned));
+
+    let temp_dir = temp_dir.cloned();
+    let mut file = web::block(move || match temp_dir {
+        Some(dir) => NamedTempFile::new_in(dir),
+        None => NamedTempFile::new(),
+    })
+    .await
+    .map_err(blocking_error)??;
+
+    let mut size = 0;
+    while let Some(chunk) = field.next().await {
+        let chunk = chunk?;
+        size += chunk.len();
+
+        if let Some(max_size) = opts.max_size {
+            if size > max_size {
+                return Err(MultipartError::Limits(LimitError::FieldSize(max_size)));
+            }
+        }
+
+        file = web::block(move || file.write_all(&chunk).map(|_| file))
+            .await
+            .map_err(blocking_error)??;
+    }
+
+    Ok(UploadedFile {
+        file,
+        file_name,
+        content_type,
+        size,
+    })
+}
+
+fn blocking_error(err: actix_web::error::BlockingError) -> MultipartError {
+    MultipartError::Io(io::Error::new(io::ErrorKind::Other, err.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::io::Read;
+
+    use actix_http::h1::Payload;
+    use actix_web::http::{header, StatusCode};
+    use actix_web::test::TestRequest;
+    use bytes::Bytes;
+
+    const BOUNDARY: &str = "abbc761f78ff4d7cb7573b5a23f96ef0";
+
+    #[derive(Debug)]
+    struct Profile {
+        name: String,
+        avatar: UploadedFile,
+    }
+
+    impl FromMultipartForm for Profile {
+        fn schema() -> MultipartSchema {
+            MultipartSchema::new().text("name").file(
+                "avatar",
+                FileOpts {
+                    max_size: Some(16),
+                    content_types: vec![mime::IMAGE_PNG],
+                },
+            )
+        }
+
+        fn from_form(mut form: FormData) -> Result<Self, MultipartError> {
+            Ok(Profile {
+                name: form.take_text("name")?,
+                avatar: form.take_file("avatar")?,
+            })
+        }
+    }
+
+    async fn extract<T: FromMultipartForm + 'static>(
+        body: &'static [u8],
+    ) -> Result<MultipartForm<T>, Error> {
+        let (mut sender, inner_payload) = Payload::create(false);
+        sender.feed_data(Bytes::from_static(body));
+        sender.feed_eof();
+
+        let mut payload = actix_web::dev::Payload::from(inner_payload);
+        let req = TestRequest::default()
+            .insert_header((
+                header::CONTENT_TYPE,
+                format!("multipart/form-data; boundary={}", BOUNDARY),
+            ))
+            .to_http_request();
+
+        MultipartForm::<T>::from_request(&req, &mut payload).await
+    }
+
+    #[actix_rt::test]
+    async fn test_form() {
+        let form = extract::<Profile>(
+            b"--abbc761f78ff4d7cb7573b5a23f96ef0\r\n\
+             Content-Disposition: form-data; name=\"name\"\r\n\
+             Content-Type: text/plain; charset=iso-8859-1\r\n\r\n\
+             J\xfcrgen\r\n\
+             --abbc761f78ff4d7cb7573b5a23f96ef0\r\n\
+             Content-Disposition: form-data; name=\"avatar\"; filename=\"me.png\"\r\n\
+             Content-Type: image/png\r\n\r\n\
+             PNGDATA\r\n\
+             --abbc761f78ff4d7cb7573b5a23f96ef0--\r\n",
+        )
+        .await
+        .unwrap();
+
+        let Profile { name, avatar } = form.into_inner();
+        assert_eq!(name, "J\u{fc}rgen");
+        assert_eq!(avatar.file_name.as_deref(), Some("me.png"));
+        assert_eq!(avatar.content_type, mime::IMAGE_PNG);
+        assert_eq!(avatar.size, 7);
+
+        let mut contents = String::new();
+        avatar
+            .file
+            .reopen()
+            .unwrap()
+            .read_to_string(&mut contents)
+            .unwrap();
+        assert_eq!(contents, "PNGDATA");
+    }
+
+    #[actix_rt::test]
+    async fn test_form_errors() {
+        let err = extract::<Profile>(
+            b"--abbc761f78ff4d7cb7573b5a23f96ef0\r\n\
+             Content-Disposition: form-data; name=\"other\"\r\n\r\n\
+             value\r\n\
+             --abbc761f78ff4d7cb7573b5a23f96ef0--\r\n",
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(
+            err.as_error::<MultipartError>().unwrap().to_string(),
+            "Unknown multipart field: other"
+        );
+
+        let err = extract::<Profile>(
+            b"--abbc761f78ff4d7cb7573b5a23f96ef0\r\n\
+             Content-Disposition: form-data; name=\"avatar\"; filename=\"me.gif\"\r\n\
+             Content-Type: image/gif\r\n\r\n\
+             GIFDATA\r\n\
+             --abbc761f78ff4d7cb7573b5a23f96ef0--\r\n",
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(
+            err.as_response_error().status_code(),
+            StatusCode::UNSUPPORTED_MEDIA_TYPE
+        );
+
+        let err = extract::<Profile>(
+            b"--abbc761f78ff4d7cb7573b5a23f96ef0\r\n\
+             Content-Disposition: form-data; name=\"name\"\r\n\r\n\
+             Ferris\r\n\
+             --abbc761f78ff4d7cb7573b5a23f96ef0--\r\n",
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(
+            err.as_error::<MultipartError>().unwrap().to_string(),
+            "Multipart field is missing: avatar"
+        );
+    }
+
+    #[actix_rt::test]
+    async fn test_form_text_limits() {
+        #[derive(Debug)]
+        struct Names;
+
+        impl FromMultipartForm for Names {
+            fn schema() -> MultipartSchema {
+                MultipartSchema::new()
+                    .text("first")
+                    .text("last")
+                    .max_text_size(8)
+                    .max_total_text_size(12)
+            }
+
+            fn from_form(_: FormData) -> Result<Self, MultipartError> {
+                Ok(Names)
+            }
+        }
+
+        extract::<Names>(
+            b"--abbc761f78ff4d7cb7573b5a23f96ef0\r\n\
+             Content-Disposition: form-data; name=\"first\"\r\n\r\n\
+             Ferris\r\n\
+             --abbc761f78ff4d7cb7573b5a23f96ef0\r\n\
+             Content-Disposition: form-data; name=\"last\"\r\n\r\n\
+             Crab\r\n\
+             --abbc761f78ff4d7cb7573b5a23f96ef0--\r\n",
+        )
+        .await
+        .unwrap();
+
+        let err = extract::<Names>(
+            b"--abbc761f78ff4d7cb7573b5a23f96ef0\r\n\
+             Content-Disposition: form-data; name=\"first\"\r\n\r\n\
+             Ferris the crab\r\n\
+             --abbc761f78ff4d7cb7573b5a23f96ef0--\r\n",
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(
+            err.as_response_error().status_code(),
+            StatusCode::PAYLOAD_TOO_LARGE
+        );
+        assert!(matches!(
+            err.as_error::<MultipartError>(),
+            Some(MultipartError::Limits(LimitError::FieldSize(8)))
+        ));
+
+        let err = extract::<Names>(
+            b"--abbc761f78ff4d7cb7573b5a23f96ef0\r\n\
+             Content-Disposition: form-data; name=\"first\"\r\n\r\n\
+             Ferris\r\n\
+             --abbc761f78ff4d7cb7573b5a23f96ef0\r\n\
+             Content-Disposition: form-data; name=\"last\"\r\n\r\n\
+             Crabbies\r\n\
+             --abbc761f78ff4d7cb7573b5a23f96ef0--\r\n",
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(
+            err.as_response_error().status_code(),
+            StatusCode::PAYLOAD_TOO_LARGE
+        );
+        assert!(matches!(
+            err.as_error::<MultipartError>(),
+            Some(MultipartError::Limits(LimitError::TextSize(12)))
+        ));
+    }
+
+    #[actix_rt::test]
+    async fn test_form_ignore_unknown() {
+        struct Name(String);
+
+        impl FromMultipartForm for Name {
+            fn schema() -> MultipartSchema {
+                MultipartSchema::new()
+                    .text("name")
+                    .ignore_unknown_fields(true)
+            }
+
+            fn from_form(mut form: FormData) -> Result<Self, MultipartError> {
+                Ok(Name(form.take_text("name")?))
+            }
+        }
+
+        let form = extract::<Name>(
+            b"--abbc761f78ff4d7cb7573b5a23f96ef0\r\n\
+             Content-Disposition: form-data; name=\"other\"\r\n\r\n\
+             value\r\n\
+             --abbc761f78ff4d7cb7573b5a23f96ef0\r\n\
+             Content-Disposition: form-data; name=\"name\"\r\n\r\n\
+             Ferris\r\n\
+             --abbc761f78ff4d7cb7573b5a23f96ef0--\r\n",
+        )
+        .await
+        .unwrap();
+        assert_eq!(form.0 .0, "Ferris");
+    }
+}
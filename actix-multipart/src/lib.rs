@@ -5,7 +5,13 @@
 
 mod error;
 mod extractor;
+mod form;
 mod server;
+mod transfer;
 
-pub use self::error::MultipartError;
+pub use self::error::{LimitError, MultipartError};
+pub use self::extractor::MultipartConfig;
+pub use self::form::{
+    FileOpts, FormData, FromMultipartForm, MultipartForm, MultipartSchema, UploadedFile,
+};
 pub use self::server::{Field, Multipart};
@@ -15,16 +15,17 @@ use actix_utils::task::LocalWaker;
 use actix_web::error::{ParseError, PayloadError};
 use actix_web::http::header::{self, ContentDisposition, HeaderMap, HeaderName, HeaderValue};
 
-use crate::error::MultipartError;
+use crate::error::{LimitError, MultipartError};
+use crate::extractor::MultipartConfig;
+use crate::transfer::TransferDecoder;
 
 const MAX_HEADERS: usize = 32;
 
 /// The server-side implementation of `multipart/form-data` requests.
 ///
-/// This will parse the incoming stream into `MultipartItem` instances via its
-/// Stream implementation.
-/// `MultipartItem::Field` contains multipart field. `MultipartItem::Multipart`
-/// is used for nested multipart streams.
+/// This will parse the incoming stream into [`Field`] instances via its
+/// Stream implementation. Nested multipart streams are accessed with
+/// [`Field::multipart`].
 pub struct Multipart {
     safety: Safety,
     error: Option<MultipartError>,
@@ -53,16 +54,27 @@ struct InnerMultipart {
     boundary: String,
     state: InnerState,
     item: InnerMultipartItem,
+    config: MultipartConfig,
+    parts: usize,
+    limit: Option<LimitError>,
 }
 
 impl Multipart {
     /// Create multipart instance for boundary.
     pub fn new<S>(headers: &HeaderMap, stream: S) -> Multipart
+    where
+        S: Stream<Item = Result<Bytes, PayloadError>> + Unpin + 'static,
+    {
+        Multipart::with_config(headers, stream, &MultipartConfig::default())
+    }
+
+    /// Create multipart instance for boundary, enforcing the limits of `config`.
+    pub fn with_config<S>(headers: &HeaderMap, stream: S, config: &MultipartConfig) -> Multipart
     where
         S: Stream<Item = Result<Bytes, PayloadError>> + Unpin + 'static,
     {
         match Self::boundary(headers) {
-            Ok(boundary) => Multipart::from_boundary(boundary, stream),
+            Ok(boundary) => Multipart::from_boundary(boundary, stream, config),
             Err(err) => Multipart::from_error(err),
         }
     }
@@ -72,11 +84,7 @@ impl Multipart {
         if let Some(content_type) = headers.get(&header::CONTENT_TYPE) {
             if let Ok(content_type) = content_type.to_str() {
                 if let Ok(ct) = content_type.parse::<mime::Mime>() {
-                    if let Some(boundary) = ct.get_param(mime::BOUNDARY) {
-                        Ok(boundary.as_str().to_owned())
-                    } else {
-                        Err(MultipartError::Boundary)
-                    }
+                    Multipart::boundary_param(&ct)
                 } else {
                     Err(MultipartError::ParseContentType)
                 }
@@ -88,19 +96,43 @@ impl Multipart {
         }
     }
 
+    /// Extract boundary parameter of a multipart content type.
+    fn boundary_param(ct: &mime::Mime) -> Result<String, MultipartError> {
+        match ct.get_param(mime::BOUNDARY) {
+            Some(boundary)
+                if !boundary.as_str().is_empty()
+                    && !boundary.as_str().contains(|c| c == '\r' || c == '\n') =>
+            {
+                Ok(boundary.as_str().to_owned())
+            }
+            _ => Err(MultipartError::Boundary),
+        }
+    }
+
     /// Create multipart instance for given boundary and stream
-    pub(crate) fn from_boundary<S>(boundary: String, stream: S) -> Multipart
+    pub(crate) fn from_boundary<S, E>(
+        boundary: String,
+        stream: S,
+        config: &MultipartConfig,
+    ) -> Multipart
     where
-        S: Stream<Item = Result<Bytes, PayloadError>> + Unpin + 'static,
+        S: Stream<Item = Result<Bytes, E>> + Unpin + 'static,
+        E: Into<MultipartError>,
     {
+        let mut payload = PayloadBuffer::new(Box::new(stream));
+        payload.limit = config.max_total_size;
+
         Multipart {
             error: None,
             safety: Safety::new(),
             inner: Some(Rc::new(RefCell::new(InnerMultipart {
                 boundary,
-                payload: PayloadRef::new(PayloadBuffer::new(Box::new(stream))),
+                payload: PayloadRef::new(payload),
                 state: InnerState::FirstBoundary,
                 item: InnerMultipartItem::None,
+                config: *config,
+                parts: 0,
+                limit: None,
             }))),
         }
     }
@@ -252,7 +284,9 @@ impl InnerMultipart {
         safety: &Safety,
         cx: &mut Context<'_>,
     ) -> Poll<Option<Result<Field, MultipartError>>> {
-        if self.state == InnerState::Eof {
+        if let Some(err) = self.limit {
+            Poll::Ready(Some(Err(MultipartError::Limits(err))))
+        } else if self.state == InnerState::Eof {
             Poll::Ready(None)
         } else {
             // release field
@@ -332,6 +366,16 @@ impl InnerMultipart {
                 return Poll::Pending;
             };
 
+            self.parts += 1;
+            if let Some(max_parts) = self.config.max_parts {
+                if self.parts > max_parts {
+                    self.limit = Some(LimitError::Parts(max_parts));
+                    return Poll::Ready(Some(Err(MultipartError::Limits(LimitError::Parts(
+                        max_parts,
+                    )))));
+                }
+            }
+
             // content type
             let mut mt = mime::APPLICATION_OCTET_STREAM;
             if let Some(content_type) = headers.get(&header::CONTENT_TYPE) {
@@ -344,19 +388,28 @@ impl InnerMultipart {
 
             self.state = InnerState::Boundary;
 
-            // nested multipart stream
-            if mt.type_() == mime::MULTIPART {
-                Poll::Ready(Some(Err(MultipartError::Nested)))
+            let decoder = if self.config.decode_transfer_encoding {
+                TransferDecoder::from_headers(&headers)
             } else {
-                let field = Rc::new(RefCell::new(InnerField::new(
-                    self.payload.clone(),
-                    self.boundary.clone(),
-                    &headers,
-                )?));
-                self.item = InnerMultipartItem::Field(Rc::clone(&field));
-
-                Poll::Ready(Some(Ok(Field::new(safety.clone(cx), headers, mt, field))))
-            }
+                None
+            };
+
+            let field = Rc::new(RefCell::new(InnerField::new(
+                self.payload.clone(),
+                self.boundary.clone(),
+                &headers,
+                self.config.max_field_size,
+            )?));
+            self.item = InnerMultipartItem::Field(Rc::clone(&field));
+
+            Poll::Ready(Some(Ok(Field::new(
+                safety.clone(cx),
+                headers,
+                mt,
+                field,
+                decoder,
+                self.config,
+            ))))
         }
     }
 }
@@ -374,6 +427,8 @@ pub struct Field {
     headers: HeaderMap,
     inner: Rc<RefCell<InnerField>>,
     safety: Safety,
+    decoder: Option<TransferDecoder>,
+    config: MultipartConfig,
 }
 
 impl Field {
@@ -382,12 +437,16 @@ impl Field {
         headers: HeaderMap,
         ct: mime::Mime,
         inner: Rc<RefCell<InnerField>>,
+        decoder: Option<TransferDecoder>,
+        config: MultipartConfig,
     ) -> Self {
         Field {
             ct,
             headers,
             inner,
             safety,
+            decoder,
+            config,
         }
     }
 
@@ -411,12 +470,27 @@ impl Field {
             None
         }
     }
-}
 
-impl Stream for Field {
-    type Item = Result<Bytes, MultipartError>;
+    /// Get the nested multipart stream of the field, if its content type is `multipart/*`.
+    ///
+    /// The parent multipart stream continues with the next field once the nested stream
+    /// is dropped.
+    pub fn multipart(self) -> Option<Multipart> {
+        if self.ct.type_() != mime::MULTIPART {
+            return None;
+        }
 
-    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let config = self.config;
+        Some(match Multipart::boundary_param(&self.ct) {
+            Ok(boundary) => Multipart::from_boundary(boundary, self, &config),
+            Err(err) => Multipart::from_error(err),
+        })
+    }
+
+    fn poll_inner(
+        &mut self,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Bytes, MultipartError>>> {
         if self.safety.current() {
             let mut inner = self.inner.borrow_mut();
             if let Some(mut payload) = inner.payload.as_ref().unwrap().get_mut(&self.safety) {
@@ -431,6 +505,38 @@ impl Stream for Field {
     }
 }
 
+impl Stream for Field {
+    type Item = Result<Bytes, MultipartError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+
+        loop {
+            let res = this.poll_inner(cx);
+
+            let decoder = match this.decoder {
+                Some(ref mut decoder) => decoder,
+                None => return res,
+            };
+
+            return match res {
+                Poll::Ready(Some(Ok(chunk))) => {
+                    let chunk = decoder.decode(&chunk)?;
+                    if chunk.is_empty() {
+                        continue;
+                    }
+                    Poll::Ready(Some(Ok(chunk)))
+                }
+                Poll::Ready(None) => {
+                    this.decoder.take().unwrap().finish()?;
+                    Poll::Ready(None)
+                }
+                res => res,
+            };
+        }
+    }
+}
+
 impl fmt::Debug for Field {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         writeln!(f, "\nField: {}", self.ct)?;
@@ -448,6 +554,9 @@ struct InnerField {
     boundary: String,
     eof: bool,
     length: Option<u64>,
+    max_size: Option<usize>,
+    size: usize,
+    limit: Option<LimitError>,
 }
 
 impl InnerField {
@@ -455,6 +564,7 @@ impl InnerField {
         payload: PayloadRef,
         boundary: String,
         headers: &HeaderMap,
+        max_size: Option<usize>,
     ) -> Result<InnerField, PayloadError> {
         let len = if let Some(len) = headers.get(&header::CONTENT_LENGTH) {
             if let Ok(s) = len.to_str() {
@@ -475,6 +585,9 @@ impl InnerField {
             payload: Some(payload),
             eof: false,
             length: len,
+            max_size,
+            size: 0,
+            limit: None,
         })
     }
 
@@ -526,7 +639,7 @@ impl InnerField {
         }
 
         // check boundary
-        if len > 4 && payload.buf[0] == b'\r' {
+        if len >= 4 && payload.buf[0] == b'\r' {
             let b_len = if &payload.buf[..2] == b"\r\n" && &payload.buf[2..4] == b"--" {
                 Some(4)
             } else if &payload.buf[1..3] == b"--" {
@@ -584,6 +697,10 @@ impl InnerField {
     }
 
     fn poll(&mut self, s: &Safety) -> Poll<Option<Result<Bytes, MultipartError>>> {
+        if let Some(err) = self.limit {
+            return Poll::Ready(Some(Err(MultipartError::Limits(err))));
+        }
+
         if self.payload.is_none() {
             return Poll::Ready(None);
         }
@@ -598,7 +715,20 @@ impl InnerField {
 
                 match res {
                     Poll::Pending => return Poll::Pending,
-                    Poll::Ready(Some(Ok(bytes))) => return Poll::Ready(Some(Ok(bytes))),
+                    Poll::Ready(Some(Ok(bytes))) => {
+                        self.size += bytes.len();
+
+                        if let Some(max_size) = self.max_size {
+                            if self.size > max_size {
+                                self.limit = Some(LimitError::FieldSize(max_size));
+                                return Poll::Ready(Some(Err(MultipartError::Limits(
+                                    LimitError::FieldSize(max_size),
+                                ))));
+                            }
+                        }
+
+                        return Poll::Ready(Some(Ok(bytes)));
+                    }
                     Poll::Ready(Some(Err(e))) => return Poll::Ready(Some(Err(e))),
                     Poll::Ready(None) => self.eof = true,
                 }
@@ -715,26 +845,43 @@ impl Drop for Safety {
 struct PayloadBuffer {
     eof: bool,
     buf: BytesMut,
-    stream: LocalBoxStream<'static, Result<Bytes, PayloadError>>,
+    stream: LocalBoxStream<'static, Result<Bytes, MultipartError>>,
+    /// Maximum number of bytes read from the stream
+    limit: Option<usize>,
+    read: usize,
 }
 
 impl PayloadBuffer {
     /// Create new `PayloadBuffer` instance
-    fn new<S>(stream: S) -> Self
+    fn new<S, E>(stream: S) -> Self
     where
-        S: Stream<Item = Result<Bytes, PayloadError>> + 'static,
+        S: Stream<Item = Result<Bytes, E>> + 'static,
+        E: Into<MultipartError>,
     {
         PayloadBuffer {
             eof: false,
             buf: BytesMut::new(),
-            stream: stream.boxed_local(),
+            stream: stream.map(|res| res.map_err(Into::into)).boxed_local(),
+            limit: None,
+            read: 0,
         }
     }
 
-    fn poll_stream(&mut self, cx: &mut Context<'_>) -> Result<(), PayloadError> {
+    fn poll_stream(&mut self, cx: &mut Context<'_>) -> Result<(), MultipartError> {
+        // nested multipart streams read from fields, which must not be polled after completion
+        if self.eof {
+            return Ok(());
+        }
+
         loop {
+            self.check_limit()?;
+
             match Pin::new(&mut self.stream).poll_next(cx) {
-                Poll::Ready(Some(Ok(data))) => self.buf.extend_from_slice(&data),
+                Poll::Ready(Some(Ok(data))) => {
+                    self.read += data.len();
+                    self.check_limit()?;
+                    self.buf.extend_from_slice(&data);
+                }
                 Poll::Ready(Some(Err(e))) => return Err(e),
                 Poll::Ready(None) => {
                     self.eof = true;
@@ -745,6 +892,15 @@ impl PayloadBuffer {
         }
     }
 
+    fn check_limit(&self) -> Result<(), MultipartError> {
+        match self.limit {
+            Some(limit) if self.read > limit => {
+                Err(MultipartError::Limits(LimitError::TotalSize(limit)))
+            }
+            _ => Ok(()),
+        }
+    }
+
     /// Read exact number of bytes
     #[cfg(test)]
     fn read_exact(&mut self, size: usize) -> Option<Bytes> {
@@ -1014,7 +1170,7 @@ mod tests {
             match field.next().await {
                 Some(Ok(chunk)) => b.extend_from_slice(&chunk),
                 None => return b,
-                _ => unreachable!(),
+                Some(Err(err)) => panic!("{}", err),
             }
         }
     }
@@ -1162,7 +1318,198 @@ mod tests {
         let (_, headers) = create_simple_request_with_header();
         let boundary = Multipart::boundary(&headers);
         assert!(boundary.is_ok());
-        let _ = Multipart::from_boundary(boundary.unwrap(), payload);
+        let _ =
+            Multipart::from_boundary(boundary.unwrap(), payload, &MultipartConfig::default());
+    }
+
+    #[actix_rt::test]
+    async fn test_max_parts() {
+        let bytes = Bytes::from(
+            "--abbc761f78ff4d7cb7573b5a23f96ef0\r\n\
+             Content-Disposition: form-data; name=\"a\"\r\n\r\n\
+             1\r\n\
+             --abbc761f78ff4d7cb7573b5a23f96ef0\r\n\
+             Content-Disposition: form-data; name=\"b\"\r\n\r\n\
+             2\r\n\
+             --abbc761f78ff4d7cb7573b5a23f96ef0\r\n\
+             Content-Disposition: form-data; name=\"c\"\r\n\r\n\
+             3\r\n\
+             --abbc761f78ff4d7cb7573b5a23f96ef0--\r\n",
+        );
+        let (mut sender, inner_payload) = Payload::create(false);
+        sender.feed_data(bytes);
+        sender.feed_eof();
+
+        let mut payload = actix_web::dev::Payload::from(inner_payload);
+        let req = TestRequest::default()
+            .insert_header((
+                header::CONTENT_TYPE,
+                "multipart/form-data; boundary=abbc761f78ff4d7cb7573b5a23f96ef0",
+            ))
+            .app_data(MultipartConfig::default().max_parts(2))
+            .to_http_request();
+        let mut multipart = Multipart::from_request(&req, &mut payload).await.unwrap();
+
+        for expected in &["1", "2"] {
+            let mut field = multipart.next().await.unwrap().unwrap();
+            assert_eq!(get_whole_field(&mut field).await, expected);
+        }
+
+        match multipart.next().await {
+            Some(Err(MultipartError::Limits(LimitError::Parts(2)))) => {}
+            _ => unreachable!(),
+        }
+        assert!(multipart.next().await.unwrap().is_err());
+    }
+
+    #[actix_rt::test]
+    async fn test_max_field_size() {
+        let (sender, payload) = create_stream();
+        let (_, headers) = create_simple_request_with_header();
+        let config = MultipartConfig::default().max_field_size(8);
+        let mut multipart = Multipart::with_config(&headers, payload, &config);
+
+        sender
+            .send(Ok(Bytes::from(
+                "--abbc761f78ff4d7cb7573b5a23f96ef0\r\n\
+                 Content-Disposition: form-data; name=\"file\"\r\n\r\n\
+                 12345",
+            )))
+            .unwrap();
+
+        let mut field = multipart.next().await.unwrap().unwrap();
+        assert_eq!(field.next().await.unwrap().unwrap(), "12345");
+
+        // the rest of the field is still arriving when the limit is hit
+        sender.send(Ok(Bytes::from("67890"))).unwrap();
+        match field.next().await {
+            Some(Err(MultipartError::Limits(LimitError::FieldSize(8)))) => {}
+            _ => unreachable!(),
+        }
+        assert!(field.next().await.unwrap().is_err());
+        drop(field);
+
+        assert!(multipart.next().await.unwrap().is_err());
+    }
+
+    #[actix_rt::test]
+    async fn test_max_total_size() {
+        let (bytes, headers) = create_simple_request_with_header();
+        let config = MultipartConfig::default().max_total_size(bytes.len() - 1);
+        let mut multipart = Multipart::with_config(&headers, SlowStream::new(bytes), &config);
+
+        let mut field = multipart.next().await.unwrap().unwrap();
+        assert_eq!(get_whole_field(&mut field).await, "test");
+        drop(field);
+
+        let err = loop {
+            match multipart.next().await {
+                Some(Ok(mut field)) => while let Some(Ok(_)) = field.next().await {},
+                Some(Err(err)) => break err,
+                None => unreachable!(),
+            }
+        };
+        match err {
+            MultipartError::Limits(LimitError::TotalSize(_)) => {}
+            _ => unreachable!(),
+        }
+    }
+
+    #[actix_rt::test]
+    async fn test_nested_multipart() {
+        let (sender, payload) = create_stream();
+        let (_, headers) = create_simple_request_with_header();
+        let bytes = Bytes::from(
+            "--abbc761f78ff4d7cb7573b5a23f96ef0\r\n\
+             Content-Disposition: form-data; name=\"files\"\r\n\
+             Content-Type: multipart/mixed; boundary=inner\r\n\r\n\
+             --inner\r\n\
+             Content-Disposition: file; filename=\"a.txt\"\r\n\r\n\
+             first\r\n\
+             --inner\r\n\
+             Content-Disposition: file; filename=\"b.txt\"\r\n\r\n\
+             second\r\n\
+             --inner--\r\n\
+             --abbc761f78ff4d7cb7573b5a23f96ef0\r\n\
+             Content-Disposition: form-data; name=\"after\"\r\n\r\n\
+             data\r\n\
+             --abbc761f78ff4d7cb7573b5a23f96ef0--\r\n",
+        );
+        sender.send(Ok(bytes)).unwrap();
+        drop(sender);
+
+        let mut multipart = Multipart::new(&headers, payload);
+
+        let field = multipart.next().await.unwrap().unwrap();
+        assert_eq!(field.content_type().subtype(), "mixed");
+        let mut nested = field.multipart().unwrap();
+
+        for (name, content) in &[("a.txt", "first"), ("b.txt", "second")] {
+            let mut field = nested.next().await.unwrap().unwrap();
+            let cd = field.content_disposition().unwrap();
+            assert_eq!(cd.get_filename(), Some(*name));
+            assert_eq!(get_whole_field(&mut field).await, content);
+        }
+        assert!(nested.next().await.is_none());
+        drop(nested);
+
+        let mut field = multipart.next().await.unwrap().unwrap();
+        assert!(field.content_type().type_() != mime::MULTIPART);
+        assert_eq!(get_whole_field(&mut field).await, "data");
+        assert!(field.multipart().is_none());
+
+        assert!(multipart.next().await.is_none());
+    }
+
+    #[actix_rt::test]
+    async fn test_transfer_encoding() {
+        let bytes = Bytes::from(
+            "--abbc761f78ff4d7cb7573b5a23f96ef0\r\n\
+             Content-Disposition: form-data; name=\"file\"\r\n\
+             Content-Transfer-Encoding: base64\r\n\r\n\
+             aGVsbG8g\r\nd29ybGQ=\r\n\
+             --abbc761f78ff4d7cb7573b5a23f96ef0\r\n\
+             Content-Disposition: form-data; name=\"text\"\r\n\
+             Content-Transfer-Encoding: quoted-printable\r\n\r\n\
+             caf=C3=A9 au=\r\n lait\r\n\
+             --abbc761f78ff4d7cb7573b5a23f96ef0--\r\n",
+        );
+        let (_, headers) = create_simple_request_with_header();
+        let config = MultipartConfig::default().decode_transfer_encoding(true);
+
+        // decoded when enabled, including when read one byte at a time
+        let payload = SlowStream::new(bytes.clone());
+        let mut multipart = Multipart::with_config(&headers, payload, &config);
+
+        let mut field = multipart.next().await.unwrap().unwrap();
+        assert_eq!(get_whole_field(&mut field).await, "hello world");
+        drop(field);
+
+        let mut field = multipart.next().await.unwrap().unwrap();
+        assert_eq!(get_whole_field(&mut field).await, "caf\u{e9} au lait");
+        drop(field);
+
+        assert!(multipart.next().await.is_none());
+
+        // raw bytes by default
+        let mut multipart = Multipart::new(&headers, SlowStream::new(bytes));
+        let mut field = multipart.next().await.unwrap().unwrap();
+        assert_eq!(get_whole_field(&mut field).await, "aGVsbG8g\r\nd29ybGQ=");
+    }
+
+    #[test]
+    fn test_boundary_line_break() {
+        let ct: mime::Mime = "multipart/mixed; boundary=abc".parse().unwrap();
+        assert_eq!(Multipart::boundary_param(&ct).unwrap(), "abc");
+
+        // line breaks never reach the boundary check through content type parsing
+        for ct in &[
+            "multipart/mixed; boundary=\"ab\r\ncd\"",
+            "multipart/mixed; boundary=ab\ncd",
+        ] {
+            let parsed = ct.parse::<mime::Mime>();
+            assert!(parsed.map_or(true, |ct| Multipart::boundary_param(&ct).is_err()));
+        }
     }
 
     #[actix_rt::test]
@@ -0,0 +1,138 @@
+//! Content-Transfer-Encoding decoding for multipart fields
+
+use bytes::{Buf, Bytes, BytesMut};
+
+use actix_web::http::header::{HeaderMap, HeaderName};
+
+use crate::error::MultipartError;
+
+/// Incremental decoder for the `base64` and `quoted-printable` transfer encodings.
+///
+/// Input that can not be decoded yet is kept until the next chunk arrives.
+pub(crate) enum TransferDecoder {
+    Base64(BytesMut),
+    QuotedPrintable(BytesMut),
+}
+
+impl TransferDecoder {
+    /// Create decoder for the `Content-Transfer-Encoding` of a part, if it needs decoding.
+    pub(crate) fn from_headers(headers: &HeaderMap) -> Option<TransferDecoder> {
+        let encoding = headers
+            .get(HeaderName::from_static("content-transfer-encoding"))?
+            .to_str()
+            .ok()?
+            .trim();
+
+        if encoding.eq_ignore_ascii_case("base64") {
+            Some(TransferDecoder::Base64(BytesMut::new()))
+        } else if encoding.eq_ignore_ascii_case("quoted-printable") {
+            Some(TransferDecoder::QuotedPrintable(BytesMut::new()))
+        } else {
+            None
+        }
+    }
+
+    /// Decode next chunk of field content.
+    pub(crate) fn decode(&mut self, chunk: &[u8]) -> Result<Bytes, MultipartError> {
+        match self {
+            TransferDecoder::Base64(pending) => {
+                pending.extend(chunk.iter().filter(|b| !b.is_ascii_whitespace()));
+
+                let len = pending.len() / 4 * 4;
+                let encoded = pending.split_to(len);
+                base64::decode(&encoded)
+                    .map(Bytes::from)
+                    .map_err(|_| MultipartError::TransferEncoding)
+            }
+
+            TransferDecoder::QuotedPrintable(pending) => {
+                pending.extend_from_slice(chunk);
+
+                let mut decoded = BytesMut::with_capacity(pending.len());
+                let mut pos = 0;
+
+                while pos < pending.len() {
+                    if pending[pos] != b'=' {
+                        decoded.extend_from_slice(&pending[pos..=pos]);
+                        pos += 1;
+                        continue;
+                    }
+
+                    match pending[pos + 1..] {
+                        // incomplete escape; wait for more data
+                        [] | [_] => break,
+
+                        // soft line break
+                        [b'\r', b'\n', ..] => pos += 3,
+                        [b'\n', ..] => pos += 2,
+
+                        [hi, lo, ..] => match (hex_value(hi), hex_value(lo)) {
+                            (Some(hi), Some(lo)) => {
+                                decoded.extend_from_slice(&[hi << 4 | lo]);
+                                pos += 3;
+                            }
+                            _ => return Err(MultipartError::TransferEncoding),
+                        },
+                    }
+                }
+
+                pending.advance(pos);
+                Ok(decoded.freeze())
+            }
+        }
+    }
+
+    /// Finish decoding at the end of the field. Fails if undecoded input is left.
+    pub(crate) fn finish(self) -> Result<(), MultipartError> {
+        match self {
+            TransferDecoder::Base64(pending) | TransferDecoder::QuotedPrintable(pending) => {
+                if pending.is_empty() {
+                    Ok(())
+                } else {
+                    Err(MultipartError::TransferEncoding)
+                }
+            }
+        }
+    }
+}
+
+fn hex_value(b: u8) -> Option<u8> {
+    match b {
+        b'0'..=b'9' => Some(b - b'0'),
+        b'A'..=b'F' => Some(b - b'A' + 10),
+        b'a'..=b'f' => Some(b - b'a' + 10),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_base64_chunks() {
+        let mut decoder = TransferDecoder::Base64(BytesMut::new());
+
+        assert_eq!(decoder.decode(b"aGVsbG").unwrap(), "hel");
+        assert_eq!(decoder.decode(b"8gd29y\r\n").unwrap(), "lo wor");
+        assert_eq!(decoder.decode(b"bGQ=").unwrap(), "ld");
+        assert!(decoder.finish().is_ok());
+
+        let mut decoder = TransferDecoder::Base64(BytesMut::new());
+        decoder.decode(b"aGV").unwrap();
+        assert!(decoder.finish().is_err());
+    }
+
+    #[test]
+    fn test_quoted_printable_chunks() {
+        let mut decoder = TransferDecoder::QuotedPrintable(BytesMut::new());
+
+        assert_eq!(decoder.decode(b"caf=").unwrap(), "caf");
+        assert_eq!(decoder.decode(b"C3=A9 au=\r").unwrap(), "\u{e9} au");
+        assert_eq!(decoder.decode(b"\nlait").unwrap(), "lait");
+        assert!(decoder.finish().is_ok());
+
+        let mut decoder = TransferDecoder::QuotedPrintable(BytesMut::new());
+        assert!(decoder.decode(b"=ZZ").is_err());
+    }
+}
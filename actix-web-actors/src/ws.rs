@@ -15,13 +15,14 @@ use actix::{
     SpawnHandle,
 };
 use actix_codec::{Decoder, Encoder};
-use actix_http::ws::{hash_key, Codec};
+use actix_http::h1::WriteBackpressure;
+use actix_http::ws::{hash_key, verify_handshake, Codec, Item};
 pub use actix_http::ws::{
-    CloseCode, CloseReason, Frame, HandshakeError, Message, ProtocolError,
+    CloseCode, CloseReason, Frame, HandshakeError, Message, ProtocolError, WsHandshakeOptions,
 };
 use actix_web::dev::HttpResponseBuilder;
 use actix_web::error::{Error, PayloadError};
-use actix_web::http::{header, Method, StatusCode};
+use actix_web::http::{header, StatusCode};
 use actix_web::{HttpRequest, HttpResponse};
 use bytes::{Bytes, BytesMut};
 use bytestring::ByteString;
@@ -35,7 +36,8 @@ where
     T: Stream<Item = Result<Bytes, PayloadError>> + 'static,
 {
     let mut res = handshake(req)?;
-    Ok(res.streaming(WebsocketContext::create(actor, stream)))
+    let (_, out_stream) = WebsocketContext::create_inner(actor, stream, backpressure(req));
+    Ok(res.streaming(out_stream))
 }
 
 /// Perform WebSocket handshake and start actor.
@@ -59,7 +61,7 @@ where
     T: Stream<Item = Result<Bytes, PayloadError>> + 'static,
 {
     let mut res = handshake(req)?;
-    let (addr, out_stream) = WebsocketContext::create_with_addr(actor, stream);
+    let (addr, out_stream) = WebsocketContext::create_inner(actor, stream, backpressure(req));
     Ok((addr, res.streaming(out_stream)))
 }
 
@@ -77,7 +79,13 @@ where
     T: Stream<Item = Result<Bytes, PayloadError>> + 'static,
 {
     let mut res = handshake_with_protocols(req, protocols)?;
-    Ok(res.streaming(WebsocketContext::create(actor, stream)))
+    let (_, out_stream) = WebsocketContext::create_inner(actor, stream, backpressure(req));
+    Ok(res.streaming(out_stream))
+}
+
+/// Write buffer state of the connection `req` was received on.
+fn backpressure(req: &HttpRequest) -> Option<WriteBackpressure> {
+    req.extensions().get::<WriteBackpressure>().cloned()
 }
 
 /// Prepare WebSocket handshake response.
@@ -100,65 +108,35 @@ pub fn handshake_with_protocols(
     req: &HttpRequest,
     protocols: &[&str],
 ) -> Result<HttpResponseBuilder, HandshakeError> {
-    // WebSocket accepts only GET
-    if *req.method() != Method::GET {
-        return Err(HandshakeError::GetMethodRequired);
-    }
-
-    // check for "UPGRADE" to WebSocket header
-    let has_hdr = if let Some(hdr) = req.headers().get(&header::UPGRADE) {
-        if let Ok(s) = hdr.to_str() {
-            s.to_ascii_lowercase().contains("websocket")
-        } else {
-            false
-        }
-    } else {
-        false
-    };
-    if !has_hdr {
-        return Err(HandshakeError::NoWebsocketUpgrade);
-    }
-
-    // Upgrade connection
-    if !req.head().upgrade() {
-        return Err(HandshakeError::NoConnectionUpgrade);
-    }
+    handshake_with(
+        req,
+        WsHandshakeOptions {
+            protocols,
+            ..Default::default()
+        },
+    )
+}
 
-    // check supported version
-    if !req.headers().contains_key(&header::SEC_WEBSOCKET_VERSION) {
-        return Err(HandshakeError::NoVersionHeader);
-    }
-    let supported_ver = {
-        if let Some(hdr) = req.headers().get(&header::SEC_WEBSOCKET_VERSION) {
-            hdr == "13" || hdr == "8" || hdr == "7"
-        } else {
-            false
-        }
-    };
-    if !supported_ver {
-        return Err(HandshakeError::UnsupportedVersion);
-    }
+/// Prepare WebSocket handshake response, negotiating a subprotocol with `options`.
+///
+/// This function returns handshake `HttpResponse`, ready to send to peer.
+/// It does not perform any IO.
+///
+/// If the client only offers subprotocols the server does not support, the handshake fails with
+/// [`HandshakeError::UnsupportedProtocol`], which responds with `400 Bad Request`.
+pub fn handshake_with(
+    req: &HttpRequest,
+    options: WsHandshakeOptions<'_>,
+) -> Result<HttpResponseBuilder, HandshakeError> {
+    verify_handshake(req.head())?;
 
-    // check client handshake for validity
-    if !req.headers().contains_key(&header::SEC_WEBSOCKET_KEY) {
-        return Err(HandshakeError::BadWebsocketKey);
-    }
     let key = {
         let key = req.headers().get(&header::SEC_WEBSOCKET_KEY).unwrap();
         hash_key(key.as_ref())
     };
 
     // check requested protocols
-    let protocol =
-        req.headers()
-            .get(&header::SEC_WEBSOCKET_PROTOCOL)
-            .and_then(|req_protocols| {
-                let req_protocols = req_protocols.to_str().ok()?;
-                req_protocols
-                    .split(',')
-                    .map(|req_p| req_p.trim())
-                    .find(|req_p| protocols.iter().any(|p| p == req_p))
-            });
+    let protocol = options.negotiate(req.head())?;
 
     let mut response = HttpResponse::build(StatusCode::SWITCHING_PROTOCOLS)
         .upgrade("websocket")
@@ -172,6 +150,21 @@ pub fn handshake_with_protocols(
     Ok(response)
 }
 
+/// What a [`WebsocketContext`] does when its outgoing buffer exceeds the configured limit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CloseOrDrop {
+    /// Close the connection with the [`CloseCode::Policy`] (1008) close code and stop the actor.
+    ///
+    /// If the client is not reading, the close frame can not be delivered and the connection is
+    /// dropped instead.
+    Close,
+
+    /// Drop the oldest buffered text and binary messages until the buffer fits the limit.
+    ///
+    /// Control frames and message fragments are never dropped.
+    Drop,
+}
+
 /// Execution context for `WebSockets` actors
 pub struct WebsocketContext<A>
 where
@@ -179,6 +172,10 @@ where
 {
     inner: ContextParts<A>,
     messages: VecDeque<Option<Message>>,
+    buffered: usize,
+    limits: Option<(usize, CloseOrDrop)>,
+    overflowed: bool,
+    backpressure: Option<WriteBackpressure>,
 }
 
 impl<A> ActorContext for WebsocketContext<A>
@@ -259,15 +256,24 @@ where
         actor: A,
         stream: S,
     ) -> (Addr<A>, impl Stream<Item = Result<Bytes, Error>>)
+    where
+        A: StreamHandler<Result<Message, ProtocolError>>,
+        S: Stream<Item = Result<Bytes, PayloadError>> + 'static,
+    {
+        WebsocketContext::create_inner(actor, stream, None)
+    }
+
+    fn create_inner<S>(
+        actor: A,
+        stream: S,
+        backpressure: Option<WriteBackpressure>,
+    ) -> (Addr<A>, impl Stream<Item = Result<Bytes, Error>>)
     where
         A: StreamHandler<Result<Message, ProtocolError>>,
         S: Stream<Item = Result<Bytes, PayloadError>> + 'static,
     {
         let mb = Mailbox::default();
-        let mut ctx = WebsocketContext {
-            inner: ContextParts::new(mb.sender_producer()),
-            messages: VecDeque::new(),
-        };
+        let mut ctx = WebsocketContext::new(&mb, backpressure);
         ctx.add_stream(WsStream::new(stream, Codec::new()));
 
         let addr = ctx.address();
@@ -287,11 +293,8 @@ where
         S: Stream<Item = Result<Bytes, PayloadError>> + 'static,
     {
         let mb = Mailbox::default();
-        let mut ctx = WebsocketContext {
-            inner: ContextParts::new(mb.sender_producer()),
-            messages: VecDeque::new(),
-        };
-        ctx.add_stream(WsStream::new(stream, codec));
+        let mut ctx = WebsocketContext::new(&mb, None);
+        ctx.add_stream(WsStream::new(stream, codec.clone()));
 
         WebsocketContextFut::new(ctx, actor, mb, codec)
     }
@@ -304,10 +307,7 @@ where
         S: Stream<Item = Result<Bytes, PayloadError>> + 'static,
     {
         let mb = Mailbox::default();
-        let mut ctx = WebsocketContext {
-            inner: ContextParts::new(mb.sender_producer()),
-            messages: VecDeque::new(),
-        };
+        let mut ctx = WebsocketContext::new(&mb, None);
         ctx.add_stream(WsStream::new(stream, Codec::new()));
 
         let act = f(&mut ctx);
@@ -320,6 +320,17 @@ impl<A> WebsocketContext<A>
 where
     A: Actor<Context = Self>,
 {
+    fn new(mb: &Mailbox<A>, backpressure: Option<WriteBackpressure>) -> Self {
+        WebsocketContext {
+            inner: ContextParts::new(mb.sender_producer()),
+            messages: VecDeque::new(),
+            buffered: 0,
+            limits: None,
+            overflowed: false,
+            backpressure,
+        }
+    }
+
     /// Write payload
     ///
     /// This is a low-level function that accepts framed messages that should
@@ -328,7 +339,81 @@ where
     /// that handle the framing for you.
     #[inline]
     pub fn write_raw(&mut self, msg: Message) {
+        // the connection is being closed because of a slow client
+        if self.overflowed {
+            return;
+        }
+
+        self.buffered += message_size(&msg);
         self.messages.push_back(Some(msg));
+
+        if let Some((max, on_overflow)) = self.limits {
+            if self.buffered_bytes() > max {
+                self.overflow(max, on_overflow);
+            }
+        }
+    }
+
+    fn overflow(&mut self, max: usize, on_overflow: CloseOrDrop) {
+        match on_overflow {
+            CloseOrDrop::Close => {
+                self.messages.clear();
+                self.buffered = 0;
+                self.messages
+                    .push_back(Some(Message::Close(Some(CloseCode::Policy.into()))));
+                self.messages.push_back(None);
+                self.overflowed = true;
+                self.stop();
+            }
+
+            CloseOrDrop::Drop => {
+                while self.buffered_bytes() > max {
+                    let pos = self.messages.iter().position(|msg| {
+                        matches!(msg, Some(Message::Text(_)) | Some(Message::Binary(_)))
+                    });
+
+                    match pos.and_then(|pos| self.messages.remove(pos)) {
+                        Some(Some(msg)) => self.buffered -= message_size(&msg),
+                        _ => break,
+                    }
+                }
+            }
+        }
+    }
+
+    /// Limit the amount of outgoing data buffered for a client that does not keep up.
+    ///
+    /// Once more than `max_buffered_bytes` are buffered, the context either closes the
+    /// connection or drops old messages, depending on `on_overflow`. See
+    /// [`buffered_bytes`](Self::buffered_bytes) for what is counted.
+    ///
+    /// For contexts created with [`start`], [`start_with_addr`] or [`start_with_protocols`] the
+    /// actor keeps running while the client is not reading, so messages it receives in the
+    /// meantime are buffered and counted against the limit.
+    pub fn with_limits(&mut self, max_buffered_bytes: usize, on_overflow: CloseOrDrop) {
+        self.limits = Some((max_buffered_bytes, on_overflow));
+
+        if let Some(ref bp) = self.backpressure {
+            bp.subscribe();
+        }
+    }
+
+    /// Number of outgoing payload bytes that have not been written to the client yet.
+    ///
+    /// Includes messages queued in this context and, for contexts created with [`start`],
+    /// [`start_with_addr`] or [`start_with_protocols`], data waiting in the connection's write
+    /// buffer. Applications can use it to shed load before the limit set with
+    /// [`with_limits`](Self::with_limits) is reached.
+    pub fn buffered_bytes(&self) -> usize {
+        self.buffered + self.backpressure.as_ref().map_or(0, |bp| bp.buffered())
+    }
+
+    /// Returns true if the client is not reading and this context is waiting for it.
+    fn is_blocked(&self) -> bool {
+        match self.backpressure {
+            Some(ref bp) => self.limits.is_some() && bp.is_blocked(),
+            None => false,
+        }
     }
 
     /// Send text frame
@@ -423,9 +508,25 @@ where
             let _ = Pin::new(&mut this.fut).poll(cx);
         }
 
+        let ctx = this.fut.ctx();
+
+        // keep messages buffered until the client reads again
+        if ctx.is_blocked() {
+            if ctx.overflowed {
+                return Poll::Ready(Some(Err(io::Error::new(
+                    io::ErrorKind::Other,
+                    "WebSocket client is not reading",
+                )
+                .into())));
+            }
+
+            return Poll::Pending;
+        }
+
         // encode messages
-        while let Some(item) = this.fut.ctx().messages.pop_front() {
+        while let Some(item) = ctx.messages.pop_front() {
             if let Some(msg) = item {
+                ctx.buffered -= message_size(&msg);
                 this.encoder.encode(msg, &mut this.buf)?;
             } else {
                 this.closed = true;
@@ -443,6 +544,24 @@ where
     }
 }
 
+/// Payload size of a message, used to account for buffered data.
+fn message_size(msg: &Message) -> usize {
+    match msg {
+        Message::Text(text) => text.len(),
+        Message::Binary(bin) | Message::Ping(bin) | Message::Pong(bin) => bin.len(),
+        Message::Continuation(item) => match item {
+            Item::FirstText(bin)
+            | Item::FirstBinary(bin)
+            | Item::Continue(bin)
+            | Item::Last(bin) => bin.len(),
+        },
+        Message::Close(reason) => reason.as_ref().map_or(0, |reason| {
+            2 + reason.description.as_ref().map_or(0, |desc| desc.len())
+        }),
+        Message::Nop => 0,
+    }
+}
+
 impl<A, M> ToEnvelope<A, M> for WebsocketContext<A>
 where
     A: Actor<Context = WebsocketContext<A>> + Handler<M>,
@@ -778,5 +897,20 @@ mod tests {
                 .headers()
                 .get(&header::SEC_WEBSOCKET_PROTOCOL)
         );
+
+        let protocols = vec!["p4"];
+
+        assert_eq!(
+            HandshakeError::UnsupportedProtocol,
+            handshake_with_protocols(&req, &protocols).err().unwrap()
+        );
+        assert_eq!(
+            None,
+            handshake(&req)
+                .unwrap()
+                .finish()
+                .headers()
+                .get(&header::SEC_WEBSOCKET_PROTOCOL)
+        );
     }
 }
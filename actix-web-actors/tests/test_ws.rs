@@ -1,3 +1,9 @@
+use std::io::{Read, Write};
+use std::net::TcpStream;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
 use actix::prelude::*;
 use actix_web::{test, web, App, HttpRequest};
 use actix_web_actors::*;
@@ -56,3 +62,124 @@ async fn test_simple() {
     let item = framed.next().await.unwrap().unwrap();
     assert_eq!(item, ws::Frame::Close(Some(ws::CloseCode::Normal.into())));
 }
+
+const LIMIT: usize = 256 * 1024;
+
+/// Sends a 64KiB binary message every millisecond.
+struct Flood {
+    on_overflow: ws::CloseOrDrop,
+    max_buffered: Arc<AtomicUsize>,
+    stopped: Arc<AtomicBool>,
+}
+
+impl Actor for Flood {
+    type Context = ws::WebsocketContext<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        ctx.with_limits(LIMIT, self.on_overflow);
+
+        ctx.run_interval(Duration::from_millis(1), |act, ctx| {
+            ctx.binary(vec![0; 64 * 1024]);
+            act.max_buffered
+                .fetch_max(ctx.buffered_bytes(), Ordering::SeqCst);
+        });
+    }
+
+    fn stopped(&mut self, _: &mut Self::Context) {
+        self.stopped.store(true, Ordering::SeqCst);
+    }
+}
+
+impl StreamHandler<Result<ws::Message, ws::ProtocolError>> for Flood {
+    fn handle(&mut self, _: Result<ws::Message, ws::ProtocolError>, _: &mut Self::Context) {}
+}
+
+/// Start a flooding server and connect a client which never reads.
+fn flood(
+    on_overflow: ws::CloseOrDrop,
+) -> (
+    test::TestServer,
+    TcpStream,
+    Arc<AtomicUsize>,
+    Arc<AtomicBool>,
+) {
+    let max_buffered = Arc::new(AtomicUsize::new(0));
+    let stopped = Arc::new(AtomicBool::new(false));
+
+    let (max, stop) = (max_buffered.clone(), stopped.clone());
+    let srv = test::start(move || {
+        let (max, stop) = (max.clone(), stop.clone());
+        App::new().service(web::resource("/").to(
+            move |req: HttpRequest, stream: web::Payload| {
+                let actor = Flood {
+                    on_overflow,
+                    max_buffered: max.clone(),
+                    stopped: stop.clone(),
+                };
+                async move { ws::start(actor, &req, stream) }
+            },
+        ))
+    });
+
+    let mut client = TcpStream::connect(srv.addr()).unwrap();
+    client
+        .write_all(
+            b"GET / HTTP/1.1\r\n\
+              Host: localhost\r\n\
+              Connection: Upgrade\r\n\
+              Upgrade: websocket\r\n\
+              Sec-WebSocket-Version: 13\r\n\
+              Sec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\n\r\n",
+        )
+        .unwrap();
+
+    (srv, client, max_buffered, stopped)
+}
+
+/// Wait up to 10 seconds for `cond` to become true.
+async fn wait_for(cond: impl Fn() -> bool) -> bool {
+    let start = Instant::now();
+    while !cond() {
+        if start.elapsed() > Duration::from_secs(10) {
+            return false;
+        }
+        actix_rt::time::sleep(Duration::from_millis(10)).await;
+    }
+    true
+}
+
+#[actix_rt::test]
+async fn test_slow_client_close() {
+    let (_srv, mut client, max_buffered, stopped) = flood(ws::CloseOrDrop::Close);
+
+    // the actor is stopped once the client falls behind by more than the limit
+    assert!(wait_for(|| stopped.load(Ordering::SeqCst)).await);
+    assert!(max_buffered.load(Ordering::SeqCst) <= LIMIT);
+
+    // and the connection is dropped without waiting for the client
+    client
+        .set_read_timeout(Some(Duration::from_secs(10)))
+        .unwrap();
+    let mut buf = vec![0; 64 * 1024];
+    loop {
+        match client.read(&mut buf) {
+            Ok(0) => break,
+            Ok(_) => {}
+            Err(err) => {
+                assert_eq!(err.kind(), std::io::ErrorKind::ConnectionReset);
+                break;
+            }
+        }
+    }
+}
+
+#[actix_rt::test]
+async fn test_slow_client_drop() {
+    let (_srv, _client, max_buffered, stopped) = flood(ws::CloseOrDrop::Drop);
+
+    // old messages are dropped to stay within the limit and the actor keeps running
+    assert!(wait_for(|| max_buffered.load(Ordering::SeqCst) > LIMIT / 2).await);
+    actix_rt::time::sleep(Duration::from_millis(100)).await;
+    assert!(max_buffered.load(Ordering::SeqCst) <= LIMIT);
+    assert!(!stopped.load(Ordering::SeqCst));
+}
@@ -45,6 +45,25 @@
 //! }
 //! ```
 //!
+//! # Scopes
+//! Handlers defined in a module can be registered together under a common path prefix. See
+//! [macro@scope] macro docs.
+//!
+//! ```
+//! # use actix_web_codegen::scope;
+//! #[scope("/api")]
+//! mod api {
+//!     # use actix_web::HttpResponse;
+//!     # use actix_web_codegen::get;
+//!     #[get("/status")]
+//!     async fn status() -> HttpResponse {
+//!         HttpResponse::Ok().finish()
+//!     }
+//! }
+//!
+//! let app = actix_web::App::new().service(api::scope());
+//! ```
+//!
 //! [actix-web attributes docs]: https://docs.rs/actix-web/*/actix_web/#attributes
 //! [GET]: macro@get
 //! [POST]: macro@post
@@ -61,6 +80,7 @@
 use proc_macro::TokenStream;
 
 mod route;
+mod scope;
 
 /// Creates resource handler, allowing multiple HTTP method guards.
 ///
@@ -73,6 +93,8 @@ mod route;
 /// - `"path"` - Raw literal string with path for which to register handler.
 /// - `method="HTTP_METHOD"` - Registers HTTP method to provide guard for. Upper-case string, "GET", "POST" for example.
 /// - `guard="function_name"` - Registers function as guard using `actix_web::guard::fn_guard`
+/// - `name="resource_name"` - Resource name for use with `HttpRequest::url_for`. Defaults to the
+///   function name.
 /// - `wrap="Middleware"` - Registers a resource middleware.
 ///
 /// # Notes
@@ -94,6 +116,55 @@ pub fn route(args: TokenStream, input: TokenStream) -> TokenStream {
     route::with_method(None, args, input)
 }
 
+/// Generates a `scope` function in a module, registering the module's handlers under a path.
+///
+/// # Syntax
+/// ```text
+/// #[scope("path")]
+/// mod module_name { ... }
+/// ```
+///
+/// The generated `pub fn scope() -> actix_web::Scope` registers handlers of the module that are
+/// annotated with [macro@route] or one of the single method macros, in the order they are
+/// defined. Nested modules with their own `#[scope]` are registered as nested scopes.
+///
+/// Resource names have to be unique in an app. Handlers in the module tree with the same
+/// resource name, the function name unless set with `name="..."`, are a compile error.
+///
+/// # Example
+///
+/// ```rust
+/// # use actix_web::App;
+/// # use actix_web_codegen::scope;
+/// #[scope("/api")]
+/// mod api {
+///     use actix_web::HttpResponse;
+///     use actix_web_codegen::{get, scope};
+///
+///     #[get("/status")]
+///     async fn status() -> HttpResponse {
+///         HttpResponse::Ok().finish()
+///     }
+///
+///     #[scope("/v1")]
+///     mod v1 {
+///         use actix_web::HttpResponse;
+///         use actix_web_codegen::post;
+///
+///         #[post("/items")]
+///         async fn create_item() -> HttpResponse {
+///             HttpResponse::Created().finish()
+///         }
+///     }
+/// }
+///
+/// let app = App::new().service(api::scope());
+/// ```
+#[proc_macro_attribute]
+pub fn scope(args: TokenStream, input: TokenStream) -> TokenStream {
+    scope::with_scope(args, input)
+}
+
 macro_rules! doc_comment {
     ($x:expr; $($tt:tt)*) => {
         #[doc = $x]
@@ -117,6 +188,8 @@ Creates route handler with `actix_web::guard::", stringify!($variant), "`.
 # Attributes
 - `"path"` - Raw literal string with path for which to register handler.
 - `guard="function_name"` - Registers function as guard using `actix_web::guard::fn_guard`.
+- `name="resource_name"` - Resource name for use with `HttpRequest::url_for`. Defaults to the
+  function name.
 - `wrap="Middleware"` - Registers a resource middleware.
 
 # Notes
@@ -0,0 +1,278 @@
+use proc_macro::TokenStream;
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::{quote, ToTokens};
+use syn::{parse_macro_input, Attribute, Ident, Item, ItemMod, NestedMeta};
+
+/// Attribute macros that turn a function into a resource handler.
+const HANDLER_MACROS: &[&str] = &[
+    "route", "get", "post", "put", "delete", "head", "connect", "options", "trace", "patch",
+];
+
+/// Name of the function generated in the annotated module.
+const SCOPE_FN: &str = "scope";
+
+/// Service registered by the generated scope, in module order.
+enum ScopeService {
+    Handler(Ident),
+    Scope(Ident),
+}
+
+impl ToTokens for ScopeService {
+    fn to_tokens(&self, stream: &mut TokenStream2) {
+        let service = match self {
+            ScopeService::Handler(name) => quote! { #name },
+            ScopeService::Scope(module) => {
+                let scope_fn = Ident::new(SCOPE_FN, Span::call_site());
+                quote! { #module::#scope_fn() }
+            }
+        };
+        stream.extend(service);
+    }
+}
+
+/// Resource name of a handler, with the span to report conflicts at.
+struct ResourceName {
+    name: String,
+    span: Span,
+    /// Index of the nested scope module the handler was found in, `None` for handlers of the
+    /// annotated module itself.
+    nested: Option<usize>,
+}
+
+fn last_segment_is(attr: &Attribute, names: &[&str]) -> bool {
+    attr.path
+        .segments
+        .last()
+        .map_or(false, |seg| names.iter().any(|name| seg.ident == name))
+}
+
+fn is_scope_attr(attr: &Attribute) -> bool {
+    last_segment_is(attr, &[SCOPE_FN])
+}
+
+/// Returns the resource name given to a handler: its `name` attribute or the function name.
+fn resource_name(attr: &Attribute, func: &syn::ItemFn) -> syn::Result<(String, Span)> {
+    if let syn::Meta::List(list) = attr.parse_meta()? {
+        for arg in list.nested {
+            if let NestedMeta::Meta(syn::Meta::NameValue(nv)) = arg {
+                if nv.path.is_ident("name") {
+                    if let syn::Lit::Str(lit) = nv.lit {
+                        return Ok((lit.value(), lit.span()));
+                    }
+                }
+            }
+        }
+    }
+
+    Ok((func.sig.ident.to_string(), func.sig.ident.span()))
+}
+
+/// Collects resource names of handlers in `items`, recursing into nested scope modules.
+fn collect_names(
+    items: &[Item],
+    nested: Option<usize>,
+    names: &mut Vec<ResourceName>,
+) -> syn::Result<()> {
+    for item in items {
+        match item {
+            Item::Fn(func) => {
+                if let Some(attr) = func
+                    .attrs
+                    .iter()
+                    .find(|attr| last_segment_is(attr, HANDLER_MACROS))
+                {
+                    let (name, span) = resource_name(attr, func)?;
+                    names.push(ResourceName { name, span, nested });
+                }
+            }
+            Item::Mod(module) if module.attrs.iter().any(is_scope_attr) => {
+                if let Some((_, ref items)) = module.content {
+                    collect_names(items, nested, names)?;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    Ok(())
+}
+
+struct Scope {
+    path: syn::LitStr,
+    module: ItemMod,
+    services: Vec<ScopeService>,
+    conflicts: Option<syn::Error>,
+}
+
+impl Scope {
+    fn new(args: syn::AttributeArgs, module: ItemMod) -> syn::Result<Self> {
+        let mut path = None;
+
+        for arg in args {
+            match arg {
+                NestedMeta::Lit(syn::Lit::Str(lit)) if path.is_none() => {
+                    path = Some(lit);
+                }
+                NestedMeta::Lit(syn::Lit::Str(lit)) => {
+                    return Err(syn::Error::new_spanned(
+                        lit,
+                        "Multiple paths specified! Should be only one!",
+                    ));
+                }
+                arg => {
+                    return Err(syn::Error::new_spanned(arg, "Unknown attribute."));
+                }
+            }
+        }
+
+        let path = path.ok_or_else(|| {
+            syn::Error::new(
+                Span::call_site(),
+                r#"invalid scope definition, expected #[scope("<path>")]"#,
+            )
+        })?;
+
+        let items = match module.content {
+            Some((_, ref items)) => items,
+            None => {
+                return Err(syn::Error::new_spanned(
+                    module,
+                    "#[scope] can only be used on modules with an inline body",
+                ));
+            }
+        };
+
+        let mut services = Vec::new();
+        let mut names = Vec::new();
+
+        for item in items {
+            match item {
+                Item::Fn(func) => {
+                    if func.sig.ident == SCOPE_FN {
+                        return Err(syn::Error::new_spanned(
+                            &func.sig.ident,
+                            "`scope` is the name of the function generated by #[scope]",
+                        ));
+                    }
+
+                    if let Some(attr) = func
+                        .attrs
+                        .iter()
+                        .find(|attr| last_segment_is(attr, HANDLER_MACROS))
+                    {
+                        let (name, span) = resource_name(attr, func)?;
+                        names.push(ResourceName {
+                            name,
+                            span,
+                            nested: None,
+                        });
+                        services.push(ScopeService::Handler(func.sig.ident.clone()));
+                    }
+                }
+                Item::Mod(nested) if nested.attrs.iter().any(is_scope_attr) => {
+                    if let Some((_, ref items)) = nested.content {
+                        collect_names(items, Some(services.len()), &mut names)?;
+                    }
+                    services.push(ScopeService::Scope(nested.ident.clone()));
+                }
+                _ => {}
+            }
+        }
+
+        Ok(Self {
+            path,
+            module,
+            services,
+            conflicts: check_names(&names).err(),
+        })
+    }
+}
+
+/// Resource names are global to the app, a handler's name must not be used by any other handler
+/// in the module tree. Conflicts within a nested scope module are left to its own #[scope].
+fn check_names(names: &[ResourceName]) -> syn::Result<()> {
+    let mut err: Option<syn::Error> = None;
+
+    for (idx, second) in names.iter().enumerate() {
+        let first = names[..idx].iter().find(|first| {
+            first.name == second.name
+                && (first.nested.is_none() || first.nested != second.nested)
+        });
+
+        if let Some(first) = first {
+            let mut conflict = syn::Error::new(
+                second.span,
+                format!("resource name `{}` is defined more than once", second.name),
+            );
+            conflict.combine(syn::Error::new(
+                first.span,
+                format!("resource name `{}` is first defined here", first.name),
+            ));
+
+            match err {
+                Some(ref mut err) => err.combine(conflict),
+                None => err = Some(conflict),
+            }
+        }
+    }
+
+    match err {
+        Some(err) => Err(err),
+        None => Ok(()),
+    }
+}
+
+impl ToTokens for Scope {
+    fn to_tokens(&self, output: &mut TokenStream2) {
+        let Self {
+            path,
+            module,
+            services,
+            conflicts,
+        } = self;
+        let ItemMod {
+            attrs,
+            vis,
+            mod_token,
+            ident,
+            content,
+            ..
+        } = module;
+        let items = &content.as_ref().unwrap().1;
+        let scope_fn = Ident::new(SCOPE_FN, Span::call_site());
+
+        // generate the scope even with conflicting names, so they are the only errors reported
+        let conflicts = conflicts.as_ref().map(syn::Error::to_compile_error);
+
+        let stream = quote! {
+            #conflicts
+
+            #(#attrs)*
+            #vis #mod_token #ident {
+                #(#items)*
+
+                /// Scope registering the handlers of this module.
+                pub fn #scope_fn() -> actix_web::Scope {
+                    actix_web::web::scope(#path)
+                        #(.service(#services))*
+                }
+            }
+        };
+
+        output.extend(stream);
+    }
+}
+
+pub(crate) fn with_scope(args: TokenStream, input: TokenStream) -> TokenStream {
+    let args = parse_macro_input!(args as syn::AttributeArgs);
+    let module = parse_macro_input!(input as ItemMod);
+
+    match Scope::new(args, module.clone()) {
+        Ok(scope) => scope.into_token_stream().into(),
+        Err(err) => {
+            let mut stream = err.to_compile_error();
+            module.to_tokens(&mut stream);
+            stream.into()
+        }
+    }
+}
@@ -3,8 +3,10 @@ use std::task::{Context, Poll};
 
 use actix_web::dev::{Service, ServiceRequest, ServiceResponse, Transform};
 use actix_web::http::header::{HeaderName, HeaderValue};
-use actix_web::{http, test, web::Path, App, Error, HttpResponse, Responder};
-use actix_web_codegen::{connect, delete, get, head, options, patch, post, put, route, trace};
+use actix_web::{http, test, web::Path, App, Error, HttpRequest, HttpResponse, Responder};
+use actix_web_codegen::{
+    connect, delete, get, head, options, patch, post, put, route, scope, trace,
+};
 use futures_util::future::{self, LocalBoxFuture};
 
 // Make sure that we can name function as 'config'
@@ -83,6 +85,58 @@ async fn route_test() -> impl Responder {
     HttpResponse::Ok()
 }
 
+fn accepts_json(req: &actix_web::dev::RequestHead) -> bool {
+    req.headers
+        .get(http::header::ACCEPT)
+        .map_or(false, |accept| accept == "application/json")
+}
+
+#[route(
+    "/thing/{id}",
+    method = "GET",
+    method = "HEAD",
+    guard = "accepts_json",
+    name = "thing"
+)]
+async fn named_route_test(req: HttpRequest) -> impl Responder {
+    let url = req.url_for("thing", &["1"]).unwrap();
+    HttpResponse::Ok().body(url.path().to_owned())
+}
+
+#[scope("/api")]
+mod api {
+    use actix_web::{HttpRequest, HttpResponse, Responder};
+    use actix_web_codegen::{get, route, scope};
+
+    #[scope("/v1")]
+    mod v1 {
+        use actix_web::{HttpResponse, Responder};
+        use actix_web_codegen::get;
+
+        #[get("/items")]
+        async fn list_items_v1() -> impl Responder {
+            HttpResponse::Ok().body("v1 items")
+        }
+    }
+
+    #[get("/items")]
+    async fn list_items(req: HttpRequest) -> impl Responder {
+        let url = req.url_for_static("list_items").unwrap();
+        HttpResponse::Ok().body(url.path().to_owned())
+    }
+
+    #[route("/items", method = "POST", method = "PUT", name = "store_item")]
+    async fn store() -> impl Responder {
+        HttpResponse::Created()
+    }
+
+    // registered last, so it does not shadow the handlers above
+    #[get("/{tail:.*}")]
+    async fn fallback() -> impl Responder {
+        HttpResponse::NotFound().body("fallback")
+    }
+}
+
 pub struct ChangeStatusCode;
 
 impl<S, B> Transform<S, ServiceRequest> for ChangeStatusCode
@@ -230,6 +284,56 @@ async fn test_body() {
     assert!(!response.status().is_success());
 }
 
+#[actix_rt::test]
+async fn test_named_route() {
+    let srv = test::init_service(App::new().service(named_route_test)).await;
+
+    for method in &[http::Method::GET, http::Method::HEAD] {
+        let req = test::TestRequest::with_uri("/thing/2")
+            .method(method.clone())
+            .insert_header((http::header::ACCEPT, "application/json"))
+            .to_request();
+        let res = test::call_service(&srv, req).await;
+        assert!(res.status().is_success());
+        assert_eq!(test::read_body(res).await, "/thing/1");
+    }
+
+    let req = test::TestRequest::post()
+        .uri("/thing/2")
+        .insert_header((http::header::ACCEPT, "application/json"))
+        .to_request();
+    let res = test::call_service(&srv, req).await;
+    assert!(!res.status().is_success());
+
+    let req = test::TestRequest::with_uri("/thing/2").to_request();
+    let res = test::call_service(&srv, req).await;
+    assert!(!res.status().is_success());
+}
+
+#[actix_rt::test]
+async fn test_scope() {
+    let srv = test::init_service(App::new().service(api::scope())).await;
+
+    let req = test::TestRequest::with_uri("/api/v1/items").to_request();
+    let res = test::call_service(&srv, req).await;
+    assert_eq!(res.status(), http::StatusCode::OK);
+    assert_eq!(test::read_body(res).await, "v1 items");
+
+    let req = test::TestRequest::with_uri("/api/items").to_request();
+    let res = test::call_service(&srv, req).await;
+    assert_eq!(res.status(), http::StatusCode::OK);
+    assert_eq!(test::read_body(res).await, "/api/items");
+
+    let req = test::TestRequest::put().uri("/api/items").to_request();
+    let res = test::call_service(&srv, req).await;
+    assert_eq!(res.status(), http::StatusCode::CREATED);
+
+    let req = test::TestRequest::with_uri("/api/other").to_request();
+    let res = test::call_service(&srv, req).await;
+    assert_eq!(res.status(), http::StatusCode::NOT_FOUND);
+    assert_eq!(test::read_body(res).await, "fallback");
+}
+
 #[actix_rt::test]
 async fn test_auto_async() {
     let srv = test::start(|| App::new().service(auto_async));
@@ -10,6 +10,9 @@ fn compile_macros() {
     t.compile_fail("tests/trybuild/route-duplicate-method-fail.rs");
     t.compile_fail("tests/trybuild/route-unexpected-method-fail.rs");
 
+    t.pass("tests/trybuild/scope-ok.rs");
+    t.compile_fail("tests/trybuild/scope-duplicate-name-fail.rs");
+
     t.pass("tests/trybuild/docstring-ok.rs");
 }
 
@@ -0,0 +1,32 @@
+use actix_web_codegen::*;
+
+#[scope("/api")]
+mod api {
+    use actix_web_codegen::*;
+
+    #[get("/")]
+    async fn index() -> String {
+        "Hello World!".to_owned()
+    }
+
+    #[scope("/v1")]
+    mod v1 {
+        use actix_web_codegen::*;
+
+        #[get("/")]
+        async fn index() -> String {
+            "Hello World!".to_owned()
+        }
+    }
+}
+
+#[actix_web::main]
+async fn main() {
+    use actix_web::{App, test};
+
+    let srv = test::start(|| App::new().service(api::scope()));
+
+    let request = srv.get("/api/v1/");
+    let response = request.send().await.unwrap();
+    assert!(response.status().is_success());
+}
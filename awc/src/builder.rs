@@ -6,7 +6,11 @@ use std::time::Duration;
 use actix_codec::{AsyncRead, AsyncWrite};
 use actix_http::{
     client::{Connector, TcpConnect, TcpConnectError, TcpConnection},
-    http::{self, header, Error as HttpError, HeaderMap, HeaderName, Uri},
+    http::{
+        self,
+        header::{self, HeaderCaseMap, IntoHeaderPair},
+        Error as HttpError, HeaderMap, HeaderName, Uri,
+    },
 };
 use actix_rt::net::TcpStream;
 use actix_service::Service;
@@ -24,7 +28,10 @@ pub struct ClientBuilder<T = (), U = ()> {
     stream_window_size: Option<u32>,
     conn_window_size: Option<u32>,
     headers: HeaderMap,
+    header_case: HeaderCaseMap,
+    preserve_header_case: bool,
     timeout: Option<Duration>,
+    response_decompress: bool,
     connector: Connector<T, U>,
 }
 
@@ -41,7 +48,10 @@ impl ClientBuilder {
         ClientBuilder {
             default_headers: true,
             headers: HeaderMap::new(),
+            header_case: HeaderCaseMap::new(),
+            preserve_header_case: false,
             timeout: Some(Duration::from_secs(5)),
+            response_decompress: true,
             connector: Connector::new(),
             max_http_version: None,
             stream_window_size: None,
@@ -71,7 +81,10 @@ where
         ClientBuilder {
             default_headers: self.default_headers,
             headers: self.headers,
+            header_case: self.header_case,
+            preserve_header_case: self.preserve_header_case,
             timeout: self.timeout,
+            response_decompress: self.response_decompress,
             connector,
             max_http_version: self.max_http_version,
             stream_window_size: self.stream_window_size,
@@ -94,6 +107,16 @@ where
         self
     }
 
+    /// Disable automatic decompress of response's body.
+    ///
+    /// Can be enabled again for individual requests with [`ClientRequest::decompress`].
+    ///
+    /// [`ClientRequest::decompress`]: crate::ClientRequest::decompress
+    pub fn no_decompress(mut self) -> Self {
+        self.response_decompress = false;
+        self
+    }
+
     /// Maximum supported HTTP major version.
     ///
     /// Supported versions are HTTP/1.1 and HTTP/2.
@@ -148,6 +171,39 @@ where
         self
     }
 
+    /// Add default header. Headers added by this method get added to every request.
+    ///
+    /// Unlike [`header`](Self::header), the casing of the header name is recorded for use with
+    /// [`preserve_header_case`](Self::preserve_header_case).
+    pub fn add_default_header(mut self, header: impl IntoHeaderPair) -> Self {
+        let original = header.original_name().map(ToOwned::to_owned);
+
+        match header.try_into_header_pair() {
+            Ok((key, value)) => {
+                if let Some(original) = original {
+                    self.header_case.insert(&key, &original);
+                }
+                self.headers.append(key, value);
+            }
+            Err(e) => log::error!("Header error: {:?}", e.into()),
+        }
+        self
+    }
+
+    /// Write header names of HTTP/1 requests with the casing they were given.
+    ///
+    /// Header names are normally written in lowercase, or in `Camel-Case` if requested. With this
+    /// setting enabled, names given as strings to [`add_default_header`] and to the header methods
+    /// of [`ClientRequest`] are written exactly as given, for servers that do not treat header
+    /// names as case-insensitive. Disabled by default.
+    ///
+    /// [`add_default_header`]: Self::add_default_header
+    /// [`ClientRequest`]: crate::ClientRequest
+    pub fn preserve_header_case(mut self, enable: bool) -> Self {
+        self.preserve_header_case = enable;
+        self
+    }
+
     /// Set client wide HTTP basic authorization header
     pub fn basic_auth<N>(self, username: N, password: Option<&str>) -> Self
     where
@@ -188,6 +244,9 @@ where
         let config = ClientConfig {
             headers: self.headers,
             timeout: self.timeout,
+            response_decompress: self.response_decompress,
+            preserve_header_case: self.preserve_header_case,
+            header_case: self.header_case,
             connector: Box::new(ConnectorWrapper::new(connector.finish())) as _,
         };
 
@@ -33,6 +33,9 @@ pub enum WsClientError {
     /// Invalid challenge response
     #[display(fmt = "Invalid challenge response")]
     InvalidChallengeResponse(String, HeaderValue),
+    /// Invalid SEC-WEBSOCKET-EXTENSIONS header
+    #[display(fmt = "Invalid SEC-WEBSOCKET-EXTENSIONS header")]
+    InvalidExtensionsHeader,
     /// Protocol error
     #[display(fmt = "{}", _0)]
     Protocol(WsProtocolError),
@@ -103,7 +103,7 @@ pub use actix_http::{client::Connector, http};
 
 use actix_http::{
     client::{TcpConnect, TcpConnectError, TcpConnection},
-    http::{Error as HttpError, HeaderMap, Method, Uri},
+    http::{header::HeaderCaseMap, Error as HttpError, HeaderMap, Method, Uri},
     RequestHead,
 };
 use actix_rt::net::TcpStream;
@@ -123,7 +123,7 @@ pub use self::builder::ClientBuilder;
 pub use self::connect::{BoxedSocket, ConnectRequest, ConnectResponse, ConnectService};
 pub use self::frozen::{FrozenClientRequest, FrozenSendBuilder};
 pub use self::request::ClientRequest;
-pub use self::response::{ClientResponse, JsonBody, MessageBody};
+pub use self::response::{ClientResponse, JsonBody, MessageBody, TextBody};
 pub use self::sender::SendClientRequest;
 
 use self::connect::ConnectorWrapper;
@@ -154,6 +154,9 @@ pub(crate) struct ClientConfig {
     pub(crate) connector: ConnectService,
     pub(crate) headers: HeaderMap,
     pub(crate) timeout: Option<Duration>,
+    pub(crate) response_decompress: bool,
+    pub(crate) preserve_header_case: bool,
+    pub(crate) header_case: HeaderCaseMap,
 }
 
 impl Default for Client {
@@ -162,6 +165,9 @@ impl Default for Client {
             connector: Box::new(ConnectorWrapper::new(Connector::new().finish())),
             headers: HeaderMap::new(),
             timeout: Some(Duration::from_secs(5)),
+            response_decompress: true,
+            preserve_header_case: false,
+            header_case: HeaderCaseMap::new(),
         }))
     }
 }
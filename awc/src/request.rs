@@ -10,9 +10,10 @@ use serde::Serialize;
 use actix_http::body::Body;
 #[cfg(feature = "cookies")]
 use actix_http::cookie::{Cookie, CookieJar};
-use actix_http::http::header::{self, IntoHeaderPair};
+use actix_http::http::header::{self, HeaderCaseMap, IntoHeaderPair};
 use actix_http::http::{
-    uri, ConnectionType, Error as HttpError, HeaderMap, HeaderValue, Method, Uri, Version,
+    uri, ConnectionType, Error as HttpError, HeaderMap, HeaderName, HeaderValue, Method, Uri,
+    Version,
 };
 use actix_http::{Error, RequestHead};
 
@@ -70,15 +71,20 @@ impl ClientRequest {
         Uri: TryFrom<U>,
         <Uri as TryFrom<U>>::Error: Into<HttpError>,
     {
+        let head = RequestHead::default();
+        if config.preserve_header_case {
+            head.extensions_mut().insert(config.header_case.clone());
+        }
+
         ClientRequest {
+            response_decompress: config.response_decompress,
             config,
-            head: RequestHead::default(),
+            head,
             err: None,
             addr: None,
             #[cfg(feature = "cookies")]
             cookies: None,
             timeout: None,
-            response_decompress: true,
         }
         .method(method)
         .uri(uri)
@@ -161,8 +167,11 @@ impl ClientRequest {
     where
         H: IntoHeaderPair,
     {
+        let original = self.original_case(&header);
+
         match header.try_into_header_pair() {
             Ok((key, value)) => {
+                self.set_header_case(&key, original);
                 self.head.headers.insert(key, value);
             }
             Err(e) => self.err = Some(e.into()),
@@ -176,9 +185,12 @@ impl ClientRequest {
     where
         H: IntoHeaderPair,
     {
+        let original = self.original_case(&header);
+
         match header.try_into_header_pair() {
             Ok((key, value)) => {
                 if !self.head.headers.contains_key(&key) {
+                    self.set_header_case(&key, original);
                     self.head.headers.insert(key, value);
                 }
             }
@@ -206,14 +218,78 @@ impl ClientRequest {
     where
         H: IntoHeaderPair,
     {
+        let original = self.original_case(&header);
+
         match header.try_into_header_pair() {
-            Ok((key, value)) => self.head.headers.append(key, value),
+            Ok((key, value)) => {
+                self.set_header_case(&key, original);
+                self.head.headers.append(key, value);
+            }
             Err(e) => self.err = Some(e.into()),
         };
 
         self
     }
 
+    /// Remove a header that was added to every request by the client, for this request only.
+    ///
+    /// Does nothing if the client has no default header with this name.
+    ///
+    /// ```rust
+    /// # #[actix_rt::main]
+    /// # async fn main() {
+    /// use awc::Client;
+    ///
+    /// let client = Client::builder()
+    ///     .add_default_header(("X-Api-Key", "secret"))
+    ///     .finish();
+    ///
+    /// let req = client
+    ///     .get("http://www.rust-lang.org")
+    ///     .remove_default_header("x-api-key");
+    /// assert!(!req.headers().contains_key("x-api-key"));
+    /// # }
+    /// ```
+    pub fn remove_default_header<K>(mut self, name: K) -> Self
+    where
+        HeaderName: TryFrom<K>,
+        <HeaderName as TryFrom<K>>::Error: Into<HttpError>,
+    {
+        match HeaderName::try_from(name) {
+            Ok(name) => {
+                if self.config.headers.contains_key(&name) {
+                    self.head.headers.remove(&name);
+                }
+            }
+            Err(e) => self.err = Some(e.into()),
+        }
+
+        self
+    }
+
+    /// Returns the original casing of the name of `header` if header casing is preserved.
+    fn original_case<H: IntoHeaderPair>(&self, header: &H) -> Option<String> {
+        if self.config.preserve_header_case {
+            header.original_name().map(ToOwned::to_owned)
+        } else {
+            None
+        }
+    }
+
+    fn set_header_case(&mut self, name: &HeaderName, original: Option<String>) {
+        if let Some(original) = original {
+            let mut extensions = self.head.extensions_mut();
+            match extensions.get_mut::<HeaderCaseMap>() {
+                Some(case) => case.insert(name, &original),
+                None => {
+                    let mut case = HeaderCaseMap::new();
+                    case.insert(name, &original);
+                    extensions.insert(case);
+                }
+            }
+        }
+    }
+
     /// Send headers in `Camel-Case` form.
     #[inline]
     pub fn camel_case(mut self) -> Self {
@@ -229,6 +305,22 @@ impl ClientRequest {
         self
     }
 
+    /// Send an `Expect: 100-continue` header and wait for the server before sending the body.
+    ///
+    /// The body is sent once the server responds with `100 Continue`, or when no response arrives
+    /// within `timeout`. If the server responds with a final status instead, such as
+    /// `401 Unauthorized` or `417 Expectation Failed`, that response is returned and the body is
+    /// not sent. This setting affects only HTTP/1 connections.
+    pub fn expect_continue(mut self, timeout: Duration) -> Self {
+        self.head
+            .headers
+            .insert(header::EXPECT, HeaderValue::from_static("100-continue"));
+        self.head
+            .extensions_mut()
+            .insert(actix_http::client::ExpectContinue(timeout));
+        self
+    }
+
     /// Set request's content type
     #[inline]
     pub fn content_type<V>(mut self, value: V) -> Self
@@ -312,6 +404,13 @@ impl ClientRequest {
         self
     }
 
+    /// Set whether response's body is automatically decompressed. Overrides client wide
+    /// decompress setting.
+    pub fn decompress(mut self, enable: bool) -> Self {
+        self.response_decompress = enable;
+        self
+    }
+
     /// Set request timeout. Overrides client wide timeout setting.
     ///
     /// Request timeout is the total time before a response must be received.
@@ -1,4 +1,5 @@
 use std::{
+    borrow::Cow,
     cell::{Ref, RefMut},
     fmt,
     future::Future,
@@ -16,6 +17,7 @@ use actix_http::{
 };
 use actix_rt::time::{sleep, Sleep};
 use bytes::{Bytes, BytesMut};
+use encoding_rs::{Encoding, UTF_8};
 use futures_core::{ready, Stream};
 use serde::de::DeserializeOwned;
 
@@ -191,6 +193,16 @@ where
         MessageBody::new(self)
     }
 
+    /// Loads HTTP response's body and decodes it as text.
+    ///
+    /// The body is decoded according to the charset of the response's content type, falling back
+    /// to UTF-8 when it is missing or unknown. Invalid byte sequences are replaced with
+    /// `U+FFFD REPLACEMENT CHARACTER`. Any content encoding has already been removed at this
+    /// point unless automatic decompression is disabled.
+    pub fn text(&mut self) -> TextBody<S> {
+        TextBody::new(self)
+    }
+
     /// Loads and parse `application/json` encoded body.
     /// Return `JsonBody<T>` future. It resolves to a `T` value.
     ///
@@ -306,6 +318,49 @@ where
     }
 }
 
+/// Future that resolves to a complete HTTP message body decoded as text.
+pub struct TextBody<S> {
+    encoding: &'static Encoding,
+    body: MessageBody<S>,
+}
+
+impl<S> TextBody<S>
+where
+    S: Stream<Item = Result<Bytes, PayloadError>>,
+{
+    /// Create `TextBody` for request.
+    pub fn new(res: &mut ClientResponse<S>) -> Self {
+        TextBody {
+            encoding: res.encoding().unwrap_or(UTF_8),
+            body: MessageBody::new(res),
+        }
+    }
+
+    /// Change max size of payload before decoding. By default max size is 256kB
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.body = self.body.limit(limit);
+        self
+    }
+}
+
+impl<S> Future for TextBody<S>
+where
+    S: Stream<Item = Result<Bytes, PayloadError>> + Unpin,
+{
+    type Output = Result<String, PayloadError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        let body = ready!(Pin::new(&mut this.body).poll(cx))?;
+        Poll::Ready(Ok(decode_text(&body, this.encoding).into_owned()))
+    }
+}
+
+/// Decodes `body` with `encoding`, without copying when it is valid UTF-8 already.
+fn decode_text<'a>(body: &'a [u8], encoding: &'static Encoding) -> Cow<'a, str> {
+    encoding.decode_without_bom_handling(body).0
+}
+
 /// Response's payload json parser, it resolves to a deserialized `T` value.
 ///
 /// Returns error:
@@ -314,6 +369,7 @@ where
 /// * content length is greater than 64k
 pub struct JsonBody<S, U> {
     length: Option<usize>,
+    encoding: &'static Encoding,
     err: Option<JsonPayloadError>,
     timeout: ResponseTimeout,
     fut: Option<ReadBody<S>>,
@@ -336,6 +392,7 @@ where
         if !json {
             return JsonBody {
                 length: None,
+                encoding: UTF_8,
                 fut: None,
                 timeout: ResponseTimeout::default(),
                 err: Some(JsonPayloadError::ContentType),
@@ -355,6 +412,7 @@ where
 
         JsonBody {
             length: len,
+            encoding: res.encoding().unwrap_or(UTF_8),
             err: None,
             timeout: std::mem::take(&mut res.timeout),
             fut: Some(ReadBody::new(res.take_payload(), 65536)),
@@ -400,8 +458,14 @@ where
             .poll_timeout(cx)
             .map_err(JsonPayloadError::Payload)?;
 
-        let body = ready!(Pin::new(&mut self.get_mut().fut.as_mut().unwrap()).poll(cx))?;
-        Poll::Ready(serde_json::from_slice::<U>(&body).map_err(JsonPayloadError::from))
+        let this = self.get_mut();
+        let body = ready!(Pin::new(&mut this.fut.as_mut().unwrap()).poll(cx))?;
+        let res = if this.encoding == UTF_8 {
+            serde_json::from_slice::<U>(&body)
+        } else {
+            serde_json::from_str::<U>(&decode_text(&body, this.encoding))
+        };
+        Poll::Ready(res.map_err(JsonPayloadError::from))
     }
 }
 
@@ -482,6 +546,41 @@ mod tests {
         }
     }
 
+    #[actix_rt::test]
+    async fn test_text_body() {
+        let mut req = TestResponse::default()
+            .set_payload(Bytes::from_static("caf\u{e9}".as_bytes()))
+            .finish();
+        assert_eq!(req.text().await.unwrap(), "caf\u{e9}");
+
+        let mut req = TestResponse::default()
+            .header(
+                header::CONTENT_TYPE,
+                header::HeaderValue::from_static("text/plain; charset=iso-8859-1"),
+            )
+            .set_payload(Bytes::from_static(b"caf\xe9"))
+            .finish();
+        assert_eq!(req.text().await.unwrap(), "caf\u{e9}");
+
+        // unknown charsets fall back to UTF-8
+        let mut req = TestResponse::default()
+            .header(
+                header::CONTENT_TYPE,
+                header::HeaderValue::from_static("text/plain; charset=unknown"),
+            )
+            .set_payload(Bytes::from_static(b"caf\xc3\xa9"))
+            .finish();
+        assert_eq!(req.text().await.unwrap(), "caf\u{e9}");
+
+        let mut req = TestResponse::default()
+            .set_payload(Bytes::from_static(b"11111111111111"))
+            .finish();
+        match req.text().limit(5).await.err().unwrap() {
+            PayloadError::Overflow => {}
+            _ => unreachable!("error"),
+        }
+    }
+
     #[derive(Serialize, Deserialize, PartialEq, Debug)]
     struct MyObject {
         name: String,
@@ -550,5 +649,20 @@ mod tests {
                 name: "test".to_owned()
             }
         );
+        let mut req = TestResponse::default()
+            .header(
+                header::CONTENT_TYPE,
+                header::HeaderValue::from_static("application/json; charset=iso-8859-1"),
+            )
+            .set_payload(Bytes::from_static(b"{\"name\": \"caf\xe9\"}"))
+            .finish();
+
+        let json = JsonBody::<_, MyObject>::new(&mut req).await;
+        assert_eq!(
+            json.ok().unwrap(),
+            MyObject {
+                name: "caf\u{e9}".to_owned()
+            }
+        );
     }
 }
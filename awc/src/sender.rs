@@ -8,7 +8,7 @@ use std::{
 };
 
 use actix_http::{
-    body::{Body, BodyStream},
+    body::{Body, BodyStream, IntoErrorStream},
     http::{
         header::{self, HeaderMap, HeaderName, IntoHeaderValue},
         Error as HttpError,
@@ -271,7 +271,7 @@ impl RequestSender {
             response_decompress,
             timeout,
             config,
-            Body::from_message(BodyStream::new(stream)),
+            Body::from_message(BodyStream::new(IntoErrorStream::new(stream))),
         )
     }
 
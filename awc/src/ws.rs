@@ -58,6 +58,9 @@ pub struct WebsocketsRequest {
     server_mode: bool,
     config: Rc<ClientConfig>,
 
+    #[cfg(feature = "compress")]
+    deflate: Option<ws::DeflateConfig>,
+
     #[cfg(feature = "cookies")]
     cookies: Option<CookieJar>,
 }
@@ -95,6 +98,8 @@ impl WebsocketsRequest {
             server_mode: false,
             #[cfg(feature = "cookies")]
             cookies: None,
+            #[cfg(feature = "compress")]
+            deflate: None,
         }
     }
 
@@ -155,6 +160,16 @@ impl WebsocketsRequest {
         self
     }
 
+    /// Offer the `permessage-deflate` extension to the server.
+    ///
+    /// Messages are compressed if the server accepts the offer. The decompressed size of
+    /// received messages is limited by the max frame size.
+    #[cfg(feature = "compress")]
+    pub fn deflate(mut self, config: ws::DeflateConfig) -> Self {
+        self.deflate = Some(config);
+        self
+    }
+
     /// Disable payload masking. By default ws client masks frame payload.
     pub fn server_mode(mut self) -> Self {
         self.server_mode = true;
@@ -324,6 +339,13 @@ impl WebsocketsRequest {
             HeaderValue::try_from(key.as_str()).unwrap(),
         );
 
+        #[cfg(feature = "compress")]
+        if let Some(ref deflate) = self.deflate {
+            self.head
+                .headers
+                .insert(header::SEC_WEBSOCKET_EXTENSIONS, deflate.offer());
+        }
+
         let head = self.head;
         let max_size = self.max_size;
         let server_mode = self.server_mode;
@@ -397,16 +419,29 @@ impl WebsocketsRequest {
             return Err(WsClientError::MissingWebSocketAcceptHeader);
         };
 
+        let mut codec = if server_mode {
+            ws::Codec::new().max_size(max_size)
+        } else {
+            ws::Codec::new().max_size(max_size).client_mode()
+        };
+
+        // check for accepted extensions
+        #[cfg(feature = "compress")]
+        if let Some(ref deflate) = self.deflate {
+            match deflate.accept_response(&head.headers) {
+                Ok(Some(agreed)) => codec = codec.deflate(agreed),
+                Ok(None) => {}
+                Err(_) => {
+                    log::trace!("Invalid permessage-deflate response");
+                    return Err(WsClientError::InvalidExtensionsHeader);
+                }
+            }
+        }
+
         // response and ws framed
         Ok((
             ClientResponse::new(head, Payload::None),
-            framed.into_map_codec(|_| {
-                if server_mode {
-                    ws::Codec::new().max_size(max_size)
-                } else {
-                    ws::Codec::new().max_size(max_size).client_mode()
-                }
-            }),
+            framed.into_map_codec(|_| codec.clone()),
         ))
     }
 }
@@ -5,19 +5,20 @@ use std::sync::Arc;
 use std::time::Duration;
 
 use brotli2::write::BrotliEncoder;
-use bytes::Bytes;
+use bytes::{Bytes, BytesMut};
 use flate2::read::GzDecoder;
 use flate2::write::GzEncoder;
 use flate2::Compression;
-use futures_util::{future::ok, stream};
+use futures_util::{future::ok, stream, StreamExt};
 use rand::Rng;
 
 use actix_http::{
+    error,
     http::{self, StatusCode},
-    HttpService,
+    HttpService, Request, Response,
 };
 use actix_http_test::test_server;
-use actix_service::{map_config, pipeline_factory};
+use actix_service::{fn_service, map_config, pipeline_factory};
 use actix_web::{
     dev::{AppConfig, BodyEncoding},
     http::{header, Cookie},
@@ -527,6 +528,49 @@ async fn test_client_gzip_encoding() {
     assert_eq!(bytes, Bytes::from_static(STR.as_ref()));
 }
 
+#[actix_rt::test]
+async fn test_client_gzip_encoding_text() {
+    let srv = test::start(|| {
+        App::new().service(web::resource("/").route(web::to(|| {
+            let mut e = GzEncoder::new(Vec::new(), Compression::default());
+            e.write_all(b"Gr\xfc\xdfe aus K\xf6ln").unwrap();
+            let data = e.finish().unwrap();
+
+            HttpResponse::Ok()
+                .insert_header(("content-encoding", "gzip"))
+                .content_type("text/plain; charset=iso-8859-1")
+                .body(data)
+        })))
+    });
+
+    let mut response = srv.get("/").send().await.unwrap();
+    assert!(response.status().is_success());
+    assert_eq!(response.text().await.unwrap(), "Grüße aus Köln");
+
+    // decompression disabled for the client and enabled again for the request
+    let client = awc::Client::builder().no_decompress().finish();
+    let mut response = client.get(srv.url("/")).send().await.unwrap();
+    let bytes = response.body().await.unwrap();
+    let mut dec = Vec::new();
+    GzDecoder::new(&bytes[..]).read_to_end(&mut dec).unwrap();
+    assert_eq!(dec, b"Gr\xfc\xdfe aus K\xf6ln");
+
+    let mut response = client
+        .get(srv.url("/"))
+        .decompress(true)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.text().await.unwrap(), "Grüße aus Köln");
+
+    // and disabled for a single request
+    let mut response = srv.get("/").decompress(false).send().await.unwrap();
+    let bytes = response.body().await.unwrap();
+    let mut dec = Vec::new();
+    GzDecoder::new(&bytes[..]).read_to_end(&mut dec).unwrap();
+    assert_eq!(dec, b"Gr\xfc\xdfe aus K\xf6ln");
+}
+
 #[actix_rt::test]
 async fn test_client_gzip_encoding_large() {
     let srv = test::start(|| {
@@ -673,6 +717,78 @@ async fn test_client_deflate_encoding_large_random() {
     assert_eq!(bytes, Bytes::from(data));
 }
 
+#[actix_rt::test]
+async fn test_client_expect_continue() {
+    let srv = test_server(|| {
+        HttpService::build()
+            .expect(fn_service(|req: Request| async move {
+                // slow to decide when asked to, so the client stops waiting
+                if req.head().uri.query() == Some("slow") {
+                    actix_rt::time::sleep(Duration::from_millis(300)).await;
+                }
+
+                if req.headers().contains_key(header::AUTHORIZATION) {
+                    Ok(req)
+                } else {
+                    Err(error::ErrorUnauthorized("unauthorized"))
+                }
+            }))
+            .finish(|mut req: Request| async move {
+                let mut body = BytesMut::new();
+                let mut payload = req.take_payload();
+                while let Some(chunk) = payload.next().await {
+                    body.extend_from_slice(&chunk?);
+                }
+                Ok::<_, Error>(Response::Ok().body(body.freeze()))
+            })
+            .tcp()
+    })
+    .await;
+
+    let polled = Arc::new(AtomicUsize::new(0));
+    let body = |polled: &Arc<AtomicUsize>| {
+        let polled = Arc::clone(polled);
+        stream::once(async move {
+            polled.fetch_add(1, Ordering::Relaxed);
+            Ok::<_, Error>(Bytes::from_static(STR.as_ref()))
+        })
+        .boxed_local()
+    };
+
+    // rejected by the server; the body is never sent
+    let res = srv
+        .post("/")
+        .expect_continue(Duration::from_secs(5))
+        .send_stream(body(&polled))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+    assert_eq!(polled.load(Ordering::Relaxed), 0);
+
+    // accepted by the server
+    let mut res = srv
+        .post("/")
+        .insert_header((header::AUTHORIZATION, "Bearer token"))
+        .expect_continue(Duration::from_secs(5))
+        .send_stream(body(&polled))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+    assert_eq!(res.body().await.unwrap(), STR.as_bytes());
+    assert_eq!(polled.load(Ordering::Relaxed), 1);
+
+    // no interim response in time; the body is sent anyway and the late `100 Continue` skipped
+    let mut res = srv
+        .post("/?slow")
+        .insert_header((header::AUTHORIZATION, "Bearer token"))
+        .expect_continue(Duration::from_millis(20))
+        .send_body(STR)
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+    assert_eq!(res.body().await.unwrap(), STR.as_bytes());
+}
+
 #[actix_rt::test]
 async fn test_client_streaming_explicit() {
     let srv = test::start(|| {
@@ -816,6 +932,146 @@ async fn client_unread_response() {
     assert_eq!(bytes, Bytes::from_static(b""));
 }
 
+#[actix_rt::test]
+async fn client_idle_connection_closed_by_server() {
+    let addr = test::unused_addr();
+    let lst = std::net::TcpListener::bind(addr).unwrap();
+    let accepted = Arc::new(AtomicUsize::new(0));
+
+    // keep-alive server that closes connections after 100ms of idleness
+    let accepted_clone = accepted.clone();
+    std::thread::spawn(move || {
+        for stream in lst.incoming() {
+            accepted_clone.fetch_add(1, Ordering::Relaxed);
+            let mut stream = stream.unwrap();
+            std::thread::spawn(move || {
+                stream
+                    .set_read_timeout(Some(Duration::from_millis(100)))
+                    .unwrap();
+
+                let mut head = Vec::new();
+                let mut b = [0; 1000];
+                loop {
+                    match stream.read(&mut b) {
+                        Ok(0) | Err(_) => return,
+                        Ok(n) => head.extend_from_slice(&b[..n]),
+                    }
+
+                    if head.ends_with(b"\r\n\r\n") {
+                        head.clear();
+                        let _ = stream.write_all(
+                            b"HTTP/1.1 200 OK\r\n\
+                            content-length: 8\r\n\
+                            \r\n\
+                            welcome!",
+                        );
+                    }
+                }
+            });
+        }
+    });
+
+    let client = awc::Client::builder()
+        .connector(awc::Connector::new().max_idle_age(Duration::from_secs(10)))
+        .finish();
+    let url = format!("http://{}/", addr);
+
+    for _ in 0..20 {
+        let mut res = client.get(url.as_str()).send().await.unwrap();
+        assert!(res.status().is_success());
+        assert_eq!(res.body().await.unwrap(), "welcome!");
+
+        actix_rt::time::sleep(Duration::from_millis(200)).await;
+    }
+
+    // every request found its pooled connection closed and opened a new one
+    assert_eq!(accepted.load(Ordering::Relaxed), 20);
+
+    // connections idle for longer than max idle age are closed by the client
+    let client = awc::Client::builder()
+        .connector(awc::Connector::new().max_idle_age(Duration::from_millis(20)))
+        .finish();
+
+    for _ in 0..5 {
+        let mut res = client.get(url.as_str()).send().await.unwrap();
+        assert!(res.status().is_success());
+        assert_eq!(res.body().await.unwrap(), "welcome!");
+
+        actix_rt::time::sleep(Duration::from_millis(80)).await;
+    }
+
+    assert_eq!(accepted.load(Ordering::Relaxed), 25);
+}
+
+#[actix_rt::test]
+async fn client_preserve_header_case() {
+    let addr = test::unused_addr();
+    let lst = std::net::TcpListener::bind(addr).unwrap();
+    let (tx, rx) = std::sync::mpsc::channel();
+
+    // records the raw request heads
+    std::thread::spawn(move || {
+        for stream in lst.incoming() {
+            let mut stream = stream.unwrap();
+            let mut head = Vec::new();
+            let mut b = [0; 1000];
+            while !head.ends_with(b"\r\n\r\n") {
+                let n = stream.read(&mut b).unwrap();
+                head.extend_from_slice(&b[..n]);
+            }
+            tx.send(String::from_utf8(head).unwrap()).unwrap();
+            let _ = stream.write_all(
+                b"HTTP/1.1 200 OK\r\n\
+                connection: close\r\n\
+                content-length: 0\r\n\
+                \r\n",
+            );
+        }
+    });
+
+    let url = format!("http://{}/", addr);
+    let client = awc::Client::builder()
+        .add_default_header(("X-Api-Key", "secret"))
+        .preserve_header_case(true)
+        .finish();
+
+    let res = client
+        .post(url.as_str())
+        .insert_header(("SOAPAction", "urn:get"))
+        .send()
+        .await
+        .unwrap();
+    assert!(res.status().is_success());
+    let head = rx.recv().unwrap();
+    assert!(head.contains("\r\nSOAPAction: urn:get\r\n"));
+    assert!(head.contains("\r\nX-Api-Key: secret\r\n"));
+
+    // default header dropped for one request
+    let res = client
+        .post(url.as_str())
+        .remove_default_header("x-api-key")
+        .send()
+        .await
+        .unwrap();
+    assert!(res.status().is_success());
+    let head = rx.recv().unwrap();
+    assert!(!head.to_lowercase().contains("x-api-key"));
+
+    // casing is not preserved by default
+    let res = awc::Client::builder()
+        .add_default_header(("X-Api-Key", "secret"))
+        .finish()
+        .post(url.as_str())
+        .insert_header(("SOAPAction", "urn:get"))
+        .send()
+        .await
+        .unwrap();
+    assert!(res.status().is_success());
+    let head = rx.recv().unwrap();
+    assert!(head.contains("\r\nsoapaction: urn:get\r\n"));
+    assert!(head.contains("\r\nx-api-key: secret\r\n"));
+}
+
 #[actix_rt::test]
 async fn client_basic_auth() {
     let srv = test::start(|| {
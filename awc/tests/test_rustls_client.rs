@@ -68,7 +68,8 @@ async fn test_connection_reuse_h2() {
                     |_| AppConfig::default(),
                 ))
                 .rustls(tls_config())
-                .map_err(|_| ()),
+                .map_err(|_| ())
+                .map_init_err(|_| ()),
         )
     })
     .await;
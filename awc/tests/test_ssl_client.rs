@@ -59,7 +59,8 @@ async fn test_connection_reuse_h2() {
                     |_| AppConfig::default(),
                 ))
                 .openssl(tls_config())
-                .map_err(|_| ()),
+                .map_err(|_| ())
+                .map_init_err(|_| ()),
         )
     })
     .await;
@@ -66,3 +66,68 @@ async fn test_simple() {
     let item = framed.next().await.unwrap().unwrap();
     assert_eq!(item, ws::Frame::Close(Some(ws::CloseCode::Normal.into())));
 }
+
+#[actix_rt::test]
+async fn test_deflate() {
+    let srv = test_server(|| {
+        HttpService::build()
+            .upgrade(|(req, mut framed): (Request, Framed<_, _>)| {
+                async move {
+                    let config = ws::DeflateConfig::new().server_no_context_takeover(true);
+                    let (mut res, codec) = ws::handshake_with_deflate(req.head(), &config)
+                        .map_err(|_| io::Error::new(io::ErrorKind::Other, "handshake"))?;
+
+                    // send handshake response
+                    framed
+                        .send(h1::Message::Item((
+                            res.finish().drop_body(),
+                            BodySize::None,
+                        )))
+                        .await?;
+
+                    // start WebSocket service
+                    let framed = framed.replace_codec(codec);
+                    ws::Dispatcher::with(framed, ws_service).await
+                }
+            })
+            .finish(|_| ok::<_, Error>(Response::NotFound()))
+            .tcp()
+    })
+    .await;
+
+    let (res, mut framed) = awc::Client::new()
+        .ws(srv.url("/"))
+        .deflate(ws::DeflateConfig::new().client_no_context_takeover(true))
+        .connect()
+        .await
+        .unwrap();
+    assert_eq!(
+        res.headers()
+            .get(actix_http::http::header::SEC_WEBSOCKET_EXTENSIONS)
+            .unwrap(),
+        "permessage-deflate; server_no_context_takeover; client_no_context_takeover"
+    );
+
+    for text in &["text ".repeat(100), "text ".repeat(300)] {
+        framed
+            .send(ws::Message::Text(text.clone().into()))
+            .await
+            .unwrap();
+        let item = framed.next().await.unwrap().unwrap();
+        assert_eq!(item, ws::Frame::Text(Bytes::from(text.clone())));
+    }
+
+    framed
+        .send(ws::Message::Binary(Bytes::from(vec![7; 10_000])))
+        .await
+        .unwrap();
+    let item = framed.next().await.unwrap().unwrap();
+    assert_eq!(item, ws::Frame::Binary(Bytes::from(vec![7; 10_000])));
+
+    framed
+        .send(ws::Message::Close(Some(ws::CloseCode::Normal.into())))
+        .await
+        .unwrap();
+    let item = framed.next().await.unwrap().unwrap();
+    assert_eq!(item, ws::Frame::Close(Some(ws::CloseCode::Normal.into())));
+}
@@ -1,12 +1,12 @@
 //! This example shows how to use `actix_web::HttpServer::on_connect` to access a lower-level socket
-//! properties and pass them to a handler through request-local data.
+//! properties and pass them to a handler through connection-level data.
 //!
 //! For an example of extracting a client TLS certificate, see:
 //! <https://github.com/actix/examples/tree/HEAD/rustls-client-cert>
 
 use std::{any::Any, io, net::SocketAddr};
 
-use actix_web::{dev::Extensions, rt::net::TcpStream, web, App, HttpServer};
+use actix_web::{dev::Extensions, rt::net::TcpStream, web, App, HttpRequest, HttpServer};
 
 #[derive(Debug, Clone)]
 struct ConnectionInfo {
@@ -15,11 +15,11 @@ struct ConnectionInfo {
     ttl: Option<u32>,
 }
 
-async fn route_whoami(conn_info: web::ReqData<ConnectionInfo>) -> String {
-    format!(
-        "Here is some info about your connection:\n\n{:#?}",
-        conn_info
-    )
+async fn route_whoami(req: HttpRequest) -> String {
+    match req.conn_data::<ConnectionInfo>() {
+        Some(info) => format!("Here is some info about your connection:\n\n{:#?}", info),
+        None => "Unknown connection info".to_owned(),
+    }
 }
 
 fn get_conn_info(connection: &dyn Any, data: &mut Extensions) {
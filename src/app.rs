@@ -36,6 +36,8 @@ pub struct App<T, B> {
     data_factories: Vec<FnDataFactory>,
     external: Vec<ResourceDef>,
     extensions: Extensions,
+    auto_head: bool,
+    config_error: Option<Error>,
     _phantom: PhantomData<B>,
 }
 
@@ -52,6 +54,8 @@ impl App<AppEntry, Body> {
             factory_ref: fref,
             external: Vec::new(),
             extensions: Extensions::new(),
+            auto_head: true,
+            config_error: None,
             _phantom: PhantomData,
         }
     }
@@ -179,6 +183,46 @@ where
         self
     }
 
+    /// Run fallible external configuration as part of the application building process.
+    ///
+    /// Works like [`configure`](Self::configure) but the configuration function can return an
+    /// error, e.g. when it reads files or validates settings. When it fails, nothing it registered
+    /// is kept and the application fails to initialize with the first such error.
+    ///
+    /// ```rust
+    /// use actix_web::{web, App, HttpResponse};
+    ///
+    /// fn config(cfg: &mut web::ServiceConfig) -> Result<(), std::io::Error> {
+    ///     let greeting = std::fs::read_to_string("greeting.txt")?;
+    ///     cfg.route("/", web::get().to(move || HttpResponse::Ok().body(greeting.clone())));
+    ///     Ok(())
+    /// }
+    ///
+    /// App::new().try_configure(config);
+    /// ```
+    pub fn try_configure<F, E>(mut self, f: F) -> Self
+    where
+        F: FnOnce(&mut ServiceConfig) -> Result<(), E>,
+        E: Into<Error>,
+    {
+        let mut cfg = ServiceConfig::new();
+
+        match f(&mut cfg) {
+            Ok(()) => {
+                self.services.extend(cfg.services);
+                self.external.extend(cfg.external);
+                self.extensions.extend(cfg.app_data);
+            }
+            Err(err) => {
+                if self.config_error.is_none() {
+                    self.config_error = Some(err.into());
+                }
+            }
+        }
+
+        self
+    }
+
     /// Configure route for a specific path.
     ///
     /// This is a simplified version of the `App::service()` method.
@@ -310,6 +354,25 @@ where
         self
     }
 
+    /// Set whether `HEAD` requests are answered by `GET` routes.
+    ///
+    /// Enabled by default. A `HEAD` request that no route or resource accepts is routed as if it
+    /// were a `GET` request, so every `GET`-able resource supports `HEAD`; handlers still see the
+    /// `HEAD` method and the response body is not sent. Explicit `HEAD` routes take precedence.
+    ///
+    /// ```rust
+    /// use actix_web::{web, App, HttpResponse};
+    ///
+    /// // `HEAD /index.html` gets `405 Method Not Allowed`
+    /// let app = App::new()
+    ///     .auto_head(false)
+    ///     .route("/index.html", web::get().to(|| HttpResponse::Ok()));
+    /// ```
+    pub fn auto_head(mut self, enabled: bool) -> Self {
+        self.auto_head = enabled;
+        self
+    }
+
     /// Registers middleware, in the form of a middleware component (type),
     /// that runs during inbound and/or outbound processing in the request
     /// life-cycle (request -> response), modifying request/response as
@@ -371,6 +434,8 @@ where
             factory_ref: self.factory_ref,
             external: self.external,
             extensions: self.extensions,
+            auto_head: self.auto_head,
+            config_error: self.config_error,
             _phantom: PhantomData,
         }
     }
@@ -432,6 +497,8 @@ where
             factory_ref: self.factory_ref,
             external: self.external,
             extensions: self.extensions,
+            auto_head: self.auto_head,
+            config_error: self.config_error,
             _phantom: PhantomData,
         }
     }
@@ -458,6 +525,8 @@ where
             default: self.default,
             factory_ref: self.factory_ref,
             extensions: RefCell::new(Some(self.extensions)),
+            auto_head: self.auto_head,
+            config_error: RefCell::new(self.config_error),
         }
     }
 }
@@ -469,6 +538,7 @@ mod tests {
     use futures_util::future::{err, ok};
 
     use super::*;
+    use crate::error::{self, ErrorInternalServerError};
     use crate::http::{header, HeaderValue, Method, StatusCode};
     use crate::middleware::DefaultHeaders;
     use crate::service::ServiceRequest;
@@ -553,6 +623,33 @@ mod tests {
         assert!(srv.is_err());
     }
 
+    #[actix_rt::test]
+    async fn test_try_configure() {
+        let srv = init_service(App::new().try_configure(|cfg| {
+            cfg.route("/", web::get().to(HttpResponse::Ok));
+            Ok::<_, Error>(())
+        }))
+        .await;
+        let req = TestRequest::default().to_request();
+        let resp = srv.call(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        let srv = try_init_service(
+            App::new()
+                .try_configure(|cfg| {
+                    cfg.route("/", web::get().to(HttpResponse::Ok));
+                    Err(ErrorInternalServerError("missing setting"))
+                })
+                .try_configure(|_| Err(ErrorInternalServerError("second error")))
+                .route("/other", web::get().to(HttpResponse::Ok)),
+        )
+        .await;
+        match srv {
+            Err(err) => assert_eq!(err.to_string(), "missing setting"),
+            Ok(_) => panic!("app initialization should fail"),
+        }
+    }
+
     #[actix_rt::test]
     async fn test_extension() {
         let srv = init_service(App::new().app_data(10usize).service(web::resource("/").to(
@@ -567,6 +664,33 @@ mod tests {
         assert_eq!(resp.status(), StatusCode::OK);
     }
 
+    #[cfg(feature = "cookies")]
+    #[actix_rt::test]
+    async fn test_cookie_defaults_app_data() {
+        use crate::cookie::{Cookie, SameSite};
+        use crate::http::CookieDefaults;
+
+        let srv = init_service(
+            App::new()
+                .app_data(CookieDefaults::new().same_site(SameSite::Lax))
+                .route(
+                    "/",
+                    web::get().to(|req: HttpRequest| {
+                        HttpResponse::Ok()
+                            .cookie_with_defaults(Cookie::new("id", "1"), req.app_data())
+                            .finish()
+                    }),
+                ),
+        )
+        .await;
+        let req = TestRequest::default().to_request();
+        let resp = srv.call(req).await.unwrap();
+        assert_eq!(
+            resp.headers().get(header::SET_COOKIE).unwrap(),
+            "id=1; SameSite=Lax"
+        );
+    }
+
     #[actix_rt::test]
     async fn test_wrap() {
         let srv = init_service(
@@ -632,6 +756,41 @@ mod tests {
         );
     }
 
+    #[actix_rt::test]
+    async fn test_wrap_fn_error_source() {
+        let srv = init_service(
+            App::new()
+                .wrap_fn(|req, srv| {
+                    let fut = srv.call(req);
+                    async move {
+                        let mut res = fut.await?;
+                        let source = res.response().error().map(ToString::to_string);
+                        if let Some(source) = source {
+                            res.headers_mut().insert(
+                                header::HeaderName::from_static("x-error-source"),
+                                HeaderValue::from_str(&source).unwrap(),
+                            );
+                        }
+                        Ok(res)
+                    }
+                })
+                .route(
+                    "/test",
+                    web::get().to(|| async {
+                        Err::<HttpResponse, _>(error::ErrorBadRequest("nope"))
+                    }),
+                ),
+        )
+        .await;
+        let req = TestRequest::with_uri("/test").to_request();
+        let resp = call_service(&srv, req).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(
+            resp.headers().get("x-error-source").unwrap(),
+            HeaderValue::from_static("nope")
+        );
+    }
+
     #[actix_rt::test]
     async fn test_router_wrap_fn() {
         let srv = init_service(
@@ -1,8 +1,10 @@
 use std::cell::RefCell;
+use std::future::Future;
+use std::pin::Pin;
 use std::rc::Rc;
-use std::task::Poll;
+use std::task::{Context, Poll};
 
-use actix_http::{Extensions, Request, Response};
+use actix_http::{http::Method, Extensions, Request, RequestHead, Response};
 use actix_router::{Path, ResourceDef, Router, Url};
 use actix_service::boxed::{self, BoxService, BoxServiceFactory};
 use actix_service::{fn_service, Service, ServiceFactory};
@@ -11,8 +13,11 @@ use futures_util::future::join_all;
 
 use crate::config::{AppConfig, AppService};
 use crate::data::FnDataFactory;
-use crate::error::Error;
+use crate::error::{Error, ErrorInternalServerError};
 use crate::guard::Guard;
+use crate::handle::ServerHandle;
+use crate::health::HealthCheck;
+use crate::lifecycle::{InFlight, InFlightGuard};
 use crate::request::{HttpRequest, HttpRequestPool};
 use crate::rmap::ResourceMap;
 use crate::service::{AppServiceFactory, ServiceRequest, ServiceResponse};
@@ -40,6 +45,8 @@ where
     pub(crate) default: Option<Rc<HttpNewService>>,
     pub(crate) factory_ref: Rc<RefCell<Option<AppRoutingFactory>>>,
     pub(crate) external: RefCell<Vec<ResourceDef>>,
+    pub(crate) auto_head: bool,
+    pub(crate) config_error: RefCell<Option<Error>>,
 }
 
 impl<T, B> ServiceFactory<Request> for AppInit<T, B>
@@ -57,7 +64,7 @@ where
     type Error = T::Error;
     type Config = AppConfig;
     type Service = AppInitService<T::Service, B>;
-    type InitError = T::InitError;
+    type InitError = Error;
     type Future = LocalBoxFuture<'static, Result<Self::Service, Self::InitError>>;
 
     fn new_service(&self, config: AppConfig) -> Self::Future {
@@ -69,14 +76,23 @@ where
             })))
         });
 
+        let health_check = config.health_check.clone();
+        let worker_hooks = config.worker_hooks.clone();
+        let server_handle = config.server_handle.clone();
+
         // App config
-        let mut config = AppService::new(config, default.clone());
+        let mut config = AppService::new(config, default.clone(), self.auto_head);
 
         // register services
         std::mem::take(&mut *self.services.borrow_mut())
             .into_iter()
             .for_each(|mut srv| srv.register(&mut config));
 
+        let config_error = self.config_error.borrow_mut().take();
+        if let Some(err) = config_error.or_else(|| config.take_init_error()) {
+            return Box::pin(async { Err(err) });
+        }
+
         let mut rmap = ResourceMap::new(ResourceDef::new(""));
 
         let (config, services) = config.into_services();
@@ -84,6 +100,7 @@ where
         // complete pipeline creation.
         *self.factory_ref.borrow_mut() = Some(AppRoutingFactory {
             default,
+            auto_head: self.auto_head,
             services: services
                 .into_iter()
                 .map(|(mut rdef, srv, guards, nested)| {
@@ -123,20 +140,54 @@ where
                 .await
                 .into_iter()
                 .collect::<Result<Vec<_>, _>>()
-                .map_err(|_| ())?;
+                .map_err(|_| ErrorInternalServerError("Can not construct data instance"))?;
 
             // app service and middleware
-            let service = endpoint_fut.await?;
+            let service = endpoint_fut
+                .await
+                .map_err(|_| ErrorInternalServerError("Can not create application service"))?;
 
             // populate app data container from (async) data factories.
             async_data_factories.iter().for_each(|factory| {
                 factory.create(&mut app_data);
             });
 
+            if let Some(ref health_check) = health_check {
+                if !app_data.contains::<HealthCheck>() {
+                    app_data.insert(health_check.clone());
+                }
+            }
+
+            if let Some(server_handle) = server_handle {
+                if !app_data.contains::<ServerHandle>() {
+                    app_data.insert(server_handle);
+                }
+            }
+
+            let app_data = Rc::new(app_data);
+
+            // run worker start hooks and wait for shutdown to run stop hooks
+            let mut in_flight = None;
+
+            if let Some(hooks) = worker_hooks {
+                hooks.start(&app_data).await;
+
+                if hooks.has_stop_hooks() {
+                    let tracker = Rc::new(InFlight::default());
+                    hooks.spawn_stop(Rc::clone(&app_data), Rc::clone(&tracker));
+                    in_flight = Some(tracker);
+                }
+            }
+
+            if let Some(health_check) = health_check {
+                health_check.worker_started();
+            }
+
             Ok(AppInitService {
                 service,
-                app_data: Rc::new(app_data),
+                app_data,
                 app_state: AppInitServiceState::new(rmap, config),
+                in_flight,
             })
         })
     }
@@ -150,6 +201,7 @@ where
     service: T,
     app_data: Rc<Extensions>,
     app_state: Rc<AppInitServiceState>,
+    in_flight: Option<Rc<InFlight>>,
 }
 
 /// A collection of [`AppInitService`] state that shared across `HttpRequest`s.
@@ -192,18 +244,29 @@ where
 {
     type Response = ServiceResponse<B>;
     type Error = T::Error;
-    type Future = T::Future;
+    type Future = AppInitServiceFuture<T::Future>;
 
     actix_service::forward_ready!(service);
 
-    fn call(&self, req: Request) -> Self::Future {
+    fn call(&self, mut req: Request) -> Self::Future {
+        let conn_data = req.take_conn_data();
         let (head, payload) = req.into_parts();
 
+        // guards only see the request head
+        #[cfg(any(feature = "openssl", feature = "rustls"))]
+        if let Some(certs) = conn_data
+            .as_ref()
+            .and_then(|data| data.get::<crate::tls::PeerCertificates>())
+        {
+            head.extensions_mut().insert(certs.clone());
+        }
+
         let req = if let Some(mut req) = self.app_state.pool().pop() {
             let inner = Rc::get_mut(&mut req.inner).unwrap();
             inner.path.get_mut().update(&head.uri);
             inner.path.reset();
             inner.head = head;
+            inner.conn_data = conn_data;
             req
         } else {
             HttpRequest::new(
@@ -211,9 +274,30 @@ where
                 head,
                 self.app_state.clone(),
                 self.app_data.clone(),
+                conn_data,
             )
         };
-        self.service.call(ServiceRequest::new(req, payload))
+
+        AppInitServiceFuture {
+            fut: self.service.call(ServiceRequest::new(req, payload)),
+            _in_flight: self.in_flight.as_ref().map(InFlight::enter),
+        }
+    }
+}
+
+/// Response future of [`AppInitService`], tracking the request as in-flight until it resolves.
+#[pin_project::pin_project]
+pub struct AppInitServiceFuture<F> {
+    #[pin]
+    fut: F,
+    _in_flight: Option<InFlightGuard>,
+}
+
+impl<F: Future> Future for AppInitServiceFuture<F> {
+    type Output = F::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        self.project().fut.poll(cx)
     }
 }
 
@@ -229,6 +313,7 @@ where
 pub struct AppRoutingFactory {
     services: Rc<[(ResourceDef, HttpNewService, RefCell<Option<Guards>>)]>,
     default: Rc<HttpNewService>,
+    auto_head: bool,
 }
 
 impl ServiceFactory<ServiceRequest> for AppRoutingFactory {
@@ -254,6 +339,8 @@ impl ServiceFactory<ServiceRequest> for AppRoutingFactory {
         // construct default service factory future
         let default_fut = self.default.new_service(());
 
+        let auto_head = self.auto_head;
+
         Box::pin(async move {
             let default = default_fut.await?;
 
@@ -269,7 +356,11 @@ impl ServiceFactory<ServiceRequest> for AppRoutingFactory {
                 })
                 .finish();
 
-            Ok(AppRouting { router, default })
+            Ok(AppRouting {
+                router,
+                default,
+                auto_head,
+            })
         })
     }
 }
@@ -277,6 +368,7 @@ impl ServiceFactory<ServiceRequest> for AppRoutingFactory {
 pub struct AppRouting {
     router: Router<HttpService, Guards>,
     default: HttpService,
+    auto_head: bool,
 }
 
 impl Service<ServiceRequest> for AppRouting {
@@ -287,16 +379,14 @@ impl Service<ServiceRequest> for AppRouting {
     actix_service::always_ready!();
 
     fn call(&self, mut req: ServiceRequest) -> Self::Future {
-        let res = self.router.recognize_checked(&mut req, |req, guards| {
-            if let Some(ref guards) = guards {
-                for f in guards {
-                    if !f.check(req.head()) {
-                        return false;
-                    }
-                }
-            }
-            true
-        });
+        let mut res = self.router.recognize_checked(&mut req, check_guards);
+
+        if res.is_none() && self.auto_head && req.method() == Method::HEAD {
+            let head = req.head_as_get();
+            res = self
+                .router
+                .recognize_checked(&mut req, |_, guards| check_head_guards(&head, guards));
+        }
 
         if let Some((srv, _info)) = res {
             srv.call(req)
@@ -306,6 +396,21 @@ impl Service<ServiceRequest> for AppRouting {
     }
 }
 
+pub(crate) fn check_guards(req: &ServiceRequest, guards: &Option<Guards>) -> bool {
+    check_head_guards(req.head(), guards)
+}
+
+pub(crate) fn check_head_guards(head: &RequestHead, guards: &Option<Guards>) -> bool {
+    if let Some(ref guards) = guards {
+        for f in guards {
+            if !f.check(head) {
+                return false;
+            }
+        }
+    }
+    true
+}
+
 /// Wrapper service for routing
 pub struct AppEntry {
     factory: Rc<RefCell<Option<AppRoutingFactory>>>,
@@ -0,0 +1,262 @@
+//! Per-worker limits for the blocking thread pool used by [`web::block`](crate::web::block).
+
+use std::{
+    collections::VecDeque,
+    future::Future,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll, Waker},
+};
+
+use crate::error::BlockingError;
+
+thread_local! {
+    static POOL: Arc<Mutex<PoolState>> = Arc::new(Mutex::new(PoolState::default()));
+}
+
+/// Snapshot of the blocking pool usage of the current worker.
+///
+/// Returned by [`web::blocking_pool_status`](crate::web::blocking_pool_status).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BlockingPoolStatus {
+    /// Number of blocking tasks currently running.
+    pub active: usize,
+
+    /// Number of blocking tasks waiting for a free thread.
+    pub queued: usize,
+
+    /// Maximum number of concurrently running tasks, if limited.
+    pub size: Option<usize>,
+
+    /// Maximum number of waiting tasks, if limited.
+    pub max_queue: Option<usize>,
+}
+
+#[derive(Default)]
+struct PoolState {
+    limits: Option<(usize, usize)>,
+    active: usize,
+    queue: VecDeque<Arc<Mutex<Waiter>>>,
+}
+
+#[derive(Default)]
+struct Waiter {
+    granted: bool,
+    waker: Option<Waker>,
+}
+
+/// Sets the blocking pool limits of the current worker thread.
+pub(crate) fn configure(size: usize, max_queue: usize) {
+    POOL.with(|pool| pool.lock().unwrap().limits = Some((size.max(1), max_queue)));
+}
+
+pub(crate) fn status() -> BlockingPoolStatus {
+    POOL.with(|pool| {
+        let state = pool.lock().unwrap();
+
+        BlockingPoolStatus {
+            active: state.active,
+            queued: state.queue.len(),
+            size: state.limits.map(|(size, _)| size),
+            max_queue: state.limits.map(|(_, max_queue)| max_queue),
+        }
+    })
+}
+
+/// Runs `f` on the blocking thread pool once the current worker's limits allow it.
+pub(crate) async fn run<F, R>(f: F) -> Result<R, BlockingError>
+where
+    F: FnOnce() -> R + Send + 'static,
+    R: Send + 'static,
+{
+    let pool = POOL.with(Arc::clone);
+    let slot = Acquire { pool, waiter: None }.await?;
+
+    // the slot is released on the blocking thread, even if the caller stops waiting
+    crate::rt::spawn_blocking(move || {
+        let res = f();
+        drop(slot);
+        res
+    })
+    .await
+    .map_err(|_| BlockingError::Canceled)
+}
+
+/// A running slot in the pool. Dropping it passes the slot on to the next waiting task.
+struct Slot {
+    pool: Arc<Mutex<PoolState>>,
+}
+
+impl Drop for Slot {
+    fn drop(&mut self) {
+        let mut state = self.pool.lock().unwrap();
+
+        match state.queue.pop_front() {
+            Some(waiter) => {
+                let mut waiter = waiter.lock().unwrap();
+                waiter.granted = true;
+
+                if let Some(waker) = waiter.waker.take() {
+                    waker.wake();
+                }
+            }
+            None => state.active = state.active.saturating_sub(1),
+        }
+    }
+}
+
+struct Acquire {
+    pool: Arc<Mutex<PoolState>>,
+    waiter: Option<Arc<Mutex<Waiter>>>,
+}
+
+impl Future for Acquire {
+    type Output = Result<Slot, BlockingError>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = &mut *self;
+        let mut state = this.pool.lock().unwrap();
+
+        if let Some(ref waiter) = this.waiter {
+            let mut waiter = waiter.lock().unwrap();
+
+            if !waiter.granted {
+                waiter.waker = Some(cx.waker().clone());
+                return Poll::Pending;
+            }
+
+            drop(waiter);
+            this.waiter = None;
+            return Poll::Ready(Ok(Slot {
+                pool: Arc::clone(&this.pool),
+            }));
+        }
+
+        match state.limits {
+            Some((size, max_queue)) if state.active >= size => {
+                if state.queue.len() >= max_queue {
+                    return Poll::Ready(Err(BlockingError::QueueFull));
+                }
+
+                let waiter = Arc::new(Mutex::new(Waiter {
+                    granted: false,
+                    waker: Some(cx.waker().clone()),
+                }));
+                state.queue.push_back(Arc::clone(&waiter));
+                this.waiter = Some(waiter);
+
+                Poll::Pending
+            }
+
+            _ => {
+                state.active += 1;
+
+                Poll::Ready(Ok(Slot {
+                    pool: Arc::clone(&this.pool),
+                }))
+            }
+        }
+    }
+}
+
+impl Drop for Acquire {
+    fn drop(&mut self) {
+        if let Some(waiter) = self.waiter.take() {
+            // slots are granted while holding the pool lock
+            let mut state = self.pool.lock().unwrap();
+
+            if waiter.lock().unwrap().granted {
+                drop(state);
+
+                // slot was granted but never used; pass it on
+                drop(Slot {
+                    pool: Arc::clone(&self.pool),
+                });
+            } else {
+                state.queue.retain(|queued| !Arc::ptr_eq(queued, &waiter));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::mpsc;
+
+    use super::*;
+    use crate::{http::StatusCode, web, HttpResponse};
+
+    async fn wait_for(active: usize, queued: usize) {
+        for _ in 0..1000 {
+            let status = status();
+            if status.active == active && status.queued == queued {
+                return;
+            }
+            actix_rt::task::yield_now().await;
+        }
+
+        panic!("blocking pool did not reach expected state: {:?}", status());
+    }
+
+    #[actix_rt::test]
+    async fn test_unlimited() {
+        let res = web::block(|| 42).await.unwrap();
+        assert_eq!(res, 42);
+
+        let status = status();
+        assert_eq!(status.active, 0);
+        assert_eq!(status.size, None);
+    }
+
+    #[actix_rt::test]
+    async fn test_queue_full() {
+        configure(1, 1);
+
+        let (tx, rx) = mpsc::channel::<()>();
+        let first = actix_rt::spawn(web::block(move || rx.recv().unwrap()));
+        wait_for(1, 0).await;
+
+        let second = actix_rt::spawn(web::block(|| 2));
+        wait_for(1, 1).await;
+
+        let err = web::block(|| 3).await.unwrap_err();
+        assert!(matches!(err, BlockingError::QueueFull));
+        let res = HttpResponse::from_error(err.into());
+        assert_eq!(res.status(), StatusCode::SERVICE_UNAVAILABLE);
+
+        tx.send(()).unwrap();
+        first.await.unwrap().unwrap();
+        assert_eq!(second.await.unwrap().unwrap(), 2);
+
+        assert_eq!(
+            status(),
+            BlockingPoolStatus {
+                active: 0,
+                queued: 0,
+                size: Some(1),
+                max_queue: Some(1),
+            }
+        );
+    }
+
+    #[actix_rt::test]
+    async fn test_dropped_waiter() {
+        configure(1, 1);
+
+        let (tx, rx) = mpsc::channel::<()>();
+        let first = actix_rt::spawn(web::block(move || rx.recv().unwrap()));
+        wait_for(1, 0).await;
+
+        // a waiter that stops waiting leaves the queue
+        let second = actix_rt::spawn(web::block(|| 2));
+        wait_for(1, 1).await;
+        second.abort();
+        wait_for(1, 0).await;
+
+        tx.send(()).unwrap();
+        first.await.unwrap().unwrap();
+        wait_for(0, 0).await;
+
+        assert_eq!(web::block(|| 3).await.unwrap(), 3);
+    }
+}
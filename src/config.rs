@@ -8,6 +8,9 @@ use actix_service::{boxed, IntoServiceFactory, ServiceFactory};
 use crate::data::Data;
 use crate::error::Error;
 use crate::guard::Guard;
+use crate::handle::ServerHandle;
+use crate::health::HealthCheck;
+use crate::lifecycle::WorkerHooks;
 use crate::resource::Resource;
 use crate::rmap::ResourceMap;
 use crate::route::Route;
@@ -23,6 +26,7 @@ type HttpNewService = boxed::BoxServiceFactory<(), ServiceRequest, ServiceRespon
 pub struct AppService {
     config: AppConfig,
     root: bool,
+    auto_head: bool,
     default: Rc<HttpNewService>,
     services: Vec<(
         ResourceDef,
@@ -30,16 +34,19 @@ pub struct AppService {
         Option<Guards>,
         Option<Rc<ResourceMap>>,
     )>,
+    init_error: Option<Error>,
 }
 
 impl AppService {
     /// Crate server settings instance.
-    pub(crate) fn new(config: AppConfig, default: Rc<HttpNewService>) -> Self {
+    pub(crate) fn new(config: AppConfig, default: Rc<HttpNewService>, auto_head: bool) -> Self {
         AppService {
             config,
             default,
             root: true,
+            auto_head,
             services: Vec::new(),
+            init_error: None,
         }
     }
 
@@ -68,9 +75,30 @@ impl AppService {
             default: self.default.clone(),
             services: Vec::new(),
             root: false,
+            auto_head: self.auto_head,
+            init_error: None,
         }
     }
 
+    /// Fails app initialization with `err`, unless an earlier error was set.
+    pub(crate) fn init_error(&mut self, err: Error) {
+        if self.init_error.is_none() {
+            self.init_error = Some(err);
+        }
+    }
+
+    /// Takes the error set with [`init_error`](Self::init_error).
+    pub(crate) fn take_init_error(&mut self) -> Option<Error> {
+        self.init_error.take()
+    }
+
+    /// Check if `HEAD` requests are routed to `GET` routes.
+    ///
+    /// See [`App::auto_head`](crate::App::auto_head).
+    pub fn auto_head(&self) -> bool {
+        self.auto_head
+    }
+
     /// Service configuration
     pub fn config(&self) -> &AppConfig {
         &self.config
@@ -109,11 +137,39 @@ pub struct AppConfig {
     secure: bool,
     host: String,
     addr: SocketAddr,
+    pub(crate) health_check: Option<HealthCheck>,
+    pub(crate) worker_hooks: Option<WorkerHooks>,
+    pub(crate) server_handle: Option<ServerHandle>,
 }
 
 impl AppConfig {
     pub(crate) fn new(secure: bool, addr: SocketAddr, host: String) -> Self {
-        AppConfig { secure, addr, host }
+        AppConfig {
+            secure,
+            addr,
+            host,
+            health_check: None,
+            worker_hooks: None,
+            server_handle: None,
+        }
+    }
+
+    /// Sets the server's health check handle, maintained by the app once it is initialized.
+    pub(crate) fn health_check(mut self, health_check: Option<HealthCheck>) -> Self {
+        self.health_check = health_check;
+        self
+    }
+
+    /// Sets the server's worker lifecycle hooks, run by the app.
+    pub(crate) fn worker_hooks(mut self, worker_hooks: Option<WorkerHooks>) -> Self {
+        self.worker_hooks = worker_hooks;
+        self
+    }
+
+    /// Sets the handle of the server, added to the app data.
+    pub(crate) fn server_handle(mut self, server_handle: Option<ServerHandle>) -> Self {
+        self.server_handle = server_handle;
+        self
     }
 
     /// Server host name.
@@ -36,6 +36,10 @@ pub(crate) type FnDataFactory =
 /// If route data is not set for a handler, using `Data<T>` extractor would cause *Internal
 /// Server Error* response.
 ///
+/// Since `T` may be unsized, a trait object can be registered by converting an `Arc<dyn Trait>`
+/// with `Data::from` and later extracted as `Data<dyn Trait>`. This allows handlers to stay
+/// agnostic of the concrete type stored in the app.
+///
 /// ```rust
 /// use std::sync::Mutex;
 /// use actix_web::{web, App, HttpResponse, Responder};
@@ -62,6 +66,37 @@ pub(crate) type FnDataFactory =
 ///                 web::get().to(index)));
 /// }
 /// ```
+///
+/// Registering and extracting a trait object:
+///
+/// ```rust
+/// use std::sync::Arc;
+/// use actix_web::{web, App, HttpResponse, Responder};
+///
+/// trait Repo {
+///     fn name(&self) -> String;
+/// }
+///
+/// struct InMemoryRepo;
+///
+/// impl Repo for InMemoryRepo {
+///     fn name(&self) -> String {
+///         "in-memory".to_owned()
+///     }
+/// }
+///
+/// async fn index(repo: web::Data<dyn Repo>) -> impl Responder {
+///     HttpResponse::Ok().body(repo.name())
+/// }
+///
+/// fn main() {
+///     let repo: Arc<dyn Repo> = Arc::new(InMemoryRepo);
+///
+///     let app = App::new()
+///         .app_data(web::Data::from(repo))
+///         .route("/", web::get().to(index));
+/// }
+/// ```
 #[derive(Debug)]
 pub struct Data<T: ?Sized>(Arc<T>);
 
@@ -118,9 +153,11 @@ impl<T: ?Sized + 'static> FromRequest for Data<T> {
                 req.path(),
                 type_name::<T>(),
             );
-            err(ErrorInternalServerError(
-                "App data is not configured, to configure use App::data()",
-            ))
+            err(ErrorInternalServerError(format!(
+                "App data is not configured for type {}, to configure use App::data() \
+                 or App::app_data()",
+                type_name::<T>(),
+            )))
         }
     }
 }
@@ -325,4 +362,48 @@ mod tests {
         let data_arc = Data::from(dyn_arc);
         assert_eq!(data_arc_box.get_num(), data_arc.get_num())
     }
+
+    #[actix_rt::test]
+    async fn test_dyn_data_extractor() {
+        trait Repo {
+            fn get_num(&self) -> i32;
+        }
+
+        struct A;
+
+        impl Repo for A {
+            fn get_num(&self) -> i32 {
+                42
+            }
+        }
+
+        let repo: Arc<dyn Repo> = Arc::new(A);
+
+        let srv = init_service(App::new().app_data(Data::from(repo)).service(
+            web::resource("/").to(|repo: web::Data<dyn Repo>| {
+                assert_eq!(repo.get_num(), 42);
+                HttpResponse::Ok()
+            }),
+        ))
+        .await;
+
+        let req = TestRequest::default().to_request();
+        let resp = srv.call(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        // concrete type is not extractable as the trait object
+        let srv = init_service(
+            App::new()
+                .app_data(Data::new(A))
+                .service(web::resource("/").to(|_: web::Data<dyn Repo>| HttpResponse::Ok())),
+        )
+        .await;
+
+        let req = TestRequest::default().to_request();
+        let resp = srv.call(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::INTERNAL_SERVER_ERROR);
+
+        let body = test::read_body(resp).await;
+        assert!(std::str::from_utf8(&body).unwrap().contains("dyn"));
+    }
 }
@@ -1,8 +1,11 @@
 //! Error and Result module
 
+use std::fmt;
+
 pub use actix_http::error::*;
 use derive_more::{Display, Error, From};
-use serde_json::error::Error as JsonError;
+use serde::Serialize;
+use serde_json::{error::Category as JsonCategory, Value};
 use url::ParseError as UrlParseError;
 
 use crate::{http::StatusCode, HttpResponse};
@@ -72,43 +75,268 @@ impl ResponseError for UrlencodedError {
 }
 
 /// A set of errors that can occur during parsing json payloads
-#[derive(Debug, Display, From)]
+///
+/// The default error response is a JSON body as rendered by [`JsonError`], with the structured
+/// context of the error in the `detail` field. Neither the response nor the `Display` output
+/// include any part of the request body.
+#[derive(Debug)]
 pub enum JsonPayloadError {
     /// Payload size is bigger than allowed. (default: 32kB)
-    #[display(fmt = "Json payload size is bigger than allowed")]
-    Overflow,
+    Overflow {
+        /// Maximum accepted payload size in bytes.
+        limit: usize,
+
+        /// Payload size declared in the `Content-Length` header, if any.
+        length: Option<usize>,
+    },
+
     /// Content type error
-    #[display(fmt = "Content type error")]
     ContentType,
+
     /// Deserialize error
-    #[display(fmt = "Json deserialize error: {}", _0)]
-    Deserialize(JsonError),
+    Deserialize {
+        /// The underlying error, which may include parts of the payload in its message.
+        source: serde_json::Error,
+
+        /// Path of the value that failed to deserialize, e.g. `items[0].quantity`.
+        ///
+        /// Only available with the `json-error-path` feature.
+        path: Option<String>,
+    },
+
     /// Payload error
-    #[display(fmt = "Error that occur during reading payload: {}", _0)]
     Payload(PayloadError),
 }
 
-impl std::error::Error for JsonPayloadError {}
+impl JsonPayloadError {
+    /// Structured context of the error, sent as the `detail` field of the error response.
+    fn detail(&self) -> Option<Value> {
+        match self {
+            JsonPayloadError::Overflow { limit, length } => Some(serde_json::json!({
+                "limit": limit,
+                "length": length,
+            })),
+
+            JsonPayloadError::Deserialize { source, path } => Some(serde_json::json!({
+                "line": source.line(),
+                "column": source.column(),
+                "path": path,
+            })),
+
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for JsonPayloadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            JsonPayloadError::Overflow {
+                limit,
+                length: Some(length),
+            } => write!(
+                f,
+                "Json payload ({} bytes) is larger than allowed (limit: {} bytes)",
+                length, limit
+            ),
+
+            JsonPayloadError::Overflow {
+                limit,
+                length: None,
+            } => write!(
+                f,
+                "Json payload is larger than allowed (limit: {} bytes)",
+                limit
+            ),
+
+            JsonPayloadError::ContentType => write!(f, "Content type error"),
+
+            // serde's message is left out since it may quote the payload
+            JsonPayloadError::Deserialize { source, path } => {
+                let kind = match source.classify() {
+                    JsonCategory::Io => "I/O error",
+                    JsonCategory::Syntax => "syntax error",
+                    JsonCategory::Data => "invalid data",
+                    JsonCategory::Eof => "unexpected end of input",
+                };
+
+                write!(
+                    f,
+                    "Json deserialize error: {} at line {} column {}",
+                    kind,
+                    source.line(),
+                    source.column()
+                )?;
+
+                match path {
+                    Some(path) => write!(f, " (path: {})", path),
+                    None => Ok(()),
+                }
+            }
+
+            JsonPayloadError::Payload(err) => {
+                write!(f, "Error that occur during reading payload: {}", err)
+            }
+        }
+    }
+}
+
+impl std::error::Error for JsonPayloadError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            JsonPayloadError::Deserialize { source, .. } => Some(source),
+            JsonPayloadError::Payload(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<serde_json::Error> for JsonPayloadError {
+    fn from(source: serde_json::Error) -> Self {
+        JsonPayloadError::Deserialize { source, path: None }
+    }
+}
+
+impl From<PayloadError> for JsonPayloadError {
+    fn from(err: PayloadError) -> Self {
+        JsonPayloadError::Payload(err)
+    }
+}
 
 /// Return `BadRequest` for `JsonPayloadError`
 impl ResponseError for JsonPayloadError {
-    fn error_response(&self) -> HttpResponse {
+    fn status_code(&self) -> StatusCode {
         match *self {
-            JsonPayloadError::Overflow => HttpResponse::new(StatusCode::PAYLOAD_TOO_LARGE),
-            _ => HttpResponse::new(StatusCode::BAD_REQUEST),
+            JsonPayloadError::Overflow { .. } => StatusCode::PAYLOAD_TOO_LARGE,
+            _ => StatusCode::BAD_REQUEST,
         }
     }
+
+    fn error_response(&self) -> HttpResponse {
+        let mut err = JsonError::new(self.status_code()).message(self.to_string());
+
+        if let Some(detail) = self.detail() {
+            err = err.detail(detail);
+        }
+
+        err.error_response()
+    }
+}
+
+/// Defines the payload error type of a binary message format extractor.
+#[cfg(any(feature = "cbor", feature = "msgpack", feature = "protobuf"))]
+macro_rules! encoded_payload_error {
+    ($(#[$meta:meta])* $name:ident, $format:literal, $decode:ty, $decode_msg:literal) => {
+        $(#[$meta])*
+        #[derive(Debug, From)]
+        pub enum $name {
+            /// Payload size is bigger than allowed. (default: 256kB)
+            #[from(ignore)]
+            Overflow { limit: usize },
+
+            /// Content type error
+            ContentType,
+
+            /// Deserialize error
+            Deserialize($decode),
+
+            /// Payload error
+            Payload(PayloadError),
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                match self {
+                    $name::Overflow { limit } => write!(
+                        f,
+                        concat!($format, " payload is larger than allowed (limit: {} bytes)"),
+                        limit
+                    ),
+                    $name::ContentType => f.write_str("Content type error"),
+                    $name::Deserialize(err) => write!(f, concat!($decode_msg, ": {}"), err),
+                    $name::Payload(err) => {
+                        write!(f, "Error that occur during reading payload: {}", err)
+                    }
+                }
+            }
+        }
+
+        impl std::error::Error for $name {}
+
+        /// Return `PayloadTooLarge` for `Overflow` and `BadRequest` for other errors
+        impl ResponseError for $name {
+            fn status_code(&self) -> StatusCode {
+                match *self {
+                    $name::Overflow { .. } => StatusCode::PAYLOAD_TOO_LARGE,
+                    _ => StatusCode::BAD_REQUEST,
+                }
+            }
+        }
+    };
 }
 
+#[cfg(feature = "cbor")]
+encoded_payload_error!(
+    /// A set of errors that can occur during parsing CBOR payloads
+    CborPayloadError,
+    "CBOR",
+    serde_cbor::Error,
+    "CBOR deserialize error"
+);
+
+#[cfg(feature = "msgpack")]
+encoded_payload_error!(
+    /// A set of errors that can occur during parsing MessagePack payloads
+    MsgPackPayloadError,
+    "MessagePack",
+    rmp_serde::decode::Error,
+    "MessagePack deserialize error"
+);
+
+#[cfg(feature = "protobuf")]
+encoded_payload_error!(
+    /// A set of errors that can occur during parsing Protocol Buffers payloads
+    ProtoBufPayloadError,
+    "ProtoBuf",
+    prost::DecodeError,
+    "ProtoBuf decode error"
+);
+
 /// A set of errors that can occur during parsing request paths
 #[derive(Debug, Display, From)]
 pub enum PathError {
     /// Deserialize error
     #[display(fmt = "Path deserialize error: {}", _0)]
     Deserialize(serde::de::value::Error),
+
+    /// A path segment could not be deserialized into the expected type.
+    #[display(
+        fmt = "Path segment `{}` is not a valid `{}`: {}",
+        segment,
+        expected,
+        source
+    )]
+    #[from(ignore)]
+    Segment {
+        /// Name of the segment, as declared in the resource pattern.
+        segment: String,
+
+        /// Name of the type the segment was deserialized into.
+        expected: String,
+
+        /// Deserialization error.
+        source: serde::de::value::Error,
+    },
 }
 
-impl std::error::Error for PathError {}
+impl std::error::Error for PathError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            PathError::Deserialize(err) => Some(err),
+            PathError::Segment { source, .. } => Some(source),
+        }
+    }
+}
 
 /// Return `BadRequest` for `PathError`
 impl ResponseError for PathError {
@@ -123,6 +351,11 @@ pub enum QueryPayloadError {
     /// Query deserialize error.
     #[display(fmt = "Query deserialize error: {}", _0)]
     Deserialize(serde::de::value::Error),
+
+    /// Malformed percent-encoding or invalid UTF-8, rejected in
+    /// [strict mode](crate::web::QueryConfig::strict).
+    #[display(fmt = "Query string is not well-formed")]
+    Malformed,
 }
 
 /// Return `BadRequest` for `QueryPayloadError`
@@ -162,9 +395,206 @@ impl ResponseError for ReadlinesError {
     }
 }
 
+/// Errors that can occur when answering a CORS preflight request, see
+/// [`Cors`](crate::middleware::Cors).
+#[derive(Debug, Display, Error, PartialEq)]
+pub enum CorsError {
+    /// The request's `Origin` is not allowed.
+    #[display(fmt = "Origin is not allowed to make this request")]
+    OriginNotAllowed,
+
+    /// The method in `Access-Control-Request-Method` is missing, malformed or not allowed.
+    #[display(fmt = "Requested method is not allowed")]
+    MethodNotAllowed,
+
+    /// A header in `Access-Control-Request-Headers` is malformed or not allowed.
+    #[display(fmt = "Requested headers are not allowed")]
+    HeadersNotAllowed,
+
+    /// Access from a public network to a private network was requested but is not allowed.
+    #[display(fmt = "Private network access is not allowed")]
+    PrivateNetworkNotAllowed,
+}
+
+/// Return `BadRequest` for `CorsError`
+impl ResponseError for CorsError {
+    fn status_code(&self) -> StatusCode {
+        StatusCode::BAD_REQUEST
+    }
+}
+
+/// Error that renders as a JSON object with a stable shape.
+///
+/// The response body has the form `{"error": <message>, "detail": <detail>}`. When no message
+/// is set, the canonical reason of the status code is used. When no detail is set, `detail` is
+/// `null`.
+///
+/// ```
+/// use actix_web::{error::JsonError, http::StatusCode, Error};
+/// use serde_json::json;
+///
+/// async fn index() -> Result<&'static str, Error> {
+///     Err(JsonError::new(StatusCode::UNPROCESSABLE_ENTITY)
+///         .message("validation failed")
+///         .detail(json!({ "field": "name" }))
+///         .into())
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct JsonError {
+    status: StatusCode,
+    message: Option<String>,
+    detail: Option<Value>,
+}
+
+impl JsonError {
+    /// Create new JSON error with the given status code.
+    pub fn new(status: StatusCode) -> Self {
+        JsonError {
+            status,
+            message: None,
+            detail: None,
+        }
+    }
+
+    /// Set the human-readable message, sent as the `error` field.
+    pub fn message(mut self, message: impl Into<String>) -> Self {
+        self.message = Some(message.into());
+        self
+    }
+
+    /// Set additional structured information, sent as the `detail` field.
+    pub fn detail(mut self, detail: Value) -> Self {
+        self.detail = Some(detail);
+        self
+    }
+
+    fn message_str(&self) -> &str {
+        match self.message {
+            Some(ref message) => message,
+            None => self.status.canonical_reason().unwrap_or("Unknown Error"),
+        }
+    }
+}
+
+impl fmt::Display for JsonError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.message_str())
+    }
+}
+
+impl std::error::Error for JsonError {}
+
+impl ResponseError for JsonError {
+    fn status_code(&self) -> StatusCode {
+        self.status
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        HttpResponse::build(self.status).json(&serde_json::json!({
+            "error": self.message_str(),
+            "detail": self.detail,
+        }))
+    }
+}
+
+/// Error that renders a serializable value as its JSON response body.
+///
+/// The value is only serialized when the error response is generated.
+///
+/// ```
+/// use actix_web::{error::ErrorJson, http::StatusCode, Error};
+/// use serde::Serialize;
+///
+/// #[derive(Debug, Serialize)]
+/// struct Conflict {
+///     id: u32,
+/// }
+///
+/// async fn index() -> Result<&'static str, Error> {
+///     Err(ErrorJson(StatusCode::CONFLICT, Conflict { id: 42 }).into())
+/// }
+/// ```
+#[derive(Debug)]
+pub struct ErrorJson<T>(pub StatusCode, pub T);
+
+impl<T> fmt::Display for ErrorJson<T>
+where
+    T: Serialize,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match serde_json::to_string(&self.1) {
+            Ok(json) => f.write_str(&json),
+            Err(_) => f.write_str(self.0.canonical_reason().unwrap_or("Unknown Error")),
+        }
+    }
+}
+
+impl<T> ResponseError for ErrorJson<T>
+where
+    T: Serialize + fmt::Debug,
+{
+    fn status_code(&self) -> StatusCode {
+        self.0
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        HttpResponse::build(self.0).json(&self.1)
+    }
+}
+
+/// Failures of several extractors of a handler, reported together.
+///
+/// Returned in [`ExtractorErrorMode::Aggregate`](crate::web::ExtractorErrorMode) mode and rendered
+/// as a `400 Bad Request` with a JSON body listing the failures:
+/// `{"errors": [{"source": "Path", "message": "..."}]}`.
+#[derive(Debug, Clone)]
+pub struct AggregateError {
+    /// Name of the failed extractor, such as `Query`, and its error message.
+    pub failures: Vec<(&'static str, String)>,
+}
+
+impl fmt::Display for AggregateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, (source, message)) in self.failures.iter().enumerate() {
+            if i > 0 {
+                f.write_str("; ")?;
+            }
+            write!(f, "{}: {}", source, message)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for AggregateError {}
+
+impl ResponseError for AggregateError {
+    fn status_code(&self) -> StatusCode {
+        StatusCode::BAD_REQUEST
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        let errors = self
+            .failures
+            .iter()
+            .map(|(source, message)| {
+                serde_json::json!({
+                    "source": source,
+                    "message": message,
+                })
+            })
+            .collect::<Vec<_>>();
+
+        HttpResponse::BadRequest().json(&serde_json::json!({ "errors": errors }))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::dev::ServiceResponse;
+    use crate::http::header::CONTENT_TYPE;
+    use crate::test::{read_body, TestRequest};
 
     #[test]
     fn test_urlencoded_error() {
@@ -179,7 +609,11 @@ mod tests {
 
     #[test]
     fn test_json_payload_error() {
-        let resp: HttpResponse = JsonPayloadError::Overflow.error_response();
+        let resp: HttpResponse = JsonPayloadError::Overflow {
+            limit: 0,
+            length: None,
+        }
+        .error_response();
         assert_eq!(resp.status(), StatusCode::PAYLOAD_TOO_LARGE);
         let resp: HttpResponse = JsonPayloadError::ContentType.error_response();
         assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
@@ -201,4 +635,59 @@ mod tests {
         let resp: HttpResponse = ReadlinesError::EncodingError.error_response();
         assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
     }
+
+    async fn json_body(resp: HttpResponse) -> Value {
+        let req = TestRequest::default().to_http_request();
+        let body = read_body(ServiceResponse::new(req, resp)).await;
+        serde_json::from_slice(&body).unwrap()
+    }
+
+    #[actix_rt::test]
+    async fn test_json_error() {
+        let err = JsonError::new(StatusCode::UNPROCESSABLE_ENTITY)
+            .message("validation failed")
+            .detail(serde_json::json!({ "field": "name" }));
+        assert_eq!(err.to_string(), "validation failed");
+
+        let resp = Error::from(err).as_response_error().error_response();
+        assert_eq!(resp.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(
+            resp.headers().get(CONTENT_TYPE).unwrap(),
+            "application/json"
+        );
+
+        let body = json_body(resp).await;
+        assert_eq!(
+            body,
+            serde_json::json!({ "error": "validation failed", "detail": { "field": "name" } })
+        );
+
+        let resp = JsonError::new(StatusCode::NOT_FOUND).error_response();
+        let body = json_body(resp).await;
+        assert_eq!(
+            body,
+            serde_json::json!({ "error": "Not Found", "detail": null })
+        );
+    }
+
+    #[actix_rt::test]
+    async fn test_error_json() {
+        #[derive(Debug, Serialize)]
+        struct Conflict {
+            id: u32,
+        }
+
+        let err: Error = ErrorJson(StatusCode::CONFLICT, Conflict { id: 42 }).into();
+        assert_eq!(err.to_string(), r#"{"id":42}"#);
+
+        let resp = err.as_response_error().error_response();
+        assert_eq!(resp.status(), StatusCode::CONFLICT);
+        assert_eq!(
+            resp.headers().get(CONTENT_TYPE).unwrap(),
+            "application/json"
+        );
+
+        let body = json_body(resp).await;
+        assert_eq!(body, serde_json::json!({ "id": 42 }));
+    }
 }
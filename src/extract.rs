@@ -11,11 +11,20 @@ use futures_util::{
     ready,
 };
 
-use crate::{dev::Payload, Error, HttpRequest};
+use crate::{dev::Payload, error::AggregateError, http::StatusCode, Error, HttpRequest};
 
 /// Trait implemented by types that can be extracted from request.
 ///
 /// Types that implement this trait can be used with `Route` handlers.
+#[rustversion::attr(
+    since(1.78),
+    diagnostic::on_unimplemented(
+        message = "`{Self}` is not a valid request extractor",
+        label = "handler parameter does not implement `FromRequest`",
+        note = "handler parameters must implement `FromRequest`; application state is extracted \
+                with `web::Data<T>` and the request itself with `HttpRequest`"
+    )
+)]
 pub trait FromRequest: Sized {
     /// Configuration for this extractor.
     type Config: Default + 'static;
@@ -220,6 +229,63 @@ where
     }
 }
 
+/// How handlers with several extractors report extraction failures.
+///
+/// Register as app data to choose the mode of the handlers it applies to.
+///
+/// # Examples
+/// ```rust
+/// use actix_web::{web, App, HttpResponse};
+/// use serde_derive::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct Page {
+///     page: u32,
+/// }
+///
+/// async fn index(id: web::Path<u32>, page: web::Query<Page>) -> HttpResponse {
+///     HttpResponse::Ok().finish()
+/// }
+///
+/// // invalid path and query of "/users/abc?page=x" are both reported in the response
+/// let app = App::new()
+///     .app_data(web::ExtractorErrorMode::Aggregate)
+///     .route("/users/{id}", web::get().to(index));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExtractorErrorMode {
+    /// Respond with the error of the first extractor that fails. This is the default.
+    ShortCircuit,
+
+    /// Run all extractors and respond with an [`AggregateError`] when several fail.
+    ///
+    /// Extractors reading the body are polled to completion so that their failures are reported
+    /// too. When a single extractor fails, its error is returned as in `ShortCircuit` mode. Server
+    /// errors and `413 Payload Too Large` are still returned immediately.
+    Aggregate,
+}
+
+impl Default for ExtractorErrorMode {
+    fn default() -> Self {
+        ExtractorErrorMode::ShortCircuit
+    }
+}
+
+/// Returns true if the error of an extractor ends extraction, even in
+/// [`Aggregate`](ExtractorErrorMode::Aggregate) mode.
+fn is_fatal(err: &Error) -> bool {
+    let status = err.as_response_error().status_code();
+    status.is_server_error() || status == StatusCode::PAYLOAD_TOO_LARGE
+}
+
+/// Name of an extractor type without its path and type parameters, e.g. `Path` for
+/// `actix_web::types::path::Path<(u32,)>`.
+fn extractor_name<T>() -> &'static str {
+    let name = std::any::type_name::<T>();
+    let name = name.split('<').next().unwrap_or(name);
+    name.rsplit("::").next().unwrap_or(name)
+}
+
 #[doc(hidden)]
 impl FromRequest for () {
     type Error = Error;
@@ -231,88 +297,130 @@ impl FromRequest for () {
     }
 }
 
-macro_rules! tuple_from_req ({$fut_type:ident, $(($n:tt, $T:ident)),+} => {
-
-    // This module is a trick to get around the inability of
-    // `macro_rules!` macros to make new idents. We want to make
-    // a new `FutWrapper` struct for each distinct invocation of
-    // this macro. Ideally, we would name it something like
-    // `FutWrapper_$fut_type`, but this can't be done in a macro_rules
-    // macro.
-    //
-    // Instead, we put everything in a module named `$fut_type`, thus allowing
-    // us to use the name `FutWrapper` without worrying about conflicts.
-    // This macro only exists to generate trait impls for tuples - these
-    // are inherently global, so users don't have to care about this
-    // weird trick.
-    #[allow(non_snake_case)]
-    mod $fut_type {
-
-        // Bring everything into scope, so we don't need
-        // redundant imports
-        use super::*;
-
-        /// A helper struct to allow us to pin-project through
-        /// to individual fields
-        #[pin_project::pin_project]
-        struct FutWrapper<$($T: FromRequest),+>($(#[pin] $T::Future),+);
-
-        /// FromRequest implementation for tuple
-        #[doc(hidden)]
-        #[allow(unused_parens)]
-        impl<$($T: FromRequest + 'static),+> FromRequest for ($($T,)+)
-        {
-            type Error = Error;
-            type Future = $fut_type<$($T),+>;
-            type Config = ($($T::Config),+);
-
-            fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
-                $fut_type {
-                    items: <($(Option<$T>,)+)>::default(),
-                    futs: FutWrapper($($T::from_request(req, payload),)+),
+macro_rules! tuple_from_req {
+    ($fut_type:ident, $(($n:tt, $T:ident)),+) => {
+        tuple_from_req!(@impl $fut_type, ($($T::Config),+), $(($n, $T)),+);
+    };
+
+    // tuples of more than 12 elements do not implement `Default`
+    (@unit_config $fut_type:ident, $(($n:tt, $T:ident)),+) => {
+        tuple_from_req!(@impl $fut_type, (), $(($n, $T)),+);
+    };
+
+    (@impl $fut_type:ident, $config:ty, $(($n:tt, $T:ident)),+) => {
+        // This module is a trick to get around the inability of
+        // `macro_rules!` macros to make new idents. We want to make
+        // a new `FutWrapper` struct for each distinct invocation of
+        // this macro. Ideally, we would name it something like
+        // `FutWrapper_$fut_type`, but this can't be done in a macro_rules
+        // macro.
+        //
+        // Instead, we put everything in a module named `$fut_type`, thus allowing
+        // us to use the name `FutWrapper` without worrying about conflicts.
+        // This macro only exists to generate trait impls for tuples - these
+        // are inherently global, so users don't have to care about this
+        // weird trick.
+        #[allow(non_snake_case)]
+        mod $fut_type {
+
+            // Bring everything into scope, so we don't need
+            // redundant imports
+            use super::*;
+
+            /// A helper struct to allow us to pin-project through
+            /// to individual fields
+            #[pin_project::pin_project]
+            struct FutWrapper<$($T: FromRequest),+>($(#[pin] $T::Future),+);
+
+            /// FromRequest implementation for tuple
+            #[doc(hidden)]
+            #[allow(unused_parens)]
+            impl<$($T: FromRequest + 'static),+> FromRequest for ($($T,)+)
+            {
+                type Error = Error;
+                type Future = $fut_type<$($T),+>;
+                type Config = $config;
+
+                fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
+                    let aggregate = matches!(
+                        req.app_data::<ExtractorErrorMode>(),
+                        Some(ExtractorErrorMode::Aggregate)
+                    );
+
+                    $fut_type {
+                        items: ($(Option::<$T>::None,)+),
+                        aggregate,
+                        failures: Vec::new(),
+                        futs: FutWrapper($($T::from_request(req, payload),)+),
+                    }
                 }
             }
-        }
-
-        #[doc(hidden)]
-        #[pin_project::pin_project]
-        pub struct $fut_type<$($T: FromRequest),+> {
-            items: ($(Option<$T>,)+),
-            #[pin]
-            futs: FutWrapper<$($T,)+>,
-        }
-
-        impl<$($T: FromRequest),+> Future for $fut_type<$($T),+>
-        {
-            type Output = Result<($($T,)+), Error>;
 
-            fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
-                let mut this = self.project();
+            #[doc(hidden)]
+            #[pin_project::pin_project]
+            pub struct $fut_type<$($T: FromRequest),+> {
+                items: ($(Option<$T>,)+),
+                aggregate: bool,
+                /// Failed extractors, by index, in aggregate mode.
+                failures: Vec<(usize, &'static str, Error)>,
+                #[pin]
+                futs: FutWrapper<$($T,)+>,
+            }
 
-                let mut ready = true;
-                $(
-                    if this.items.$n.is_none() {
-                        match this.futs.as_mut().project().$n.poll(cx) {
-                            Poll::Ready(Ok(item)) => {
-                                this.items.$n = Some(item);
+            impl<$($T: FromRequest),+> Future for $fut_type<$($T),+>
+            {
+                type Output = Result<($($T,)+), Error>;
+
+                fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+                    let mut this = self.project();
+
+                    let mut ready = true;
+                    $(
+                        if this.items.$n.is_none()
+                            && !this.failures.iter().any(|(n, ..)| *n == $n)
+                        {
+                            match this.futs.as_mut().project().$n.poll(cx) {
+                                Poll::Ready(Ok(item)) => {
+                                    this.items.$n = Some(item);
+                                }
+                                Poll::Pending => ready = false,
+                                Poll::Ready(Err(e)) => {
+                                    let e = e.into();
+
+                                    if !*this.aggregate || is_fatal(&e) {
+                                        return Poll::Ready(Err(e));
+                                    }
+
+                                    this.failures.push(($n, extractor_name::<$T>(), e));
+                                }
                             }
-                            Poll::Pending => ready = false,
-                            Poll::Ready(Err(e)) => return Poll::Ready(Err(e.into())),
                         }
+                    )+
+
+                    if !ready {
+                        return Poll::Pending;
                     }
-                )+
 
-                    if ready {
-                        Poll::Ready(Ok(
+                    match this.failures.len() {
+                        0 => Poll::Ready(Ok(
                             ($(this.items.$n.take().unwrap(),)+)
-                        ))
-                    } else {
-                        Poll::Pending
+                        )),
+                        1 => Poll::Ready(Err(this.failures.pop().unwrap().2)),
+                        _ => {
+                            let failures = this
+                                .failures
+                                .drain(..)
+                                .map(|(_, source, err)| (source, err.to_string()))
+                                .collect();
+
+                            Poll::Ready(Err(AggregateError { failures }.into()))
+                        }
                     }
+                }
             }
         }
-    }
-});
+    };
+}
 
 #[rustfmt::skip]
 mod m {
@@ -328,6 +436,12 @@ tuple_from_req!(TupleFromRequest7, (0, A), (1, B), (2, C), (3, D), (4, E), (5, F
 tuple_from_req!(TupleFromRequest8, (0, A), (1, B), (2, C), (3, D), (4, E), (5, F), (6, G), (7, H));
 tuple_from_req!(TupleFromRequest9, (0, A), (1, B), (2, C), (3, D), (4, E), (5, F), (6, G), (7, H), (8, I));
 tuple_from_req!(TupleFromRequest10, (0, A), (1, B), (2, C), (3, D), (4, E), (5, F), (6, G), (7, H), (8, I), (9, J));
+tuple_from_req!(TupleFromRequest11, (0, A), (1, B), (2, C), (3, D), (4, E), (5, F), (6, G), (7, H), (8, I), (9, J), (10, K));
+tuple_from_req!(TupleFromRequest12, (0, A), (1, B), (2, C), (3, D), (4, E), (5, F), (6, G), (7, H), (8, I), (9, J), (10, K), (11, L));
+tuple_from_req!(@unit_config TupleFromRequest13, (0, A), (1, B), (2, C), (3, D), (4, E), (5, F), (6, G), (7, H), (8, I), (9, J), (10, K), (11, L), (12, M));
+tuple_from_req!(@unit_config TupleFromRequest14, (0, A), (1, B), (2, C), (3, D), (4, E), (5, F), (6, G), (7, H), (8, I), (9, J), (10, K), (11, L), (12, M), (13, N));
+tuple_from_req!(@unit_config TupleFromRequest15, (0, A), (1, B), (2, C), (3, D), (4, E), (5, F), (6, G), (7, H), (8, I), (9, J), (10, K), (11, L), (12, M), (13, N), (14, O));
+tuple_from_req!(@unit_config TupleFromRequest16, (0, A), (1, B), (2, C), (3, D), (4, E), (5, F), (6, G), (7, H), (8, I), (9, J), (10, K), (11, L), (12, M), (13, N), (14, O), (15, P));
 }
 
 #[cfg(test)]
@@ -337,8 +451,11 @@ mod tests {
     use serde_derive::Deserialize;
 
     use super::*;
-    use crate::test::TestRequest;
+    use crate::test::{
+        call_and_read_body, call_service, init_service, read_body, read_body_json, TestRequest,
+    };
     use crate::types::{Form, FormConfig};
+    use crate::{web, App};
 
     #[derive(Deserialize, Debug, PartialEq)]
     struct Info {
@@ -415,4 +532,97 @@ mod tests {
             .unwrap();
         assert!(r.is_err());
     }
+
+    #[actix_rt::test]
+    async fn test_sixteen_parameter_handler() {
+        #[allow(clippy::too_many_arguments)]
+        async fn handler(
+            a: HttpRequest,
+            _: Option<web::Data<u8>>,
+            _: Option<web::Data<u8>>,
+            _: Option<web::Data<u8>>,
+            _: HttpRequest,
+            _: HttpRequest,
+            _: HttpRequest,
+            _: HttpRequest,
+            _: Option<Form<Info>>,
+            _: Option<Form<Info>>,
+            _: Option<web::Data<u8>>,
+            _: Option<web::Data<u8>>,
+            _: HttpRequest,
+            _: HttpRequest,
+            o: HttpRequest,
+            p: Bytes,
+        ) -> String {
+            format!("{} {} {:?}", a.method(), o.path(), p)
+        }
+
+        let srv = init_service(App::new().route("/sixteen", web::post().to(handler))).await;
+
+        let req = TestRequest::post()
+            .uri("/sixteen")
+            .set_payload("body")
+            .to_request();
+        let body = call_and_read_body(&srv, req).await;
+        assert_eq!(body, "POST /sixteen b\"body\"");
+    }
+
+    #[actix_rt::test]
+    async fn test_aggregate_errors() {
+        async fn handler(
+            _: web::Path<u32>,
+            _: web::Query<Info>,
+            _: web::Json<Info>,
+        ) -> &'static str {
+            "ok"
+        }
+
+        let srv = init_service(
+            App::new()
+                .service(
+                    web::scope("/aggregate")
+                        .app_data(ExtractorErrorMode::Aggregate)
+                        .route("/{id}", web::post().to(handler)),
+                )
+                .route("/{id}", web::post().to(handler)),
+        )
+        .await;
+
+        let req = |uri: &str, body: &'static str| {
+            TestRequest::post()
+                .uri(uri)
+                .insert_header((header::CONTENT_TYPE, "application/json"))
+                .set_payload(body)
+                .to_request()
+        };
+
+        // bad path and query are reported together
+        let res = call_service(&srv, req("/aggregate/abc?bye=1", r#"{"hello":"x"}"#)).await;
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+        let body: serde_json::Value = read_body_json(res).await;
+        let errors = body["errors"].as_array().unwrap();
+        assert_eq!(errors.len(), 2);
+        assert_eq!(errors[0]["source"], "Path");
+        assert_eq!(errors[1]["source"], "Query");
+        assert!(errors[1]["message"]
+            .as_str()
+            .unwrap()
+            .contains("missing field `hello`"));
+
+        // by default, only the first failure is reported
+        let res = call_service(&srv, req("/abc?bye=1", r#"{"hello":"x"}"#)).await;
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+
+        // a single failure is reported as in the default mode
+        let res = call_service(&srv, req("/aggregate/1?hello=1", "{")).await;
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+        let aggregate = read_body(res).await;
+
+        let res = call_service(&srv, req("/1?hello=1", "{")).await;
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(read_body(res).await, aggregate);
+
+        let res = call_service(&srv, req("/aggregate/1?hello=1", r#"{"hello":"x"}"#)).await;
+        assert_eq!(res.status(), StatusCode::OK);
+    }
 }
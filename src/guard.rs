@@ -30,6 +30,9 @@ use std::convert::TryFrom;
 use actix_http::http::{self, header, uri::Uri};
 use actix_http::RequestHead;
 
+#[cfg(any(feature = "openssl", feature = "rustls"))]
+use crate::tls::PeerCertificates;
+
 /// Trait defines resource guards. Guards are used for route selection.
 ///
 /// Guards can not modify the request object. But it is possible
@@ -257,6 +260,47 @@ impl Guard for HeaderGuard {
     }
 }
 
+/// Return predicate that matches if the client presented a verified TLS certificate accepted by
+/// `pred`.
+///
+/// Requests without a verified certificate do not match. See [`PeerCertificates`].
+///
+/// ```rust
+/// use actix_web::{guard, web, App, HttpResponse};
+///
+/// App::new().service(
+///     web::resource("/admin")
+///         .guard(guard::ClientCert(|certs| certs.chain().len() > 1))
+///         .to(|| HttpResponse::Ok())
+/// );
+/// ```
+///
+/// [`PeerCertificates`]: crate::tls::PeerCertificates
+#[cfg(any(feature = "openssl", feature = "rustls"))]
+pub fn ClientCert<F>(pred: F) -> ClientCertGuard<F>
+where
+    F: Fn(&PeerCertificates) -> bool,
+{
+    ClientCertGuard(pred)
+}
+
+#[cfg(any(feature = "openssl", feature = "rustls"))]
+#[doc(hidden)]
+pub struct ClientCertGuard<F>(F);
+
+#[cfg(any(feature = "openssl", feature = "rustls"))]
+impl<F> Guard for ClientCertGuard<F>
+where
+    F: Fn(&PeerCertificates) -> bool,
+{
+    fn check(&self, req: &RequestHead) -> bool {
+        match req.extensions().get::<PeerCertificates>() {
+            Some(certs) => certs.is_verified() && (self.0)(certs),
+            None => false,
+        }
+    }
+}
+
 /// Return predicate that matches if request contains specified Host name.
 ///
 /// ```rust
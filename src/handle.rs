@@ -0,0 +1,207 @@
+//! Programmatic control of a running server, see [`ServerHandle`].
+
+use std::{
+    fmt,
+    future::Future,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    thread::{self, ThreadId},
+    time::Duration,
+};
+
+use actix_http::GracefulShutdown;
+use actix_rt::{Arbiter, ArbiterHandle};
+use actix_server::Server;
+use tokio::sync::Notify;
+
+/// Handle for stopping a server started with [`HttpServer::run`](crate::HttpServer::run).
+///
+/// Obtained from [`HttpServer::handle`](crate::HttpServer::handle), before or after the server
+/// is started; clones control the same server. Together with
+/// [`HttpServer::disable_signals`](crate::HttpServer::disable_signals) it lets applications that
+/// manage signals themselves decide when the server stops.
+///
+/// The handle is also added to the app data of every worker, so handlers can read it with
+/// [`HttpRequest::app_data`](crate::HttpRequest::app_data).
+///
+/// ```rust,no_run
+/// use actix_web::{dev::ServerHandle, web, App, HttpRequest, HttpResponse, HttpServer};
+///
+/// async fn shutdown(req: HttpRequest) -> HttpResponse {
+///     let handle = req.app_data::<ServerHandle>().unwrap().clone();
+///     actix_web::rt::spawn(async move { handle.stop(true).await });
+///     HttpResponse::Accepted().finish()
+/// }
+///
+/// #[actix_rt::main]
+/// async fn main() -> std::io::Result<()> {
+///     HttpServer::new(|| App::new().route("/shutdown", web::post().to(shutdown)))
+///         .disable_signals()
+///         .bind("127.0.0.1:8080")?
+///         .run()
+///         .await
+/// }
+/// ```
+#[derive(Clone)]
+pub struct ServerHandle {
+    inner: Arc<Inner>,
+}
+
+struct Inner {
+    running: Mutex<Option<Running>>,
+    workers: Mutex<Vec<(ThreadId, ArbiterHandle)>>,
+    stopped: AtomicBool,
+    notify: Notify,
+}
+
+struct Running {
+    server: Server,
+    shutdown: GracefulShutdown,
+    stop_hooks: bool,
+}
+
+impl ServerHandle {
+    pub(crate) fn new() -> Self {
+        ServerHandle {
+            inner: Arc::new(Inner {
+                running: Mutex::new(None),
+                workers: Mutex::new(Vec::new()),
+                stopped: AtomicBool::new(false),
+                notify: Notify::new(),
+            }),
+        }
+    }
+
+    /// Stops the server.
+    ///
+    /// A graceful stop triggers the server's
+    /// [graceful shutdown](crate::HttpServer::graceful_shutdown) handle, runs worker
+    /// [stop hooks](crate::HttpServer::on_worker_stop) and gives connections up to the
+    /// [shutdown timeout](crate::HttpServer::shutdown_timeout) to finish. Otherwise connections
+    /// are dropped right away.
+    ///
+    /// The returned future resolves once the server has stopped. Does nothing if the server has
+    /// not been started.
+    pub fn stop(&self, graceful: bool) -> impl Future<Output = ()> + 'static {
+        let running = self.inner.running.lock().unwrap().as_ref().map(|running| {
+            (
+                running.server.clone(),
+                running.shutdown.clone(),
+                running.stop_hooks,
+            )
+        });
+
+        let inner = Arc::clone(&self.inner);
+
+        async move {
+            let (server, shutdown, stop_hooks) = match running {
+                Some(running) => running,
+                None => return,
+            };
+
+            if graceful {
+                shutdown.shutdown();
+
+                // with stop hooks, the server stops itself once they have run
+                if stop_hooks {
+                    inner.stopped().await;
+                    return;
+                }
+
+                server.stop(true).await;
+            } else {
+                let stop = server.stop(false);
+                inner.abort_workers();
+                stop.await;
+            }
+
+            inner.set_stopped();
+        }
+    }
+
+    /// Stops the server gracefully, dropping the connections still open after `timeout`.
+    ///
+    /// Same as [`stop(true)`](Self::stop), with `timeout` overriding the configured
+    /// [shutdown timeout](crate::HttpServer::shutdown_timeout) for this stop.
+    pub fn stop_with_timeout(&self, timeout: Duration) -> impl Future<Output = ()> + 'static {
+        let stop = self.stop(true);
+        let inner = Arc::clone(&self.inner);
+
+        async move {
+            let mut stop = Box::pin(stop);
+
+            if actix_rt::time::timeout(timeout, &mut stop).await.is_err() {
+                log::warn!("Connections did not finish within shutdown timeout, dropping them");
+                inner.abort_workers();
+                stop.await;
+            }
+        }
+    }
+
+    /// Resolves once the server has stopped, however it was stopped.
+    pub fn await_stopped(&self) -> impl Future<Output = ()> + 'static {
+        let inner = Arc::clone(&self.inner);
+        async move { inner.stopped().await }
+    }
+
+    /// Called by the server once it has started.
+    pub(crate) fn start(&self, server: Server, shutdown: GracefulShutdown, stop_hooks: bool) {
+        *self.inner.running.lock().unwrap() = Some(Running {
+            server: server.clone(),
+            shutdown,
+            stop_hooks,
+        });
+
+        let inner = Arc::clone(&self.inner);
+        actix_rt::spawn(async move {
+            let _ = server.await;
+            inner.set_stopped();
+        });
+    }
+
+    /// Called on each worker thread, so that its connections can be dropped.
+    pub(crate) fn register_worker(&self) {
+        let id = thread::current().id();
+        let mut workers = self.inner.workers.lock().unwrap();
+
+        if workers.iter().all(|(worker, _)| *worker != id) {
+            workers.push((id, Arbiter::current()));
+        }
+    }
+}
+
+impl Inner {
+    fn abort_workers(&self) {
+        for (_, arbiter) in self.workers.lock().unwrap().drain(..) {
+            arbiter.stop();
+        }
+    }
+
+    fn set_stopped(&self) {
+        self.stopped.store(true, Ordering::Release);
+        self.notify.notify_waiters();
+    }
+
+    async fn stopped(&self) {
+        loop {
+            let notified = self.notify.notified();
+
+            if self.stopped.load(Ordering::Acquire) {
+                return;
+            }
+
+            notified.await;
+        }
+    }
+}
+
+impl fmt::Debug for ServerHandle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ServerHandle")
+            .field("started", &self.inner.running.lock().unwrap().is_some())
+            .field("stopped", &self.inner.stopped.load(Ordering::Acquire))
+            .finish()
+    }
+}
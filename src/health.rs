@@ -0,0 +1,179 @@
+//! Liveness and readiness state of a server, see [`HealthCheck`].
+
+use std::{
+    fmt,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+};
+
+use actix_http::GracefulShutdown;
+
+use crate::{web, HttpResponse, Resource};
+
+/// Liveness and readiness state of a server, for use by health check probes.
+///
+/// Register a handle with [`HttpServer::health_check`](crate::HttpServer::health_check) and the
+/// server keeps it up to date:
+/// - [`live`](Self::live) becomes true once the first worker's app has started;
+/// - [`ready`](Self::ready) becomes true once the apps of all workers have started, including
+///   their [`data_factory`](crate::App::data_factory) initializations, and becomes false again
+///   once the server's [`GracefulShutdown`] handle is triggered.
+///
+/// The handle is also added to the app data of every worker, so handlers can read it with
+/// [`HttpRequest::app_data`](crate::HttpRequest::app_data).
+///
+/// ```rust,no_run
+/// use actix_web::{web, App, HttpServer};
+///
+/// #[actix_rt::main]
+/// async fn main() -> std::io::Result<()> {
+///     let health = web::HealthCheck::new();
+///
+///     HttpServer::new({
+///         let health = health.clone();
+///         move || App::new().service(health.routes())
+///     })
+///     .health_check(health)
+///     .bind("127.0.0.1:8080")?
+///     .run()
+///     .await
+/// }
+/// ```
+#[derive(Clone)]
+pub struct HealthCheck {
+    inner: Arc<Inner>,
+}
+
+struct Inner {
+    app_ready: AtomicBool,
+    started: AtomicUsize,
+    expected: AtomicUsize,
+    shutdown: Mutex<Option<GracefulShutdown>>,
+}
+
+impl HealthCheck {
+    /// Constructs a handle for a server that has not started yet.
+    pub fn new() -> Self {
+        HealthCheck {
+            inner: Arc::new(Inner {
+                app_ready: AtomicBool::new(true),
+                started: AtomicUsize::new(0),
+                expected: AtomicUsize::new(0),
+                shutdown: Mutex::new(None),
+            }),
+        }
+    }
+
+    /// Returns true once a worker has started serving requests.
+    pub fn live(&self) -> bool {
+        self.inner.started.load(Ordering::Acquire) > 0
+    }
+
+    /// Returns true if all workers have started, graceful shutdown has not begun and the
+    /// application has not marked itself as not ready.
+    pub fn ready(&self) -> bool {
+        let started = self.inner.started.load(Ordering::Acquire);
+
+        started > 0
+            && started >= self.inner.expected.load(Ordering::Acquire)
+            && self.inner.app_ready.load(Ordering::Acquire)
+            && !self.is_shutdown()
+    }
+
+    /// Sets whether the application is ready to receive traffic, e.g. while waiting for a
+    /// database connection to be re-established.
+    ///
+    /// Defaults to ready. This can not make a server that is starting or shutting down ready.
+    pub fn set_ready(&self, ready: bool) {
+        self.inner.app_ready.store(ready, Ordering::Release);
+    }
+
+    /// Returns `/livez` and `/readyz` resources, answering `200 OK` when the corresponding state
+    /// is true and `503 Service Unavailable` otherwise.
+    ///
+    /// Register them with [`App::service`](crate::App::service).
+    pub fn routes(&self) -> (Resource, Resource) {
+        let live = self.clone();
+        let ready = self.clone();
+
+        (
+            web::resource("/livez").to(move || probe(live.live())),
+            web::resource("/readyz").to(move || probe(ready.ready())),
+        )
+    }
+
+    /// Called by the server before starting its workers.
+    pub(crate) fn start(&self, expected: usize, shutdown: GracefulShutdown) {
+        self.inner.expected.store(expected, Ordering::Release);
+        *self.inner.shutdown.lock().unwrap() = Some(shutdown);
+    }
+
+    /// Called by each worker's app once it has been initialized.
+    pub(crate) fn worker_started(&self) {
+        self.inner.started.fetch_add(1, Ordering::AcqRel);
+    }
+
+    fn is_shutdown(&self) -> bool {
+        self.inner
+            .shutdown
+            .lock()
+            .unwrap()
+            .as_ref()
+            .map_or(false, GracefulShutdown::is_shutdown)
+    }
+}
+
+impl Default for HealthCheck {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for HealthCheck {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HealthCheck")
+            .field("live", &self.live())
+            .field("ready", &self.ready())
+            .finish()
+    }
+}
+
+fn probe(ok: bool) -> futures_util::future::Ready<HttpResponse> {
+    futures_util::future::ready(if ok {
+        HttpResponse::Ok().finish()
+    } else {
+        HttpResponse::ServiceUnavailable().finish()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ready_after_all_workers() {
+        let health = HealthCheck::new();
+        let shutdown = GracefulShutdown::new();
+        health.start(2, shutdown.clone());
+        assert!(!health.live());
+        assert!(!health.ready());
+
+        health.worker_started();
+        assert!(health.live());
+        assert!(!health.ready());
+
+        health.worker_started();
+        assert!(health.ready());
+
+        health.set_ready(false);
+        assert!(!health.ready());
+        health.set_ready(true);
+        assert!(health.ready());
+
+        shutdown.shutdown();
+        assert!(health.live());
+        assert!(!health.ready());
+    }
+}
@@ -1,7 +1,14 @@
-use std::cell::Ref;
+use std::{
+    cell::Ref,
+    fmt,
+    net::{IpAddr, SocketAddr},
+    str::FromStr,
+};
+
+use derive_more::{Display, Error};
 
 use crate::dev::{AppConfig, RequestHead};
-use crate::http::header::{self, HeaderName};
+use crate::http::header::{self, Forwarded, HeaderName};
 
 const X_FORWARDED_FOR: &[u8] = b"x-forwarded-for";
 const X_FORWARDED_HOST: &[u8] = b"x-forwarded-host";
@@ -16,64 +23,106 @@ pub struct ConnectionInfo {
     remote_addr: Option<String>,
 }
 
+/// Connection info resolved with [`TrustedProxies`], cached apart from the info resolved without.
+struct TrustedConnectionInfo(ConnectionInfo);
+
 impl ConnectionInfo {
     /// Create *ConnectionInfo* instance for a request.
+    ///
+    /// Forwarded headers are trusted as described in
+    /// [`realip_remote_addr`](Self::realip_remote_addr), ignoring any [`TrustedProxies`] of the
+    /// app. Use `HttpRequest::connection_info` to resolve the connection info with them.
     pub fn get<'a>(req: &'a RequestHead, cfg: &AppConfig) -> Ref<'a, Self> {
-        if !req.extensions().contains::<ConnectionInfo>() {
-            req.extensions_mut().insert(ConnectionInfo::new(req, cfg));
-        }
-        Ref::map(req.extensions(), |e| e.get().unwrap())
+        Self::get_with(req, cfg, None)
     }
 
-    #[allow(clippy::cognitive_complexity, clippy::borrow_interior_mutable_const)]
-    fn new(req: &RequestHead, cfg: &AppConfig) -> ConnectionInfo {
-        let mut host = None;
-        let mut scheme = None;
-        let mut realip_remote_addr = None;
-
-        // load forwarded header
-        for hdr in req.headers.get_all(&header::FORWARDED) {
-            if let Ok(val) = hdr.to_str() {
-                for pair in val.split(';') {
-                    for el in pair.split(',') {
-                        let mut items = el.trim().splitn(2, '=');
-                        if let Some(name) = items.next() {
-                            if let Some(val) = items.next() {
-                                match &name.to_lowercase() as &str {
-                                    "for" => {
-                                        if realip_remote_addr.is_none() {
-                                            realip_remote_addr = Some(val.trim());
-                                        }
-                                    }
-                                    "proto" => {
-                                        if scheme.is_none() {
-                                            scheme = Some(val.trim());
-                                        }
-                                    }
-                                    "host" => {
-                                        if host.is_none() {
-                                            host = Some(val.trim());
-                                        }
-                                    }
-                                    _ => {}
-                                }
-                            }
-                        }
-                    }
+    /// Create *ConnectionInfo* instance for a request, using the app's trusted proxies.
+    ///
+    /// The info is cached in the request extensions separately for each trust mode, so that
+    /// resolving it without trusted proxies does not hide the info resolved with them.
+    pub(crate) fn get_with<'a>(
+        req: &'a RequestHead,
+        cfg: &AppConfig,
+        trusted: Option<&TrustedProxies>,
+    ) -> Ref<'a, Self> {
+        match trusted {
+            None => {
+                if !req.extensions().contains::<ConnectionInfo>() {
+                    req.extensions_mut()
+                        .insert(ConnectionInfo::new(req, cfg, None));
+                }
+                Ref::map(req.extensions(), |e| e.get().unwrap())
+            }
+            Some(trusted) => {
+                if !req.extensions().contains::<TrustedConnectionInfo>() {
+                    let info = ConnectionInfo::new(req, cfg, Some(trusted));
+                    req.extensions_mut().insert(TrustedConnectionInfo(info));
                 }
+                Ref::map(req.extensions(), |e| {
+                    &e.get::<TrustedConnectionInfo>().unwrap().0
+                })
             }
         }
+    }
+
+    #[allow(clippy::cognitive_complexity, clippy::borrow_interior_mutable_const)]
+    fn new(
+        req: &RequestHead,
+        cfg: &AppConfig,
+        trusted: Option<&TrustedProxies>,
+    ) -> ConnectionInfo {
+        // with trusted proxies configured, forwarded headers only count when set by one of them
+        let forwarded_trusted = match (trusted, req.peer_addr) {
+            (None, _) => true,
+            (Some(trusted), Some(peer)) => trusted.contains(peer.ip()),
+            (Some(_), None) => false,
+        };
+
+        let forwarded = joined_header(req, &header::FORWARDED)
+            .filter(|_| forwarded_trusted)
+            .and_then(|val| val.parse::<Forwarded>().ok())
+            .map(|forwarded| forwarded.0)
+            .unwrap_or_default();
+
+        // without trusted proxies the first element, the hop closest to the client, is used;
+        // with them the element added by the first trusted proxy, since the client can send any
+        // elements on its left
+        let hop = match trusted {
+            Some(trusted) => trusted.client_hop(&forwarded, |el| {
+                el.for_node.as_ref().and_then(|node| node.ip())
+            }),
+            None => forwarded.first(),
+        };
+
+        let mut host = hop.and_then(|el| el.host.as_deref());
+        let mut scheme = hop.and_then(|el| el.proto.as_deref());
+
+        // obfuscated and unknown nodes do not identify the client
+        let mut realip_remote_addr = hop
+            .and_then(|el| el.for_node.as_ref())
+            .filter(|node| node.ip().is_some())
+            .map(|node| node.to_string());
+
+        // of the X-Forwarded-* lists, only the rightmost element is set by a trusted proxy
+        let list_value = |list: &str| -> Option<String> {
+            let value = match trusted {
+                Some(_) => list.rsplit(',').next(),
+                None => list.split(',').next(),
+            };
+            value.map(|v| v.trim().to_owned())
+        };
+
+        let forwarded_proto;
+        let forwarded_host;
 
         // scheme
         if scheme.is_none() {
-            if let Some(h) = req
-                .headers
-                .get(&HeaderName::from_lowercase(X_FORWARDED_PROTO).unwrap())
-            {
-                if let Ok(h) = h.to_str() {
-                    scheme = h.split(',').next().map(|v| v.trim());
-                }
-            }
+            forwarded_proto =
+                joined_header(req, &HeaderName::from_lowercase(X_FORWARDED_PROTO).unwrap())
+                    .filter(|_| forwarded_trusted)
+                    .and_then(|h| list_value(&h));
+            scheme = forwarded_proto.as_deref();
+
             if scheme.is_none() {
                 scheme = req.uri.scheme().map(|a| a.as_str());
                 if scheme.is_none() && cfg.secure() {
@@ -84,14 +133,12 @@ impl ConnectionInfo {
 
         // host
         if host.is_none() {
-            if let Some(h) = req
-                .headers
-                .get(&HeaderName::from_lowercase(X_FORWARDED_HOST).unwrap())
-            {
-                if let Ok(h) = h.to_str() {
-                    host = h.split(',').next().map(|v| v.trim());
-                }
-            }
+            forwarded_host =
+                joined_header(req, &HeaderName::from_lowercase(X_FORWARDED_HOST).unwrap())
+                    .filter(|_| forwarded_trusted)
+                    .and_then(|h| list_value(&h));
+            host = forwarded_host.as_deref();
+
             if host.is_none() {
                 if let Some(h) = req.headers.get(&header::HOST) {
                     host = h.to_str().ok();
@@ -108,14 +155,25 @@ impl ConnectionInfo {
         // get remote_addraddr from socketaddr
         let remote_addr = req.peer_addr.map(|addr| format!("{}", addr));
 
-        if realip_remote_addr.is_none() {
-            if let Some(h) = req
-                .headers
-                .get(&HeaderName::from_lowercase(X_FORWARDED_FOR).unwrap())
+        // with trusted proxies, the Forwarded header replaces X-Forwarded-For when present
+        let use_xff = match trusted {
+            Some(_) => forwarded_trusted && forwarded.is_empty(),
+            None => realip_remote_addr.is_none(),
+        };
+
+        if use_xff {
+            if let Some(h) =
+                joined_header(req, &HeaderName::from_lowercase(X_FORWARDED_FOR).unwrap())
             {
-                if let Ok(h) = h.to_str() {
-                    realip_remote_addr = h.split(',').next().map(|v| v.trim());
+                let hops = h.split(',').map(str::trim).collect::<Vec<_>>();
+
+                realip_remote_addr = match trusted {
+                    Some(trusted) => trusted
+                        .client_hop(&hops, |hop| parse_xff_ip(hop))
+                        .filter(|hop| parse_xff_ip(hop).is_some()),
+                    None => hops.first(),
                 }
+                .map(|hop| (*hop).to_owned());
             }
         }
 
@@ -123,7 +181,7 @@ impl ConnectionInfo {
             remote_addr,
             scheme: scheme.unwrap_or("http").to_owned(),
             host: host.unwrap_or("localhost").to_owned(),
-            realip_remote_addr: realip_remote_addr.map(|s| s.to_owned()),
+            realip_remote_addr,
         }
     }
 
@@ -170,10 +228,20 @@ impl ConnectionInfo {
     /// - X-Forwarded-For
     /// - remote_addr name of opened socket
     ///
+    /// Only the first element of the `Forwarded` header is used, and it is skipped if its `for`
+    /// parameter is obfuscated or `unknown`. IPv6 addresses are returned in brackets, and include
+    /// the port if one was forwarded.
+    ///
+    /// When the app has [`TrustedProxies`], forwarded headers are only used if the request comes
+    /// from a trusted proxy. The `Forwarded` header, or X-Forwarded-For if it is absent, is then
+    /// walked from the right, skipping trusted proxies, and the first untrusted hop is the client.
+    /// Header lines are joined in order, and the host and scheme are taken from the element of
+    /// that hop, or from the rightmost X-Forwarded-Host and X-Forwarded-Proto values.
+    ///
     /// # Security
-    /// Do not use this function for security purposes, unless you can ensure the Forwarded and
-    /// X-Forwarded-For headers cannot be spoofed by the client. If you want the client's socket
-    /// address explicitly, use
+    /// Without [`TrustedProxies`], do not use this function for security purposes unless you can
+    /// ensure the Forwarded and X-Forwarded-For headers cannot be spoofed by the client. If you
+    /// want the client's socket address explicitly, use
     /// [`HttpRequest::peer_addr()`](super::web::HttpRequest::peer_addr()) instead.
     #[inline]
     pub fn realip_remote_addr(&self) -> Option<&str> {
@@ -187,10 +255,184 @@ impl ConnectionInfo {
     }
 }
 
+/// Proxies trusted to set forwarded headers, for [`ConnectionInfo`].
+///
+/// Register it as app data on the [`App`](crate::App), so that it applies to every request,
+/// including those inspected by middleware. Requests from peers outside these ranges have their
+/// forwarded headers ignored, and the client address is resolved by skipping trusted proxies from
+/// the right of the forwarding chain, since the hops on the left may be sent by the client.
+///
+/// ```
+/// use actix_web::{dev::TrustedProxies, App};
+///
+/// let app = App::new().app_data(TrustedProxies(vec![
+///     "10.0.0.0/8".parse().unwrap(),
+///     "2001:db8::1".parse().unwrap(),
+/// ]));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TrustedProxies(pub Vec<IpCidr>);
+
+impl TrustedProxies {
+    /// Returns true if `ip` is in one of the trusted ranges.
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        self.0.iter().any(|cidr| cidr.contains(ip))
+    }
+
+    /// Returns the rightmost hop that is not a trusted proxy, or the leftmost hop if all are.
+    fn client_hop<'a, T>(
+        &self,
+        hops: &'a [T],
+        ip: impl Fn(&T) -> Option<IpAddr>,
+    ) -> Option<&'a T> {
+        let mut client = None;
+
+        for hop in hops.iter().rev() {
+            client = Some(hop);
+
+            match ip(hop) {
+                Some(ip) if self.contains(ip) => {}
+                _ => break,
+            }
+        }
+
+        client
+    }
+}
+
+/// Parses an X-Forwarded-For entry, which may include a port.
+fn parse_xff_ip(hop: &str) -> Option<IpAddr> {
+    hop.parse::<IpAddr>()
+        .or_else(|_| hop.parse::<SocketAddr>().map(|addr| addr.ip()))
+        .ok()
+}
+
+/// Range of IP addresses in CIDR notation, e.g. `192.168.0.0/16`.
+///
+/// Parsing an address without prefix length gives a range containing only that address.
+/// IPv4-mapped IPv6 addresses are matched as the IPv4 address they map.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IpCidr {
+    addr: IpAddr,
+    prefix_len: u8,
+}
+
+impl IpCidr {
+    /// Create a range from an address and a prefix length.
+    ///
+    /// Returns an error if the prefix length is longer than the address.
+    pub fn new(addr: IpAddr, prefix_len: u8) -> Result<Self, IpCidrParseError> {
+        let max_len = if addr.is_ipv4() { 32 } else { 128 };
+
+        if prefix_len > max_len {
+            return Err(IpCidrParseError);
+        }
+
+        Ok(IpCidr { addr, prefix_len })
+    }
+
+    /// Returns the address the range was created with.
+    pub fn addr(&self) -> IpAddr {
+        self.addr
+    }
+
+    /// Returns the prefix length.
+    pub fn prefix_len(&self) -> u8 {
+        self.prefix_len
+    }
+
+    /// Returns true if `ip` is in the range.
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        match (self.addr, unmap_ipv4(ip)) {
+            (IpAddr::V4(net), IpAddr::V4(ip)) => {
+                let mask = u32::MAX
+                    .checked_shl(32 - u32::from(self.prefix_len))
+                    .unwrap_or(0);
+                u32::from(net) & mask == u32::from(ip) & mask
+            }
+            (IpAddr::V6(net), IpAddr::V6(ip)) => {
+                let mask = u128::MAX
+                    .checked_shl(128 - u32::from(self.prefix_len))
+                    .unwrap_or(0);
+                u128::from(net) & mask == u128::from(ip) & mask
+            }
+            _ => false,
+        }
+    }
+}
+
+impl From<IpAddr> for IpCidr {
+    fn from(addr: IpAddr) -> Self {
+        let prefix_len = if addr.is_ipv4() { 32 } else { 128 };
+        IpCidr { addr, prefix_len }
+    }
+}
+
+impl FromStr for IpCidr {
+    type Err = IpCidrParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.splitn(2, '/');
+        let addr = parts
+            .next()
+            .unwrap()
+            .parse::<IpAddr>()
+            .map_err(|_| IpCidrParseError)?;
+
+        match parts.next() {
+            Some(len) => IpCidr::new(addr, len.parse().map_err(|_| IpCidrParseError)?),
+            None => Ok(IpCidr::from(addr)),
+        }
+    }
+}
+
+impl fmt::Display for IpCidr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.addr, self.prefix_len)
+    }
+}
+
+/// Error returned when parsing an invalid [`IpCidr`].
+#[derive(Debug, Display, Error)]
+#[display(fmt = "invalid IP address range")]
+pub struct IpCidrParseError;
+
+/// Returns all lines of a header joined in order, as if they had been sent as a single line.
+///
+/// Proxies may append their own header line instead of extending the existing one. Returns `None`
+/// if the header is missing or any of its lines is not valid text.
+fn joined_header(req: &RequestHead, name: &HeaderName) -> Option<String> {
+    let lines = req
+        .headers
+        .get_all(name)
+        .map(|val| val.to_str())
+        .collect::<Result<Vec<_>, _>>()
+        .ok()?;
+
+    if lines.is_empty() {
+        None
+    } else {
+        Some(lines.join(","))
+    }
+}
+
+/// Converts an IPv4-mapped IPv6 address, `::ffff:a.b.c.d`, to its IPv4 address.
+fn unmap_ipv4(ip: IpAddr) -> IpAddr {
+    match ip {
+        IpAddr::V6(v6) => match v6.octets() {
+            [0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0xff, 0xff, a, b, c, d] => {
+                IpAddr::from([a, b, c, d])
+            }
+            _ => ip,
+        },
+        IpAddr::V4(_) => ip,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::test::TestRequest;
+    use crate::{http::header::IntoHeaderPair, test::TestRequest};
 
     #[test]
     fn test_forwarded() {
@@ -239,4 +481,232 @@ mod tests {
         let info = req.connection_info();
         assert_eq!(info.scheme(), "https");
     }
+
+    #[test]
+    fn test_forwarded_elements() {
+        let req = TestRequest::default()
+            .insert_header((
+                header::FORWARDED,
+                "for=\"[2001:db8:cafe::17]:4711\";proto=https, for=192.0.2.43;host=proxy.local",
+            ))
+            .to_http_request();
+        let info = req.connection_info();
+        assert_eq!(info.realip_remote_addr(), Some("[2001:db8:cafe::17]:4711"));
+        assert_eq!(info.scheme(), "https");
+        assert_eq!(info.host(), "localhost:8080");
+
+        let req = TestRequest::default()
+            .insert_header((header::FORWARDED, "for=\"_hidden\", for=192.0.2.43"))
+            .insert_header((X_FORWARDED_FOR, "198.51.100.17"))
+            .to_http_request();
+        let info = req.connection_info();
+        assert_eq!(info.realip_remote_addr(), Some("198.51.100.17"));
+
+        let req = TestRequest::default()
+            .insert_header((header::FORWARDED, "for=unknown"))
+            .to_http_request();
+        let info = req.connection_info();
+        assert_eq!(info.realip_remote_addr(), None);
+    }
+
+    #[test]
+    fn test_forwarded_malformed() {
+        let req = TestRequest::default()
+            .insert_header((header::FORWARDED, "for=\"192.0.2.60;proto=https"))
+            .insert_header((X_FORWARDED_PROTO, "http"))
+            .to_http_request();
+        let info = req.connection_info();
+        assert_eq!(info.scheme(), "http");
+        assert_eq!(info.realip_remote_addr(), None);
+    }
+
+    fn proxied_request(peer: &str, header: impl IntoHeaderPair) -> TestRequest {
+        let trusted = TrustedProxies(vec![
+            "10.0.0.0/8".parse().unwrap(),
+            "192.168.1.5".parse().unwrap(),
+        ]);
+
+        TestRequest::default()
+            .peer_addr(peer.parse().unwrap())
+            .app_data(trusted)
+            .insert_header(header)
+    }
+
+    #[test]
+    fn test_trusted_proxies_spoofed() {
+        // the client sent the leading entry, the proxy appended the address it saw
+        let req = proxied_request("10.0.0.1:443", (X_FORWARDED_FOR, "1.2.3.4, 203.0.113.7"))
+            .to_http_request();
+        assert_eq!(
+            req.connection_info().realip_remote_addr(),
+            Some("203.0.113.7")
+        );
+
+        let req = proxied_request(
+            "10.0.0.1:443",
+            (header::FORWARDED, "for=1.2.3.4, for=203.0.113.7"),
+        )
+        .to_srv_request();
+        assert_eq!(
+            req.connection_info().realip_remote_addr(),
+            Some("203.0.113.7")
+        );
+
+        // forwarded headers of untrusted peers are ignored
+        let req = proxied_request("203.0.113.7:5000", (X_FORWARDED_FOR, "10.0.0.3"))
+            .insert_header((X_FORWARDED_HOST, "spoofed.example"))
+            .to_http_request();
+        let info = req.connection_info();
+        assert_eq!(info.realip_remote_addr(), Some("203.0.113.7:5000"));
+        assert_eq!(info.host(), "localhost:8080");
+
+        let req = proxied_request(
+            "203.0.113.7:5000",
+            (header::FORWARDED, "for=10.0.0.3;proto=https"),
+        )
+        .to_http_request();
+        let info = req.connection_info();
+        assert_eq!(info.realip_remote_addr(), Some("203.0.113.7:5000"));
+        assert_eq!(info.scheme(), "http");
+    }
+
+    #[test]
+    fn test_trusted_proxies_cache() {
+        let req = proxied_request("203.0.113.7:5000", (X_FORWARDED_FOR, "10.0.0.3"))
+            .to_http_request();
+
+        let info = ConnectionInfo::get(req.head(), req.app_config());
+        assert_eq!(info.realip_remote_addr(), Some("10.0.0.3"));
+        drop(info);
+
+        // the untrusted info cached above does not leak into the trusted lookup
+        assert_eq!(
+            req.connection_info().realip_remote_addr(),
+            Some("203.0.113.7:5000")
+        );
+        assert_eq!(
+            ConnectionInfo::get(req.head(), req.app_config()).realip_remote_addr(),
+            Some("10.0.0.3")
+        );
+    }
+
+    #[test]
+    fn test_trusted_proxies_chain() {
+        let req = proxied_request(
+            "10.0.0.2:443",
+            (X_FORWARDED_FOR, "6.6.6.6, 198.51.100.9, 192.168.1.5"),
+        )
+        .to_http_request();
+        assert_eq!(
+            req.connection_info().realip_remote_addr(),
+            Some("198.51.100.9")
+        );
+
+        let req = proxied_request(
+            "10.0.0.2:443",
+            (
+                header::FORWARDED,
+                "for=6.6.6.6, for=\"[2001:db8::9]:4711\", for=192.168.1.5",
+            ),
+        )
+        .insert_header((X_FORWARDED_FOR, "6.6.6.6"))
+        .to_http_request();
+        assert_eq!(
+            req.connection_info().realip_remote_addr(),
+            Some("[2001:db8::9]:4711")
+        );
+
+        // a client inside the trusted ranges
+        let req = proxied_request("10.0.0.2:443", (X_FORWARDED_FOR, "10.1.2.3, 192.168.1.5"))
+            .to_http_request();
+        assert_eq!(req.connection_info().realip_remote_addr(), Some("10.1.2.3"));
+
+        // a hidden node between the proxies does not identify the client
+        let req = proxied_request(
+            "10.0.0.2:443",
+            (header::FORWARDED, "for=6.6.6.6, for=_hidden"),
+        )
+        .to_http_request();
+        assert_eq!(
+            req.connection_info().realip_remote_addr(),
+            Some("10.0.0.2:443")
+        );
+    }
+
+    #[test]
+    fn test_trusted_proxies_header_lines() {
+        // the proxy appended its own line instead of extending the spoofed one
+        let req = proxied_request("10.0.0.2:443", (X_FORWARDED_FOR, "1.2.3.4"))
+            .append_header((X_FORWARDED_FOR, "203.0.113.7"))
+            .to_http_request();
+        assert_eq!(
+            req.connection_info().realip_remote_addr(),
+            Some("203.0.113.7")
+        );
+
+        let req = proxied_request("10.0.0.2:443", (header::FORWARDED, "for=1.2.3.4"))
+            .append_header((header::FORWARDED, "for=203.0.113.7"))
+            .to_http_request();
+        assert_eq!(
+            req.connection_info().realip_remote_addr(),
+            Some("203.0.113.7")
+        );
+    }
+
+    #[test]
+    fn test_trusted_proxies_host_and_scheme() {
+        // the leftmost elements are sent by the client
+        let req = proxied_request(
+            "10.0.0.2:443",
+            (
+                header::FORWARDED,
+                "for=6.6.6.6;host=spoofed.example;proto=http, \
+                 for=203.0.113.7;host=rust-lang.org;proto=https",
+            ),
+        )
+        .to_http_request();
+        let info = req.connection_info();
+        assert_eq!(info.host(), "rust-lang.org");
+        assert_eq!(info.scheme(), "https");
+
+        let req = proxied_request("10.0.0.2:443", (X_FORWARDED_HOST, "spoofed.example"))
+            .append_header((X_FORWARDED_HOST, "rust-lang.org"))
+            .insert_header((X_FORWARDED_PROTO, "http, https"))
+            .to_http_request();
+        let info = req.connection_info();
+        assert_eq!(info.host(), "rust-lang.org");
+        assert_eq!(info.scheme(), "https");
+
+        // without trusted proxies, the first element is used
+        let req = TestRequest::default()
+            .insert_header((X_FORWARDED_HOST, "spoofed.example, rust-lang.org"))
+            .to_http_request();
+        assert_eq!(req.connection_info().host(), "spoofed.example");
+    }
+
+    #[test]
+    fn test_ip_cidr() {
+        let cidr = "10.0.0.0/8".parse::<IpCidr>().unwrap();
+        assert!(cidr.contains("10.200.3.4".parse().unwrap()));
+        assert!(cidr.contains("::ffff:10.0.0.1".parse().unwrap()));
+        assert!(!cidr.contains("11.0.0.1".parse().unwrap()));
+        assert!(!cidr.contains("::a00:1".parse().unwrap()));
+        assert_eq!(cidr.to_string(), "10.0.0.0/8");
+
+        let cidr = "2001:db8::/32".parse::<IpCidr>().unwrap();
+        assert!(cidr.contains("2001:db8:1::1".parse().unwrap()));
+        assert!(!cidr.contains("2001:db9::1".parse().unwrap()));
+
+        let cidr = "192.168.1.5".parse::<IpCidr>().unwrap();
+        assert_eq!(cidr.prefix_len(), 32);
+        assert!(cidr.contains("192.168.1.5".parse().unwrap()));
+        assert!(!cidr.contains("192.168.1.6".parse().unwrap()));
+
+        let cidr = "0.0.0.0/0".parse::<IpCidr>().unwrap();
+        assert!(cidr.contains("203.0.113.7".parse().unwrap()));
+
+        assert!("10.0.0.0/33".parse::<IpCidr>().is_err());
+        assert!("10.0.0/8".parse::<IpCidr>().is_err());
+        assert!("10.0.0.0/".parse::<IpCidr>().is_err());
+    }
 }
@@ -60,11 +60,17 @@
 //!
 //! ## Crate Features
 //!
+//! * `cbor` - CBOR extractor and responder, see [`web::Cbor`]
 //! * `compress` - content encoding compression support (enabled by default)
 //! * `cookies` - cookies support (enabled by default)
+//! * `json-error-path` - path of the failing value in JSON deserialize errors
+//! * `msgpack` - MessagePack extractor and responder, see [`web::MsgPack`]
 //! * `openssl` - HTTPS support via `openssl` crate, supports `HTTP/2`
+//! * `protobuf` - Protocol Buffers extractor and responder, see [`web::ProtoBuf`]
 //! * `rustls` - HTTPS support via `rustls` crate, supports `HTTP/2`
 //! * `secure-cookies` - secure cookies support
+//! * `tracing` - request and connection spans for the `tracing` crate, see
+//!   [`middleware::TracingLogger`]
 
 #![deny(rust_2018_idioms, nonstandard_style)]
 #![allow(clippy::needless_doctest_main, clippy::type_complexity)]
@@ -75,16 +81,22 @@
 extern crate tls_openssl as openssl;
 #[cfg(feature = "rustls")]
 extern crate tls_rustls as rustls;
+#[cfg(feature = "tracing")]
+extern crate tracing_rs as tracing;
 
 mod app;
 mod app_service;
+mod blocking;
 mod config;
 mod data;
 pub mod error;
 mod extract;
 pub mod guard;
+mod handle;
 mod handler;
+mod health;
 mod info;
+mod lifecycle;
 pub mod middleware;
 mod request;
 mod request_data;
@@ -92,10 +104,13 @@ mod resource;
 mod responder;
 mod rmap;
 mod route;
+pub mod rt;
 mod scope;
 mod server;
 mod service;
 pub mod test;
+#[cfg(any(feature = "openssl", feature = "rustls"))]
+pub mod tls;
 pub(crate) mod types;
 pub mod web;
 
@@ -103,14 +118,13 @@ pub mod web;
 pub use actix_http::cookie;
 pub use actix_http::Response as HttpResponse;
 pub use actix_http::{body, http, Error, HttpMessage, ResponseError, Result};
-pub use actix_rt as rt;
 pub use actix_web_codegen::*;
 
 pub use crate::app::App;
 pub use crate::extract::FromRequest;
 pub use crate::request::HttpRequest;
 pub use crate::resource::Resource;
-pub use crate::responder::Responder;
+pub use crate::responder::{CustomResponder, Responder};
 pub use crate::route::Route;
 pub use crate::scope::Scope;
 pub use crate::server::HttpServer;
@@ -129,9 +143,10 @@ pub mod dev {
     //! ```
 
     pub use crate::config::{AppConfig, AppService};
+    pub use crate::handle::ServerHandle;
     #[doc(hidden)]
     pub use crate::handler::Handler;
-    pub use crate::info::ConnectionInfo;
+    pub use crate::info::{ConnectionInfo, IpCidr, IpCidrParseError, TrustedProxies};
     pub use crate::rmap::ResourceMap;
     pub use crate::service::{HttpServiceFactory, ServiceRequest, ServiceResponse, WebService};
 
@@ -139,11 +154,16 @@ pub mod dev {
     pub use crate::types::json::JsonBody;
     pub use crate::types::readlines::Readlines;
 
-    pub use actix_http::body::{Body, BodySize, MessageBody, ResponseBody, SizedStream};
+    pub use actix_http::body::{
+        Body, BodySize, BoxBody, EitherBody, MessageBody, ResponseBody, SizedStream,
+    };
     #[cfg(feature = "compress")]
     pub use actix_http::encoding::Decoder as Decompress;
     pub use actix_http::ResponseBuilder as HttpResponseBuilder;
-    pub use actix_http::{Extensions, Payload, PayloadStream, RequestHead, ResponseHead};
+    pub use actix_http::{
+        ConnectionStats, Extensions, GracefulShutdown, Payload, PayloadStream, RequestHead,
+        ResponseHead,
+    };
     pub use actix_router::{Path, ResourceDef, ResourcePath, Url};
     pub use actix_server::Server;
     pub use actix_service::{Service, Transform};
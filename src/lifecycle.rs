@@ -0,0 +1,144 @@
+//! Worker lifecycle hooks, see [`HttpServer::on_worker_start`] and
+//! [`HttpServer::on_worker_stop`].
+//!
+//! [`HttpServer::on_worker_start`]: crate::HttpServer::on_worker_start
+//! [`HttpServer::on_worker_stop`]: crate::HttpServer::on_worker_stop
+
+use std::{
+    cell::Cell,
+    future::Future,
+    rc::Rc,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    task::Poll,
+};
+
+use actix_http::{Extensions, GracefulShutdown};
+use actix_utils::task::LocalWaker;
+use futures_core::future::LocalBoxFuture;
+use futures_util::future::poll_fn;
+use tokio::sync::Notify;
+
+pub(crate) type WorkerHook =
+    Arc<dyn Fn(Rc<Extensions>) -> LocalBoxFuture<'static, ()> + Send + Sync>;
+
+/// Hooks registered on a server, shared with the apps of all its workers.
+#[derive(Clone, Default)]
+pub(crate) struct WorkerHooks {
+    on_start: Vec<WorkerHook>,
+    on_stop: Vec<WorkerHook>,
+    stopped: Arc<Stopped>,
+    pub(crate) shutdown: GracefulShutdown,
+}
+
+#[derive(Default)]
+struct Stopped {
+    count: AtomicUsize,
+    notify: Notify,
+}
+
+impl WorkerHooks {
+    pub(crate) fn on_start<H, Fut>(&mut self, hook: H)
+    where
+        H: Fn(Rc<Extensions>) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ()> + 'static,
+    {
+        self.on_start
+            .push(Arc::new(move |data| Box::pin(hook(data))));
+    }
+
+    pub(crate) fn on_stop<H, Fut>(&mut self, hook: H)
+    where
+        H: Fn(Rc<Extensions>) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ()> + 'static,
+    {
+        self.on_stop
+            .push(Arc::new(move |data| Box::pin(hook(data))));
+    }
+
+    pub(crate) fn has_stop_hooks(&self) -> bool {
+        !self.on_stop.is_empty()
+    }
+
+    /// Runs the start hooks of an app instance.
+    pub(crate) async fn start(&self, app_data: &Rc<Extensions>) {
+        for hook in &self.on_start {
+            hook(Rc::clone(app_data)).await;
+        }
+    }
+
+    /// Spawns a task on the current worker running the stop hooks of an app instance once
+    /// shutdown is triggered and its in-flight requests have finished.
+    pub(crate) fn spawn_stop(&self, app_data: Rc<Extensions>, in_flight: Rc<InFlight>) {
+        let shutdown = self.shutdown.clone();
+        let hooks = self.on_stop.clone();
+        let stopped = Arc::clone(&self.stopped);
+
+        actix_rt::spawn(async move {
+            shutdown.wait().await;
+            in_flight.drained().await;
+
+            for hook in &hooks {
+                hook(Rc::clone(&app_data)).await;
+            }
+
+            stopped.count.fetch_add(1, Ordering::AcqRel);
+            stopped.notify.notify_waiters();
+        });
+    }
+
+    /// Resolves once the stop hooks of `expected` app instances have finished.
+    pub(crate) async fn stopped(&self, expected: usize) {
+        loop {
+            let notified = self.stopped.notify.notified();
+
+            if self.stopped.count.load(Ordering::Acquire) >= expected {
+                return;
+            }
+
+            notified.await;
+        }
+    }
+}
+
+/// Number of requests being handled by an app instance.
+#[derive(Default)]
+pub(crate) struct InFlight {
+    count: Cell<usize>,
+    waker: LocalWaker,
+}
+
+impl InFlight {
+    pub(crate) fn enter(self: &Rc<Self>) -> InFlightGuard {
+        self.count.set(self.count.get() + 1);
+        InFlightGuard(Rc::clone(self))
+    }
+
+    async fn drained(&self) {
+        poll_fn(|cx| {
+            if self.count.get() == 0 {
+                Poll::Ready(())
+            } else {
+                self.waker.register(cx.waker());
+                Poll::Pending
+            }
+        })
+        .await
+    }
+}
+
+/// Marks a request as finished when dropped.
+pub(crate) struct InFlightGuard(Rc<InFlight>);
+
+impl Drop for InFlightGuard {
+    fn drop(&mut self) {
+        let count = self.0.count.get() - 1;
+        self.0.count.set(count);
+
+        if count == 0 {
+            self.0.waker.wake();
+        }
+    }
+}
@@ -0,0 +1,1107 @@
+//! For middleware documentation, see [`Cache`].
+
+use std::{
+    collections::BTreeMap,
+    convert::TryFrom,
+    fmt,
+    future::Future,
+    marker::PhantomData,
+    pin::Pin,
+    rc::Rc,
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+    time::{Duration, Instant},
+};
+
+use actix_http::http::Error as HttpError;
+use ahash::AHashMap;
+use bytes::{Bytes, BytesMut};
+use futures_util::{
+    future::{ready, Either, Ready},
+    ready,
+};
+
+use crate::{
+    dev::{
+        BodySize, EitherBody, Extensions, MessageBody, Payload, ResponseBody, Service,
+        Transform,
+    },
+    error::ErrorInternalServerError,
+    http::{
+        header::{
+            HeaderMap, HeaderName, HeaderValue, VaryHeaders, AUTHORIZATION, CACHE_CONTROL,
+            COOKIE, RANGE, SET_COOKIE, VARY,
+        },
+        Method, StatusCode,
+    },
+    service::{ServiceRequest, ServiceResponse},
+    Error, FromRequest, HttpRequest, HttpResponse,
+};
+
+/// Default time a response stays in the cache.
+const DEFAULT_TTL: Duration = Duration::from_secs(60);
+
+/// Default size limit of cached bodies.
+const DEFAULT_MAX_BODY_SIZE: usize = 256 * 1024;
+
+/// Default number of entries kept by the default [`MemoryStore`].
+const DEFAULT_CAPACITY: usize = 1024;
+
+/// Number of resources whose response `Vary` header names are remembered.
+const MAX_VARIANTS: usize = 4096;
+
+/// Middleware caching successful `GET` responses on the server.
+///
+/// Responses are cached by method, host, path, query string, the request headers selected with
+/// [`vary`](Self::vary) and the request headers the response varies on, from its `Vary` header
+/// or its [`VaryHeaders`] extension (as recorded by [`Compress`](super::Compress)). A cached
+/// response is served without calling the wrapped service; it has the status, headers and body
+/// of the original response.
+///
+/// A response is cached when:
+/// - the request method is `GET`;
+/// - the request has no `Authorization`, `Cookie` or `Range` header;
+/// - the status is `200 OK` or `203 Non-Authoritative Information`, so that partial responses
+///   are never cached;
+/// - its `Cache-Control` header contains neither `no-store` nor `private`;
+/// - it has no `Set-Cookie` header and does not vary on `*`;
+/// - its body, including streamed bodies, is no larger than
+///   [`max_body_size`](Self::max_body_size).
+///
+/// Requests with an `Authorization`, `Cookie` or `Range` header are never served from the cache.
+///
+/// The body is collected while it is sent to the client, so caching a response does not delay it.
+/// Bodies that turn out to be too large, or that fail, are not cached.
+///
+/// Entries are kept in a [`CacheStore`], by default a bounded in-memory LRU [`MemoryStore`]
+/// shared by every clone of the middleware. Handlers and other services wrapped by the middleware
+/// can purge entries with the [`Invalidate`] handle, which is available as app data.
+///
+/// Wrap individual resources or scopes to cache only some routes, using
+/// [`Compat`](super::Compat) since the middleware changes the response body type.
+///
+/// # Examples
+/// ```rust
+/// use std::time::Duration;
+/// use actix_web::middleware::{Cache, Compat, Invalidate};
+/// use actix_web::{web, App, HttpResponse, HttpServer};
+///
+/// # fn run() -> std::io::Result<()> {
+/// let cache = Cache::new().ttl(Duration::from_secs(30)).vary("accept-language");
+///
+/// HttpServer::new(move || {
+///     App::new()
+///         .service(
+///             web::resource("/articles")
+///                 .wrap(Compat::new(cache.clone()))
+///                 .route(web::get().to(|| HttpResponse::Ok().body("articles")))
+///                 .route(web::post().to(|invalidate: Invalidate| {
+///                     invalidate.path("/articles");
+///                     HttpResponse::Created()
+///                 })),
+///         )
+/// });
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct Cache {
+    store: Arc<dyn CacheStore>,
+    ttl: Duration,
+    max_body_size: usize,
+    vary: Vec<HeaderName>,
+    variants: Arc<Mutex<AHashMap<CacheKey, Vec<HeaderName>>>>,
+}
+
+impl Default for Cache {
+    fn default() -> Self {
+        Cache::with_store(MemoryStore::new(DEFAULT_CAPACITY))
+    }
+}
+
+impl Cache {
+    /// Constructs a `Cache` middleware using a [`MemoryStore`] of 1024 entries.
+    pub fn new() -> Self {
+        Cache::default()
+    }
+
+    /// Constructs a `Cache` middleware using `store` for cached responses.
+    pub fn with_store(store: impl CacheStore + 'static) -> Self {
+        Cache {
+            store: Arc::new(store),
+            ttl: DEFAULT_TTL,
+            max_body_size: DEFAULT_MAX_BODY_SIZE,
+            vary: Vec::new(),
+            variants: Arc::new(Mutex::new(AHashMap::default())),
+        }
+    }
+
+    /// Sets the time responses stay in the cache.
+    ///
+    /// Default is 60 seconds.
+    pub fn ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    /// Sets the size limit, in bytes, of cached response bodies.
+    ///
+    /// Larger responses are passed through without being cached. Default is 256kB.
+    pub fn max_body_size(mut self, size: usize) -> Self {
+        self.max_body_size = size;
+        self
+    }
+
+    /// Adds a request header to the cache key, so that requests with different values of this
+    /// header are cached separately.
+    ///
+    /// # Panics
+    /// Panics if `name` is not a valid header name.
+    pub fn vary<K>(mut self, name: K) -> Self
+    where
+        HeaderName: TryFrom<K>,
+        <HeaderName as TryFrom<K>>::Error: Into<HttpError>,
+    {
+        match HeaderName::try_from(name) {
+            Ok(name) => self.vary.push(name),
+            Err(_) => panic!("Can not create header name"),
+        }
+        self
+    }
+
+    /// Returns a handle for purging entries of this cache, for use outside of wrapped services.
+    pub fn invalidator(&self) -> Invalidate {
+        Invalidate {
+            store: Arc::clone(&self.store),
+        }
+    }
+}
+
+impl fmt::Debug for Cache {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Cache")
+            .field("ttl", &self.ttl)
+            .field("max_body_size", &self.max_body_size)
+            .field("vary", &self.vary)
+            .finish()
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for Cache
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    B: MessageBody,
+{
+    type Response = ServiceResponse<EitherBody<CacheBody<B>, Bytes>>;
+    type Error = Error;
+    type Transform = CacheMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        let mut data = Extensions::new();
+        data.insert(self.invalidator());
+
+        ready(Ok(CacheMiddleware {
+            service,
+            inner: Rc::new(Inner {
+                store: Arc::clone(&self.store),
+                ttl: self.ttl,
+                max_body_size: self.max_body_size,
+                vary: self.vary.clone(),
+                variants: Arc::clone(&self.variants),
+                data: Rc::new(data),
+            }),
+        }))
+    }
+}
+
+struct Inner {
+    store: Arc<dyn CacheStore>,
+    ttl: Duration,
+    max_body_size: usize,
+    vary: Vec<HeaderName>,
+
+    /// Names from the `Vary` header of the last cached response, by key without those headers.
+    variants: Arc<Mutex<AHashMap<CacheKey, Vec<HeaderName>>>>,
+
+    /// App data container holding the [`Invalidate`] handle.
+    data: Rc<Extensions>,
+}
+
+pub struct CacheMiddleware<S> {
+    service: S,
+    inner: Rc<Inner>,
+}
+
+impl<S, B> Service<ServiceRequest> for CacheMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    B: MessageBody,
+{
+    type Response = ServiceResponse<EitherBody<CacheBody<B>, Bytes>>;
+    type Error = Error;
+    type Future = Either<Ready<Result<Self::Response, Error>>, CacheFuture<S, B>>;
+
+    actix_service::forward_ready!(service);
+
+    fn call(&self, mut req: ServiceRequest) -> Self::Future {
+        req.add_data_container(Rc::clone(&self.inner.data));
+
+        let key = if req.method() == Method::GET
+            && !req.headers().contains_key(AUTHORIZATION)
+            && !req.headers().contains_key(COOKIE)
+            && !req.headers().contains_key(RANGE)
+        {
+            Some(CacheKey::new(req.request(), &self.inner.vary, &[]))
+        } else {
+            None
+        };
+
+        let cached = key.as_ref().and_then(|key| {
+            let names = self.inner.variants.lock().unwrap().get(key).cloned();
+
+            match names {
+                Some(names) => {
+                    let key = CacheKey::new(req.request(), &self.inner.vary, &names);
+                    let cached = self.inner.store.get(&key)?;
+                    Some((cached, names))
+                }
+                None => Some((self.inner.store.get(key)?, Vec::new())),
+            }
+        });
+
+        if let Some((cached, names)) = cached {
+            let mut res = HttpResponse::with_body(cached.status, cached.body);
+            *res.headers_mut() = cached.headers;
+
+            if !names.is_empty() {
+                let mut vary = VaryHeaders::new();
+                for name in names {
+                    vary.add(name);
+                }
+                res.extensions_mut().insert(vary);
+            }
+
+            return Either::Left(ready(Ok(req.into_response(res).map_into_right_body())));
+        }
+
+        Either::Right(CacheFuture {
+            fut: self.service.call(req),
+            key,
+            inner: Rc::clone(&self.inner),
+            _body: PhantomData,
+        })
+    }
+}
+
+#[pin_project::pin_project]
+pub struct CacheFuture<S: Service<ServiceRequest>, B> {
+    #[pin]
+    fut: S::Future,
+    key: Option<CacheKey>,
+    inner: Rc<Inner>,
+    _body: PhantomData<B>,
+}
+
+impl<S, B> Future for CacheFuture<S, B>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    B: MessageBody,
+{
+    type Output = Result<ServiceResponse<EitherBody<CacheBody<B>, Bytes>>, Error>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        let res = ready!(this.fut.poll(cx))?;
+
+        let max_body_size = this.inner.max_body_size as u64;
+        let inner = Rc::clone(this.inner);
+        let key = this.key.take().and_then(|key| {
+            let cacheable = matches!(
+                res.status(),
+                StatusCode::OK | StatusCode::NON_AUTHORITATIVE_INFORMATION
+            ) && !forbids_caching(res.headers())
+                && match res.response().body().size() {
+                    BodySize::Sized(size) => size <= max_body_size,
+                    _ => true,
+                };
+
+            if !cacheable {
+                return None;
+            }
+
+            let names = vary_names(res.response(), &inner.vary)?;
+
+            let mut variants = inner.variants.lock().unwrap();
+            if names.is_empty() {
+                variants.remove(&key);
+            } else {
+                if variants.len() >= MAX_VARIANTS && !variants.contains_key(&key) {
+                    variants.clear();
+                }
+                variants.insert(key, names.clone());
+            }
+            drop(variants);
+
+            Some(CacheKey::new(res.request(), &inner.vary, &names))
+        });
+
+        let res = res.map_body(move |head, body| {
+            let collect = key.map(|key| Collect {
+                key,
+                status: head.status,
+                headers: head.headers.clone(),
+                buf: BytesMut::new(),
+                inner,
+            });
+
+            ResponseBody::Body(EitherBody::Left(CacheBody { body, collect }))
+        });
+
+        Poll::Ready(Ok(res))
+    }
+}
+
+/// Returns true if the response must not be stored in a shared cache.
+fn forbids_caching(headers: &HeaderMap) -> bool {
+    headers.contains_key(SET_COOKIE)
+        || headers
+            .get_all(CACHE_CONTROL)
+            .filter_map(|val| val.to_str().ok())
+            .flat_map(|val| val.split(','))
+            .map(str::trim)
+            .any(|directive| {
+                directive.eq_ignore_ascii_case("no-store")
+                    || directive.eq_ignore_ascii_case("private")
+            })
+}
+
+/// Returns the request headers the response varies on that are not already part of the cache
+/// key, or `None` if the response varies on `*` or has an invalid `Vary` header.
+fn vary_names<B>(res: &HttpResponse<B>, configured: &[HeaderName]) -> Option<Vec<HeaderName>> {
+    let mut names = Vec::new();
+
+    if let Some(vary) = res.extensions().get::<VaryHeaders>() {
+        if vary.is_any() {
+            return None;
+        }
+
+        for name in vary.names() {
+            if !configured.contains(name) && !names.contains(name) {
+                names.push(name.clone());
+            }
+        }
+    }
+
+    for val in res.headers().get_all(VARY) {
+        for name in val.to_str().ok()?.split(',').map(str::trim) {
+            if name.is_empty() {
+                continue;
+            }
+
+            if name == "*" {
+                return None;
+            }
+
+            let name = HeaderName::try_from(name).ok()?;
+            if !configured.contains(&name) && !names.contains(&name) {
+                names.push(name);
+            }
+        }
+    }
+
+    Some(names)
+}
+
+/// Response body of the [`Cache`] middleware for responses not served from the cache.
+///
+/// Cacheable bodies are collected while being streamed and stored once complete.
+#[pin_project::pin_project]
+pub struct CacheBody<B> {
+    #[pin]
+    body: ResponseBody<B>,
+    collect: Option<Collect>,
+}
+
+struct Collect {
+    key: CacheKey,
+    status: StatusCode,
+    headers: HeaderMap,
+    buf: BytesMut,
+    inner: Rc<Inner>,
+}
+
+impl<B: MessageBody> MessageBody for CacheBody<B> {
+    fn size(&self) -> BodySize {
+        self.body.size()
+    }
+
+    fn poll_next(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Bytes, Error>>> {
+        let this = self.project();
+
+        match ready!(this.body.poll_next(cx)) {
+            Some(Ok(chunk)) => {
+                if let Some(ref mut collect) = this.collect {
+                    if collect.buf.len() + chunk.len() > collect.inner.max_body_size {
+                        *this.collect = None;
+                    } else {
+                        collect.buf.extend_from_slice(&chunk);
+                    }
+                }
+
+                Poll::Ready(Some(Ok(chunk)))
+            }
+
+            Some(Err(err)) => {
+                *this.collect = None;
+                Poll::Ready(Some(Err(err)))
+            }
+
+            None => {
+                if let Some(collect) = this.collect.take() {
+                    let res = CachedResponse {
+                        status: collect.status,
+                        headers: collect.headers,
+                        body: collect.buf.freeze(),
+                    };
+
+                    collect.inner.store.put(collect.key, res, collect.inner.ttl);
+                }
+
+                Poll::Ready(None)
+            }
+        }
+    }
+}
+
+/// Key of a cached response.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CacheKey {
+    method: Method,
+    host: String,
+    path: String,
+    query: String,
+    vary: Vec<(HeaderName, Option<HeaderValue>)>,
+}
+
+impl CacheKey {
+    fn new(req: &HttpRequest, vary: &[HeaderName], response_vary: &[HeaderName]) -> Self {
+        CacheKey {
+            method: req.method().clone(),
+            host: req.connection_info().host().to_owned(),
+            path: req.path().to_owned(),
+            query: req.query_string().to_owned(),
+            vary: vary
+                .iter()
+                .chain(response_vary)
+                .map(|name| (name.clone(), req.headers().get(name).cloned()))
+                .collect(),
+        }
+    }
+
+    /// Method of the cached request.
+    pub fn method(&self) -> &Method {
+        &self.method
+    }
+
+    /// Host of the cached request, as resolved by [`ConnectionInfo`](crate::dev::ConnectionInfo).
+    pub fn host(&self) -> &str {
+        &self.host
+    }
+
+    /// Path of the cached request.
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// Query string of the cached request.
+    pub fn query_string(&self) -> &str {
+        &self.query
+    }
+}
+
+/// A response stored by the [`Cache`] middleware.
+#[derive(Debug, Clone)]
+pub struct CachedResponse {
+    pub status: StatusCode,
+    pub headers: HeaderMap,
+    pub body: Bytes,
+}
+
+/// Storage of cached responses for the [`Cache`] middleware.
+///
+/// A store is shared by all workers, so it must be thread safe.
+pub trait CacheStore: Send + Sync {
+    /// Returns the response cached for `key`, unless it has expired.
+    fn get(&self, key: &CacheKey) -> Option<CachedResponse>;
+
+    /// Stores the response for `key` for `ttl`, replacing any previous entry.
+    fn put(&self, key: CacheKey, res: CachedResponse, ttl: Duration);
+
+    /// Removes all responses cached for `path`, whatever their query string and varying headers.
+    fn invalidate(&self, path: &str);
+
+    /// Removes all cached responses.
+    fn clear(&self);
+}
+
+/// Bounded in-memory [`CacheStore`], evicting the least recently used entries.
+pub struct MemoryStore {
+    capacity: usize,
+    lru: Mutex<Lru>,
+}
+
+#[derive(Default)]
+struct Lru {
+    entries: AHashMap<CacheKey, Entry>,
+
+    /// Keys by last use; the first key is the least recently used.
+    order: BTreeMap<u64, CacheKey>,
+    tick: u64,
+}
+
+struct Entry {
+    res: CachedResponse,
+    expires: Instant,
+    used: u64,
+}
+
+impl MemoryStore {
+    /// Constructs a store holding at most `capacity` responses.
+    ///
+    /// # Panics
+    /// Panics if `capacity` is zero.
+    pub fn new(capacity: usize) -> Self {
+        assert!(capacity > 0, "Cache capacity must be greater than zero");
+
+        MemoryStore {
+            capacity,
+            lru: Mutex::new(Lru::default()),
+        }
+    }
+
+    /// Returns the number of stored responses, including expired ones not yet removed.
+    pub fn len(&self) -> usize {
+        self.lru.lock().unwrap().entries.len()
+    }
+
+    /// Returns true if no responses are stored.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl Lru {
+    fn remove(&mut self, key: &CacheKey) {
+        if let Some(entry) = self.entries.remove(key) {
+            self.order.remove(&entry.used);
+        }
+    }
+}
+
+impl CacheStore for MemoryStore {
+    fn get(&self, key: &CacheKey) -> Option<CachedResponse> {
+        let mut lru = self.lru.lock().unwrap();
+        let lru = &mut *lru;
+
+        let entry = lru.entries.get_mut(key)?;
+
+        if entry.expires <= Instant::now() {
+            lru.remove(key);
+            return None;
+        }
+
+        lru.tick += 1;
+        let key = lru.order.remove(&entry.used).unwrap();
+        lru.order.insert(lru.tick, key);
+        entry.used = lru.tick;
+
+        Some(entry.res.clone())
+    }
+
+    fn put(&self, key: CacheKey, res: CachedResponse, ttl: Duration) {
+        let mut lru = self.lru.lock().unwrap();
+
+        lru.remove(&key);
+
+        while lru.entries.len() >= self.capacity {
+            let oldest = lru.order.keys().next().copied().unwrap();
+            let key = lru.order.remove(&oldest).unwrap();
+            lru.entries.remove(&key);
+        }
+
+        lru.tick += 1;
+        let used = lru.tick;
+
+        lru.order.insert(used, key.clone());
+        lru.entries.insert(
+            key,
+            Entry {
+                res,
+                expires: Instant::now() + ttl,
+                used,
+            },
+        );
+    }
+
+    fn invalidate(&self, path: &str) {
+        let mut lru = self.lru.lock().unwrap();
+
+        let keys = lru
+            .entries
+            .keys()
+            .filter(|key| key.path == path)
+            .cloned()
+            .collect::<Vec<_>>();
+
+        for key in &keys {
+            lru.remove(key);
+        }
+    }
+
+    fn clear(&self) {
+        let mut lru = self.lru.lock().unwrap();
+        lru.entries.clear();
+        lru.order.clear();
+    }
+}
+
+impl fmt::Debug for MemoryStore {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MemoryStore")
+            .field("capacity", &self.capacity)
+            .field("len", &self.len())
+            .finish()
+    }
+}
+
+/// Handle for purging responses cached by a [`Cache`] middleware.
+///
+/// Services wrapped by the middleware can extract it, as it is added to their app data. Outside
+/// of them, use [`Cache::invalidator`].
+#[derive(Clone)]
+pub struct Invalidate {
+    store: Arc<dyn CacheStore>,
+}
+
+impl Invalidate {
+    /// Removes all responses cached for `path`.
+    pub fn path(&self, path: &str) {
+        self.store.invalidate(path);
+    }
+
+    /// Removes all cached responses.
+    pub fn all(&self) {
+        self.store.clear();
+    }
+}
+
+impl fmt::Debug for Invalidate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Invalidate").finish()
+    }
+}
+
+impl FromRequest for Invalidate {
+    type Config = ();
+    type Error = Error;
+    type Future = Ready<Result<Self, Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        ready(match req.app_data::<Invalidate>() {
+            Some(invalidate) => Ok(invalidate.clone()),
+            None => Err(ErrorInternalServerError(
+                "Invalidate is only available to services wrapped by the Cache middleware",
+            )),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use actix_rt::time::sleep;
+    use futures_util::stream;
+
+    use super::*;
+    use crate::{
+        body::BodyStream,
+        http::header::{ACCEPT_ENCODING, CONTENT_ENCODING, CONTENT_TYPE, HOST},
+        middleware::Compress,
+        test::{call_service, init_service, read_body, TestRequest},
+        web, App,
+    };
+
+    fn counter() -> (Arc<AtomicUsize>, Arc<AtomicUsize>) {
+        let hits = Arc::new(AtomicUsize::new(0));
+        (Arc::clone(&hits), hits)
+    }
+
+    #[actix_rt::test]
+    async fn test_cache_hit() {
+        let (hits, handler_hits) = counter();
+
+        let srv = init_service(
+            App::new().wrap(Cache::new()).service(
+                web::resource("/")
+                    .route(web::get().to(move || {
+                        let n = handler_hits.fetch_add(1, Ordering::SeqCst) + 1;
+                        HttpResponse::NonAuthoritativeInformation()
+                            .insert_header((CONTENT_TYPE, "text/plain"))
+                            .body(format!("hit {}", n))
+                    }))
+                    .route(web::post().to(|invalidate: Invalidate| {
+                        invalidate.path("/");
+                        HttpResponse::NoContent()
+                    })),
+            ),
+        )
+        .await;
+
+        for _ in 0..2 {
+            let req = TestRequest::get().uri("/").to_request();
+            let res = call_service(&srv, req).await;
+            assert_eq!(res.status(), StatusCode::NON_AUTHORITATIVE_INFORMATION);
+            assert_eq!(res.headers().get(CONTENT_TYPE).unwrap(), "text/plain");
+            assert_eq!(read_body(res).await, "hit 1");
+        }
+        assert_eq!(hits.load(Ordering::SeqCst), 1);
+
+        // query strings are cached separately
+        let req = TestRequest::get().uri("/?page=2").to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(read_body(res).await, "hit 2");
+
+        let req = TestRequest::post().uri("/").to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::NO_CONTENT);
+
+        let req = TestRequest::get().uri("/").to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(read_body(res).await, "hit 3");
+        assert_eq!(hits.load(Ordering::SeqCst), 3);
+    }
+
+    #[actix_rt::test]
+    async fn test_ttl_expiry() {
+        let (hits, handler_hits) = counter();
+
+        let srv = init_service(
+            App::new()
+                .wrap(Cache::new().ttl(Duration::from_millis(50)))
+                .route(
+                    "/",
+                    web::get().to(move || {
+                        handler_hits.fetch_add(1, Ordering::SeqCst);
+                        HttpResponse::Ok()
+                    }),
+                ),
+        )
+        .await;
+
+        for _ in 0..2 {
+            let req = TestRequest::get().uri("/").to_request();
+            let res = call_service(&srv, req).await;
+            read_body(res).await;
+        }
+        assert_eq!(hits.load(Ordering::SeqCst), 1);
+
+        sleep(Duration::from_millis(100)).await;
+
+        let req = TestRequest::get().uri("/").to_request();
+        let res = call_service(&srv, req).await;
+        read_body(res).await;
+        assert_eq!(hits.load(Ordering::SeqCst), 2);
+    }
+
+    #[actix_rt::test]
+    async fn test_large_stream_bypass() {
+        let (hits, handler_hits) = counter();
+
+        let srv = init_service(App::new().wrap(Cache::new().max_body_size(16)).route(
+            "/",
+            web::get().to(move || {
+                handler_hits.fetch_add(1, Ordering::SeqCst);
+
+                let chunks = (0..4).map(|_| Ok::<_, Error>(Bytes::from_static(b"0123456789")));
+                HttpResponse::Ok().body(BodyStream::new(stream::iter(chunks)))
+            }),
+        ))
+        .await;
+
+        for _ in 0..2 {
+            let req = TestRequest::get().uri("/").to_request();
+            let res = call_service(&srv, req).await;
+            assert_eq!(read_body(res).await.len(), 40);
+        }
+        assert_eq!(hits.load(Ordering::SeqCst), 2);
+    }
+
+    #[actix_rt::test]
+    async fn test_not_cached() {
+        let (hits, handler_hits) = counter();
+
+        let srv = init_service(App::new().wrap(Cache::new()).route(
+            "/{kind}",
+            web::get().to(move |kind: web::Path<String>| {
+                handler_hits.fetch_add(1, Ordering::SeqCst);
+
+                let mut res = HttpResponse::Ok();
+                match kind.as_str() {
+                    "no-store" => res.insert_header((CACHE_CONTROL, "no-store")),
+                    "private" => res.insert_header((CACHE_CONTROL, "max-age=60, Private")),
+                    "cookie" => res.insert_header((SET_COOKIE, "id=1")),
+                    "vary" => res.insert_header((VARY, "accept-language, *")),
+                    _ => res.status(StatusCode::NOT_FOUND),
+                };
+                res.finish()
+            }),
+        ))
+        .await;
+
+        for kind in &["no-store", "private", "cookie", "vary", "missing"] {
+            for _ in 0..2 {
+                let req = TestRequest::get().uri(&format!("/{}", kind)).to_request();
+                let res = call_service(&srv, req).await;
+                read_body(res).await;
+            }
+        }
+        assert_eq!(hits.load(Ordering::SeqCst), 10);
+    }
+
+    #[actix_rt::test]
+    async fn test_range_request() {
+        let (hits, handler_hits) = counter();
+
+        let srv = init_service(App::new().wrap(Cache::new()).route(
+            "/",
+            web::get().to(move |req: HttpRequest| {
+                handler_hits.fetch_add(1, Ordering::SeqCst);
+
+                if req.headers().contains_key(RANGE) {
+                    HttpResponse::PartialContent().body("01")
+                } else {
+                    HttpResponse::Ok().body("0123")
+                }
+            }),
+        ))
+        .await;
+
+        let req = TestRequest::get()
+            .uri("/")
+            .insert_header((RANGE, "bytes=0-1"))
+            .to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::PARTIAL_CONTENT);
+        assert_eq!(read_body(res).await, "01");
+
+        // the partial response was not cached
+        for _ in 0..2 {
+            let req = TestRequest::get().uri("/").to_request();
+            let res = call_service(&srv, req).await;
+            assert_eq!(res.status(), StatusCode::OK);
+            assert_eq!(read_body(res).await, "0123");
+        }
+        assert_eq!(hits.load(Ordering::SeqCst), 2);
+
+        // and ranged requests are not served from the cache
+        let req = TestRequest::get()
+            .uri("/")
+            .insert_header((RANGE, "bytes=0-1"))
+            .to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::PARTIAL_CONTENT);
+        assert_eq!(hits.load(Ordering::SeqCst), 3);
+    }
+
+    #[actix_rt::test]
+    async fn test_virtual_hosts() {
+        let (hits, handler_hits) = counter();
+
+        let srv = init_service(App::new().wrap(Cache::new()).route(
+            "/",
+            web::get().to(move |req: HttpRequest| {
+                handler_hits.fetch_add(1, Ordering::SeqCst);
+                HttpResponse::Ok().body(req.connection_info().host().to_owned())
+            }),
+        ))
+        .await;
+
+        for _ in 0..2 {
+            for host in &["a.example", "b.example"] {
+                let req = TestRequest::get()
+                    .uri("/")
+                    .insert_header((HOST, *host))
+                    .to_request();
+                let res = call_service(&srv, req).await;
+                assert_eq!(read_body(res).await, *host);
+            }
+        }
+        assert_eq!(hits.load(Ordering::SeqCst), 2);
+    }
+
+    #[actix_rt::test]
+    async fn test_credentialed_request() {
+        let (hits, handler_hits) = counter();
+
+        let srv = init_service(App::new().wrap(Cache::new()).route(
+            "/",
+            web::get().to(move |req: HttpRequest| {
+                let n = handler_hits.fetch_add(1, Ordering::SeqCst) + 1;
+                match req.headers().get(AUTHORIZATION) {
+                    Some(_) => HttpResponse::Ok().body("secret"),
+                    None => HttpResponse::Ok().body(format!("public {}", n)),
+                }
+            }),
+        ))
+        .await;
+
+        let req = TestRequest::get()
+            .uri("/")
+            .insert_header((AUTHORIZATION, "Bearer token"))
+            .to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(read_body(res).await, "secret");
+
+        // the credentialed response was not stored
+        let req = TestRequest::get().uri("/").to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(read_body(res).await, "public 2");
+
+        // and credentialed requests are not served from the cache
+        let req = TestRequest::get()
+            .uri("/")
+            .insert_header((COOKIE, "id=1"))
+            .to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(read_body(res).await, "public 3");
+
+        let req = TestRequest::get().uri("/").to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(read_body(res).await, "public 2");
+        assert_eq!(hits.load(Ordering::SeqCst), 3);
+    }
+
+    #[actix_rt::test]
+    async fn test_vary() {
+        let (hits, handler_hits) = counter();
+
+        let srv = init_service(App::new().wrap(Cache::new().vary("accept-language")).route(
+            "/",
+            web::get().to(move || {
+                handler_hits.fetch_add(1, Ordering::SeqCst);
+                HttpResponse::Ok()
+            }),
+        ))
+        .await;
+
+        for lang in &["en", "fr", "en"] {
+            let req = TestRequest::get()
+                .uri("/")
+                .insert_header(("accept-language", *lang))
+                .to_request();
+            let res = call_service(&srv, req).await;
+            read_body(res).await;
+        }
+        assert_eq!(hits.load(Ordering::SeqCst), 2);
+    }
+
+    #[actix_rt::test]
+    async fn test_response_vary() {
+        let (hits, handler_hits) = counter();
+
+        let srv = init_service(
+            App::new()
+                .wrap(Compress::default())
+                .wrap(Cache::new())
+                .route(
+                    "/",
+                    web::get().to(move |req: HttpRequest| {
+                        handler_hits.fetch_add(1, Ordering::SeqCst);
+
+                        let mut res = HttpResponse::Ok();
+                        res.insert_header((VARY, "x-variant"));
+                        if let Some(variant) = req.headers().get("x-variant") {
+                            res.insert_header(("x-echo", variant.clone()));
+                        }
+                        res.body("body")
+                    }),
+                ),
+        )
+        .await;
+
+        let requests = [
+            (None, None),
+            (Some("gzip"), None),
+            (None, Some("a")),
+            (Some("gzip"), Some("a")),
+        ];
+
+        for _ in 0..2 {
+            for (encoding, variant) in &requests {
+                let mut req = TestRequest::get().uri("/");
+                if let Some(encoding) = encoding {
+                    req = req.insert_header((ACCEPT_ENCODING, *encoding));
+                }
+                if let Some(variant) = variant {
+                    req = req.insert_header(("x-variant", *variant));
+                }
+
+                let res = call_service(&srv, req.to_request()).await;
+                assert_eq!(
+                    res.headers().contains_key(CONTENT_ENCODING),
+                    encoding.is_some()
+                );
+                assert_eq!(res.headers().contains_key("x-echo"), variant.is_some());
+
+                let vary = res.response().extensions().get::<VaryHeaders>().cloned();
+                assert!(vary.unwrap().names().contains(&ACCEPT_ENCODING));
+                read_body(res).await;
+            }
+        }
+        assert_eq!(hits.load(Ordering::SeqCst), 4);
+    }
+
+    #[test]
+    fn test_memory_store_eviction() {
+        let store = MemoryStore::new(2);
+        let ttl = Duration::from_secs(60);
+
+        let key = |path: &str| CacheKey {
+            method: Method::GET,
+            host: "localhost".to_owned(),
+            path: path.to_owned(),
+            query: String::new(),
+            vary: Vec::new(),
+        };
+        let res = || CachedResponse {
+            status: StatusCode::OK,
+            headers: HeaderMap::new(),
+            body: Bytes::new(),
+        };
+
+        store.put(key("/a"), res(), ttl);
+        store.put(key("/b"), res(), ttl);
+
+        // "/a" becomes the most recently used
+        assert!(store.get(&key("/a")).is_some());
+
+        store.put(key("/c"), res(), ttl);
+        assert_eq!(store.len(), 2);
+        assert!(store.get(&key("/a")).is_some());
+        assert!(store.get(&key("/b")).is_none());
+        assert!(store.get(&key("/c")).is_some());
+
+        store.invalidate("/a");
+        assert!(store.get(&key("/a")).is_none());
+        assert_eq!(store.len(), 1);
+
+        store.clear();
+        assert!(store.is_empty());
+    }
+}
@@ -12,7 +12,7 @@ use std::{
 use actix_http::{
     body::MessageBody,
     encoding::Encoder,
-    http::header::{ContentEncoding, ACCEPT_ENCODING},
+    http::header::{ContentEncoding, VaryHeaders, ACCEPT_ENCODING},
     Error,
 };
 use actix_service::{Service, Transform};
@@ -30,6 +30,9 @@ use crate::{
 /// Use `BodyEncoding` trait for overriding response compression. To disable compression set
 /// encoding to `ContentEncoding::Identity`.
 ///
+/// Unless the encoding was overridden, `Accept-Encoding` is added to the response's
+/// [`VaryHeaders`](actix_http::http::header::VaryHeaders).
+///
 /// # Examples
 /// ```rust
 /// use actix_web::{web, middleware, App, HttpResponse};
@@ -133,10 +136,16 @@ where
         let this = self.project();
 
         match ready!(this.fut.poll(cx)) {
-            Ok(resp) => {
+            Ok(mut resp) => {
                 let enc = if let Some(enc) = resp.response().get_encoding() {
                     enc
                 } else {
+                    // encoding was negotiated from the request
+                    resp.response_mut()
+                        .extensions_mut()
+                        .get_or_insert_with(VaryHeaders::new)
+                        .add(ACCEPT_ENCODING);
+
                     *this.encoding
                 };
 
@@ -8,7 +8,7 @@ use futures_util::future::{Either, FutureExt, LocalBoxFuture};
 /// Middleware for conditionally enabling other middleware.
 ///
 /// The controlled middleware must not change the `Service` interfaces. This means you cannot
-/// control such middlewares like `Logger` or `Compress` directly. See the [`Compat`](super::Compat)
+/// control such middlewares like `Logger`, `Compress` or `ErrorHandlers` directly. See the [`Compat`](super::Compat)
 /// middleware for a workaround.
 ///
 /// # Examples
@@ -101,7 +101,7 @@ mod tests {
         dev::{ServiceRequest, ServiceResponse},
         error::Result,
         http::{header::CONTENT_TYPE, HeaderValue, StatusCode},
-        middleware::err_handlers::*,
+        middleware::{err_handlers::*, Compat},
         test::{self, TestRequest},
         HttpResponse,
     };
@@ -110,7 +110,7 @@ mod tests {
         res.response_mut()
             .headers_mut()
             .insert(CONTENT_TYPE, HeaderValue::from_static("0001"));
-        Ok(ErrorHandlerResponse::Response(res))
+        Ok(ErrorHandlerResponse::Response(res.map_into_left_body()))
     }
 
     #[actix_rt::test]
@@ -121,7 +121,7 @@ mod tests {
 
         let mw = ErrorHandlers::new().handler(StatusCode::INTERNAL_SERVER_ERROR, render_500);
 
-        let mw = Condition::new(true, mw)
+        let mw = Condition::new(true, Compat::new(mw))
             .new_transform(srv.into_service())
             .await
             .unwrap();
@@ -137,7 +137,7 @@ mod tests {
 
         let mw = ErrorHandlers::new().handler(StatusCode::INTERNAL_SERVER_ERROR, render_500);
 
-        let mw = Condition::new(false, mw)
+        let mw = Condition::new(false, Compat::new(mw))
             .new_transform(srv.into_service())
             .await
             .unwrap();
@@ -0,0 +1,521 @@
+//! For middleware documentation, see [`ConditionalGet`].
+
+use std::{
+    future::Future,
+    marker::PhantomData,
+    pin::Pin,
+    rc::Rc,
+    task::{Context, Poll},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use futures_util::{
+    future::{ready, Either, LocalBoxFuture, Ready},
+    ready,
+};
+
+use crate::{
+    dev::{Body, ResponseBody, Service, Transform},
+    error::ErrorPreconditionFailed,
+    http::{
+        header::{
+            self, EntityTag, HeaderMap, HttpDate, IfMatch, IfModifiedSince, IfNoneMatch,
+            IfUnmodifiedSince,
+        },
+        Method, StatusCode,
+    },
+    service::{ServiceRequest, ServiceResponse},
+    Error, HttpMessage, HttpRequest,
+};
+
+type ValidatorsFn = dyn Fn(&ServiceRequest) -> LocalBoxFuture<'static, Option<Validators>>;
+
+/// Validators of the current representation of a resource, used to evaluate preconditions.
+#[derive(Debug, Clone, Default)]
+pub struct Validators {
+    /// Entity tag of the representation.
+    pub etag: Option<EntityTag>,
+
+    /// Modification date of the representation.
+    pub last_modified: Option<HttpDate>,
+}
+
+/// Middleware for answering conditional requests using the response's validators.
+///
+/// For successful `GET` and `HEAD` responses carrying an `ETag` and/or `Last-Modified` header, the
+/// request's `If-None-Match` and `If-Modified-Since` headers are evaluated as described in
+/// [RFC 7232 §6]. When the representation has not changed, the response is converted to a
+/// `304 Not Modified`; its headers are kept and the body is dropped without being polled.
+///
+/// When [`check_preconditions`](Self::check_preconditions) is set, requests with unsafe methods
+/// are checked against `If-Match` and `If-Unmodified-Since` before the wrapped service is called,
+/// using the validators of the current representation. If either fails, the request is answered
+/// with a `412 Precondition Failed` error and the service is not called. Otherwise these headers
+/// are not evaluated, and handlers of unsafe methods must check them themselves.
+///
+/// # Examples
+/// ```rust
+/// use actix_web::{http::header, middleware, web, App, HttpResponse};
+///
+/// let app = App::new()
+///     .wrap(middleware::ConditionalGet::new())
+///     .service(web::resource("/").to(|| {
+///         HttpResponse::Ok()
+///             .insert_header(header::ETag(header::EntityTag::strong("v1".to_owned())))
+///             .body("hello")
+///     }));
+/// ```
+///
+/// [RFC 7232 §6]: https://tools.ietf.org/html/rfc7232#section-6
+#[derive(Clone, Default)]
+pub struct ConditionalGet {
+    validators: Option<Rc<ValidatorsFn>>,
+}
+
+impl ConditionalGet {
+    /// Constructs a `ConditionalGet` middleware that only handles `GET` and `HEAD` requests.
+    pub fn new() -> Self {
+        ConditionalGet::default()
+    }
+
+    /// Enables answering `412 Precondition Failed` for unsafe methods whose `If-Match` or
+    /// `If-Unmodified-Since` headers do not match the current representation.
+    ///
+    /// `validators` looks up the validators of the current representation of the requested
+    /// resource, or `None` if it does not exist. It is only called for requests with one of these
+    /// headers, before the wrapped service.
+    ///
+    /// ```rust
+    /// use actix_web::{
+    ///     http::header::EntityTag,
+    ///     middleware::{ConditionalGet, Validators},
+    /// };
+    ///
+    /// let mw = ConditionalGet::new().check_preconditions(|req| {
+    ///     // look up the version of the resource at `req.path()`
+    ///     async {
+    ///         Some(Validators {
+    ///             etag: Some(EntityTag::strong("v1".to_owned())),
+    ///             last_modified: None,
+    ///         })
+    ///     }
+    /// });
+    /// ```
+    pub fn check_preconditions<F, Fut>(mut self, validators: F) -> Self
+    where
+        F: Fn(&ServiceRequest) -> Fut + 'static,
+        Fut: Future<Output = Option<Validators>> + 'static,
+    {
+        self.validators = Some(Rc::new(move |req: &ServiceRequest| {
+            Box::pin(validators(req)) as LocalBoxFuture<'static, _>
+        }));
+        self
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for ConditionalGet
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Transform = ConditionalGetMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(ConditionalGetMiddleware {
+            service: Rc::new(service),
+            validators: self.validators.clone(),
+        }))
+    }
+}
+
+pub struct ConditionalGetMiddleware<S> {
+    service: Rc<S>,
+    validators: Option<Rc<ValidatorsFn>>,
+}
+
+impl<S, B> Service<ServiceRequest> for ConditionalGetMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = Either<
+        ConditionalGetFuture<S, B>,
+        LocalBoxFuture<'static, Result<ServiceResponse<B>, Error>>,
+    >;
+
+    actix_service::forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let validators = self.validators.as_ref().filter(|_| {
+            !matches!(
+                *req.method(),
+                Method::GET | Method::HEAD | Method::OPTIONS | Method::TRACE
+            ) && (req.headers().contains_key(header::IF_MATCH)
+                || req.headers().contains_key(header::IF_UNMODIFIED_SINCE))
+        });
+
+        match validators {
+            Some(validators) => {
+                let validators = validators(&req);
+                let service = Rc::clone(&self.service);
+
+                Either::Right(Box::pin(async move {
+                    let validators = validators.await;
+
+                    if is_precondition_failed(req.request(), validators.as_ref()) {
+                        return Err(ErrorPreconditionFailed("Precondition Failed"));
+                    }
+
+                    service.call(req).await
+                }))
+            }
+
+            None => Either::Left(ConditionalGetFuture {
+                fut: self.service.call(req),
+                _body: PhantomData,
+            }),
+        }
+    }
+}
+
+#[pin_project::pin_project]
+pub struct ConditionalGetFuture<S: Service<ServiceRequest>, B> {
+    #[pin]
+    fut: S::Future,
+    _body: PhantomData<B>,
+}
+
+impl<S, B> Future for ConditionalGetFuture<S, B>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+{
+    type Output = <S::Future as Future>::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        let res = ready!(this.fut.poll(cx))?;
+
+        let not_modified = res.status().is_success()
+            && matches!(*res.request().method(), Method::GET | Method::HEAD)
+            && is_not_modified(res.request(), res.headers());
+
+        let res = if not_modified {
+            res.map_body(|head, _| {
+                head.status = StatusCode::NOT_MODIFIED;
+                ResponseBody::Other(Body::None)
+            })
+        } else {
+            res
+        };
+
+        Poll::Ready(Ok(res))
+    }
+}
+
+fn response_etag(headers: &HeaderMap) -> Option<EntityTag> {
+    header::from_one_raw_str(headers.get(header::ETAG)).ok()
+}
+
+fn response_last_modified(headers: &HeaderMap) -> Option<HttpDate> {
+    header::from_one_raw_str(headers.get(header::LAST_MODIFIED)).ok()
+}
+
+/// Compares two dates at the one second resolution of HTTP dates.
+fn secs_since_epoch(date: HttpDate) -> Option<u64> {
+    SystemTime::from(date)
+        .duration_since(UNIX_EPOCH)
+        .ok()
+        .map(|dur| dur.as_secs())
+}
+
+/// Returns true if the client's cached representation matches the response.
+fn is_not_modified(req: &HttpRequest, headers: &HeaderMap) -> bool {
+    match req.get_header::<IfNoneMatch>() {
+        Some(IfNoneMatch::Any) => return true,
+
+        Some(IfNoneMatch::Items(ref items)) => {
+            // If-Modified-Since is ignored when If-None-Match is present
+            return match response_etag(headers) {
+                Some(etag) => items.iter().any(|item| item.weak_eq(&etag)),
+                None => false,
+            };
+        }
+
+        None => {}
+    }
+
+    match (response_last_modified(headers), req.get_header()) {
+        (Some(modified), Some(IfModifiedSince(since))) => {
+            match (secs_since_epoch(modified), secs_since_epoch(since)) {
+                (Some(modified), Some(since)) => modified <= since,
+                _ => false,
+            }
+        }
+        _ => false,
+    }
+}
+
+/// Returns true if the request's `If-Match` or `If-Unmodified-Since` header fails for the current
+/// representation, or `None` if there is none.
+fn is_precondition_failed(req: &HttpRequest, validators: Option<&Validators>) -> bool {
+    match req.get_header::<IfMatch>() {
+        Some(IfMatch::Any) => return validators.is_none(),
+
+        Some(IfMatch::Items(ref items)) => {
+            // If-Unmodified-Since is ignored when If-Match is present
+            return match validators.and_then(|v| v.etag.as_ref()) {
+                Some(etag) => !items.iter().any(|item| item.strong_eq(etag)),
+                None => true,
+            };
+        }
+
+        None => {}
+    }
+
+    match (validators.and_then(|v| v.last_modified), req.get_header()) {
+        (Some(modified), Some(IfUnmodifiedSince(since))) => {
+            match (secs_since_epoch(modified), secs_since_epoch(since)) {
+                (Some(modified), Some(since)) => modified > since,
+                _ => false,
+            }
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::Cell, time::Duration};
+
+    use actix_service::IntoService;
+    use futures_util::future::ok;
+
+    use super::*;
+    use crate::{
+        dev::ServiceRequest,
+        http::header::{ETag, LastModified},
+        test::{self, TestRequest},
+        HttpResponse,
+    };
+
+    fn modified_at() -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(1_600_000_000)
+    }
+
+    fn validators() -> Validators {
+        Validators {
+            etag: Some(EntityTag::strong("abc".to_owned())),
+            last_modified: Some(modified_at().into()),
+        }
+    }
+
+    async fn etag_mw(
+        mw: ConditionalGet,
+    ) -> impl Service<ServiceRequest, Response = ServiceResponse, Error = Error> {
+        let srv = |req: ServiceRequest| {
+            ok(req.into_response(
+                HttpResponse::Ok()
+                    .insert_header(ETag(EntityTag::strong("abc".to_owned())))
+                    .insert_header(LastModified(modified_at().into()))
+                    .body("body"),
+            ))
+        };
+
+        mw.new_transform(srv.into_service()).await.unwrap()
+    }
+
+    /// Middleware checking preconditions against [`validators`], counting calls of the service.
+    async fn precondition_mw(
+        found: bool,
+    ) -> (
+        impl Service<ServiceRequest, Response = ServiceResponse, Error = Error>,
+        Rc<Cell<usize>>,
+    ) {
+        let calls = Rc::new(Cell::new(0));
+        let srv_calls = Rc::clone(&calls);
+
+        let srv = move |req: ServiceRequest| {
+            srv_calls.set(srv_calls.get() + 1);
+            // the updated representation has new validators
+            ok(req.into_response(
+                HttpResponse::Ok()
+                    .insert_header(ETag(EntityTag::strong("def".to_owned())))
+                    .finish(),
+            ))
+        };
+
+        let mw = ConditionalGet::new()
+            .check_preconditions(move |_| ready(if found { Some(validators()) } else { None }));
+
+        (mw.new_transform(srv.into_service()).await.unwrap(), calls)
+    }
+
+    #[actix_rt::test]
+    async fn test_no_conditional_headers() {
+        let mw = etag_mw(ConditionalGet::new()).await;
+
+        let req = TestRequest::default().to_srv_request();
+        let res = mw.call(req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+    }
+
+    #[actix_rt::test]
+    async fn test_if_none_match() {
+        let mw = etag_mw(ConditionalGet::new()).await;
+
+        let req = TestRequest::default()
+            .insert_header((header::IF_NONE_MATCH, "\"abc\""))
+            .to_srv_request();
+        let res = mw.call(req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::NOT_MODIFIED);
+        assert_eq!(res.headers().get(header::ETAG).unwrap(), "\"abc\"");
+        assert!(res.headers().contains_key(header::LAST_MODIFIED));
+        assert!(test::read_body(res).await.is_empty());
+
+        // weak comparison and multiple tags
+        let req = TestRequest::default()
+            .insert_header((header::IF_NONE_MATCH, "\"xyz\", W/\"abc\""))
+            .to_srv_request();
+        let res = mw.call(req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::NOT_MODIFIED);
+
+        let req = TestRequest::default()
+            .insert_header((header::IF_NONE_MATCH, "\"xyz\""))
+            .to_srv_request();
+        let res = mw.call(req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(test::read_body(res).await, "body");
+
+        let req = TestRequest::default()
+            .insert_header((header::IF_NONE_MATCH, "*"))
+            .to_srv_request();
+        let res = mw.call(req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::NOT_MODIFIED);
+    }
+
+    #[actix_rt::test]
+    async fn test_if_none_match_takes_precedence() {
+        let mw = etag_mw(ConditionalGet::new()).await;
+
+        let since = HttpDate::from(modified_at() + Duration::from_secs(60));
+        let req = TestRequest::default()
+            .insert_header((header::IF_NONE_MATCH, "\"xyz\""))
+            .insert_header(IfModifiedSince(since))
+            .to_srv_request();
+        let res = mw.call(req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+    }
+
+    #[actix_rt::test]
+    async fn test_if_modified_since() {
+        let mw = etag_mw(ConditionalGet::new()).await;
+
+        let req = TestRequest::default()
+            .insert_header(IfModifiedSince(modified_at().into()))
+            .to_srv_request();
+        let res = mw.call(req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::NOT_MODIFIED);
+
+        let since = HttpDate::from(modified_at() - Duration::from_secs(60));
+        let req = TestRequest::default()
+            .insert_header(IfModifiedSince(since))
+            .to_srv_request();
+        let res = mw.call(req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+    }
+
+    #[actix_rt::test]
+    async fn test_unsafe_methods_ignored_by_default() {
+        let mw = etag_mw(ConditionalGet::new()).await;
+
+        let req = TestRequest::post()
+            .insert_header((header::IF_NONE_MATCH, "\"abc\""))
+            .insert_header((header::IF_MATCH, "\"xyz\""))
+            .to_srv_request();
+        let res = mw.call(req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+    }
+
+    #[actix_rt::test]
+    async fn test_if_match() {
+        let (mw, calls) = precondition_mw(true).await;
+
+        let req = TestRequest::post()
+            .insert_header((header::IF_MATCH, "\"abc\""))
+            .to_srv_request();
+        let res = mw.call(req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+
+        let req = TestRequest::post()
+            .insert_header((header::IF_MATCH, "*"))
+            .to_srv_request();
+        let res = mw.call(req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(calls.get(), 2);
+
+        // weak tags never match with strong comparison, and the service is not called
+        let req = TestRequest::post()
+            .insert_header((header::IF_MATCH, "W/\"abc\""))
+            .to_srv_request();
+        let err = mw.call(req).await.unwrap_err();
+        assert_eq!(
+            err.as_response_error().status_code(),
+            StatusCode::PRECONDITION_FAILED
+        );
+        assert_eq!(calls.get(), 2);
+
+        // a missing resource never matches
+        let (mw, calls) = precondition_mw(false).await;
+
+        for tag in &["*", "\"abc\""] {
+            let req = TestRequest::put()
+                .insert_header((header::IF_MATCH, *tag))
+                .to_srv_request();
+            assert!(mw.call(req).await.is_err());
+        }
+        assert_eq!(calls.get(), 0);
+    }
+
+    #[actix_rt::test]
+    async fn test_if_unmodified_since() {
+        let (mw, calls) = precondition_mw(true).await;
+
+        let req = TestRequest::delete()
+            .insert_header(IfUnmodifiedSince(modified_at().into()))
+            .to_srv_request();
+        let res = mw.call(req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+
+        let since = HttpDate::from(modified_at() - Duration::from_secs(60));
+        let req = TestRequest::delete()
+            .insert_header(IfUnmodifiedSince(since))
+            .to_srv_request();
+        assert!(mw.call(req).await.is_err());
+        assert_eq!(calls.get(), 1);
+    }
+
+    #[actix_rt::test]
+    async fn test_preconditions_not_checked() {
+        let (mw, calls) = precondition_mw(true).await;
+
+        // safe methods and requests without preconditions skip the lookup
+        let req = TestRequest::default()
+            .insert_header((header::IF_MATCH, "\"xyz\""))
+            .to_srv_request();
+        let res = mw.call(req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+
+        let req = TestRequest::post().to_srv_request();
+        let res = mw.call(req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(calls.get(), 2);
+    }
+}
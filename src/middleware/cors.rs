@@ -0,0 +1,741 @@
+//! For middleware documentation, see [`Cors`].
+
+use std::{
+    convert::TryFrom,
+    fmt,
+    future::Future,
+    marker::PhantomData,
+    pin::Pin,
+    rc::Rc,
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use futures_util::{
+    future::{ready, Either, Ready},
+    ready,
+};
+use regex::Regex;
+
+use crate::{
+    dev::{RequestHead, Service, Transform},
+    error::CorsError,
+    http::{
+        header::{self, HeaderMap, HeaderName, HeaderValue},
+        Error as HttpError, Method, StatusCode,
+    },
+    service::{ServiceRequest, ServiceResponse},
+    Error, HttpResponse, ResponseError,
+};
+
+const REQUEST_PRIVATE_NETWORK: &str = "access-control-request-private-network";
+const ALLOW_PRIVATE_NETWORK: &str = "access-control-allow-private-network";
+
+/// Middleware implementing [Cross-Origin Resource Sharing] (CORS).
+///
+/// Preflight requests (`OPTIONS` requests carrying `Origin` and `Access-Control-Request-Method`
+/// headers) are answered by the middleware without reaching the wrapped service. They receive
+/// `200 OK` with the allowed methods and headers when the origin, method and headers are allowed,
+/// and a `400 Bad Request` [`CorsError`] otherwise.
+///
+/// Other requests from an allowed origin are passed on and the CORS headers are added to their
+/// response, including error responses. Requests from origins that are not allowed are passed on
+/// unchanged, so the browser denies the page access to the response; this keeps same-origin
+/// requests, which may also carry an `Origin` header, working.
+///
+/// A `Vary` header is added whenever the response depends on the request's `Origin`.
+///
+/// The default configuration allows no origins, the `GET`, `HEAD` and `POST` methods and no
+/// request headers beyond the [CORS-safelisted] ones.
+///
+/// # Credentials
+/// With [`supports_credentials`](Self::supports_credentials), browsers ignore a wildcard
+/// `Access-Control-Allow-Origin: *`. When any origin is allowed the request's origin is therefore
+/// reflected instead, and likewise the requested method and headers when any are allowed. Only
+/// combine the two for APIs that should be usable with credentials from every site.
+///
+/// # Examples
+/// ```rust
+/// use std::time::Duration;
+/// use actix_web::{http::header, middleware::Cors, web, App, HttpResponse};
+///
+/// let cors = Cors::new()
+///     .allowed_origin("https://www.rust-lang.org")
+///     .allowed_origin_regex(r"https://[a-z0-9-]+\.example\.com")
+///     .allowed_methods(vec!["GET", "POST", "DELETE"])
+///     .allowed_headers(vec![header::AUTHORIZATION, header::CONTENT_TYPE])
+///     .expose_headers(vec!["x-request-id"])
+///     .supports_credentials()
+///     .max_age(Duration::from_secs(3600));
+///
+/// let app = App::new()
+///     .wrap(cors)
+///     .service(web::resource("/").to(|| HttpResponse::Ok()));
+/// ```
+///
+/// [Cross-Origin Resource Sharing]: https://fetch.spec.whatwg.org/#http-cors-protocol
+/// [CORS-safelisted]: https://fetch.spec.whatwg.org/#cors-safelisted-request-header
+#[derive(Clone)]
+pub struct Cors {
+    inner: Rc<Inner>,
+}
+
+struct Inner {
+    any_origin: bool,
+    origins: Vec<OriginRule>,
+    /// Allowed methods, or `None` to allow any.
+    methods: Option<Vec<Method>>,
+    /// Allowed request headers, or `None` to allow any.
+    headers: Option<Vec<HeaderName>>,
+    expose_headers: Vec<HeaderName>,
+    max_age: Option<Duration>,
+    credentials: bool,
+    private_network: bool,
+}
+
+enum OriginRule {
+    Exact(HeaderValue),
+    Regex(Regex),
+    Fn(Box<dyn Fn(&HeaderValue, &RequestHead) -> bool>),
+}
+
+impl Default for Cors {
+    fn default() -> Self {
+        Cors {
+            inner: Rc::new(Inner {
+                any_origin: false,
+                origins: Vec::new(),
+                methods: Some(vec![Method::GET, Method::HEAD, Method::POST]),
+                headers: Some(Vec::new()),
+                expose_headers: Vec::new(),
+                max_age: None,
+                credentials: false,
+                private_network: false,
+            }),
+        }
+    }
+}
+
+impl Cors {
+    /// Constructs a `Cors` middleware that allows no origins.
+    pub fn new() -> Self {
+        Cors::default()
+    }
+
+    /// Allows requests from any origin.
+    pub fn allow_any_origin(mut self) -> Self {
+        self.inner_mut().any_origin = true;
+        self
+    }
+
+    /// Allows requests from an origin, given in its serialized form such as
+    /// `https://example.com:8080`. Passing `"*"` allows any origin.
+    ///
+    /// # Panics
+    /// Panics if the origin is not a valid header value.
+    pub fn allowed_origin(mut self, origin: &str) -> Self {
+        if origin == "*" {
+            return self.allow_any_origin();
+        }
+
+        let origin = HeaderValue::from_str(origin).expect("Can not create origin header value");
+        self.inner_mut().origins.push(OriginRule::Exact(origin));
+        self
+    }
+
+    /// Allows requests from origins matching a regular expression.
+    ///
+    /// The expression must match the whole origin; it is anchored at both ends.
+    ///
+    /// # Panics
+    /// Panics if the expression is invalid.
+    pub fn allowed_origin_regex(mut self, pattern: &str) -> Self {
+        let regex = Regex::new(&format!("^(?:{})$", pattern)).expect("Invalid origin regex");
+        self.inner_mut().origins.push(OriginRule::Regex(regex));
+        self
+    }
+
+    /// Allows requests from origins for which the predicate returns true.
+    pub fn allowed_origin_fn<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&HeaderValue, &RequestHead) -> bool + 'static,
+    {
+        self.inner_mut().origins.push(OriginRule::Fn(Box::new(f)));
+        self
+    }
+
+    /// Allows any method in preflight requests.
+    pub fn allow_any_method(mut self) -> Self {
+        self.inner_mut().methods = None;
+        self
+    }
+
+    /// Sets the methods allowed in preflight requests, replacing the default `GET`, `HEAD` and
+    /// `POST`.
+    ///
+    /// # Panics
+    /// Panics if a method is invalid.
+    pub fn allowed_methods<U, M>(mut self, methods: U) -> Self
+    where
+        U: IntoIterator<Item = M>,
+        Method: TryFrom<M>,
+        <Method as TryFrom<M>>::Error: Into<HttpError>,
+    {
+        let methods = methods
+            .into_iter()
+            .map(|method| Method::try_from(method).unwrap_or_else(|_| panic!("Invalid method")))
+            .collect();
+
+        self.inner_mut().methods = Some(methods);
+        self
+    }
+
+    /// Allows any request header in preflight requests.
+    pub fn allow_any_header(mut self) -> Self {
+        self.inner_mut().headers = None;
+        self
+    }
+
+    /// Adds request headers allowed in preflight requests.
+    ///
+    /// # Panics
+    /// Panics if a header name is invalid.
+    pub fn allowed_headers<U, H>(mut self, headers: U) -> Self
+    where
+        U: IntoIterator<Item = H>,
+        HeaderName: TryFrom<H>,
+        <HeaderName as TryFrom<H>>::Error: Into<HttpError>,
+    {
+        let inner = self.inner_mut();
+        let allowed = inner.headers.get_or_insert_with(Vec::new);
+
+        for name in headers {
+            allowed.push(header_name(name));
+        }
+
+        self
+    }
+
+    /// Adds response headers that pages are allowed to read, sent in
+    /// `Access-Control-Expose-Headers`.
+    ///
+    /// # Panics
+    /// Panics if a header name is invalid.
+    pub fn expose_headers<U, H>(mut self, headers: U) -> Self
+    where
+        U: IntoIterator<Item = H>,
+        HeaderName: TryFrom<H>,
+        <HeaderName as TryFrom<H>>::Error: Into<HttpError>,
+    {
+        self.inner_mut()
+            .expose_headers
+            .extend(headers.into_iter().map(header_name));
+        self
+    }
+
+    /// Sets how long browsers may cache the result of a preflight request, sent in
+    /// `Access-Control-Max-Age` with a resolution of one second.
+    pub fn max_age(mut self, max_age: Duration) -> Self {
+        self.inner_mut().max_age = Some(max_age);
+        self
+    }
+
+    /// Allows requests with credentials, such as cookies and `Authorization` headers, and allows
+    /// pages to read their responses.
+    ///
+    /// See the [type level documentation](Self#credentials) for how this changes wildcards.
+    pub fn supports_credentials(mut self) -> Self {
+        self.inner_mut().credentials = true;
+        self
+    }
+
+    /// Allows pages on public networks to make requests to this server when it is on a private
+    /// network, by answering the [Private Network Access] preflight header
+    /// `Access-Control-Request-Private-Network`.
+    ///
+    /// [Private Network Access]: https://wicg.github.io/private-network-access/
+    pub fn allow_private_network_access(mut self) -> Self {
+        self.inner_mut().private_network = true;
+        self
+    }
+
+    fn inner_mut(&mut self) -> &mut Inner {
+        Rc::get_mut(&mut self.inner).expect("Multiple copies exist")
+    }
+}
+
+fn header_name<H>(name: H) -> HeaderName
+where
+    HeaderName: TryFrom<H>,
+    <HeaderName as TryFrom<H>>::Error: Into<HttpError>,
+{
+    HeaderName::try_from(name).unwrap_or_else(|_| panic!("Can not create header name"))
+}
+
+impl Inner {
+    fn is_origin_allowed(&self, origin: &HeaderValue, head: &RequestHead) -> bool {
+        self.any_origin
+            || self.origins.iter().any(|rule| match rule {
+                OriginRule::Exact(allowed) => allowed == origin,
+                OriginRule::Regex(regex) => origin
+                    .to_str()
+                    .map_or(false, |origin| regex.is_match(origin)),
+                OriginRule::Fn(f) => f(origin, head),
+            })
+    }
+
+    /// Returns true if `Access-Control-Allow-Origin` is the same for every origin.
+    fn is_wildcard(&self) -> bool {
+        self.any_origin && !self.credentials
+    }
+
+    fn allow_origin(&self, origin: &HeaderValue) -> HeaderValue {
+        if self.is_wildcard() {
+            HeaderValue::from_static("*")
+        } else {
+            origin.clone()
+        }
+    }
+
+    /// Headers added to the response of a request that is not a preflight request.
+    fn response_headers(&self, head: &RequestHead) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+
+        if !self.is_wildcard() {
+            headers.append(header::VARY, HeaderValue::from_static("Origin"));
+        }
+
+        let origin = match head.headers().get(header::ORIGIN) {
+            Some(origin) if self.is_origin_allowed(origin, head) => origin,
+            _ => return headers,
+        };
+
+        headers.insert(
+            header::ACCESS_CONTROL_ALLOW_ORIGIN,
+            self.allow_origin(origin),
+        );
+
+        if self.credentials {
+            headers.insert(
+                header::ACCESS_CONTROL_ALLOW_CREDENTIALS,
+                HeaderValue::from_static("true"),
+            );
+        }
+
+        if !self.expose_headers.is_empty() {
+            headers.insert(
+                header::ACCESS_CONTROL_EXPOSE_HEADERS,
+                join(self.expose_headers.iter().map(HeaderName::as_str)),
+            );
+        }
+
+        headers
+    }
+
+    /// Builds the response to a preflight request.
+    fn preflight(&self, head: &RequestHead) -> Result<HttpResponse, CorsError> {
+        let headers = head.headers();
+
+        let origin = match headers.get(header::ORIGIN) {
+            Some(origin) if self.is_origin_allowed(origin, head) => origin,
+            _ => return Err(CorsError::OriginNotAllowed),
+        };
+
+        let requested_method = headers
+            .get(header::ACCESS_CONTROL_REQUEST_METHOD)
+            .ok_or(CorsError::MethodNotAllowed)?;
+        let method = Method::from_bytes(requested_method.as_bytes())
+            .map_err(|_| CorsError::MethodNotAllowed)?;
+
+        if let Some(ref methods) = self.methods {
+            if !methods.contains(&method) {
+                return Err(CorsError::MethodNotAllowed);
+            }
+        }
+
+        let requested_headers = headers.get(header::ACCESS_CONTROL_REQUEST_HEADERS);
+
+        if let (Some(requested), Some(allowed)) = (requested_headers, &self.headers) {
+            let requested = requested
+                .to_str()
+                .map_err(|_| CorsError::HeadersNotAllowed)?;
+
+            for name in requested
+                .split(',')
+                .map(str::trim)
+                .filter(|name| !name.is_empty())
+            {
+                let name =
+                    HeaderName::try_from(name).map_err(|_| CorsError::HeadersNotAllowed)?;
+
+                if !allowed.contains(&name) {
+                    return Err(CorsError::HeadersNotAllowed);
+                }
+            }
+        }
+
+        let private_network = headers
+            .get(REQUEST_PRIVATE_NETWORK)
+            .map_or(false, |value| value == "true");
+
+        if private_network && !self.private_network {
+            return Err(CorsError::PrivateNetworkNotAllowed);
+        }
+
+        let mut res = HttpResponse::Ok();
+        res.insert_header((
+            header::ACCESS_CONTROL_ALLOW_ORIGIN,
+            self.allow_origin(origin),
+        ));
+
+        match self.methods {
+            Some(ref methods) => res.insert_header((
+                header::ACCESS_CONTROL_ALLOW_METHODS,
+                join(methods.iter().map(Method::as_str)),
+            )),
+            None => res.insert_header((
+                header::ACCESS_CONTROL_ALLOW_METHODS,
+                requested_method.clone(),
+            )),
+        };
+
+        match (&self.headers, requested_headers) {
+            (Some(allowed), _) if !allowed.is_empty() => {
+                res.insert_header((
+                    header::ACCESS_CONTROL_ALLOW_HEADERS,
+                    join(allowed.iter().map(HeaderName::as_str)),
+                ));
+            }
+            (None, Some(requested)) => {
+                res.insert_header((header::ACCESS_CONTROL_ALLOW_HEADERS, requested.clone()));
+            }
+            _ => {}
+        }
+
+        if let Some(max_age) = self.max_age {
+            res.insert_header((header::ACCESS_CONTROL_MAX_AGE, max_age.as_secs()));
+        }
+
+        if self.credentials {
+            res.insert_header((header::ACCESS_CONTROL_ALLOW_CREDENTIALS, "true"));
+        }
+
+        if private_network {
+            res.insert_header((ALLOW_PRIVATE_NETWORK, "true"));
+        }
+
+        Ok(res.finish())
+    }
+}
+
+fn join<'a>(items: impl Iterator<Item = &'a str>) -> HeaderValue {
+    let value = items.collect::<Vec<_>>().join(", ");
+    HeaderValue::from_str(&value).expect("methods and header names are valid header values")
+}
+
+/// Adds CORS headers to a response, keeping the values of any existing `Vary` header.
+fn apply_headers(res_headers: &mut HeaderMap, headers: &HeaderMap) {
+    for (name, value) in headers.iter() {
+        if name == header::VARY {
+            res_headers.append(name.clone(), value.clone());
+        } else {
+            res_headers.insert(name.clone(), value.clone());
+        }
+    }
+}
+
+fn is_preflight(head: &RequestHead) -> bool {
+    head.method == Method::OPTIONS
+        && head.headers().contains_key(header::ORIGIN)
+        && head
+            .headers()
+            .contains_key(header::ACCESS_CONTROL_REQUEST_METHOD)
+}
+
+impl<S, B> Transform<S, ServiceRequest> for Cors
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Transform = CorsMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(CorsMiddleware {
+            service,
+            inner: self.inner.clone(),
+        }))
+    }
+}
+
+pub struct CorsMiddleware<S> {
+    service: S,
+    inner: Rc<Inner>,
+}
+
+impl<S, B> Service<ServiceRequest> for CorsMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = Either<Ready<Result<ServiceResponse<B>, Error>>, CorsResponse<S, B>>;
+
+    actix_service::forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        if is_preflight(req.head()) {
+            let mut res = self
+                .inner
+                .preflight(req.head())
+                .unwrap_or_else(|err| HttpResponse::from_error(err.into()));
+
+            res.headers_mut().append(
+                header::VARY,
+                HeaderValue::from_static(
+                    "Origin, Access-Control-Request-Method, Access-Control-Request-Headers",
+                ),
+            );
+
+            return Either::Left(ready(Ok(req.into_response(res.into_body()))));
+        }
+
+        let headers = self.inner.response_headers(req.head());
+
+        Either::Right(CorsResponse {
+            fut: self.service.call(req),
+            headers: Some(headers),
+            _body: PhantomData,
+        })
+    }
+}
+
+#[pin_project::pin_project]
+pub struct CorsResponse<S: Service<ServiceRequest>, B> {
+    #[pin]
+    fut: S::Future,
+    headers: Option<HeaderMap>,
+    _body: PhantomData<B>,
+}
+
+impl<S, B> Future for CorsResponse<S, B>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+{
+    type Output = <S::Future as Future>::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        let res = ready!(this.fut.poll(cx));
+        let headers = this.headers.take().expect("polled after completion");
+
+        Poll::Ready(match res {
+            Ok(mut res) => {
+                apply_headers(res.headers_mut(), &headers);
+                Ok(res)
+            }
+            Err(error) => Err(WithCorsHeaders { error, headers }.into()),
+        })
+    }
+}
+
+/// An error from the wrapped service, rendered into a response with CORS headers added.
+struct WithCorsHeaders {
+    error: Error,
+    headers: HeaderMap,
+}
+
+impl fmt::Debug for WithCorsHeaders {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&self.error, f)
+    }
+}
+
+impl fmt::Display for WithCorsHeaders {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.error, f)
+    }
+}
+
+impl ResponseError for WithCorsHeaders {
+    fn status_code(&self) -> StatusCode {
+        self.error.as_response_error().status_code()
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        let mut res = self.error.as_response_error().error_response();
+        apply_headers(res.headers_mut(), &self.headers);
+        res
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_service::IntoService;
+    use futures_util::future::{err, ok};
+
+    use super::*;
+    use crate::{
+        error::ErrorForbidden,
+        test::{self, TestRequest},
+    };
+
+    async fn cors_mw(
+        cors: Cors,
+    ) -> impl Service<ServiceRequest, Response = ServiceResponse, Error = Error> {
+        let srv = |req: ServiceRequest| ok(req.into_response(HttpResponse::Ok().finish()));
+        cors.new_transform(srv.into_service()).await.unwrap()
+    }
+
+    fn preflight(origin: &str, method: &str) -> TestRequest {
+        TestRequest::default()
+            .method(Method::OPTIONS)
+            .insert_header((header::ORIGIN, origin))
+            .insert_header((header::ACCESS_CONTROL_REQUEST_METHOD, method))
+    }
+
+    #[actix_rt::test]
+    async fn test_origin_rules() {
+        let mw = cors_mw(
+            Cors::new()
+                .allowed_origin("https://exact.com")
+                .allowed_origin_regex(r"https://[a-z]+\.example\.com")
+                .allowed_origin_fn(|origin, head| {
+                    origin.as_bytes().ends_with(b".test") && head.uri.path() == "/fn"
+                }),
+        )
+        .await;
+
+        for (origin, path, allowed) in &[
+            ("https://exact.com", "/", true),
+            ("https://exact.com.evil.com", "/", false),
+            ("https://api.example.com", "/", true),
+            ("https://api.example.com.evil.com", "/", false),
+            ("https://evil.com/?https://api.example.com", "/", false),
+            ("https://site.test", "/fn", true),
+            ("https://site.test", "/", false),
+        ] {
+            let req = TestRequest::with_uri(path)
+                .insert_header((header::ORIGIN, *origin))
+                .to_srv_request();
+            let res = test::call_service(&mw, req).await;
+
+            assert_eq!(
+                res.headers()
+                    .get(header::ACCESS_CONTROL_ALLOW_ORIGIN)
+                    .is_some(),
+                *allowed,
+                "{} {}",
+                origin,
+                path
+            );
+        }
+    }
+
+    #[actix_rt::test]
+    async fn test_preflight_errors() {
+        let mw = cors_mw(
+            Cors::new()
+                .allowed_origin("https://a.com")
+                .allowed_headers(vec![header::CONTENT_TYPE]),
+        )
+        .await;
+
+        for (req, error) in vec![
+            (
+                preflight("https://b.com", "GET"),
+                CorsError::OriginNotAllowed,
+            ),
+            (
+                preflight("https://a.com", "PUT"),
+                CorsError::MethodNotAllowed,
+            ),
+            (
+                preflight("https://a.com", "G E T"),
+                CorsError::MethodNotAllowed,
+            ),
+            (
+                preflight("https://a.com", "GET")
+                    .insert_header((header::ACCESS_CONTROL_REQUEST_HEADERS, "x-token")),
+                CorsError::HeadersNotAllowed,
+            ),
+            (
+                preflight("https://a.com", "GET")
+                    .insert_header((REQUEST_PRIVATE_NETWORK, "true")),
+                CorsError::PrivateNetworkNotAllowed,
+            ),
+        ] {
+            let res = test::call_service(&mw, req.to_srv_request()).await;
+            assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+            assert_eq!(res.response().error().unwrap().as_error(), Some(&error));
+            assert!(!res
+                .headers()
+                .contains_key(header::ACCESS_CONTROL_ALLOW_ORIGIN));
+        }
+    }
+
+    #[actix_rt::test]
+    async fn test_any_method_and_header_reflected() {
+        let mw = cors_mw(
+            Cors::new()
+                .allow_any_origin()
+                .allow_any_method()
+                .allow_any_header(),
+        )
+        .await;
+
+        let req = preflight("https://a.com", "PATCH")
+            .insert_header((header::ACCESS_CONTROL_REQUEST_HEADERS, "x-one, X-Two"))
+            .to_srv_request();
+        let res = test::call_service(&mw, req).await;
+
+        assert_eq!(res.status(), StatusCode::OK);
+        let headers = res.headers();
+        assert_eq!(
+            headers.get(header::ACCESS_CONTROL_ALLOW_ORIGIN).unwrap(),
+            "*"
+        );
+        assert_eq!(
+            headers.get(header::ACCESS_CONTROL_ALLOW_METHODS).unwrap(),
+            "PATCH"
+        );
+        assert_eq!(
+            headers.get(header::ACCESS_CONTROL_ALLOW_HEADERS).unwrap(),
+            "x-one, X-Two"
+        );
+        assert!(!headers.contains_key(header::ACCESS_CONTROL_MAX_AGE));
+    }
+
+    #[actix_rt::test]
+    async fn test_service_error_gets_headers() {
+        let srv = |_: ServiceRequest| err::<ServiceResponse, _>(ErrorForbidden("denied"));
+        let mw = Cors::new()
+            .allowed_origin("https://a.com")
+            .new_transform(srv.into_service())
+            .await
+            .unwrap();
+
+        let req = TestRequest::default()
+            .insert_header((header::ORIGIN, "https://a.com"))
+            .to_srv_request();
+        let error = mw.call(req).await.unwrap_err();
+
+        assert_eq!(
+            error.as_response_error().status_code(),
+            StatusCode::FORBIDDEN
+        );
+        let res = error.as_response_error().error_response();
+        assert_eq!(res.status(), StatusCode::FORBIDDEN);
+        assert_eq!(
+            res.headers()
+                .get(header::ACCESS_CONTROL_ALLOW_ORIGIN)
+                .unwrap(),
+            "https://a.com"
+        );
+        assert_eq!(res.headers().get(header::VARY).unwrap(), "Origin");
+    }
+}
@@ -0,0 +1,162 @@
+//! For middleware documentation, see [`EnforceDeadline`].
+
+use actix_service::{Service, Transform};
+use futures_util::future::{ready, LocalBoxFuture, Ready};
+
+use crate::{
+    dev::{ServiceRequest, ServiceResponse},
+    error::{Error, ErrorGatewayTimeout},
+    rt::time::timeout,
+    web::{Deadline, DeadlineConfig},
+};
+
+/// Middleware for responding with `504 Gateway Timeout` when the deadline of a request expires.
+///
+/// The deadline is read from the request as described for the [`Deadline`] extractor, using the
+/// [`DeadlineConfig`] registered on the app. Handlers that extract the `Deadline` see the same
+/// deadline, and can stop early or respond differently. If they do not finish in time, their
+/// future is dropped and the request fails with a `504 Gateway Timeout` error.
+///
+/// # Examples
+/// ```rust
+/// use std::time::Duration;
+///
+/// use actix_web::{middleware::EnforceDeadline, web, App, HttpResponse};
+///
+/// let app = App::new()
+///     .app_data(web::DeadlineConfig::default().max(Duration::from_secs(5)))
+///     .wrap(EnforceDeadline)
+///     .route("/", web::get().to(|| HttpResponse::Ok()));
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct EnforceDeadline;
+
+impl<S, B> Transform<S, ServiceRequest> for EnforceDeadline
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Transform = EnforceDeadlineMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(EnforceDeadlineMiddleware { service }))
+    }
+}
+
+pub struct EnforceDeadlineMiddleware<S> {
+    service: S,
+}
+
+impl<S, B> Service<ServiceRequest> for EnforceDeadlineMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<ServiceResponse<B>, Error>>;
+
+    actix_service::forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let deadline =
+            Deadline::of_request(&req, req.request().app_data_or_data::<DeadlineConfig>());
+        let fut = timeout(deadline.remaining(), self.service.call(req));
+
+        Box::pin(async move {
+            match fut.await {
+                Ok(res) => res,
+                Err(_) => Err(ErrorGatewayTimeout("Request deadline expired")),
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use super::*;
+    use crate::{
+        http::StatusCode,
+        rt,
+        test::{call_service, init_service, try_call_service, TestRequest},
+        web, App, HttpResponse,
+    };
+
+    #[actix_rt::test]
+    async fn test_slow_handler() {
+        let srv = init_service(
+            App::new()
+                .wrap(EnforceDeadline)
+                .route(
+                    "/slow",
+                    web::get().to(|| async {
+                        rt::sleep(Duration::from_secs(5)).await;
+                        HttpResponse::Ok()
+                    }),
+                )
+                .route(
+                    "/fast",
+                    web::get().to(|deadline: Deadline| async move {
+                        assert!(!deadline.is_expired());
+                        HttpResponse::Ok()
+                    }),
+                ),
+        )
+        .await;
+
+        let start = Instant::now();
+        let req = TestRequest::with_uri("/slow")
+            .insert_header(("x-request-deadline-ms", "50"))
+            .to_request();
+        let err = try_call_service(&srv, req).await.unwrap_err();
+        assert_eq!(
+            err.as_response_error().status_code(),
+            StatusCode::GATEWAY_TIMEOUT
+        );
+        assert!(start.elapsed() < Duration::from_secs(1));
+
+        let req = TestRequest::with_uri("/fast")
+            .insert_header(("x-request-deadline-ms", "1000"))
+            .to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+    }
+
+    #[actix_rt::test]
+    async fn test_shared_deadline() {
+        let srv = init_service(
+            App::new()
+                .app_data(DeadlineConfig::default().max(Duration::from_millis(500)))
+                // delays the request between the middleware and the extractor
+                .wrap_fn(|req, srv| {
+                    let fut = srv.call(req);
+                    async move {
+                        rt::sleep(Duration::from_millis(50)).await;
+                        fut.await
+                    }
+                })
+                .wrap(EnforceDeadline)
+                .route(
+                    "/",
+                    web::get().to(|deadline: Deadline| async move {
+                        // the deadline was fixed by the middleware
+                        assert!(deadline.remaining() <= Duration::from_millis(450));
+                        HttpResponse::Ok()
+                    }),
+                ),
+        )
+        .await;
+
+        let req = TestRequest::default().to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+    }
+}
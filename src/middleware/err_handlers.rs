@@ -12,18 +12,22 @@ use ahash::AHashMap;
 use futures_core::{future::LocalBoxFuture, ready};
 
 use crate::{
-    dev::{ServiceRequest, ServiceResponse},
+    dev::{EitherBody, ServiceRequest, ServiceResponse},
     error::{Error, Result},
     http::StatusCode,
 };
 
 /// Return type for [`ErrorHandlers`] custom handlers.
+///
+/// Handlers that keep the original body return it with
+/// [`ServiceResponse::map_into_left_body`]; handlers that replace it use
+/// [`ServiceResponse::map_into_right_body`] with a [`BoxBody`](crate::dev::BoxBody).
 pub enum ErrorHandlerResponse<B> {
     /// Immediate HTTP response.
-    Response(ServiceResponse<B>),
+    Response(ServiceResponse<EitherBody<B>>),
 
     /// A future that resolves to an HTTP response.
-    Future(LocalBoxFuture<'static, Result<ServiceResponse<B>, Error>>),
+    Future(LocalBoxFuture<'static, Result<ServiceResponse<EitherBody<B>>, Error>>),
 }
 
 type ErrorHandler<B> = dyn Fn(ServiceResponse<B>) -> Result<ErrorHandlerResponse<B>>;
@@ -33,21 +37,32 @@ type ErrorHandler<B> = dyn Fn(ServiceResponse<B>) -> Result<ErrorHandlerResponse
 /// Register handlers with the `ErrorHandlers::handler()` method to register a custom error handler
 /// for a given status code. Handlers can modify existing responses or create completely new ones.
 ///
+/// The middleware's response body is an [`EitherBody`]: the original body on the left and a
+/// replacement body chosen by a handler on the right, so neither is buffered. To use it with
+/// [`Condition`](super::Condition), wrap it in [`Compat`](super::Compat).
+///
 /// # Examples
 /// ```rust
 /// use actix_web::middleware::{ErrorHandlers, ErrorHandlerResponse};
 /// use actix_web::{web, http, dev, App, HttpRequest, HttpResponse, Result};
+/// use actix_web::dev::{MessageBody, ResponseBody};
 ///
-/// fn render_500<B>(mut res: dev::ServiceResponse<B>) -> Result<ErrorHandlerResponse<B>> {
+/// fn add_error_header<B>(mut res: dev::ServiceResponse<B>) -> Result<ErrorHandlerResponse<B>> {
 ///     res.response_mut()
 ///        .headers_mut()
 ///        .insert(http::header::CONTENT_TYPE, http::HeaderValue::from_static("Error"));
-///     Ok(ErrorHandlerResponse::Response(res))
+///     Ok(ErrorHandlerResponse::Response(res.map_into_left_body()))
+/// }
+///
+/// fn render_500<B>(res: dev::ServiceResponse<B>) -> Result<ErrorHandlerResponse<B>> {
+///     let res = res.map_body(|_, _| ResponseBody::Body("An error occurred.".boxed()));
+///     Ok(ErrorHandlerResponse::Response(res.map_into_right_body()))
 /// }
 ///
 /// let app = App::new()
 ///     .wrap(
 ///         ErrorHandlers::new()
+///             .handler(http::StatusCode::BAD_REQUEST, add_error_header)
 ///             .handler(http::StatusCode::INTERNAL_SERVER_ERROR, render_500),
 ///     )
 ///     .service(web::resource("/test")
@@ -93,7 +108,7 @@ where
     S::Future: 'static,
     B: 'static,
 {
-    type Response = ServiceResponse<B>;
+    type Response = ServiceResponse<EitherBody<B>>;
     type Error = Error;
     type Transform = ErrorHandlersMiddleware<S, B>;
     type InitError = ();
@@ -117,7 +132,7 @@ where
     S::Future: 'static,
     B: 'static,
 {
-    type Response = ServiceResponse<B>;
+    type Response = ServiceResponse<EitherBody<B>>;
     type Error = Error;
     type Future = ErrorHandlersFuture<S::Future, B>;
 
@@ -141,7 +156,7 @@ where
         handlers: Handlers<B>,
     },
     HandlerFuture {
-        fut: LocalBoxFuture<'static, Fut::Output>,
+        fut: LocalBoxFuture<'static, Result<ServiceResponse<EitherBody<B>>, Error>>,
     },
 }
 
@@ -149,7 +164,7 @@ impl<Fut, B> Future for ErrorHandlersFuture<Fut, B>
 where
     Fut: Future<Output = Result<ServiceResponse<B>, Error>>,
 {
-    type Output = Fut::Output;
+    type Output = Result<ServiceResponse<EitherBody<B>>, Error>;
 
     fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
         match self.as_mut().project() {
@@ -164,7 +179,7 @@ where
                             self.poll(cx)
                         }
                     },
-                    None => Poll::Ready(Ok(res)),
+                    None => Poll::Ready(Ok(res.map_into_left_body())),
                 }
             }
             ErrorHandlersProj::HandlerFuture { fut } => fut.as_mut().poll(cx),
@@ -175,18 +190,35 @@ where
 #[cfg(test)]
 mod tests {
     use actix_service::IntoService;
-    use futures_util::future::{ok, FutureExt};
+    use bytes::Bytes;
+    use futures_util::{
+        future::{ok, FutureExt},
+        stream,
+    };
 
     use super::*;
+    use crate::body::BodyStream;
+    use crate::dev::{BodySize, MessageBody, ResponseBody};
     use crate::http::{header::CONTENT_TYPE, HeaderValue, StatusCode};
     use crate::test::{self, TestRequest};
     use crate::HttpResponse;
 
+    fn chunks(
+        chunks: &[&'static str],
+    ) -> stream::Iter<std::vec::IntoIter<Result<Bytes, Error>>> {
+        stream::iter(
+            chunks
+                .iter()
+                .map(|chunk| Ok(Bytes::from_static(chunk.as_bytes())))
+                .collect::<Vec<_>>(),
+        )
+    }
+
     fn render_500<B>(mut res: ServiceResponse<B>) -> Result<ErrorHandlerResponse<B>> {
         res.response_mut()
             .headers_mut()
             .insert(CONTENT_TYPE, HeaderValue::from_static("0001"));
-        Ok(ErrorHandlerResponse::Response(res))
+        Ok(ErrorHandlerResponse::Response(res.map_into_left_body()))
     }
 
     #[actix_rt::test]
@@ -211,7 +243,9 @@ mod tests {
         res.response_mut()
             .headers_mut()
             .insert(CONTENT_TYPE, HeaderValue::from_static("0001"));
-        Ok(ErrorHandlerResponse::Future(ok(res).boxed_local()))
+        Ok(ErrorHandlerResponse::Future(
+            ok(res.map_into_left_body()).boxed_local(),
+        ))
     }
 
     #[actix_rt::test]
@@ -229,4 +263,41 @@ mod tests {
         let resp = test::call_service(&mw, TestRequest::default().to_srv_request()).await;
         assert_eq!(resp.headers().get(CONTENT_TYPE).unwrap(), "0001");
     }
+
+    fn replace_body<B>(res: ServiceResponse<B>) -> Result<ErrorHandlerResponse<B>> {
+        let res = res.map_body(|_, _| {
+            ResponseBody::Body(BodyStream::new(chunks(&["some", "thing ", "failed"])).boxed())
+        });
+        Ok(ErrorHandlerResponse::Response(res.map_into_right_body()))
+    }
+
+    #[actix_rt::test]
+    async fn test_handler_replaces_body() {
+        let srv = |req: ServiceRequest| {
+            let res = if req.path() == "/fail" {
+                HttpResponse::InternalServerError().streaming(chunks(&["secret"]))
+            } else {
+                HttpResponse::Ok().streaming(chunks(&["original ", "body"]))
+            };
+            ok(req.into_response(res))
+        };
+
+        let mw = ErrorHandlers::new()
+            .handler(StatusCode::INTERNAL_SERVER_ERROR, replace_body)
+            .new_transform(srv.into_service())
+            .await
+            .unwrap();
+
+        let req = TestRequest::with_uri("/fail").to_srv_request();
+        let resp = test::call_service(&mw, req).await;
+        assert_eq!(resp.status(), StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(resp.response().body().size(), BodySize::Stream);
+        assert_eq!(test::read_body(resp).await, "something failed");
+
+        let req = TestRequest::default().to_srv_request();
+        let resp = test::call_service(&mw, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(resp.response().body().size(), BodySize::Stream);
+        assert_eq!(test::read_body(resp).await, "original body");
+    }
 }
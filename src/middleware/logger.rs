@@ -72,6 +72,11 @@ use crate::{
 /// `%{FOO}e` | `env_var["FOO"]`
 /// `%{FOO}xi` | [Custom request replacement](Logger::custom_request_replace) labelled "FOO"
 ///
+/// # Aborted Requests
+/// When the response body fails after the response head was sent, the client receives an
+/// incomplete response. Such requests are logged with the WARN level instead, with
+/// `[aborted: <error>]` appended to the formatted line.
+///
 /// # Security
 /// **\*** "Real IP" remote address is calculated using
 /// [`ConnectionInfo::realip_remote_addr()`](crate::dev::ConnectionInfo::realip_remote_addr())
@@ -295,6 +300,7 @@ where
                 time,
                 format,
                 size: 0,
+                error: None,
             })
         })))
     }
@@ -309,6 +315,8 @@ pub struct StreamLog<B> {
     format: Option<Format>,
     size: usize,
     time: OffsetDateTime,
+    /// Error the body failed with, making the request an aborted one.
+    error: Option<String>,
 }
 
 #[pinned_drop]
@@ -321,7 +329,12 @@ impl<B> PinnedDrop for StreamLog<B> {
                 }
                 Ok(())
             };
-            log::info!("{}", FormatDisplay(&render));
+            match self.error {
+                None => log::info!("{}", FormatDisplay(&render)),
+                Some(ref err) => {
+                    log::warn!("{} [aborted: {}]", FormatDisplay(&render), err)
+                }
+            }
         }
     }
 }
@@ -341,6 +354,10 @@ impl<B: MessageBody> MessageBody for StreamLog<B> {
                 *this.size += chunk.len();
                 Poll::Ready(Some(Ok(chunk)))
             }
+            Poll::Ready(Some(Err(err))) => {
+                *this.error = Some(err.to_string());
+                Poll::Ready(Some(Err(err)))
+            }
             val => val,
         }
     }
@@ -616,6 +633,40 @@ mod tests {
         let _res = srv.call(req).await;
     }
 
+    #[actix_rt::test]
+    async fn test_logger_aborted_body() {
+        use futures_util::{future::poll_fn, stream};
+
+        let srv = |req: ServiceRequest| {
+            let body = stream::iter(vec![
+                Ok(Bytes::from_static(b"partial")),
+                Err(crate::error::ErrorBadRequest("connection reset")),
+            ]);
+            ok(req.into_response(HttpResponse::Ok().streaming(body)))
+        };
+        let logger = Logger::default();
+        let srv = logger.new_transform(srv.into_service()).await.unwrap();
+
+        let req = TestRequest::with_uri("/upload").to_srv_request();
+        let mut res = srv.call(req).await.unwrap();
+
+        let body = res.take_body();
+        actix_rt::pin!(body);
+
+        let aborted = |body: &ResponseBody<StreamLog<_>>| match body {
+            ResponseBody::Body(log) => log.error.clone(),
+            ResponseBody::Other(_) => panic!("body should be wrapped by the logger"),
+        };
+
+        let chunk = poll_fn(|cx| body.as_mut().poll_next(cx)).await;
+        assert_eq!(chunk.unwrap().unwrap(), Bytes::from_static(b"partial"));
+        assert_eq!(aborted(&body), None);
+
+        let chunk = poll_fn(|cx| body.as_mut().poll_next(cx)).await;
+        assert!(chunk.unwrap().is_err());
+        assert_eq!(aborted(&body).as_deref(), Some("connection reset"));
+    }
+
     #[actix_rt::test]
     async fn test_logger_exclude_regex() {
         let srv = |req: ServiceRequest| {
@@ -0,0 +1,218 @@
+//! For middleware documentation, see [`MethodOverride`].
+
+use std::rc::Rc;
+
+use actix_service::{Service, Transform};
+use futures_util::future::{ready, Ready};
+
+use crate::{
+    http::{header::HeaderName, Method},
+    service::{ServiceRequest, ServiceResponse},
+    Error,
+};
+
+const X_HTTP_METHOD_OVERRIDE: &str = "x-http-method-override";
+
+/// Middleware for overriding the method of `POST` requests with the `X-HTTP-Method-Override`
+/// header, for clients that can only send `GET` and `POST` requests.
+///
+/// By default `PUT`, `PATCH` and `DELETE` can be requested. Requests using any other method, or
+/// with a header value that is not an allowed method, are passed on unchanged. The header is left
+/// in place.
+///
+/// The method has to be replaced before routing, so register the middleware on the `App`.
+///
+/// # Examples
+/// ```rust
+/// use actix_web::{web, middleware, App, HttpResponse};
+///
+/// # actix_web::rt::System::new().block_on(async {
+/// let app = App::new()
+///     .wrap(middleware::MethodOverride::default())
+///     .route("/item", web::delete().to(HttpResponse::NoContent));
+///
+/// use actix_web::http::StatusCode;
+/// use actix_web::test::{call_service, init_service, TestRequest};
+///
+/// let app = init_service(app).await;
+///
+/// let req = TestRequest::post()
+///     .uri("/item")
+///     .insert_header(("X-HTTP-Method-Override", "DELETE"))
+///     .to_request();
+/// let res = call_service(&app, req).await;
+/// assert_eq!(res.status(), StatusCode::NO_CONTENT);
+/// # })
+/// ```
+#[derive(Debug, Clone)]
+pub struct MethodOverride {
+    inner: Rc<Inner>,
+}
+
+#[derive(Debug)]
+struct Inner {
+    header: HeaderName,
+    methods: Vec<Method>,
+}
+
+impl Default for MethodOverride {
+    fn default() -> Self {
+        MethodOverride {
+            inner: Rc::new(Inner {
+                header: HeaderName::from_static(X_HTTP_METHOD_OVERRIDE),
+                methods: vec![Method::PUT, Method::PATCH, Method::DELETE],
+            }),
+        }
+    }
+}
+
+impl MethodOverride {
+    /// Constructs `MethodOverride` middleware allowing `PUT`, `PATCH` and `DELETE`.
+    pub fn new() -> Self {
+        MethodOverride::default()
+    }
+
+    /// Read the requested method from another header instead of `X-HTTP-Method-Override`.
+    pub fn header(mut self, header: HeaderName) -> Self {
+        Rc::get_mut(&mut self.inner)
+            .expect("Multiple copies exist")
+            .header = header;
+        self
+    }
+
+    /// Allow overriding the method with `method`.
+    pub fn allow(mut self, method: Method) -> Self {
+        let inner = Rc::get_mut(&mut self.inner).expect("Multiple copies exist");
+        if !inner.methods.contains(&method) {
+            inner.methods.push(method);
+        }
+        self
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for MethodOverride
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Transform = MethodOverrideMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(MethodOverrideMiddleware {
+            service,
+            inner: Rc::clone(&self.inner),
+        }))
+    }
+}
+
+pub struct MethodOverrideMiddleware<S> {
+    service: S,
+    inner: Rc<Inner>,
+}
+
+impl<S, B> Service<ServiceRequest> for MethodOverrideMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = S::Future;
+
+    actix_service::forward_ready!(service);
+
+    fn call(&self, mut req: ServiceRequest) -> Self::Future {
+        if req.method() == Method::POST {
+            let method = req
+                .headers()
+                .get(&self.inner.header)
+                .and_then(|val| Method::from_bytes(val.as_bytes()).ok())
+                .filter(|method| self.inner.methods.contains(method));
+
+            if let Some(method) = method {
+                req.set_method(method);
+            }
+        }
+
+        self.service.call(req)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        http::StatusCode,
+        test::{call_service, init_service, TestRequest},
+        web, App, HttpResponse,
+    };
+
+    #[actix_rt::test]
+    async fn test_method_override() {
+        let app = init_service(
+            App::new()
+                .wrap(MethodOverride::default())
+                .route("/", web::post().to(HttpResponse::Created))
+                .route("/", web::put().to(HttpResponse::Ok))
+                .route("/", web::get().to(HttpResponse::NoContent)),
+        )
+        .await;
+
+        let req = TestRequest::post()
+            .insert_header(("X-HTTP-Method-Override", "PUT"))
+            .to_request();
+        let res = call_service(&app, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+
+        // only allowed methods can be requested
+        let req = TestRequest::post()
+            .insert_header(("X-HTTP-Method-Override", "GET"))
+            .to_request();
+        let res = call_service(&app, req).await;
+        assert_eq!(res.status(), StatusCode::CREATED);
+
+        let req = TestRequest::post()
+            .insert_header(("X-HTTP-Method-Override", "not a method"))
+            .to_request();
+        let res = call_service(&app, req).await;
+        assert_eq!(res.status(), StatusCode::CREATED);
+
+        // only POST requests are overridden
+        let req = TestRequest::get()
+            .insert_header(("X-HTTP-Method-Override", "PUT"))
+            .to_request();
+        let res = call_service(&app, req).await;
+        assert_eq!(res.status(), StatusCode::NO_CONTENT);
+    }
+
+    #[actix_rt::test]
+    async fn test_method_override_custom() {
+        let app = init_service(
+            App::new()
+                .wrap(
+                    MethodOverride::new()
+                        .header(HeaderName::from_static("x-method"))
+                        .allow(Method::GET),
+                )
+                .route("/", web::post().to(HttpResponse::Created))
+                .route("/", web::get().to(HttpResponse::Ok)),
+        )
+        .await;
+
+        let req = TestRequest::post()
+            .insert_header(("X-Method", "GET"))
+            .to_request();
+        let res = call_service(&app, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+
+        let req = TestRequest::post()
+            .insert_header(("X-HTTP-Method-Override", "GET"))
+            .to_request();
+        let res = call_service(&app, req).await;
+        assert_eq!(res.status(), StatusCode::CREATED);
+    }
+}
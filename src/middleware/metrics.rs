@@ -0,0 +1,478 @@
+//! For middleware documentation, see [`Metrics`].
+
+use std::{
+    fmt::{self, Write as _},
+    future::Future,
+    marker::PhantomData,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicI64, AtomicU64, Ordering},
+        Arc, RwLock,
+    },
+    task::{Context, Poll},
+    time::Instant,
+};
+
+use ahash::AHashMap;
+use futures_util::future::{ready, Ready};
+
+use crate::{
+    dev::{Service, Transform},
+    http::{header::CONTENT_TYPE, Method, StatusCode},
+    service::{ServiceRequest, ServiceResponse},
+    web, Error, HttpResponse, Route,
+};
+
+/// Default histogram buckets, in seconds.
+const DEFAULT_BUCKETS: &[f64] = &[
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+/// Middleware for collecting request metrics in the Prometheus text exposition format.
+///
+/// Three metric families are collected:
+/// - `http_requests_total`: counter of handled requests;
+/// - `http_requests_in_flight`: gauge of requests currently being handled;
+/// - `http_request_duration_seconds`: histogram of the time taken to produce a response.
+///
+/// The counter and histogram are labeled by `method`, `pattern` and `status`. The pattern is the
+/// matched resource pattern (see [`HttpRequest::match_pattern`]) so that dynamic path segments do
+/// not create new series; requests that match no resource use an empty pattern. The status label
+/// is the response's status class, such as `2xx`.
+///
+/// Durations are measured until the response head is ready; streaming the response body is not
+/// included.
+///
+/// `Metrics` is a cheap handle to a shared registry and can be cloned into every worker's app.
+/// Mount [`handler`](Self::handler) to serve the collected metrics.
+///
+/// # Examples
+/// ```rust
+/// use actix_web::{middleware::Metrics, web, App, HttpResponse, HttpServer};
+///
+/// # fn run() -> std::io::Result<()> {
+/// let metrics = Metrics::new();
+///
+/// HttpServer::new(move || {
+///     App::new()
+///         .wrap(metrics.clone())
+///         .route("/metrics", metrics.handler())
+///         .route("/users/{id}", web::get().to(HttpResponse::Ok))
+/// });
+/// # Ok(())
+/// # }
+/// ```
+///
+/// [`HttpRequest::match_pattern`]: crate::HttpRequest::match_pattern
+#[derive(Clone)]
+pub struct Metrics {
+    inner: Arc<Registry>,
+}
+
+struct Registry {
+    buckets: Vec<f64>,
+    in_flight: AtomicI64,
+    series: RwLock<AHashMap<SeriesKey, Arc<Series>>>,
+}
+
+#[derive(PartialEq, Eq, Hash)]
+struct SeriesKey {
+    method: Method,
+    pattern: String,
+    status: u16,
+}
+
+struct Series {
+    count: AtomicU64,
+    duration_nanos: AtomicU64,
+
+    /// Observations per bucket, not cumulative. Last bucket is `+Inf`.
+    buckets: Box<[AtomicU64]>,
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Metrics {
+            inner: Arc::new(Registry {
+                buckets: DEFAULT_BUCKETS.to_vec(),
+                in_flight: AtomicI64::new(0),
+                series: RwLock::new(AHashMap::default()),
+            }),
+        }
+    }
+}
+
+impl Metrics {
+    /// Constructs a `Metrics` middleware with an empty registry and default histogram buckets.
+    pub fn new() -> Metrics {
+        Metrics::default()
+    }
+
+    /// Sets the upper bounds, in seconds, of the request duration histogram buckets.
+    ///
+    /// The `+Inf` bucket is always added. Defaults to the Prometheus client default buckets,
+    /// from 5ms to 10s.
+    ///
+    /// # Panics
+    /// Panics if a bound is not finite or if this handle has already been cloned.
+    pub fn buckets(mut self, buckets: impl Into<Vec<f64>>) -> Self {
+        let mut buckets = buckets.into();
+
+        assert!(
+            buckets.iter().all(|b| b.is_finite()),
+            "Histogram buckets must be finite"
+        );
+
+        buckets.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        buckets.dedup();
+
+        Arc::get_mut(&mut self.inner)
+            .expect("Multiple copies exist")
+            .buckets = buckets;
+
+        self
+    }
+
+    /// Returns a route serving the collected metrics in the Prometheus text exposition format.
+    pub fn handler(&self) -> Route {
+        let metrics = self.clone();
+
+        web::get().to(move || {
+            ready(
+                HttpResponse::Ok()
+                    .insert_header((CONTENT_TYPE, "text/plain; version=0.0.4"))
+                    .body(metrics.render()),
+            )
+        })
+    }
+
+    /// Renders the collected metrics in the Prometheus text exposition format.
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        self.inner
+            .render(&mut out)
+            .expect("Writing to a String can not fail");
+        out
+    }
+}
+
+impl Registry {
+    fn observe(&self, key: SeriesKey, elapsed: std::time::Duration) {
+        let series = self.series.read().unwrap().get(&key).cloned();
+
+        let series = match series {
+            Some(series) => series,
+            None => {
+                let buckets = self.buckets.len() + 1;
+
+                self.series
+                    .write()
+                    .unwrap()
+                    .entry(key)
+                    .or_insert_with(|| {
+                        Arc::new(Series {
+                            count: AtomicU64::new(0),
+                            duration_nanos: AtomicU64::new(0),
+                            buckets: (0..buckets).map(|_| AtomicU64::new(0)).collect(),
+                        })
+                    })
+                    .clone()
+            }
+        };
+
+        let secs = elapsed.as_secs_f64();
+        let bucket = self
+            .buckets
+            .iter()
+            .position(|&le| secs <= le)
+            .unwrap_or(self.buckets.len());
+
+        series.buckets[bucket].fetch_add(1, Ordering::Relaxed);
+        series
+            .duration_nanos
+            .fetch_add(elapsed.as_nanos() as u64, Ordering::Relaxed);
+        series.count.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn render(&self, out: &mut String) -> fmt::Result {
+        let series = self.series.read().unwrap();
+
+        let mut series = series
+            .iter()
+            .map(|(key, series)| {
+                let labels = format!(
+                    "method=\"{}\",pattern=\"{}\",status=\"{}xx\"",
+                    key.method,
+                    escape_label(&key.pattern),
+                    key.status
+                );
+                (labels, Arc::clone(series))
+            })
+            .collect::<Vec<_>>();
+        series.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+        writeln!(
+            out,
+            "# HELP http_requests_total Total number of HTTP requests."
+        )?;
+        writeln!(out, "# TYPE http_requests_total counter")?;
+        for (labels, series) in &series {
+            let count = series.count.load(Ordering::Relaxed);
+            writeln!(out, "http_requests_total{{{}}} {}", labels, count)?;
+        }
+
+        writeln!(
+            out,
+            "# HELP http_requests_in_flight Number of HTTP requests being handled."
+        )?;
+        writeln!(out, "# TYPE http_requests_in_flight gauge")?;
+        writeln!(
+            out,
+            "http_requests_in_flight {}",
+            self.in_flight.load(Ordering::Relaxed)
+        )?;
+
+        writeln!(
+            out,
+            "# HELP http_request_duration_seconds Time taken to produce HTTP responses."
+        )?;
+        writeln!(out, "# TYPE http_request_duration_seconds histogram")?;
+        for (labels, series) in &series {
+            let mut cumulative = 0;
+
+            for (i, bucket) in series.buckets.iter().enumerate() {
+                cumulative += bucket.load(Ordering::Relaxed);
+
+                let le = match self.buckets.get(i) {
+                    Some(le) => le.to_string(),
+                    None => "+Inf".to_owned(),
+                };
+
+                writeln!(
+                    out,
+                    "http_request_duration_seconds_bucket{{{},le=\"{}\"}} {}",
+                    labels, le, cumulative
+                )?;
+            }
+
+            let nanos = series.duration_nanos.load(Ordering::Relaxed);
+            writeln!(
+                out,
+                "http_request_duration_seconds_sum{{{}}} {}",
+                labels,
+                nanos as f64 / 1e9
+            )?;
+            writeln!(
+                out,
+                "http_request_duration_seconds_count{{{}}} {}",
+                labels, cumulative
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Escapes a label value for the text exposition format.
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+impl<S, B> Transform<S, ServiceRequest> for Metrics
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Transform = MetricsMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(MetricsMiddleware {
+            service,
+            inner: Arc::clone(&self.inner),
+        }))
+    }
+}
+
+/// Metrics middleware service.
+pub struct MetricsMiddleware<S> {
+    service: S,
+    inner: Arc<Registry>,
+}
+
+impl<S, B> Service<ServiceRequest> for MetricsMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = MetricsFuture<S, B>;
+
+    actix_service::forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let method = req.method().clone();
+        let pattern = req.match_pattern().unwrap_or_default();
+
+        self.inner.in_flight.fetch_add(1, Ordering::Relaxed);
+
+        MetricsFuture {
+            fut: self.service.call(req),
+            in_flight: InFlight(Arc::clone(&self.inner)),
+            method,
+            pattern,
+            start: Instant::now(),
+            _body: PhantomData,
+        }
+    }
+}
+
+/// Decrements the in-flight gauge when the request is finished or cancelled.
+struct InFlight(Arc<Registry>);
+
+impl Drop for InFlight {
+    fn drop(&mut self) {
+        self.0.in_flight.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+#[pin_project::pin_project]
+pub struct MetricsFuture<S: Service<ServiceRequest>, B> {
+    #[pin]
+    fut: S::Future,
+    in_flight: InFlight,
+    method: Method,
+    pattern: String,
+    start: Instant,
+    _body: PhantomData<B>,
+}
+
+impl<S, B> Future for MetricsFuture<S, B>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+{
+    type Output = <S::Future as Future>::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        let res = futures_util::ready!(this.fut.poll(cx));
+
+        let status = match res {
+            Ok(ref res) => res.status(),
+            Err(ref err) => err.as_response_error().status_code(),
+        };
+
+        this.in_flight.0.observe(
+            SeriesKey {
+                method: this.method.clone(),
+                pattern: std::mem::take(this.pattern),
+                status: status_class(status),
+            },
+            this.start.elapsed(),
+        );
+
+        Poll::Ready(res)
+    }
+}
+
+fn status_class(status: StatusCode) -> u16 {
+    status.as_u16() / 100
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        test::{call_service, init_service, read_body, TestRequest},
+        App,
+    };
+
+    #[actix_rt::test]
+    async fn test_metrics_exposition() {
+        let metrics = Metrics::new().buckets(vec![10.0, 0.5]);
+
+        let srv = init_service(
+            App::new()
+                .wrap(metrics.clone())
+                .route("/metrics", metrics.handler())
+                .service(
+                    web::scope("/users")
+                        .route("/{id}", web::get().to(HttpResponse::Ok))
+                        .route("/{id}", web::post().to(HttpResponse::BadRequest)),
+                ),
+        )
+        .await;
+
+        for (method, path) in &[
+            (Method::GET, "/users/1"),
+            (Method::GET, "/users/2"),
+            (Method::POST, "/users/3"),
+            (Method::GET, "/unknown/path"),
+        ] {
+            let req = TestRequest::default()
+                .method(method.clone())
+                .uri(path)
+                .to_request();
+            call_service(&srv, req).await;
+        }
+
+        let req = TestRequest::get().uri("/metrics").to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(
+            res.headers().get(CONTENT_TYPE).unwrap(),
+            "text/plain; version=0.0.4"
+        );
+
+        let body = read_body(res).await;
+        let body = std::str::from_utf8(&body).unwrap();
+
+        for line in &[
+            "# TYPE http_requests_total counter",
+            "http_requests_total{method=\"GET\",pattern=\"/users/{id}\",status=\"2xx\"} 2",
+            "http_requests_total{method=\"POST\",pattern=\"/users/{id}\",status=\"4xx\"} 1",
+            "http_requests_total{method=\"GET\",pattern=\"\",status=\"4xx\"} 1",
+            "# TYPE http_requests_in_flight gauge",
+            "http_requests_in_flight 1",
+            "# TYPE http_request_duration_seconds histogram",
+            "http_request_duration_seconds_bucket{method=\"GET\",pattern=\"/users/{id}\",status=\"2xx\",le=\"0.5\"} 2",
+            "http_request_duration_seconds_bucket{method=\"GET\",pattern=\"/users/{id}\",status=\"2xx\",le=\"10\"} 2",
+            "http_request_duration_seconds_bucket{method=\"GET\",pattern=\"/users/{id}\",status=\"2xx\",le=\"+Inf\"} 2",
+            "http_request_duration_seconds_count{method=\"GET\",pattern=\"/users/{id}\",status=\"2xx\"} 2",
+        ] {
+            assert!(
+                body.lines().any(|l| l == *line),
+                "missing {:?} in:\n{}",
+                line,
+                body
+            );
+        }
+
+        assert!(!body.contains("/metrics\""));
+        assert!(!body.contains("/users/1"));
+
+        let res = call_service(&srv, TestRequest::get().uri("/metrics").to_request()).await;
+        let body = read_body(res).await;
+        assert!(std::str::from_utf8(&body).unwrap().contains(
+            "http_requests_total{method=\"GET\",pattern=\"/metrics\",status=\"2xx\"} 1"
+        ));
+    }
+
+    #[test]
+    fn test_escape_label() {
+        assert_eq!(escape_label(r#"/{id:\d+}/"a""#), r#"/{id:\\d+}/\"a\""#);
+    }
+
+    #[test]
+    #[should_panic(expected = "must be finite")]
+    fn test_non_finite_bucket() {
+        Metrics::new().buckets(vec![0.1, f64::INFINITY]);
+    }
+}
@@ -1,20 +1,39 @@
 //! Commonly used middleware.
 
+mod cache;
 mod compat;
 mod condition;
+mod conditional;
+mod cors;
+mod deadline;
 mod default_headers;
 mod err_handlers;
 mod logger;
+mod method_override;
+mod metrics;
 mod normalize;
 
+pub use self::cache::{
+    Cache, CacheBody, CacheKey, CacheStore, CachedResponse, Invalidate, MemoryStore,
+};
 pub use self::compat::Compat;
 pub use self::condition::Condition;
+pub use self::conditional::{ConditionalGet, Validators};
+pub use self::cors::Cors;
+pub use self::deadline::EnforceDeadline;
 pub use self::default_headers::DefaultHeaders;
 pub use self::err_handlers::{ErrorHandlerResponse, ErrorHandlers};
 pub use self::logger::Logger;
+pub use self::method_override::MethodOverride;
+pub use self::metrics::Metrics;
 pub use self::normalize::{NormalizePath, TrailingSlash};
 
 #[cfg(feature = "compress")]
 mod compress;
 #[cfg(feature = "compress")]
 pub use self::compress::Compress;
+
+#[cfg(feature = "tracing")]
+mod tracing_logger;
+#[cfg(feature = "tracing")]
+pub use self::tracing_logger::{TraceParent, TracingLogger};
@@ -173,9 +173,7 @@ where
             };
             parts.path_and_query = Some(PathAndQuery::from_maybe_shared(path).unwrap());
 
-            let uri = Uri::from_parts(parts).unwrap();
-            req.match_info_mut().get_mut().update(&uri);
-            req.head_mut().uri = uri;
+            req.set_uri(Uri::from_parts(parts).unwrap());
         }
 
         self.service.call(req)
@@ -0,0 +1,297 @@
+//! For middleware documentation, see [`TracingLogger`].
+
+use std::{
+    fmt,
+    future::Future,
+    marker::PhantomData,
+    pin::Pin,
+    rc::Rc,
+    task::{Context, Poll},
+    time::Instant,
+};
+
+use futures_util::future::{ready, Ready};
+use tracing::{field::Empty, Span};
+
+use crate::{
+    dev::{Service, Transform},
+    service::{ServiceRequest, ServiceResponse},
+    Error, HttpMessage,
+};
+
+const TRACEPARENT: &str = "traceparent";
+
+/// Middleware creating a [`tracing`] span for every request.
+///
+/// The span is entered while the request is handled. With the default span, named
+/// `http.request`, these fields are recorded:
+/// - `method`: the request method;
+/// - `route`: the matched resource pattern (see [`HttpRequest::match_pattern`]), if any;
+/// - `status`: the response status code, once the response is ready;
+/// - `latency_ms`: the time taken to produce the response, in milliseconds;
+/// - `trace_id` and `parent_id`: the remote trace context of a valid W3C `traceparent` header.
+///
+/// Use [`new`](Self::new()) to create the span with other fields. The fields listed above are
+/// recorded if the span declares them, typically with [`tracing::field::Empty`]. The parsed
+/// `traceparent` header is also inserted into request extensions as [`TraceParent`], for
+/// propagating the trace context to other services.
+///
+/// When served with the `tracing` feature enabled, requests are handled within the
+/// `http.connection` span of their connection, which becomes the parent of request spans.
+///
+/// # Examples
+/// ```rust
+/// # extern crate tracing_rs as tracing;
+/// use actix_web::{middleware::TracingLogger, App};
+///
+/// // default span
+/// let app = App::new().wrap(TracingLogger::default());
+///
+/// // span with an additional field
+/// let app = App::new().wrap(TracingLogger::new(|req| {
+///     tracing::info_span!(
+///         "http.request",
+///         method = %req.method(),
+///         user_agent = ?req.headers().get("user-agent"),
+///         route = tracing::field::Empty,
+///         status = tracing::field::Empty,
+///         latency_ms = tracing::field::Empty,
+///     )
+/// }));
+/// ```
+///
+/// [`HttpRequest::match_pattern`]: crate::HttpRequest::match_pattern
+#[derive(Clone)]
+pub struct TracingLogger {
+    make_span: Rc<dyn Fn(&ServiceRequest) -> Span>,
+}
+
+impl Default for TracingLogger {
+    fn default() -> Self {
+        TracingLogger::new(|req| {
+            tracing::info_span!(
+                "http.request",
+                method = %req.method(),
+                route = Empty,
+                status = Empty,
+                latency_ms = Empty,
+                trace_id = Empty,
+                parent_id = Empty,
+            )
+        })
+    }
+}
+
+impl TracingLogger {
+    /// Constructs `TracingLogger` middleware creating request spans with `make_span`.
+    pub fn new<F>(make_span: F) -> Self
+    where
+        F: Fn(&ServiceRequest) -> Span + 'static,
+    {
+        TracingLogger {
+            make_span: Rc::new(make_span),
+        }
+    }
+}
+
+impl fmt::Debug for TracingLogger {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TracingLogger").finish()
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for TracingLogger
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Transform = TracingLoggerMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(TracingLoggerMiddleware {
+            service,
+            make_span: Rc::clone(&self.make_span),
+        }))
+    }
+}
+
+/// Tracing logger middleware service.
+pub struct TracingLoggerMiddleware<S> {
+    service: S,
+    make_span: Rc<dyn Fn(&ServiceRequest) -> Span>,
+}
+
+impl<S, B> Service<ServiceRequest> for TracingLoggerMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = TracingLoggerFuture<S, B>;
+
+    actix_service::forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let span = (self.make_span)(&req);
+
+        if let Some(pattern) = req.match_pattern() {
+            span.record("route", pattern.as_str());
+        }
+
+        let parent = req
+            .headers()
+            .get(TRACEPARENT)
+            .and_then(|val| val.to_str().ok())
+            .and_then(TraceParent::parse);
+
+        if let Some(parent) = parent {
+            span.record("trace_id", parent.trace_id());
+            span.record("parent_id", parent.parent_id());
+            req.extensions_mut().insert(parent);
+        }
+
+        let fut = span.in_scope(|| self.service.call(req));
+
+        TracingLoggerFuture {
+            fut,
+            span,
+            start: Instant::now(),
+            _body: PhantomData,
+        }
+    }
+}
+
+#[pin_project::pin_project]
+pub struct TracingLoggerFuture<S: Service<ServiceRequest>, B> {
+    #[pin]
+    fut: S::Future,
+    span: Span,
+    start: Instant,
+    _body: PhantomData<B>,
+}
+
+impl<S, B> Future for TracingLoggerFuture<S, B>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+{
+    type Output = <S::Future as Future>::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        let _entered = this.span.enter();
+
+        let res = futures_util::ready!(this.fut.poll(cx));
+
+        let status = match res {
+            Ok(ref res) => res.status(),
+            Err(ref err) => err.as_response_error().status_code(),
+        };
+
+        this.span.record("status", status.as_u16());
+        this.span
+            .record("latency_ms", this.start.elapsed().as_secs_f64() * 1000.0);
+
+        Poll::Ready(res)
+    }
+}
+
+/// Remote trace context of a request, parsed from a W3C `traceparent` header.
+///
+/// Inserted into request extensions by [`TracingLogger`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraceParent {
+    trace_id: String,
+    parent_id: String,
+    flags: u8,
+}
+
+impl TraceParent {
+    /// Parses a `traceparent` header value.
+    ///
+    /// Returns `None` for malformed values, unsupported versions and all-zero ids.
+    pub fn parse(value: &str) -> Option<Self> {
+        let value = value.trim();
+        let mut parts = value.splitn(5, '-');
+
+        let version = parts.next().filter(|v| is_hex(v, 2))?;
+        let trace_id = parts.next().filter(|id| is_hex(id, 32))?;
+        let parent_id = parts.next().filter(|id| is_hex(id, 16))?;
+        let flags = parts.next().filter(|flags| is_hex(flags, 2))?;
+
+        match version {
+            "ff" => return None,
+            // later versions may append fields
+            "00" if parts.next().is_some() => return None,
+            _ => {}
+        }
+
+        if is_zero(trace_id) || is_zero(parent_id) {
+            return None;
+        }
+
+        Some(TraceParent {
+            trace_id: trace_id.to_owned(),
+            parent_id: parent_id.to_owned(),
+            flags: u8::from_str_radix(flags, 16).ok()?,
+        })
+    }
+
+    /// Id of the trace, as 32 lowercase hex digits.
+    pub fn trace_id(&self) -> &str {
+        &self.trace_id
+    }
+
+    /// Id of the caller's span, as 16 lowercase hex digits.
+    pub fn parent_id(&self) -> &str {
+        &self.parent_id
+    }
+
+    /// Returns `true` if the caller may have recorded the trace.
+    pub fn sampled(&self) -> bool {
+        self.flags & 0x01 != 0
+    }
+}
+
+fn is_hex(s: &str, len: usize) -> bool {
+    s.len() == len && s.bytes().all(|b| matches!(b, b'0'..=b'9' | b'a'..=b'f'))
+}
+
+fn is_zero(s: &str) -> bool {
+    s.bytes().all(|b| b == b'0')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_traceparent() {
+        let parent =
+            TraceParent::parse("00-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-01")
+                .unwrap();
+        assert_eq!(parent.trace_id(), "0af7651916cd43dd8448eb211c80319c");
+        assert_eq!(parent.parent_id(), "b7ad6b7169203331");
+        assert!(parent.sampled());
+
+        let parent =
+            TraceParent::parse("01-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-00-extra")
+                .unwrap();
+        assert!(!parent.sampled());
+
+        for invalid in &[
+            "",
+            "00-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331",
+            "00-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-01-extra",
+            "ff-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-01",
+            "00-0AF7651916CD43DD8448EB211C80319C-b7ad6b7169203331-01",
+            "00-00000000000000000000000000000000-b7ad6b7169203331-01",
+            "00-0af7651916cd43dd8448eb211c80319c-0000000000000000-01",
+            "00-0af7651916cd43dd8448eb211c80319-b7ad6b7169203331-01",
+        ] {
+            assert!(TraceParent::parse(invalid).is_none(), "{:?}", invalid);
+        }
+    }
+}
@@ -2,18 +2,23 @@ use std::cell::{Ref, RefCell, RefMut};
 use std::rc::Rc;
 use std::{fmt, net};
 
-use actix_http::http::{HeaderMap, Method, Uri, Version};
-use actix_http::{Error, Extensions, HttpMessage, Message, Payload, RequestHead};
+use actix_http::http::{HeaderMap, Method, StatusCode, Uri, Version};
+use actix_http::{
+    error::PushError, h1::Informational, h2::ServerPush, Error, Extensions, HttpMessage,
+    Message, Payload, RequestHead,
+};
 use actix_router::{Path, Url};
 use futures_util::future::{ok, Ready};
 use smallvec::SmallVec;
 
 use crate::app_service::AppInitServiceState;
 use crate::config::AppConfig;
+use crate::data::Data;
 use crate::error::UrlGenerationError;
 use crate::extract::FromRequest;
 use crate::info::ConnectionInfo;
 use crate::rmap::ResourceMap;
+use crate::types::QueryParams;
 
 #[derive(Clone)]
 /// An HTTP Request
@@ -29,6 +34,7 @@ pub(crate) struct HttpRequestInner {
     pub(crate) head: Message<RequestHead>,
     pub(crate) path: Path<Url>,
     pub(crate) app_data: SmallVec<[Rc<Extensions>; 4]>,
+    pub(crate) conn_data: Option<Rc<Extensions>>,
     app_state: Rc<AppInitServiceState>,
 }
 
@@ -39,6 +45,7 @@ impl HttpRequest {
         head: Message<RequestHead>,
         app_state: Rc<AppInitServiceState>,
         app_data: Rc<Extensions>,
+        conn_data: Option<Rc<Extensions>>,
     ) -> HttpRequest {
         let mut data = SmallVec::<[Rc<Extensions>; 4]>::new();
         data.push(app_data);
@@ -49,6 +56,7 @@ impl HttpRequest {
                 path,
                 app_state,
                 app_data: data,
+                conn_data,
             }),
         }
     }
@@ -110,6 +118,14 @@ impl HttpRequest {
         }
     }
 
+    /// The parameters of the query string in the URL.
+    ///
+    /// The query string is parsed on first use and cached for the rest of the request; it is
+    /// parsed again if the URI has been rewritten since. See [`QueryParams`] for details.
+    pub fn query(&self) -> QueryParams {
+        QueryParams::from_request(self)
+    }
+
     /// Get a reference to the Path parameters.
     ///
     /// Params is a container for url parameters.
@@ -157,6 +173,88 @@ impl HttpRequest {
         self.head().extensions_mut()
     }
 
+    /// Returns a reference to a piece of connection data set in an [on-connect] callback.
+    ///
+    /// Connection data is kept separately from request extensions and is shared by all requests
+    /// on the same connection.
+    ///
+    /// ```ignore
+    /// let opt_t = req.conn_data::<PeerCertificate>();
+    /// ```
+    ///
+    /// [on-connect]: crate::HttpServer::on_connect
+    pub fn conn_data<T: 'static>(&self) -> Option<&T> {
+        self.inner
+            .conn_data
+            .as_deref()
+            .and_then(|container| container.get::<T>())
+    }
+
+    /// Push a resource to the client with HTTP/2 server push.
+    ///
+    /// Sends a `PUSH_PROMISE` for a `GET` request of `uri` with `headers`. The promised request
+    /// is handled by the application like a regular request, so routing and middleware apply.
+    /// Relative URIs are resolved against the scheme and authority of this request.
+    ///
+    /// Returns an error on HTTP/1.x connections, when the client disabled server push, when
+    /// its concurrent stream limit is reached or once the response to this request was sent.
+    ///
+    /// ```rust
+    /// # use actix_web::{http::HeaderMap, HttpRequest, HttpResponse};
+    /// async fn index(req: HttpRequest) -> HttpResponse {
+    ///     // pushing is an optimization; ignore failures
+    ///     let _ = req.push_resource("/style.css".parse().unwrap(), HeaderMap::new());
+    ///     HttpResponse::Ok().body("<link rel=\"stylesheet\" href=\"/style.css\">")
+    /// }
+    /// ```
+    pub fn push_resource(&self, uri: Uri, headers: HeaderMap) -> Result<(), PushError> {
+        let push = self
+            .extensions()
+            .get::<ServerPush>()
+            .cloned()
+            .ok_or(PushError::Unavailable)?;
+
+        push.push(uri, headers)
+    }
+
+    /// Send an informational (`1xx`) response, such as `103 Early Hints`, before the response of
+    /// the handler.
+    ///
+    /// The interim response is written to the connection right away. Several can be sent until
+    /// the final response is written. They are only supported on HTTP/1.1 connections; for other
+    /// clients nothing is sent. Returns `true` if the response was sent.
+    ///
+    /// # Panics
+    /// Panics if `status` is not a `1xx` status or is `101 Switching Protocols`.
+    ///
+    /// ```rust
+    /// # use actix_web::{http::{header, HeaderMap, StatusCode}, HttpRequest, HttpResponse};
+    /// async fn index(req: HttpRequest) -> HttpResponse {
+    ///     let mut hints = HeaderMap::new();
+    ///     hints.insert(
+    ///         header::LINK,
+    ///         header::HeaderValue::from_static("</style.css>; rel=preload; as=style"),
+    ///     );
+    ///     req.send_informational(StatusCode::from_u16(103).unwrap(), hints);
+    ///
+    ///     HttpResponse::Ok().body("<link rel=\"stylesheet\" href=\"/style.css\">")
+    /// }
+    /// ```
+    pub fn send_informational(&self, status: StatusCode, headers: HeaderMap) -> bool {
+        let informational = self.extensions().get::<Informational>().cloned();
+
+        match informational {
+            Some(informational) => informational.send(status, headers),
+            None => {
+                assert!(
+                    status.is_informational() && status != StatusCode::SWITCHING_PROTOCOLS,
+                    "Informational responses must have a 1xx status other than 101"
+                );
+                false
+            }
+        }
+    }
+
     /// Generate url for named resource
     ///
     /// ```rust
@@ -217,7 +315,7 @@ impl HttpRequest {
     /// borrowed.
     #[inline]
     pub fn connection_info(&self) -> Ref<'_, ConnectionInfo> {
-        ConnectionInfo::get(self.head(), self.app_config())
+        ConnectionInfo::get_with(self.head(), self.app_config(), self.app_data())
     }
 
     /// App config
@@ -234,6 +332,9 @@ impl HttpRequest {
     /// ```rust,ignore
     /// let opt_t = req.app_data::<Data<T>>();
     /// ```
+    ///
+    /// App data is resolved innermost first: data of the matched resource, then of its enclosing
+    /// scopes from the innermost outwards, then of the app. The first layer containing a `T` wins.
     pub fn app_data<T: 'static>(&self) -> Option<&T> {
         for container in self.inner.app_data.iter().rev() {
             if let Some(data) = container.get::<T>() {
@@ -244,6 +345,30 @@ impl HttpRequest {
         None
     }
 
+    /// Get an application data object stored either as `T` or wrapped in [`Data<T>`].
+    ///
+    /// Both are checked in each layer of app data before moving to the next, in the same order
+    /// as [`app_data`](Self::app_data), so a `Data<T>` registered on a scope takes precedence over
+    /// a `T` registered on the app. This is how extractor configs such as
+    /// [`JsonConfig`](crate::web::JsonConfig) are looked up.
+    pub fn app_data_or_data<T: 'static>(&self) -> Option<&T> {
+        self.inner.app_data.iter().rev().find_map(|container| {
+            container
+                .get::<T>()
+                .or_else(|| container.get::<Data<T>>().map(Data::get_ref))
+        })
+    }
+
+    /// Iterates over every `T` in the app data visible to this request, from the innermost layer
+    /// to the outermost, e.g. for merging configs registered at several levels.
+    pub fn app_data_layers<T: 'static>(&self) -> impl Iterator<Item = &T> {
+        self.inner
+            .app_data
+            .iter()
+            .rev()
+            .filter_map(|container| container.get::<T>())
+    }
+
     #[inline]
     fn app_state(&self) -> &AppInitServiceState {
         &*self.inner.app_state
@@ -289,6 +414,8 @@ impl Drop for HttpRequest {
                 // inner is borrowed mut here. get head's Extension mutably
                 // to reduce borrow check
                 inner.head.extensions.get_mut().clear();
+                // connection data is re-attached when the request is reused
+                inner.conn_data = None;
 
                 // a re-borrow of pool is necessary here.
                 let req = self.inner.clone();
@@ -518,6 +645,14 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_push_resource_unavailable() {
+        let req = TestRequest::default().to_http_request();
+
+        let res = req.push_resource(Uri::from_static("/style.css"), HeaderMap::new());
+        assert!(matches!(res, Err(PushError::Unavailable)));
+    }
+
     #[test]
     fn test_match_name() {
         let mut rdef = ResourceDef::new("/index.html");
@@ -668,6 +803,99 @@ mod tests {
         assert_eq!(body, Bytes::from_static(b"1"));
     }
 
+    #[actix_rt::test]
+    async fn test_layered_data_resolution() {
+        struct Layer(&'static str);
+
+        fn echo_layers(req: HttpRequest) -> HttpResponse {
+            let layers = req
+                .app_data_layers::<Layer>()
+                .map(|layer| layer.0)
+                .collect::<Vec<_>>();
+            let wrapped = req
+                .app_data_layers::<web::Data<Layer>>()
+                .map(|layer| layer.0)
+                .collect::<Vec<_>>();
+
+            HttpResponse::Ok().body(format!(
+                "{}; {}; {}; {}",
+                req.app_data::<Layer>().unwrap().0,
+                req.app_data_or_data::<Layer>().unwrap().0,
+                layers.join(", "),
+                wrapped.join(", ")
+            ))
+        }
+
+        let srv = init_service(
+            App::new()
+                .app_data(Layer("app"))
+                .app_data(web::Data::new(Layer("app data")))
+                .service(web::resource("/top").to(echo_layers))
+                .service(
+                    web::scope("/outer")
+                        .app_data(web::Data::new(Layer("outer data")))
+                        .service(
+                            web::scope("/inner")
+                                .app_data(Layer("inner"))
+                                .service(
+                                    web::resource("/res")
+                                        .app_data(web::Data::new(Layer("res data")))
+                                        .to(echo_layers),
+                                )
+                                .service(web::resource("/plain").to(echo_layers)),
+                        )
+                        .service(web::resource("/plain").to(echo_layers)),
+                ),
+        )
+        .await;
+
+        for (path, expected) in &[
+            ("/top", "app; app; app; app data"),
+            ("/outer/plain", "app; outer data; app; outer data, app data"),
+            (
+                "/outer/inner/plain",
+                "inner; inner; inner, app; outer data, app data",
+            ),
+            (
+                "/outer/inner/res",
+                "inner; res data; inner, app; res data, outer data, app data",
+            ),
+        ] {
+            let req = TestRequest::get().uri(path).to_request();
+            let resp = srv.call(req).await.unwrap();
+            assert_eq!(read_body(resp).await, expected, "{}", path);
+        }
+    }
+
+    #[actix_rt::test]
+    async fn test_config_data_shadows_outer_config() {
+        let srv = init_service(
+            App::new()
+                .app_data(web::PayloadConfig::new(1024))
+                .service(
+                    web::scope("/limited")
+                        .app_data(web::Data::new(web::PayloadConfig::new(4)))
+                        .route("/", web::post().to(|body: Bytes| async move { body })),
+                )
+                .route("/", web::post().to(|body: Bytes| async move { body })),
+        )
+        .await;
+
+        let req = TestRequest::post()
+            .uri("/limited/")
+            .set_payload("too long")
+            .to_request();
+        let resp = srv.call(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::PAYLOAD_TOO_LARGE);
+
+        let req = TestRequest::post()
+            .uri("/")
+            .set_payload("too long")
+            .to_request();
+        let resp = srv.call(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+    }
+
     #[actix_rt::test]
     async fn test_extensions_dropped() {
         struct Tracker {
@@ -770,4 +998,61 @@ mod tests {
         let res = call_service(&srv, req).await;
         assert_eq!(res.status(), StatusCode::OK);
     }
+
+    #[actix_rt::test]
+    async fn match_pattern_and_name_in_middleware() {
+        let seen = Rc::new(RefCell::new(Vec::new()));
+        let seen2 = Rc::clone(&seen);
+
+        let srv = init_service(
+            App::new()
+                .wrap_fn(move |req, srv| {
+                    seen2
+                        .borrow_mut()
+                        .push((req.match_pattern(), req.match_name().map(str::to_owned)));
+                    srv.call(req)
+                })
+                .service(
+                    web::scope("/api").service(
+                        web::scope("/{version}").service(
+                            web::resource("/files/{tail:.*}")
+                                .name("files")
+                                .to(HttpResponse::Ok),
+                        ),
+                    ),
+                )
+                .service(
+                    web::scope("/app")
+                        .service(web::resource("").name("app_index").to(HttpResponse::Ok)),
+                )
+                .service(web::resource("/info").name("info").to(HttpResponse::Ok))
+                .default_service(web::to(HttpResponse::NotFound)),
+        )
+        .await;
+
+        for path in &[
+            "/api/v1/files/a/b.txt",
+            "/app",
+            "/info",
+            "/api/v1/nope",
+            "/nope",
+        ] {
+            let req = TestRequest::get().uri(path).to_request();
+            call_service(&srv, req).await;
+        }
+
+        let pattern =
+            |pat: &str, name: Option<&str>| (Some(pat.to_owned()), name.map(str::to_owned));
+
+        assert_eq!(
+            *seen.borrow(),
+            vec![
+                pattern("/api/{version}/files/{tail:.*}", Some("files")),
+                pattern("/app", Some("app_index")),
+                pattern("/info", Some("info")),
+                (None, None),
+                (None, None),
+            ]
+        );
+    }
 }
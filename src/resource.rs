@@ -4,7 +4,7 @@ use std::future::Future;
 use std::rc::Rc;
 use std::task::Poll;
 
-use actix_http::{Error, Extensions, Response};
+use actix_http::{http::Method, Error, Extensions, Response};
 use actix_router::IntoPattern;
 use actix_service::boxed::{self, BoxService, BoxServiceFactory};
 use actix_service::{
@@ -56,6 +56,7 @@ pub struct Resource<T = ResourceEndpoint> {
     app_data: Option<Extensions>,
     guards: Vec<Box<dyn Guard>>,
     default: HttpNewService,
+    auto_head: bool,
     factory_ref: Rc<RefCell<Option<ResourceFactory>>>,
 }
 
@@ -74,6 +75,7 @@ impl Resource {
             default: boxed::factory(fn_service(|req: ServiceRequest| async {
                 Ok(req.into_response(Response::MethodNotAllowed().finish()))
             })),
+            auto_head: true,
         }
     }
 }
@@ -277,6 +279,7 @@ where
             routes: self.routes,
             default: self.default,
             app_data: self.app_data,
+            auto_head: self.auto_head,
             factory_ref: self.factory_ref,
         }
     }
@@ -339,6 +342,7 @@ where
             routes: self.routes,
             default: self.default,
             app_data: self.app_data,
+            auto_head: self.auto_head,
             factory_ref: self.factory_ref,
         }
     }
@@ -394,6 +398,8 @@ where
             *rdef.name_mut() = name.clone();
         }
 
+        self.auto_head = config.auto_head();
+
         config.register_service(rdef, guards, self, None)
     }
 }
@@ -413,6 +419,7 @@ where
             routes: self.routes,
             app_data: self.app_data.map(Rc::new),
             default: self.default,
+            auto_head: self.auto_head,
         });
 
         self.endpoint
@@ -423,6 +430,7 @@ pub struct ResourceFactory {
     routes: Vec<Route>,
     app_data: Option<Rc<Extensions>>,
     default: HttpNewService,
+    auto_head: bool,
 }
 
 impl ServiceFactory<ServiceRequest> for ResourceFactory {
@@ -441,6 +449,7 @@ impl ServiceFactory<ServiceRequest> for ResourceFactory {
         let factory_fut = join_all(self.routes.iter().map(|route| route.new_service(())));
 
         let app_data = self.app_data.clone();
+        let auto_head = self.auto_head;
 
         Box::pin(async move {
             let default = default_fut.await?;
@@ -453,6 +462,7 @@ impl ServiceFactory<ServiceRequest> for ResourceFactory {
                 app_data,
                 default,
                 routes,
+                auto_head,
             })
         })
     }
@@ -462,6 +472,7 @@ pub struct ResourceService {
     routes: Vec<RouteService>,
     app_data: Option<Rc<Extensions>>,
     default: HttpService,
+    auto_head: bool,
 }
 
 impl Service<ServiceRequest> for ResourceService {
@@ -472,14 +483,20 @@ impl Service<ServiceRequest> for ResourceService {
     actix_service::always_ready!();
 
     fn call(&self, mut req: ServiceRequest) -> Self::Future {
-        for route in self.routes.iter() {
-            if route.check(&mut req) {
-                if let Some(ref app_data) = self.app_data {
-                    req.add_data_container(app_data.clone());
-                }
+        let mut route = self.routes.iter().find(|route| route.check(&mut req));
+
+        // answer HEAD requests with GET routes, unless a route accepts them
+        if route.is_none() && self.auto_head && req.method() == Method::HEAD {
+            let head = req.head_as_get();
+            route = self.routes.iter().find(|route| route.check_head(&head));
+        }
 
-                return route.call(req);
+        if let Some(route) = route {
+            if let Some(ref app_data) = self.app_data {
+                req.add_data_container(app_data.clone());
             }
+
+            return route.call(req);
         }
 
         if let Some(ref app_data) = self.app_data {
@@ -526,7 +543,7 @@ mod tests {
     use crate::middleware::DefaultHeaders;
     use crate::service::ServiceRequest;
     use crate::test::{call_service, init_service, TestRequest};
-    use crate::{guard, web, App, Error, HttpResponse};
+    use crate::{guard, web, App, Error, HttpRequest, HttpResponse};
 
     #[actix_rt::test]
     async fn test_middleware() {
@@ -652,6 +669,91 @@ mod tests {
         assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
     }
 
+    #[actix_rt::test]
+    async fn test_auto_head() {
+        let srv = init_service(
+            App::new()
+                .service(
+                    web::resource("/get").route(web::get().to(|req: HttpRequest| {
+                        HttpResponse::Ok()
+                            .insert_header(("x-method", req.method().as_str()))
+                            .body("body")
+                    })),
+                )
+                .service(
+                    web::resource("/head")
+                        .route(web::get().to(HttpResponse::Ok))
+                        .route(web::head().to(HttpResponse::NoContent)),
+                )
+                .service(
+                    web::resource("/guarded")
+                        .guard(guard::Get())
+                        .to(HttpResponse::Accepted),
+                ),
+        )
+        .await;
+
+        // handler sees the HEAD method
+        let req = TestRequest::with_uri("/get")
+            .method(Method::HEAD)
+            .to_request();
+        let resp = call_service(&srv, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(
+            resp.headers().get("x-method").unwrap(),
+            HeaderValue::from_static("HEAD")
+        );
+
+        // explicit HEAD route wins
+        let req = TestRequest::with_uri("/head")
+            .method(Method::HEAD)
+            .to_request();
+        let resp = call_service(&srv, req).await;
+        assert_eq!(resp.status(), StatusCode::NO_CONTENT);
+
+        // resource level guard, as generated by the route macros
+        let req = TestRequest::with_uri("/guarded")
+            .method(Method::HEAD)
+            .to_request();
+        let resp = call_service(&srv, req).await;
+        assert_eq!(resp.status(), StatusCode::ACCEPTED);
+
+        let req = TestRequest::with_uri("/get")
+            .method(Method::POST)
+            .to_request();
+        let resp = call_service(&srv, req).await;
+        assert_eq!(resp.status(), StatusCode::METHOD_NOT_ALLOWED);
+    }
+
+    #[actix_rt::test]
+    async fn test_auto_head_disabled() {
+        let srv = init_service(
+            App::new()
+                .auto_head(false)
+                .service(web::resource("/get").route(web::get().to(HttpResponse::Ok)))
+                .service(
+                    web::scope("/scope").service(
+                        web::resource("/guarded")
+                            .guard(guard::Get())
+                            .to(HttpResponse::Ok),
+                    ),
+                ),
+        )
+        .await;
+
+        let req = TestRequest::with_uri("/get")
+            .method(Method::HEAD)
+            .to_request();
+        let resp = call_service(&srv, req).await;
+        assert_eq!(resp.status(), StatusCode::METHOD_NOT_ALLOWED);
+
+        let req = TestRequest::with_uri("/scope/guarded")
+            .method(Method::HEAD)
+            .to_request();
+        let resp = call_service(&srv, req).await;
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+    }
+
     #[actix_rt::test]
     async fn test_resource_guards() {
         let srv = init_service(
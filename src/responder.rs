@@ -103,6 +103,60 @@ impl<T: Responder> Responder for (T, StatusCode) {
     }
 }
 
+/// Headers in the map are inserted into the inner responder's response, overriding any headers
+/// with the same name.
+impl<T: Responder> Responder for (T, HeaderMap) {
+    fn respond_to(self, req: &HttpRequest) -> HttpResponse {
+        let mut res = self.0.respond_to(req);
+        insert_headers(&mut res, self.1);
+        res
+    }
+}
+
+/// Status and headers are applied after the inner responder has built its response so they
+/// take precedence.
+///
+/// ```rust
+/// use actix_web::{http::{header, HeaderMap, HeaderValue, StatusCode}, web, Responder};
+/// use serde::Serialize;
+///
+/// #[derive(Serialize)]
+/// struct MyObj {
+///     id: u32,
+/// }
+///
+/// async fn create() -> impl Responder {
+///     let mut headers = HeaderMap::new();
+///     headers.insert(header::LOCATION, HeaderValue::from_static("/objects/1"));
+///
+///     (web::Json(MyObj { id: 1 }), StatusCode::CREATED, headers)
+/// }
+/// ```
+impl<T: Responder> Responder for (T, StatusCode, HeaderMap) {
+    fn respond_to(self, req: &HttpRequest) -> HttpResponse {
+        let mut res = self.0.respond_to(req);
+        *res.status_mut() = self.1;
+        insert_headers(&mut res, self.2);
+        res
+    }
+}
+
+fn insert_headers(res: &mut HttpResponse, mut headers: HeaderMap) {
+    let mut name = None;
+
+    // drain yields the name only with the first value of each header
+    for (key, value) in headers.drain() {
+        if let Some(key) = key {
+            res.headers_mut().remove(&key);
+            name = Some(key);
+        }
+
+        if let Some(ref name) = name {
+            res.headers_mut().append(name.clone(), value);
+        }
+    }
+}
+
 impl Responder for &'static str {
     fn respond_to(self, _: &HttpRequest) -> HttpResponse {
         HttpResponse::Ok()
@@ -160,7 +214,8 @@ pub struct CustomResponder<T> {
 }
 
 impl<T: Responder> CustomResponder<T> {
-    fn new(responder: T) -> Self {
+    /// Wrap a responder so its status code and headers can be customized.
+    pub fn new(responder: T) -> Self {
         CustomResponder {
             responder,
             status: None,
@@ -427,4 +482,84 @@ pub(crate) mod tests {
             HeaderValue::from_static("application/json")
         );
     }
+
+    #[actix_rt::test]
+    async fn test_tuple_responder_with_headers() {
+        use crate::http::header::{HeaderName, LOCATION, SET_COOKIE};
+
+        let req = TestRequest::default().to_http_request();
+
+        let mut headers = HeaderMap::new();
+        headers.insert(LOCATION, HeaderValue::from_static("/new"));
+        let res = ("test", StatusCode::CREATED, headers).respond_to(&req);
+        assert_eq!(res.status(), StatusCode::CREATED);
+        assert_eq!(res.body().bin_ref(), b"test");
+        assert_eq!(res.headers().get(LOCATION).unwrap(), "/new");
+        assert_eq!(
+            res.headers().get(CONTENT_TYPE).unwrap(),
+            HeaderValue::from_static("text/plain; charset=utf-8")
+        );
+
+        // headers from the tuple override those set by the inner responder
+        let mut headers = HeaderMap::new();
+        headers.insert(CONTENT_TYPE, HeaderValue::from_static("text/html"));
+        headers.insert(
+            HeaderName::from_static("x-test"),
+            HeaderValue::from_static("1"),
+        );
+        let res = ("test".to_string(), headers).respond_to(&req);
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(res.body().bin_ref(), b"test");
+        assert_eq!(res.headers().get(CONTENT_TYPE).unwrap(), "text/html");
+        assert_eq!(res.headers().get("x-test").unwrap(), "1");
+
+        // every value of a multi-valued header is kept
+        let mut headers = HeaderMap::new();
+        headers.append(SET_COOKIE, HeaderValue::from_static("a=1"));
+        headers.append(SET_COOKIE, HeaderValue::from_static("b=2"));
+        let res = (
+            HttpResponse::Ok()
+                .insert_header((SET_COOKIE, "c=3"))
+                .finish(),
+            headers,
+        )
+            .respond_to(&req);
+        let cookies = res.headers().get_all(SET_COOKIE).collect::<Vec<_>>();
+        assert_eq!(cookies, vec!["a=1", "b=2"]);
+
+        let mut headers = HeaderMap::new();
+        headers.insert(LOCATION, HeaderValue::from_static("/objects/1"));
+        let res = (
+            web::Json(serde_json::json!({ "id": 1 })),
+            StatusCode::CREATED,
+            headers,
+        )
+            .respond_to(&req);
+        assert_eq!(res.status(), StatusCode::CREATED);
+        assert_eq!(res.body().bin_ref(), br#"{"id":1}"#);
+        assert_eq!(res.headers().get(LOCATION).unwrap(), "/objects/1");
+        assert_eq!(
+            res.headers().get(CONTENT_TYPE).unwrap(),
+            HeaderValue::from_static("application/json")
+        );
+    }
+
+    #[actix_rt::test]
+    async fn test_tuple_responder_in_handler() {
+        let srv = init_service(App::new().service(web::resource("/").to(|| async {
+            (
+                web::Json(serde_json::json!({ "id": 1 })),
+                StatusCode::CREATED,
+            )
+        })))
+        .await;
+
+        let req = TestRequest::default().to_request();
+        let resp = srv.call(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::CREATED);
+        assert_eq!(
+            resp.headers().get(CONTENT_TYPE).unwrap(),
+            HeaderValue::from_static("application/json")
+        );
+    }
 }
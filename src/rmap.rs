@@ -86,7 +86,7 @@ impl ResourceMap {
                 if let Some(plen) = pattern.is_prefix_match(path) {
                     return rmap.has_resource(&path[plen..]);
                 }
-            } else if pattern.is_match(path) || pattern.pattern() == "" && path == "/" {
+            } else if is_resource_match(pattern, path) {
                 return true;
             }
         }
@@ -103,7 +103,7 @@ impl ResourceMap {
                 if let Some(plen) = pattern.is_prefix_match(path) {
                     return rmap.match_name(&path[plen..]);
                 }
-            } else if pattern.is_match(path) {
+            } else if is_resource_match(pattern, path) {
                 return match pattern.name() {
                     "" => None,
                     s => Some(s),
@@ -145,7 +145,7 @@ impl ResourceMap {
                     ]
                     .concat();
                 }
-            } else if pattern.is_match(remaining) {
+            } else if is_resource_match(pattern, remaining) {
                 return pattern.pattern().to_owned();
             }
         }
@@ -247,6 +247,13 @@ impl ResourceMap {
     }
 }
 
+/// Checks a resource (non-scope) pattern against the remaining path.
+///
+/// Empty path resources inside scopes are also reached by requests for the scope root.
+fn is_resource_match(pattern: &ResourceDef, path: &str) -> bool {
+    pattern.is_match(path) || pattern.pattern() == "" && path == "/"
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
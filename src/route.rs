@@ -5,7 +5,7 @@ use std::pin::Pin;
 use std::rc::Rc;
 use std::task::{Context, Poll};
 
-use actix_http::{http::Method, Error};
+use actix_http::{http::Method, Error, RequestHead};
 use actix_service::{Service, ServiceFactory};
 use futures_util::future::{ready, FutureExt, LocalBoxFuture};
 
@@ -107,8 +107,12 @@ pub struct RouteService {
 
 impl RouteService {
     pub fn check(&self, req: &mut ServiceRequest) -> bool {
+        self.check_head(req.head())
+    }
+
+    pub(crate) fn check_head(&self, head: &RequestHead) -> bool {
         for f in self.guards.iter() {
-            if !f.check(req.head()) {
+            if !f.check(head) {
                 return false;
             }
         }
@@ -371,6 +375,12 @@ mod tests {
             .method(Method::HEAD)
             .to_request();
         let resp = call_service(&srv, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        let req = TestRequest::with_uri("/test")
+            .method(Method::PATCH)
+            .to_request();
+        let resp = call_service(&srv, req).await;
         assert_eq!(resp.status(), StatusCode::METHOD_NOT_ALLOWED);
 
         let req = TestRequest::with_uri("/json").to_request();
@@ -0,0 +1,68 @@
+//! Async runtime utilities.
+//!
+//! Re-exports everything from [`actix_rt`], the runtime each worker runs on, and adds the helpers
+//! most often needed by handlers and middleware at the top level: [`spawn`], [`spawn_blocking`],
+//! [`sleep`] and [`timeout`]. Using them instead of calling `tokio` directly keeps application
+//! code independent of the runtime version actix-web uses.
+//!
+//! Tasks spawned with [`spawn`] run on the worker that handles the current request and do not
+//! need to be `Send`.
+//!
+//! ```
+//! use std::time::Duration;
+//!
+//! use actix_web::{rt, HttpResponse};
+//!
+//! async fn index() -> HttpResponse {
+//!     // runs in the background without delaying the response
+//!     rt::spawn(async {
+//!         rt::sleep(Duration::from_millis(10)).await;
+//!     });
+//!
+//!     let sum = rt::spawn_blocking(|| (0..1_000u64).sum::<u64>()).await.unwrap();
+//!     HttpResponse::Ok().body(sum.to_string())
+//! }
+//! ```
+
+pub use actix_rt::task::spawn_blocking;
+pub use actix_rt::time::{sleep, timeout};
+pub use actix_rt::*;
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::{
+        http::StatusCode,
+        rt,
+        test::{call_service, init_service, read_body, TestRequest},
+        web, App, HttpResponse,
+    };
+
+    #[actix_rt::test]
+    async fn test_handler_runtime_helpers() {
+        let srv = init_service(App::new().route(
+            "/",
+            web::get().to(|| async {
+                rt::sleep(Duration::from_millis(10)).await;
+
+                let slow = rt::timeout(
+                    Duration::from_millis(10),
+                    rt::sleep(Duration::from_secs(10)),
+                )
+                .await;
+                assert!(slow.is_err());
+
+                let spawned = rt::spawn(async { 1 }).await.unwrap();
+                let blocking = rt::spawn_blocking(|| 2).await.unwrap();
+
+                HttpResponse::Ok().body((spawned + blocking).to_string())
+            }),
+        ))
+        .await;
+
+        let res = call_service(&srv, TestRequest::default().to_request()).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(read_body(res).await, "3");
+    }
+}
@@ -4,7 +4,7 @@ use std::future::Future;
 use std::rc::Rc;
 use std::task::Poll;
 
-use actix_http::Extensions;
+use actix_http::{http::Method, Extensions};
 use actix_router::{ResourceDef, Router};
 use actix_service::boxed::{self, BoxService, BoxServiceFactory};
 use actix_service::{
@@ -14,6 +14,7 @@ use actix_service::{
 use futures_core::future::LocalBoxFuture;
 use futures_util::future::join_all;
 
+use crate::app_service::{check_guards, check_head_guards};
 use crate::config::ServiceConfig;
 use crate::data::Data;
 use crate::dev::{AppService, HttpServiceFactory};
@@ -66,6 +67,7 @@ pub struct Scope<T = ScopeEndpoint> {
     guards: Vec<Box<dyn Guard>>,
     default: Option<Rc<HttpNewService>>,
     external: Vec<ResourceDef>,
+    config_error: Option<Error>,
     factory_ref: Rc<RefCell<Option<ScopeFactory>>>,
 }
 
@@ -81,6 +83,7 @@ impl Scope {
             services: Vec::new(),
             default: None,
             external: Vec::new(),
+            config_error: None,
             factory_ref: fref,
         }
     }
@@ -206,6 +209,37 @@ where
         self
     }
 
+    /// Run fallible external configuration as part of the scope building process.
+    ///
+    /// Works like [`configure`](Self::configure) but the configuration function can return an
+    /// error. When it fails, nothing it registered is kept and the application containing the
+    /// scope fails to initialize with the first such error.
+    pub fn try_configure<F, E>(mut self, f: F) -> Self
+    where
+        F: FnOnce(&mut ServiceConfig) -> Result<(), E>,
+        E: Into<Error>,
+    {
+        let mut cfg = ServiceConfig::new();
+
+        match f(&mut cfg) {
+            Ok(()) => {
+                self.services.extend(cfg.services);
+                self.external.extend(cfg.external);
+
+                self.app_data
+                    .get_or_insert_with(Extensions::new)
+                    .extend(cfg.app_data);
+            }
+            Err(err) => {
+                if self.config_error.is_none() {
+                    self.config_error = Some(err.into());
+                }
+            }
+        }
+
+        self
+    }
+
     /// Register HTTP service.
     ///
     /// This is similar to `App's` service registration.
@@ -331,6 +365,7 @@ where
             services: self.services,
             default: self.default,
             external: self.external,
+            config_error: self.config_error,
             factory_ref: self.factory_ref,
         }
     }
@@ -391,6 +426,7 @@ where
             services: self.services,
             default: self.default,
             external: self.external,
+            config_error: self.config_error,
             factory_ref: self.factory_ref,
         }
     }
@@ -416,6 +452,11 @@ where
             .into_iter()
             .for_each(|mut srv| srv.register(&mut cfg));
 
+        // configuration errors fail the initialization of the whole app
+        if let Some(err) = self.config_error.take().or_else(|| cfg.take_init_error()) {
+            config.init_error(err);
+        }
+
         let mut rmap = ResourceMap::new(ResourceDef::root_prefix(&self.rdef));
 
         // external resources
@@ -427,6 +468,7 @@ where
         *self.factory_ref.borrow_mut() = Some(ScopeFactory {
             app_data: self.app_data.take().map(Rc::new),
             default,
+            auto_head: config.auto_head(),
             services: cfg
                 .into_services()
                 .1
@@ -461,6 +503,7 @@ pub struct ScopeFactory {
     app_data: Option<Rc<Extensions>>,
     services: Rc<[(ResourceDef, HttpNewService, RefCell<Option<Guards>>)]>,
     default: Rc<HttpNewService>,
+    auto_head: bool,
 }
 
 impl ServiceFactory<ServiceRequest> for ScopeFactory {
@@ -487,6 +530,7 @@ impl ServiceFactory<ServiceRequest> for ScopeFactory {
         }));
 
         let app_data = self.app_data.clone();
+        let auto_head = self.auto_head;
 
         Box::pin(async move {
             let default = default_fut.await?;
@@ -507,6 +551,7 @@ impl ServiceFactory<ServiceRequest> for ScopeFactory {
                 app_data,
                 router,
                 default,
+                auto_head,
             })
         })
     }
@@ -516,6 +561,7 @@ pub struct ScopeService {
     app_data: Option<Rc<Extensions>>,
     router: Router<HttpService, Vec<Box<dyn Guard>>>,
     default: HttpService,
+    auto_head: bool,
 }
 
 impl Service<ServiceRequest> for ScopeService {
@@ -526,16 +572,14 @@ impl Service<ServiceRequest> for ScopeService {
     actix_service::always_ready!();
 
     fn call(&self, mut req: ServiceRequest) -> Self::Future {
-        let res = self.router.recognize_checked(&mut req, |req, guards| {
-            if let Some(ref guards) = guards {
-                for f in guards {
-                    if !f.check(req.head()) {
-                        return false;
-                    }
-                }
-            }
-            true
-        });
+        let mut res = self.router.recognize_checked(&mut req, check_guards);
+
+        if res.is_none() && self.auto_head && req.method() == Method::HEAD {
+            let head = req.head_as_get();
+            res = self
+                .router
+                .recognize_checked(&mut req, |_, guards| check_head_guards(&head, guards));
+        }
 
         if let Some(ref app_data) = self.app_data {
             req.add_data_container(app_data.clone());
@@ -583,7 +627,7 @@ mod tests {
     use crate::http::{header, HeaderValue, Method, StatusCode};
     use crate::middleware::DefaultHeaders;
     use crate::service::ServiceRequest;
-    use crate::test::{call_service, init_service, read_body, TestRequest};
+    use crate::test::{call_service, init_service, read_body, try_init_service, TestRequest};
     use crate::{guard, web, App, HttpRequest, HttpResponse};
 
     #[actix_rt::test]
@@ -1070,6 +1114,38 @@ mod tests {
         assert_eq!(resp.status(), StatusCode::OK);
     }
 
+    #[actix_rt::test]
+    async fn test_scope_try_configure() {
+        let srv = init_service(App::new().service(web::scope("/app").try_configure(|s| {
+            s.route("/path1", web::get().to(HttpResponse::Ok));
+            Ok::<_, crate::Error>(())
+        })))
+        .await;
+
+        let req = TestRequest::with_uri("/app/path1").to_request();
+        let resp = srv.call(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        let srv = try_init_service(App::new().service(web::scope("/app").service(
+            web::scope("/v1").try_configure(|s| {
+                s.route("/", web::get().to(HttpResponse::Ok));
+                Err(std::io::Error::new(
+                    std::io::ErrorKind::NotFound,
+                    "missing setting",
+                ))
+            }),
+        )))
+        .await;
+        match srv {
+            Err(err) => {
+                assert_eq!(err.to_string(), "missing setting");
+                let err = err.as_error::<std::io::Error>().unwrap();
+                assert_eq!(err.kind(), std::io::ErrorKind::NotFound);
+            }
+            Ok(_) => panic!("app initialization should fail"),
+        }
+    }
+
     #[actix_rt::test]
     async fn test_url_for_external() {
         let srv = init_service(App::new().service(web::scope("/app").configure(|s| {
@@ -1,14 +1,24 @@
 use std::{
     any::Any,
-    cmp, fmt, io,
+    cell::Cell,
+    cmp, fmt,
+    future::Future,
+    io,
     marker::PhantomData,
     net,
-    sync::{Arc, Mutex},
+    rc::Rc,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
 };
 
 use actix_http::{
-    body::MessageBody, Error, Extensions, HttpService, KeepAlive, Request, Response,
+    body::MessageBody, h2::H2Settings, ConnectionStats, Error, Extensions, GracefulShutdown,
+    HttpService, KeepAlive, Request, Response,
 };
+use actix_rt::System;
 use actix_server::{Server, ServerBuilder};
 use actix_service::{map_config, IntoServiceFactory, Service, ServiceFactory};
 
@@ -17,18 +27,35 @@ use actix_http::Protocol;
 #[cfg(unix)]
 use actix_service::pipeline_factory;
 #[cfg(unix)]
-use futures_util::future::ok;
+use futures_util::future::{ok, select_all};
+use futures_util::future::{select, Either};
 
+#[cfg(any(feature = "openssl", feature = "rustls"))]
+use actix_rt::net::TcpStream;
 #[cfg(feature = "openssl")]
-use actix_tls::accept::openssl::{AlpnError, SslAcceptor, SslAcceptorBuilder};
+use actix_tls::accept::openssl::{AlpnError, SslAcceptor, SslAcceptorBuilder, SslStream};
 #[cfg(feature = "rustls")]
-use actix_tls::accept::rustls::ServerConfig as RustlsServerConfig;
+use actix_tls::accept::rustls::{ServerConfig as RustlsServerConfig, TlsStream};
+#[cfg(feature = "rustls")]
+use tls_rustls::RootCertStore;
+
+#[cfg(any(feature = "openssl", feature = "rustls"))]
+use crate::tls::ClientCertAuth;
+#[cfg(feature = "openssl")]
+use crate::tls::{openssl_peer_certificates, ReloadableSslAcceptor};
+#[cfg(feature = "rustls")]
+use crate::tls::{rustls_peer_certificates, ClientAuthVerifier, ReloadableCert};
 
 use crate::config::AppConfig;
+use crate::handle::ServerHandle;
+use crate::health::HealthCheck;
+use crate::lifecycle::WorkerHooks;
 
 struct Socket {
     scheme: &'static str,
     addr: net::SocketAddr,
+    /// Unix domain sockets have a placeholder address.
+    uds: bool,
 }
 
 struct Config {
@@ -36,6 +63,44 @@ struct Config {
     keep_alive: KeepAlive,
     client_timeout: u64,
     client_shutdown: u64,
+    blocking_pool: Option<(usize, usize)>,
+    h2_settings: H2Settings,
+    allow_h2c: bool,
+    allow_obsolete_header_folding: bool,
+    respond_after_half_close: bool,
+    advertise_keep_alive: bool,
+    stats: ConnectionStats,
+    max_connection_age: Option<Duration>,
+    shutdown: GracefulShutdown,
+    health_check: Option<HealthCheck>,
+    worker_hooks: WorkerHooks,
+    worker_thread: WorkerThread,
+    handle: ServerHandle,
+}
+
+impl Config {
+    /// Per-thread setup run by every listener's service factory before the service is built.
+    fn start_worker(&self) {
+        self.worker_thread.setup();
+        self.handle.register_worker();
+
+        if let Some((size, max_queue)) = self.blocking_pool {
+            crate::blocking::configure(size, max_queue);
+        }
+    }
+
+    /// Config of the app of a worker listening on `addr`.
+    fn app_config(&self, secure: bool, addr: net::SocketAddr) -> AppConfig {
+        let host = self.host.clone().unwrap_or_else(|| format!("{}", addr));
+
+        let mut worker_hooks = self.worker_hooks.clone();
+        worker_hooks.shutdown = self.shutdown.clone();
+
+        AppConfig::new(secure, addr, host)
+            .health_check(self.health_check.clone())
+            .worker_hooks(Some(worker_hooks))
+            .server_handle(Some(self.handle.clone()))
+    }
 }
 
 /// An HTTP Server.
@@ -68,8 +133,11 @@ where
     pub(super) factory: F,
     config: Arc<Mutex<Config>>,
     backlog: u32,
+    workers: usize,
+    shutdown_timeout: Duration,
     sockets: Vec<Socket>,
     builder: ServerBuilder,
+    handle_signals: bool,
     on_connect_fn: Option<Arc<dyn Fn(&dyn Any, &mut Extensions) + Send + Sync>>,
     _phantom: PhantomData<(S, B)>,
 }
@@ -95,10 +163,26 @@ where
                 keep_alive: KeepAlive::Timeout(5),
                 client_timeout: 5000,
                 client_shutdown: 5000,
+                blocking_pool: None,
+                h2_settings: H2Settings::default(),
+                allow_h2c: false,
+                allow_obsolete_header_folding: false,
+                respond_after_half_close: true,
+                advertise_keep_alive: false,
+                stats: ConnectionStats::default(),
+                max_connection_age: None,
+                shutdown: GracefulShutdown::default(),
+                health_check: None,
+                worker_hooks: WorkerHooks::default(),
+                worker_thread: WorkerThread::default(),
+                handle: ServerHandle::new(),
             })),
             backlog: 1024,
+            workers: num_cpus::get(),
+            shutdown_timeout: Duration::from_secs(30),
             sockets: Vec::new(),
             builder: ServerBuilder::default(),
+            handle_signals: true,
             on_connect_fn: None,
             _phantom: PhantomData,
         }
@@ -106,7 +190,9 @@ where
 
     /// Sets function that will be called once before each connection is handled.
     /// It will receive a `&std::any::Any`, which contains underlying connection type and an
-    /// [Extensions] container so that request-local data can be passed to middleware and handlers.
+    /// [Extensions] container so that connection-level data can be passed to middleware and
+    /// handlers. This data is accessed using [`HttpRequest::conn_data`](crate::HttpRequest::conn_data)
+    /// and is kept separately from the request extensions.
     ///
     /// For example:
     /// - `actix_tls::openssl::SslStream<actix_web::rt::net::TcpStream>` when using openssl.
@@ -122,8 +208,11 @@ where
             factory: self.factory,
             config: self.config,
             backlog: self.backlog,
+            workers: self.workers,
+            shutdown_timeout: self.shutdown_timeout,
             sockets: self.sockets,
             builder: self.builder,
+            handle_signals: self.handle_signals,
             on_connect_fn: Some(Arc::new(f)),
             _phantom: PhantomData,
         }
@@ -133,10 +222,38 @@ where
     ///
     /// By default, server uses number of available logical CPU as thread count.
     pub fn workers(mut self, num: usize) -> Self {
+        self.workers = num;
         self.builder = self.builder.workers(num);
         self
     }
 
+    /// Set number of workers to start as a multiple of the number of available logical CPUs.
+    ///
+    /// The number is rounded to the nearest integer and is at least one.
+    pub fn workers_per_core(self, factor: f32) -> Self {
+        let num = (num_cpus::get() as f32 * factor).round() as usize;
+        self.workers(cmp::max(num, 1))
+    }
+
+    /// Pin the thread of each worker to a single CPU.
+    ///
+    /// Workers are assigned the CPUs the process may run on in turn, in the order they start.
+    /// Disabled by default.
+    #[cfg(target_os = "linux")]
+    pub fn worker_affinity(self, enabled: bool) -> Self {
+        self.config.lock().unwrap().worker_thread.affinity = enabled;
+        self
+    }
+
+    /// Name the thread of each worker with `prefix` followed by the index of the worker.
+    ///
+    /// The name is truncated to the 15 bytes allowed by the OS. Thread names are only set on
+    /// Linux.
+    pub fn worker_name_prefix(self, prefix: &str) -> Self {
+        self.config.lock().unwrap().worker_thread.name_prefix = Some(prefix.to_owned());
+        self
+    }
+
     /// Set the maximum number of pending connections.
     ///
     /// This refers to the number of clients that can be waiting to be served.
@@ -212,6 +329,178 @@ where
         self
     }
 
+    /// Limit the blocking thread pool used by [`web::block`](crate::web::block).
+    ///
+    /// Each worker runs at most `size` blocking functions at a time and queues up to `max_queue`
+    /// more. Further calls fail with [`BlockingError::QueueFull`](crate::error::BlockingError),
+    /// which results in a `503 Service Unavailable` response.
+    ///
+    /// By default the blocking pool is not limited.
+    pub fn blocking_pool(self, size: usize, max_queue: usize) -> Self {
+        self.config.lock().unwrap().blocking_pool = Some((size, max_queue));
+        self
+    }
+
+    /// Set the maximum number of concurrent streams per HTTP/2 connection.
+    ///
+    /// By default the `h2` crate's default is used.
+    pub fn h2_max_concurrent_streams(self, max: u32) -> Self {
+        self.update_h2_settings(|settings| settings.max_concurrent_streams(max))
+    }
+
+    /// Set the initial HTTP/2 flow control window size of each stream in bytes.
+    ///
+    /// By default the protocol default of 65,535 bytes is used.
+    ///
+    /// # Panics
+    /// Panics if `size` is larger than 2^31 - 1.
+    pub fn h2_initial_window_size(self, size: u32) -> Self {
+        self.update_h2_settings(|settings| settings.initial_window_size(size))
+    }
+
+    /// Set the initial HTTP/2 flow control window size of each connection in bytes.
+    ///
+    /// By default the protocol default of 65,535 bytes is used.
+    ///
+    /// # Panics
+    /// Panics if `size` is larger than 2^31 - 1.
+    pub fn h2_initial_connection_window_size(self, size: u32) -> Self {
+        self.update_h2_settings(|settings| settings.initial_connection_window_size(size))
+    }
+
+    /// Set the largest HTTP/2 frame payload accepted from clients in bytes.
+    ///
+    /// By default the protocol default of 16,384 bytes is used.
+    ///
+    /// # Panics
+    /// Panics if `size` is not between 2^14 and 2^24 - 1.
+    pub fn h2_max_frame_size(self, size: u32) -> Self {
+        self.update_h2_settings(|settings| settings.max_frame_size(size))
+    }
+
+    /// Serve HTTP/2 over cleartext TCP (h2c) to clients with prior knowledge of HTTP/2.
+    ///
+    /// Applies to listeners added with [`bind`](Self::bind) and [`listen`](Self::listen). Plain
+    /// connections starting with the HTTP/2 connection preface are served with HTTP/2; all others
+    /// keep using HTTP/1.x. Only prior knowledge is supported, not `Upgrade: h2c`.
+    ///
+    /// By default h2c is not allowed.
+    pub fn allow_h2c(self) -> Self {
+        self.config.lock().unwrap().allow_h2c = true;
+        self
+    }
+
+    /// Accept HTTP/1 header fields using obsolete line folding, as sent by some old clients.
+    ///
+    /// Each folded line is joined to the header line it continues, separated by a single space.
+    /// By default requests with folded header fields are answered with `400 Bad Request`.
+    pub fn allow_obsolete_header_folding(self, allow: bool) -> Self {
+        self.config.lock().unwrap().allow_obsolete_header_folding = allow;
+        self
+    }
+
+    /// Keep responding after a client shuts down its write side following a complete request.
+    ///
+    /// By default HTTP/1 responses in flight are finished before the connection is closed. When
+    /// disabled, the end of the read side closes the connection right away.
+    pub fn respond_after_half_close(self, enabled: bool) -> Self {
+        self.config.lock().unwrap().respond_after_half_close = enabled;
+        self
+    }
+
+    /// Advertise the keep-alive timeout to HTTP/1 clients with a `Keep-Alive: timeout=<secs>`
+    /// response header.
+    ///
+    /// The advertised timeout is the one set by [`keep_alive`](Self::keep_alive), or the shorter
+    /// timeout requested by the client. By default the timeout is not advertised.
+    pub fn advertise_keep_alive(self, enabled: bool) -> Self {
+        self.config.lock().unwrap().advertise_keep_alive = enabled;
+        self
+    }
+
+    /// Set the counters updated by all connections of the server, e.g. the time HTTP/2 responses
+    /// spent waiting for flow control window.
+    ///
+    /// Pass a clone of the same [`ConnectionStats`] to read them while the server is running.
+    pub fn connection_stats(self, stats: ConnectionStats) -> Self {
+        self.config.lock().unwrap().stats = stats;
+        self
+    }
+
+    /// Set the age after which connections are gracefully closed.
+    ///
+    /// HTTP/2 connections send `GOAWAY`, finish their in-flight streams and close. HTTP/1.x
+    /// connections stop keep-alive, closing after the next response. Useful for spreading
+    /// long-lived connections across instances behind a load balancer.
+    ///
+    /// By default connections have no maximum age.
+    pub fn max_connection_age(self, age: Duration) -> Self {
+        self.config.lock().unwrap().max_connection_age = Some(age);
+        self
+    }
+
+    /// Set the handle used to gracefully shut down all connections of the server.
+    ///
+    /// Unless signal handling is disabled, the handle is triggered when the server receives
+    /// `SIGTERM`. When stopping the server with [`Server::stop`], trigger it first so that
+    /// HTTP/2 clients receive `GOAWAY` instead of having their connections dropped.
+    pub fn graceful_shutdown(self, shutdown: GracefulShutdown) -> Self {
+        self.config.lock().unwrap().shutdown = shutdown;
+        self
+    }
+
+    /// Set the handle reporting the liveness and readiness of the server.
+    ///
+    /// The handle becomes ready once every worker has started and stops being ready when the
+    /// [graceful shutdown](Self::graceful_shutdown) handle is triggered. It is also added to the
+    /// app data of every worker.
+    pub fn health_check(self, health_check: HealthCheck) -> Self {
+        self.config.lock().unwrap().health_check = Some(health_check);
+        self
+    }
+
+    /// Add a hook run by the app of each worker once its data factories have completed, before
+    /// the worker accepts connections.
+    ///
+    /// The hook receives the app data of the worker, including data created by
+    /// [`App::data_factory`](crate::App::data_factory). Workers run a separate app for each bound
+    /// socket, so hooks run once per worker and socket. Hooks run in the order they were added.
+    pub fn on_worker_start<H, Fut>(self, hook: H) -> Self
+    where
+        H: Fn(Rc<Extensions>) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ()> + 'static,
+    {
+        self.config.lock().unwrap().worker_hooks.on_start(hook);
+        self
+    }
+
+    /// Add a hook run by the app of each worker during graceful shutdown, for flushing and
+    /// closing the resources it uses.
+    ///
+    /// The hook receives the same app data as [`on_worker_start`](Self::on_worker_start) hooks.
+    /// Graceful shutdown begins when the [graceful shutdown](Self::graceful_shutdown) handle is
+    /// triggered or, unless signal handling is disabled, when the server receives a stop signal.
+    /// Each app runs its stop hooks once the requests it is handling have produced responses,
+    /// while the worker is still running. The server stops after the stop hooks of all workers
+    /// have finished or the [shutdown timeout](Self::shutdown_timeout) has passed.
+    ///
+    /// Stopping the server directly with [`Server::stop`] does not run stop hooks.
+    pub fn on_worker_stop<H, Fut>(self, hook: H) -> Self
+    where
+        H: Fn(Rc<Extensions>) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ()> + 'static,
+    {
+        self.config.lock().unwrap().worker_hooks.on_stop(hook);
+        self
+    }
+
+    fn update_h2_settings(self, f: impl FnOnce(H2Settings) -> H2Settings) -> Self {
+        let mut c = self.config.lock().unwrap();
+        c.h2_settings = f(c.h2_settings);
+        drop(c);
+        self
+    }
+
     /// Set server host name.
     ///
     /// Host name is used by application router as a hostname for url generation.
@@ -230,9 +519,19 @@ where
         self
     }
 
+    /// Returns a handle for stopping the server once it runs.
+    ///
+    /// The handle is also added to the app data of every worker. See [`ServerHandle`].
+    pub fn handle(&self) -> ServerHandle {
+        self.config.lock().unwrap().handle.clone()
+    }
+
     /// Disable signal handling
+    ///
+    /// Stop the server with a [`handle`](Self::handle) instead.
     pub fn disable_signals(mut self) -> Self {
         self.builder = self.builder.disable_signals();
+        self.handle_signals = false;
         self
     }
 
@@ -244,6 +543,7 @@ where
     ///
     /// By default shutdown timeout sets to 30 seconds.
     pub fn shutdown_timeout(mut self, sec: u64) -> Self {
+        self.shutdown_timeout = Duration::from_secs(sec);
         self.builder = self.builder.shutdown_timeout(sec);
         self
     }
@@ -253,6 +553,18 @@ where
         self.sockets.iter().map(|s| s.addr).collect()
     }
 
+    /// Get the local addresses of bound TCP sockets.
+    ///
+    /// Unlike [`addrs`](Self::addrs), Unix domain sockets are left out. This is useful to find the
+    /// port chosen by the OS when binding port 0.
+    pub fn bound_addrs(&self) -> Vec<net::SocketAddr> {
+        self.sockets
+            .iter()
+            .filter(|s| !s.uds)
+            .map(|s| s.addr)
+            .collect()
+    }
+
     /// Get addresses of bound sockets and the scheme for it.
     ///
     /// This is useful when the server is bound from different sources
@@ -274,6 +586,7 @@ where
         self.sockets.push(Socket {
             addr,
             scheme: "http",
+            uds: false,
         });
         let on_connect_fn = self.on_connect_fn.clone();
 
@@ -281,23 +594,32 @@ where
             self.builder
                 .listen(format!("actix-web-service-{}", addr), lst, move || {
                     let c = cfg.lock().unwrap();
-                    let host = c.host.clone().unwrap_or_else(|| format!("{}", addr));
+                    c.start_worker();
+
+                    let app_config = c.app_config(false, addr);
 
                     let svc = HttpService::build()
                         .keep_alive(c.keep_alive)
                         .client_timeout(c.client_timeout)
+                        .h2_settings(c.h2_settings)
+                        .allow_obsolete_header_folding(c.allow_obsolete_header_folding)
+                        .respond_after_half_close(c.respond_after_half_close)
+                        .advertise_keep_alive(c.advertise_keep_alive)
+                        .stats(c.stats.clone())
+                        .max_connection_age(c.max_connection_age)
+                        .graceful_shutdown(c.shutdown.clone())
                         .local_addr(addr);
 
+                    let svc = if c.allow_h2c { svc.allow_h2c() } else { svc };
+
                     let svc = if let Some(handler) = on_connect_fn.clone() {
                         svc.on_connect_ext(move |io: &_, ext: _| (handler)(io as &dyn Any, ext))
                     } else {
                         svc
                     };
 
-                    svc.finish(map_config(factory(), move |_| {
-                        AppConfig::new(false, addr, host.clone())
-                    }))
-                    .tcp()
+                    svc.finish(map_config(factory(), move |_| app_config.clone()))
+                        .tcp()
                 })?;
         Ok(self)
     }
@@ -326,6 +648,7 @@ where
         self.sockets.push(Socket {
             addr,
             scheme: "https",
+            uds: false,
         });
 
         let on_connect_fn = self.on_connect_fn.clone();
@@ -334,25 +657,37 @@ where
             self.builder
                 .listen(format!("actix-web-service-{}", addr), lst, move || {
                     let c = cfg.lock().unwrap();
-                    let host = c.host.clone().unwrap_or_else(|| format!("{}", addr));
+                    c.start_worker();
+
+                    let app_config = c.app_config(true, addr);
 
                     let svc = HttpService::build()
                         .keep_alive(c.keep_alive)
                         .client_timeout(c.client_timeout)
+                        .h2_settings(c.h2_settings)
+                        .allow_obsolete_header_folding(c.allow_obsolete_header_folding)
+                        .respond_after_half_close(c.respond_after_half_close)
+                        .advertise_keep_alive(c.advertise_keep_alive)
+                        .stats(c.stats.clone())
+                        .max_connection_age(c.max_connection_age)
+                        .graceful_shutdown(c.shutdown.clone())
                         .client_disconnect(c.client_shutdown);
 
-                    let svc = if let Some(handler) = on_connect_fn.clone() {
-                        svc.on_connect_ext(move |io: &_, ext: _| {
-                            (&*handler)(io as &dyn Any, ext)
-                        })
-                    } else {
-                        svc
-                    };
-
-                    svc.finish(map_config(factory(), move |_| {
-                        AppConfig::new(true, addr, host.clone())
-                    }))
-                    .openssl(acceptor.clone())
+                    let on_connect_fn = on_connect_fn.clone();
+                    let svc = svc.on_connect_ext(
+                        move |io: &SslStream<TcpStream>, ext: &mut Extensions| {
+                            if let Some(certs) = openssl_peer_certificates(io.ssl()) {
+                                ext.insert(certs);
+                            }
+
+                            if let Some(ref handler) = on_connect_fn {
+                                (handler)(io as &dyn Any, ext)
+                            }
+                        },
+                    );
+
+                    svc.finish(map_config(factory(), move |_| app_config.clone()))
+                        .openssl(acceptor.clone())
                 })?;
         Ok(self)
     }
@@ -366,7 +701,7 @@ where
         lst: net::TcpListener,
         config: RustlsServerConfig,
     ) -> io::Result<Self> {
-        self.listen_rustls_inner(lst, config)
+        self.listen_rustls_inner(lst, config, None)
     }
 
     #[cfg(feature = "rustls")]
@@ -374,6 +709,7 @@ where
         mut self,
         lst: net::TcpListener,
         config: RustlsServerConfig,
+        client_auth: Option<Arc<ClientAuthVerifier>>,
     ) -> io::Result<Self> {
         let factory = self.factory.clone();
         let cfg = self.config.clone();
@@ -381,6 +717,7 @@ where
         self.sockets.push(Socket {
             addr,
             scheme: "https",
+            uds: false,
         });
 
         let on_connect_fn = self.on_connect_fn.clone();
@@ -389,23 +726,41 @@ where
             self.builder
                 .listen(format!("actix-web-service-{}", addr), lst, move || {
                     let c = cfg.lock().unwrap();
-                    let host = c.host.clone().unwrap_or_else(|| format!("{}", addr));
+                    c.start_worker();
+
+                    let app_config = c.app_config(true, addr);
 
                     let svc = HttpService::build()
                         .keep_alive(c.keep_alive)
                         .client_timeout(c.client_timeout)
+                        .h2_settings(c.h2_settings)
+                        .allow_obsolete_header_folding(c.allow_obsolete_header_folding)
+                        .respond_after_half_close(c.respond_after_half_close)
+                        .advertise_keep_alive(c.advertise_keep_alive)
+                        .stats(c.stats.clone())
+                        .max_connection_age(c.max_connection_age)
+                        .graceful_shutdown(c.shutdown.clone())
                         .client_disconnect(c.client_shutdown);
 
-                    let svc = if let Some(handler) = on_connect_fn.clone() {
-                        svc.on_connect_ext(move |io: &_, ext: _| (handler)(io as &dyn Any, ext))
-                    } else {
-                        svc
-                    };
-
-                    svc.finish(map_config(factory(), move |_| {
-                        AppConfig::new(true, addr, host.clone())
-                    }))
-                    .rustls(config.clone())
+                    let on_connect_fn = on_connect_fn.clone();
+                    let client_auth = client_auth.clone();
+                    let svc = svc.on_connect_ext(
+                        move |io: &TlsStream<TcpStream>, ext: &mut Extensions| {
+                            let (_, session) = io.get_ref();
+                            if let Some(certs) =
+                                rustls_peer_certificates(session, client_auth.as_deref())
+                            {
+                                ext.insert(certs);
+                            }
+
+                            if let Some(ref handler) = on_connect_fn {
+                                (handler)(io as &dyn Any, ext)
+                            }
+                        },
+                    );
+
+                    svc.finish(map_config(factory(), move |_| app_config.clone()))
+                        .rustls(config.clone())
                 })?;
         Ok(self)
     }
@@ -413,6 +768,10 @@ where
     /// The socket address to bind
     ///
     /// To bind multiple addresses this method can be called multiple times.
+    ///
+    /// Every address that `addr` resolves to is bound. Succeeds if at least one of them could be
+    /// bound; failures of the other addresses are logged. Use [`bind_all`](Self::bind_all) to
+    /// require all of them.
     pub fn bind<A: net::ToSocketAddrs>(mut self, addr: A) -> io::Result<Self> {
         let sockets = self.bind2(addr)?;
 
@@ -423,22 +782,80 @@ where
         Ok(self)
     }
 
+    /// Bind every address of `addrs`, failing unless all of them can be bound.
+    ///
+    /// Each item may resolve to multiple addresses, e.g. a host name with IPv4 and IPv6 addresses.
+    /// The error names every address that could not be bound; nothing is bound in that case.
+    ///
+    /// ```no_run
+    /// use actix_web::{App, HttpServer};
+    ///
+    /// # fn run() -> std::io::Result<()> {
+    /// let srv = HttpServer::new(|| App::new())
+    ///     .bind_all(vec!["127.0.0.1:8080", "[::1]:8080"])?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn bind_all<T>(mut self, addrs: T) -> io::Result<Self>
+    where
+        T: IntoIterator,
+        T::Item: net::ToSocketAddrs,
+    {
+        let mut sockets = Vec::new();
+        let mut failed = Vec::new();
+
+        for addr in addrs {
+            for (addr, res) in self.bind_each(addr)? {
+                match res {
+                    Ok(lst) => sockets.push(lst),
+                    Err(err) => failed.push((addr, err)),
+                }
+            }
+        }
+
+        if !failed.is_empty() {
+            let kind = failed[0].1.kind();
+            let msg = failed
+                .iter()
+                .map(|(addr, err)| format!("{}: {}", addr, err))
+                .collect::<Vec<_>>()
+                .join(", ");
+
+            return Err(io::Error::new(
+                kind,
+                format!("Can not bind to address. {}", msg),
+            ));
+        }
+
+        if sockets.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::Other,
+                "Can not bind to address.",
+            ));
+        }
+
+        for lst in sockets {
+            self = self.listen(lst)?;
+        }
+
+        Ok(self)
+    }
+
     fn bind2<A: net::ToSocketAddrs>(&self, addr: A) -> io::Result<Vec<net::TcpListener>> {
         let mut err = None;
-        let mut success = false;
         let mut sockets = Vec::new();
 
-        for addr in addr.to_socket_addrs()? {
-            match create_tcp_listener(addr, self.backlog) {
-                Ok(lst) => {
-                    success = true;
-                    sockets.push(lst);
+        for (addr, res) in self.bind_each(addr)? {
+            match res {
+                Ok(lst) => sockets.push(lst),
+                Err(e) => {
+                    log::warn!("Can not bind to address {}: {}", addr, e);
+                    err = Some(e);
                 }
-                Err(e) => err = Some(e),
             }
         }
 
-        if !success {
+        if sockets.is_empty() {
             if let Some(e) = err.take() {
                 Err(e)
             } else {
@@ -452,6 +869,17 @@ where
         }
     }
 
+    /// Attempts to bind every address `addr` resolves to.
+    fn bind_each<A: net::ToSocketAddrs>(
+        &self,
+        addr: A,
+    ) -> io::Result<Vec<(net::SocketAddr, io::Result<net::TcpListener>)>> {
+        Ok(addr
+            .to_socket_addrs()?
+            .map(|addr| (addr, create_tcp_listener(addr, self.backlog)))
+            .collect())
+    }
+
     #[cfg(feature = "openssl")]
     /// Start listening for incoming tls connections.
     ///
@@ -470,6 +898,56 @@ where
         Ok(self)
     }
 
+    #[cfg(feature = "openssl")]
+    /// Start listening for incoming tls connections, using an acceptor that can be reloaded while
+    /// the server is running.
+    ///
+    /// See [`ReloadableSslAcceptor`](crate::tls::ReloadableSslAcceptor).
+    pub fn bind_openssl_with_reload<A>(
+        mut self,
+        addr: A,
+        acceptor: ReloadableSslAcceptor,
+    ) -> io::Result<Self>
+    where
+        A: net::ToSocketAddrs,
+    {
+        let sockets = self.bind2(addr)?;
+
+        for lst in sockets {
+            self = self.listen_ssl_inner(lst, acceptor.acceptor())?;
+        }
+
+        Ok(self)
+    }
+
+    #[cfg(feature = "openssl")]
+    /// Start listening for incoming tls connections, asking clients for a certificate.
+    ///
+    /// Client certificates are verified against the CA certificates configured on `builder`.
+    /// See [`ClientCertAuth`](crate::tls::ClientCertAuth).
+    ///
+    /// This method sets alpn protocols to "h2" and "http/1.1"
+    pub fn bind_openssl_with_client_auth<A>(
+        mut self,
+        addr: A,
+        mut builder: SslAcceptorBuilder,
+        auth: ClientCertAuth,
+    ) -> io::Result<Self>
+    where
+        A: net::ToSocketAddrs,
+    {
+        auth.configure_openssl(&mut builder)?;
+
+        let sockets = self.bind2(addr)?;
+        let acceptor = openssl_acceptor(builder)?;
+
+        for lst in sockets {
+            self = self.listen_ssl_inner(lst, acceptor.clone())?;
+        }
+
+        Ok(self)
+    }
+
     #[cfg(feature = "rustls")]
     /// Start listening for incoming tls connections.
     ///
@@ -481,7 +959,45 @@ where
     ) -> io::Result<Self> {
         let sockets = self.bind2(addr)?;
         for lst in sockets {
-            self = self.listen_rustls_inner(lst, config.clone())?;
+            self = self.listen_rustls_inner(lst, config.clone(), None)?;
+        }
+        Ok(self)
+    }
+
+    #[cfg(feature = "rustls")]
+    /// Start listening for incoming tls connections, using a certificate that can be reloaded
+    /// while the server is running.
+    ///
+    /// `cert` replaces the certificate resolver of `config`. See
+    /// [`ReloadableCert`](crate::tls::ReloadableCert).
+    pub fn bind_rustls_with_reload<A: net::ToSocketAddrs>(
+        self,
+        addr: A,
+        mut config: RustlsServerConfig,
+        cert: ReloadableCert,
+    ) -> io::Result<Self> {
+        config.cert_resolver = Arc::new(cert);
+        self.bind_rustls(addr, config)
+    }
+
+    #[cfg(feature = "rustls")]
+    /// Start listening for incoming tls connections, asking clients for a certificate.
+    ///
+    /// Client certificates are verified against `roots` and `auth` replaces the client
+    /// certificate verifier of `config`. See [`ClientCertAuth`](crate::tls::ClientCertAuth).
+    pub fn bind_rustls_with_client_auth<A: net::ToSocketAddrs>(
+        mut self,
+        addr: A,
+        mut config: RustlsServerConfig,
+        roots: RootCertStore,
+        auth: ClientCertAuth,
+    ) -> io::Result<Self> {
+        let verifier = Arc::new(ClientAuthVerifier::new(roots, auth));
+        config.set_client_certificate_verifier(verifier.clone());
+
+        let sockets = self.bind2(addr)?;
+        for lst in sockets {
+            self = self.listen_rustls_inner(lst, config.clone(), Some(verifier.clone()))?;
         }
         Ok(self)
     }
@@ -498,6 +1014,7 @@ where
         self.sockets.push(Socket {
             scheme: "http",
             addr: socket_addr,
+            uds: true,
         });
 
         let addr = format!("actix-web-service-{:?}", lst.local_addr()?);
@@ -505,16 +1022,21 @@ where
 
         self.builder = self.builder.listen_uds(addr, lst, move || {
             let c = cfg.lock().unwrap();
-            let config = AppConfig::new(
-                false,
-                socket_addr,
-                c.host.clone().unwrap_or_else(|| format!("{}", socket_addr)),
-            );
+            c.start_worker();
+
+            let config = c.app_config(false, socket_addr);
 
             pipeline_factory(|io: UnixStream| ok((io, Protocol::Http1, None))).and_then({
                 let svc = HttpService::build()
                     .keep_alive(c.keep_alive)
-                    .client_timeout(c.client_timeout);
+                    .client_timeout(c.client_timeout)
+                    .h2_settings(c.h2_settings)
+                    .allow_obsolete_header_folding(c.allow_obsolete_header_folding)
+                    .respond_after_half_close(c.respond_after_half_close)
+                    .advertise_keep_alive(c.advertise_keep_alive)
+                    .stats(c.stats.clone())
+                    .max_connection_age(c.max_connection_age)
+                    .graceful_shutdown(c.shutdown.clone());
 
                 let svc = if let Some(handler) = on_connect_fn.clone() {
                     svc.on_connect_ext(move |io: &_, ext: _| (&*handler)(io as &dyn Any, ext))
@@ -543,6 +1065,7 @@ where
         self.sockets.push(Socket {
             scheme: "http",
             addr: socket_addr,
+            uds: true,
         });
 
         self.builder = self.builder.bind_uds(
@@ -550,15 +1073,20 @@ where
             addr,
             move || {
                 let c = cfg.lock().unwrap();
-                let config = AppConfig::new(
-                    false,
-                    socket_addr,
-                    c.host.clone().unwrap_or_else(|| format!("{}", socket_addr)),
-                );
+                c.start_worker();
+
+                let config = c.app_config(false, socket_addr);
                 pipeline_factory(|io: UnixStream| ok((io, Protocol::Http1, None))).and_then(
                     HttpService::build()
                         .keep_alive(c.keep_alive)
                         .client_timeout(c.client_timeout)
+                        .h2_settings(c.h2_settings)
+                        .allow_obsolete_header_folding(c.allow_obsolete_header_folding)
+                        .respond_after_half_close(c.respond_after_half_close)
+                        .advertise_keep_alive(c.advertise_keep_alive)
+                        .stats(c.stats.clone())
+                        .max_connection_age(c.max_connection_age)
+                        .graceful_shutdown(c.shutdown.clone())
                         .finish(map_config(factory(), move |_| config.clone())),
                 )
             },
@@ -599,8 +1127,205 @@ where
     ///         .await
     /// }
     /// ```
-    pub fn run(self) -> Server {
-        self.builder.run()
+    pub fn run(mut self) -> Server {
+        let apps = self.workers * self.sockets.len();
+
+        let (shutdown, worker_hooks, handle) = {
+            let c = self.config.lock().unwrap();
+
+            if let Some(ref health_check) = c.health_check {
+                health_check.start(apps, c.shutdown.clone());
+            }
+
+            (c.shutdown.clone(), c.worker_hooks.clone(), c.handle.clone())
+        };
+
+        // with stop hooks, stop signals are handled below so hooks run before workers stop
+        let stop_hooks = worker_hooks.has_stop_hooks();
+        if stop_hooks && self.handle_signals {
+            self.builder = self.builder.disable_signals();
+        }
+
+        #[cfg(unix)]
+        {
+            if self.handle_signals && !stop_hooks {
+                let shutdown = self.config.lock().unwrap().shutdown.clone();
+
+                // SIGTERM starts a graceful stop; let connections know right away
+                actix_rt::spawn(async move {
+                    use actix_rt::signal::unix::{signal, SignalKind};
+
+                    if let Ok(mut sigterm) = signal(SignalKind::terminate()) {
+                        if sigterm.recv().await.is_some() {
+                            shutdown.shutdown();
+                        }
+                    }
+                });
+            }
+        }
+
+        let srv = self.builder.run();
+        handle.start(srv.clone(), shutdown.clone(), stop_hooks);
+
+        if stop_hooks {
+            let srv = srv.clone();
+            let handle_signals = self.handle_signals;
+            let timeout = self.shutdown_timeout;
+
+            actix_rt::spawn(async move {
+                let signalled = if handle_signals {
+                    match select(Box::pin(shutdown.wait()), Box::pin(stop_signal())).await {
+                        Either::Left(_) => false,
+                        Either::Right(_) => {
+                            shutdown.shutdown();
+                            true
+                        }
+                    }
+                } else {
+                    shutdown.wait().await;
+                    false
+                };
+
+                if actix_rt::time::timeout(timeout, worker_hooks.stopped(apps))
+                    .await
+                    .is_err()
+                {
+                    log::warn!("Worker stop hooks did not finish within shutdown timeout");
+                }
+
+                srv.stop(true).await;
+
+                if signalled {
+                    System::current().stop();
+                }
+            });
+        }
+
+        srv
+    }
+}
+
+thread_local! {
+    static WORKER_THREAD_SET_UP: Cell<bool> = Cell::new(false);
+}
+
+/// Naming and CPU affinity of worker threads.
+#[derive(Clone, Default)]
+struct WorkerThread {
+    name_prefix: Option<String>,
+    affinity: bool,
+    started: Arc<AtomicUsize>,
+}
+
+impl WorkerThread {
+    /// Sets up the current worker thread, once per thread.
+    fn setup(&self) {
+        if self.name_prefix.is_none() && !self.affinity {
+            return;
+        }
+
+        if WORKER_THREAD_SET_UP.with(|set_up| set_up.replace(true)) {
+            return;
+        }
+
+        let idx = self.started.fetch_add(1, Ordering::Relaxed);
+
+        if let Some(ref prefix) = self.name_prefix {
+            set_thread_name(&format!("{}{}", prefix, idx));
+        }
+
+        if self.affinity {
+            set_thread_affinity(idx);
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn set_thread_name(name: &str) {
+    // the kernel limits thread names to 15 bytes
+    let mut len = cmp::min(name.len(), 15);
+    while !name.is_char_boundary(len) {
+        len -= 1;
+    }
+
+    match std::ffi::CString::new(&name[..len]) {
+        Ok(name) => {
+            // SAFETY: the name is a valid nul-terminated string
+            let res = unsafe { libc::prctl(libc::PR_SET_NAME, name.as_ptr(), 0, 0, 0) };
+            if res != 0 {
+                log::warn!(
+                    "Failed to set worker thread name: {}",
+                    io::Error::last_os_error()
+                );
+            }
+        }
+        Err(_) => log::warn!("Worker thread name contains a nul byte"),
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn set_thread_name(_name: &str) {}
+
+/// Pins the current thread to the `idx`-th CPU the process may run on, wrapping around.
+#[cfg(target_os = "linux")]
+fn set_thread_affinity(idx: usize) {
+    use std::mem;
+
+    // SAFETY: cpu sets are plain bit sets and the calls only access the given set
+    unsafe {
+        let mut allowed: libc::cpu_set_t = mem::zeroed();
+        if libc::sched_getaffinity(0, mem::size_of::<libc::cpu_set_t>(), &mut allowed) != 0 {
+            log::warn!("Failed to get CPU affinity: {}", io::Error::last_os_error());
+            return;
+        }
+
+        let cpus = (0..libc::CPU_SETSIZE as usize)
+            .filter(|cpu| libc::CPU_ISSET(*cpu, &allowed))
+            .collect::<Vec<_>>();
+        if cpus.is_empty() {
+            return;
+        }
+
+        let mut set: libc::cpu_set_t = mem::zeroed();
+        libc::CPU_SET(cpus[idx % cpus.len()], &mut set);
+        if libc::sched_setaffinity(0, mem::size_of::<libc::cpu_set_t>(), &set) != 0 {
+            log::warn!(
+                "Failed to set worker CPU affinity: {}",
+                io::Error::last_os_error()
+            );
+        }
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn set_thread_affinity(_idx: usize) {}
+
+/// Resolves when the process receives a signal asking it to stop.
+async fn stop_signal() {
+    #[cfg(unix)]
+    {
+        use actix_rt::signal::unix::{signal, SignalKind};
+
+        let signals = [
+            SignalKind::interrupt(),
+            SignalKind::terminate(),
+            SignalKind::quit(),
+        ]
+        .iter()
+        .filter_map(|kind| signal(*kind).ok())
+        .map(|mut signal| Box::pin(async move { signal.recv().await }))
+        .collect::<Vec<_>>();
+
+        if signals.is_empty() {
+            futures_util::future::pending::<()>().await;
+        }
+
+        select_all(signals).await;
+    }
+
+    #[cfg(not(unix))]
+    {
+        let _ = actix_rt::signal::ctrl_c().await;
     }
 }
 
@@ -621,7 +1346,7 @@ fn create_tcp_listener(addr: net::SocketAddr, backlog: u32) -> io::Result<net::T
 
 #[cfg(feature = "openssl")]
 /// Configure `SslAcceptorBuilder` with custom server flags.
-fn openssl_acceptor(mut builder: SslAcceptorBuilder) -> io::Result<SslAcceptor> {
+pub(crate) fn openssl_acceptor(mut builder: SslAcceptorBuilder) -> io::Result<SslAcceptor> {
     builder.set_alpn_select_callback(|_, protocols| {
         const H2: &[u8] = b"\x02h2";
         const H11: &[u8] = b"\x08http/1.1";
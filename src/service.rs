@@ -2,13 +2,15 @@ use std::cell::{Ref, RefMut};
 use std::rc::Rc;
 use std::{fmt, net};
 
-use actix_http::body::{Body, MessageBody, ResponseBody};
-use actix_http::http::{HeaderMap, Method, StatusCode, Uri, Version};
+use actix_http::body::{Body, BoxBody, EitherBody, MessageBody, ResponseBody};
+use actix_http::error::PayloadError;
+use actix_http::http::{header, HeaderMap, Method, StatusCode, Uri, Version};
 use actix_http::{
     Error, Extensions, HttpMessage, Payload, PayloadStream, RequestHead, Response, ResponseHead,
 };
 use actix_router::{IntoPattern, Path, Resource, ResourceDef, Url};
 use actix_service::{IntoServiceFactory, ServiceFactory};
+use bytes::{Bytes, BytesMut};
 
 use crate::config::{AppConfig, AppService};
 use crate::dev::insert_slash;
@@ -16,6 +18,7 @@ use crate::guard::Guard;
 use crate::info::ConnectionInfo;
 use crate::request::HttpRequest;
 use crate::rmap::ResourceMap;
+use crate::types::QueryParams;
 
 pub trait HttpServiceFactory {
     fn register(self, config: &mut AppService);
@@ -55,6 +58,12 @@ where
     }
 }
 
+/// Request body buffered by [`ServiceRequest::extract_buffered_body`].
+///
+/// A private type, so that the body can not be confused with other `Bytes` in the extensions.
+#[derive(Clone)]
+struct BufferedBody(Bytes);
+
 /// An service http request
 ///
 /// ServiceRequest allows mutable access to request's internal structures
@@ -75,6 +84,27 @@ impl ServiceRequest {
         (self.req, self.payload)
     }
 
+    /// Get reference to inner parts.
+    #[inline]
+    pub fn parts(&self) -> (&HttpRequest, &Payload) {
+        (&self.req, &self.payload)
+    }
+
+    /// Get mutable access to inner parts.
+    ///
+    /// The `HttpRequest` may be cloned and held across await points, unlike a borrow of the
+    /// request extensions.
+    #[inline]
+    pub fn parts_mut(&mut self) -> (&mut HttpRequest, &mut Payload) {
+        (&mut self.req, &mut self.payload)
+    }
+
+    /// Get reference to the inner `HttpRequest`.
+    #[inline]
+    pub fn request(&self) -> &HttpRequest {
+        &self.req
+    }
+
     /// Construct request from parts.
     pub fn from_parts(req: HttpRequest, payload: Payload) -> Self {
         Self { req, payload }
@@ -127,6 +157,43 @@ impl ServiceRequest {
         &self.head().method
     }
 
+    /// Returns a copy of the request head with the `GET` method.
+    ///
+    /// Lets routing check `HEAD` requests against the guards of `GET` routes without changing the
+    /// request itself. Extensions are not copied.
+    pub(crate) fn head_as_get(&self) -> RequestHead {
+        let head = self.head();
+
+        let mut get = RequestHead::default();
+        get.uri = head.uri.clone();
+        get.method = Method::GET;
+        get.version = head.version;
+        get.headers = head.headers.clone();
+        get.peer_addr = head.peer_addr;
+        get
+    }
+
+    /// Replace the request's URI.
+    ///
+    /// Path parameters matched so far and the parsed query parameters cached by
+    /// [`query`](Self::query()) are cleared, so routing that happens after this call uses the new
+    /// URI. Rewrite URIs from middleware registered on the `App`, which runs before any routing.
+    pub fn set_uri(&mut self, uri: Uri) {
+        let path = self.req.match_info_mut();
+        path.get_mut().update(&uri);
+        path.reset();
+
+        self.req.extensions_mut().remove::<QueryParams>();
+        self.req.head_mut().uri = uri;
+    }
+
+    /// Replace the request's method.
+    ///
+    /// Route guards evaluated after this call see the new method.
+    pub fn set_method(&mut self, method: Method) {
+        self.req.head_mut().method = method;
+    }
+
     /// Read the Request Version.
     #[inline]
     pub fn version(&self) -> Version {
@@ -163,6 +230,11 @@ impl ServiceRequest {
         }
     }
 
+    /// The parameters of the query string in the URL, see [`HttpRequest::query`].
+    pub fn query(&self) -> QueryParams {
+        self.req.query()
+    }
+
     /// Peer socket address.
     ///
     /// Peer address is the directly connected peer's socket address. If a proxy is used in front of
@@ -179,7 +251,7 @@ impl ServiceRequest {
     /// Get *ConnectionInfo* for the current request.
     #[inline]
     pub fn connection_info(&self) -> Ref<'_, ConnectionInfo> {
-        ConnectionInfo::get(self.head(), &*self.app_config())
+        ConnectionInfo::get_with(self.head(), &*self.app_config(), self.app_data())
     }
 
     /// Get a reference to the Path parameters.
@@ -234,11 +306,82 @@ impl ServiceRequest {
         None
     }
 
+    /// Counterpart to [`HttpRequest::app_data_or_data`](super::HttpRequest::app_data_or_data()).
+    #[inline]
+    pub fn app_data_or_data<T: 'static>(&self) -> Option<&T> {
+        self.req.app_data_or_data()
+    }
+
+    /// Counterpart to [`HttpRequest::app_data_layers`](super::HttpRequest::app_data_layers()).
+    #[inline]
+    pub fn app_data_layers<T: 'static>(&self) -> impl Iterator<Item = &T> {
+        self.req.app_data_layers()
+    }
+
+    /// Counterpart to [`HttpRequest::conn_data`](super::HttpRequest::conn_data()).
+    #[inline]
+    pub fn conn_data<T: 'static>(&self) -> Option<&T> {
+        self.req.conn_data()
+    }
+
+    /// Insert an item into the request extensions, returning the previous value of that type.
+    ///
+    /// The extensions borrow is released before this method returns so it is safe to call from
+    /// middleware that later awaits the inner service.
+    #[inline]
+    pub fn extensions_insert<T: 'static>(&self, val: T) -> Option<T> {
+        self.req.extensions_mut().insert(val)
+    }
+
     /// Set request payload.
     pub fn set_payload(&mut self, payload: Payload) {
         self.payload = payload;
     }
 
+    /// Read the request payload into memory, keeping it available to extractors.
+    ///
+    /// The body is stored in the request extensions and the payload is replaced with one that
+    /// replays it, so middleware can inspect the body (e.g. to verify a signature) while handlers
+    /// still extract it as usual. Calling this again returns the stored body.
+    ///
+    /// The body is buffered as received, without decompression. Returns
+    /// `PayloadError::Overflow` if it is larger than `limit` bytes; the payload is then partially
+    /// consumed.
+    pub async fn extract_buffered_body(&mut self, limit: usize) -> Result<Bytes, PayloadError> {
+        let buffered = self.req.extensions().get::<BufferedBody>().cloned();
+
+        if let Some(BufferedBody(body)) = buffered {
+            self.payload = Payload::from_bytes(body.clone());
+            return Ok(body);
+        }
+
+        if let Some(len) = self.headers().get(&header::CONTENT_LENGTH) {
+            match len.to_str().ok().and_then(|len| len.parse::<usize>().ok()) {
+                Some(len) if len > limit => return Err(PayloadError::Overflow),
+                Some(_) => {}
+                None => return Err(PayloadError::UnknownLength),
+            }
+        }
+
+        let mut buf = BytesMut::new();
+
+        while let Some(chunk) = futures_util::StreamExt::next(&mut self.payload).await {
+            let chunk = chunk?;
+
+            if buf.len() + chunk.len() > limit {
+                return Err(PayloadError::Overflow);
+            }
+
+            buf.extend_from_slice(&chunk);
+        }
+
+        let body = buf.freeze();
+        self.req.extensions_mut().insert(BufferedBody(body.clone()));
+        self.payload = Payload::from_bytes(body.clone());
+
+        Ok(body)
+    }
+
     /// Add data container to request's resolution set.
     ///
     /// In middleware, prefer [`extensions_mut`](ServiceRequest::extensions_mut) for request-local
@@ -410,6 +553,36 @@ impl<B> ServiceResponse<B> {
             request: self.request,
         }
     }
+
+    /// Wrap the body as the left variant of an [`EitherBody`].
+    ///
+    /// Middleware that only sometimes replaces the body can use this for the responses it passes
+    /// through unchanged and [`map_into_right_body`](Self::map_into_right_body) for the others.
+    pub fn map_into_left_body<R>(self) -> ServiceResponse<EitherBody<B, R>> {
+        self.map_body(|_, body| match body {
+            ResponseBody::Body(body) => ResponseBody::Body(EitherBody::Left(body)),
+            ResponseBody::Other(body) => ResponseBody::Other(body),
+        })
+    }
+
+    /// Wrap the body as the right variant of an [`EitherBody`].
+    pub fn map_into_right_body<L>(self) -> ServiceResponse<EitherBody<L, B>> {
+        self.map_body(|_, body| match body {
+            ResponseBody::Body(body) => ResponseBody::Body(EitherBody::Right(body)),
+            ResponseBody::Other(body) => ResponseBody::Other(body),
+        })
+    }
+
+    /// Box the body, erasing its type.
+    pub fn map_into_boxed_body(self) -> ServiceResponse<BoxBody>
+    where
+        B: MessageBody + 'static,
+    {
+        self.map_body(|_, body| match body {
+            ResponseBody::Body(body) => ResponseBody::Body(body.boxed()),
+            ResponseBody::Other(body) => ResponseBody::Other(body),
+        })
+    }
 }
 
 impl<B> From<ServiceResponse<B>> for Response<B> {
@@ -651,6 +824,78 @@ mod tests {
         assert_eq!(resp.status(), http::StatusCode::OK);
     }
 
+    #[actix_rt::test]
+    async fn test_extensions_insert_across_await() {
+        use std::time::Instant;
+
+        struct Started(Instant);
+
+        let srv = init_service(
+            App::new()
+                .wrap_fn(|req, srv| {
+                    assert!(req.extensions_insert(Started(Instant::now())).is_none());
+                    let fut = srv.call(req);
+
+                    async move {
+                        let res = fut.await?;
+                        let elapsed = res
+                            .request()
+                            .extensions()
+                            .get::<Started>()
+                            .map(|started| started.0.elapsed());
+                        assert!(elapsed.is_some());
+                        Ok(res)
+                    }
+                })
+                .service(web::resource("/test").to(|req: HttpRequest| async move {
+                    assert!(req.extensions().contains::<Started>());
+                    HttpResponse::Ok().finish()
+                })),
+        )
+        .await;
+
+        let req = TestRequest::with_uri("/test").to_request();
+        let resp = srv.call(req).await.unwrap();
+        assert_eq!(resp.status(), http::StatusCode::OK);
+    }
+
+    #[actix_rt::test]
+    async fn test_set_uri() {
+        let srv = init_service(
+            App::new()
+                .wrap_fn(|mut req, srv| {
+                    // parsed query is cached, the handler must not see stale params
+                    assert_eq!(req.query().get("v").as_deref(), Some("1"));
+
+                    req.set_uri(Uri::from_static("/v2/item?v=2"));
+                    req.set_method(http::Method::PUT);
+                    srv.call(req)
+                })
+                .route(
+                    "/v2/{name}",
+                    web::put().to(|req: HttpRequest| async move {
+                        assert_eq!(req.match_info().get("name"), Some("item"));
+                        assert_eq!(req.query().get("v").as_deref(), Some("2"));
+                        HttpResponse::Ok().finish()
+                    }),
+                ),
+        )
+        .await;
+
+        let req = TestRequest::with_uri("/item?v=1").to_request();
+        let resp = srv.call(req).await.unwrap();
+        assert_eq!(resp.status(), http::StatusCode::OK);
+    }
+
+    #[test]
+    fn test_conn_data_separate_from_extensions() {
+        let req = TestRequest::default().to_srv_request();
+        req.extensions_insert(42u32);
+        assert_eq!(req.conn_data::<u32>(), None);
+        assert_eq!(req.extensions().get::<u32>(), Some(&42));
+        assert_eq!(req.request().extensions().get::<u32>(), Some(&42));
+    }
+
     #[test]
     fn test_fmt_debug() {
         let req = TestRequest::get()
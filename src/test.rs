@@ -1,6 +1,7 @@
 //! Various helpers for Actix applications to use during testing.
 
 use std::net::SocketAddr;
+use std::pin::Pin;
 use std::rc::Rc;
 use std::sync::mpsc;
 use std::{fmt, net, thread, time};
@@ -8,10 +9,10 @@ use std::{fmt, net, thread, time};
 use actix_codec::{AsyncRead, AsyncWrite, Framed};
 #[cfg(feature = "cookies")]
 use actix_http::cookie::Cookie;
-use actix_http::http::header::{ContentType, IntoHeaderPair};
-use actix_http::http::{Method, StatusCode, Uri, Version};
+use actix_http::http::header::{AsHeaderName, ContentType, IntoHeaderPair};
+use actix_http::http::{HeaderMap, Method, StatusCode, Uri, Version};
 use actix_http::test::TestRequest as HttpTestRequest;
-use actix_http::{ws, Extensions, HttpService, Request};
+use actix_http::{ws, Extensions, HttpMessage, HttpService, Request};
 use actix_router::{Path, ResourceDef, Url};
 use actix_rt::{time::sleep, System};
 use actix_service::{map_config, IntoService, IntoServiceFactory, Service, ServiceFactory};
@@ -31,6 +32,7 @@ use crate::app_service::AppInitServiceState;
 use crate::config::AppConfig;
 use crate::data::Data;
 use crate::dev::{Body, MessageBody, Payload, Server};
+use crate::error::JsonPayloadError;
 use crate::rmap::ResourceMap;
 use crate::service::{ServiceRequest, ServiceResponse};
 use crate::{Error, HttpRequest, HttpResponse};
@@ -86,8 +88,9 @@ where
         .expect("service initilization failed")
 }
 
-/// Fallible version of init_service that allows testing data factory errors.
-pub(crate) async fn try_init_service<R, S, B, E>(
+/// Fallible version of [`init_service`] that allows testing initialization errors, such as
+/// failing data factories or [`App::try_configure`](crate::App::try_configure) functions.
+pub async fn try_init_service<R, S, B, E>(
     app: R,
 ) -> Result<impl Service<Request, Response = ServiceResponse<B>, Error = E>, S::InitError>
 where
@@ -129,6 +132,152 @@ where
     app.call(req).await.unwrap()
 }
 
+/// Calls service and waits for response future completion, returning service errors instead of
+/// panicking.
+///
+/// ```rust
+/// use actix_web::{test, web, App, HttpResponse, http::StatusCode};
+///
+/// #[actix_rt::test]
+/// async fn test_response() {
+///     let app = test::init_service(
+///         App::new().route("/test", web::get().to(|| HttpResponse::Ok()))
+///     ).await;
+///
+///     let req = test::TestRequest::with_uri("/test").to_request();
+///     let resp = test::try_call_service(&app, req).await.unwrap();
+///     assert_eq!(resp.status(), StatusCode::OK);
+/// }
+/// ```
+pub async fn try_call_service<S, R, B, E>(app: &S, req: R) -> Result<S::Response, E>
+where
+    S: Service<R, Response = ServiceResponse<B>, Error = E>,
+{
+    app.call(req).await
+}
+
+/// Calls service and returns the response body.
+///
+/// Unlike [`read_response`], a failure to read the body panics with the response status,
+/// content type and the part of the body that was read.
+pub async fn call_and_read_body<S, B>(app: &S, req: Request) -> Bytes
+where
+    S: Service<Request, Response = ServiceResponse<B>, Error = Error>,
+    B: MessageBody + Unpin,
+{
+    let res = app
+        .call(req)
+        .await
+        .unwrap_or_else(|err| panic!("call_and_read_body failed at application call: {}", err));
+
+    let (_, _, body) = read_described_body(res, "call_and_read_body").await;
+    body
+}
+
+/// Calls service and returns the deserialized JSON response body.
+///
+/// If the body can not be deserialized, the panic message includes the response status,
+/// content type and the first kilobyte of the body.
+///
+/// ```rust
+/// use actix_web::{test, web, App, HttpResponse};
+/// use serde::{Deserialize, Serialize};
+///
+/// #[derive(Serialize, Deserialize)]
+/// struct Person {
+///     name: String,
+/// }
+///
+/// #[actix_rt::test]
+/// async fn test_person() {
+///     let app = test::init_service(App::new().route(
+///         "/person",
+///         web::get().to(|| HttpResponse::Ok().json(&Person { name: "Ferris".to_owned() })),
+///     ))
+///     .await;
+///
+///     let req = test::TestRequest::with_uri("/person").to_request();
+///     let person: Person = test::call_and_read_body_json(&app, req).await;
+///     assert_eq!(person.name, "Ferris");
+/// }
+/// ```
+pub async fn call_and_read_body_json<S, B, T>(app: &S, req: Request) -> T
+where
+    S: Service<Request, Response = ServiceResponse<B>, Error = Error>,
+    B: MessageBody + Unpin,
+    T: DeserializeOwned,
+{
+    let res = app.call(req).await.unwrap_or_else(|err| {
+        panic!(
+            "call_and_read_body_json failed at application call: {}",
+            err
+        )
+    });
+
+    let (status, headers, body) = read_described_body(res, "call_and_read_body_json").await;
+
+    serde_json::from_slice(&body).unwrap_or_else(|err| {
+        panic!(
+            "call_and_read_body_json failed during deserialization: {}\n{}",
+            err,
+            describe_response(status, &headers, &body)
+        )
+    })
+}
+
+/// Reads a response body, panicking with a description of the response on errors.
+async fn read_described_body<B>(
+    mut res: ServiceResponse<B>,
+    helper: &str,
+) -> (StatusCode, HeaderMap, Bytes)
+where
+    B: MessageBody + Unpin,
+{
+    let status = res.status();
+    let headers = res.headers().clone();
+
+    let mut body = res.take_body();
+    let mut bytes = BytesMut::new();
+    while let Some(item) = body.next().await {
+        match item {
+            Ok(chunk) => bytes.extend_from_slice(&chunk),
+            Err(err) => panic!(
+                "{} failed reading response body: {}\n{}",
+                helper,
+                err,
+                describe_response(status, &headers, &bytes)
+            ),
+        }
+    }
+
+    (status, headers, bytes.freeze())
+}
+
+/// Describes a response for the panic messages of test helpers.
+///
+/// Only the first kilobyte of the body is included.
+fn describe_response(status: StatusCode, headers: &HeaderMap, body: &[u8]) -> String {
+    const MAX_BODY: usize = 1024;
+
+    let content_type = headers
+        .get(crate::http::header::CONTENT_TYPE)
+        .and_then(|val| val.to_str().ok())
+        .unwrap_or("<none>");
+
+    let mut desc = format!(
+        "response status: {}\nresponse content-type: {}\nresponse body: {}",
+        status,
+        content_type,
+        String::from_utf8_lossy(&body[..body.len().min(MAX_BODY)])
+    );
+
+    if body.len() > MAX_BODY {
+        desc.push_str(&format!("... ({} bytes total)", body.len()));
+    }
+
+    desc
+}
+
 /// Helper function that returns a response body of a TestRequest
 ///
 /// ```rust
@@ -252,10 +401,15 @@ where
     B: MessageBody + Unpin,
     T: DeserializeOwned,
 {
-    let body = read_body(res).await;
+    let (status, headers, body) = read_described_body(res, "read_body_json").await;
 
-    serde_json::from_slice(&body)
-        .unwrap_or_else(|e| panic!("read_response_json failed during deserialization: {}", e))
+    serde_json::from_slice(&body).unwrap_or_else(|e| {
+        panic!(
+            "read_response_json failed during deserialization: {}\n{}",
+            e,
+            describe_response(status, &headers, &body)
+        )
+    })
 }
 
 pub async fn load_stream<S>(mut stream: S) -> Result<Bytes, Error>
@@ -309,10 +463,187 @@ where
     B: MessageBody + Unpin,
     T: DeserializeOwned,
 {
-    let body = read_response(app, req).await;
+    let res = app
+        .call(req)
+        .await
+        .unwrap_or_else(|_| panic!("read_response_json failed at application call"));
+
+    let (status, headers, body) = read_described_body(res, "read_response_json").await;
+
+    serde_json::from_slice(&body).unwrap_or_else(|e| {
+        panic!(
+            "read_response_json failed during deserialization: {}\n{}",
+            e,
+            describe_response(status, &headers, &body)
+        )
+    })
+}
+
+/// Responses that can be inspected with the test helpers.
+///
+/// Implemented for [`ServiceResponse`], as returned by [`call_service`], and for
+/// [`ClientResponse`], as returned by requests to a [`TestServer`].
+pub trait TestResponse {
+    /// Stream of the response body.
+    type Body: Stream<Item = Result<Bytes, Error>> + Unpin;
+
+    /// Response status code.
+    fn status(&self) -> StatusCode;
+
+    /// Response headers.
+    fn headers(&self) -> &HeaderMap;
+
+    /// Take the response body, leaving an empty body in its place.
+    fn take_body(&mut self) -> Self::Body;
+}
+
+impl<B> TestResponse for ServiceResponse<B>
+where
+    B: MessageBody + Unpin,
+{
+    type Body = crate::dev::ResponseBody<B>;
+
+    fn status(&self) -> StatusCode {
+        ServiceResponse::status(self)
+    }
+
+    fn headers(&self) -> &HeaderMap {
+        ServiceResponse::headers(self)
+    }
+
+    fn take_body(&mut self) -> Self::Body {
+        ServiceResponse::take_body(self)
+    }
+}
+
+impl<S> TestResponse for ClientResponse<S>
+where
+    S: Stream<Item = Result<Bytes, PayloadError>> + Unpin + 'static,
+{
+    type Body = Pin<Box<dyn Stream<Item = Result<Bytes, Error>>>>;
+
+    fn status(&self) -> StatusCode {
+        ClientResponse::status(self)
+    }
+
+    fn headers(&self) -> &HeaderMap {
+        ClientResponse::headers(self)
+    }
+
+    fn take_body(&mut self) -> Self::Body {
+        Box::pin(self.take_payload().map(|item| item.map_err(Error::from)))
+    }
+}
+
+/// Helper function that returns the body of a response as a stream of chunks.
+///
+/// Useful for asserting on streaming responses, such as Server-Sent Events, one chunk at a time
+/// instead of waiting for the whole body.
+///
+/// ```rust
+/// use actix_web::{test, web, App, HttpResponse};
+/// use bytes::Bytes;
+/// use futures_util::{stream, StreamExt as _};
+///
+/// #[actix_rt::test]
+/// async fn test_stream() {
+///     let app = test::init_service(App::new().route(
+///         "/",
+///         web::get().to(|| async {
+///             let chunks = vec![Ok::<_, actix_web::Error>(Bytes::from("a")), Ok(Bytes::from("b"))];
+///             HttpResponse::Ok().streaming(stream::iter(chunks))
+///         }),
+///     ))
+///     .await;
+///
+///     let res = test::call_service(&app, test::TestRequest::get().to_request()).await;
+///     let mut body = test::read_body_stream(res);
+///
+///     assert_eq!(body.next().await.unwrap().unwrap(), Bytes::from_static(b"a"));
+///     assert_eq!(body.next().await.unwrap().unwrap(), Bytes::from_static(b"b"));
+///     assert!(body.next().await.is_none());
+/// }
+/// ```
+pub fn read_body_stream<R>(mut res: R) -> impl Stream<Item = Result<Bytes, Error>> + Unpin
+where
+    R: TestResponse,
+{
+    res.take_body()
+}
+
+/// Helper function that deserializes a JSON response body, returning an error instead of
+/// panicking.
+///
+/// Bodies larger than 10MiB are rejected; use [`read_body_json_limited`] for a custom limit.
+pub async fn try_read_body_json<T, R>(res: R) -> Result<T, Error>
+where
+    R: TestResponse,
+    T: DeserializeOwned,
+{
+    read_body_json_limited(res, 10_485_760).await
+}
 
-    serde_json::from_slice(&body)
-        .unwrap_or_else(|_| panic!("read_response_json failed during deserialization"))
+/// Helper function that deserializes a JSON response body of at most `limit` bytes.
+///
+/// Returns [`JsonPayloadError::Overflow`] if the body is larger than `limit` and
+/// [`JsonPayloadError::Deserialize`] if it is not valid JSON for `T`.
+pub async fn read_body_json_limited<T, R>(mut res: R, limit: usize) -> Result<T, Error>
+where
+    R: TestResponse,
+    T: DeserializeOwned,
+{
+    let mut body = res.take_body();
+    let mut bytes = BytesMut::new();
+    while let Some(item) = body.next().await {
+        let chunk = item?;
+        if bytes.len() + chunk.len() > limit {
+            return Err(JsonPayloadError::Overflow {
+                limit,
+                length: None,
+            }
+            .into());
+        }
+        bytes.extend_from_slice(&chunk);
+    }
+
+    Ok(crate::types::json::from_slice(&bytes)?)
+}
+
+/// Helper function that asserts the status of a response and the value of one of its headers.
+///
+/// ```rust
+/// use actix_web::{test, web, App, HttpResponse, http::{header, StatusCode}};
+///
+/// #[actix_rt::test]
+/// async fn test_created() {
+///     let app = test::init_service(App::new().route(
+///         "/",
+///         web::post().to(|| async {
+///             HttpResponse::Created().insert_header((header::LOCATION, "/1")).finish()
+///         }),
+///     ))
+///     .await;
+///
+///     let res = test::call_service(&app, test::TestRequest::post().to_request()).await;
+///     test::assert_status_and_header(&res, StatusCode::CREATED, header::LOCATION, "/1");
+/// }
+/// ```
+pub fn assert_status_and_header<R, N>(res: &R, status: StatusCode, name: N, value: &str)
+where
+    R: TestResponse,
+    N: AsHeaderName + fmt::Debug,
+{
+    assert_eq!(res.status(), status, "unexpected response status");
+
+    let name_dbg = format!("{:?}", name);
+    match res.headers().get(name) {
+        Some(actual) => assert_eq!(
+            actual, value,
+            "unexpected value of response header {}",
+            name_dbg
+        ),
+        None => panic!("response header {} is missing", name_dbg),
+    }
 }
 
 /// Test `Request` builder.
@@ -440,12 +771,28 @@ impl TestRequest {
     }
 
     /// Set cookie for this request.
+    ///
+    /// Cookies are sent in a single `Cookie` header, together with any cookies set using
+    /// `insert_header` or `append_header`.
     #[cfg(feature = "cookies")]
     pub fn cookie(mut self, cookie: Cookie<'_>) -> Self {
         self.req.cookie(cookie);
         self
     }
 
+    /// Set the cookies of a previous response for this request, like a browser session would.
+    ///
+    /// Cookies removed by the response (with a `Max-Age` of zero) are skipped.
+    #[cfg(feature = "cookies")]
+    pub fn cookies_from<B>(mut self, res: &ServiceResponse<B>) -> Self {
+        for cookie in res.response().cookies() {
+            if !cookie.max_age().map_or(false, |age| age.is_zero()) {
+                self.req.cookie(cookie);
+            }
+        }
+        self
+    }
+
     /// Set request path pattern parameter
     pub fn param(mut self, name: &'static str, value: &'static str) -> Self {
         self.path.add_static(name, value);
@@ -520,7 +867,7 @@ impl TestRequest {
         let app_state = AppInitServiceState::new(Rc::new(self.rmap), self.config.clone());
 
         ServiceRequest::new(
-            HttpRequest::new(self.path, head, app_state, Rc::new(self.app_data)),
+            HttpRequest::new(self.path, head, app_state, Rc::new(self.app_data), None),
             payload,
         )
     }
@@ -538,7 +885,7 @@ impl TestRequest {
 
         let app_state = AppInitServiceState::new(Rc::new(self.rmap), self.config.clone());
 
-        HttpRequest::new(self.path, head, app_state, Rc::new(self.app_data))
+        HttpRequest::new(self.path, head, app_state, Rc::new(self.app_data), None)
     }
 
     /// Complete request creation and generate `HttpRequest` and `Payload` instances
@@ -549,7 +896,7 @@ impl TestRequest {
 
         let app_state = AppInitServiceState::new(Rc::new(self.rmap), self.config.clone());
 
-        let req = HttpRequest::new(self.path, head, app_state, Rc::new(self.app_data));
+        let req = HttpRequest::new(self.path, head, app_state, Rc::new(self.app_data), None);
 
         (req, payload)
     }
@@ -565,6 +912,50 @@ impl TestRequest {
     }
 }
 
+/// Drive an application with raw HTTP/1 request bytes and return the raw response bytes.
+///
+/// The request bytes are read by the HTTP/1 dispatcher from an in-memory connection which is
+/// closed by the client after the last byte, so several pipelined requests can be sent in one
+/// buffer. Malformed requests are answered the same way as by a real server, which makes this
+/// useful for testing request parsing without starting a [`TestServer`].
+///
+/// ```rust
+/// use actix_web::{test, web, App, HttpResponse};
+///
+/// #[actix_rt::test]
+/// async fn test_raw() {
+///     let app = App::new().route("/", web::get().to(|| HttpResponse::Ok()));
+///
+///     let res = test::raw_call(app, b"GET / HTTP/1.1\r\n\r\n").await;
+///     assert!(res.starts_with(b"HTTP/1.1 200 OK\r\n"));
+/// }
+/// ```
+pub async fn raw_call<F, S, B>(app: F, req: &[u8]) -> Vec<u8>
+where
+    F: IntoServiceFactory<S, Request>,
+    S: ServiceFactory<Request, Config = AppConfig> + 'static,
+    S::Error: Into<Error> + 'static,
+    S::InitError: fmt::Debug,
+    S::Response: Into<HttpResponse<B>> + 'static,
+    <S::Service as Service<Request>>::Future: 'static,
+    B: MessageBody + 'static,
+{
+    let srv = HttpService::build()
+        .h1(map_config(app, |_| AppConfig::default()))
+        .new_service(())
+        .await
+        .unwrap_or_else(|_| panic!("raw_call failed to create service"));
+
+    let mut io = actix_http::test::TestSeqBuffer::new(req);
+    io.close_read();
+
+    // dispatch errors, such as malformed requests, are already answered on the connection
+    let _ = srv.call((io.clone(), None)).await;
+
+    let res = io.write_buf().to_vec();
+    res
+}
+
 /// Start test server with default configuration
 ///
 /// Test server is very simple server that simplify process of writing
@@ -643,6 +1034,9 @@ where
 {
     let (tx, rx) = mpsc::channel();
 
+    #[cfg(feature = "rustls")]
+    let client_tls = cfg.client_tls.clone();
+
     let ssl = match cfg.stream {
         StreamType::Tcp => false,
         #[cfg(feature = "openssl")]
@@ -659,7 +1053,7 @@ where
         let factory = factory.clone();
         let cfg = cfg.clone();
         let ctimeout = cfg.client_timeout;
-        let builder = Server::build().workers(1).disable_signals();
+        let builder = Server::build().workers(cfg.workers).disable_signals();
 
         let srv = match cfg.stream {
             StreamType::Tcp => match cfg.tp {
@@ -770,6 +1164,13 @@ where
             }
         };
 
+        // trust the certificate of the server
+        #[cfg(feature = "rustls")]
+        let connector = match client_tls {
+            Some(config) => connector.rustls(config),
+            None => connector,
+        };
+
         Client::builder().connector(connector).finish()
     };
 
@@ -787,6 +1188,9 @@ pub struct TestServerConfig {
     tp: HttpVer,
     stream: StreamType,
     client_timeout: u64,
+    workers: usize,
+    #[cfg(feature = "rustls")]
+    client_tls: Option<std::sync::Arc<rustls::ClientConfig>>,
 }
 
 #[derive(Clone)]
@@ -823,6 +1227,9 @@ impl TestServerConfig {
             tp: HttpVer::Both,
             stream: StreamType::Tcp,
             client_timeout: 5000,
+            workers: 1,
+            #[cfg(feature = "rustls")]
+            client_tls: None,
         }
     }
 
@@ -838,6 +1245,14 @@ impl TestServerConfig {
         self
     }
 
+    /// Start server accepting both HTTP/1.1 and HTTP/2 (default).
+    ///
+    /// With TLS the protocol is selected using ALPN.
+    pub fn auto(mut self) -> Self {
+        self.tp = HttpVer::Both;
+        self
+    }
+
     /// Start openssl server
     #[cfg(feature = "openssl")]
     pub fn openssl(mut self, acceptor: openssl::ssl::SslAcceptor) -> Self {
@@ -852,11 +1267,74 @@ impl TestServerConfig {
         self
     }
 
+    /// Start rustls server with `cert`, and let the test client trust it.
+    #[cfg(feature = "rustls")]
+    pub fn rustls_cert(mut self, cert: &TestCert) -> Self {
+        self.stream = StreamType::Rustls(cert.server_config());
+        self.client_tls = Some(std::sync::Arc::new(cert.client_config()));
+        self
+    }
+
+    /// Start rustls server with a newly generated self-signed certificate for `localhost`.
+    #[cfg(feature = "rustls")]
+    pub fn rustls_self_signed(self) -> Self {
+        self.rustls_cert(&TestCert::generate())
+    }
+
     /// Set server client timeout in milliseconds for first request.
     pub fn client_timeout(mut self, val: u64) -> Self {
         self.client_timeout = val;
         self
     }
+
+    /// Set number of server worker threads (default: 1).
+    pub fn workers(mut self, num: usize) -> Self {
+        self.workers = num;
+        self
+    }
+}
+
+/// Self-signed certificate for `localhost`, for starting TLS test servers.
+#[cfg(feature = "rustls")]
+#[derive(Clone)]
+pub struct TestCert {
+    cert: rustls::Certificate,
+    key: rustls::PrivateKey,
+}
+
+#[cfg(feature = "rustls")]
+impl TestCert {
+    /// Generate a new certificate and private key.
+    pub fn generate() -> Self {
+        let cert = rcgen::generate_simple_self_signed(vec!["localhost".to_owned()]).unwrap();
+
+        TestCert {
+            cert: rustls::Certificate(cert.serialize_der().unwrap()),
+            key: rustls::PrivateKey(cert.serialize_private_key_der()),
+        }
+    }
+
+    /// DER encoded certificate.
+    pub fn certificate(&self) -> &rustls::Certificate {
+        &self.cert
+    }
+
+    /// Server configuration using this certificate.
+    pub fn server_config(&self) -> rustls::ServerConfig {
+        let mut config = rustls::ServerConfig::new(rustls::NoClientAuth::new());
+        config
+            .set_single_cert(vec![self.cert.clone()], self.key.clone())
+            .unwrap();
+        config
+    }
+
+    /// Client configuration trusting only this certificate and offering HTTP/2 and HTTP/1.1.
+    pub fn client_config(&self) -> rustls::ClientConfig {
+        let mut config = rustls::ClientConfig::new();
+        config.root_store.add(&self.cert).unwrap();
+        config.set_protocols(&[b"h2".to_vec(), b"http/1.1".to_vec()]);
+        config
+    }
 }
 
 /// Get first available unused address
@@ -884,6 +1362,11 @@ impl TestServer {
         self.addr
     }
 
+    /// Client configured to connect to this server.
+    pub fn client(&self) -> &awc::Client {
+        &self.client
+    }
+
     /// Construct test server url
     pub fn url(&self, uri: &str) -> String {
         let scheme = if self.ssl { "https" } else { "http" };
@@ -1217,4 +1700,310 @@ mod tests {
         let res = app.call(req).await.unwrap();
         assert!(res.status().is_success());
     }
+
+    #[actix_rt::test]
+    async fn test_read_body_stream_sse() {
+        use futures_util::stream;
+        use tokio::sync::mpsc;
+
+        use crate::web::sse::{Event, Sse};
+
+        let (tx, rx) = mpsc::channel::<Event>(1);
+        let rx = Rc::new(std::cell::RefCell::new(Some(rx)));
+
+        let app =
+            init_service(App::new().route(
+                "/events",
+                web::get().to(move || {
+                    let rx = rx.borrow_mut().take().unwrap();
+                    let events = stream::unfold(rx, |mut rx| async move {
+                        rx.recv().await.map(|ev| (ev, rx))
+                    });
+                    async move { Sse::new(events) }
+                }),
+            ))
+            .await;
+
+        let req = TestRequest::get().uri("/events").to_request();
+        let res = call_service(&app, req).await;
+        assert_status_and_header(
+            &res,
+            StatusCode::OK,
+            header::CONTENT_TYPE,
+            "text/event-stream",
+        );
+
+        // events are read as they are sent
+        let mut body = read_body_stream(res);
+
+        tx.send(Event::new("first")).await.unwrap();
+        let chunk = body.next().await.unwrap().unwrap();
+        assert_eq!(chunk, Bytes::from_static(b"data: first\n\n"));
+
+        tx.send(Event::new("second").event("update")).await.unwrap();
+        let chunk = body.next().await.unwrap().unwrap();
+        assert_eq!(
+            chunk,
+            Bytes::from_static(b"event: update\ndata: second\n\n")
+        );
+
+        drop(tx);
+        assert!(body.next().await.is_none());
+    }
+
+    #[actix_rt::test]
+    async fn test_json_error_body() {
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct ErrorBody {
+            error: String,
+        }
+
+        let srv = start(|| {
+            App::new().route(
+                "/",
+                web::get().to(|| {
+                    HttpResponse::BadRequest()
+                        .insert_header(("x-request-id", "42"))
+                        .json(&serde_json::json!({ "error": "invalid input" }))
+                }),
+            )
+        });
+
+        let res = srv.get("/").send().await.unwrap();
+        assert_status_and_header(&res, StatusCode::BAD_REQUEST, "x-request-id", "42");
+        let body: ErrorBody = try_read_body_json(res).await.unwrap();
+        assert_eq!(body.error, "invalid input");
+
+        // wrong shape and oversized bodies are errors instead of panics
+        let res = srv.get("/").send().await.unwrap();
+        assert!(try_read_body_json::<Person, _>(res).await.is_err());
+
+        let res = srv.get("/").send().await.unwrap();
+        let err = read_body_json_limited::<ErrorBody, _>(res, 8)
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err.as_error::<JsonPayloadError>(),
+            Some(JsonPayloadError::Overflow { limit: 8, .. })
+        ));
+    }
+
+    #[actix_rt::test]
+    #[should_panic(expected = "response header \"x-missing\" is missing")]
+    async fn test_assert_status_and_header_missing() {
+        let res = TestRequest::default().to_srv_response(HttpResponse::Ok().finish());
+        assert_status_and_header(&res, StatusCode::OK, "x-missing", "");
+    }
+
+    #[actix_rt::test]
+    async fn test_call_and_read_body() {
+        let app = init_service(
+            App::new().route("/", web::get().to(|| HttpResponse::Ok().body("ok"))),
+        )
+        .await;
+
+        let req = TestRequest::get().to_request();
+        assert_eq!(
+            call_and_read_body(&app, req).await,
+            Bytes::from_static(b"ok")
+        );
+
+        let req = TestRequest::get().uri("/missing").to_request();
+        let res = try_call_service(&app, req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[actix_rt::test]
+    async fn test_call_and_read_body_json_panic_message() {
+        use std::panic::AssertUnwindSafe;
+
+        use futures_util::FutureExt as _;
+
+        let long = "x".repeat(2000);
+        let app = init_service(App::new().route(
+            "/",
+            web::get().to(move || {
+                HttpResponse::BadRequest()
+                    .content_type("text/plain")
+                    .body(format!("invalid input {}", long))
+            }),
+        ))
+        .await;
+
+        let req = TestRequest::get().to_request();
+        let err = AssertUnwindSafe(call_and_read_body_json::<_, _, Person>(&app, req))
+            .catch_unwind()
+            .await
+            .err()
+            .unwrap();
+
+        let msg = err.downcast::<String>().unwrap();
+        assert!(msg.starts_with("call_and_read_body_json failed during deserialization"));
+        assert!(msg.contains("response status: 400 Bad Request"));
+        assert!(msg.contains("response content-type: text/plain"));
+        assert!(msg.contains("response body: invalid input xxx"));
+        assert!(msg.ends_with("... (2014 bytes total)"));
+
+        // only the first kilobyte of the body is included
+        assert!(msg.contains(&"x".repeat(1010)));
+        assert!(!msg.contains(&"x".repeat(1011)));
+    }
+
+    #[actix_rt::test]
+    async fn test_raw_call_pipelined() {
+        let app = App::new().route(
+            "/{name}",
+            web::get().to(|name: web::Path<String>| async move { name.into_inner() }),
+        );
+
+        let res = raw_call(
+            app,
+            b"GET /first HTTP/1.1\r\n\r\nGET /second HTTP/1.1\r\n\r\n",
+        )
+        .await;
+        let res = String::from_utf8(res).unwrap();
+
+        // both responses are written in order
+        assert_eq!(res.matches("HTTP/1.1 200 OK\r\n").count(), 2);
+        let first = res.find("\r\n\r\nfirst").unwrap();
+        let second = res.find("\r\n\r\nsecond").unwrap();
+        assert!(first < second);
+    }
+
+    #[actix_rt::test]
+    async fn test_raw_call_invalid_header() {
+        let app = App::new().route("/", web::get().to(HttpResponse::Ok));
+
+        let res = raw_call(app, b"GET / HTTP/1.1\r\nbad header\r\n\r\n").await;
+        assert!(res.starts_with(b"HTTP/1.1 400 Bad Request\r\n"));
+    }
+
+    #[actix_rt::test]
+    async fn test_peer_addr_guard() {
+        let app = init_service(
+            App::new().service(
+                web::resource("/")
+                    .guard(crate::guard::fn_guard(|head| {
+                        head.peer_addr.map_or(false, |addr| addr.ip().is_loopback())
+                    }))
+                    .to(|req: HttpRequest| async move {
+                        let info = req.connection_info();
+                        info.realip_remote_addr().unwrap().to_owned()
+                    }),
+            ),
+        )
+        .await;
+
+        let req = TestRequest::get()
+            .peer_addr("127.0.0.1:8080".parse().unwrap())
+            .to_request();
+        let res = call_service(&app, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(read_body(res).await, Bytes::from_static(b"127.0.0.1:8080"));
+
+        let req = TestRequest::get()
+            .peer_addr("10.0.0.1:8080".parse().unwrap())
+            .to_request();
+        let res = call_service(&app, req).await;
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[cfg(feature = "cookies")]
+    #[actix_rt::test]
+    async fn test_cookies() {
+        async fn handler(req: HttpRequest) -> HttpResponse {
+            let cookie = |name| req.cookie(name).map(|c| c.value().to_owned());
+            HttpResponse::Ok().body(format!("{:?} {:?}", cookie("a"), cookie("b")))
+        }
+
+        let app = init_service(App::new().route("/", web::get().to(handler))).await;
+
+        // cookies from the jar and from a raw header are folded into one header
+        let req = TestRequest::get()
+            .insert_header((header::COOKIE, "b=2"))
+            .cookie(Cookie::new("a", "1"));
+        let http_req = req.to_http_request();
+        assert_eq!(http_req.headers().get_all(header::COOKIE).count(), 1);
+
+        let req = TestRequest::get()
+            .insert_header((header::COOKIE, "b=2"))
+            .cookie(Cookie::new("a", "1"))
+            .to_request();
+        let res = call_service(&app, req).await;
+        assert_eq!(
+            read_body(res).await,
+            Bytes::from_static(b"Some(\"1\") Some(\"2\")")
+        );
+    }
+
+    #[cfg(feature = "cookies")]
+    #[actix_rt::test]
+    async fn test_cookies_from() {
+        let app = init_service(
+            App::new()
+                .route(
+                    "/login",
+                    web::post().to(|| {
+                        HttpResponse::Ok()
+                            .cookie(Cookie::new("session", "abc"))
+                            .del_cookie(&Cookie::named("old"))
+                            .finish()
+                    }),
+                )
+                .route(
+                    "/me",
+                    web::get().to(|req: HttpRequest| {
+                        assert!(req.cookie("old").is_none());
+                        match req.cookie("session") {
+                            Some(session) => {
+                                HttpResponse::Ok().body(session.value().to_owned())
+                            }
+                            None => HttpResponse::Unauthorized().finish(),
+                        }
+                    }),
+                ),
+        )
+        .await;
+
+        let res = TestRequest::post().uri("/login").send_request(&app).await;
+        assert_eq!(res.status(), StatusCode::OK);
+
+        let res = TestRequest::get()
+            .uri("/me")
+            .cookies_from(&res)
+            .send_request(&app)
+            .await;
+        assert_eq!(read_body(res).await, Bytes::from_static(b"abc"));
+    }
+
+    #[cfg(feature = "rustls")]
+    #[actix_rt::test]
+    async fn test_rustls_scheme() {
+        let srv = start_with(config().rustls_self_signed(), || {
+            App::new().route(
+                "/",
+                web::get().to(|req: HttpRequest| async move {
+                    req.connection_info().scheme().to_owned()
+                }),
+            )
+        });
+
+        assert!(srv.url("/").starts_with("https://"));
+
+        let mut res = srv.get("/").send().await.unwrap();
+        assert!(res.status().is_success());
+        assert_eq!(res.body().await.unwrap(), Bytes::from_static(b"https"));
+    }
+
+    #[cfg(feature = "rustls")]
+    #[actix_rt::test]
+    async fn test_rustls_h2() {
+        let srv = start_with(config().rustls_self_signed().h2().workers(2), || {
+            App::new().route("/", web::get().to(HttpResponse::Ok))
+        });
+
+        let res = srv.client().get(srv.url("/")).send().await.unwrap();
+        assert!(res.status().is_success());
+        assert_eq!(res.version(), Version::HTTP_2);
+    }
 }
@@ -0,0 +1,507 @@
+//! TLS configuration that can be replaced while a server is running, and client certificates.
+//!
+//! Certificates are usually rotated well before they expire. Binding a server with
+//! [`HttpServer::bind_openssl_with_reload`] or [`HttpServer::bind_rustls_with_reload`] allows
+//! new certificates to be installed without restarting the server; connections accepted after a
+//! reload use the new certificate and existing connections are not affected.
+//!
+//! Servers bound with [`HttpServer::bind_openssl_with_client_auth`] or
+//! [`HttpServer::bind_rustls_with_client_auth`] ask clients for a certificate, see
+//! [`ClientCertAuth`]. The certificates presented by a client are available to handlers through
+//! the [`ClientCert`] extractor, to route guards through [`guard::ClientCert`] and as
+//! [`PeerCertificates`] connection data.
+//!
+//! [`HttpServer::bind_openssl_with_reload`]: crate::HttpServer::bind_openssl_with_reload
+//! [`HttpServer::bind_rustls_with_reload`]: crate::HttpServer::bind_rustls_with_reload
+//! [`HttpServer::bind_openssl_with_client_auth`]: crate::HttpServer::bind_openssl_with_client_auth
+//! [`HttpServer::bind_rustls_with_client_auth`]: crate::HttpServer::bind_rustls_with_client_auth
+//! [`guard::ClientCert`]: crate::guard::ClientCert
+
+use std::{fmt, ops, rc::Rc, sync::Arc};
+
+use futures_util::future::{ready, Ready};
+
+use crate::{dev::Payload, error::ErrorForbidden, Error, FromRequest, HttpRequest};
+
+#[cfg(feature = "openssl")]
+pub(crate) use self::openssl::peer_certificates as openssl_peer_certificates;
+#[cfg(feature = "openssl")]
+pub use self::openssl::ReloadableSslAcceptor;
+#[cfg(feature = "rustls")]
+pub use self::rustls::ReloadableCert;
+#[cfg(feature = "rustls")]
+pub(crate) use self::rustls::{
+    peer_certificates as rustls_peer_certificates, ClientAuthVerifier,
+};
+
+/// Callback deciding whether a verified client certificate chain is accepted.
+type VerifyFn = dyn Fn(&[Vec<u8>]) -> bool + Send + Sync;
+
+/// Client certificate authentication for TLS listeners.
+///
+/// Clients are asked for a certificate, which is verified against the trust roots of the
+/// listener: the CA certificates of the OpenSSL builder or the root store passed to
+/// [`HttpServer::bind_rustls_with_client_auth`]. A [`verify`](Self::verify()) callback can further
+/// restrict which certificates are accepted.
+///
+/// In [`require`](Self::require()) mode, handshakes without an accepted certificate fail. In
+/// [`request`](Self::request()) mode, clients may connect without a certificate or with one
+/// failing verification; their requests can tell the cases apart with
+/// [`PeerCertificates::is_verified`].
+///
+/// # Examples
+/// ```no_run
+/// # extern crate tls_openssl as openssl;
+/// use actix_web::{get, tls::{ClientCert, ClientCertAuth}, App, HttpServer};
+/// use openssl::ssl::{SslAcceptor, SslFiletype, SslMethod};
+///
+/// #[get("/")]
+/// async fn index(cert: ClientCert) -> String {
+///     format!("certificate of {} bytes", cert.leaf().len())
+/// }
+///
+/// # async fn run() -> std::io::Result<()> {
+/// let mut builder = SslAcceptor::mozilla_intermediate(SslMethod::tls()).unwrap();
+/// builder.set_private_key_file("key.pem", SslFiletype::PEM).unwrap();
+/// builder.set_certificate_chain_file("cert.pem").unwrap();
+/// builder.set_ca_file("client-ca.pem").unwrap();
+///
+/// HttpServer::new(|| App::new().service(index))
+///     .bind_openssl_with_client_auth("127.0.0.1:8443", builder, ClientCertAuth::require())?
+///     .run()
+///     .await
+/// # }
+/// ```
+///
+/// [`HttpServer::bind_rustls_with_client_auth`]: crate::HttpServer::bind_rustls_with_client_auth
+#[derive(Clone)]
+pub struct ClientCertAuth {
+    required: bool,
+    verify: Option<Arc<VerifyFn>>,
+}
+
+impl ClientCertAuth {
+    /// Ask clients for a certificate, but also accept connections without an accepted one.
+    pub fn request() -> Self {
+        ClientCertAuth {
+            required: false,
+            verify: None,
+        }
+    }
+
+    /// Only accept connections presenting an accepted certificate.
+    pub fn require() -> Self {
+        ClientCertAuth {
+            required: true,
+            verify: None,
+        }
+    }
+
+    /// Set callback deciding whether a certificate chain that passed verification against the
+    /// trust roots is accepted.
+    ///
+    /// The chain is passed as DER-encoded certificates, starting with the client certificate.
+    pub fn verify<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&[Vec<u8>]) -> bool + Send + Sync + 'static,
+    {
+        self.verify = Some(Arc::new(f));
+        self
+    }
+
+    fn accepts(&self, chain: &[Vec<u8>]) -> bool {
+        self.verify.as_ref().map_or(true, |verify| verify(chain))
+    }
+}
+
+impl fmt::Debug for ClientCertAuth {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ClientCertAuth")
+            .field("required", &self.required)
+            .field("verify", &self.verify.is_some())
+            .finish()
+    }
+}
+
+/// Certificates presented by the client of a TLS connection.
+///
+/// Inserted into the connection data of TLS listeners when the client presented a certificate,
+/// see [`HttpRequest::conn_data`].
+#[derive(Debug, Clone)]
+pub struct PeerCertificates {
+    chain: Rc<[Vec<u8>]>,
+    verified: bool,
+}
+
+impl PeerCertificates {
+    /// Returns `None` for an empty chain.
+    pub(crate) fn new(chain: Vec<Vec<u8>>, verified: bool) -> Option<Self> {
+        if chain.is_empty() {
+            return None;
+        }
+
+        Some(PeerCertificates {
+            chain: chain.into(),
+            verified,
+        })
+    }
+
+    /// DER-encoded certificate chain, starting with the client certificate.
+    pub fn chain(&self) -> &[Vec<u8>] {
+        &self.chain
+    }
+
+    /// DER-encoded client certificate.
+    pub fn leaf(&self) -> &[u8] {
+        &self.chain[0]
+    }
+
+    /// Returns `true` if the chain was verified and accepted by the listener.
+    ///
+    /// Only listeners configured with [`ClientCertAuth::request`] accept connections with
+    /// certificates that are not verified.
+    pub fn is_verified(&self) -> bool {
+        self.verified
+    }
+}
+
+/// Extractor for the verified client certificate of a TLS connection.
+///
+/// Responds with `403 Forbidden` if the client did not present a certificate or the certificate
+/// was not verified.
+#[derive(Debug, Clone)]
+pub struct ClientCert(pub PeerCertificates);
+
+impl ClientCert {
+    /// Unwrap into inner `PeerCertificates`.
+    pub fn into_inner(self) -> PeerCertificates {
+        self.0
+    }
+}
+
+impl ops::Deref for ClientCert {
+    type Target = PeerCertificates;
+
+    fn deref(&self) -> &PeerCertificates {
+        &self.0
+    }
+}
+
+impl FromRequest for ClientCert {
+    type Error = Error;
+    type Future = Ready<Result<Self, Error>>;
+    type Config = ();
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        let cert = req
+            .conn_data::<PeerCertificates>()
+            .filter(|certs| certs.is_verified())
+            .map(|certs| ClientCert(certs.clone()))
+            .ok_or_else(|| ErrorForbidden("client certificate required"));
+
+        ready(cert)
+    }
+}
+
+#[cfg(feature = "openssl")]
+mod openssl {
+    use std::{
+        io,
+        sync::{Arc, RwLock},
+    };
+
+    use actix_tls::accept::openssl::{SslAcceptor, SslAcceptorBuilder};
+    use openssl::{
+        ssl::{SniError, SslRef, SslVerifyMode},
+        x509::X509VerifyResult,
+    };
+
+    use super::{ClientCertAuth, PeerCertificates};
+    use crate::server::openssl_acceptor;
+
+    /// OpenSSL acceptor that can be replaced while the server is running.
+    ///
+    /// Clones share the same configuration, keep one around to [`reload`](Self::reload()) it.
+    /// Protocol options (versions, ciphers, etc.) are taken from the initial builder; reloading
+    /// replaces the context holding the certificate and private key.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// # extern crate tls_openssl as openssl;
+    /// use actix_web::{tls::ReloadableSslAcceptor, App, HttpServer};
+    /// use openssl::ssl::{SslAcceptor, SslAcceptorBuilder, SslFiletype, SslMethod};
+    ///
+    /// fn builder() -> SslAcceptorBuilder {
+    ///     let mut builder = SslAcceptor::mozilla_intermediate(SslMethod::tls()).unwrap();
+    ///     builder.set_private_key_file("key.pem", SslFiletype::PEM).unwrap();
+    ///     builder.set_certificate_chain_file("cert.pem").unwrap();
+    ///     builder
+    /// }
+    ///
+    /// # async fn run() -> std::io::Result<()> {
+    /// let acceptor = ReloadableSslAcceptor::new(builder())?;
+    ///
+    /// let srv = HttpServer::new(App::new)
+    ///     .bind_openssl_with_reload("127.0.0.1:8443", acceptor.clone())?
+    ///     .run();
+    ///
+    /// // later, after certificate files were renewed
+    /// acceptor.reload(builder())?;
+    /// # srv.await
+    /// # }
+    /// ```
+    #[derive(Clone)]
+    pub struct ReloadableSslAcceptor {
+        acceptor: SslAcceptor,
+        reloaded: Arc<RwLock<Option<SslAcceptor>>>,
+    }
+
+    impl ReloadableSslAcceptor {
+        /// Creates acceptor using the configuration of `builder`.
+        ///
+        /// Sets ALPN protocols to "h2" and "http/1.1".
+        pub fn new(mut builder: SslAcceptorBuilder) -> io::Result<Self> {
+            let reloaded = Arc::new(RwLock::new(None::<SslAcceptor>));
+
+            let current = Arc::clone(&reloaded);
+            // called for every handshake, whether the client sends a server name or not
+            builder.set_servername_callback(move |ssl, _| match *current.read().unwrap() {
+                Some(ref acceptor) => ssl
+                    .set_ssl_context(acceptor.context())
+                    .map_err(|_| SniError::ALERT_FATAL),
+                None => Ok(()),
+            });
+
+            Ok(ReloadableSslAcceptor {
+                acceptor: openssl_acceptor(builder)?,
+                reloaded,
+            })
+        }
+
+        /// Replaces the configuration for new connections with the one of `builder`.
+        ///
+        /// Sets ALPN protocols to "h2" and "http/1.1".
+        pub fn reload(&self, builder: SslAcceptorBuilder) -> io::Result<()> {
+            let acceptor = openssl_acceptor(builder)?;
+            *self.reloaded.write().unwrap() = Some(acceptor);
+            Ok(())
+        }
+
+        pub(crate) fn acceptor(&self) -> SslAcceptor {
+            self.acceptor.clone()
+        }
+    }
+
+    impl ClientCertAuth {
+        /// Ask for client certificates in handshakes of acceptors built by `builder`.
+        pub(crate) fn configure_openssl(
+            self,
+            builder: &mut SslAcceptorBuilder,
+        ) -> io::Result<()> {
+            let mut mode = SslVerifyMode::PEER;
+            if self.required {
+                mode |= SslVerifyMode::FAIL_IF_NO_PEER_CERT;
+            }
+
+            let required = self.required;
+            builder.set_verify_callback(mode, move |preverified, ctx| {
+                let mut verified = preverified && ctx.error() == X509VerifyResult::OK;
+
+                // called for every certificate of the chain, the client certificate is the last one
+                if verified && ctx.error_depth() == 0 {
+                    let chain = ctx
+                        .chain()
+                        .map(|chain| {
+                            chain.iter().filter_map(|cert| cert.to_der().ok()).collect()
+                        })
+                        .unwrap_or_else(Vec::new);
+
+                    if !self.accepts(&chain) {
+                        ctx.set_error(X509VerifyResult::APPLICATION_VERIFICATION);
+                        verified = false;
+                    }
+                }
+
+                // when not required, failures only show up in the verify result of the connection
+                verified || !required
+            });
+
+            // sessions can not be resumed when peers are verified without a session id context
+            builder.set_session_id_context(b"actix-web")?;
+
+            Ok(())
+        }
+    }
+
+    /// Certificates presented by the client of `ssl`.
+    pub(crate) fn peer_certificates(ssl: &SslRef) -> Option<PeerCertificates> {
+        let mut chain = vec![ssl.peer_certificate()?.to_der().ok()?];
+
+        // on the server side, the chain does not include the client certificate
+        if let Some(certs) = ssl.peer_cert_chain() {
+            chain.extend(certs.iter().filter_map(|cert| cert.to_der().ok()));
+        }
+
+        PeerCertificates::new(chain, ssl.verify_result() == X509VerifyResult::OK)
+    }
+}
+
+#[cfg(feature = "rustls")]
+mod rustls {
+    use std::sync::{Arc, RwLock};
+
+    use rustls::{
+        sign::{self, CertifiedKey},
+        AllowAnyAnonymousOrAuthenticatedClient, Certificate, ClientCertVerified,
+        ClientCertVerifier, ClientHello, DistinguishedNames, PrivateKey, ResolvesServerCert,
+        RootCertStore, ServerSession, Session, TLSError,
+    };
+    use webpki::DNSName;
+
+    use super::{ClientCertAuth, PeerCertificates};
+
+    /// Server certificate that can be replaced while the server is running.
+    ///
+    /// Used as the certificate resolver of a rustls `ServerConfig`. Clones share the same
+    /// certificate, keep one around to [`reload`](Self::reload()) it.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// # extern crate tls_rustls as rustls;
+    /// use actix_web::{tls::ReloadableCert, App, HttpServer};
+    /// use rustls::{Certificate, NoClientAuth, PrivateKey, ServerConfig};
+    ///
+    /// fn load_cert() -> (Vec<Certificate>, PrivateKey) {
+    ///     // read certificate chain and private key files
+    /// #   unimplemented!()
+    /// }
+    ///
+    /// # async fn run() -> std::io::Result<()> {
+    /// let (chain, key) = load_cert();
+    /// let cert = ReloadableCert::new(chain, &key).unwrap();
+    ///
+    /// let srv = HttpServer::new(App::new)
+    ///     .bind_rustls_with_reload(
+    ///         "127.0.0.1:8443",
+    ///         ServerConfig::new(NoClientAuth::new()),
+    ///         cert.clone(),
+    ///     )?
+    ///     .run();
+    ///
+    /// // later, after certificate files were renewed
+    /// let (chain, key) = load_cert();
+    /// cert.reload(chain, &key).unwrap();
+    /// # srv.await
+    /// # }
+    /// ```
+    #[derive(Clone)]
+    pub struct ReloadableCert {
+        current: Arc<RwLock<CertifiedKey>>,
+    }
+
+    impl ReloadableCert {
+        /// Creates resolver using a certificate chain and its private key.
+        pub fn new(cert_chain: Vec<Certificate>, key: &PrivateKey) -> Result<Self, TLSError> {
+            Ok(ReloadableCert {
+                current: Arc::new(RwLock::new(certified_key(cert_chain, key)?)),
+            })
+        }
+
+        /// Replaces the certificate for new connections.
+        pub fn reload(
+            &self,
+            cert_chain: Vec<Certificate>,
+            key: &PrivateKey,
+        ) -> Result<(), TLSError> {
+            let certified = certified_key(cert_chain, key)?;
+            *self.current.write().unwrap() = certified;
+            Ok(())
+        }
+    }
+
+    impl ResolvesServerCert for ReloadableCert {
+        fn resolve(&self, _: ClientHello<'_>) -> Option<CertifiedKey> {
+            Some(self.current.read().unwrap().clone())
+        }
+    }
+
+    fn certified_key(
+        cert_chain: Vec<Certificate>,
+        key: &PrivateKey,
+    ) -> Result<CertifiedKey, TLSError> {
+        let key = sign::any_supported_type(key)
+            .map_err(|_| TLSError::General("invalid private key".into()))?;
+        Ok(CertifiedKey::new(cert_chain, Arc::new(key)))
+    }
+
+    /// Client certificate verifier applying a [`ClientCertAuth`].
+    pub(crate) struct ClientAuthVerifier {
+        roots: Arc<dyn ClientCertVerifier>,
+        auth: ClientCertAuth,
+    }
+
+    impl ClientAuthVerifier {
+        pub(crate) fn new(roots: RootCertStore, auth: ClientCertAuth) -> Self {
+            ClientAuthVerifier {
+                roots: AllowAnyAnonymousOrAuthenticatedClient::new(roots),
+                auth,
+            }
+        }
+
+        fn check(
+            &self,
+            certs: &[Certificate],
+            sni: Option<&DNSName>,
+        ) -> Result<ClientCertVerified, TLSError> {
+            let verified = self.roots.verify_client_cert(certs, sni)?;
+            let chain = certs.iter().map(|cert| cert.0.clone()).collect::<Vec<_>>();
+
+            if self.auth.accepts(&chain) {
+                Ok(verified)
+            } else {
+                Err(TLSError::General("client certificate rejected".into()))
+            }
+        }
+    }
+
+    impl ClientCertVerifier for ClientAuthVerifier {
+        fn client_auth_mandatory(&self, _: Option<&DNSName>) -> Option<bool> {
+            Some(self.auth.required)
+        }
+
+        fn client_auth_root_subjects(
+            &self,
+            sni: Option<&DNSName>,
+        ) -> Option<DistinguishedNames> {
+            self.roots.client_auth_root_subjects(sni)
+        }
+
+        fn verify_client_cert(
+            &self,
+            certs: &[Certificate],
+            sni: Option<&DNSName>,
+        ) -> Result<ClientCertVerified, TLSError> {
+            match self.check(certs, sni) {
+                // when not required, failures are recorded in the peer certificates instead
+                Err(_) if !self.auth.required => Ok(ClientCertVerified::assertion()),
+                res => res,
+            }
+        }
+    }
+
+    /// Certificates presented by the client of `session`.
+    ///
+    /// Without a `verifier`, rustls only completes handshakes with verified certificates.
+    pub(crate) fn peer_certificates(
+        session: &ServerSession,
+        verifier: Option<&ClientAuthVerifier>,
+    ) -> Option<PeerCertificates> {
+        let certs = session.get_peer_certificates()?;
+
+        let verified = verifier.map_or(true, |verifier| {
+            verifier.auth.required || verifier.check(&certs, None).is_ok()
+        });
+
+        PeerCertificates::new(certs.into_iter().map(|cert| cert.0).collect(), verified)
+    }
+}
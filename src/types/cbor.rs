@@ -0,0 +1,234 @@
+//! For CBOR helper documentation, see [`Cbor`].
+
+use crate::{
+    error::CborPayloadError,
+    types::encoded::{is_application, serde_format},
+};
+
+/// Content type of CBOR payloads.
+const CONTENT_TYPE: &str = "application/cbor";
+
+serde_format! {
+    /// CBOR extractor and responder.
+    ///
+    /// `Cbor` has two uses: CBOR responses, and extracting typed data from CBOR request payloads.
+    /// The inner type `T` is (de)serialized with `serde_cbor`. This type is only available with the
+    /// `cbor` feature.
+    ///
+    /// # Extractor
+    /// The request must have an `application/cbor` content type or a `+cbor` suffixed one.
+    /// Compressed payloads are decompressed before decoding. Use [`CborConfig`] to configure the
+    /// extraction process.
+    ///
+    /// ```
+    /// use actix_web::{post, web};
+    /// use serde::Deserialize;
+    ///
+    /// #[derive(Deserialize)]
+    /// struct Info {
+    ///     username: String,
+    /// }
+    ///
+    /// /// deserialize `Info` from request's body
+    /// #[post("/")]
+    /// async fn index(info: web::Cbor<Info>) -> String {
+    ///     format!("Welcome {}!", info.username)
+    /// }
+    /// ```
+    ///
+    /// # Responder
+    /// A handler may return a `Cbor<T>` to respond with the serialized value and an
+    /// `application/cbor` content type.
+    ///
+    /// ```
+    /// use actix_web::{get, web};
+    /// use serde::Serialize;
+    ///
+    /// #[derive(Serialize)]
+    /// struct Info {
+    ///     name: String,
+    /// }
+    ///
+    /// #[get("/{name}")]
+    /// async fn index(name: web::Path<String>) -> web::Cbor<Info> {
+    ///     web::Cbor(Info {
+    ///         name: name.into_inner(),
+    ///     })
+    /// }
+    /// ```
+    pub struct Cbor;
+
+    /// [`Cbor`] extractor configuration.
+    ///
+    /// ```
+    /// use actix_web::{post, web, App};
+    /// use serde::Deserialize;
+    ///
+    /// #[derive(Deserialize)]
+    /// struct Info {
+    ///     username: String,
+    /// }
+    ///
+    /// // Max payload size for CBOR is set to 4kB and requests without a content type are accepted.
+    /// #[post("/")]
+    /// async fn index(info: web::Cbor<Info>) -> String {
+    ///     format!("Welcome {}!", info.username)
+    /// }
+    ///
+    /// App::new()
+    ///     .app_data(
+    ///         web::CborConfig::default()
+    ///             .limit(4096)
+    ///             .strict_content_type(false),
+    ///     )
+    ///     .service(index);
+    /// ```
+    pub struct CborConfig;
+
+    error: CborPayloadError,
+    content_type: CONTENT_TYPE,
+    accepts: |mime| is_application(mime, &["cbor"], Some("cbor")),
+    decode: serde_cbor::from_slice,
+    encode: serde_cbor::to_vec,
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+    use serde::{Deserialize, Serialize};
+
+    use super::*;
+    use crate::{
+        http::{header, StatusCode},
+        test::{call_service, init_service, read_body, TestRequest},
+        web, App, Error, FromRequest,
+    };
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    struct Person {
+        name: String,
+        age: u32,
+        tags: Vec<String>,
+    }
+
+    fn person() -> Person {
+        Person {
+            name: "Ferris".to_owned(),
+            age: 10,
+            tags: vec!["crab".to_owned()],
+        }
+    }
+
+    fn encode(person: &Person) -> Bytes {
+        Bytes::from(serde_cbor::to_vec(person).unwrap())
+    }
+
+    #[actix_rt::test]
+    async fn test_round_trip() {
+        let srv = init_service(App::new().route(
+            "/",
+            web::post().to(|person: Cbor<Person>| async move {
+                let mut person = person.into_inner();
+                person.age += 1;
+                Cbor(person)
+            }),
+        ))
+        .await;
+
+        for ct in &["application/cbor", "application/vnd.example+cbor"] {
+            let req = TestRequest::post()
+                .insert_header((header::CONTENT_TYPE, *ct))
+                .set_payload(encode(&person()))
+                .to_request();
+            let res = call_service(&srv, req).await;
+            assert_eq!(res.status(), StatusCode::OK);
+            assert_eq!(
+                res.headers().get(header::CONTENT_TYPE).unwrap(),
+                CONTENT_TYPE
+            );
+
+            let body = read_body(res).await;
+            let decoded: Person = serde_cbor::from_slice(&body).unwrap();
+            assert_eq!(
+                decoded,
+                Person {
+                    age: 11,
+                    ..person()
+                }
+            );
+        }
+    }
+
+    async fn extract_err(req: TestRequest) -> Error {
+        let (req, mut pl) = req.to_http_parts();
+        Cbor::<Person>::from_request(&req, &mut pl)
+            .await
+            .unwrap_err()
+    }
+
+    #[actix_rt::test]
+    async fn test_content_type() {
+        let err = extract_err(
+            TestRequest::post()
+                .insert_header((header::CONTENT_TYPE, "application/json"))
+                .set_payload(encode(&person())),
+        )
+        .await;
+        assert!(matches!(
+            err.as_error::<CborPayloadError>(),
+            Some(CborPayloadError::ContentType)
+        ));
+        assert_eq!(
+            err.as_response_error().status_code(),
+            StatusCode::BAD_REQUEST
+        );
+
+        let err = extract_err(TestRequest::post().set_payload(encode(&person()))).await;
+        assert!(matches!(
+            err.as_error::<CborPayloadError>(),
+            Some(CborPayloadError::ContentType)
+        ));
+
+        // without strict content type checking a missing content type is accepted
+        let (req, mut pl) = TestRequest::post()
+            .set_payload(encode(&person()))
+            .app_data(CborConfig::default().strict_content_type(false))
+            .to_http_parts();
+        let extracted = Cbor::<Person>::from_request(&req, &mut pl).await.unwrap();
+        assert_eq!(extracted.into_inner(), person());
+    }
+
+    #[actix_rt::test]
+    async fn test_extract_errors() {
+        let err = extract_err(
+            TestRequest::post()
+                .insert_header((header::CONTENT_TYPE, CONTENT_TYPE))
+                .set_payload(Bytes::from_static(b"\xff\x00")),
+        )
+        .await;
+        assert!(matches!(
+            err.as_error::<CborPayloadError>(),
+            Some(CborPayloadError::Deserialize(_))
+        ));
+        assert_eq!(
+            err.as_response_error().status_code(),
+            StatusCode::BAD_REQUEST
+        );
+
+        let err = extract_err(
+            TestRequest::post()
+                .insert_header((header::CONTENT_TYPE, CONTENT_TYPE))
+                .set_payload(encode(&person()))
+                .app_data(CborConfig::default().limit(8)),
+        )
+        .await;
+        assert!(matches!(
+            err.as_error::<CborPayloadError>(),
+            Some(CborPayloadError::Overflow { limit: 8 })
+        ));
+        assert_eq!(
+            err.as_response_error().status_code(),
+            StatusCode::PAYLOAD_TOO_LARGE
+        );
+    }
+}
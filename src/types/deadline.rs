@@ -0,0 +1,262 @@
+//! For deadline extractor helper documentation, see [`Deadline`].
+
+use std::{future::Future, time::Duration};
+
+use futures_util::future::{ok, Ready};
+
+use crate::{
+    dev::Payload,
+    extract::FromRequest,
+    http::{header::HeaderName, HeaderMap},
+    rt::time::{sleep_until, Instant},
+    Error, HttpMessage, HttpRequest,
+};
+
+/// Name of the header that carries the remaining time budget by default.
+const DEFAULT_HEADER: &str = "x-request-deadline-ms";
+
+/// Default upper bound of the time budget.
+const DEFAULT_MAX: Duration = Duration::from_secs(30);
+
+/// Deadline of a request, propagated by the caller.
+///
+/// The deadline is read from a header that carries the remaining time budget of the request in
+/// milliseconds, `x-request-deadline-ms` by default. The budget is capped at the configured
+/// maximum, which is also used when the header is missing or invalid. Use [`DeadlineConfig`] to
+/// configure the header name and maximum.
+///
+/// The deadline is fixed when it is first extracted, or when the request passes the
+/// [`EnforceDeadline`](crate::middleware::EnforceDeadline) middleware, and is shared by all later
+/// extractions for the same request. Time is measured by the runtime clock.
+///
+/// # Examples
+/// ```
+/// use std::time::Duration;
+///
+/// use actix_web::{get, rt, web, HttpResponse};
+/// use futures_util::future::{select, Either};
+///
+/// #[get("/")]
+/// async fn index(deadline: web::Deadline) -> HttpResponse {
+///     let work = rt::sleep(Duration::from_millis(100));
+///     let expired = deadline.expired();
+///     rt::pin!(work, expired);
+///
+///     match select(work, expired).await {
+///         Either::Left(_) => HttpResponse::Ok().body("done"),
+///         Either::Right(_) => HttpResponse::GatewayTimeout().finish(),
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Deadline {
+    at: Instant,
+}
+
+impl Deadline {
+    /// Create deadline that expires after `budget`.
+    pub fn after(budget: Duration) -> Self {
+        Deadline {
+            at: Instant::now() + budget,
+        }
+    }
+
+    /// Returns the instant the deadline expires at.
+    pub fn instant(&self) -> Instant {
+        self.at
+    }
+
+    /// Returns the time left until the deadline expires, or zero if it has expired.
+    pub fn remaining(&self) -> Duration {
+        self.at.saturating_duration_since(Instant::now())
+    }
+
+    /// Returns true if the deadline has expired.
+    pub fn is_expired(&self) -> bool {
+        Instant::now() >= self.at
+    }
+
+    /// Returns a future that resolves when the deadline expires.
+    ///
+    /// The future can be raced against the work of a handler to stop it in time.
+    pub fn expired(&self) -> impl Future<Output = ()> {
+        sleep_until(self.at)
+    }
+
+    /// Returns the deadline of the request, reading it from the request headers if it has not
+    /// been fixed yet.
+    pub(crate) fn of_request<M: HttpMessage>(msg: &M, config: Option<&DeadlineConfig>) -> Self {
+        if let Some(deadline) = msg.extensions().get::<Deadline>() {
+            return *deadline;
+        }
+
+        let deadline = match config {
+            Some(config) => config.deadline(msg.headers()),
+            None => DeadlineConfig::default().deadline(msg.headers()),
+        };
+        msg.extensions_mut().insert(deadline);
+
+        deadline
+    }
+}
+
+impl FromRequest for Deadline {
+    type Error = Error;
+    type Future = Ready<Result<Self, Error>>;
+    type Config = DeadlineConfig;
+
+    #[inline]
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        ok(Deadline::of_request(
+            req,
+            req.app_data_or_data::<Self::Config>(),
+        ))
+    }
+}
+
+/// [`Deadline`] extractor configuration.
+///
+/// ```
+/// use std::time::Duration;
+///
+/// use actix_web::{http::header::HeaderName, web, App};
+///
+/// App::new().app_data(
+///     web::DeadlineConfig::default()
+///         .header_name(HeaderName::from_static("x-timeout-ms"))
+///         .max(Duration::from_secs(5)),
+/// );
+/// ```
+#[derive(Debug, Clone)]
+pub struct DeadlineConfig {
+    header_name: HeaderName,
+    max: Duration,
+}
+
+impl DeadlineConfig {
+    /// Set name of the header that carries the remaining time budget in milliseconds. By default
+    /// this is `x-request-deadline-ms`.
+    pub fn header_name(mut self, header_name: HeaderName) -> Self {
+        self.header_name = header_name;
+        self
+    }
+
+    /// Set upper bound of the time budget, which is also used when the header is missing or
+    /// invalid. By default this is 30 seconds.
+    pub fn max(mut self, max: Duration) -> Self {
+        self.max = max;
+        self
+    }
+
+    fn deadline(&self, headers: &HeaderMap) -> Deadline {
+        let budget = headers
+            .get(&self.header_name)
+            .and_then(|val| val.to_str().ok())
+            .and_then(|val| val.trim().parse::<u64>().ok())
+            .map_or(self.max, |ms| Duration::from_millis(ms).min(self.max));
+
+        Deadline::after(budget)
+    }
+}
+
+impl Default for DeadlineConfig {
+    fn default() -> Self {
+        DeadlineConfig {
+            header_name: HeaderName::from_static(DEFAULT_HEADER),
+            max: DEFAULT_MAX,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures_util::future::{select, Either};
+
+    use super::*;
+    use crate::{
+        http::StatusCode,
+        rt,
+        test::{call_service, init_service, read_body, TestRequest},
+        web, App, HttpResponse,
+    };
+
+    async fn extract(req: TestRequest) -> Deadline {
+        let (req, mut pl) = req.to_http_parts();
+        Deadline::from_request(&req, &mut pl).await.unwrap()
+    }
+
+    #[actix_rt::test]
+    async fn test_budget() {
+        let deadline =
+            extract(TestRequest::default().insert_header((DEFAULT_HEADER, "500"))).await;
+        assert!(deadline.remaining() <= Duration::from_millis(500));
+        assert!(deadline.remaining() > Duration::from_millis(400));
+        assert!(!deadline.is_expired());
+
+        // missing and invalid budgets fall back to the max
+        for req in vec![
+            TestRequest::default(),
+            TestRequest::default().insert_header((DEFAULT_HEADER, "soon")),
+        ] {
+            let deadline = extract(req).await;
+            assert!(deadline.remaining() > Duration::from_secs(29));
+        }
+
+        // budgets are capped at the max
+        let deadline = extract(
+            TestRequest::default()
+                .insert_header(("x-timeout-ms", "60000"))
+                .app_data(
+                    DeadlineConfig::default()
+                        .header_name(HeaderName::from_static("x-timeout-ms"))
+                        .max(Duration::from_secs(1)),
+                ),
+        )
+        .await;
+        assert!(deadline.remaining() <= Duration::from_secs(1));
+        assert!(deadline.remaining() > Duration::from_millis(900));
+    }
+
+    #[actix_rt::test]
+    async fn test_expired() {
+        let (req, mut pl) = TestRequest::default()
+            .insert_header((DEFAULT_HEADER, "0"))
+            .to_http_parts();
+        let deadline = Deadline::from_request(&req, &mut pl).await.unwrap();
+        assert!(deadline.is_expired());
+        assert_eq!(deadline.remaining(), Duration::from_secs(0));
+        deadline.expired().await;
+
+        // the deadline is fixed at the first extraction
+        rt::sleep(Duration::from_millis(10)).await;
+        let again = Deadline::from_request(&req, &mut pl).await.unwrap();
+        assert_eq!(again, deadline);
+    }
+
+    #[actix_rt::test]
+    async fn test_race_slow_handler() {
+        let srv = init_service(App::new().route(
+            "/",
+            web::get().to(|deadline: Deadline| async move {
+                let work = rt::sleep(Duration::from_secs(5));
+                let expired = deadline.expired();
+                rt::pin!(work, expired);
+
+                match select(work, expired).await {
+                    Either::Left(_) => HttpResponse::Ok().body("done"),
+                    Either::Right(_) => HttpResponse::GatewayTimeout().body("expired"),
+                }
+            }),
+        ))
+        .await;
+
+        let start = Instant::now();
+        let req = TestRequest::default()
+            .insert_header((DEFAULT_HEADER, "50"))
+            .to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::GATEWAY_TIMEOUT);
+        assert_eq!(read_body(res).await, "expired");
+        assert!(start.elapsed() < Duration::from_secs(1));
+    }
+}
@@ -66,6 +66,40 @@ use crate::{
 ///     }
 /// }
 /// ```
+///
+/// A `Result` whose error type is itself a `Responder` can be converted into an `Either` so that
+/// the error branch is rendered directly instead of going through `Error` conversion. This
+/// allows error responses to keep a structured body and their own status code.
+///
+/// ```
+/// use actix_web::{get, http::StatusCode, web, Either};
+/// use serde::Serialize;
+///
+/// #[derive(Serialize)]
+/// struct Item {
+///     id: u32,
+/// }
+///
+/// #[derive(Serialize)]
+/// struct NotFound {
+///     message: &'static str,
+/// }
+///
+/// type ItemError = (web::Json<NotFound>, StatusCode);
+///
+/// fn find_item(id: u32) -> Result<web::Json<Item>, ItemError> {
+///     if id == 1 {
+///         Ok(web::Json(Item { id }))
+///     } else {
+///         Err((web::Json(NotFound { message: "no such item" }), StatusCode::NOT_FOUND))
+///     }
+/// }
+///
+/// #[get("/{id}")]
+/// async fn index(id: web::Path<u32>) -> Either<web::Json<Item>, ItemError> {
+///     find_item(id.into_inner()).into()
+/// }
+/// ```
 #[derive(Debug, PartialEq)]
 pub enum Either<L, R> {
     /// A value of type `L`.
@@ -111,6 +145,15 @@ impl<L, R> From<Either<L, R>> for either::Either<L, R> {
     }
 }
 
+impl<L, R> From<Result<L, R>> for Either<L, R> {
+    fn from(val: Result<L, R>) -> Self {
+        match val {
+            Ok(l) => Either::Left(l),
+            Err(r) => Either::Right(r),
+        }
+    }
+}
+
 #[cfg(test)]
 impl<L, R> Either<L, R> {
     pub(self) fn unwrap_left(self) -> L {
@@ -229,8 +272,10 @@ mod tests {
 
     use super::*;
     use crate::{
-        test::TestRequest,
-        web::{Form, Json},
+        http::StatusCode,
+        test::{self, TestRequest},
+        web::{self, Form, Json},
+        App,
     };
 
     #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -303,4 +348,52 @@ mod tests {
         .into_inner();
         assert_eq!(&form.hello, "world");
     }
+
+    #[actix_rt::test]
+    async fn test_either_from_result_responder() {
+        use actix_service::Service;
+
+        #[derive(Serialize)]
+        struct NotFound {
+            message: &'static str,
+        }
+
+        async fn index(
+            id: web::Path<u32>,
+        ) -> Either<&'static str, (Json<NotFound>, StatusCode)> {
+            let res = if id.into_inner() == 1 {
+                Ok("found")
+            } else {
+                Err((
+                    Json(NotFound {
+                        message: "not found",
+                    }),
+                    StatusCode::NOT_FOUND,
+                ))
+            };
+
+            res.into()
+        }
+
+        let srv = test::init_service(App::new().route("/{id}", web::get().to(index))).await;
+
+        let req = TestRequest::with_uri("/1").to_request();
+        let res = srv.call(req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(test::read_body(res).await, Bytes::from_static(b"found"));
+
+        let req = TestRequest::with_uri("/2").to_request();
+        let res = srv.call(req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+        assert_eq!(
+            res.headers()
+                .get(crate::http::header::CONTENT_TYPE)
+                .unwrap(),
+            "application/json"
+        );
+        assert_eq!(
+            test::read_body(res).await,
+            Bytes::from_static(br#"{"message":"not found"}"#)
+        );
+    }
 }
@@ -0,0 +1,288 @@
+//! Body collection shared by the extractors of binary message formats.
+
+use std::future::Future;
+
+use actix_http::Payload;
+use bytes::Bytes;
+
+use crate::{error::PayloadError, types::payload::HttpMessageBody, HttpMessage, HttpRequest};
+
+/// Default maximum accepted payload size.
+pub(crate) const DEFAULT_LIMIT: usize = 262_144; // 2^18 bytes (~256kB)
+
+/// Errors that can occur while collecting the body of an encoded message.
+///
+/// Each format converts these into its own payload error type.
+#[derive(Debug)]
+pub(crate) enum BodyError {
+    /// The content type was rejected.
+    ContentType,
+
+    /// Payload size is bigger than `limit`.
+    Overflow { limit: usize },
+
+    /// Error that occurred while reading the payload.
+    Payload(PayloadError),
+}
+
+/// Checks the content type of the request and collects its body.
+///
+/// `content_type` is called with the parsed content type of the request, or `None` if it is
+/// missing, and decides whether the request is accepted. The body is decompressed according to
+/// its `Content-Encoding` and may be at most `limit` bytes long.
+pub(crate) fn collect_body<F>(
+    req: &HttpRequest,
+    payload: &mut Payload,
+    limit: usize,
+    content_type: F,
+) -> impl Future<Output = Result<Bytes, BodyError>>
+where
+    F: FnOnce(Option<&mime::Mime>) -> bool,
+{
+    let accepted = match req.mime_type() {
+        Ok(mime) => content_type(mime.as_ref()),
+        Err(_) => false,
+    };
+
+    let body = if accepted {
+        Some(HttpMessageBody::new(req, payload).limit(limit))
+    } else {
+        None
+    };
+
+    async move {
+        match body {
+            Some(body) => body.await.map_err(|err| match err {
+                PayloadError::Overflow => BodyError::Overflow { limit },
+                err => BodyError::Payload(err),
+            }),
+            None => Err(BodyError::ContentType),
+        }
+    }
+}
+
+/// Returns true if `mime` is `application/<subtype>`, or has a `+<suffix>` structured syntax
+/// suffix when `suffix` is given.
+pub(crate) fn is_application(
+    mime: &mime::Mime,
+    subtypes: &[&str],
+    suffix: Option<&str>,
+) -> bool {
+    mime.type_() == mime::APPLICATION
+        && (subtypes.contains(&mime.subtype().as_str())
+            || suffix.map_or(false, |suffix| {
+                mime.suffix().map_or(false, |name| name == suffix)
+            }))
+}
+
+/// Defines an extractor and responder for a serde based format, along with its configuration.
+///
+/// The format is described by the content type of responses, a closure deciding whether a request
+/// content type is accepted, and its `from_slice` and `to_vec` functions.
+#[cfg(any(feature = "cbor", feature = "msgpack"))]
+macro_rules! serde_format {
+    (
+        $(#[$meta:meta])*
+        pub struct $name:ident;
+
+        $(#[$config_meta:meta])*
+        pub struct $config:ident;
+
+        error: $error:ident,
+        content_type: $content_type:expr,
+        accepts: $accepts:expr,
+        decode: $decode:path,
+        encode: $encode:path,
+    ) => {
+        $(#[$meta])*
+        pub struct $name<T>(pub T);
+
+        impl<T> $name<T> {
+            /// Unwrap into inner `T` value.
+            pub fn into_inner(self) -> T {
+                self.0
+            }
+        }
+
+        impl<T> std::ops::Deref for $name<T> {
+            type Target = T;
+
+            fn deref(&self) -> &T {
+                &self.0
+            }
+        }
+
+        impl<T> std::ops::DerefMut for $name<T> {
+            fn deref_mut(&mut self) -> &mut T {
+                &mut self.0
+            }
+        }
+
+        impl<T: std::fmt::Debug> std::fmt::Debug for $name<T> {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                write!(f, concat!(stringify!($name), ": {:?}"), self.0)
+            }
+        }
+
+        /// See [here](#extractor) for example of usage as an extractor.
+        impl<T> $crate::FromRequest for $name<T>
+        where
+            T: serde::de::DeserializeOwned + 'static,
+        {
+            type Config = $config;
+            type Error = $crate::Error;
+            type Future =
+                futures_util::future::LocalBoxFuture<'static, Result<Self, $crate::Error>>;
+
+            #[inline]
+            fn from_request(
+                req: &$crate::HttpRequest,
+                payload: &mut actix_http::Payload,
+            ) -> Self::Future {
+                use futures_util::future::FutureExt as _;
+
+                use $crate::types::encoded::{collect_body, BodyError};
+
+                let req2 = req.clone();
+                let (limit, strict, err_handler) = req
+                    .app_data_or_data::<Self::Config>()
+                    .map(|c| (c.limit, c.strict_content_type, c.err_handler.clone()))
+                    .unwrap_or(($crate::types::encoded::DEFAULT_LIMIT, true, None));
+
+                let accepts: fn(&mime::Mime) -> bool = $accepts;
+                let body = collect_body(req, payload, limit, move |mime| match mime {
+                    Some(mime) => accepts(mime),
+                    None => !strict,
+                });
+
+                async move {
+                    let res = body
+                        .await
+                        .map_err(|err| match err {
+                            BodyError::ContentType => $error::ContentType,
+                            BodyError::Overflow { limit } => $error::Overflow { limit },
+                            BodyError::Payload(err) => $error::Payload(err),
+                        })
+                        .and_then(|body| $decode(&body).map_err($error::Deserialize));
+
+                    match res {
+                        Ok(data) => Ok($name(data)),
+                        Err(err) => match err_handler {
+                            Some(err_handler) => Err((err_handler)(err, &req2)),
+                            None => Err(err.into()),
+                        },
+                    }
+                }
+                .boxed_local()
+            }
+        }
+
+        /// See [here](#responder) for example of usage as a handler return type.
+        impl<T: serde::Serialize> $crate::Responder for $name<T> {
+            fn respond_to(self, _: &$crate::HttpRequest) -> $crate::HttpResponse {
+                match $encode(&self.0) {
+                    Ok(body) => $crate::HttpResponse::Ok()
+                        .content_type($content_type)
+                        .body(body),
+                    Err(err) => $crate::HttpResponse::from_error(
+                        $crate::error::ErrorInternalServerError(err),
+                    ),
+                }
+            }
+        }
+
+        $(#[$config_meta])*
+        #[derive(Clone)]
+        pub struct $config {
+            limit: usize,
+            strict_content_type: bool,
+            err_handler:
+                Option<std::rc::Rc<dyn Fn($error, &$crate::HttpRequest) -> $crate::Error>>,
+        }
+
+        impl $config {
+            /// Set maximum accepted payload size. By default this limit is 256kB.
+            pub fn limit(mut self, limit: usize) -> Self {
+                self.limit = limit;
+                self
+            }
+
+            /// Set whether requests must have a content type of this format. By default this is
+            /// `true`.
+            ///
+            /// When `false`, requests without a `Content-Type` header are accepted as well.
+            /// Requests with a different content type are always rejected.
+            pub fn strict_content_type(mut self, strict: bool) -> Self {
+                self.strict_content_type = strict;
+                self
+            }
+
+            /// Set custom error handler.
+            pub fn error_handler<F>(mut self, f: F) -> Self
+            where
+                F: Fn($error, &$crate::HttpRequest) -> $crate::Error + 'static,
+            {
+                self.err_handler = Some(std::rc::Rc::new(f));
+                self
+            }
+        }
+
+        impl Default for $config {
+            fn default() -> Self {
+                $config {
+                    limit: $crate::types::encoded::DEFAULT_LIMIT,
+                    strict_content_type: true,
+                    err_handler: None,
+                }
+            }
+        }
+    };
+}
+
+#[cfg(any(feature = "cbor", feature = "msgpack"))]
+pub(crate) use serde_format;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{http::header, test::TestRequest};
+
+    #[actix_rt::test]
+    async fn test_collect_body() {
+        let (req, mut pl) = TestRequest::default()
+            .insert_header((header::CONTENT_TYPE, "application/cbor"))
+            .set_payload(Bytes::from_static(b"0123456789"))
+            .to_http_parts();
+        let body = collect_body(&req, &mut pl, 16, |mime| {
+            is_application(mime.unwrap(), &["cbor"], None)
+        })
+        .await;
+        assert_eq!(body.unwrap(), "0123456789");
+
+        let (req, mut pl) = TestRequest::default()
+            .set_payload(Bytes::from_static(b"0123456789"))
+            .to_http_parts();
+        let body = collect_body(&req, &mut pl, 4, |mime| mime.is_none()).await;
+        assert!(matches!(body, Err(BodyError::Overflow { limit: 4 })));
+
+        let (req, mut pl) = TestRequest::default()
+            .insert_header((header::CONTENT_TYPE, "text/plain"))
+            .to_http_parts();
+        let body = collect_body(&req, &mut pl, 16, |mime| mime.is_none()).await;
+        assert!(matches!(body, Err(BodyError::ContentType)));
+    }
+
+    #[test]
+    fn test_is_application() {
+        let mime = "application/cbor".parse().unwrap();
+        assert!(is_application(&mime, &["cbor"], None));
+        assert!(!is_application(&mime, &["msgpack"], None));
+
+        let mime = "application/vnd.example+cbor".parse().unwrap();
+        assert!(is_application(&mime, &["cbor"], Some("cbor")));
+        assert!(!is_application(&mime, &["cbor"], None));
+
+        let mime = "text/cbor".parse().unwrap();
+        assert!(!is_application(&mime, &["cbor"], Some("cbor")));
+    }
+}
@@ -21,8 +21,11 @@ use serde::{de::DeserializeOwned, Serialize};
 #[cfg(feature = "compress")]
 use crate::dev::Decompress;
 use crate::{
-    error::UrlencodedError, extract::FromRequest, http::header::CONTENT_LENGTH, web, Error,
-    HttpMessage, HttpRequest, HttpResponse, Responder,
+    error::UrlencodedError,
+    extract::FromRequest,
+    http::header::CONTENT_LENGTH,
+    types::{ExtractorLimits, QueryParser},
+    Error, HttpMessage, HttpRequest, HttpResponse, Responder,
 };
 
 /// URL encoded payload extractor and responder.
@@ -118,17 +121,19 @@ where
     #[inline]
     fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
         let req2 = req.clone();
-        let (limit, err_handler) = req
-            .app_data::<Self::Config>()
+        let (limit, parser, err_handler) = req
+            .app_data_or_data::<Self::Config>()
+            .map(|c| (c.limit, c.parser, c.err_handler.clone()))
             .or_else(|| {
-                req.app_data::<web::Data<Self::Config>>()
-                    .map(|d| d.as_ref())
+                req.app_data_or_data::<ExtractorLimits>()
+                    .and_then(|limits| limits.form_limit)
+                    .map(|limit| (limit, QueryParser::default(), None))
             })
-            .map(|c| (c.limit, c.err_handler.clone()))
-            .unwrap_or((16384, None));
+            .unwrap_or((16384, QueryParser::default(), None));
 
         UrlEncoded::new(req, payload)
             .limit(limit)
+            .parser(parser)
             .map(move |res| match res {
                 Err(err) => match err_handler {
                     Some(err_handler) => Err((err_handler)(err, &req2)),
@@ -189,6 +194,7 @@ impl<T: Serialize> Responder for Form<T> {
 #[derive(Clone)]
 pub struct FormConfig {
     limit: usize,
+    parser: QueryParser,
     err_handler: Option<Rc<dyn Fn(UrlencodedError, &HttpRequest) -> Error>>,
 }
 
@@ -199,6 +205,13 @@ impl FormConfig {
         self
     }
 
+    /// Set the deserializer for form bodies, e.g. [`QueryParser::Extended`] for nested fields.
+    /// By default it is [`QueryParser::Urlencoded`].
+    pub fn parser(mut self, parser: QueryParser) -> Self {
+        self.parser = parser;
+        self
+    }
+
     /// Set custom error handler
     pub fn error_handler<F>(mut self, f: F) -> Self
     where
@@ -213,6 +226,7 @@ impl Default for FormConfig {
     fn default() -> Self {
         FormConfig {
             limit: 16_384, // 2^14 bytes (~16kB)
+            parser: QueryParser::default(),
             err_handler: None,
         }
     }
@@ -232,6 +246,7 @@ pub struct UrlEncoded<T> {
     stream: Option<Payload>,
 
     limit: usize,
+    parser: QueryParser,
     length: Option<usize>,
     encoding: &'static Encoding,
     err: Option<UrlencodedError>,
@@ -273,6 +288,7 @@ impl<T> UrlEncoded<T> {
             encoding,
             stream: Some(payload),
             limit: 32_768,
+            parser: QueryParser::default(),
             length: len,
             fut: None,
             err: None,
@@ -283,6 +299,7 @@ impl<T> UrlEncoded<T> {
         UrlEncoded {
             stream: None,
             limit: 32_768,
+            parser: QueryParser::default(),
             fut: None,
             err: Some(err),
             length: None,
@@ -295,6 +312,12 @@ impl<T> UrlEncoded<T> {
         self.limit = limit;
         self
     }
+
+    /// Set the deserializer for the payload. By default it is [`QueryParser::Urlencoded`].
+    pub fn parser(mut self, parser: QueryParser) -> Self {
+        self.parser = parser;
+        self
+    }
 }
 
 impl<T> Future for UrlEncoded<T>
@@ -322,6 +345,7 @@ where
 
         // future
         let encoding = self.encoding;
+        let parser = self.parser;
         let mut stream = self.stream.take().unwrap();
 
         self.fut = Some(
@@ -341,15 +365,16 @@ where
                     }
                 }
 
-                if encoding == UTF_8 {
+                if encoding == UTF_8 && parser == QueryParser::Urlencoded {
                     serde_urlencoded::from_bytes::<T>(&body).map_err(|_| UrlencodedError::Parse)
                 } else {
                     let body = encoding
                         .decode_without_bom_handling_and_without_replacement(&body)
-                        .map(|s| s.into_owned())
                         .ok_or(UrlencodedError::Parse)?;
 
-                    serde_urlencoded::from_str::<T>(&body).map_err(|_| UrlencodedError::Parse)
+                    parser
+                        .deserialize::<T>(&body)
+                        .map_err(|_| UrlencodedError::Parse)
                 }
             }
             .boxed_local(),
@@ -370,6 +395,7 @@ mod tests {
         StatusCode,
     };
     use crate::test::TestRequest;
+    use crate::{error, web};
 
     #[derive(Deserialize, Serialize, Debug, PartialEq)]
     struct Info {
@@ -395,6 +421,55 @@ mod tests {
         );
     }
 
+    #[actix_rt::test]
+    async fn test_form_extended_parser() {
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct Nested {
+            info: Info,
+            tags: Vec<String>,
+        }
+
+        let body = "info[hello]=world&info[counter]=123&tags[0]=a&tags[1]=b";
+        let (req, mut pl) = TestRequest::default()
+            .insert_header((CONTENT_TYPE, "application/x-www-form-urlencoded"))
+            .app_data(FormConfig::default().parser(QueryParser::Extended))
+            .set_payload(body)
+            .to_http_parts();
+
+        let Form(s) = Form::<Nested>::from_request(&req, &mut pl).await.unwrap();
+        assert_eq!(
+            s,
+            Nested {
+                info: Info {
+                    hello: "world".into(),
+                    counter: 123
+                },
+                tags: vec!["a".into(), "b".into()],
+            }
+        );
+
+        let (req, mut pl) = TestRequest::default()
+            .insert_header((CONTENT_TYPE, "application/x-www-form-urlencoded"))
+            .app_data(
+                FormConfig::default()
+                    .parser(QueryParser::Extended)
+                    .error_handler(|err, _| {
+                        assert!(matches!(err, UrlencodedError::Parse));
+                        error::ErrorConflict("nested")
+                    }),
+            )
+            .set_payload("info[hello]=world&info[counter]=x")
+            .to_http_parts();
+
+        let err = Form::<Nested>::from_request(&req, &mut pl)
+            .await
+            .unwrap_err();
+        assert_eq!(
+            err.as_response_error().error_response().status(),
+            StatusCode::CONFLICT
+        );
+    }
+
     fn eq(err: UrlencodedError, other: UrlencodedError) -> bool {
         match err {
             UrlencodedError::Overflow { .. } => {
@@ -0,0 +1,108 @@
+//! For header extractor helper documentation, see [`Header`](crate::types::Header).
+
+use std::{fmt, ops};
+
+use futures_util::future::{err, ok, Ready};
+
+use crate::{
+    dev::Payload, error::ParseError, extract::FromRequest, http::header::Header as ParseHeader,
+    HttpRequest,
+};
+
+/// Extract typed headers from the request.
+///
+/// To extract a header, the inner type `T` must implement the
+/// [`Header`](crate::http::header::Header) trait. Extraction fails with a `400 Bad Request` if the
+/// header is missing or cannot be parsed.
+///
+/// # Examples
+/// ```
+/// use actix_web::{get, web, http::header};
+///
+/// #[get("/")]
+/// async fn index(cache_control: web::Header<header::CacheControl>) -> String {
+///     format!("Cache-Control: {}", cache_control.into_inner())
+/// }
+/// ```
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Header<T>(pub T);
+
+impl<T> Header<T> {
+    /// Unwrap into inner `T` value.
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> ops::Deref for Header<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T> ops::DerefMut for Header<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.0
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for Header<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl<T: fmt::Display> fmt::Display for Header<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl<T> FromRequest for Header<T>
+where
+    T: ParseHeader,
+{
+    type Error = ParseError;
+    type Future = Ready<Result<Self, Self::Error>>;
+    type Config = ();
+
+    #[inline]
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        match ParseHeader::parse(req) {
+            Ok(header) => ok(Header(header)),
+            Err(e) => err(e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::header::{self, CacheControl, CacheDirective};
+    use crate::test::TestRequest;
+
+    #[actix_rt::test]
+    async fn test_header_extract() {
+        let (req, mut pl) = TestRequest::default()
+            .insert_header((header::CACHE_CONTROL, "no-cache, max-age=60, x-ext=\"a\""))
+            .to_http_parts();
+
+        let cache_control = Header::<CacheControl>::from_request(&req, &mut pl)
+            .await
+            .unwrap();
+        assert_eq!(
+            cache_control.into_inner(),
+            CacheControl(vec![
+                CacheDirective::NoCache,
+                CacheDirective::MaxAge(60),
+                CacheDirective::Extension("x-ext".to_owned(), Some("\"a\"".to_owned())),
+            ])
+        );
+
+        let (req, mut pl) = TestRequest::default().to_http_parts();
+        let res = Header::<CacheControl>::from_request(&req, &mut pl).await;
+        assert!(res.is_err());
+    }
+}
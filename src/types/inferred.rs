@@ -0,0 +1,63 @@
+//! For binary responses with a detected content type, see [`Inferred`].
+
+use bytes::Bytes;
+
+use crate::{http::sniff, HttpRequest, HttpResponse, Responder};
+
+/// Responder that sends a binary body with a `Content-Type` detected from its content.
+///
+/// Binary responses such as `Bytes` are sent as `application/octet-stream`. Wrapping one in
+/// `Inferred` sets the content type from the signature of the data instead, using
+/// [`sniff::content_type`]; unrecognized data is still sent as `application/octet-stream`.
+///
+/// ```no_run
+/// use actix_web::{get, web, Responder};
+///
+/// #[get("/logo")]
+/// async fn logo() -> impl Responder {
+///     let data: Vec<u8> = std::fs::read("logo").unwrap();
+///     web::Inferred(data)
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Inferred<T = Bytes>(pub T);
+
+impl<T> Inferred<T> {
+    /// Unwrap into the inner body.
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T: Into<Bytes>> Responder for Inferred<T> {
+    fn respond_to(self, _: &HttpRequest) -> HttpResponse {
+        let body = self.0.into();
+
+        HttpResponse::Ok()
+            .content_type(sniff::content_type(&body))
+            .body(body)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{http::header::CONTENT_TYPE, test::TestRequest};
+
+    #[test]
+    fn test_inferred_responder() {
+        let req = TestRequest::default().to_http_request();
+
+        let res = Inferred(b"\x89PNG\r\n\x1a\n\x00\x00".to_vec()).respond_to(&req);
+        assert_eq!(res.headers().get(CONTENT_TYPE).unwrap(), "image/png");
+
+        let res = Inferred(Bytes::from_static(b"%PDF-1.4")).respond_to(&req);
+        assert_eq!(res.headers().get(CONTENT_TYPE).unwrap(), "application/pdf");
+
+        let res = Inferred(Bytes::from_static(b"\x00\x01\x02")).respond_to(&req);
+        assert_eq!(
+            res.headers().get(CONTENT_TYPE).unwrap(),
+            "application/octet-stream"
+        );
+    }
+}
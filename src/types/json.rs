@@ -1,6 +1,7 @@
 //! For JSON helper documentation, see [`Json`].
 
 use std::{
+    borrow::Cow,
     fmt,
     future::Future,
     marker::PhantomData,
@@ -23,7 +24,8 @@ use crate::{
     extract::FromRequest,
     http::header::CONTENT_LENGTH,
     request::HttpRequest,
-    web, HttpMessage, HttpResponse, Responder,
+    types::ExtractorLimits,
+    HttpMessage, HttpResponse, Responder,
 };
 
 /// JSON extractor and responder.
@@ -260,12 +262,20 @@ impl JsonConfig {
         self
     }
 
-    /// Extract payload config from app data. Check both `T` and `Data<T>`, in that order, and fall
-    /// back to the default payload config.
-    fn from_req(req: &HttpRequest) -> &Self {
-        req.app_data::<Self>()
-            .or_else(|| req.app_data::<web::Data<Self>>().map(|d| d.as_ref()))
-            .unwrap_or(&DEFAULT_CONFIG)
+    /// Extract payload config from app data. Check both `T` and `Data<T>` at each layer, innermost first, then
+    /// the JSON limit of [`ExtractorLimits`], and fall back to the default payload config.
+    fn from_req(req: &HttpRequest) -> Cow<'_, Self> {
+        if let Some(config) = req.app_data_or_data::<Self>() {
+            return Cow::Borrowed(config);
+        }
+
+        match req
+            .app_data_or_data::<ExtractorLimits>()
+            .and_then(|limits| limits.json_limit)
+        {
+            Some(limit) => Cow::Owned(DEFAULT_CONFIG.limit(limit)),
+            None => Cow::Borrowed(&DEFAULT_CONFIG),
+        }
     }
 }
 
@@ -364,7 +374,10 @@ where
             } => {
                 if let Some(len) = length {
                     if len > limit {
-                        return JsonBody::Error(Some(JsonPayloadError::Overflow));
+                        return JsonBody::Error(Some(JsonPayloadError::Overflow {
+                            limit,
+                            length,
+                        }));
                     }
                 }
 
@@ -393,6 +406,7 @@ where
         match this {
             JsonBody::Body {
                 limit,
+                length,
                 buf,
                 payload,
                 ..
@@ -402,13 +416,16 @@ where
                     Some(chunk) => {
                         let chunk = chunk?;
                         if (buf.len() + chunk.len()) > *limit {
-                            return Poll::Ready(Err(JsonPayloadError::Overflow));
+                            return Poll::Ready(Err(JsonPayloadError::Overflow {
+                                limit: *limit,
+                                length: *length,
+                            }));
                         } else {
                             buf.extend_from_slice(&chunk);
                         }
                     }
                     None => {
-                        let json = serde_json::from_slice::<T>(&buf)?;
+                        let json = from_slice::<T>(&buf)?;
                         return Poll::Ready(Ok(json));
                     }
                 }
@@ -418,6 +435,33 @@ where
     }
 }
 
+/// Deserializes `T` from a JSON payload, recording the path of the failing value when the
+/// `json-error-path` feature is enabled.
+pub(crate) fn from_slice<T: DeserializeOwned>(buf: &[u8]) -> Result<T, JsonPayloadError> {
+    #[cfg(feature = "json-error-path")]
+    {
+        let mut de = serde_json::Deserializer::from_slice(buf);
+
+        let value = serde_path_to_error::deserialize(&mut de).map_err(|err| {
+            // the root path is displayed as "."
+            let path = Some(err.path().to_string()).filter(|path| path != ".");
+
+            JsonPayloadError::Deserialize {
+                source: err.into_inner(),
+                path,
+            }
+        })?;
+
+        de.end()?;
+        Ok(value)
+    }
+
+    #[cfg(not(feature = "json-error-path"))]
+    {
+        Ok(serde_json::from_slice(buf)?)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use bytes::Bytes;
@@ -425,12 +469,14 @@ mod tests {
 
     use super::*;
     use crate::{
+        dev::ServiceResponse,
         error::InternalError,
         http::{
             header::{self, CONTENT_LENGTH, CONTENT_TYPE},
             StatusCode,
         },
-        test::{load_stream, TestRequest},
+        test::{self, load_stream, TestRequest},
+        web,
     };
 
     #[derive(Serialize, Deserialize, PartialEq, Debug)]
@@ -440,7 +486,9 @@ mod tests {
 
     fn json_eq(err: JsonPayloadError, other: JsonPayloadError) -> bool {
         match err {
-            JsonPayloadError::Overflow => matches!(other, JsonPayloadError::Overflow),
+            JsonPayloadError::Overflow { .. } => {
+                matches!(other, JsonPayloadError::Overflow { .. })
+            }
             JsonPayloadError::ContentType => {
                 matches!(other, JsonPayloadError::ContentType)
             }
@@ -534,8 +582,10 @@ mod tests {
             .to_http_parts();
 
         let s = Json::<MyObject>::from_request(&req, &mut pl).await;
-        assert!(format!("{}", s.err().unwrap())
-            .contains("Json payload size is bigger than allowed"));
+        assert_eq!(
+            s.err().unwrap().to_string(),
+            "Json payload (16 bytes) is larger than allowed (limit: 10 bytes)"
+        );
 
         let (req, mut pl) = TestRequest::default()
             .insert_header((
@@ -586,7 +636,13 @@ mod tests {
         let json = JsonBody::<MyObject>::new(&req, &mut pl, None)
             .limit(100)
             .await;
-        assert!(json_eq(json.err().unwrap(), JsonPayloadError::Overflow));
+        assert!(json_eq(
+            json.err().unwrap(),
+            JsonPayloadError::Overflow {
+                limit: 100,
+                length: Some(10000),
+            }
+        ));
 
         let (req, mut pl) = TestRequest::default()
             .insert_header((
@@ -683,6 +739,125 @@ mod tests {
         assert!(s.is_err());
 
         let err_str = s.err().unwrap().to_string();
-        assert!(err_str.contains("Json payload size is bigger than allowed"));
+        assert!(err_str.contains("is larger than allowed (limit: 10 bytes)"));
+    }
+
+    async fn error_body(err: Error) -> serde_json::Value {
+        let res = err.as_response_error().error_response();
+        let req = TestRequest::default().to_http_request();
+        let body = test::read_body(ServiceResponse::new(req, res)).await;
+        serde_json::from_slice(&body).unwrap()
+    }
+
+    #[actix_rt::test]
+    async fn test_overflow_error_detail() {
+        let (req, mut pl) = TestRequest::default()
+            .insert_header((CONTENT_TYPE, mime::APPLICATION_JSON))
+            .insert_header((CONTENT_LENGTH, 16))
+            .set_payload(Bytes::from_static(b"{\"name\": \"test\"}"))
+            .app_data(JsonConfig::default().limit(10).error_handler(|err, _| {
+                assert!(matches!(
+                    err,
+                    JsonPayloadError::Overflow {
+                        limit: 10,
+                        length: Some(16),
+                    }
+                ));
+                err.into()
+            }))
+            .to_http_parts();
+
+        let err = Json::<MyObject>::from_request(&req, &mut pl)
+            .await
+            .err()
+            .unwrap();
+        assert_eq!(
+            err.as_response_error().status_code(),
+            StatusCode::PAYLOAD_TOO_LARGE
+        );
+
+        let body = error_body(err).await;
+        assert_eq!(
+            body["error"],
+            "Json payload (16 bytes) is larger than allowed (limit: 10 bytes)"
+        );
+        assert_eq!(body["detail"]["limit"], 10);
+        assert_eq!(body["detail"]["length"], 16);
+
+        // without a content length the limit is exceeded while reading
+        let (req, mut pl) = TestRequest::default()
+            .insert_header((CONTENT_TYPE, mime::APPLICATION_JSON))
+            .set_payload(Bytes::from_static(b"{\"name\": \"test\"}"))
+            .to_http_parts();
+
+        let err = JsonBody::<MyObject>::new(&req, &mut pl, None)
+            .limit(10)
+            .await
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Json payload is larger than allowed (limit: 10 bytes)"
+        );
+    }
+
+    #[derive(Debug, Deserialize)]
+    #[allow(dead_code)]
+    struct Order {
+        customer: MyObject,
+        items: Vec<OrderItem>,
+    }
+
+    #[derive(Debug, Deserialize)]
+    #[allow(dead_code)]
+    struct OrderItem {
+        quantity: u32,
+    }
+
+    #[actix_rt::test]
+    async fn test_deserialize_error_detail() {
+        let payload = "{\"customer\": {\"name\": \"test\"},\n\
+                       \"items\": [{\"quantity\": 1}, {\"quantity\": \"secret\"}]}";
+
+        let (req, mut pl) = TestRequest::default()
+            .insert_header((CONTENT_TYPE, mime::APPLICATION_JSON))
+            .set_payload(payload)
+            .app_data(JsonConfig::default().error_handler(|err, _| {
+                match err {
+                    JsonPayloadError::Deserialize { ref source, .. } => {
+                        assert_eq!(source.line(), 2);
+                    }
+                    _ => panic!("unexpected error: {}", err),
+                }
+                err.into()
+            }))
+            .to_http_parts();
+
+        let err = Json::<Order>::from_request(&req, &mut pl)
+            .await
+            .err()
+            .unwrap();
+        assert_eq!(
+            err.as_response_error().status_code(),
+            StatusCode::BAD_REQUEST
+        );
+
+        let msg = err.to_string();
+        assert!(msg.starts_with("Json deserialize error: invalid data at line 2 column"));
+        assert!(!msg.contains("secret"));
+
+        let body = error_body(err).await;
+        assert!(!body.to_string().contains("secret"));
+        assert_eq!(body["error"], msg);
+        assert_eq!(body["detail"]["line"], 2);
+        assert!(body["detail"]["column"].as_u64().unwrap() > 0);
+
+        #[cfg(feature = "json-error-path")]
+        {
+            assert!(msg.ends_with(" (path: items[1].quantity)"));
+            assert_eq!(body["detail"]["path"], "items[1].quantity");
+        }
+
+        #[cfg(not(feature = "json-error-path"))]
+        assert!(body["detail"]["path"].is_null());
     }
 }
@@ -0,0 +1,121 @@
+//! For app-wide extractor defaults, see [`ExtractorLimits`].
+
+/// App-wide defaults for the built-in body and query extractors.
+///
+/// Registering one `ExtractorLimits` with `.app_data()` sets the limits of the `Bytes`, `String`,
+/// [`Json`](super::Json) and [`Form`](super::Form) extractors, and the strictness of
+/// [`Query`](super::Query), without registering a config type for each of them. An extractor's
+/// own config type, e.g. [`JsonConfig`](super::JsonConfig), still wins wherever it is registered;
+/// settings left unset here keep the extractor's built-in default.
+///
+/// ```
+/// use actix_web::{web, App};
+///
+/// let app = App::new()
+///     .app_data(
+///         web::ExtractorLimits::default()
+///             .body_limit(1_048_576)
+///             .json_limit(65_536)
+///             .form_limit(8_192)
+///             .query_strict(true),
+///     )
+///     // this resource accepts larger JSON payloads
+///     .service(
+///         web::resource("/upload")
+///             .app_data(web::JsonConfig::default().limit(4_194_304))
+///             .route(web::post().to(|| async { "" })),
+///     );
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ExtractorLimits {
+    pub(crate) body_limit: Option<usize>,
+    pub(crate) json_limit: Option<usize>,
+    pub(crate) form_limit: Option<usize>,
+    pub(crate) query_strict: Option<bool>,
+}
+
+impl ExtractorLimits {
+    /// Set maximum accepted payload size in bytes for `Bytes` and `String`.
+    pub fn body_limit(mut self, limit: usize) -> Self {
+        self.body_limit = Some(limit);
+        self
+    }
+
+    /// Set maximum accepted payload size in bytes for `Json`.
+    pub fn json_limit(mut self, limit: usize) -> Self {
+        self.json_limit = Some(limit);
+        self
+    }
+
+    /// Set maximum accepted payload size in bytes for `Form`.
+    pub fn form_limit(mut self, limit: usize) -> Self {
+        self.form_limit = Some(limit);
+        self
+    }
+
+    /// Set whether `Query` rejects malformed query strings, see
+    /// [`QueryConfig::strict`](super::QueryConfig::strict).
+    pub fn query_strict(mut self, strict: bool) -> Self {
+        self.query_strict = Some(strict);
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+    use serde_json::Value;
+
+    use super::*;
+    use crate::{
+        http::{header, StatusCode},
+        test::{call_service, init_service, TestRequest},
+        web, App, Responder,
+    };
+
+    #[actix_rt::test]
+    async fn test_umbrella_limits() {
+        async fn bytes_handler(_: Bytes) -> impl Responder {
+            "bytes"
+        }
+
+        async fn json_handler(_: web::Json<Value>) -> impl Responder {
+            "json"
+        }
+
+        let srv = init_service(
+            App::new()
+                .app_data(ExtractorLimits::default().body_limit(16).json_limit(16))
+                .route("/bytes", web::post().to(bytes_handler))
+                .route("/json", web::post().to(json_handler))
+                .service(
+                    web::resource("/json-override")
+                        .app_data(web::JsonConfig::default().limit(64))
+                        .route(web::post().to(json_handler)),
+                ),
+        )
+        .await;
+
+        let small = r#"{"a":1}"#;
+        let large = r#"{"name":"something longer"}"#;
+
+        for (path, body, status) in [
+            ("/bytes", small, StatusCode::OK),
+            ("/bytes", large, StatusCode::PAYLOAD_TOO_LARGE),
+            ("/json", small, StatusCode::OK),
+            ("/json", large, StatusCode::PAYLOAD_TOO_LARGE),
+            ("/json-override", large, StatusCode::OK),
+        ]
+        .iter()
+        {
+            let req = TestRequest::post()
+                .uri(path)
+                .insert_header((header::CONTENT_TYPE, "application/json"))
+                .insert_header((header::CONTENT_LENGTH, body.len()))
+                .set_payload(*body)
+                .to_request();
+            let res = call_service(&srv, req).await;
+            assert_eq!(res.status(), *status, "{} {}", path, body);
+        }
+    }
+}
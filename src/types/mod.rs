@@ -1,18 +1,57 @@
 //! Common extractors and responders.
 
 // TODO: review visibility
+pub mod auth;
+#[cfg(feature = "cbor")]
+mod cbor;
+mod deadline;
 mod either;
+#[cfg(any(feature = "cbor", feature = "msgpack", feature = "protobuf"))]
+mod encoded;
 pub(crate) mod form;
+mod header;
+mod inferred;
 pub(crate) mod json;
+mod limits;
+#[cfg(feature = "msgpack")]
+mod msgpack;
+pub mod multipart_stream;
+mod negotiate;
 mod path;
 pub(crate) mod payload;
+#[cfg(feature = "protobuf")]
+mod protobuf;
+pub mod proxy;
 mod query;
+mod query_parser;
+mod range_body;
+mod read_stream;
 pub(crate) mod readlines;
+mod redirect;
+#[cfg(feature = "secure-cookies")]
+pub mod signed_cookie;
+pub mod sse;
+pub mod ws;
 
+#[cfg(feature = "cbor")]
+pub use self::cbor::{Cbor, CborConfig};
+pub use self::deadline::{Deadline, DeadlineConfig};
 pub use self::either::{Either, EitherExtractError};
 pub use self::form::{Form, FormConfig};
+pub use self::header::Header;
+pub use self::inferred::Inferred;
 pub use self::json::{Json, JsonConfig};
+pub use self::limits::ExtractorLimits;
+#[cfg(feature = "msgpack")]
+pub use self::msgpack::{MsgPack, MsgPackConfig};
+pub use self::negotiate::{Accept, Negotiated};
 pub use self::path::{Path, PathConfig};
 pub use self::payload::{Payload, PayloadConfig};
-pub use self::query::{Query, QueryConfig};
+#[cfg(feature = "protobuf")]
+pub use self::protobuf::{ProtoBuf, ProtoBufConfig};
+pub use self::query::{Query, QueryConfig, QueryParams};
+pub use self::query_parser::QueryParser;
+pub use self::range_body::RangeBody;
+pub use self::read_stream::ReadStream;
 pub use self::readlines::Readlines;
+pub use self::redirect::Redirect;
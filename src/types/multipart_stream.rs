@@ -0,0 +1,614 @@
+//! Streaming `multipart/*` response bodies.
+//!
+//! See [`MultipartWriter`] for usage.
+
+use std::{
+    error::Error as StdError,
+    fmt,
+    ops::Range,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use actix_http::body::{Body, BodySize, MessageBody};
+use bytes::{BufMut as _, Bytes, BytesMut};
+use derive_more::{Display, Error};
+use futures_core::{ready, Stream};
+use pin_project::pin_project;
+use rand::{distributions::Alphanumeric, Rng as _};
+
+use crate::{
+    dev::HttpResponseBuilder,
+    error::Error,
+    http::{
+        header::{
+            HeaderMap, HeaderValue, IntoHeaderPair, CONTENT_ENCODING, CONTENT_RANGE,
+            CONTENT_TYPE,
+        },
+        Error as HttpError,
+    },
+    HttpRequest, HttpResponse, Responder,
+};
+
+/// Maximum length of a boundary, see [RFC 2046 §5.1.1](https://tools.ietf.org/html/rfc2046#section-5.1.1).
+const MAX_BOUNDARY_LEN: usize = 70;
+
+/// Length of generated boundaries.
+const BOUNDARY_LEN: usize = 32;
+
+/// A single part of a multipart body, made of its headers and a body.
+///
+/// ```
+/// use actix_web::{http::header::CONTENT_TYPE, web::multipart_stream::Part};
+///
+/// let part = Part::new("frame").insert_header((CONTENT_TYPE, "image/jpeg"));
+/// ```
+pub struct Part {
+    headers: HeaderMap,
+    body: PartBody,
+    err: Option<HttpError>,
+}
+
+enum PartBody {
+    Bytes(Bytes),
+    Stream(Pin<Box<dyn Stream<Item = Result<Bytes, Error>>>>),
+}
+
+impl Part {
+    /// Create new part with the given body.
+    pub fn new(body: impl Into<Bytes>) -> Self {
+        Part {
+            headers: HeaderMap::new(),
+            body: PartBody::Bytes(body.into()),
+            err: None,
+        }
+    }
+
+    /// Create new part with a body that is streamed as it is produced.
+    pub fn streaming<S, E>(stream: S) -> Self
+    where
+        S: Stream<Item = Result<Bytes, E>> + 'static,
+        E: Into<Box<dyn StdError>> + 'static,
+    {
+        Part {
+            headers: HeaderMap::new(),
+            body: PartBody::Stream(Box::pin(PartStream { stream })),
+            err: None,
+        }
+    }
+
+    /// Insert a header, replacing any that were set with an equivalent field name.
+    ///
+    /// An invalid header fails the multipart body when the part is reached.
+    pub fn insert_header<H: IntoHeaderPair>(mut self, header: H) -> Self {
+        match header.try_into_header_pair() {
+            Ok((name, value)) => {
+                self.headers.insert(name, value);
+            }
+            Err(err) => {
+                if self.err.is_none() {
+                    self.err = Some(err.into());
+                }
+            }
+        }
+
+        self
+    }
+
+    /// Set the `Content-Range` header of a part of a `multipart/byteranges` body.
+    ///
+    /// `range` is the range of bytes contained in the part and `len` the length of the whole
+    /// representation.
+    pub fn content_range(self, range: Range<u64>, len: u64) -> Self {
+        let value = format!(
+            "bytes {}-{}/{}",
+            range.start,
+            range.end.saturating_sub(1),
+            len
+        );
+        self.insert_header((CONTENT_RANGE, value))
+    }
+
+    /// Returns the headers of the part.
+    pub fn headers(&self) -> &HeaderMap {
+        &self.headers
+    }
+}
+
+impl fmt::Debug for Part {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let body = match self.body {
+            PartBody::Bytes(ref bytes) => format!("{:?}", bytes),
+            PartBody::Stream(_) => "Stream(..)".to_owned(),
+        };
+
+        f.debug_struct("Part")
+            .field("headers", &self.headers)
+            .field("body", &body)
+            .finish()
+    }
+}
+
+#[pin_project]
+struct PartStream<S> {
+    #[pin]
+    stream: S,
+}
+
+impl<S, E> Stream for PartStream<S>
+where
+    S: Stream<Item = Result<Bytes, E>>,
+    E: Into<Box<dyn StdError>>,
+{
+    type Item = Result<Bytes, Error>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.project()
+            .stream
+            .poll_next(cx)
+            .map(|opt| opt.map(|res| res.map_err(|err| Error::from(err.into()))))
+    }
+}
+
+/// Streaming `multipart/*` body.
+///
+/// Wraps a stream of [`Part`]s and writes each part, preceded by its headers, between boundary
+/// delimiters as it is produced. The end of a part is written as soon as its body is finished,
+/// so that `multipart/x-mixed-replace` clients can display a part without waiting for the next.
+///
+/// A random boundary is generated for each writer; parts must not contain it. It can be
+/// replaced using [`boundary`](Self::boundary).
+///
+/// Used as a [`Responder`], the writer responds with `200 OK`. Use
+/// [`respond_with`](Self::respond_with) to send it with another status, e.g. for
+/// `multipart/byteranges` responses to requests for multiple ranges.
+///
+/// ```
+/// use actix_web::{
+///     get,
+///     http::header::CONTENT_TYPE,
+///     web::{multipart_stream::{MultipartWriter, Part}, Bytes},
+///     Responder,
+/// };
+/// use futures_util::stream;
+///
+/// #[get("/camera")]
+/// async fn camera() -> impl Responder {
+///     let frames = stream::iter(vec![Bytes::from_static(b"frame 1"), Bytes::from_static(b"frame 2")]);
+///     let parts = stream::StreamExt::map(frames, |frame| {
+///         Part::new(frame).insert_header((CONTENT_TYPE, "image/jpeg"))
+///     });
+///
+///     MultipartWriter::mixed_replace(parts)
+/// }
+/// ```
+#[pin_project]
+pub struct MultipartWriter<S> {
+    #[pin]
+    parts: S,
+    subtype: &'static str,
+    boundary: String,
+    body: Option<Pin<Box<dyn Stream<Item = Result<Bytes, Error>>>>>,
+    started: bool,
+    finished: bool,
+}
+
+impl<S> MultipartWriter<S>
+where
+    S: Stream<Item = Part>,
+{
+    /// Create new `multipart/mixed` body from a stream of parts.
+    pub fn new(parts: S) -> Self {
+        Self::with_subtype(parts, "mixed")
+    }
+
+    /// Create new `multipart/x-mixed-replace` body, in which each part replaces the previous
+    /// one, e.g. for streaming frames of a video.
+    pub fn mixed_replace(parts: S) -> Self {
+        Self::with_subtype(parts, "x-mixed-replace")
+    }
+
+    /// Create new `multipart/byteranges` body, for answering requests for multiple ranges.
+    ///
+    /// Each part should describe its range using [`Part::content_range`].
+    pub fn byteranges(parts: S) -> Self {
+        Self::with_subtype(parts, "byteranges")
+    }
+
+    fn with_subtype(parts: S, subtype: &'static str) -> Self {
+        MultipartWriter {
+            parts,
+            subtype,
+            boundary: random_boundary(),
+            body: None,
+            started: false,
+            finished: false,
+        }
+    }
+
+    /// Replace the generated boundary.
+    ///
+    /// # Panics
+    /// Panics if `boundary` is empty, longer than 70 characters or contains characters not
+    /// allowed in a boundary by RFC 2046.
+    pub fn boundary(mut self, boundary: impl Into<String>) -> Self {
+        let boundary = boundary.into();
+
+        assert!(
+            is_valid_boundary(&boundary),
+            "invalid multipart boundary: {:?}",
+            boundary
+        );
+
+        self.boundary = boundary;
+        self
+    }
+
+    /// Replace the generated boundary, returning an error if it is not a valid boundary.
+    ///
+    /// See [`boundary`](Self::boundary) for the requirements.
+    pub fn try_boundary(
+        mut self,
+        boundary: impl Into<String>,
+    ) -> Result<Self, InvalidBoundary> {
+        let boundary = boundary.into();
+
+        if !is_valid_boundary(&boundary) {
+            return Err(InvalidBoundary);
+        }
+
+        self.boundary = boundary;
+        Ok(self)
+    }
+
+    /// Returns the `Content-Type` of the body, including its boundary.
+    pub fn content_type(&self) -> String {
+        // boundaries may contain characters that are not allowed in unquoted parameter values
+        if self.boundary.bytes().any(|b| b"(),/:=? ".contains(&b)) {
+            format!("multipart/{}; boundary=\"{}\"", self.subtype, self.boundary)
+        } else {
+            format!("multipart/{}; boundary={}", self.subtype, self.boundary)
+        }
+    }
+
+    /// Set the body of the response built by `res`, along with its `Content-Type`.
+    ///
+    /// Compression is disabled for the response so that parts are flushed to the client as they
+    /// are produced.
+    pub fn respond_with(self, res: &mut HttpResponseBuilder) -> HttpResponse
+    where
+        S: 'static,
+    {
+        // content type is always a valid header value
+        let content_type = HeaderValue::from_str(&self.content_type()).unwrap();
+
+        res.insert_header((CONTENT_TYPE, content_type))
+            .insert_header((CONTENT_ENCODING, "identity"))
+            .body(Body::from_message(self.boxed()))
+    }
+}
+
+impl<S> MessageBody for MultipartWriter<S>
+where
+    S: Stream<Item = Part>,
+{
+    fn size(&self) -> BodySize {
+        BodySize::Stream
+    }
+
+    fn poll_next(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Bytes, Error>>> {
+        let mut this = self.project();
+
+        if *this.finished {
+            return Poll::Ready(None);
+        }
+
+        // write the body of the current part, then the delimiter ending it
+        if let Some(body) = this.body {
+            loop {
+                match ready!(body.as_mut().poll_next(cx)) {
+                    Some(Ok(chunk)) if chunk.is_empty() => continue,
+                    Some(Ok(chunk)) => return Poll::Ready(Some(Ok(chunk))),
+                    Some(Err(err)) => {
+                        *this.finished = true;
+                        return Poll::Ready(Some(Err(err)));
+                    }
+                    None => {
+                        *this.body = None;
+                        let mut buf = BytesMut::new();
+                        write_delimiter(&mut buf, this.boundary);
+                        return Poll::Ready(Some(Ok(buf.freeze())));
+                    }
+                }
+            }
+        }
+
+        match ready!(this.parts.as_mut().poll_next(cx)) {
+            Some(part) => {
+                if let Some(err) = part.err {
+                    *this.finished = true;
+                    return Poll::Ready(Some(Err(err.into())));
+                }
+
+                let mut buf = BytesMut::new();
+
+                if !*this.started {
+                    *this.started = true;
+                    buf.put_slice(b"--");
+                    buf.put_slice(this.boundary.as_bytes());
+                }
+
+                buf.put_slice(b"\r\n");
+                for (name, value) in part.headers.iter() {
+                    buf.put_slice(name.as_str().as_bytes());
+                    buf.put_slice(b": ");
+                    buf.put_slice(value.as_bytes());
+                    buf.put_slice(b"\r\n");
+                }
+                buf.put_slice(b"\r\n");
+
+                match part.body {
+                    PartBody::Bytes(bytes) => {
+                        buf.put_slice(&bytes);
+                        write_delimiter(&mut buf, this.boundary);
+                    }
+                    PartBody::Stream(stream) => *this.body = Some(stream),
+                }
+
+                Poll::Ready(Some(Ok(buf.freeze())))
+            }
+
+            None => {
+                *this.finished = true;
+
+                // the delimiter of the last part is already written
+                if *this.started {
+                    Poll::Ready(Some(Ok(Bytes::from_static(b"--\r\n"))))
+                } else {
+                    let close = format!("--{}--\r\n", this.boundary);
+                    Poll::Ready(Some(Ok(Bytes::from(close))))
+                }
+            }
+        }
+    }
+}
+
+impl<S> Responder for MultipartWriter<S>
+where
+    S: Stream<Item = Part> + 'static,
+{
+    fn respond_to(self, _: &HttpRequest) -> HttpResponse {
+        self.respond_with(&mut HttpResponse::Ok())
+    }
+}
+
+/// Writes the delimiter ending a part, which is followed by either the next part's headers or
+/// the closing `--`.
+/// Error returned by [`MultipartWriter::try_boundary`] for an invalid boundary.
+#[derive(Debug, Display, Error)]
+#[display(fmt = "invalid multipart boundary")]
+pub struct InvalidBoundary;
+
+fn write_delimiter(buf: &mut BytesMut, boundary: &str) {
+    buf.put_slice(b"\r\n--");
+    buf.put_slice(boundary.as_bytes());
+}
+
+fn random_boundary() -> String {
+    rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(BOUNDARY_LEN)
+        .map(char::from)
+        .collect()
+}
+
+fn is_valid_boundary(boundary: &str) -> bool {
+    !boundary.is_empty()
+        && boundary.len() <= MAX_BOUNDARY_LEN
+        && !boundary.ends_with(' ')
+        && boundary
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b"'()+_,-./:=? ".contains(&b))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::Infallible;
+
+    use actix_service::Service;
+    use futures_util::{future::poll_fn, stream};
+
+    use super::*;
+    use crate::{
+        http::StatusCode,
+        test::{self, TestRequest},
+        web, App,
+    };
+
+    async fn load_body<B: MessageBody + Unpin>(mut body: B) -> Result<Bytes, Error> {
+        let mut buf = BytesMut::new();
+        while let Some(chunk) = poll_fn(|cx| Pin::new(&mut body).poll_next(cx)).await {
+            buf.extend_from_slice(&chunk?);
+        }
+        Ok(buf.freeze())
+    }
+
+    /// Splits a multipart body into the headers and body of each part.
+    fn parse(body: &[u8], boundary: &str) -> Vec<(Vec<String>, String)> {
+        let body = std::str::from_utf8(body).unwrap();
+
+        let start = format!("--{}\r\n", boundary);
+        let delimiter = format!("\r\n--{}\r\n", boundary);
+        let close = format!("\r\n--{}--\r\n", boundary);
+
+        assert!(body.starts_with(&start), "{:?}", body);
+        assert!(body.ends_with(&close), "{:?}", body);
+
+        body[start.len()..body.len() - close.len()]
+            .split(&delimiter)
+            .map(|part| {
+                // a part without headers starts with the empty line ending them
+                let (head, body) = if part.starts_with("\r\n") {
+                    ("", &part[2..])
+                } else {
+                    let idx = part.find("\r\n\r\n").unwrap();
+                    (&part[..idx], &part[idx + 4..])
+                };
+
+                let headers = head.split_terminator("\r\n").map(str::to_owned).collect();
+
+                (headers, body.to_owned())
+            })
+            .collect()
+    }
+
+    #[actix_rt::test]
+    async fn test_mixed_replace() {
+        let srv = test::init_service(App::new().route(
+            "/",
+            web::get().to(|| async {
+                let frames = vec!["frame 1", "frame 2"]
+                    .into_iter()
+                    .map(|frame| Part::new(frame).insert_header((CONTENT_TYPE, "image/jpeg")));
+
+                MultipartWriter::mixed_replace(stream::iter(frames))
+            }),
+        ))
+        .await;
+
+        let req = TestRequest::default().to_request();
+        let res = srv.call(req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(res.headers().get(CONTENT_ENCODING).unwrap(), "identity");
+
+        let content_type = res.headers().get(CONTENT_TYPE).unwrap().to_str().unwrap();
+        let boundary = content_type
+            .strip_prefix("multipart/x-mixed-replace; boundary=")
+            .unwrap()
+            .to_owned();
+        assert_eq!(boundary.len(), 32);
+
+        let body = test::read_body(res).await;
+        assert_eq!(
+            parse(&body, &boundary),
+            vec![
+                (
+                    vec!["content-type: image/jpeg".to_owned()],
+                    "frame 1".to_owned()
+                ),
+                (
+                    vec!["content-type: image/jpeg".to_owned()],
+                    "frame 2".to_owned()
+                ),
+            ]
+        );
+    }
+
+    #[actix_rt::test]
+    async fn test_byteranges_wire_format() {
+        let parts = stream::iter(vec![
+            Part::new("Hello").content_range(0..5, 13),
+            Part::streaming(stream::iter(vec![
+                Ok::<_, Infallible>(Bytes::from_static(b"Wor")),
+                Ok(Bytes::new()),
+                Ok(Bytes::from_static(b"ld")),
+            ]))
+            .content_range(7..12, 13),
+        ]);
+
+        let writer = MultipartWriter::byteranges(parts).boundary("sep");
+        assert_eq!(writer.content_type(), "multipart/byteranges; boundary=sep");
+
+        let mut res = writer.respond_with(&mut HttpResponse::PartialContent());
+        assert_eq!(res.status(), StatusCode::PARTIAL_CONTENT);
+
+        let body = load_body(res.take_body()).await.unwrap();
+        assert_eq!(
+            body,
+            Bytes::from_static(
+                b"--sep\r\n\
+                content-range: bytes 0-4/13\r\n\r\n\
+                Hello\r\n\
+                --sep\r\n\
+                content-range: bytes 7-11/13\r\n\r\n\
+                World\r\n\
+                --sep--\r\n"
+            )
+        );
+        assert_eq!(
+            parse(&body, "sep"),
+            vec![
+                (
+                    vec!["content-range: bytes 0-4/13".to_owned()],
+                    "Hello".to_owned()
+                ),
+                (
+                    vec!["content-range: bytes 7-11/13".to_owned()],
+                    "World".to_owned()
+                ),
+            ]
+        );
+    }
+
+    #[actix_rt::test]
+    async fn test_no_parts() {
+        let writer = MultipartWriter::new(stream::empty()).boundary("sep");
+        assert_eq!(writer.size(), BodySize::Stream);
+
+        let body = load_body(writer).await.unwrap();
+        assert_eq!(body, Bytes::from_static(b"--sep--\r\n"));
+    }
+
+    #[actix_rt::test]
+    async fn test_part_errors() {
+        let parts = stream::iter(vec![
+            Part::new("ok"),
+            Part::streaming(stream::iter(vec![Err::<Bytes, _>(std::io::Error::new(
+                std::io::ErrorKind::Other,
+                "camera gone",
+            ))])),
+        ]);
+        let err = load_body(MultipartWriter::new(parts)).await.unwrap_err();
+        assert_eq!(err.to_string(), "camera gone");
+
+        let parts = stream::iter(vec![Part::new("bad").insert_header(("x-bad", "\n"))]);
+        assert!(load_body(MultipartWriter::new(parts)).await.is_err());
+    }
+
+    #[test]
+    fn test_boundary() {
+        let a = MultipartWriter::new(stream::empty::<Part>());
+        let b = MultipartWriter::new(stream::empty::<Part>());
+        assert_ne!(a.boundary, b.boundary);
+        assert_eq!(a.boundary.len(), BOUNDARY_LEN);
+        assert!(is_valid_boundary(&a.boundary));
+
+        assert!(is_valid_boundary("simple boundary"));
+        let writer = MultipartWriter::new(stream::empty::<Part>()).boundary("simple boundary");
+        assert_eq!(
+            writer.content_type(),
+            "multipart/mixed; boundary=\"simple boundary\""
+        );
+        assert!(!is_valid_boundary(""));
+        assert!(!is_valid_boundary("trailing "));
+        assert!(!is_valid_boundary("semi;colon"));
+        assert!(!is_valid_boundary(&"a".repeat(71)));
+
+        let writer = MultipartWriter::new(stream::empty::<Part>())
+            .try_boundary("sep")
+            .unwrap();
+        assert_eq!(writer.content_type(), "multipart/mixed; boundary=sep");
+        assert!(MultipartWriter::new(stream::empty::<Part>())
+            .try_boundary("a\r\nb")
+            .is_err());
+    }
+
+    #[test]
+    #[should_panic(expected = "invalid multipart boundary")]
+    fn test_invalid_boundary() {
+        let _ = MultipartWriter::new(stream::empty::<Part>()).boundary("a\r\nb");
+    }
+}
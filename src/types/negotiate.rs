@@ -0,0 +1,287 @@
+//! For content negotiation documentation, see [`Accept`] and [`Negotiated`].
+
+use std::{fmt, ops};
+
+use actix_http::body::Body;
+use futures_util::future::{ok, Ready};
+use mime::Mime;
+use serde::Serialize;
+
+use crate::{
+    dev::Payload,
+    http::header::{self, ACCEPT, VARY},
+    Error, FromRequest, HttpMessage, HttpRequest, HttpResponse, Responder,
+};
+
+/// Extract the media ranges accepted by the client from the `Accept` header.
+///
+/// A missing or unparseable `Accept` header is treated as `*/*`, so this extractor never fails.
+///
+/// # Examples
+/// ```
+/// use actix_web::{get, web, HttpResponse};
+///
+/// #[get("/")]
+/// async fn index(accept: web::Accept) -> HttpResponse {
+///     match accept.negotiate(&[mime::TEXT_HTML, mime::APPLICATION_JSON]) {
+///         Some(mime) if mime == mime::TEXT_HTML => HttpResponse::Ok().body("<p>hello</p>"),
+///         Some(_) => HttpResponse::Ok().json("hello"),
+///         None => HttpResponse::NotAcceptable().finish(),
+///     }
+/// }
+/// ```
+#[derive(Clone, PartialEq)]
+pub struct Accept(pub header::Accept);
+
+impl Accept {
+    /// Unwrap into inner `Accept` header.
+    pub fn into_inner(self) -> header::Accept {
+        self.0
+    }
+
+    /// Returns the accepted media ranges, sorted from highest to lowest preference.
+    ///
+    /// See [`header::Accept::mime_precedence`].
+    pub fn media_ranges(&self) -> Vec<Mime> {
+        self.0.mime_precedence()
+    }
+
+    /// Selects the most acceptable of the `available` mime types.
+    ///
+    /// See [`header::Accept::negotiate`].
+    pub fn negotiate(&self, available: &[Mime]) -> Option<Mime> {
+        self.0.negotiate(available)
+    }
+
+    fn from_req(req: &HttpRequest) -> Self {
+        if !req.headers().contains_key(ACCEPT) {
+            return Accept(header::Accept::star());
+        }
+
+        Accept(
+            req.get_header::<header::Accept>()
+                .unwrap_or_else(header::Accept::star),
+        )
+    }
+}
+
+impl ops::Deref for Accept {
+    type Target = header::Accept;
+
+    fn deref(&self) -> &header::Accept {
+        &self.0
+    }
+}
+
+impl fmt::Debug for Accept {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl FromRequest for Accept {
+    type Error = Error;
+    type Future = Ready<Result<Self, Error>>;
+    type Config = ();
+
+    #[inline]
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        ok(Accept::from_req(req))
+    }
+}
+
+type Encoder<T> = Box<dyn FnOnce(&T) -> Result<Body, Error>>;
+
+/// Responder that renders a value in the representation preferred by the client.
+///
+/// Encoders are registered for a mime type and the one matching the request's `Accept` header
+/// best is used to render the response body. When several encoders are equally acceptable, the
+/// first registered one is used. If no encoder is acceptable, `406 Not Acceptable` is returned.
+/// All responses include a `Vary: Accept` header.
+///
+/// # Examples
+/// ```
+/// use actix_web::{get, web, Responder};
+/// use serde::Serialize;
+///
+/// #[derive(Serialize)]
+/// struct User {
+///     name: String,
+/// }
+///
+/// #[get("/user")]
+/// async fn user() -> impl Responder {
+///     let user = User { name: "Ferris".to_owned() };
+///
+///     web::Negotiated::new(user)
+///         .json()
+///         .html(|user| format!("<h1>{}</h1>", user.name))
+/// }
+/// ```
+pub struct Negotiated<T> {
+    value: T,
+    encoders: Vec<(Mime, Encoder<T>)>,
+}
+
+impl<T> Negotiated<T> {
+    /// Create new negotiated responder without any encoders.
+    pub fn new(value: T) -> Self {
+        Negotiated {
+            value,
+            encoders: Vec::new(),
+        }
+    }
+
+    /// Register an encoder for the given mime type.
+    ///
+    /// The mime type is also used as the response's `Content-Type`.
+    pub fn encoder<F, B>(mut self, mime: Mime, encoder: F) -> Self
+    where
+        F: FnOnce(&T) -> Result<B, Error> + 'static,
+        B: Into<Body>,
+    {
+        self.encoders
+            .push((mime, Box::new(move |value| encoder(value).map(Into::into))));
+        self
+    }
+
+    /// Register an HTML encoder, served as `text/html; charset=utf-8`.
+    pub fn html<F, S>(self, encoder: F) -> Self
+    where
+        F: FnOnce(&T) -> S + 'static,
+        S: Into<String>,
+    {
+        self.encoder(mime::TEXT_HTML_UTF_8, move |value| {
+            Ok::<_, Error>(encoder(value).into())
+        })
+    }
+}
+
+impl<T: Serialize> Negotiated<T> {
+    /// Register a JSON encoder using `serde_json`, served as `application/json`.
+    pub fn json(self) -> Self {
+        self.encoder(mime::APPLICATION_JSON, |value| {
+            serde_json::to_string(value).map_err(Error::from)
+        })
+    }
+}
+
+impl<T> Responder for Negotiated<T> {
+    fn respond_to(self, req: &HttpRequest) -> HttpResponse {
+        let Negotiated { value, encoders } = self;
+
+        let available = encoders
+            .iter()
+            .map(|(mime, _)| mime.clone())
+            .collect::<Vec<_>>();
+
+        let encoder = Accept::from_req(req)
+            .negotiate(&available)
+            .and_then(|chosen| encoders.into_iter().find(|(mime, _)| *mime == chosen));
+
+        let (mime, encoder) = match encoder {
+            Some(encoder) => encoder,
+            None => {
+                return HttpResponse::NotAcceptable()
+                    .insert_header((VARY, "accept"))
+                    .finish()
+            }
+        };
+
+        match encoder(&value) {
+            Ok(body) => HttpResponse::Ok()
+                .content_type(mime)
+                .insert_header((VARY, "accept"))
+                .body(body),
+            Err(err) => HttpResponse::from_error(err),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Serialize;
+
+    use super::*;
+    use crate::{
+        http::{header::CONTENT_TYPE, StatusCode},
+        test::{self, TestRequest},
+    };
+
+    #[derive(Serialize)]
+    struct Greeting {
+        msg: &'static str,
+    }
+
+    fn greeting() -> Negotiated<Greeting> {
+        Negotiated::new(Greeting { msg: "hello" })
+            .json()
+            .html(|greeting| format!("<p>{}</p>", greeting.msg))
+    }
+
+    #[actix_rt::test]
+    async fn test_accept_extractor() {
+        let (req, mut pl) = TestRequest::default().to_http_parts();
+        let accept = Accept::from_request(&req, &mut pl).await.unwrap();
+        assert_eq!(accept.media_ranges(), vec![mime::STAR_STAR]);
+
+        let (req, mut pl) = TestRequest::default()
+            .insert_header((ACCEPT, "text/*;q=0.5, application/json"))
+            .to_http_parts();
+        let accept = Accept::from_request(&req, &mut pl).await.unwrap();
+        assert_eq!(
+            accept.media_ranges(),
+            vec![mime::APPLICATION_JSON, mime::TEXT_STAR]
+        );
+        assert_eq!(accept.negotiate(&[mime::TEXT_HTML]), Some(mime::TEXT_HTML));
+    }
+
+    #[actix_rt::test]
+    async fn test_negotiated_star() {
+        let req = TestRequest::default()
+            .insert_header((ACCEPT, "*/*"))
+            .to_http_request();
+        let res = greeting().respond_to(&req);
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(res.headers().get(CONTENT_TYPE).unwrap(), "application/json");
+        assert_eq!(res.headers().get(VARY).unwrap(), "accept");
+
+        let res = TestRequest::default().to_srv_response(greeting().respond_to(&req));
+        assert_eq!(test::read_body(res).await, r#"{"msg":"hello"}"#);
+    }
+
+    #[actix_rt::test]
+    async fn test_negotiated_quality() {
+        let req = TestRequest::default()
+            .insert_header((ACCEPT, "text/*;q=0.5, application/json"))
+            .to_http_request();
+        let res = greeting().respond_to(&req);
+        assert_eq!(res.headers().get(CONTENT_TYPE).unwrap(), "application/json");
+
+        let req = TestRequest::default()
+            .insert_header((ACCEPT, "text/*, application/json;q=0.5"))
+            .to_http_request();
+        let res = greeting().respond_to(&req);
+        assert_eq!(
+            res.headers().get(CONTENT_TYPE).unwrap(),
+            "text/html; charset=utf-8"
+        );
+
+        let res = TestRequest::default().to_srv_response(res);
+        assert_eq!(test::read_body(res).await, "<p>hello</p>");
+    }
+
+    #[actix_rt::test]
+    async fn test_negotiated_not_acceptable() {
+        let req = TestRequest::default()
+            .insert_header((ACCEPT, "image/png"))
+            .to_http_request();
+        let res = greeting().respond_to(&req);
+        assert_eq!(res.status(), StatusCode::NOT_ACCEPTABLE);
+        assert_eq!(res.headers().get(VARY).unwrap(), "accept");
+
+        let req = TestRequest::default().to_http_request();
+        let res = Negotiated::new(()).respond_to(&req);
+        assert_eq!(res.status(), StatusCode::NOT_ACCEPTABLE);
+    }
+}
@@ -1,13 +1,13 @@
 //! For path segment extractor documentation, see [`Path`].
 
-use std::{fmt, ops, sync::Arc};
+use std::{any::type_name, cell::Cell, fmt, ops, sync::Arc};
 
-use actix_http::error::{Error, ErrorNotFound};
+use actix_http::error::{Error, InternalError};
 use actix_router::PathDeserializer;
 use futures_util::future::{ready, Ready};
 use serde::de;
 
-use crate::{dev::Payload, error::PathError, FromRequest, HttpRequest};
+use crate::{dev::Payload, error::PathError, http::StatusCode, FromRequest, HttpRequest};
 
 /// Extract typed data from request path segments.
 ///
@@ -104,33 +104,60 @@ where
 
     #[inline]
     fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
-        let error_handler = req
+        let (error_handler, error_status) = req
             .app_data::<Self::Config>()
-            .map(|c| c.ehandler.clone())
-            .unwrap_or(None);
-
-        ready(
-            de::Deserialize::deserialize(PathDeserializer::new(req.match_info()))
-                .map(Path)
-                .map_err(move |e| {
-                    log::debug!(
-                        "Failed during Path extractor deserialization. \
-                         Request path: {:?}",
-                        req.path()
-                    );
-                    if let Some(error_handler) = error_handler {
-                        let e = PathError::Deserialize(e);
-                        (error_handler)(e, req)
-                    } else {
-                        ErrorNotFound(e)
-                    }
-                }),
-        )
+            .map(|c| (c.ehandler.clone(), c.status))
+            .unwrap_or((None, None));
+
+        let path = req.match_info();
+        let tracker = SegmentTracker::default();
+
+        let de = TrackSegments {
+            de: PathDeserializer::new(path),
+            tracker: &tracker,
+            // only a path with a single segment can be deserialized directly into `T`
+            single: if path.len() == 1 {
+                Some(type_name::<T>())
+            } else {
+                None
+            },
+        };
+
+        ready(de::Deserialize::deserialize(de).map(Path).map_err(|err| {
+            log::debug!(
+                "Failed during Path extractor deserialization. \
+                 Request path: {:?}",
+                req.path()
+            );
+
+            let err = match tracker
+                .current
+                .get()
+                .and_then(|(idx, ty)| path.iter().nth(idx).map(|(name, _)| (name, ty)))
+            {
+                Some((name, ty)) => PathError::Segment {
+                    segment: name.to_owned(),
+                    expected: short_type_name(ty),
+                    source: err,
+                },
+                None => PathError::Deserialize(err),
+            };
+
+            if let Some(error_handler) = error_handler {
+                (error_handler)(err, req)
+            } else {
+                InternalError::new(err, error_status.unwrap_or(StatusCode::NOT_FOUND)).into()
+            }
+        }))
     }
 }
 
 /// Path extractor configuration
 ///
+/// By default, path segments that can not be deserialized are rejected with `404 Not Found`.
+/// Use [`error_status`](Self::error_status) to respond with a different status, such as
+/// `400 Bad Request`.
+///
 /// ```
 /// use actix_web::web::PathConfig;
 /// use actix_web::{error, web, App, FromRequest, HttpResponse};
@@ -166,6 +193,7 @@ where
 #[derive(Clone)]
 pub struct PathConfig {
     ehandler: Option<Arc<dyn Fn(PathError, &HttpRequest) -> Error + Send + Sync>>,
+    status: Option<StatusCode>,
 }
 
 impl PathConfig {
@@ -177,12 +205,263 @@ impl PathConfig {
         self.ehandler = Some(Arc::new(f));
         self
     }
+
+    /// Set the status code of responses to requests whose path can not be deserialized.
+    ///
+    /// Has no effect when a custom [error handler](Self::error_handler) is set.
+    ///
+    /// ```
+    /// use actix_web::{http::StatusCode, web, App};
+    ///
+    /// let app = App::new().service(
+    ///     web::scope("/users")
+    ///         .app_data(web::PathConfig::default().error_status(StatusCode::BAD_REQUEST))
+    ///         .route("/{id}", web::get().to(|id: web::Path<u64>| async move { id.to_string() })),
+    /// );
+    /// ```
+    pub fn error_status(mut self, status: StatusCode) -> Self {
+        self.status = Some(status);
+        self
+    }
 }
 
 impl Default for PathConfig {
     fn default() -> Self {
-        PathConfig { ehandler: None }
+        PathConfig {
+            ehandler: None,
+            status: None,
+        }
+    }
+}
+
+/// Position of the segment being deserialized and the name of the type it is deserialized into.
+#[derive(Default)]
+struct SegmentTracker {
+    current: Cell<Option<(usize, &'static str)>>,
+    next: Cell<usize>,
+}
+
+impl SegmentTracker {
+    fn enter<T: ?Sized>(&self) {
+        self.current.set(Some((self.next.get(), type_name::<T>())));
+    }
+
+    fn leave(&self) {
+        self.current.set(None);
+        self.next.set(self.next.get() + 1);
+    }
+}
+
+/// Deserializer that keeps track of the path segment being deserialized, so that errors can name
+/// the segment that failed.
+///
+/// Multiple segments are visited in order as a sequence or a map, so the position of a value
+/// matches the position of its segment in the path. A value deserialized directly from the path
+/// is labeled with the extracted type, as long as the path has a single segment.
+struct TrackSegments<'a, D> {
+    de: D,
+    tracker: &'a SegmentTracker,
+    single: Option<&'static str>,
+}
+
+impl<'a, D> TrackSegments<'a, D> {
+    fn enter_single(&self) {
+        if let Some(ty) = self.single {
+            self.tracker.current.set(Some((0, ty)));
+        }
+    }
+}
+
+macro_rules! forward_single {
+    ($($method:ident)*) => {$(
+        fn $method<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+            self.enter_single();
+            self.de.$method(visitor)
+        }
+    )*};
+}
+
+macro_rules! forward_multiple {
+    ($($method:ident)*) => {$(
+        fn $method<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+            self.de.$method(TrackVisitor::new(visitor, self.tracker))
+        }
+    )*};
+}
+
+impl<'de, 'a, D: de::Deserializer<'de>> de::Deserializer<'de> for TrackSegments<'a, D> {
+    type Error = D::Error;
+
+    forward_single! {
+        deserialize_any deserialize_bool deserialize_i8 deserialize_i16 deserialize_i32
+        deserialize_i64 deserialize_u8 deserialize_u16 deserialize_u32 deserialize_u64
+        deserialize_f32 deserialize_f64 deserialize_char deserialize_str deserialize_string
+        deserialize_bytes deserialize_byte_buf deserialize_option deserialize_unit
+        deserialize_identifier deserialize_ignored_any
+    }
+
+    forward_multiple! { deserialize_seq deserialize_map }
+
+    fn deserialize_unit_struct<V: de::Visitor<'de>>(
+        self,
+        name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        self.enter_single();
+        self.de.deserialize_unit_struct(name, visitor)
+    }
+
+    fn deserialize_newtype_struct<V: de::Visitor<'de>>(
+        self,
+        name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        self.enter_single();
+        self.de.deserialize_newtype_struct(name, visitor)
+    }
+
+    fn deserialize_enum<V: de::Visitor<'de>>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        self.enter_single();
+        self.de.deserialize_enum(name, variants, visitor)
+    }
+
+    fn deserialize_tuple<V: de::Visitor<'de>>(
+        self,
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        self.de
+            .deserialize_tuple(len, TrackVisitor::new(visitor, self.tracker))
+    }
+
+    fn deserialize_tuple_struct<V: de::Visitor<'de>>(
+        self,
+        name: &'static str,
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        self.de
+            .deserialize_tuple_struct(name, len, TrackVisitor::new(visitor, self.tracker))
+    }
+
+    fn deserialize_struct<V: de::Visitor<'de>>(
+        self,
+        name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        self.de
+            .deserialize_struct(name, fields, TrackVisitor::new(visitor, self.tracker))
+    }
+}
+
+struct TrackVisitor<'a, V> {
+    visitor: V,
+    tracker: &'a SegmentTracker,
+}
+
+impl<'a, V> TrackVisitor<'a, V> {
+    fn new(visitor: V, tracker: &'a SegmentTracker) -> Self {
+        TrackVisitor { visitor, tracker }
+    }
+}
+
+impl<'de, 'a, V: de::Visitor<'de>> de::Visitor<'de> for TrackVisitor<'a, V> {
+    type Value = V::Value;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.visitor.expecting(f)
+    }
+
+    fn visit_seq<A: de::SeqAccess<'de>>(self, seq: A) -> Result<Self::Value, A::Error> {
+        self.visitor.visit_seq(TrackAccess {
+            access: seq,
+            tracker: self.tracker,
+        })
+    }
+
+    fn visit_map<A: de::MapAccess<'de>>(self, map: A) -> Result<Self::Value, A::Error> {
+        self.visitor.visit_map(TrackAccess {
+            access: map,
+            tracker: self.tracker,
+        })
+    }
+}
+
+struct TrackAccess<'a, A> {
+    access: A,
+    tracker: &'a SegmentTracker,
+}
+
+impl<'de, 'a, A: de::SeqAccess<'de>> de::SeqAccess<'de> for TrackAccess<'a, A> {
+    type Error = A::Error;
+
+    fn next_element_seed<S: de::DeserializeSeed<'de>>(
+        &mut self,
+        seed: S,
+    ) -> Result<Option<S::Value>, Self::Error> {
+        self.tracker.enter::<S::Value>();
+        let item = self.access.next_element_seed(seed)?;
+        self.tracker.leave();
+        Ok(item)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        self.access.size_hint()
+    }
+}
+
+impl<'de, 'a, A: de::MapAccess<'de>> de::MapAccess<'de> for TrackAccess<'a, A> {
+    type Error = A::Error;
+
+    fn next_key_seed<K: de::DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, Self::Error> {
+        self.access.next_key_seed(seed)
+    }
+
+    fn next_value_seed<S: de::DeserializeSeed<'de>>(
+        &mut self,
+        seed: S,
+    ) -> Result<S::Value, Self::Error> {
+        self.tracker.enter::<S::Value>();
+        let value = self.access.next_value_seed(seed)?;
+        self.tracker.leave();
+        Ok(value)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        self.access.size_hint()
+    }
+}
+
+/// Strips module paths from a type name, e.g. `core::option::Option<my_app::Id>` becomes
+/// `Option<Id>`.
+fn short_type_name(name: &str) -> String {
+    let mut short = String::with_capacity(name.len());
+    let mut ident_start = 0;
+    let mut chars = name.chars().peekable();
+
+    while let Some(ch) = chars.next() {
+        if ch == ':' && chars.peek() == Some(&':') {
+            chars.next();
+            short.truncate(ident_start);
+        } else {
+            short.push(ch);
+
+            if !(ch.is_alphanumeric() || ch == '_') {
+                ident_start = short.len();
+            }
+        }
     }
+
+    short
 }
 
 #[cfg(test)]
@@ -192,8 +471,8 @@ mod tests {
     use serde::Deserialize;
 
     use super::*;
-    use crate::test::TestRequest;
-    use crate::{error, http, HttpResponse};
+    use crate::test::{call_service, init_service, read_body, TestRequest};
+    use crate::{error, http, web, App, HttpResponse};
 
     #[derive(Deserialize, Debug, Display)]
     #[display(fmt = "MyStruct({}, {})", key, value)]
@@ -208,6 +487,32 @@ mod tests {
         value: u32,
     }
 
+    /// Hex encoded ID, deserialized using `FromStr` like `uuid::Uuid`.
+    #[derive(Debug, PartialEq)]
+    struct HexId(u64);
+
+    impl std::str::FromStr for HexId {
+        type Err = std::num::ParseIntError;
+
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            u64::from_str_radix(s, 16).map(HexId)
+        }
+    }
+
+    impl<'de> de::Deserialize<'de> for HexId {
+        fn deserialize<D: de::Deserializer<'de>>(de: D) -> Result<Self, D::Error> {
+            let s = String::deserialize(de)?;
+            s.parse().map_err(de::Error::custom)
+        }
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct Item {
+        #[allow(dead_code)]
+        name: String,
+        id: HexId,
+    }
+
     #[actix_rt::test]
     async fn test_extract_path_single() {
         let resource = ResourceDef::new("/{value}/");
@@ -310,4 +615,137 @@ mod tests {
 
         assert_eq!(res.status(), http::StatusCode::CONFLICT);
     }
+
+    #[actix_rt::test]
+    async fn test_segment_error() {
+        let resource = ResourceDef::new("/{name}/{id}");
+        let mut req = TestRequest::with_uri("/item/ff").to_srv_request();
+        resource.match_path(req.match_info_mut());
+        let (req, mut pl) = req.into_parts();
+
+        let item = Path::<Item>::from_request(&req, &mut pl).await.unwrap();
+        assert_eq!(item.id, HexId(255));
+        let (_, id) = Path::<(String, HexId)>::from_request(&req, &mut pl)
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(id, HexId(255));
+
+        let mut req = TestRequest::with_uri("/item/xyz").to_srv_request();
+        resource.match_path(req.match_info_mut());
+        let (req, mut pl) = req.into_parts();
+
+        let err = Path::<Item>::from_request(&req, &mut pl).await.unwrap_err();
+        assert_eq!(
+            err.as_response_error().status_code(),
+            http::StatusCode::NOT_FOUND
+        );
+        assert_eq!(
+            err.to_string(),
+            "Path segment `id` is not a valid `HexId`: invalid digit found in string"
+        );
+
+        let err = Path::<(String, u32)>::from_request(&req, &mut pl)
+            .await
+            .unwrap_err();
+        assert!(err
+            .to_string()
+            .starts_with("Path segment `id` is not a valid `u32`"));
+
+        // errors that do not relate to a single segment
+        let err = Path::<(String, String, String)>::from_request(&req, &mut pl)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().starts_with("Path deserialize error"));
+    }
+
+    #[actix_rt::test]
+    async fn test_single_segment_error() {
+        let resource = ResourceDef::new("/{id}");
+        let mut req = TestRequest::with_uri("/xyz").to_srv_request();
+        resource.match_path(req.match_info_mut());
+        let (req, mut pl) = req.into_parts();
+
+        let err = Path::<HexId>::from_request(&req, &mut pl)
+            .await
+            .unwrap_err();
+        assert!(err
+            .to_string()
+            .starts_with("Path segment `id` is not a valid `HexId`"));
+
+        let err = Path::<Option<u8>>::from_request(&req, &mut pl)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("not a valid `Option<u8>`"));
+
+        let err = Path::<(u32,)>::from_request(&req, &mut pl)
+            .await
+            .unwrap_err();
+        assert!(err
+            .to_string()
+            .starts_with("Path segment `id` is not a valid `u32`"));
+
+        #[derive(Debug, Deserialize)]
+        struct User {
+            #[allow(dead_code)]
+            id: HexId,
+        }
+
+        let err = Path::<User>::from_request(&req, &mut pl).await.unwrap_err();
+        assert!(err
+            .to_string()
+            .starts_with("Path segment `id` is not a valid `HexId`"));
+    }
+
+    #[actix_rt::test]
+    async fn test_error_status() {
+        let srv = init_service(
+            App::new()
+                .service(
+                    web::scope("/users")
+                        .app_data(
+                            PathConfig::default().error_status(http::StatusCode::BAD_REQUEST),
+                        )
+                        .route(
+                            "/{user_id}",
+                            web::get().to(|_: Path<HexId>| HttpResponse::Ok()),
+                        ),
+                )
+                .route(
+                    "/items/{item_id}",
+                    web::get().to(|_: Path<HexId>| HttpResponse::Ok()),
+                ),
+        )
+        .await;
+
+        let req = TestRequest::with_uri("/users/1f").to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.status(), http::StatusCode::OK);
+
+        let req = TestRequest::with_uri("/users/nope").to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.status(), http::StatusCode::BAD_REQUEST);
+        let body = read_body(res).await;
+        assert!(body.starts_with(b"Path segment `user_id` is not a valid `HexId`"));
+
+        let req = TestRequest::with_uri("/items/nope").to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.status(), http::StatusCode::NOT_FOUND);
+        let body = read_body(res).await;
+        assert!(body.starts_with(b"Path segment `item_id` is not a valid `HexId`"));
+    }
+
+    #[test]
+    fn test_short_type_name() {
+        assert_eq!(short_type_name("u32"), "u32");
+        assert_eq!(short_type_name("my_app::ids::UserId"), "UserId");
+        assert_eq!(
+            short_type_name("core::option::Option<my_app::Id>"),
+            "Option<Id>"
+        );
+        assert_eq!(
+            short_type_name("(alloc::string::String, my_app::Id)"),
+            "(String, Id)"
+        );
+    }
 }
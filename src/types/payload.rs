@@ -1,6 +1,7 @@
 //! Basic binary and string payload extractors.
 
 use std::{
+    borrow::Cow,
     future::Future,
     pin::Pin,
     str,
@@ -17,7 +18,9 @@ use futures_util::{
 };
 use mime::Mime;
 
-use crate::{dev, http::header, web, Error, FromRequest, HttpMessage, HttpRequest};
+use crate::{
+    dev, http::header, types::ExtractorLimits, Error, FromRequest, HttpMessage, HttpRequest,
+};
 
 /// Extract a request's raw payload stream.
 ///
@@ -238,12 +241,20 @@ impl PayloadConfig {
         Ok(())
     }
 
-    /// Extract payload config from app data. Check both `T` and `Data<T>`, in that order, and fall
-    /// back to the default payload config if neither is found.
-    fn from_req(req: &HttpRequest) -> &Self {
-        req.app_data::<Self>()
-            .or_else(|| req.app_data::<web::Data<Self>>().map(|d| d.as_ref()))
-            .unwrap_or(&DEFAULT_CONFIG)
+    /// Extract payload config from app data. Check both `T` and `Data<T>` at each layer, innermost first, then
+    /// the body limit of [`ExtractorLimits`], and fall back to the default payload config if none is found.
+    fn from_req(req: &HttpRequest) -> Cow<'_, Self> {
+        if let Some(config) = req.app_data_or_data::<Self>() {
+            return Cow::Borrowed(config);
+        }
+
+        match req
+            .app_data_or_data::<ExtractorLimits>()
+            .and_then(|limits| limits.body_limit)
+        {
+            Some(limit) => Cow::Owned(PayloadConfig::new(limit)),
+            None => Cow::Borrowed(&DEFAULT_CONFIG),
+        }
     }
 }
 
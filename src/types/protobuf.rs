@@ -0,0 +1,347 @@
+//! For Protocol Buffers helper documentation, see [`ProtoBuf`].
+
+use std::{fmt, ops, rc::Rc};
+
+use actix_http::Payload;
+use futures_util::future::{FutureExt, LocalBoxFuture};
+use prost::Message;
+
+use crate::{
+    error::{ErrorInternalServerError, ProtoBufPayloadError},
+    extract::FromRequest,
+    types::encoded::{collect_body, is_application, BodyError, DEFAULT_LIMIT},
+    Error, HttpRequest, HttpResponse, Responder,
+};
+
+/// Content type used for Protocol Buffers responses.
+const CONTENT_TYPE: &str = "application/x-protobuf";
+
+/// Protocol Buffers extractor and responder.
+///
+/// `ProtoBuf` has two uses: Protocol Buffers responses, and extracting typed data from Protocol
+/// Buffers request payloads. The inner type `T` is a [`prost::Message`]. This type is only
+/// available with the `protobuf` feature.
+///
+/// # Extractor
+/// The request must have an `application/x-protobuf` or `application/protobuf` content type.
+/// Compressed payloads are decompressed before decoding. Use [`ProtoBufConfig`] to configure the
+/// extraction process.
+///
+/// ```
+/// use actix_web::{post, web};
+///
+/// #[derive(Clone, PartialEq, prost::Message)]
+/// struct Info {
+///     #[prost(string, tag = "1")]
+///     username: String,
+/// }
+///
+/// /// decode `Info` from request's body
+/// #[post("/")]
+/// async fn index(info: web::ProtoBuf<Info>) -> String {
+///     format!("Welcome {}!", info.username)
+/// }
+/// ```
+///
+/// # Responder
+/// A handler may return a `ProtoBuf<T>` to respond with the encoded message and an
+/// `application/x-protobuf` content type.
+///
+/// ```
+/// use actix_web::{get, web};
+///
+/// #[derive(Clone, PartialEq, prost::Message)]
+/// struct Info {
+///     #[prost(string, tag = "1")]
+///     name: String,
+/// }
+///
+/// #[get("/{name}")]
+/// async fn index(name: web::Path<String>) -> web::ProtoBuf<Info> {
+///     web::ProtoBuf(Info {
+///         name: name.into_inner(),
+///     })
+/// }
+/// ```
+pub struct ProtoBuf<T>(pub T);
+
+impl<T> ProtoBuf<T> {
+    /// Unwrap into inner `T` value.
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> ops::Deref for ProtoBuf<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T> ops::DerefMut for ProtoBuf<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.0
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for ProtoBuf<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "ProtoBuf: {:?}", self.0)
+    }
+}
+
+/// See [here](#extractor) for example of usage as an extractor.
+impl<T> FromRequest for ProtoBuf<T>
+where
+    T: Message + Default + 'static,
+{
+    type Config = ProtoBufConfig;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self, Error>>;
+
+    #[inline]
+    fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
+        let req2 = req.clone();
+        let (limit, err_handler) = req
+            .app_data_or_data::<Self::Config>()
+            .map(|c| (c.limit, c.err_handler.clone()))
+            .unwrap_or((DEFAULT_LIMIT, None));
+
+        let body = collect_body(req, payload, limit, |mime| {
+            mime.map_or(false, |mime| {
+                is_application(mime, &["x-protobuf", "protobuf"], None)
+            })
+        });
+
+        async move {
+            let res = body
+                .await
+                .map_err(payload_error)
+                .and_then(|body| T::decode(body).map_err(Into::into));
+
+            match res {
+                Ok(msg) => Ok(ProtoBuf(msg)),
+                Err(err) => match err_handler {
+                    Some(err_handler) => Err((err_handler)(err, &req2)),
+                    None => Err(err.into()),
+                },
+            }
+        }
+        .boxed_local()
+    }
+}
+
+fn payload_error(err: BodyError) -> ProtoBufPayloadError {
+    match err {
+        BodyError::ContentType => ProtoBufPayloadError::ContentType,
+        BodyError::Overflow { limit } => ProtoBufPayloadError::Overflow { limit },
+        BodyError::Payload(err) => ProtoBufPayloadError::Payload(err),
+    }
+}
+
+/// See [here](#responder) for example of usage as a handler return type.
+impl<T: Message> Responder for ProtoBuf<T> {
+    fn respond_to(self, _: &HttpRequest) -> HttpResponse {
+        let mut buf = Vec::with_capacity(self.0.encoded_len());
+
+        match self.0.encode(&mut buf) {
+            Ok(()) => HttpResponse::Ok().content_type(CONTENT_TYPE).body(buf),
+            Err(err) => HttpResponse::from_error(ErrorInternalServerError(err)),
+        }
+    }
+}
+
+/// [`ProtoBuf`] extractor configuration.
+///
+/// ```
+/// use actix_web::{post, web, App};
+///
+/// #[derive(Clone, PartialEq, prost::Message)]
+/// struct Info {
+///     #[prost(string, tag = "1")]
+///     username: String,
+/// }
+///
+/// // Max payload size for Protocol Buffers messages is set to 4kB.
+/// #[post("/")]
+/// async fn index(info: web::ProtoBuf<Info>) -> String {
+///     format!("Welcome {}!", info.username)
+/// }
+///
+/// App::new()
+///     .app_data(web::ProtoBufConfig::default().limit(4096))
+///     .service(index);
+/// ```
+#[derive(Clone)]
+pub struct ProtoBufConfig {
+    limit: usize,
+    err_handler: Option<Rc<dyn Fn(ProtoBufPayloadError, &HttpRequest) -> Error>>,
+}
+
+impl ProtoBufConfig {
+    /// Set maximum accepted payload size. By default this limit is 256kB.
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = limit;
+        self
+    }
+
+    /// Set custom error handler.
+    pub fn error_handler<F>(mut self, f: F) -> Self
+    where
+        F: Fn(ProtoBufPayloadError, &HttpRequest) -> Error + 'static,
+    {
+        self.err_handler = Some(Rc::new(f));
+        self
+    }
+}
+
+impl Default for ProtoBufConfig {
+    fn default() -> Self {
+        ProtoBufConfig {
+            limit: DEFAULT_LIMIT,
+            err_handler: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+
+    use super::*;
+    use crate::{
+        http::{header, StatusCode},
+        test::{call_service, init_service, read_body, TestRequest},
+        web, App,
+    };
+
+    #[derive(Clone, PartialEq, Message)]
+    struct Person {
+        #[prost(string, tag = "1")]
+        name: String,
+        #[prost(uint32, tag = "2")]
+        age: u32,
+        #[prost(string, repeated, tag = "3")]
+        emails: Vec<String>,
+    }
+
+    fn person() -> Person {
+        Person {
+            name: "Ferris".to_owned(),
+            age: 10,
+            emails: vec!["ferris@example.com".to_owned()],
+        }
+    }
+
+    fn encode(person: &Person) -> Bytes {
+        let mut buf = Vec::new();
+        person.encode(&mut buf).unwrap();
+        Bytes::from(buf)
+    }
+
+    #[actix_rt::test]
+    async fn test_round_trip() {
+        let srv = init_service(App::new().route(
+            "/",
+            web::post().to(|person: ProtoBuf<Person>| async move {
+                let mut person = person.into_inner();
+                person.age += 1;
+                ProtoBuf(person)
+            }),
+        ))
+        .await;
+
+        for ct in &["application/x-protobuf", "application/protobuf"] {
+            let req = TestRequest::post()
+                .insert_header((header::CONTENT_TYPE, *ct))
+                .set_payload(encode(&person()))
+                .to_request();
+            let res = call_service(&srv, req).await;
+            assert_eq!(res.status(), StatusCode::OK);
+            assert_eq!(
+                res.headers().get(header::CONTENT_TYPE).unwrap(),
+                CONTENT_TYPE
+            );
+
+            let body = read_body(res).await;
+            let decoded = Person::decode(body).unwrap();
+            assert_eq!(
+                decoded,
+                Person {
+                    age: 11,
+                    ..person()
+                }
+            );
+        }
+    }
+
+    #[actix_rt::test]
+    async fn test_extract_errors() {
+        let (req, mut pl) = TestRequest::post()
+            .insert_header((header::CONTENT_TYPE, "application/json"))
+            .set_payload(encode(&person()))
+            .to_http_parts();
+        let err = ProtoBuf::<Person>::from_request(&req, &mut pl)
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err.as_error::<ProtoBufPayloadError>(),
+            Some(ProtoBufPayloadError::ContentType)
+        ));
+
+        let (req, mut pl) = TestRequest::post()
+            .insert_header((header::CONTENT_TYPE, "application/x-protobuf"))
+            .set_payload(Bytes::from_static(b"\xff\xff\xff"))
+            .to_http_parts();
+        let err = ProtoBuf::<Person>::from_request(&req, &mut pl)
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err.as_error::<ProtoBufPayloadError>(),
+            Some(ProtoBufPayloadError::Deserialize(_))
+        ));
+        assert_eq!(
+            err.as_response_error().status_code(),
+            StatusCode::BAD_REQUEST
+        );
+    }
+
+    #[actix_rt::test]
+    async fn test_limit() {
+        let body = encode(&person());
+
+        let (req, mut pl) = TestRequest::post()
+            .insert_header((header::CONTENT_TYPE, "application/x-protobuf"))
+            .insert_header((header::CONTENT_LENGTH, body.len()))
+            .set_payload(body.clone())
+            .app_data(ProtoBufConfig::default().limit(8))
+            .to_http_parts();
+        let err = ProtoBuf::<Person>::from_request(&req, &mut pl)
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err.as_error::<ProtoBufPayloadError>(),
+            Some(ProtoBufPayloadError::Overflow { limit: 8 })
+        ));
+        assert_eq!(
+            err.as_response_error().status_code(),
+            StatusCode::PAYLOAD_TOO_LARGE
+        );
+
+        let (req, mut pl) = TestRequest::post()
+            .insert_header((header::CONTENT_TYPE, "application/x-protobuf"))
+            .set_payload(body)
+            .app_data(
+                ProtoBufConfig::default()
+                    .limit(8)
+                    .error_handler(|_, _| ErrorInternalServerError("custom")),
+            )
+            .to_http_parts();
+        let err = ProtoBuf::<Person>::from_request(&req, &mut pl)
+            .await
+            .unwrap_err();
+        assert_eq!(err.to_string(), "custom");
+    }
+}
@@ -0,0 +1,359 @@
+//! Reverse proxy helpers.
+//!
+//! See [`forward`] for usage.
+
+use std::{
+    net::IpAddr,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use actix_http::{
+    body::{Body, BodySize, MessageBody},
+    client::{ConnectError, SendRequestError},
+    http::{
+        header::{self, HeaderMap, HeaderName, HeaderValue},
+        uri::Authority,
+        Error as HttpError, Method, StatusCode, Uri, Version,
+    },
+    Error,
+};
+use awc::Client;
+use bytes::Bytes;
+use futures_util::TryStreamExt as _;
+
+use crate::{
+    body::SizedStream,
+    error::{ErrorBadGateway, ErrorGatewayTimeout},
+    web, HttpRequest, HttpResponse,
+};
+
+/// Hop-by-hop headers, which only apply to a single connection and must not be forwarded.
+///
+/// Besides the headers listed in RFC 7230 §6.1, this includes `Proxy-Connection` which is still
+/// sent by some clients. Headers named in the `Connection` header are removed too.
+const HOP_BY_HOP: &[HeaderName] = &[
+    header::CONNECTION,
+    header::PROXY_AUTHENTICATE,
+    header::PROXY_AUTHORIZATION,
+    header::TE,
+    header::TRAILER,
+    header::TRANSFER_ENCODING,
+    header::UPGRADE,
+];
+
+const KEEP_ALIVE: &str = "keep-alive";
+const PROXY_CONNECTION: &str = "proxy-connection";
+const X_FORWARDED_FOR: &str = "x-forwarded-for";
+
+/// Forwards a request to `upstream` and streams the upstream response back.
+///
+/// The request is sent to the scheme and authority of `upstream`, with the request path and query
+/// appended to its path. Request and response bodies are streamed without buffering.
+///
+/// Headers are copied in both directions, except for:
+/// - hop-by-hop headers (`Connection`, the headers it names, `Keep-Alive`, `TE`, `Trailer`,
+///   `Transfer-Encoding`, `Upgrade`, `Proxy-Authenticate` and `Proxy-Authorization`);
+/// - `Host`, which is set to the authority of `upstream`.
+///
+/// The client's address is appended to the `X-Forwarded-For` header and an element describing the
+/// client, protocol and original host is appended to the `Forwarded` header.
+///
+/// Failing to reach the upstream results in `502 Bad Gateway`, or `504 Gateway Timeout` if the
+/// client's timeout elapsed. Upgrade requests, e.g. for WebSockets, are forwarded as plain
+/// requests.
+///
+/// ```
+/// use actix_web::{http::Uri, web, Error, HttpRequest, HttpResponse};
+/// use awc::Client;
+///
+/// async fn proxy(
+///     req: HttpRequest,
+///     payload: web::Payload,
+///     client: web::Data<Client>,
+/// ) -> Result<HttpResponse, Error> {
+///     let upstream = Uri::from_static("http://127.0.0.1:8081");
+///     web::proxy::forward(req, payload, &client, upstream).await
+/// }
+/// ```
+pub async fn forward(
+    req: HttpRequest,
+    payload: web::Payload,
+    client: &Client,
+    upstream: Uri,
+) -> Result<HttpResponse, Error> {
+    let uri = upstream_uri(&req, upstream)?;
+
+    let mut upstream_req = client.request(req.method().clone(), uri).no_decompress();
+
+    let headers = upstream_req.headers_mut();
+    copy_headers(req.headers(), headers);
+    headers.remove(header::HOST);
+    headers.remove(header::CONTENT_LENGTH);
+    append_forwarded(&req, headers);
+
+    let body = payload.map_err(Error::from);
+
+    let res = match request_body_size(&req) {
+        BodySize::Sized(len) => upstream_req.send_body(SizedStream::new(len, body)),
+        BodySize::Stream => upstream_req.send_stream(body),
+        _ => upstream_req.send(),
+    }
+    .await
+    .map_err(|err| match err {
+        SendRequestError::Timeout | SendRequestError::Connect(ConnectError::Timeout) => {
+            ErrorGatewayTimeout(err)
+        }
+        err => ErrorBadGateway(err),
+    })?;
+
+    let mut builder = HttpResponse::build(res.status());
+
+    {
+        let mut headers = HeaderMap::new();
+        copy_headers(res.headers(), &mut headers);
+
+        for (name, value) in headers.iter() {
+            builder.append_header((name.clone(), value.clone()));
+        }
+    }
+
+    let len = res
+        .headers()
+        .get(header::CONTENT_LENGTH)
+        .and_then(|len| len.to_str().ok())
+        .and_then(|len| len.parse::<u64>().ok());
+
+    let status = res.status();
+
+    Ok(if req.method() == Method::HEAD {
+        // keep the upstream's content length without waiting for a body
+        builder.body(Body::from_message(HeadBody(len)))
+    } else if status.is_informational()
+        || status == StatusCode::NO_CONTENT
+        || status == StatusCode::NOT_MODIFIED
+    {
+        builder.body(Body::None)
+    } else if let Some(len) = len {
+        builder.streaming_sized(len, res)
+    } else {
+        builder.streaming(res)
+    })
+}
+
+/// Joins the path of `upstream` with the path and query of the request.
+fn upstream_uri(req: &HttpRequest, upstream: Uri) -> Result<Uri, Error> {
+    let mut path = upstream.path().trim_end_matches('/').to_owned();
+    path.push_str(req.path());
+
+    if let Some(query) = req.uri().query() {
+        path.push('?');
+        path.push_str(query);
+    }
+
+    let mut parts = upstream.into_parts();
+    parts.path_and_query = Some(path.parse().map_err(HttpError::from)?);
+
+    Ok(Uri::from_parts(parts).map_err(HttpError::from)?)
+}
+
+/// Copies all end-to-end headers from `src` to `dst`.
+fn copy_headers(src: &HeaderMap, dst: &mut HeaderMap) {
+    let connection_headers = src
+        .get_all(header::CONNECTION)
+        .filter_map(|val| val.to_str().ok())
+        .flat_map(|val| val.split(','))
+        .map(|name| name.trim().to_ascii_lowercase())
+        .collect::<Vec<_>>();
+
+    for (name, value) in src.iter() {
+        let hop_by_hop = HOP_BY_HOP.contains(name)
+            || name.as_str() == KEEP_ALIVE
+            || name.as_str() == PROXY_CONNECTION
+            || connection_headers.iter().any(|hop| name.as_str() == hop);
+
+        if !hop_by_hop {
+            dst.append(name.clone(), value.clone());
+        }
+    }
+}
+
+/// Returns the size of the request body, based on its headers.
+fn request_body_size(req: &HttpRequest) -> BodySize {
+    let len = req
+        .headers()
+        .get(header::CONTENT_LENGTH)
+        .and_then(|len| len.to_str().ok())
+        .and_then(|len| len.parse::<u64>().ok());
+
+    match len {
+        Some(len) => BodySize::Sized(len),
+        None if req.headers().contains_key(header::TRANSFER_ENCODING) => BodySize::Stream,
+
+        // HTTP/2 requests may omit the content length
+        None if req.version() == Version::HTTP_2
+            && req.method() != Method::GET
+            && req.method() != Method::HEAD =>
+        {
+            BodySize::Stream
+        }
+
+        None => BodySize::None,
+    }
+}
+
+/// Appends this hop to the `X-Forwarded-For` and `Forwarded` headers.
+fn append_forwarded(req: &HttpRequest, headers: &mut HeaderMap) {
+    let peer = req.peer_addr().map(|addr| addr.ip());
+
+    if let Some(peer) = peer {
+        let x_forwarded_for = HeaderName::from_static(X_FORWARDED_FOR);
+        let value = append_list(headers.get_all(&x_forwarded_for), &peer.to_string());
+        headers.insert(x_forwarded_for, value);
+    }
+
+    let node = match peer {
+        Some(IpAddr::V4(ip)) => ip.to_string(),
+        Some(IpAddr::V6(ip)) => format!("\"[{}]\"", ip),
+        None => "unknown".to_owned(),
+    };
+
+    let proto = if req.app_config().secure() {
+        "https"
+    } else {
+        "http"
+    };
+
+    let mut element = format!("for={};proto={}", node, proto);
+
+    let host = req
+        .headers()
+        .get(header::HOST)
+        .and_then(|host| host.to_str().ok())
+        .or_else(|| req.uri().authority().map(|authority| authority.as_str()))
+        // a valid authority can not break out of the quoted-string
+        .filter(|host| host.parse::<Authority>().is_ok());
+
+    if let Some(host) = host {
+        element.push_str(&format!(";host=\"{}\"", host));
+    }
+
+    let value = append_list(headers.get_all(header::FORWARDED), &element);
+    headers.insert(header::FORWARDED, value);
+}
+
+/// Appends an item to the comma separated list formed by the existing values of a header.
+fn append_list<'a>(existing: impl Iterator<Item = &'a HeaderValue>, item: &str) -> HeaderValue {
+    let mut items = existing
+        .filter_map(|val| val.to_str().ok())
+        .map(str::trim)
+        .filter(|val| !val.is_empty())
+        .collect::<Vec<_>>();
+    items.push(item);
+
+    // all items are visible ASCII
+    HeaderValue::from_str(&items.join(", ")).unwrap()
+}
+
+/// Body of a response to a `HEAD` request, which reports the size of the upstream body.
+struct HeadBody(Option<u64>);
+
+impl MessageBody for HeadBody {
+    fn size(&self) -> BodySize {
+        match self.0 {
+            Some(len) => BodySize::Sized(len),
+            None => BodySize::None,
+        }
+    }
+
+    fn poll_next(
+        self: Pin<&mut Self>,
+        _: &mut Context<'_>,
+    ) -> Poll<Option<Result<Bytes, Error>>> {
+        Poll::Ready(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::TestRequest;
+
+    #[test]
+    fn test_copy_headers() {
+        let mut src = HeaderMap::new();
+        src.insert(header::CONNECTION, HeaderValue::from_static("close, X-Hop"));
+        src.insert(
+            HeaderName::from_static("x-hop"),
+            HeaderValue::from_static("1"),
+        );
+        src.insert(
+            HeaderName::from_static("keep-alive"),
+            HeaderValue::from_static("timeout=5"),
+        );
+        src.insert(header::TE, HeaderValue::from_static("trailers"));
+        src.insert(header::UPGRADE, HeaderValue::from_static("websocket"));
+        src.insert(header::ACCEPT, HeaderValue::from_static("text/html"));
+        src.append(header::ACCEPT, HeaderValue::from_static("text/plain"));
+
+        let mut dst = HeaderMap::new();
+        copy_headers(&src, &mut dst);
+
+        assert_eq!(dst.len(), 2);
+        assert_eq!(dst.get_all(header::ACCEPT).count(), 2);
+    }
+
+    #[test]
+    fn test_upstream_uri() {
+        let req = TestRequest::with_uri("/users/1?full=true").to_http_request();
+        let uri = upstream_uri(&req, Uri::from_static("http://backend:8080/api/")).unwrap();
+        assert_eq!(uri, "http://backend:8080/api/users/1?full=true");
+
+        let req = TestRequest::with_uri("/").to_http_request();
+        let uri = upstream_uri(&req, Uri::from_static("https://backend")).unwrap();
+        assert_eq!(uri, "https://backend/");
+    }
+
+    #[test]
+    fn test_append_forwarded() {
+        let req = TestRequest::default()
+            .peer_addr("[::1]:4000".parse().unwrap())
+            .insert_header((header::HOST, "example.com:8080"))
+            .insert_header((X_FORWARDED_FOR, "10.0.0.1"))
+            .insert_header((header::FORWARDED, "for=10.0.0.1"))
+            .to_http_request();
+
+        let mut headers = req.headers().clone();
+        append_forwarded(&req, &mut headers);
+
+        assert_eq!(headers.get(X_FORWARDED_FOR).unwrap(), "10.0.0.1, ::1");
+        assert_eq!(
+            headers.get(header::FORWARDED).unwrap(),
+            "for=10.0.0.1, for=\"[::1]\";proto=http;host=\"example.com:8080\""
+        );
+
+        // hosts that are not valid authorities are left out
+        for host in &["evil\";for=1.2.3.4", "a\\b", "a b"] {
+            let req = TestRequest::default()
+                .insert_header((header::HOST, *host))
+                .to_http_request();
+            let mut headers = HeaderMap::new();
+            append_forwarded(&req, &mut headers);
+
+            assert_eq!(
+                headers.get(header::FORWARDED).unwrap(),
+                "for=unknown;proto=http"
+            );
+        }
+
+        let req = TestRequest::default().to_http_request();
+        let mut headers = HeaderMap::new();
+        append_forwarded(&req, &mut headers);
+
+        assert!(!headers.contains_key(X_FORWARDED_FOR));
+        assert_eq!(
+            headers.get(header::FORWARDED).unwrap(),
+            "for=unknown;proto=http"
+        );
+    }
+}
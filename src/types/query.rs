@@ -1,11 +1,18 @@
 //! For query parameter extractor documentation, see [`Query`].
 
-use std::{fmt, ops, sync::Arc};
+use std::{borrow::Cow, fmt, ops, ops::Range, rc::Rc, sync::Arc};
 
+use actix_http::http::Uri;
 use futures_util::future::{err, ok, Ready};
+use percent_encoding::percent_decode_str;
 use serde::de;
 
-use crate::{dev::Payload, error::QueryPayloadError, Error, FromRequest, HttpRequest};
+use crate::{
+    dev::Payload,
+    error::QueryPayloadError,
+    types::{ExtractorLimits, QueryParser},
+    Error, FromRequest, HttpRequest,
+};
 
 /// Extract typed information from the request's query.
 ///
@@ -123,30 +130,41 @@ where
 
     #[inline]
     fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
-        let error_handler = req
+        let (strict, parser, error_handler) = req
             .app_data::<Self::Config>()
-            .map(|c| c.err_handler.clone())
-            .unwrap_or(None);
-
-        serde_urlencoded::from_str::<T>(req.query_string())
-            .map(|val| ok(Query(val)))
-            .unwrap_or_else(move |e| {
-                let e = QueryPayloadError::Deserialize(e);
-
-                log::debug!(
-                    "Failed during Query extractor deserialization. \
-                     Request path: {:?}",
-                    req.path()
-                );
-
-                let e = if let Some(error_handler) = error_handler {
-                    (error_handler)(e, req)
-                } else {
-                    e.into()
-                };
-
-                err(e)
+            .map(|c| (c.strict, c.parser, c.err_handler.clone()))
+            .or_else(|| {
+                req.app_data_or_data::<ExtractorLimits>()
+                    .and_then(|limits| limits.query_strict)
+                    .map(|strict| (strict, QueryParser::default(), None))
             })
+            .unwrap_or((false, QueryParser::default(), None));
+
+        let query = req.query_string();
+
+        let res = if strict && !is_well_formed(query) {
+            Err(QueryPayloadError::Malformed)
+        } else {
+            parser
+                .deserialize::<T>(query)
+                .map_err(QueryPayloadError::Deserialize)
+        };
+
+        res.map(|val| ok(Query(val))).unwrap_or_else(move |e| {
+            log::debug!(
+                "Failed during Query extractor deserialization. \
+                 Request path: {:?}",
+                req.path()
+            );
+
+            let e = if let Some(error_handler) = error_handler {
+                (error_handler)(e, req)
+            } else {
+                e.into()
+            };
+
+            err(e)
+        })
     }
 }
 
@@ -181,10 +199,26 @@ where
 /// ```
 #[derive(Clone)]
 pub struct QueryConfig {
+    strict: bool,
+    parser: QueryParser,
     err_handler: Option<Arc<dyn Fn(QueryPayloadError, &HttpRequest) -> Error + Send + Sync>>,
 }
 
 impl QueryConfig {
+    /// Reject query strings with malformed percent-encoding or invalid UTF-8, instead of
+    /// decoding them leniently. Disabled by default.
+    pub fn strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    /// Set the deserializer for query strings, e.g. [`QueryParser::Extended`] for nested
+    /// parameters. By default it is [`QueryParser::Urlencoded`].
+    pub fn parser(mut self, parser: QueryParser) -> Self {
+        self.parser = parser;
+        self
+    }
+
     /// Set custom error handler
     pub fn error_handler<F>(mut self, f: F) -> Self
     where
@@ -197,7 +231,157 @@ impl QueryConfig {
 
 impl Default for QueryConfig {
     fn default() -> Self {
-        QueryConfig { err_handler: None }
+        QueryConfig {
+            strict: false,
+            parser: QueryParser::default(),
+            err_handler: None,
+        }
+    }
+}
+
+/// Parameters of a request's query string, see [`HttpRequest::query`].
+///
+/// The query string is split into pairs once per request and cached in the request's extensions.
+/// Names and values are percent-decoded, with `+` decoded as a space, when they are accessed and
+/// are borrowed from the request's URI unless decoding changes them.
+///
+/// ```
+/// use actix_web::{HttpRequest, HttpResponse};
+///
+/// async fn index(req: HttpRequest) -> HttpResponse {
+///     let query = req.query();
+///     let page = query.get("page").unwrap_or_else(|| "1".into());
+///     let tags = query.get_all("tag").collect::<Vec<_>>();
+///
+///     HttpResponse::Ok().body(format!("page {} tagged {:?}", page, tags))
+/// }
+/// ```
+#[derive(Clone)]
+pub struct QueryParams {
+    inner: Rc<ParsedQuery>,
+}
+
+struct ParsedQuery {
+    uri: Uri,
+    /// Name and value ranges of each pair in the query string.
+    pairs: Vec<(Range<usize>, Range<usize>)>,
+}
+
+impl QueryParams {
+    /// Returns the cached parameters of a request, parsing its query string if there are none or
+    /// its URI was rewritten since they were parsed.
+    pub(crate) fn from_request(req: &HttpRequest) -> Self {
+        if let Some(params) = req.extensions().get::<QueryParams>() {
+            if params.inner.uri.query() == req.uri().query() {
+                return params.clone();
+            }
+        }
+
+        let params = QueryParams::parse(req.uri().clone());
+        req.extensions_mut().insert(params.clone());
+        params
+    }
+
+    fn parse(uri: Uri) -> Self {
+        let mut pairs = Vec::new();
+
+        if let Some(query) = uri.query() {
+            let mut start = 0;
+
+            for pair in query.split('&') {
+                let end = start + pair.len();
+
+                if !pair.is_empty() {
+                    let eq = pair.find('=').map_or(end, |idx| start + idx);
+                    pairs.push((start..eq, (eq + 1).min(end)..end));
+                }
+
+                start = end + 1;
+            }
+        }
+
+        QueryParams {
+            inner: Rc::new(ParsedQuery { uri, pairs }),
+        }
+    }
+
+    /// Returns the value of the first parameter with the given name.
+    pub fn get(&self, name: &str) -> Option<Cow<'_, str>> {
+        self.raw_pairs()
+            .find(|(key, _)| decode(key) == name)
+            .map(|(_, value)| decode(value))
+    }
+
+    /// Returns the values of all parameters with the given name, in query string order.
+    pub fn get_all<'a>(&'a self, name: &'a str) -> impl Iterator<Item = Cow<'a, str>> + 'a {
+        self.raw_pairs()
+            .filter(move |(key, _)| decode(key) == name)
+            .map(|(_, value)| decode(value))
+    }
+
+    /// Returns true if a parameter with the given name is present.
+    pub fn contains_key(&self, name: &str) -> bool {
+        self.raw_pairs().any(|(key, _)| decode(key) == name)
+    }
+
+    /// Iterates over all name and value pairs, in query string order.
+    pub fn iter(&self) -> impl Iterator<Item = (Cow<'_, str>, Cow<'_, str>)> {
+        self.raw_pairs()
+            .map(|(key, value)| (decode(key), decode(value)))
+    }
+
+    /// Number of parameters, counting repeated names separately.
+    pub fn len(&self) -> usize {
+        self.inner.pairs.len()
+    }
+
+    /// Returns true if the query string has no parameters.
+    pub fn is_empty(&self) -> bool {
+        self.inner.pairs.is_empty()
+    }
+
+    fn raw_pairs(&self) -> impl Iterator<Item = (&str, &str)> {
+        let query = self.inner.uri.query().unwrap_or("");
+
+        self.inner
+            .pairs
+            .iter()
+            .map(move |(key, value)| (&query[key.clone()], &query[value.clone()]))
+    }
+}
+
+impl fmt::Debug for QueryParams {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+/// Decodes a form URL encoded query string component, borrowing it when no decoding is needed.
+/// Returns true if every `%` in `query` starts an escape and the decoded bytes are UTF-8.
+fn is_well_formed(query: &str) -> bool {
+    let bytes = query.as_bytes();
+    let mut i = 0;
+
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            match bytes.get(i + 1..i + 3) {
+                Some(hex) if hex.iter().all(u8::is_ascii_hexdigit) => i += 3,
+                _ => return false,
+            }
+        } else {
+            i += 1;
+        }
+    }
+
+    percent_decode_str(query).decode_utf8().is_ok()
+}
+
+pub(crate) fn decode(input: &str) -> Cow<'_, str> {
+    if input.contains('+') {
+        let input = input.replace('+', " ");
+        Cow::Owned(percent_decode_str(&input).decode_utf8_lossy().into_owned())
+    } else {
+        percent_decode_str(input).decode_utf8_lossy()
     }
 }
 
@@ -284,4 +468,150 @@ mod tests {
             StatusCode::UNPROCESSABLE_ENTITY
         );
     }
+
+    #[actix_rt::test]
+    async fn test_extended_parser() {
+        #[derive(Debug, Deserialize)]
+        struct Search {
+            filter: Id,
+            tag: Vec<String>,
+        }
+
+        let uri = "/?filter[id]=7&tag[]=a&tag[]=b";
+        let req = TestRequest::with_uri(uri).to_srv_request();
+        let (req, mut pl) = req.into_parts();
+        assert!(Query::<Search>::from_request(&req, &mut pl).await.is_err());
+
+        let cfg = QueryConfig::default()
+            .parser(QueryParser::Extended)
+            .error_handler(|e, _| {
+                let resp = HttpResponse::UnprocessableEntity().finish();
+                InternalError::from_response(e, resp).into()
+            });
+
+        let req = TestRequest::with_uri(uri)
+            .app_data(cfg.clone())
+            .to_srv_request();
+        let (req, mut pl) = req.into_parts();
+        let search = Query::<Search>::from_request(&req, &mut pl).await.unwrap();
+        assert_eq!(search.filter.id, "7");
+        assert_eq!(search.tag, vec!["a", "b"]);
+
+        // errors, including nesting too deep, go through the error handler
+        let req = TestRequest::with_uri("/?filter[id][a][b][c][d][e]=7")
+            .app_data(cfg)
+            .to_srv_request();
+        let (req, mut pl) = req.into_parts();
+        let err = Query::<Search>::from_request(&req, &mut pl)
+            .await
+            .unwrap_err();
+        assert_eq!(
+            err.as_response_error().error_response().status(),
+            StatusCode::UNPROCESSABLE_ENTITY
+        );
+    }
+
+    #[actix_rt::test]
+    async fn test_strict() {
+        let req = TestRequest::with_uri("/?id=te%zzst").to_srv_request();
+        let (req, mut pl) = req.into_parts();
+        assert!(Query::<Id>::from_request(&req, &mut pl).await.is_ok());
+
+        let req = TestRequest::with_uri("/?id=te%zzst")
+            .app_data(ExtractorLimits::default().query_strict(true))
+            .to_srv_request();
+        let (req, mut pl) = req.into_parts();
+        assert!(Query::<Id>::from_request(&req, &mut pl).await.is_err());
+
+        let req = TestRequest::with_uri("/?id=caf%C3%A9")
+            .app_data(ExtractorLimits::default().query_strict(true))
+            .to_srv_request();
+        let (req, mut pl) = req.into_parts();
+        let s = Query::<Id>::from_request(&req, &mut pl).await.unwrap();
+        assert_eq!(s.id, "caf\u{e9}");
+
+        for query in &["id=%C3", "id=100%", "id=%4"] {
+            assert!(!is_well_formed(query), "{}", query);
+        }
+
+        // `QueryConfig` wins over the umbrella setting
+        let req = TestRequest::with_uri("/?id=%FF")
+            .app_data(ExtractorLimits::default().query_strict(true))
+            .app_data(QueryConfig::default())
+            .to_srv_request();
+        let (req, mut pl) = req.into_parts();
+        assert!(Query::<Id>::from_request(&req, &mut pl).await.is_ok());
+    }
+
+    #[actix_rt::test]
+    async fn test_query_params_decoding() {
+        let req =
+            TestRequest::with_uri("/?name=John+Doe&city=S%C3%A3o%20Paulo&op=a%2Bb%3Dc&eq=x=y")
+                .to_http_request();
+        let query = req.query();
+
+        assert_eq!(query.get("name").unwrap(), "John Doe");
+        assert_eq!(query.get("city").unwrap(), "São Paulo");
+        assert_eq!(query.get("op").unwrap(), "a+b=c");
+        assert_eq!(query.get("eq").unwrap(), "x=y");
+        assert!(matches!(query.get("eq"), Some(Cow::Borrowed(_))));
+        assert!(matches!(query.get("name"), Some(Cow::Owned(_))));
+
+        let req = TestRequest::with_uri("/?first%20name=Jane&flag&empty=").to_http_request();
+        let query = req.query();
+
+        assert_eq!(query.get("first name").unwrap(), "Jane");
+        assert_eq!(query.get("flag").unwrap(), "");
+        assert_eq!(query.get("empty").unwrap(), "");
+        assert_eq!(
+            format!("{:?}", query),
+            r#"{"first name": "Jane", "flag": "", "empty": ""}"#
+        );
+    }
+
+    #[actix_rt::test]
+    async fn test_query_params_repeated_and_absent() {
+        let req = TestRequest::with_uri("/?tag=a&&id=1&tag=b&tag=").to_http_request();
+        let query = req.query();
+
+        assert_eq!(query.len(), 4);
+        assert_eq!(query.get("tag").unwrap(), "a");
+        assert_eq!(query.get_all("tag").collect::<Vec<_>>(), vec!["a", "b", ""]);
+        assert_eq!(
+            query.iter().collect::<Vec<_>>(),
+            vec![
+                ("tag".into(), "a".into()),
+                ("id".into(), "1".into()),
+                ("tag".into(), "b".into()),
+                ("tag".into(), "".into())
+            ]
+        );
+
+        assert!(query.get("missing").is_none());
+        assert!(!query.contains_key("missing"));
+        assert_eq!(query.get_all("missing").count(), 0);
+
+        let req = TestRequest::with_uri("/path").to_http_request();
+        let query = req.query();
+        assert!(query.is_empty());
+        assert!(query.get("tag").is_none());
+    }
+
+    #[actix_rt::test]
+    async fn test_query_params_cached() {
+        let mut req = TestRequest::with_uri("/?id=1").to_srv_request();
+
+        let first = req.query();
+        assert!(Rc::ptr_eq(&first.inner, &req.query().inner));
+        assert_eq!(first.get("id").unwrap(), "1");
+
+        req.head_mut().uri = Uri::from_static("/?id=2");
+        let rewritten = req.query();
+        assert!(!Rc::ptr_eq(&first.inner, &rewritten.inner));
+        assert_eq!(rewritten.get("id").unwrap(), "2");
+        assert_eq!(req.request().query().get("id").unwrap(), "2");
+
+        // handles taken before the rewrite keep their own view of the URI
+        assert_eq!(first.get("id").unwrap(), "1");
+    }
 }
@@ -0,0 +1,561 @@
+//! For selecting how query strings and URL encoded forms are deserialized, see [`QueryParser`].
+
+use serde::de::{
+    self,
+    value::{Error, StrDeserializer},
+    DeserializeOwned, IntoDeserializer as _, Visitor,
+};
+
+use super::query::decode;
+
+/// Maximum number of bracketed segments in a parameter name for [`QueryParser::Extended`].
+const MAX_DEPTH: usize = 5;
+
+/// Deserializer used by [`Query`](super::Query) and [`Form`](super::Form).
+///
+/// Selected with [`QueryConfig::parser`](super::QueryConfig::parser) and
+/// [`FormConfig::parser`](super::FormConfig::parser).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QueryParser {
+    /// Flat `name=value` pairs, deserialized with `serde_urlencoded`. This is the default.
+    Urlencoded,
+
+    /// Nested parameters with bracket syntax, like `filter[name]=x` for a field `name` of a
+    /// struct field `filter`, and `tag[0]=a&tag[1]=b` or `tag[]=a&tag[]=b` for sequences.
+    ///
+    /// Repeated names (`tag=a&tag=b`) also deserialize into sequences, and structs can use
+    /// `#[serde(flatten)]`; flattened fields receive their values as strings, so they should be
+    /// strings or types deserialized from strings.
+    ///
+    /// Names nested deeper than 5 brackets are rejected.
+    ///
+    /// ```
+    /// use actix_web::web::{Query, QueryConfig, QueryParser};
+    /// use serde::Deserialize;
+    ///
+    /// #[derive(Deserialize)]
+    /// struct Filter {
+    ///     name: String,
+    ///     min_age: u32,
+    /// }
+    ///
+    /// #[derive(Deserialize)]
+    /// struct Search {
+    ///     filter: Filter,
+    ///     sort: Vec<String>,
+    /// }
+    ///
+    /// async fn search(query: Query<Search>) -> String {
+    ///     format!("{} older than {}", query.filter.name, query.filter.min_age)
+    /// }
+    ///
+    /// // matches `/search?filter[name]=ferris&filter[min_age]=3&sort[]=name&sort[]=age`
+    /// let cfg = QueryConfig::default().parser(QueryParser::Extended);
+    /// ```
+    Extended,
+}
+
+impl Default for QueryParser {
+    fn default() -> Self {
+        QueryParser::Urlencoded
+    }
+}
+
+impl QueryParser {
+    /// Deserialize `T` from a URL encoded string.
+    pub(crate) fn deserialize<T: DeserializeOwned>(self, input: &str) -> Result<T, Error> {
+        match self {
+            QueryParser::Urlencoded => serde_urlencoded::from_str(input),
+            QueryParser::Extended => T::deserialize(Node::parse(input, MAX_DEPTH)?),
+        }
+    }
+}
+
+/// Parameters nested by their bracketed names.
+#[derive(Debug, PartialEq)]
+enum Node {
+    Leaf(String),
+    Map(Vec<(String, Node)>),
+    Seq(Vec<Node>),
+}
+
+impl Node {
+    fn parse(input: &str, max_depth: usize) -> Result<Node, Error> {
+        let mut root = Vec::new();
+
+        for pair in input.split('&').filter(|pair| !pair.is_empty()) {
+            let (name, value) = match pair.find('=') {
+                Some(idx) => (&pair[..idx], &pair[idx + 1..]),
+                None => (pair, ""),
+            };
+
+            let name = decode(name);
+            let path = split_name(&name);
+
+            if path.len() > max_depth + 1 {
+                return Err(de::Error::custom(format_args!(
+                    "parameter `{}` is nested deeper than {} levels",
+                    name, max_depth
+                )));
+            }
+
+            insert(&mut root, &path, decode(value).into_owned())?;
+        }
+
+        Ok(Node::Map(root))
+    }
+
+    fn unexpected(&self) -> de::Unexpected<'_> {
+        match self {
+            Node::Leaf(value) => de::Unexpected::Str(value),
+            Node::Map(_) => de::Unexpected::Map,
+            Node::Seq(_) => de::Unexpected::Seq,
+        }
+    }
+}
+
+/// Splits `a[b][c]` into `["a", "b", "c"]`. Anything after an unclosed bracket is part of the
+/// last segment.
+fn split_name(name: &str) -> Vec<&str> {
+    let (first, mut rest) = match name.find('[') {
+        Some(idx) if idx > 0 => (&name[..idx], &name[idx..]),
+        _ => return vec![name],
+    };
+
+    let mut path = vec![first];
+
+    while rest.starts_with('[') {
+        match rest.find(']') {
+            Some(end) => {
+                path.push(&rest[1..end]);
+                rest = &rest[end + 1..];
+            }
+            None => break,
+        }
+    }
+
+    if !rest.is_empty() {
+        // not bracket syntax after all
+        return vec![name];
+    }
+
+    path
+}
+
+fn insert(map: &mut Vec<(String, Node)>, path: &[&str], value: String) -> Result<(), Error> {
+    let (key, rest) = (path[0], &path[1..]);
+    let existing = map.iter_mut().find(|(name, _)| name == key);
+
+    match (existing, rest.is_empty()) {
+        (None, true) => map.push((key.to_owned(), Node::Leaf(value))),
+
+        (None, false) => {
+            let mut node = if rest[0].is_empty() {
+                Node::Seq(Vec::new())
+            } else {
+                Node::Map(Vec::new())
+            };
+            insert_nested(&mut node, key, rest, value)?;
+            map.push((key.to_owned(), node));
+        }
+
+        // repeated names form a sequence
+        (Some((_, node)), true) => match node {
+            Node::Leaf(_) => {
+                let first = std::mem::replace(node, Node::Seq(Vec::new()));
+                *node = Node::Seq(vec![first, Node::Leaf(value)]);
+            }
+            Node::Seq(items) => items.push(Node::Leaf(value)),
+            Node::Map(_) => return Err(conflict(key)),
+        },
+
+        (Some((_, node)), false) => insert_nested(node, key, rest, value)?,
+    }
+
+    Ok(())
+}
+
+fn insert_nested(
+    node: &mut Node,
+    key: &str,
+    path: &[&str],
+    value: String,
+) -> Result<(), Error> {
+    match node {
+        // `[]` appends a new item
+        Node::Seq(items) if path[0].is_empty() => {
+            if path.len() == 1 {
+                items.push(Node::Leaf(value));
+            } else {
+                let mut item = Vec::new();
+                insert(&mut item, &path[1..], value)?;
+                items.push(Node::Map(item));
+            }
+
+            Ok(())
+        }
+        Node::Map(entries) if !path[0].is_empty() => insert(entries, path, value),
+        _ => Err(conflict(key)),
+    }
+}
+
+fn conflict(key: &str) -> Error {
+    de::Error::custom(format_args!(
+        "parameter `{}` is used with conflicting types",
+        key
+    ))
+}
+
+macro_rules! deserialize_parsed {
+    ($($method:ident => $visit:ident,)*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+                match self {
+                    Node::Leaf(value) => match value.parse() {
+                        Ok(parsed) => visitor.$visit(parsed),
+                        Err(_) => Err(de::Error::invalid_value(
+                            de::Unexpected::Str(&value),
+                            &visitor,
+                        )),
+                    },
+                    node => Err(de::Error::invalid_type(node.unexpected(), &visitor)),
+                }
+            }
+        )*
+    };
+}
+
+impl<'de> de::Deserializer<'de> for Node {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self {
+            Node::Leaf(value) => visitor.visit_string(value),
+            Node::Map(entries) => visitor.visit_map(MapAccess::new(entries)),
+            Node::Seq(items) => visitor.visit_seq(SeqAccess(items.into_iter())),
+        }
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self {
+            Node::Leaf(value) => {
+                visitor.visit_seq(SeqAccess(vec![Node::Leaf(value)].into_iter()))
+            }
+            Node::Seq(items) => visitor.visit_seq(SeqAccess(items.into_iter())),
+            Node::Map(entries) => {
+                // indexed items, e.g. `a[1]=y&a[0]=x`
+                let mut indexed = Vec::with_capacity(entries.len());
+                for (key, node) in entries {
+                    match key.parse::<usize>() {
+                        Ok(idx) => indexed.push((idx, node)),
+                        Err(_) => {
+                            return Err(de::Error::invalid_type(de::Unexpected::Map, &visitor))
+                        }
+                    }
+                }
+
+                indexed.sort_by_key(|(idx, _)| *idx);
+                let items = indexed
+                    .into_iter()
+                    .map(|(_, node)| node)
+                    .collect::<Vec<_>>();
+                visitor.visit_seq(SeqAccess(items.into_iter()))
+            }
+        }
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(
+        self,
+        _: usize,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        self,
+        _: &'static str,
+        _: usize,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_map<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self {
+            Node::Map(entries) => visitor.visit_map(MapAccess::new(entries)),
+            node => Err(de::Error::invalid_type(node.unexpected(), &visitor)),
+        }
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _: &'static str,
+        _: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.deserialize_map(visitor)
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _: &'static str,
+        _: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        match self {
+            Node::Leaf(value) => {
+                let variant: StrDeserializer<'_, Error> = value.as_str().into_deserializer();
+                visitor.visit_enum(variant)
+            }
+            node => Err(de::Error::invalid_type(node.unexpected(), &visitor)),
+        }
+    }
+
+    fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(
+        self,
+        _: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_unit()
+    }
+
+    deserialize_parsed! {
+        deserialize_bool => visit_bool,
+        deserialize_i8 => visit_i8,
+        deserialize_i16 => visit_i16,
+        deserialize_i32 => visit_i32,
+        deserialize_i64 => visit_i64,
+        deserialize_u8 => visit_u8,
+        deserialize_u16 => visit_u16,
+        deserialize_u32 => visit_u32,
+        deserialize_u64 => visit_u64,
+        deserialize_f32 => visit_f32,
+        deserialize_f64 => visit_f64,
+        deserialize_char => visit_char,
+    }
+
+    serde::forward_to_deserialize_any! {
+        str string bytes byte_buf identifier
+    }
+}
+
+struct MapAccess {
+    entries: std::vec::IntoIter<(String, Node)>,
+    value: Option<Node>,
+}
+
+impl MapAccess {
+    fn new(entries: Vec<(String, Node)>) -> Self {
+        MapAccess {
+            entries: entries.into_iter(),
+            value: None,
+        }
+    }
+}
+
+impl<'de> de::MapAccess<'de> for MapAccess {
+    type Error = Error;
+
+    fn next_key_seed<K: de::DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, Error> {
+        match self.entries.next() {
+            Some((key, value)) => {
+                self.value = Some(value);
+                seed.deserialize(Node::Leaf(key)).map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V: de::DeserializeSeed<'de>>(
+        &mut self,
+        seed: V,
+    ) -> Result<V::Value, Error> {
+        let value = self
+            .value
+            .take()
+            .expect("next_value_seed called before next_key_seed");
+        seed.deserialize(value)
+    }
+}
+
+struct SeqAccess(std::vec::IntoIter<Node>);
+
+impl<'de> de::SeqAccess<'de> for SeqAccess {
+    type Error = Error;
+
+    fn next_element_seed<T: de::DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>, Error> {
+        self.0.next().map(|node| seed.deserialize(node)).transpose()
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.0.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use serde::Deserialize;
+
+    use super::*;
+
+    fn extended<T: DeserializeOwned>(input: &str) -> Result<T, Error> {
+        QueryParser::Extended.deserialize(input)
+    }
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Filter {
+        name: String,
+        min_age: Option<u32>,
+    }
+
+    #[test]
+    fn test_nested() {
+        #[derive(Debug, PartialEq, Deserialize)]
+        struct Search {
+            filter: Filter,
+            page: u32,
+        }
+
+        let search: Search =
+            extended("filter[name]=fer%20ris&page=2&filter[min_age]=3").unwrap();
+        assert_eq!(
+            search,
+            Search {
+                filter: Filter {
+                    name: "fer ris".to_owned(),
+                    min_age: Some(3),
+                },
+                page: 2,
+            }
+        );
+
+        let map: HashMap<String, HashMap<String, String>> = extended("a[b]=1&a[c]=2").unwrap();
+        assert_eq!(map["a"]["c"], "2");
+
+        // flat queries work as with the default parser
+        let filter: Filter = extended("name=x").unwrap();
+        assert_eq!(filter.min_age, None);
+
+        assert!(extended::<Search>("filter[name]=x&page=two").is_err());
+        assert!(extended::<Search>("filter=x&page=1").is_err());
+        assert!(extended::<Search>("filter=x&filter[name]=y&page=1").is_err());
+    }
+
+    #[test]
+    fn test_flatten() {
+        #[derive(Debug, PartialEq, Deserialize)]
+        struct Pagination {
+            cursor: String,
+        }
+
+        #[derive(Debug, PartialEq, Deserialize)]
+        struct Listing {
+            #[serde(flatten)]
+            pagination: Pagination,
+            #[serde(flatten)]
+            extra: HashMap<String, String>,
+        }
+
+        let listing: Listing = extended("cursor=abc&color=red").unwrap();
+        assert_eq!(listing.pagination.cursor, "abc");
+        assert_eq!(listing.extra["color"], "red");
+
+        #[derive(Debug, PartialEq, Deserialize)]
+        struct Nested {
+            #[serde(flatten)]
+            pagination: Pagination,
+            filter: HashMap<String, String>,
+        }
+
+        let nested: Nested = extended("cursor=abc&filter[name]=x").unwrap();
+        assert_eq!(nested.filter["name"], "x");
+    }
+
+    #[test]
+    fn test_sequences() {
+        #[derive(Debug, PartialEq, Deserialize)]
+        struct Tags {
+            tag: Vec<String>,
+        }
+
+        for query in &[
+            "tag[0]=a&tag[1]=b&tag[2]=c",
+            "tag[2]=c&tag[0]=a&tag[1]=b",
+            "tag[]=a&tag[]=b&tag[]=c",
+            "tag=a&tag=b&tag=c",
+        ] {
+            let tags: Tags = extended(query).unwrap();
+            assert_eq!(tags.tag, vec!["a", "b", "c"], "{}", query);
+        }
+
+        let tags: Tags = extended("tag=a").unwrap();
+        assert_eq!(tags.tag, vec!["a"]);
+
+        #[derive(Debug, PartialEq, Deserialize)]
+        struct Filters {
+            filters: Vec<Filter>,
+        }
+
+        let filters: Filters =
+            extended("filters[0][name]=a&filters[1][name]=b&filters[1][min_age]=5").unwrap();
+        assert_eq!(filters.filters[1].min_age, Some(5));
+
+        let filters: Filters = extended("filters[][name]=a&filters[][name]=b").unwrap();
+        assert_eq!(filters.filters.len(), 2);
+
+        assert!(extended::<Tags>("tag[x]=a").is_err());
+    }
+
+    #[test]
+    fn test_depth_limit() {
+        type Deep = HashMap<String, HashMap<String, HashMap<String, HashMap<String, String>>>>;
+
+        let deep: Deep = extended("a[b][c][d]=1").unwrap();
+        assert_eq!(deep["a"]["b"]["c"]["d"], "1");
+
+        let max = format!("a{}=1", "[b]".repeat(MAX_DEPTH));
+        assert!(Node::parse(&max, MAX_DEPTH).is_ok());
+
+        let too_deep = format!("a{}=1", "[b]".repeat(MAX_DEPTH + 1));
+        let err = Node::parse(&too_deep, MAX_DEPTH).unwrap_err();
+        assert!(err.to_string().contains("nested deeper than 5 levels"));
+    }
+
+    #[test]
+    fn test_split_name() {
+        assert_eq!(split_name("a"), vec!["a"]);
+        assert_eq!(split_name("a[b][]"), vec!["a", "b", ""]);
+        assert_eq!(split_name("a[b"), vec!["a[b"]);
+        assert_eq!(split_name("a[b]c"), vec!["a[b]c"]);
+        assert_eq!(split_name("[a]"), vec!["[a]"]);
+    }
+}
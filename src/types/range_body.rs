@@ -0,0 +1,227 @@
+//! For byte-range responses, see [`RangeBody`].
+
+use std::ops::Range;
+
+use actix_http::body::{Body, MessageBody};
+use http_range::{HttpRange, HttpRangeParseError};
+
+use crate::{
+    http::{
+        header::{ACCEPT_RANGES, CONTENT_RANGE, RANGE},
+        Method, StatusCode,
+    },
+    HttpRequest, HttpResponse, Responder,
+};
+
+/// Responder that serves byte ranges of a representation as requested by the `Range` header.
+///
+/// The body factory is called with the byte range to send, allowing any storage backend to
+/// produce just the requested part of the representation.
+///
+/// - When no valid `Range` header is present, the full representation is sent with `200 OK`.
+/// - When a single satisfiable range is requested, that range is sent with
+///   `206 Partial Content` and the matching `Content-Range` header.
+/// - When none of the requested ranges are satisfiable, `416 Range Not Satisfiable` is returned
+///   with a `Content-Range: bytes */{len}` header.
+///
+/// Requests for multiple ranges are answered with the full representation since
+/// `multipart/byteranges` responses are not supported. Only `GET` and `HEAD` requests are
+/// considered for range handling.
+///
+/// ```
+/// use actix_web::{get, web, Responder};
+///
+/// static DATA: &[u8] = b"Hello, World!";
+///
+/// #[get("/blob")]
+/// async fn blob() -> impl Responder {
+///     web::RangeBody::new(DATA.len() as u64, |range| {
+///         web::Bytes::from_static(&DATA[range.start as usize..range.end as usize])
+///     })
+/// }
+/// ```
+pub struct RangeBody<F> {
+    len: u64,
+    factory: F,
+}
+
+impl<F, B> RangeBody<F>
+where
+    F: FnOnce(Range<u64>) -> B,
+    B: MessageBody + Unpin + 'static,
+{
+    /// Create new range responder for a representation of `len` bytes.
+    pub fn new(len: u64, factory: F) -> Self {
+        RangeBody { len, factory }
+    }
+}
+
+impl<F, B> Responder for RangeBody<F>
+where
+    F: FnOnce(Range<u64>) -> B,
+    B: MessageBody + Unpin + 'static,
+{
+    fn respond_to(self, req: &HttpRequest) -> HttpResponse {
+        let RangeBody { len, factory } = self;
+
+        let ranges = match req.headers().get(RANGE) {
+            Some(header) if matches!(*req.method(), Method::GET | Method::HEAD) => {
+                HttpRange::parse_bytes(header.as_bytes(), len)
+            }
+            _ => Ok(Vec::new()),
+        };
+
+        match ranges.as_ref().map(Vec::as_slice) {
+            Ok([range]) if range.length > 0 => {
+                let end = range.start + range.length;
+
+                HttpResponse::build(StatusCode::PARTIAL_CONTENT)
+                    .insert_header((ACCEPT_RANGES, "bytes"))
+                    .insert_header((
+                        CONTENT_RANGE,
+                        format!("bytes {}-{}/{}", range.start, end - 1, len),
+                    ))
+                    .no_chunking(range.length)
+                    .body(Body::from_message(factory(range.start..end)))
+            }
+
+            Ok([_]) | Err(HttpRangeParseError::NoOverlap) => {
+                HttpResponse::build(StatusCode::RANGE_NOT_SATISFIABLE)
+                    .insert_header((ACCEPT_RANGES, "bytes"))
+                    .insert_header((CONTENT_RANGE, format!("bytes */{}", len)))
+                    .finish()
+            }
+
+            // no range, multiple ranges or invalid range syntax
+            _ => HttpResponse::Ok()
+                .insert_header((ACCEPT_RANGES, "bytes"))
+                .no_chunking(len)
+                .body(Body::from_message(factory(0..len))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_service::Service;
+    use bytes::Bytes;
+
+    use super::*;
+    use crate::{
+        http::header::CONTENT_LENGTH,
+        test::{self, TestRequest},
+        web, App,
+    };
+
+    static DATA: &[u8] = b"0123456789";
+
+    async fn request(range: Option<&str>) -> (StatusCode, Option<String>, Bytes) {
+        let srv = test::init_service(App::new().route(
+            "/",
+            web::get().to(|| async {
+                RangeBody::new(DATA.len() as u64, |range| {
+                    Bytes::from_static(&DATA[range.start as usize..range.end as usize])
+                })
+            }),
+        ))
+        .await;
+
+        let mut req = TestRequest::default();
+        if let Some(range) = range {
+            req = req.insert_header((RANGE, range));
+        }
+
+        let res = srv.call(req.to_request()).await.unwrap();
+        let status = res.status();
+        let content_range = res
+            .headers()
+            .get(CONTENT_RANGE)
+            .map(|v| v.to_str().unwrap().to_owned());
+
+        let content_length = res
+            .headers()
+            .get(CONTENT_LENGTH)
+            .map(|v| v.to_str().unwrap().parse::<usize>().unwrap());
+
+        let body = test::read_body(res).await;
+        if let Some(len) = content_length {
+            assert_eq!(body.len(), len);
+        }
+
+        (status, content_range, body)
+    }
+
+    #[actix_rt::test]
+    async fn test_no_range() {
+        let (status, content_range, body) = request(None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(content_range, None);
+        assert_eq!(body, DATA);
+    }
+
+    #[actix_rt::test]
+    async fn test_single_range() {
+        let (status, content_range, body) = request(Some("bytes=2-5")).await;
+        assert_eq!(status, StatusCode::PARTIAL_CONTENT);
+        assert_eq!(content_range.as_deref(), Some("bytes 2-5/10"));
+        assert_eq!(body, &b"2345"[..]);
+
+        // end past representation length is truncated
+        let (status, content_range, body) = request(Some("bytes=8-20")).await;
+        assert_eq!(status, StatusCode::PARTIAL_CONTENT);
+        assert_eq!(content_range.as_deref(), Some("bytes 8-9/10"));
+        assert_eq!(body, &b"89"[..]);
+    }
+
+    #[actix_rt::test]
+    async fn test_open_ended_range() {
+        let (status, content_range, body) = request(Some("bytes=7-")).await;
+        assert_eq!(status, StatusCode::PARTIAL_CONTENT);
+        assert_eq!(content_range.as_deref(), Some("bytes 7-9/10"));
+        assert_eq!(body, &b"789"[..]);
+    }
+
+    #[actix_rt::test]
+    async fn test_suffix_range() {
+        let (status, content_range, body) = request(Some("bytes=-3")).await;
+        assert_eq!(status, StatusCode::PARTIAL_CONTENT);
+        assert_eq!(content_range.as_deref(), Some("bytes 7-9/10"));
+        assert_eq!(body, &b"789"[..]);
+
+        // suffix longer than representation selects everything
+        let (status, content_range, body) = request(Some("bytes=-20")).await;
+        assert_eq!(status, StatusCode::PARTIAL_CONTENT);
+        assert_eq!(content_range.as_deref(), Some("bytes 0-9/10"));
+        assert_eq!(body, DATA);
+    }
+
+    #[actix_rt::test]
+    async fn test_unsatisfiable_range() {
+        let (status, content_range, body) = request(Some("bytes=10-")).await;
+        assert_eq!(status, StatusCode::RANGE_NOT_SATISFIABLE);
+        assert_eq!(content_range.as_deref(), Some("bytes */10"));
+        assert!(body.is_empty());
+
+        let (status, content_range, _) = request(Some("bytes=-0")).await;
+        assert_eq!(status, StatusCode::RANGE_NOT_SATISFIABLE);
+        assert_eq!(content_range.as_deref(), Some("bytes */10"));
+    }
+
+    #[actix_rt::test]
+    async fn test_invalid_range() {
+        for range in &["bytes=5-2", "bytes=abc", "items=0-5", "bytes=--5"] {
+            let (status, content_range, body) = request(Some(range)).await;
+            assert_eq!(status, StatusCode::OK, "range: {}", range);
+            assert_eq!(content_range, None);
+            assert_eq!(body, DATA);
+        }
+    }
+
+    #[actix_rt::test]
+    async fn test_multiple_ranges() {
+        let (status, content_range, body) = request(Some("bytes=0-1,5-6")).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(content_range, None);
+        assert_eq!(body, DATA);
+    }
+}
@@ -0,0 +1,258 @@
+//! For streaming `AsyncRead` bodies, see [`ReadStream`].
+
+use std::{
+    cmp, io,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use actix_codec::{poll_read_buf, AsyncRead};
+use actix_http::body::{Body, BodySize, MessageBody};
+use bytes::{BufMut as _, Bytes, BytesMut};
+use futures_core::ready;
+
+use crate::{Error, HttpRequest, HttpResponse, Responder};
+
+const DEFAULT_CHUNK_SIZE: usize = 65_536;
+
+/// Response body that streams from any [`AsyncRead`].
+///
+/// When the length is known, the body is sent with `Content-Length` framing. Reaching EOF before
+/// `len` bytes were read results in a body error. Data past `len` is not read.
+///
+/// When the length is unknown, appropriate streaming transfer encoding is used and the body ends
+/// on EOF.
+///
+/// ```
+/// use actix_web::{get, web, Responder};
+///
+/// #[get("/")]
+/// async fn index() -> impl Responder {
+///     let data: &'static [u8] = b"hello world";
+///     web::ReadStream::new(data, Some(data.len() as u64))
+/// }
+/// ```
+pub struct ReadStream<R> {
+    reader: R,
+    remaining: Option<u64>,
+    size: BodySize,
+    chunk_size: usize,
+    buf: BytesMut,
+}
+
+impl<R> ReadStream<R>
+where
+    R: AsyncRead + Unpin,
+{
+    /// Create new body that reads from `reader`, with an optional known length.
+    pub fn new(reader: R, len: Option<u64>) -> Self {
+        ReadStream {
+            reader,
+            remaining: len,
+            size: len.map_or(BodySize::Stream, BodySize::Sized),
+            chunk_size: DEFAULT_CHUNK_SIZE,
+            buf: BytesMut::new(),
+        }
+    }
+
+    /// Set maximum size of chunks read from the reader. The default is 64kB.
+    pub fn chunk_size(mut self, chunk_size: usize) -> Self {
+        assert!(chunk_size > 0, "chunk size must be greater than zero");
+        self.chunk_size = chunk_size;
+        self
+    }
+}
+
+impl<R> MessageBody for ReadStream<R>
+where
+    R: AsyncRead + Unpin,
+{
+    fn size(&self) -> BodySize {
+        self.size
+    }
+
+    fn poll_next(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Bytes, Error>>> {
+        let this = self.get_mut();
+
+        let max = match this.remaining {
+            Some(0) => return Poll::Ready(None),
+            Some(remaining) => cmp::min(remaining, this.chunk_size as u64) as usize,
+            None => this.chunk_size,
+        };
+
+        // the buffer is reused, chunks are split off with only the bytes that were read
+        this.buf.reserve(max);
+
+        let n = match ready!(poll_read_buf(
+            Pin::new(&mut this.reader),
+            cx,
+            &mut (&mut this.buf).limit(max),
+        )) {
+            Ok(n) => n,
+            Err(err) => {
+                this.remaining = Some(0);
+                return Poll::Ready(Some(Err(err.into())));
+            }
+        };
+
+        if n == 0 {
+            return match this.remaining.take() {
+                None => Poll::Ready(None),
+                Some(_) => {
+                    this.remaining = Some(0);
+                    Poll::Ready(Some(Err(io::Error::new(
+                        io::ErrorKind::UnexpectedEof,
+                        "reader ended before expected length",
+                    )
+                    .into())))
+                }
+            };
+        }
+
+        if let Some(ref mut remaining) = this.remaining {
+            *remaining -= n as u64;
+        }
+
+        Poll::Ready(Some(Ok(this.buf.split().freeze())))
+    }
+}
+
+impl<R> Responder for ReadStream<R>
+where
+    R: AsyncRead + Unpin + 'static,
+{
+    fn respond_to(self, _: &HttpRequest) -> HttpResponse {
+        HttpResponse::Ok()
+            .content_type(mime::APPLICATION_OCTET_STREAM)
+            .body(Body::from_message(self))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use actix_codec::ReadBuf;
+    use actix_service::Service;
+    use futures_util::future::poll_fn;
+
+    use super::*;
+    use crate::{
+        http::{header, StatusCode},
+        test::{self, TestRequest},
+        web, App,
+    };
+
+    /// Reader that yields `data` once and then fails.
+    struct FailingReader {
+        data: Option<&'static [u8]>,
+    }
+
+    impl AsyncRead for FailingReader {
+        fn poll_read(
+            mut self: Pin<&mut Self>,
+            _: &mut Context<'_>,
+            buf: &mut ReadBuf<'_>,
+        ) -> Poll<io::Result<()>> {
+            match self.data.take() {
+                Some(data) => {
+                    buf.put_slice(data);
+                    Poll::Ready(Ok(()))
+                }
+                None => Poll::Ready(Err(io::Error::new(io::ErrorKind::Other, "broken"))),
+            }
+        }
+    }
+
+    async fn read_all<B: MessageBody + Unpin>(mut body: B) -> Result<BytesMut, Error> {
+        let mut buf = BytesMut::new();
+
+        while let Some(chunk) = poll_fn(|cx| Pin::new(&mut body).poll_next(cx)).await {
+            buf.extend_from_slice(&chunk?);
+        }
+
+        Ok(buf)
+    }
+
+    #[actix_rt::test]
+    async fn test_sized() {
+        let body = ReadStream::new(Cursor::new(b"hello world".to_vec()), Some(5)).chunk_size(2);
+        assert_eq!(body.size(), BodySize::Sized(5));
+        assert_eq!(read_all(body).await.unwrap(), &b"hello"[..]);
+
+        let body = ReadStream::new(Cursor::new(b"hello".to_vec()), Some(10));
+        assert!(read_all(body).await.is_err());
+    }
+
+    #[actix_rt::test]
+    async fn test_unsized() {
+        let body = ReadStream::new(Cursor::new(b"hello world".to_vec()), None).chunk_size(3);
+        assert_eq!(body.size(), BodySize::Stream);
+        assert_eq!(read_all(body).await.unwrap(), &b"hello world"[..]);
+    }
+
+    #[actix_rt::test]
+    async fn test_error_midway() {
+        let mut body = ReadStream::new(
+            FailingReader {
+                data: Some(b"partial"),
+            },
+            None,
+        );
+
+        let chunk = poll_fn(|cx| Pin::new(&mut body).poll_next(cx)).await;
+        assert_eq!(chunk.unwrap().unwrap(), Bytes::from_static(b"partial"));
+
+        let chunk = poll_fn(|cx| Pin::new(&mut body).poll_next(cx)).await;
+        assert!(chunk.unwrap().is_err());
+
+        let chunk = poll_fn(|cx| Pin::new(&mut body).poll_next(cx)).await;
+        assert!(chunk.is_none());
+    }
+
+    #[actix_rt::test]
+    async fn test_stream_file() {
+        let data = (0..3 * 1024 * 1024)
+            .map(|i| (i % 251) as u8)
+            .collect::<Vec<_>>();
+
+        let path = std::env::temp_dir()
+            .join(format!("actix-web-read-stream-{}.bin", std::process::id()));
+        std::fs::write(&path, &data).unwrap();
+
+        let file_path = path.clone();
+        let srv = test::init_service(App::new().route(
+            "/",
+            web::get().to(move || {
+                let file_path = file_path.clone();
+                async move {
+                    let file = tokio::fs::File::open(&file_path).await.unwrap();
+                    let len = file.metadata().await.unwrap().len();
+                    ReadStream::new(file, Some(len))
+                }
+            }),
+        ))
+        .await;
+
+        let req = TestRequest::default().to_request();
+        let res = srv.call(req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(
+            res.headers().get(header::CONTENT_TYPE).unwrap(),
+            "application/octet-stream"
+        );
+        assert_eq!(
+            res.response().body().size(),
+            BodySize::Sized(data.len() as u64)
+        );
+
+        let body = test::read_body(res).await;
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(body.len(), data.len());
+        assert!(body == data);
+    }
+}
@@ -0,0 +1,199 @@
+//! For redirect responses and services, see [`Redirect`].
+
+use std::borrow::Cow;
+
+use futures_util::future::ready;
+use percent_encoding::{utf8_percent_encode, AsciiSet, CONTROLS};
+
+use crate::{
+    dev::{AppService, HttpServiceFactory},
+    http::{header::LOCATION, StatusCode},
+    HttpRequest, HttpResponse, Resource, Responder, Route,
+};
+
+/// Characters that are not allowed in a URI reference and have to be percent-encoded in the
+/// `Location` header. Non-ASCII characters are always encoded; existing escapes are preserved.
+const LOCATION_ENCODE_SET: &AsciiSet = &CONTROLS
+    .add(b' ')
+    .add(b'"')
+    .add(b'<')
+    .add(b'>')
+    .add(b'\\')
+    .add(b'^')
+    .add(b'`')
+    .add(b'{')
+    .add(b'|')
+    .add(b'}');
+
+/// Redirect to another location, as a responder or as a service.
+///
+/// The target can be a relative path or an absolute URL; characters not allowed in a URI are
+/// percent-encoded. Responds with `307 Temporary Redirect` unless another status is chosen, which
+/// preserves the request method and body.
+///
+/// # Examples
+/// As a responder:
+/// ```
+/// use actix_web::{get, web, Responder};
+///
+/// #[get("/login")]
+/// async fn login() -> impl Responder {
+///     web::Redirect::to("/sign-in").see_other()
+/// }
+/// ```
+///
+/// As a service, redirecting requests for one path with any method:
+/// ```
+/// use actix_web::{web, App};
+///
+/// let app = App::new()
+///     .service(web::Redirect::new("/old", "/new").permanent())
+///     .service(web::Redirect::new("/docs", "https://docs.rs/actix-web"));
+/// ```
+#[derive(Debug, Clone)]
+pub struct Redirect {
+    from: Cow<'static, str>,
+    to: Cow<'static, str>,
+    status_code: StatusCode,
+}
+
+impl Redirect {
+    /// Create redirect service from path `from` to location `to`.
+    ///
+    /// `from` is a resource pattern, see [`web::resource`](crate::web::resource()).
+    pub fn new(from: impl Into<Cow<'static, str>>, to: impl Into<Cow<'static, str>>) -> Self {
+        Redirect {
+            from: from.into(),
+            to: to.into(),
+            status_code: StatusCode::TEMPORARY_REDIRECT,
+        }
+    }
+
+    /// Create redirect responder to location `to`.
+    pub fn to(to: impl Into<Cow<'static, str>>) -> Self {
+        Redirect::new("/", to)
+    }
+
+    /// Use `308 Permanent Redirect`, which preserves the request method.
+    pub fn permanent(self) -> Self {
+        self.using_status_code(StatusCode::PERMANENT_REDIRECT)
+    }
+
+    /// Use `307 Temporary Redirect`, which preserves the request method. This is the default.
+    pub fn temporary(self) -> Self {
+        self.using_status_code(StatusCode::TEMPORARY_REDIRECT)
+    }
+
+    /// Use `303 See Other`, which makes clients follow the redirect with a `GET` request.
+    pub fn see_other(self) -> Self {
+        self.using_status_code(StatusCode::SEE_OTHER)
+    }
+
+    /// Use a custom status code, normally a `3xx` redirection status.
+    pub fn using_status_code(mut self, status_code: StatusCode) -> Self {
+        self.status_code = status_code;
+        self
+    }
+}
+
+impl Responder for Redirect {
+    fn respond_to(self, _: &HttpRequest) -> HttpResponse {
+        let location = utf8_percent_encode(&self.to, LOCATION_ENCODE_SET).to_string();
+
+        HttpResponse::build(self.status_code)
+            .insert_header((LOCATION, location))
+            .finish()
+    }
+}
+
+impl HttpServiceFactory for Redirect {
+    fn register(self, config: &mut AppService) {
+        let redirect = self.clone();
+        let route = Route::new().to(move || ready(redirect.clone()));
+
+        Resource::new(self.from.into_owned())
+            .route(route)
+            .register(config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        http::Method,
+        test::{call_service, init_service, TestRequest},
+        web, App,
+    };
+
+    #[actix_rt::test]
+    async fn test_redirect_responder() {
+        let srv = init_service(
+            App::new()
+                .route(
+                    "/temporary",
+                    web::get().to(|| async { Redirect::to("/new") }),
+                )
+                .route(
+                    "/see-other",
+                    web::post().to(|| async { Redirect::to("/new").see_other() }),
+                )
+                .route(
+                    "/absolute",
+                    web::get().to(|| async {
+                        Redirect::to("https://example.com/a b?q=ü#top").permanent()
+                    }),
+                ),
+        )
+        .await;
+
+        let req = TestRequest::get().uri("/temporary").to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::TEMPORARY_REDIRECT);
+        assert_eq!(res.headers().get(LOCATION).unwrap(), "/new");
+
+        let req = TestRequest::post().uri("/see-other").to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::SEE_OTHER);
+        assert_eq!(res.headers().get(LOCATION).unwrap(), "/new");
+
+        let req = TestRequest::get().uri("/absolute").to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::PERMANENT_REDIRECT);
+        assert_eq!(
+            res.headers().get(LOCATION).unwrap(),
+            "https://example.com/a%20b?q=%C3%BC#top"
+        );
+    }
+
+    #[actix_rt::test]
+    async fn test_redirect_service() {
+        let srv = init_service(
+            App::new().service(Redirect::new("/old", "/new")).service(
+                Redirect::new("/moved", "/new%20path")
+                    .using_status_code(StatusCode::MOVED_PERMANENTLY),
+            ),
+        )
+        .await;
+
+        for method in &[Method::GET, Method::POST, Method::DELETE] {
+            let req = TestRequest::default()
+                .method(method.clone())
+                .uri("/old")
+                .to_request();
+            let res = call_service(&srv, req).await;
+            assert_eq!(res.status(), StatusCode::TEMPORARY_REDIRECT);
+            assert_eq!(res.headers().get(LOCATION).unwrap(), "/new");
+        }
+
+        // existing escapes are kept
+        let req = TestRequest::get().uri("/moved").to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::MOVED_PERMANENTLY);
+        assert_eq!(res.headers().get(LOCATION).unwrap(), "/new%20path");
+
+        let req = TestRequest::get().uri("/old/more").to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+    }
+}
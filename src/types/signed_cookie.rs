@@ -0,0 +1,508 @@
+//! Signed cookies, for values that the client can read but not modify.
+//!
+//! See [`SignedCookies`] for usage.
+
+use std::{
+    fmt, ops,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use actix_http::{
+    cookie::Cookie,
+    error::{Error, ErrorInternalServerError, InternalError},
+    http::StatusCode,
+};
+use derive_more::{Display, Error};
+use futures_util::future::{ready, Ready};
+use hmac::{Hmac, Mac, NewMac};
+use rand::RngCore as _;
+use serde::{de::DeserializeOwned, Serialize};
+use sha2::Sha256;
+
+use crate::{dev::Payload, FromRequest, HttpMessage as _, HttpRequest, ResponseError};
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Minimum length of a signing key in bytes.
+const MIN_KEY_LEN: usize = 32;
+
+/// Secret key used to sign cookies.
+#[derive(Clone)]
+pub struct Key(Vec<u8>);
+
+impl Key {
+    /// Create a key from secret bytes, e.g. read from configuration.
+    ///
+    /// # Panics
+    /// Panics if `bytes` is shorter than 32 bytes.
+    pub fn from_bytes(bytes: &[u8]) -> Self {
+        assert!(
+            bytes.len() >= MIN_KEY_LEN,
+            "signing key must be at least {} bytes long",
+            MIN_KEY_LEN
+        );
+
+        Key(bytes.to_vec())
+    }
+
+    /// Generate a random 64 byte key.
+    ///
+    /// Cookies signed with a generated key can not be verified after a restart, unless the key
+    /// is stored.
+    pub fn generate() -> Self {
+        let mut bytes = vec![0; 64];
+        rand::thread_rng().fill_bytes(&mut bytes);
+        Key(bytes)
+    }
+
+    /// Returns the key bytes.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+
+    fn mac(&self, data: &[u8]) -> HmacSha256 {
+        let mut mac = HmacSha256::new_varkey(&self.0).expect("HMAC accepts any key length");
+        mac.update(data);
+        mac
+    }
+}
+
+impl fmt::Debug for Key {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Key(..)")
+    }
+}
+
+/// Signs and verifies cookies with HMAC-SHA256, and configures the [`SignedCookie`] extractor.
+///
+/// A signed cookie holds a JSON encoded value, the time it was signed and a signature over both
+/// and the cookie name. The value is not encrypted, so it should not contain secrets.
+///
+/// Cookies are signed with the primary key and verified with it or any fallback key, so keys can
+/// be rotated without invalidating cookies issued with the previous key: add the old key as a
+/// fallback, make the new key primary, and remove the fallback once old cookies have expired.
+///
+/// Register it as app data to use the extractor:
+///
+/// ```
+/// use std::time::Duration;
+/// use actix_web::{web, App, HttpResponse};
+/// use actix_web::web::signed_cookie::{Key, SignedCookie, SignedCookies};
+///
+/// async fn login(cookies: web::Data<SignedCookies>) -> HttpResponse {
+///     let cookie = cookies.sign("user", &42u64).unwrap();
+///     HttpResponse::Ok().cookie(cookie).finish()
+/// }
+///
+/// async fn profile(user: SignedCookie<u64>) -> String {
+///     format!("user #{}", *user)
+/// }
+///
+/// let cookies = SignedCookies::new(Key::generate())
+///     .cookie_name("user")
+///     .max_age(Duration::from_secs(24 * 60 * 60));
+///
+/// App::new()
+///     .app_data(web::Data::new(cookies))
+///     .route("/login", web::post().to(login))
+///     .route("/profile", web::get().to(profile));
+/// ```
+#[derive(Debug, Clone)]
+pub struct SignedCookies {
+    keys: Vec<Key>,
+    cookie_name: String,
+    max_age: Option<Duration>,
+    error_status: StatusCode,
+}
+
+impl SignedCookies {
+    /// Create a signer using `key` as primary key.
+    pub fn new(key: Key) -> Self {
+        SignedCookies {
+            keys: vec![key],
+            cookie_name: "signed".to_owned(),
+            max_age: None,
+            error_status: StatusCode::UNAUTHORIZED,
+        }
+    }
+
+    /// Add a key that is only used to verify cookies, e.g. the previous primary key.
+    pub fn fallback_key(mut self, key: Key) -> Self {
+        self.keys.push(key);
+        self
+    }
+
+    /// Set the name of the cookie read by the [`SignedCookie`] extractor. By default it is
+    /// `signed`.
+    pub fn cookie_name(mut self, name: impl Into<String>) -> Self {
+        self.cookie_name = name.into();
+        self
+    }
+
+    /// Set how long a signed value stays valid. Signed cookies also get this `Max-Age`.
+    ///
+    /// By default signed values do not expire.
+    pub fn max_age(mut self, max_age: Duration) -> Self {
+        self.max_age = Some(max_age);
+        self
+    }
+
+    /// Set the status of responses to requests whose cookie is missing, tampered with or
+    /// expired. By default it is `401 Unauthorized`.
+    pub fn error_status(mut self, status: StatusCode) -> Self {
+        self.error_status = status;
+        self
+    }
+
+    /// Create a cookie holding `value`, signed with the primary key.
+    ///
+    /// The cookie is `HttpOnly` with path `/`; other attributes can be set on the returned cookie.
+    /// Fails if `value` can not be serialized to JSON.
+    pub fn sign<T: Serialize>(
+        &self,
+        name: impl Into<String>,
+        value: &T,
+    ) -> Result<Cookie<'static>, serde_json::Error> {
+        self.sign_at(name.into(), value, SystemTime::now())
+    }
+
+    fn sign_at<T: Serialize>(
+        &self,
+        name: String,
+        value: &T,
+        signed_at: SystemTime,
+    ) -> Result<Cookie<'static>, serde_json::Error> {
+        let payload =
+            base64::encode_config(serde_json::to_vec(value)?, base64::URL_SAFE_NO_PAD);
+        let timestamp = signed_at
+            .duration_since(UNIX_EPOCH)
+            .map(|since| since.as_secs())
+            .unwrap_or(0);
+
+        let signed = format!("{}.{}", payload, timestamp);
+        let tag = self.keys[0]
+            .mac(&signed_data(&name, &signed))
+            .finalize()
+            .into_bytes();
+        let value = format!(
+            "{}.{}",
+            signed,
+            base64::encode_config(tag, base64::URL_SAFE_NO_PAD)
+        );
+
+        let mut cookie = Cookie::build(name, value)
+            .path("/")
+            .http_only(true)
+            .finish();
+
+        if let Some(max_age) = self.max_age {
+            cookie.set_max_age(time::Duration::seconds(max_age.as_secs() as i64));
+        }
+
+        Ok(cookie)
+    }
+
+    /// Verify a signed cookie and deserialize its value.
+    pub fn verify<T: DeserializeOwned>(
+        &self,
+        cookie: &Cookie<'_>,
+    ) -> Result<T, SignedCookieError> {
+        let (signed, tag) = split_last(cookie.value()).ok_or(SignedCookieError::Invalid)?;
+        let tag = base64::decode_config(tag, base64::URL_SAFE_NO_PAD)
+            .map_err(|_| SignedCookieError::Invalid)?;
+
+        let data = signed_data(cookie.name(), signed);
+        if !self
+            .keys
+            .iter()
+            .any(|key| key.mac(&data).verify(&tag).is_ok())
+        {
+            return Err(SignedCookieError::Invalid);
+        }
+
+        // the signature covers the rest, which is well-formed unless the key leaked
+        let (payload, timestamp) = split_last(signed).ok_or(SignedCookieError::Invalid)?;
+        let timestamp = timestamp
+            .parse::<u64>()
+            .map_err(|_| SignedCookieError::Invalid)?;
+
+        if let Some(max_age) = self.max_age {
+            let signed_at = UNIX_EPOCH + Duration::from_secs(timestamp);
+            let age = SystemTime::now()
+                .duration_since(signed_at)
+                .unwrap_or_default();
+
+            if age > max_age {
+                return Err(SignedCookieError::Expired);
+            }
+        }
+
+        let payload = base64::decode_config(payload, base64::URL_SAFE_NO_PAD)
+            .map_err(|_| SignedCookieError::Invalid)?;
+        serde_json::from_slice(&payload).map_err(SignedCookieError::Deserialize)
+    }
+}
+
+/// Data covered by the signature of a cookie.
+fn signed_data(name: &str, signed: &str) -> Vec<u8> {
+    format!("{}={}", name, signed).into_bytes()
+}
+
+/// Splits `value` at its last `.`.
+fn split_last(value: &str) -> Option<(&str, &str)> {
+    let idx = value.rfind('.')?;
+    Some((&value[..idx], &value[idx + 1..]))
+}
+
+/// Extractor for the value of a signed cookie.
+///
+/// Reads the cookie named by the [`SignedCookies`] registered as app data, as `T` or `Data<T>`.
+/// Requests whose cookie is missing, tampered with, expired or holds a value that does not
+/// deserialize as `T` are rejected with the [configured status](SignedCookies::error_status).
+/// Extraction fails with `500 Internal Server Error` if no [`SignedCookies`] is registered.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignedCookie<T>(pub T);
+
+impl<T> SignedCookie<T> {
+    /// Unwrap into inner `T` value.
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> ops::Deref for SignedCookie<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T> ops::DerefMut for SignedCookie<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.0
+    }
+}
+
+impl<T: DeserializeOwned> FromRequest for SignedCookie<T> {
+    type Error = Error;
+    type Future = Ready<Result<Self, Error>>;
+    type Config = ();
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        let cookies = match req.app_data_or_data::<SignedCookies>() {
+            Some(cookies) => cookies,
+            None => {
+                log::error!(
+                    "Failed to construct SignedCookie extractor: SignedCookies is not configured. \
+                     Request path: {:?}",
+                    req.path()
+                );
+                return ready(Err(ErrorInternalServerError(
+                    "SignedCookies is not configured, to configure use App::app_data()",
+                )));
+            }
+        };
+
+        let res = req
+            .cookie(&cookies.cookie_name)
+            .ok_or(SignedCookieError::Missing)
+            .and_then(|cookie| cookies.verify(&cookie))
+            .map(SignedCookie)
+            .map_err(|err| {
+                log::debug!(
+                    "Failed to verify signed cookie. Request path: {}",
+                    req.path()
+                );
+                InternalError::new(err, cookies.error_status).into()
+            });
+
+        ready(res)
+    }
+}
+
+/// Errors that can occur when verifying a signed cookie.
+#[derive(Debug, Display, Error)]
+pub enum SignedCookieError {
+    /// The cookie is not in the request.
+    #[display(fmt = "Signed cookie is missing")]
+    Missing,
+
+    /// The cookie is malformed or its signature does not match any key.
+    #[display(fmt = "Signed cookie is invalid")]
+    Invalid,
+
+    /// The cookie was signed longer than the max age ago.
+    #[display(fmt = "Signed cookie has expired")]
+    Expired,
+
+    /// The value of the cookie can not be deserialized.
+    #[display(fmt = "Signed cookie deserialize error: {}", _0)]
+    Deserialize(serde_json::Error),
+}
+
+/// Return `Unauthorized` for `SignedCookieError`.
+impl ResponseError for SignedCookieError {
+    fn status_code(&self) -> StatusCode {
+        StatusCode::UNAUTHORIZED
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Deserialize;
+
+    use super::*;
+    use crate::test::TestRequest;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct User {
+        id: u32,
+        name: String,
+    }
+
+    fn user() -> User {
+        User {
+            id: 7,
+            name: "ferris".to_owned(),
+        }
+    }
+
+    async fn extract<T: DeserializeOwned>(
+        cookies: SignedCookies,
+        cookie: Option<Cookie<'_>>,
+    ) -> Result<SignedCookie<T>, StatusCode> {
+        let mut req = TestRequest::default().app_data(cookies);
+        if let Some(cookie) = cookie {
+            req = req.cookie(cookie);
+        }
+
+        let (req, mut pl) = req.to_http_parts();
+        SignedCookie::<T>::from_request(&req, &mut pl)
+            .await
+            .map_err(|err| err.as_response_error().status_code())
+    }
+
+    #[actix_rt::test]
+    async fn test_round_trip() {
+        let cookies = SignedCookies::new(Key::generate()).cookie_name("user");
+        let cookie = cookies.sign("user", &user()).unwrap();
+        assert_eq!(cookie.name(), "user");
+        assert_eq!(cookie.http_only(), Some(true));
+
+        let SignedCookie(extracted) = extract::<User>(cookies.clone(), Some(cookie))
+            .await
+            .unwrap();
+        assert_eq!(extracted, user());
+
+        let res = extract::<User>(cookies, None).await;
+        assert_eq!(res.unwrap_err(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[actix_rt::test]
+    async fn test_tampered() {
+        let cookies = SignedCookies::new(Key::generate()).error_status(StatusCode::BAD_REQUEST);
+        let cookie = cookies.sign("signed", &user()).unwrap();
+
+        // replace the payload, keeping the signature
+        let (_, rest) = cookie.value().split_at(cookie.value().find('.').unwrap());
+        let forged = base64::encode_config(
+            serde_json::to_vec(&User {
+                id: 1,
+                name: "admin".to_owned(),
+            })
+            .unwrap(),
+            base64::URL_SAFE_NO_PAD,
+        );
+        let tampered = Cookie::new("signed", format!("{}{}", forged, rest));
+        assert!(matches!(
+            cookies.verify::<User>(&tampered),
+            Err(SignedCookieError::Invalid)
+        ));
+        let res = extract::<User>(cookies.clone(), Some(tampered)).await;
+        assert_eq!(res.unwrap_err(), StatusCode::BAD_REQUEST);
+
+        // signed for another cookie name
+        let renamed = Cookie::new("other", cookie.value().to_owned());
+        assert!(cookies.verify::<User>(&renamed).is_err());
+
+        // signed with another key
+        let other = SignedCookies::new(Key::generate());
+        assert!(other.verify::<User>(&cookie).is_err());
+
+        let garbage = Cookie::new("signed", "not.a-cookie");
+        assert!(cookies.verify::<User>(&garbage).is_err());
+    }
+
+    #[actix_rt::test]
+    async fn test_expired() {
+        let cookies = SignedCookies::new(Key::generate()).max_age(Duration::from_secs(60));
+
+        let cookie = cookies
+            .sign_at(
+                "signed".to_owned(),
+                &user(),
+                SystemTime::now() - Duration::from_secs(120),
+            )
+            .unwrap();
+        assert!(matches!(
+            cookies.verify::<User>(&cookie),
+            Err(SignedCookieError::Expired)
+        ));
+        let res = extract::<User>(cookies.clone(), Some(cookie)).await;
+        assert_eq!(res.unwrap_err(), StatusCode::UNAUTHORIZED);
+
+        let cookie = cookies
+            .sign_at(
+                "signed".to_owned(),
+                &user(),
+                SystemTime::now() - Duration::from_secs(30),
+            )
+            .unwrap();
+        assert_eq!(cookie.max_age(), Some(time::Duration::seconds(60)));
+        assert_eq!(cookies.verify::<User>(&cookie).unwrap(), user());
+    }
+
+    #[actix_rt::test]
+    async fn test_key_rotation() {
+        let old_key = Key::from_bytes(&[1; 32]);
+        let new_key = Key::from_bytes(&[2; 64]);
+
+        let old = SignedCookies::new(old_key.clone());
+        let old_cookie = old.sign("signed", &user()).unwrap();
+
+        let rotated = SignedCookies::new(new_key.clone()).fallback_key(old_key);
+        let SignedCookie(extracted) = extract::<User>(rotated.clone(), Some(old_cookie))
+            .await
+            .unwrap();
+        assert_eq!(extracted, user());
+
+        // new cookies are signed with the primary key
+        let new_cookie = rotated.sign("signed", &user()).unwrap();
+        assert!(old.verify::<User>(&new_cookie).is_err());
+        assert_eq!(
+            SignedCookies::new(new_key)
+                .verify::<User>(&new_cookie)
+                .unwrap(),
+            user()
+        );
+    }
+
+    #[actix_rt::test]
+    async fn test_not_configured() {
+        let (req, mut pl) = TestRequest::default().to_http_parts();
+        let err = SignedCookie::<User>::from_request(&req, &mut pl)
+            .await
+            .unwrap_err();
+        assert_eq!(
+            err.as_response_error().status_code(),
+            StatusCode::INTERNAL_SERVER_ERROR
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_short_key() {
+        Key::from_bytes(b"too short");
+    }
+}
@@ -0,0 +1,316 @@
+//! Server-Sent Events (SSE) responder.
+//!
+//! See [`Sse`] for usage.
+
+use std::{
+    convert::Infallible,
+    fmt::Write as _,
+    pin::Pin,
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use actix_rt::time::{sleep, Instant, Sleep};
+use bytes::{BufMut as _, Bytes, BytesMut};
+use futures_core::{ready, Future as _, Stream};
+use pin_project::pin_project;
+
+use crate::{
+    http::header::{CACHE_CONTROL, CONTENT_ENCODING},
+    HttpRequest, HttpResponse, Responder,
+};
+
+/// A single Server-Sent Event.
+///
+/// Data spanning multiple lines, separated by `\n`, `\r\n` or `\r`, is sent as multiple `data:`
+/// fields which the client joins back together with newlines.
+///
+/// ```
+/// use std::time::Duration;
+/// use actix_web::web::sse::Event;
+///
+/// let event = Event::new("line 1\nline 2")
+///     .event("update")
+///     .id("42")
+///     .retry(Duration::from_secs(5));
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Event {
+    data: String,
+    event: Option<String>,
+    id: Option<String>,
+    retry: Option<Duration>,
+}
+
+impl Event {
+    /// Create new event with the given data.
+    pub fn new(data: impl Into<String>) -> Self {
+        Event {
+            data: data.into(),
+            event: None,
+            id: None,
+            retry: None,
+        }
+    }
+
+    /// Set event name, sent as the `event:` field.
+    ///
+    /// Line breaks are removed from the name.
+    pub fn event(mut self, event: impl Into<String>) -> Self {
+        self.event = Some(strip_line_breaks(event.into()));
+        self
+    }
+
+    /// Set event ID, sent as the `id:` field.
+    ///
+    /// Line breaks are removed from the ID.
+    pub fn id(mut self, id: impl Into<String>) -> Self {
+        self.id = Some(strip_line_breaks(id.into()));
+        self
+    }
+
+    /// Set client reconnection time, sent as the `retry:` field in milliseconds.
+    pub fn retry(mut self, retry: Duration) -> Self {
+        self.retry = Some(retry);
+        self
+    }
+
+    /// Encode event into its wire format, including the terminating blank line.
+    fn into_bytes(self) -> Bytes {
+        let mut buf = BytesMut::with_capacity(self.data.len() + 16);
+
+        if let Some(event) = self.event {
+            write_field(&mut buf, "event", &event);
+        }
+
+        if let Some(id) = self.id {
+            write_field(&mut buf, "id", &id);
+        }
+
+        if let Some(retry) = self.retry {
+            let _ = writeln!(buf, "retry: {}", retry.as_millis());
+        }
+
+        let mut data = self.data.as_str();
+        while let Some(idx) = data.find(|c| c == '\r' || c == '\n') {
+            write_field(&mut buf, "data", &data[..idx]);
+
+            let len = if data[idx..].starts_with("\r\n") {
+                2
+            } else {
+                1
+            };
+            data = &data[idx + len..];
+        }
+        write_field(&mut buf, "data", data);
+
+        buf.put_u8(b'\n');
+        buf.freeze()
+    }
+}
+
+/// Removes `\r` and `\n` so that a field value can not start new fields.
+fn strip_line_breaks(mut value: String) -> String {
+    value.retain(|c| c != '\r' && c != '\n');
+    value
+}
+
+fn write_field(buf: &mut BytesMut, name: &str, value: &str) {
+    buf.reserve(name.len() + value.len() + 3);
+    buf.put_slice(name.as_bytes());
+    buf.put_slice(b": ");
+    buf.put_slice(value.as_bytes());
+    buf.put_u8(b'\n');
+}
+
+/// Server-Sent Events responder.
+///
+/// Wraps a stream of [`Event`]s and responds with a `text/event-stream` body. Compression is
+/// disabled for the response so that events are flushed to the client as they are produced.
+///
+/// ```
+/// use std::time::Duration;
+/// use actix_web::{get, web::sse::{Event, Sse}, Responder};
+/// use futures_util::stream;
+///
+/// #[get("/events")]
+/// async fn events() -> impl Responder {
+///     let events = stream::iter(vec![Event::new("hello"), Event::new("world")]);
+///
+///     Sse::new(events).keep_alive(Duration::from_secs(15))
+/// }
+/// ```
+pub struct Sse<S> {
+    stream: S,
+    keep_alive: Option<Duration>,
+}
+
+impl<S> Sse<S>
+where
+    S: Stream<Item = Event>,
+{
+    /// Create new SSE responder from a stream of events.
+    pub fn new(stream: S) -> Self {
+        Sse {
+            stream,
+            keep_alive: None,
+        }
+    }
+
+    /// Send a `: ping` comment whenever the event stream has been idle for `interval`.
+    ///
+    /// Keep-alive comments prevent proxies and clients from timing out idle connections. They
+    /// are disabled by default.
+    pub fn keep_alive(mut self, interval: Duration) -> Self {
+        self.keep_alive = Some(interval);
+        self
+    }
+}
+
+impl<S> Responder for Sse<S>
+where
+    S: Stream<Item = Event> + 'static,
+{
+    fn respond_to(self, _: &HttpRequest) -> HttpResponse {
+        let body = SseStream {
+            stream: self.stream,
+            keep_alive: self
+                .keep_alive
+                .map(|interval| (interval, Box::pin(sleep(interval)))),
+        };
+
+        HttpResponse::Ok()
+            .content_type(mime::TEXT_EVENT_STREAM)
+            .insert_header((CACHE_CONTROL, "no-cache"))
+            .insert_header((CONTENT_ENCODING, "identity"))
+            .streaming(Box::pin(body))
+    }
+}
+
+#[pin_project]
+struct SseStream<S> {
+    #[pin]
+    stream: S,
+    keep_alive: Option<(Duration, Pin<Box<Sleep>>)>,
+}
+
+impl<S> Stream for SseStream<S>
+where
+    S: Stream<Item = Event>,
+{
+    type Item = Result<Bytes, Infallible>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.project();
+
+        match this.stream.poll_next(cx) {
+            Poll::Ready(Some(event)) => {
+                if let Some((interval, timer)) = this.keep_alive {
+                    timer.as_mut().reset(Instant::now() + *interval);
+                }
+
+                Poll::Ready(Some(Ok(event.into_bytes())))
+            }
+
+            Poll::Ready(None) => Poll::Ready(None),
+
+            Poll::Pending => match this.keep_alive {
+                Some((interval, timer)) => {
+                    ready!(timer.as_mut().poll(cx));
+                    timer.as_mut().reset(Instant::now() + *interval);
+                    Poll::Ready(Some(Ok(Bytes::from_static(b": ping\n\n"))))
+                }
+
+                None => Poll::Pending,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_service::Service;
+    use futures_util::{stream, FutureExt as _, StreamExt as _};
+
+    use super::*;
+    use crate::{
+        http::{header::CONTENT_TYPE, StatusCode},
+        test::{self, TestRequest},
+        web, App,
+    };
+
+    #[test]
+    fn test_event_encoding() {
+        let event = Event::new("hello");
+        assert_eq!(event.into_bytes(), Bytes::from_static(b"data: hello\n\n"));
+
+        let event = Event::new("line 1\nline 2\r\nline 3\rline 4\n\r")
+            .event("update")
+            .id("42")
+            .retry(Duration::from_secs(5));
+        assert_eq!(
+            event.into_bytes(),
+            Bytes::from_static(
+                b"event: update\nid: 42\nretry: 5000\n\
+                  data: line 1\ndata: line 2\ndata: line 3\ndata: line 4\ndata: \ndata: \n\n"
+            )
+        );
+
+        // line breaks can not inject fields or events
+        let event = Event::new("x").event("a\r\ndata: b").id("1\n\nid: 2");
+        assert_eq!(
+            event.into_bytes(),
+            Bytes::from_static(b"event: adata: b\nid: 1id: 2\ndata: x\n\n")
+        );
+
+        let event = Event::new("");
+        assert_eq!(event.into_bytes(), Bytes::from_static(b"data: \n\n"));
+    }
+
+    #[actix_rt::test]
+    async fn test_sse_responder() {
+        tokio::time::pause();
+
+        let srv = test::init_service(App::new().route(
+            "/",
+            web::get().to(|| async {
+                let events = stream::iter(vec![Event::new("one").id("1")])
+                    .chain(
+                        actix_rt::time::sleep(Duration::from_millis(150))
+                            .map(|_| Event::new("two\nlines").id("2"))
+                            .into_stream(),
+                    )
+                    .chain(stream::iter(vec![Event::new("three").event("end")]));
+
+                Sse::new(events).keep_alive(Duration::from_millis(100))
+            }),
+        ))
+        .await;
+
+        let req = TestRequest::default().to_request();
+        let res = srv.call(req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(
+            res.headers().get(CONTENT_TYPE).unwrap(),
+            "text/event-stream"
+        );
+        assert_eq!(res.headers().get(CACHE_CONTROL).unwrap(), "no-cache");
+        assert_eq!(res.headers().get(CONTENT_ENCODING).unwrap(), "identity");
+
+        let body = test::read_body(res).await;
+        let frames = std::str::from_utf8(&body)
+            .unwrap()
+            .split_terminator("\n\n")
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            frames,
+            vec![
+                "id: 1\ndata: one",
+                ": ping",
+                "id: 2\ndata: two\ndata: lines",
+                "event: end\ndata: three",
+            ]
+        );
+    }
+}
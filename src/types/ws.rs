@@ -0,0 +1,324 @@
+//! WebSockets without actors.
+//!
+//! See [`handle`] for usage.
+
+use std::{
+    convert::TryFrom,
+    future::Future,
+    io,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use actix_codec::{Decoder, Encoder};
+use actix_http::ws::{hash_key, verify_handshake, Codec, Frame};
+use actix_rt::time::{sleep, Sleep};
+use bytes::{Bytes, BytesMut};
+use bytestring::ByteString;
+use derive_more::{Display, Error};
+use futures_core::Stream;
+use futures_util::task::AtomicWaker;
+use tokio::sync::mpsc;
+
+pub use actix_http::ws::{CloseCode, CloseReason, Item, Message, ProtocolError};
+
+use crate::error::Error;
+use crate::http::{header, StatusCode};
+use crate::types::Payload;
+use crate::{HttpRequest, HttpResponse};
+
+/// Time to wait for the peer's close frame after the session was closed.
+pub const CLOSE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Number of messages buffered by a session before senders have to wait.
+const MESSAGE_BUFFER: usize = 16;
+
+/// Perform WebSocket handshake and split the connection into a session and a message stream.
+///
+/// The returned response must be returned from the handler; the connection is driven by its
+/// body. Messages are sent with the [`Session`] and received from the [`MessageStream`],
+/// usually in a spawned task.
+///
+/// Neither side answers pings or close frames on its own. Closing works as follows:
+/// * [`Session::close`] sends a close frame, then waits until the peer's close frame is read from
+///   the message stream (or the stream ends) and closes the connection. If the peer does not
+///   answer within [`CLOSE_TIMEOUT`], the connection is closed anyway.
+/// * When the peer closes first, the message stream yields [`Message::Close`] and then ends. The
+///   connection is closed as soon as the session answers with [`Session::close`].
+/// * Dropping all clones of the session closes the connection without a close frame.
+///
+/// ```rust,no_run
+/// use actix_web::{web, Error, HttpRequest, HttpResponse};
+/// use futures_util::StreamExt as _;
+///
+/// async fn echo(req: HttpRequest, body: web::Payload) -> Result<HttpResponse, Error> {
+///     let (res, session, mut messages) = web::ws::handle(&req, body)?;
+///
+///     actix_web::rt::spawn(async move {
+///         while let Some(Ok(msg)) = messages.next().await {
+///             let res = match msg {
+///                 web::ws::Message::Text(text) => session.text(text).await,
+///                 web::ws::Message::Ping(bytes) => session.pong(&bytes).await,
+///                 web::ws::Message::Close(reason) => {
+///                     let _ = session.close(reason).await;
+///                     break;
+///                 }
+///                 _ => Ok(()),
+///             };
+///
+///             if res.is_err() {
+///                 break;
+///             }
+///         }
+///     });
+///
+///     Ok(res)
+/// }
+/// ```
+pub fn handle(
+    req: &HttpRequest,
+    payload: Payload,
+) -> Result<(HttpResponse, Session, MessageStream), Error> {
+    verify_handshake(req.head())?;
+
+    let key = {
+        let key = req.headers().get(header::SEC_WEBSOCKET_KEY).unwrap();
+        hash_key(key.as_ref())
+    };
+
+    let shared = Arc::new(Shared::default());
+    let (tx, rx) = mpsc::channel(MESSAGE_BUFFER);
+
+    let res = HttpResponse::build(StatusCode::SWITCHING_PROTOCOLS)
+        .upgrade("websocket")
+        .insert_header((header::SEC_WEBSOCKET_ACCEPT, key))
+        .streaming(SessionStream {
+            rx,
+            codec: Codec::new(),
+            shared: shared.clone(),
+            close_timeout: None,
+        });
+
+    let session = Session {
+        tx,
+        shared: shared.clone(),
+    };
+
+    let stream = MessageStream {
+        payload,
+        codec: Codec::new(),
+        buf: BytesMut::new(),
+        shared,
+        closed: false,
+    };
+
+    Ok((res, session, stream))
+}
+
+/// State shared by the session, the message stream and the response body.
+#[derive(Default)]
+struct Shared {
+    /// A close frame was sent by a session.
+    closed: AtomicBool,
+    /// A close frame was received from the peer, or the connection ended.
+    peer_closed: AtomicBool,
+    /// Task of the response body, waiting for the peer's close frame.
+    task: AtomicWaker,
+}
+
+/// The WebSocket session has been closed.
+#[derive(Debug, Display, Error)]
+#[display(fmt = "WebSocket session is closed.")]
+pub struct Closed;
+
+/// Sending half of a WebSocket connection.
+///
+/// Sending waits while the connection is not able to keep up with queued messages. Clones send
+/// to the same connection.
+#[derive(Clone)]
+pub struct Session {
+    tx: mpsc::Sender<Message>,
+    shared: Arc<Shared>,
+}
+
+impl Session {
+    /// Send a text message.
+    pub async fn text(&self, text: impl Into<ByteString>) -> Result<(), Closed> {
+        self.send(Message::Text(text.into())).await
+    }
+
+    /// Send a binary message.
+    pub async fn binary(&self, data: impl Into<Bytes>) -> Result<(), Closed> {
+        self.send(Message::Binary(data.into())).await
+    }
+
+    /// Send a ping.
+    pub async fn ping(&self, data: &[u8]) -> Result<(), Closed> {
+        self.send(Message::Ping(Bytes::copy_from_slice(data))).await
+    }
+
+    /// Send a pong.
+    pub async fn pong(&self, data: &[u8]) -> Result<(), Closed> {
+        self.send(Message::Pong(Bytes::copy_from_slice(data))).await
+    }
+
+    /// Send a close frame and close the connection once the peer answers.
+    ///
+    /// All clones of the session are closed; further sends fail with [`Closed`].
+    pub async fn close(self, reason: Option<CloseReason>) -> Result<(), Closed> {
+        if self.shared.closed.swap(true, Ordering::AcqRel) {
+            return Err(Closed);
+        }
+
+        self.tx
+            .send(Message::Close(reason))
+            .await
+            .map_err(|_| Closed)
+    }
+
+    /// Returns true if the session has been closed.
+    pub fn is_closed(&self) -> bool {
+        self.shared.closed.load(Ordering::Acquire) || self.tx.is_closed()
+    }
+
+    async fn send(&self, msg: Message) -> Result<(), Closed> {
+        if self.shared.closed.load(Ordering::Acquire) {
+            return Err(Closed);
+        }
+
+        self.tx.send(msg).await.map_err(|_| Closed)
+    }
+}
+
+/// Response body writing the messages of a session.
+struct SessionStream {
+    rx: mpsc::Receiver<Message>,
+    codec: Codec,
+    shared: Arc<Shared>,
+    close_timeout: Option<Pin<Box<Sleep>>>,
+}
+
+impl Stream for SessionStream {
+    type Item = Result<Bytes, ProtocolError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+
+        // close frame is sent, wait for the peer's answer
+        if let Some(ref mut timeout) = this.close_timeout {
+            this.shared.task.register(cx.waker());
+
+            if this.shared.peer_closed.load(Ordering::Acquire) {
+                return Poll::Ready(None);
+            }
+
+            return match timeout.as_mut().poll(cx) {
+                Poll::Ready(_) => {
+                    log::trace!("Peer did not answer WebSocket close frame");
+                    Poll::Ready(None)
+                }
+                Poll::Pending => Poll::Pending,
+            };
+        }
+
+        match this.rx.poll_recv(cx) {
+            Poll::Ready(Some(msg)) => {
+                let close = matches!(msg, Message::Close(_));
+
+                let mut buf = BytesMut::new();
+                this.codec.encode(msg, &mut buf)?;
+
+                if close {
+                    this.rx.close();
+                    this.close_timeout = Some(Box::pin(sleep(CLOSE_TIMEOUT)));
+                }
+
+                Poll::Ready(Some(Ok(buf.freeze())))
+            }
+
+            // all sessions are dropped
+            Poll::Ready(None) => Poll::Ready(None),
+
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+/// Receiving half of a WebSocket connection.
+///
+/// Ends after a close message is received or when the connection ends.
+pub struct MessageStream {
+    payload: Payload,
+    codec: Codec,
+    buf: BytesMut,
+    shared: Arc<Shared>,
+    closed: bool,
+}
+
+impl MessageStream {
+    fn peer_closed(&mut self) {
+        self.closed = true;
+        self.shared.peer_closed.store(true, Ordering::Release);
+        self.shared.task.wake();
+    }
+}
+
+impl Stream for MessageStream {
+    type Item = Result<Message, ProtocolError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+
+        if this.closed {
+            return Poll::Ready(None);
+        }
+
+        loop {
+            match this.codec.decode(&mut this.buf)? {
+                Some(frame) => {
+                    let msg = match frame {
+                        Frame::Text(data) => {
+                            Message::Text(ByteString::try_from(data).map_err(|e| {
+                                ProtocolError::Io(io::Error::new(
+                                    io::ErrorKind::Other,
+                                    format!("{}", e),
+                                ))
+                            })?)
+                        }
+                        Frame::Binary(data) => Message::Binary(data),
+                        Frame::Ping(data) => Message::Ping(data),
+                        Frame::Pong(data) => Message::Pong(data),
+                        Frame::Close(reason) => {
+                            this.peer_closed();
+                            Message::Close(reason)
+                        }
+                        Frame::Continuation(item) => Message::Continuation(item),
+                    };
+
+                    return Poll::Ready(Some(Ok(msg)));
+                }
+
+                None => match Pin::new(&mut this.payload).poll_next(cx) {
+                    Poll::Ready(Some(Ok(chunk))) => this.buf.extend_from_slice(&chunk),
+                    Poll::Ready(Some(Err(e))) => {
+                        this.peer_closed();
+                        return Poll::Ready(Some(Err(ProtocolError::Io(io::Error::new(
+                            io::ErrorKind::Other,
+                            format!("{}", e),
+                        )))));
+                    }
+                    Poll::Ready(None) => {
+                        this.peer_closed();
+                        return Poll::Ready(None);
+                    }
+                    Poll::Pending => return Poll::Pending,
+                },
+            }
+        }
+    }
+}
@@ -16,10 +16,15 @@ use crate::route::Route;
 use crate::scope::Scope;
 use crate::service::WebService;
 
+pub use crate::blocking::BlockingPoolStatus;
 pub use crate::config::ServiceConfig;
 pub use crate::data::Data;
+pub use crate::extract::ExtractorErrorMode;
+pub use crate::health::HealthCheck;
 pub use crate::request::HttpRequest;
 pub use crate::request_data::ReqData;
+#[cfg(any(feature = "openssl", feature = "rustls"))]
+pub use crate::tls::ClientCert;
 pub use crate::types::*;
 
 /// Create resource for a specific path.
@@ -275,11 +280,33 @@ pub fn service<T: IntoPattern>(path: T) -> WebService {
 
 /// Execute blocking function on a thread pool, returns future that resolves
 /// to result of the function execution.
+///
+/// The function runs on the same thread pool as [`rt::spawn_blocking`](crate::rt::spawn_blocking).
+/// If the pool size was limited using
+/// [`HttpServer::blocking_pool`](crate::HttpServer::blocking_pool), the function waits for a free
+/// thread and the future resolves to [`BlockingError::QueueFull`] if too many functions are
+/// already waiting. This error is returned as a `503 Service Unavailable` response.
 pub fn block<F, R>(f: F) -> impl Future<Output = Result<R, BlockingError>>
 where
     F: FnOnce() -> R + Send + 'static,
     R: Send + 'static,
 {
-    let fut = actix_rt::task::spawn_blocking(f);
-    async { fut.await.map_err(|_| BlockingError) }
+    crate::blocking::run(f)
+}
+
+/// Returns the blocking thread pool usage of the current worker.
+///
+/// Useful for reporting saturation in health check endpoints.
+///
+/// ```
+/// use actix_web::{get, web, HttpResponse};
+///
+/// #[get("/health")]
+/// async fn health() -> HttpResponse {
+///     let status = web::blocking_pool_status();
+///     HttpResponse::Ok().body(format!("active={} queued={}", status.active, status.queued))
+/// }
+/// ```
+pub fn blocking_pool_status() -> BlockingPoolStatus {
+    crate::blocking::status()
 }
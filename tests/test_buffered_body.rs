@@ -0,0 +1,128 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::rc::Rc;
+
+use actix_service::{Service, Transform};
+use actix_web::{
+    dev::{Payload, ServiceRequest, ServiceResponse},
+    error, test, web, App, Error, HttpMessage, HttpResponse,
+};
+use bytes::Bytes;
+use futures_util::future::{ok, Ready};
+use futures_util::StreamExt;
+use serde::Deserialize;
+
+/// Stand-in for an HMAC: the sum of the body bytes, sent in the `x-signature` header.
+fn sign(body: &[u8]) -> String {
+    body.iter().map(|b| *b as u64).sum::<u64>().to_string()
+}
+
+struct VerifySignature;
+
+impl<S> Transform<S, ServiceRequest> for VerifySignature
+where
+    S: Service<ServiceRequest, Response = ServiceResponse, Error = Error> + 'static,
+{
+    type Response = ServiceResponse;
+    type Error = Error;
+    type Transform = VerifySignatureMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ok(VerifySignatureMiddleware {
+            service: Rc::new(service),
+        })
+    }
+}
+
+struct VerifySignatureMiddleware<S> {
+    service: Rc<S>,
+}
+
+impl<S> Service<ServiceRequest> for VerifySignatureMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse, Error = Error> + 'static,
+{
+    type Response = ServiceResponse;
+    type Error = Error;
+    type Future = Pin<Box<dyn Future<Output = Result<ServiceResponse, Error>>>>;
+
+    actix_service::forward_ready!(service);
+
+    fn call(&self, mut req: ServiceRequest) -> Self::Future {
+        let service = Rc::clone(&self.service);
+
+        Box::pin(async move {
+            let body = req.extract_buffered_body(1024).await?;
+
+            let valid = req
+                .headers()
+                .get("x-signature")
+                .map_or(false, |sig| sig.as_bytes() == sign(&body).as_bytes());
+
+            if !valid {
+                return Ok(req.error_response(error::ErrorUnauthorized("bad signature")));
+            }
+
+            service.call(req).await
+        })
+    }
+}
+
+#[derive(Deserialize)]
+struct Order {
+    item: String,
+    quantity: u32,
+}
+
+#[actix_rt::test]
+async fn test_signature_middleware_and_json_extractor() {
+    let srv = test::init_service(App::new().wrap(VerifySignature).route(
+        "/",
+        web::post().to(|order: web::Json<Order>| {
+            HttpResponse::Ok().body(format!("{} x {}", order.quantity, order.item))
+        }),
+    ))
+    .await;
+
+    let body = r#"{"item":"widget","quantity":3}"#;
+
+    let req = test::TestRequest::post()
+        .uri("/")
+        .insert_header(("content-type", "application/json"))
+        .insert_header(("x-signature", sign(body.as_bytes())))
+        .set_payload(body)
+        .to_request();
+    let res = test::call_service(&srv, req).await;
+    assert!(res.status().is_success());
+    assert_eq!(test::read_body(res).await, "3 x widget");
+
+    let req = test::TestRequest::post()
+        .uri("/")
+        .insert_header(("content-type", "application/json"))
+        .insert_header(("x-signature", "0"))
+        .set_payload(body)
+        .to_request();
+    let res = test::call_service(&srv, req).await;
+    assert_eq!(res.status(), 401);
+}
+
+#[actix_rt::test]
+async fn test_extract_buffered_body_limit() {
+    let mut req = test::TestRequest::default()
+        .set_payload(Bytes::from_static(b"0123456789"))
+        .to_srv_request();
+    assert!(req.extract_buffered_body(4).await.is_err());
+
+    let mut req = test::TestRequest::default()
+        .set_payload(Bytes::from_static(b"0123456789"))
+        .to_srv_request();
+    assert_eq!(req.extract_buffered_body(10).await.unwrap(), "0123456789");
+
+    // the stored body is returned again and the payload is replayed each time
+    assert_eq!(req.extract_buffered_body(10).await.unwrap(), "0123456789");
+    let mut pl: Payload = req.take_payload();
+    assert_eq!(pl.next().await.unwrap().unwrap(), "0123456789");
+    assert!(pl.next().await.is_none());
+}
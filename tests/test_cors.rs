@@ -0,0 +1,263 @@
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+use std::time::Duration;
+
+use actix_web::{
+    dev::{Service, ServiceResponse},
+    error::{self, CorsError},
+    http::{header, Method, StatusCode},
+    middleware::Cors,
+    test, web, App, Error, HttpResponse,
+};
+
+const PRIVATE_NETWORK_REQUEST: &str = "access-control-request-private-network";
+const PRIVATE_NETWORK_ALLOW: &str = "access-control-allow-private-network";
+
+async fn init_app(
+    cors: Cors,
+) -> (
+    impl Service<actix_http::Request, Response = ServiceResponse, Error = Error>,
+    Arc<AtomicUsize>,
+) {
+    let hits = Arc::new(AtomicUsize::new(0));
+
+    let app = test::init_service(
+        App::new()
+            .wrap(cors)
+            .app_data(web::Data::from(Arc::clone(&hits)))
+            .service(web::resource("/").to(|hits: web::Data<AtomicUsize>| {
+                hits.fetch_add(1, Ordering::SeqCst);
+                HttpResponse::Ok()
+                    .insert_header(("x-request-id", "1"))
+                    .body("ok")
+            }))
+            .service(web::resource("/fail").to(|| async {
+                Err::<HttpResponse, _>(error::ErrorUnprocessableEntity("bad input"))
+            })),
+    )
+    .await;
+
+    (app, hits)
+}
+
+fn header_str<'a>(res: &'a ServiceResponse, name: impl header::AsHeaderName) -> &'a str {
+    res.headers().get(name).unwrap().to_str().unwrap()
+}
+
+#[actix_rt::test]
+async fn test_preflight() {
+    let cors = Cors::new()
+        .allowed_origin("https://app.example.com")
+        .allowed_methods(vec![Method::GET, Method::PUT])
+        .allowed_headers(vec![header::AUTHORIZATION, header::CONTENT_TYPE])
+        .max_age(Duration::from_secs(600))
+        .allow_private_network_access();
+    let (app, hits) = init_app(cors).await;
+
+    let req = test::TestRequest::default()
+        .method(Method::OPTIONS)
+        .insert_header((header::ORIGIN, "https://app.example.com"))
+        .insert_header((header::ACCESS_CONTROL_REQUEST_METHOD, "PUT"))
+        .insert_header((
+            header::ACCESS_CONTROL_REQUEST_HEADERS,
+            "Content-Type, authorization",
+        ))
+        .insert_header((PRIVATE_NETWORK_REQUEST, "true"))
+        .to_request();
+    let res = test::call_service(&app, req).await;
+
+    assert_eq!(res.status(), StatusCode::OK);
+    assert_eq!(
+        header_str(&res, header::ACCESS_CONTROL_ALLOW_ORIGIN),
+        "https://app.example.com"
+    );
+    assert_eq!(
+        header_str(&res, header::ACCESS_CONTROL_ALLOW_METHODS),
+        "GET, PUT"
+    );
+    assert_eq!(
+        header_str(&res, header::ACCESS_CONTROL_ALLOW_HEADERS),
+        "authorization, content-type"
+    );
+    assert_eq!(header_str(&res, header::ACCESS_CONTROL_MAX_AGE), "600");
+    assert_eq!(header_str(&res, PRIVATE_NETWORK_ALLOW), "true");
+    assert!(header_str(&res, header::VARY).starts_with("Origin"));
+    assert!(!res
+        .headers()
+        .contains_key(header::ACCESS_CONTROL_ALLOW_CREDENTIALS));
+    assert!(test::read_body(res).await.is_empty());
+
+    // the private network header is only sent when asked for
+    let req = test::TestRequest::default()
+        .method(Method::OPTIONS)
+        .insert_header((header::ORIGIN, "https://app.example.com"))
+        .insert_header((header::ACCESS_CONTROL_REQUEST_METHOD, "GET"))
+        .to_request();
+    let res = test::call_service(&app, req).await;
+    assert_eq!(res.status(), StatusCode::OK);
+    assert!(!res.headers().contains_key(PRIVATE_NETWORK_ALLOW));
+
+    let req = test::TestRequest::default()
+        .method(Method::OPTIONS)
+        .insert_header((header::ORIGIN, "https://app.example.com"))
+        .insert_header((header::ACCESS_CONTROL_REQUEST_METHOD, "DELETE"))
+        .to_request();
+    let res = test::call_service(&app, req).await;
+    assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(
+        res.response().error().unwrap().as_error(),
+        Some(&CorsError::MethodNotAllowed)
+    );
+
+    assert_eq!(hits.load(Ordering::SeqCst), 0);
+
+    // OPTIONS requests that are not preflight requests reach the handler
+    let req = test::TestRequest::default()
+        .method(Method::OPTIONS)
+        .insert_header((header::ORIGIN, "https://app.example.com"))
+        .to_request();
+    let res = test::call_service(&app, req).await;
+    assert_eq!(res.status(), StatusCode::OK);
+    assert_eq!(hits.load(Ordering::SeqCst), 1);
+}
+
+#[actix_rt::test]
+async fn test_simple_request() {
+    let cors = Cors::new()
+        .allowed_origin("https://app.example.com")
+        .expose_headers(vec!["x-request-id"]);
+    let (app, hits) = init_app(cors).await;
+
+    let req = test::TestRequest::with_uri("/")
+        .insert_header((header::ORIGIN, "https://app.example.com"))
+        .to_request();
+    let res = test::call_service(&app, req).await;
+
+    assert_eq!(res.status(), StatusCode::OK);
+    assert_eq!(
+        header_str(&res, header::ACCESS_CONTROL_ALLOW_ORIGIN),
+        "https://app.example.com"
+    );
+    assert_eq!(
+        header_str(&res, header::ACCESS_CONTROL_EXPOSE_HEADERS),
+        "x-request-id"
+    );
+    assert_eq!(header_str(&res, header::VARY), "Origin");
+    assert_eq!(test::read_body(res).await, "ok");
+    assert_eq!(hits.load(Ordering::SeqCst), 1);
+
+    // error responses carry the headers too
+    for path in &["/fail", "/missing"] {
+        let req = test::TestRequest::with_uri(path)
+            .insert_header((header::ORIGIN, "https://app.example.com"))
+            .to_request();
+        let res = test::call_service(&app, req).await;
+
+        assert!(res.status().is_client_error());
+        assert_eq!(
+            header_str(&res, header::ACCESS_CONTROL_ALLOW_ORIGIN),
+            "https://app.example.com"
+        );
+    }
+
+    // requests without an origin are left alone apart from `Vary`
+    let req = test::TestRequest::with_uri("/").to_request();
+    let res = test::call_service(&app, req).await;
+    assert_eq!(res.status(), StatusCode::OK);
+    assert!(!res
+        .headers()
+        .contains_key(header::ACCESS_CONTROL_ALLOW_ORIGIN));
+    assert_eq!(header_str(&res, header::VARY), "Origin");
+}
+
+#[actix_rt::test]
+async fn test_disallowed_origin() {
+    let cors = Cors::new().allowed_origin("https://app.example.com");
+    let (app, hits) = init_app(cors).await;
+
+    let req = test::TestRequest::with_uri("/")
+        .insert_header((header::ORIGIN, "https://evil.example.com"))
+        .to_request();
+    let res = test::call_service(&app, req).await;
+
+    assert_eq!(res.status(), StatusCode::OK);
+    assert!(!res
+        .headers()
+        .contains_key(header::ACCESS_CONTROL_ALLOW_ORIGIN));
+    assert_eq!(header_str(&res, header::VARY), "Origin");
+    assert_eq!(hits.load(Ordering::SeqCst), 1);
+
+    let req = test::TestRequest::default()
+        .method(Method::OPTIONS)
+        .insert_header((header::ORIGIN, "https://evil.example.com"))
+        .insert_header((header::ACCESS_CONTROL_REQUEST_METHOD, "GET"))
+        .to_request();
+    let res = test::call_service(&app, req).await;
+
+    assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(
+        res.response().error().unwrap().as_error(),
+        Some(&CorsError::OriginNotAllowed)
+    );
+    assert!(!res
+        .headers()
+        .contains_key(header::ACCESS_CONTROL_ALLOW_ORIGIN));
+    assert_eq!(hits.load(Ordering::SeqCst), 1);
+}
+
+#[actix_rt::test]
+async fn test_credentialed_reflection() {
+    let (app, _) = init_app(Cors::new().allow_any_origin()).await;
+
+    let req = test::TestRequest::with_uri("/")
+        .insert_header((header::ORIGIN, "https://any.example.com"))
+        .to_request();
+    let res = test::call_service(&app, req).await;
+    assert_eq!(header_str(&res, header::ACCESS_CONTROL_ALLOW_ORIGIN), "*");
+    assert!(!res.headers().contains_key(header::VARY));
+
+    let cors = Cors::new()
+        .allowed_origin("*")
+        .allow_any_header()
+        .supports_credentials();
+    let (app, _) = init_app(cors).await;
+
+    let req = test::TestRequest::with_uri("/")
+        .insert_header((header::ORIGIN, "https://any.example.com"))
+        .to_request();
+    let res = test::call_service(&app, req).await;
+
+    assert_eq!(
+        header_str(&res, header::ACCESS_CONTROL_ALLOW_ORIGIN),
+        "https://any.example.com"
+    );
+    assert_eq!(
+        header_str(&res, header::ACCESS_CONTROL_ALLOW_CREDENTIALS),
+        "true"
+    );
+    assert_eq!(header_str(&res, header::VARY), "Origin");
+
+    let req = test::TestRequest::default()
+        .method(Method::OPTIONS)
+        .insert_header((header::ORIGIN, "https://other.example.com"))
+        .insert_header((header::ACCESS_CONTROL_REQUEST_METHOD, "POST"))
+        .insert_header((header::ACCESS_CONTROL_REQUEST_HEADERS, "x-csrf-token"))
+        .to_request();
+    let res = test::call_service(&app, req).await;
+
+    assert_eq!(res.status(), StatusCode::OK);
+    assert_eq!(
+        header_str(&res, header::ACCESS_CONTROL_ALLOW_ORIGIN),
+        "https://other.example.com"
+    );
+    assert_eq!(
+        header_str(&res, header::ACCESS_CONTROL_ALLOW_HEADERS),
+        "x-csrf-token"
+    );
+    assert_eq!(
+        header_str(&res, header::ACCESS_CONTROL_ALLOW_CREDENTIALS),
+        "true"
+    );
+}
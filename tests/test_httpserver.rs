@@ -9,7 +9,7 @@ extern crate tls_rustls as rustls;
 #[cfg(feature = "openssl")]
 use openssl::ssl::SslAcceptorBuilder;
 
-use actix_web::{test, web, App, HttpResponse, HttpServer};
+use actix_web::{test, web, App, Error, HttpResponse, HttpServer};
 
 #[cfg(unix)]
 #[actix_rt::test]
@@ -33,6 +33,13 @@ async fn test_start() {
             .keep_alive(10)
             .client_timeout(5000)
             .client_shutdown(0)
+            .h2_max_concurrent_streams(100)
+            .h2_initial_window_size(1024 * 1024)
+            .h2_initial_connection_window_size(1024 * 1024)
+            .h2_max_frame_size(32 * 1024)
+            .connection_stats(actix_web::dev::ConnectionStats::new())
+            .max_connection_age(Duration::from_secs(60))
+            .graceful_shutdown(actix_web::dev::GracefulShutdown::new())
             .server_hostname("localhost")
             .system_exit()
             .disable_signals()
@@ -73,13 +80,18 @@ async fn test_start() {
 
 #[cfg(feature = "openssl")]
 fn ssl_acceptor() -> std::io::Result<SslAcceptorBuilder> {
+    ssl_acceptor_for("localhost")
+}
+
+#[cfg(feature = "openssl")]
+fn ssl_acceptor_for(name: &str) -> std::io::Result<SslAcceptorBuilder> {
     use openssl::{
         pkey::PKey,
         ssl::{SslAcceptor, SslMethod},
         x509::X509,
     };
 
-    let cert = rcgen::generate_simple_self_signed(vec!["localhost".to_owned()]).unwrap();
+    let cert = rcgen::generate_simple_self_signed(vec![name.to_owned()]).unwrap();
     let cert_file = cert.serialize_pem().unwrap();
     let key_file = cert.serialize_private_key_pem();
     let cert = X509::from_pem(cert_file.as_bytes()).unwrap();
@@ -151,3 +163,907 @@ async fn test_start_ssl() {
     thread::sleep(Duration::from_millis(100));
     let _ = sys.stop();
 }
+
+#[actix_rt::test]
+#[cfg(feature = "openssl")]
+async fn test_openssl_reload() {
+    use std::io::{Read, Write};
+
+    use actix_web::tls::ReloadableSslAcceptor;
+    use openssl::ssl::{SslConnector, SslMethod, SslStream, SslVerifyMode};
+
+    let addr = test::unused_addr();
+    let (tx, rx) = mpsc::channel();
+    let acceptor = ReloadableSslAcceptor::new(ssl_acceptor_for("one.test").unwrap()).unwrap();
+
+    let srv_acceptor = acceptor.clone();
+    thread::spawn(move || {
+        let sys = actix_rt::System::new();
+
+        let srv = HttpServer::new(|| {
+            App::new().service(web::resource("/").to(|| HttpResponse::Ok().body("test")))
+        })
+        .workers(1)
+        .shutdown_timeout(1)
+        .system_exit()
+        .disable_signals()
+        .bind_openssl_with_reload(format!("{}", addr), srv_acceptor)
+        .unwrap();
+
+        sys.block_on(async {
+            let srv = srv.run();
+            let _ = tx.send((srv, actix_rt::System::current()));
+        });
+
+        let _ = sys.run();
+    });
+    let (srv, sys) = rx.recv().unwrap();
+
+    let connect = || {
+        let mut builder = SslConnector::builder(SslMethod::tls()).unwrap();
+        builder.set_verify(SslVerifyMode::NONE);
+        let stream = std::net::TcpStream::connect(addr).unwrap();
+        builder.build().connect("localhost", stream).unwrap()
+    };
+    let peer_name = |stream: &SslStream<std::net::TcpStream>| {
+        let cert = stream.ssl().peer_certificate().unwrap();
+        let names = cert.subject_alt_names().unwrap();
+        names[0].dnsname().unwrap().to_owned()
+    };
+
+    let mut first = connect();
+    assert_eq!(peer_name(&first), "one.test");
+
+    acceptor
+        .reload(ssl_acceptor_for("two.test").unwrap())
+        .unwrap();
+
+    let second = connect();
+    assert_eq!(peer_name(&second), "two.test");
+
+    // connections made before the reload keep working
+    first
+        .write_all(b"GET / HTTP/1.1\r\nconnection: close\r\n\r\n")
+        .unwrap();
+    let mut res = String::new();
+    first.read_to_string(&mut res).unwrap();
+    assert!(res.starts_with("HTTP/1.1 200 OK"));
+    assert!(res.ends_with("test"));
+
+    // stop
+    srv.stop(false).await;
+
+    thread::sleep(Duration::from_millis(100));
+    sys.stop();
+}
+
+#[actix_rt::test]
+#[cfg(feature = "rustls")]
+async fn test_rustls_reload() {
+    use std::sync::Arc;
+
+    use actix_web::tls::ReloadableCert;
+    use rustls::Session as _;
+    use rustls::{
+        Certificate, ClientConfig, ClientSession, NoClientAuth, PrivateKey, ServerConfig,
+    };
+
+    fn generate(name: &str) -> (Certificate, PrivateKey) {
+        let cert = rcgen::generate_simple_self_signed(vec![name.to_owned()]).unwrap();
+        (
+            Certificate(cert.serialize_der().unwrap()),
+            PrivateKey(cert.serialize_private_key_der()),
+        )
+    }
+
+    let (cert_one, key_one) = generate("one.test");
+    let (cert_two, key_two) = generate("two.test");
+
+    let addr = test::unused_addr();
+    let (tx, rx) = mpsc::channel();
+    let reloadable = ReloadableCert::new(vec![cert_one.clone()], &key_one).unwrap();
+
+    let srv_cert = reloadable.clone();
+    thread::spawn(move || {
+        let sys = actix_rt::System::new();
+
+        let srv = HttpServer::new(|| {
+            App::new().service(web::resource("/").to(|| HttpResponse::Ok().body("test")))
+        })
+        .workers(1)
+        .shutdown_timeout(1)
+        .system_exit()
+        .disable_signals()
+        .bind_rustls_with_reload(
+            format!("{}", addr),
+            ServerConfig::new(NoClientAuth::new()),
+            srv_cert,
+        )
+        .unwrap();
+
+        sys.block_on(async {
+            let srv = srv.run();
+            let _ = tx.send((srv, actix_rt::System::current()));
+        });
+
+        let _ = sys.run();
+    });
+    let (srv, sys) = rx.recv().unwrap();
+
+    let mut config = ClientConfig::new();
+    config.root_store.add(&cert_one).unwrap();
+    config.root_store.add(&cert_two).unwrap();
+    let config = Arc::new(config);
+
+    // certificates are verified for `name`, handshakes fail if the server presents the other one
+    let handshake = |name: &str| {
+        let name = webpki::DNSNameRef::try_from_ascii_str(name).unwrap();
+        let mut session = ClientSession::new(&config, name);
+        let mut stream = std::net::TcpStream::connect(addr).unwrap();
+        while session.is_handshaking() {
+            session.complete_io(&mut stream)?;
+        }
+        Ok::<_, std::io::Error>(session.get_peer_certificates().unwrap())
+    };
+
+    assert_eq!(handshake("one.test").unwrap(), vec![cert_one]);
+
+    reloadable.reload(vec![cert_two.clone()], &key_two).unwrap();
+
+    assert_eq!(handshake("two.test").unwrap(), vec![cert_two]);
+    assert!(handshake("one.test").is_err());
+
+    // stop
+    srv.stop(false).await;
+
+    thread::sleep(Duration::from_millis(100));
+    sys.stop();
+}
+
+/// Self-signed CA certificate and a client certificate issued by it.
+#[cfg(any(feature = "openssl", feature = "rustls"))]
+fn client_ca() -> (rcgen::Certificate, rcgen::Certificate) {
+    use rcgen::{BasicConstraints, Certificate, CertificateParams, DnType, IsCa};
+
+    let mut params = CertificateParams::new(vec![]);
+    params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+    params
+        .distinguished_name
+        .push(DnType::CommonName, "client CA");
+    let ca = Certificate::from_params(params).unwrap();
+
+    let mut params = CertificateParams::new(vec!["client.test".to_owned()]);
+    params
+        .distinguished_name
+        .push(DnType::CommonName, "client.test");
+    let client = Certificate::from_params(params).unwrap();
+
+    (ca, client)
+}
+
+#[actix_rt::test]
+#[cfg(feature = "openssl")]
+async fn test_openssl_client_cert() {
+    use std::io::{self, Read, Write};
+
+    use actix_web::{guard, tls::ClientCertAuth};
+    use openssl::{
+        pkey::PKey,
+        ssl::{SslConnector, SslMethod, SslVerifyMode},
+        x509::X509,
+    };
+
+    let (ca, client) = client_ca();
+    let ca_cert = X509::from_der(&ca.serialize_der().unwrap()).unwrap();
+    let client_der = client.serialize_der_with_signer(&ca).unwrap();
+    let client_cert = X509::from_der(&client_der).unwrap();
+    let client_key = PKey::private_key_from_der(&client.serialize_private_key_der()).unwrap();
+
+    let required_addr = test::unused_addr();
+    let requested_addr = test::unused_addr();
+    let (tx, rx) = mpsc::channel();
+
+    let expected = client_der.clone();
+    thread::spawn(move || {
+        let sys = actix_rt::System::new();
+
+        let acceptor = || {
+            let mut builder = ssl_acceptor().unwrap();
+            builder.cert_store_mut().add_cert(ca_cert.clone()).unwrap();
+            builder
+        };
+
+        let srv = HttpServer::new(move || {
+            let expected = expected.clone();
+            App::new()
+                .service(
+                    web::resource("/guarded")
+                        .guard(guard::ClientCert(move |certs| {
+                            certs.leaf() == &expected[..]
+                        }))
+                        .to(HttpResponse::Ok),
+                )
+                .route(
+                    "/leaf",
+                    web::get().to(|cert: web::ClientCert| {
+                        HttpResponse::Ok().body(cert.leaf().to_vec())
+                    }),
+                )
+        })
+        .workers(1)
+        .shutdown_timeout(1)
+        .system_exit()
+        .disable_signals()
+        .bind_openssl_with_client_auth(
+            format!("{}", required_addr),
+            acceptor(),
+            ClientCertAuth::require(),
+        )
+        .unwrap()
+        .bind_openssl_with_client_auth(
+            format!("{}", requested_addr),
+            acceptor(),
+            ClientCertAuth::request().verify(|_| false),
+        )
+        .unwrap();
+
+        sys.block_on(async {
+            let srv = srv.run();
+            let _ = tx.send((srv, actix_rt::System::current()));
+        });
+
+        let _ = sys.run();
+    });
+    let (srv, sys) = rx.recv().unwrap();
+
+    let request = |addr, path: &str, with_cert: bool| {
+        let mut builder = SslConnector::builder(SslMethod::tls()).unwrap();
+        builder.set_verify(SslVerifyMode::NONE);
+        if with_cert {
+            builder.set_certificate(&client_cert).unwrap();
+            builder.set_private_key(&client_key).unwrap();
+        }
+
+        let stream = std::net::TcpStream::connect(addr)?;
+        let mut stream = builder
+            .build()
+            .connect("localhost", stream)
+            .map_err(|err| io::Error::new(io::ErrorKind::Other, err.to_string()))?;
+
+        write!(stream, "GET {} HTTP/1.1\r\nconnection: close\r\n\r\n", path)?;
+        let mut res = Vec::new();
+        stream.read_to_end(&mut res)?;
+        Ok::<_, io::Error>(res)
+    };
+
+    let res = request(required_addr, "/guarded", true).unwrap();
+    assert!(res.starts_with(b"HTTP/1.1 200 OK"));
+
+    let res = request(required_addr, "/leaf", true).unwrap();
+    assert!(res.starts_with(b"HTTP/1.1 200 OK"));
+    assert!(res.ends_with(&client_der));
+
+    // connections without a certificate are rejected during the handshake
+    assert!(request(required_addr, "/leaf", false).is_err());
+
+    // certificates rejected by the verify callback are not passed to the app
+    let res = request(requested_addr, "/leaf", true).unwrap();
+    assert!(res.starts_with(b"HTTP/1.1 403 Forbidden"));
+    let res = request(requested_addr, "/guarded", true).unwrap();
+    assert!(res.starts_with(b"HTTP/1.1 404 Not Found"));
+
+    let res = request(requested_addr, "/leaf", false).unwrap();
+    assert!(res.starts_with(b"HTTP/1.1 403 Forbidden"));
+
+    // stop
+    srv.stop(false).await;
+
+    thread::sleep(Duration::from_millis(100));
+    sys.stop();
+}
+
+#[actix_rt::test]
+#[cfg(feature = "rustls")]
+async fn test_rustls_client_cert() {
+    use std::{
+        io::{self, Read, Write},
+        sync::Arc,
+    };
+
+    use actix_web::tls::{ClientCertAuth, PeerCertificates};
+    use rustls::{
+        Certificate, ClientConfig, ClientSession, NoClientAuth, PrivateKey, RootCertStore,
+        ServerConfig, StreamOwned,
+    };
+
+    let (ca, client) = client_ca();
+    let client_cert = Certificate(client.serialize_der_with_signer(&ca).unwrap());
+    let client_key = PrivateKey(client.serialize_private_key_der());
+
+    let server = rcgen::generate_simple_self_signed(vec!["localhost".to_owned()]).unwrap();
+    let server_cert = Certificate(server.serialize_der().unwrap());
+    let server_key = PrivateKey(server.serialize_private_key_der());
+
+    let mut roots = RootCertStore::empty();
+    roots
+        .add(&Certificate(ca.serialize_der().unwrap()))
+        .unwrap();
+
+    let mut config = ServerConfig::new(NoClientAuth::new());
+    config
+        .set_single_cert(vec![server_cert.clone()], server_key)
+        .unwrap();
+
+    let required_addr = test::unused_addr();
+    let requested_addr = test::unused_addr();
+    let (tx, rx) = mpsc::channel();
+
+    thread::spawn(move || {
+        let sys = actix_rt::System::new();
+
+        let srv = HttpServer::new(|| {
+            App::new()
+                .route(
+                    "/leaf",
+                    web::get().to(|cert: web::ClientCert| {
+                        HttpResponse::Ok().body(cert.leaf().to_vec())
+                    }),
+                )
+                .route(
+                    "/verified",
+                    web::get().to(|req: actix_web::HttpRequest| {
+                        let verified = req
+                            .conn_data::<PeerCertificates>()
+                            .map(PeerCertificates::is_verified);
+                        HttpResponse::Ok().body(format!("{:?}", verified))
+                    }),
+                )
+        })
+        .workers(1)
+        .shutdown_timeout(1)
+        .system_exit()
+        .disable_signals()
+        .bind_rustls_with_client_auth(
+            format!("{}", required_addr),
+            config.clone(),
+            roots.clone(),
+            ClientCertAuth::require(),
+        )
+        .unwrap()
+        .bind_rustls_with_client_auth(
+            format!("{}", requested_addr),
+            config,
+            roots,
+            ClientCertAuth::request().verify(|_| false),
+        )
+        .unwrap();
+
+        sys.block_on(async {
+            let srv = srv.run();
+            let _ = tx.send((srv, actix_rt::System::current()));
+        });
+
+        let _ = sys.run();
+    });
+    let (srv, sys) = rx.recv().unwrap();
+
+    let request = |addr, path: &str, with_cert: bool| {
+        let mut config = ClientConfig::new();
+        config.root_store.add(&server_cert).unwrap();
+        if with_cert {
+            config
+                .set_single_client_cert(vec![client_cert.clone()], client_key.clone())
+                .unwrap();
+        }
+
+        let name = webpki::DNSNameRef::try_from_ascii_str("localhost").unwrap();
+        let session = ClientSession::new(&Arc::new(config), name);
+        let stream = std::net::TcpStream::connect(addr)?;
+        let mut stream = StreamOwned::new(session, stream);
+
+        write!(stream, "GET {} HTTP/1.1\r\nconnection: close\r\n\r\n", path)?;
+        let mut res = Vec::new();
+        match stream.read_to_end(&mut res) {
+            // the server closes the connection without a close_notify alert
+            Err(err) if err.kind() == io::ErrorKind::ConnectionAborted && !res.is_empty() => {}
+            Err(err) => return Err(err),
+            Ok(_) => {}
+        }
+        Ok::<_, io::Error>(res)
+    };
+
+    let res = request(required_addr, "/leaf", true).unwrap();
+    assert!(res.starts_with(b"HTTP/1.1 200 OK"));
+    assert!(res.ends_with(&client_cert.0));
+
+    // connections without a certificate are rejected during the handshake
+    assert!(request(required_addr, "/leaf", false).is_err());
+
+    let res = request(requested_addr, "/leaf", true).unwrap();
+    assert!(res.starts_with(b"HTTP/1.1 403 Forbidden"));
+    let res = request(requested_addr, "/verified", true).unwrap();
+    assert!(res.ends_with(b"Some(false)"));
+    let res = request(requested_addr, "/verified", false).unwrap();
+    assert!(res.ends_with(b"None"));
+
+    // stop
+    srv.stop(false).await;
+
+    thread::sleep(Duration::from_millis(100));
+    sys.stop();
+}
+
+#[actix_rt::test]
+async fn test_health_check() {
+    let addr = test::unused_addr();
+    let (tx, rx) = mpsc::channel();
+
+    let health = web::HealthCheck::new();
+    let shutdown = actix_web::dev::GracefulShutdown::new();
+
+    {
+        let health = health.clone();
+        let shutdown = shutdown.clone();
+
+        thread::spawn(move || {
+            let sys = actix_rt::System::new();
+
+            sys.block_on(async {
+                let srv = HttpServer::new({
+                    let health = health.clone();
+
+                    move || {
+                        App::new()
+                            .data_factory(|| async {
+                                actix_rt::time::sleep(Duration::from_millis(50)).await;
+                                Ok::<_, ()>(42usize)
+                            })
+                            .service(health.routes())
+                            .route(
+                                "/",
+                                web::get().to(|req: actix_web::HttpRequest| {
+                                    let registered =
+                                        req.app_data::<web::HealthCheck>().is_some();
+                                    HttpResponse::Ok().body(registered.to_string())
+                                }),
+                            )
+                    }
+                })
+                .workers(2)
+                .health_check(health)
+                .graceful_shutdown(shutdown)
+                .disable_signals()
+                .bind(format!("{}", addr))
+                .unwrap()
+                .run();
+
+                let _ = tx.send((srv, actix_rt::System::current()));
+            });
+
+            let _ = sys.run();
+        });
+    }
+
+    let (srv, sys) = rx.recv().unwrap();
+
+    for _ in 0..100 {
+        if health.ready() {
+            break;
+        }
+        actix_rt::time::sleep(Duration::from_millis(10)).await;
+    }
+    assert!(health.live());
+    assert!(health.ready());
+
+    let client = awc::Client::default();
+    let url = |path: &str| format!("http://{}{}", addr, path);
+
+    let mut res = client.get(url("/")).send().await.unwrap();
+    assert_eq!(res.body().await.unwrap(), "true");
+
+    let res = client.get(url("/livez")).send().await.unwrap();
+    assert_eq!(res.status().as_u16(), 200);
+    let res = client.get(url("/readyz")).send().await.unwrap();
+    assert_eq!(res.status().as_u16(), 200);
+
+    health.set_ready(false);
+    let res = client.get(url("/readyz")).send().await.unwrap();
+    assert_eq!(res.status().as_u16(), 503);
+    health.set_ready(true);
+
+    shutdown.shutdown();
+
+    let res = client.get(url("/readyz")).send().await.unwrap();
+    assert_eq!(res.status().as_u16(), 503);
+    let res = client.get(url("/livez")).send().await.unwrap();
+    assert_eq!(res.status().as_u16(), 200);
+
+    srv.stop(false).await;
+
+    thread::sleep(Duration::from_millis(100));
+    sys.stop();
+}
+
+#[actix_rt::test]
+async fn test_worker_hooks() {
+    use std::cell::Cell;
+    use std::rc::Rc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    use actix_web::dev::{Extensions, GracefulShutdown};
+
+    struct Counter(Cell<usize>);
+
+    let addr = test::unused_addr();
+    let (tx, rx) = mpsc::channel();
+
+    let shutdown = GracefulShutdown::new();
+    let started = Arc::new(AtomicUsize::new(0));
+    let flushed = Arc::new(AtomicUsize::new(0));
+    let stopped = Arc::new(AtomicUsize::new(0));
+
+    {
+        let shutdown = shutdown.clone();
+        let started = Arc::clone(&started);
+        let flushed = Arc::clone(&flushed);
+        let stopped = Arc::clone(&stopped);
+
+        thread::spawn(move || {
+            let sys = actix_rt::System::new();
+
+            sys.block_on(async {
+                let srv = HttpServer::new(|| {
+                    App::new()
+                        .data_factory(|| async { Ok::<_, ()>(Counter(Cell::new(0))) })
+                        .route(
+                            "/slow",
+                            web::get().to(|counter: web::Data<Counter>| async move {
+                                actix_rt::time::sleep(Duration::from_millis(200)).await;
+                                counter.0.set(counter.0.get() + 1);
+                                Ok::<_, Error>(HttpResponse::Ok().finish())
+                            }),
+                        )
+                })
+                .workers(3)
+                .on_worker_start(move |data: Rc<Extensions>| {
+                    let counter = data.get::<web::Data<Counter>>().unwrap().clone();
+                    let started = Arc::clone(&started);
+
+                    async move {
+                        counter.0.set(counter.0.get() + 1);
+                        started.fetch_add(1, Ordering::SeqCst);
+                    }
+                })
+                .on_worker_stop(move |data: Rc<Extensions>| {
+                    let counter = data.get::<web::Data<Counter>>().unwrap().clone();
+                    let flushed = Arc::clone(&flushed);
+                    let stopped = Arc::clone(&stopped);
+
+                    async move {
+                        actix_rt::time::sleep(Duration::from_millis(10)).await;
+                        flushed.fetch_add(counter.0.get(), Ordering::SeqCst);
+                        stopped.fetch_add(1, Ordering::SeqCst);
+                    }
+                })
+                .graceful_shutdown(shutdown)
+                .disable_signals()
+                .bind(format!("{}", addr))
+                .unwrap()
+                .run();
+
+                let _ = tx.send((srv, actix_rt::System::current()));
+            });
+
+            let _ = sys.run();
+        });
+    }
+
+    let (srv, sys) = rx.recv().unwrap();
+
+    let client = awc::Client::default();
+    let res = client
+        .get(format!("http://{}/slow", addr))
+        .send()
+        .await
+        .unwrap();
+    assert!(res.status().is_success());
+    assert_eq!(started.load(Ordering::SeqCst), 3);
+
+    // shutdown starts while a request is in flight
+    let req = client.get(format!("http://{}/slow", addr)).send();
+    let shutdown2 = shutdown.clone();
+    let (res, _) = futures_util::future::join(req, async move {
+        actix_rt::time::sleep(Duration::from_millis(50)).await;
+        shutdown2.shutdown();
+    })
+    .await;
+    assert!(res.unwrap().status().is_success());
+
+    for _ in 0..100 {
+        if stopped.load(Ordering::SeqCst) == 3 {
+            break;
+        }
+        actix_rt::time::sleep(Duration::from_millis(10)).await;
+    }
+    assert_eq!(stopped.load(Ordering::SeqCst), 3);
+    assert_eq!(flushed.load(Ordering::SeqCst), 3 + 2);
+
+    // server stops once the stop hooks of all workers have finished
+    let mut closed = false;
+    for _ in 0..300 {
+        if std::net::TcpStream::connect(addr).is_err() {
+            closed = true;
+            break;
+        }
+        actix_rt::time::sleep(Duration::from_millis(10)).await;
+    }
+    assert!(closed);
+
+    drop(srv);
+    sys.stop();
+}
+
+#[cfg(target_os = "linux")]
+#[actix_rt::test]
+async fn test_worker_threads() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    let addr = test::unused_addr();
+    let (tx, rx) = mpsc::channel();
+
+    let workers = std::cmp::max((num_cpus::get() as f32 * 0.5).round() as usize, 1);
+    let started = Arc::new(AtomicUsize::new(0));
+
+    {
+        let started = Arc::clone(&started);
+
+        thread::spawn(move || {
+            let sys = actix_rt::System::new();
+
+            sys.block_on(async {
+                let srv = HttpServer::new(|| {
+                    App::new().route(
+                        "/",
+                        web::get().to(|| {
+                            let name =
+                                std::fs::read_to_string("/proc/thread-self/comm").unwrap();
+                            let status =
+                                std::fs::read_to_string("/proc/thread-self/status").unwrap();
+                            let cpus = status
+                                .lines()
+                                .find(|line| line.starts_with("Cpus_allowed_list:"))
+                                .unwrap()
+                                .to_owned();
+
+                            HttpResponse::Ok().body(format!("{}\n{}", name.trim(), cpus))
+                        }),
+                    )
+                })
+                .workers_per_core(0.5)
+                .worker_name_prefix("web-worker-")
+                .worker_affinity(true)
+                .on_worker_start(move |_| {
+                    started.fetch_add(1, Ordering::SeqCst);
+                    async {}
+                })
+                .disable_signals()
+                .bind(format!("{}", addr))
+                .unwrap()
+                .run();
+
+                let _ = tx.send((srv, actix_rt::System::current()));
+            });
+
+            let _ = sys.run();
+        });
+    }
+
+    let (srv, sys) = rx.recv().unwrap();
+
+    let client = awc::Client::default();
+    let mut res = client
+        .get(format!("http://{}/", addr))
+        .send()
+        .await
+        .unwrap();
+    assert!(res.status().is_success());
+    assert_eq!(started.load(Ordering::SeqCst), workers);
+
+    let body = res.body().await.unwrap();
+    let body = std::str::from_utf8(&body).unwrap();
+    let mut lines = body.lines();
+    assert!(lines.next().unwrap().starts_with("web-worker-"));
+
+    // pinned to a single CPU
+    let cpus = lines.next().unwrap()["Cpus_allowed_list:".len()..].trim();
+    assert!(cpus.parse::<usize>().is_ok(), "{}", cpus);
+
+    srv.stop(false).await;
+    thread::sleep(Duration::from_millis(100));
+    sys.stop();
+}
+
+#[actix_rt::test]
+async fn test_handle_stop_from_handler() {
+    use actix_web::{dev::ServerHandle, HttpRequest};
+
+    let addr = test::unused_addr();
+    let (tx, rx) = mpsc::channel();
+    let (stopped_tx, stopped_rx) = mpsc::channel();
+
+    thread::spawn(move || {
+        let sys = actix_rt::System::new();
+
+        sys.block_on(async {
+            let srv = HttpServer::new(|| {
+                App::new().route(
+                    "/stop",
+                    web::post().to(|req: HttpRequest| {
+                        let handle = req.app_data::<ServerHandle>().unwrap().clone();
+                        actix_rt::spawn(async move { handle.stop(true).await });
+                        HttpResponse::Ok()
+                    }),
+                )
+            })
+            .workers(1)
+            .disable_signals()
+            .bind(format!("{}", addr))
+            .unwrap()
+            .run();
+
+            let _ = tx.send(());
+            let _ = srv.await;
+            let _ = stopped_tx.send(());
+        });
+    });
+
+    rx.recv().unwrap();
+
+    let client = awc::Client::default();
+    let res = client
+        .post(format!("http://{}/stop", addr))
+        .force_close()
+        .send()
+        .await
+        .unwrap();
+    assert!(res.status().is_success());
+
+    // `run` future resolves
+    stopped_rx.recv_timeout(Duration::from_secs(5)).unwrap();
+}
+
+#[actix_rt::test]
+async fn test_handle_stop_with_timeout() {
+    use std::io::{Read, Write};
+    use std::time::Instant;
+
+    let addr = test::unused_addr();
+    let (tx, rx) = mpsc::channel();
+    let (stopped_tx, stopped_rx) = mpsc::channel();
+
+    thread::spawn(move || {
+        let sys = actix_rt::System::new();
+
+        sys.block_on(async {
+            let srv = HttpServer::new(|| {
+                App::new().route(
+                    "/slow",
+                    web::get().to(|| async {
+                        actix_rt::time::sleep(Duration::from_secs(60)).await;
+                        Ok::<_, Error>(HttpResponse::Ok().finish())
+                    }),
+                )
+            })
+            .workers(1)
+            .shutdown_timeout(60)
+            .disable_signals()
+            .bind(format!("{}", addr))
+            .unwrap();
+
+            let _ = tx.send(srv.handle());
+
+            let _ = srv.run().await;
+            let _ = stopped_tx.send(());
+        });
+    });
+
+    let handle = rx.recv().unwrap();
+
+    let mut stream = std::net::TcpStream::connect(addr).unwrap();
+    stream
+        .write_all(b"GET /slow HTTP/1.1\r\nHost: localhost\r\n\r\n")
+        .unwrap();
+    actix_rt::time::sleep(Duration::from_millis(200)).await;
+
+    let start = Instant::now();
+    handle.stop_with_timeout(Duration::from_millis(300)).await;
+    assert!(start.elapsed() < Duration::from_secs(5));
+
+    stopped_rx.recv_timeout(Duration::from_secs(5)).unwrap();
+    handle.await_stopped().await;
+
+    // the slow request was cut off without a response
+    stream
+        .set_read_timeout(Some(Duration::from_secs(5)))
+        .unwrap();
+    let mut buf = Vec::new();
+    let _ = stream.read_to_end(&mut buf);
+    assert!(buf.is_empty());
+}
+
+#[actix_rt::test]
+async fn test_bound_addrs() {
+    let srv = HttpServer::new(App::new)
+        .bind("127.0.0.1:0")
+        .unwrap()
+        .bind("127.0.0.1:0")
+        .unwrap();
+
+    let addrs = srv.bound_addrs();
+    assert_eq!(addrs.len(), 2);
+    assert_ne!(addrs[0].port(), 0);
+    assert_ne!(addrs[1].port(), 0);
+    assert_ne!(addrs[0], addrs[1]);
+
+    // one of the addresses is taken; nothing is bound
+    let taken = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let taken_addr = taken.local_addr().unwrap();
+    let err = HttpServer::new(App::new)
+        .bind_all(vec!["127.0.0.1:0".to_owned(), taken_addr.to_string()])
+        .err()
+        .unwrap();
+    assert!(err.to_string().contains(&taken_addr.to_string()));
+
+    let srv = HttpServer::new(App::new)
+        .bind_all(&["127.0.0.1:0", "127.0.0.1:0"])
+        .unwrap();
+    assert_eq!(srv.bound_addrs().len(), 2);
+}
+
+#[actix_rt::test]
+async fn test_try_configure_error() {
+    let addr = test::unused_addr();
+    let (tx, rx) = mpsc::channel();
+
+    thread::spawn(move || {
+        let sys = actix_rt::System::new();
+
+        sys.block_on(async {
+            let srv = HttpServer::new(|| {
+                App::new().try_configure(|cfg| {
+                    cfg.route("/", web::get().to(HttpResponse::Ok));
+                    Err(std::io::Error::new(
+                        std::io::ErrorKind::NotFound,
+                        "missing setting",
+                    ))
+                })
+            })
+            .workers(1)
+            .disable_signals()
+            .bind(format!("{}", addr))
+            .unwrap()
+            .run();
+
+            let _ = tx.send((srv, actix_rt::System::current()));
+        });
+
+        let _ = sys.run();
+    });
+    let (srv, sys) = rx.recv().unwrap();
+
+    // the worker fails to initialize the app so requests are never served
+    let res = awc::Client::builder()
+        .timeout(Duration::from_millis(500))
+        .finish()
+        .get(format!("http://{}/", addr))
+        .send()
+        .await;
+    assert!(res.is_err());
+
+    srv.stop(false).await;
+
+    thread::sleep(Duration::from_millis(100));
+    sys.stop();
+}
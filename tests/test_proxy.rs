@@ -0,0 +1,101 @@
+use actix_web::{
+    http::{header, Uri},
+    test, web, App, Error, HttpRequest, HttpResponse,
+};
+use bytes::Bytes;
+use futures_util::stream;
+
+/// Echoes the request body and the headers a proxy is expected to touch.
+async fn echo(req: HttpRequest, body: Bytes) -> HttpResponse {
+    let mut res = HttpResponse::Ok();
+
+    for name in &[
+        "x-forwarded-for",
+        "forwarded",
+        "proxy-connection",
+        "x-custom",
+    ] {
+        if let Some(val) = req.headers().get(*name) {
+            res.insert_header((format!("x-echo-{}", name), val.clone()));
+        }
+    }
+
+    res.insert_header(("keep-alive", "timeout=5"))
+        .insert_header(("x-upstream", "1"))
+        .body(body)
+}
+
+async fn proxy(
+    req: HttpRequest,
+    payload: web::Payload,
+    client: web::Data<awc::Client>,
+    upstream: web::Data<Uri>,
+) -> Result<HttpResponse, Error> {
+    web::proxy::forward(req, payload, &client, upstream.get_ref().clone()).await
+}
+
+#[actix_rt::test]
+async fn test_forward() {
+    let upstream = test::start(|| App::new().default_service(web::to(echo)));
+    let upstream_uri: Uri = upstream.url("/").parse().unwrap();
+
+    let srv = test::start(move || {
+        App::new()
+            .app_data(web::Data::new(awc::Client::new()))
+            .app_data(web::Data::new(upstream_uri.clone()))
+            .default_service(web::to(proxy))
+    });
+
+    // sized body
+    let mut res = srv
+        .post("/echo?q=1")
+        .insert_header(("x-custom", "abc"))
+        .insert_header(("x-forwarded-for", "10.0.0.1"))
+        .insert_header(("proxy-connection", "keep-alive"))
+        .send_body("hello world")
+        .await
+        .unwrap();
+    assert!(res.status().is_success());
+
+    let headers = res.headers();
+    assert_eq!(headers.get("x-echo-x-custom").unwrap(), "abc");
+    assert_eq!(
+        headers.get("x-echo-x-forwarded-for").unwrap(),
+        "10.0.0.1, 127.0.0.1"
+    );
+    assert!(headers
+        .get("x-echo-forwarded")
+        .unwrap()
+        .to_str()
+        .unwrap()
+        .starts_with("for=127.0.0.1;proto=http;host="));
+    assert!(!headers.contains_key("x-echo-proxy-connection"));
+    assert_eq!(headers.get("x-upstream").unwrap(), "1");
+    assert!(!headers.contains_key("keep-alive"));
+    assert_eq!(headers.get(header::CONTENT_LENGTH).unwrap(), "11");
+    assert_eq!(res.body().await.unwrap(), "hello world");
+
+    // streamed body
+    let chunks = stream::iter(vec![
+        Ok::<_, Error>(Bytes::from_static(b"hello ")),
+        Ok(Bytes::from_static(b"streamed ")),
+        Ok(Bytes::from_static(b"world")),
+    ]);
+    let mut res = srv.post("/").send_stream(chunks).await.unwrap();
+    assert!(res.status().is_success());
+    assert_eq!(res.body().await.unwrap(), "hello streamed world");
+}
+
+#[actix_rt::test]
+async fn test_forward_unreachable() {
+    // nothing listens on the discard port
+    let srv = test::start(|| {
+        App::new()
+            .app_data(web::Data::new(awc::Client::new()))
+            .app_data(web::Data::new(Uri::from_static("http://127.0.0.1:9")))
+            .default_service(web::to(proxy))
+    });
+
+    let res = srv.get("/").send().await.unwrap();
+    assert_eq!(res.status(), 502);
+}
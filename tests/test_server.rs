@@ -152,6 +152,45 @@ async fn test_body_gzip() {
     assert_eq!(Bytes::from(dec), Bytes::from_static(STR.as_ref()));
 }
 
+#[actix_rt::test]
+async fn test_vary_accumulation() {
+    use actix_http::http::header::{HeaderValue, VaryHeaders, ORIGIN, VARY};
+    use actix_service::Service;
+
+    let srv = test::start_with(test::config().h1(), || {
+        App::new()
+            .wrap(Compress::new(ContentEncoding::Gzip))
+            .wrap_fn(|req, srv| {
+                let fut = srv.call(req);
+                async move {
+                    let mut res = fut.await?;
+                    res.response_mut()
+                        .extensions_mut()
+                        .get_or_insert_with(VaryHeaders::new)
+                        .add(ORIGIN);
+                    Ok(res)
+                }
+            })
+            .service(web::resource("/").route(web::to(|| {
+                HttpResponse::Ok()
+                    .insert_header((VARY, HeaderValue::from_static("Accept-Encoding")))
+                    .body(STR)
+            })))
+    });
+
+    let response = srv
+        .get("/")
+        .no_decompress()
+        .append_header((ACCEPT_ENCODING, "gzip"))
+        .send()
+        .await
+        .unwrap();
+    assert!(response.status().is_success());
+
+    let vary = response.headers().get_all(VARY).collect::<Vec<_>>();
+    assert_eq!(vary, [HeaderValue::from_static("Accept-Encoding, origin")]);
+}
+
 #[actix_rt::test]
 async fn test_body_gzip2() {
     let srv = test::start_with(test::config().h1(), || {
@@ -401,6 +440,28 @@ async fn test_head_binary() {
     assert!(bytes.is_empty());
 }
 
+#[actix_rt::test]
+async fn test_head_from_get() {
+    let srv = test::start_with(test::config().h1(), || {
+        App::new().service(web::resource("/").route(
+            web::get().to(move || HttpResponse::Ok().insert_header(("x-get", "1")).body(STR)),
+        ))
+    });
+
+    let mut response = srv.head("/").send().await.unwrap();
+    assert!(response.status().is_success());
+    assert_eq!(response.headers().get("x-get").unwrap(), "1");
+
+    {
+        let len = response.headers().get(CONTENT_LENGTH).unwrap();
+        assert_eq!(format!("{}", STR.len()), len.to_str().unwrap());
+    }
+
+    // read response
+    let bytes = response.body().await.unwrap();
+    assert!(bytes.is_empty());
+}
+
 #[actix_rt::test]
 async fn test_no_chunking() {
     let srv = test::start_with(test::config().h1(), || {
@@ -910,3 +971,71 @@ async fn test_normalize() {
     let response = srv.get("/one/").send().await.unwrap();
     assert!(response.status().is_success());
 }
+
+#[actix_rt::test]
+async fn test_informational_responses() {
+    use std::{net, time::Duration};
+
+    use actix_web::{
+        http::{header, HeaderMap, StatusCode},
+        HttpRequest,
+    };
+
+    let srv = test::start_with(test::config().h1(), || {
+        App::new().service(web::resource("/").to(|req: HttpRequest| async move {
+            let early_hints = StatusCode::from_u16(103).unwrap();
+
+            let mut hints = HeaderMap::new();
+            hints.insert(
+                header::LINK,
+                header::HeaderValue::from_static("</style.css>; rel=preload"),
+            );
+            let sent = req.send_informational(early_hints, hints.clone());
+
+            actix_rt::time::sleep(Duration::from_millis(200)).await;
+
+            hints.insert(
+                header::LINK,
+                header::HeaderValue::from_static("</script.js>; rel=preload"),
+            );
+            req.send_informational(early_hints, hints);
+
+            HttpResponse::Ok().body(format!("sent: {}", sent))
+        }))
+    });
+
+    let mut stream = net::TcpStream::connect(srv.addr()).unwrap();
+    stream
+        .write_all(b"GET / HTTP/1.1\r\nConnection: close\r\n\r\n")
+        .unwrap();
+
+    // the first interim response is written while the handler is still running
+    let mut buf = [0; 1024];
+    let n = stream.read(&mut buf).unwrap();
+    let first = String::from_utf8_lossy(&buf[..n]).into_owned();
+    assert_eq!(
+        first,
+        "HTTP/1.1 103 Early Hints\r\nlink: </style.css>; rel=preload\r\n\r\n"
+    );
+
+    let mut rest = String::new();
+    stream.read_to_string(&mut rest).unwrap();
+    assert!(rest.starts_with(
+        "HTTP/1.1 103 Early Hints\r\nlink: </script.js>; rel=preload\r\n\r\nHTTP/1.1 200 OK\r\n"
+    ));
+    assert!(rest.ends_with("\r\n\r\nsent: true"));
+
+    // HTTP/1.0 clients only get the final response
+    let mut stream = net::TcpStream::connect(srv.addr()).unwrap();
+    stream.write_all(b"GET / HTTP/1.0\r\n\r\n").unwrap();
+    let mut data = String::new();
+    stream.read_to_string(&mut data).unwrap();
+    assert!(data.starts_with("HTTP/1.0 200 OK\r\n"));
+    assert!(!data.contains("103"));
+    assert!(data.ends_with("\r\n\r\nsent: false"));
+
+    // the client skips interim responses
+    let mut res = srv.get("/").send().await.unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+    assert_eq!(res.body().await.unwrap(), Bytes::from_static(b"sent: true"));
+}
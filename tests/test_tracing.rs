@@ -0,0 +1,209 @@
+extern crate tracing_rs as tracing;
+
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    fmt,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+};
+
+use actix_web::{middleware::TracingLogger, test, web, App, HttpResponse};
+use tracing::{
+    field::{Field, Visit},
+    span::{Attributes, Id, Record},
+    Event, Metadata, Subscriber,
+};
+
+#[derive(Debug, Clone)]
+struct SpanData {
+    name: &'static str,
+    parent: Option<u64>,
+    fields: HashMap<&'static str, String>,
+}
+
+/// Subscriber capturing the spans of all threads.
+#[derive(Clone, Default)]
+struct Capture {
+    next_id: Arc<AtomicU64>,
+    spans: Arc<Mutex<HashMap<u64, SpanData>>>,
+}
+
+thread_local! {
+    static ENTERED: RefCell<Vec<u64>> = RefCell::new(Vec::new());
+}
+
+struct FieldVisitor<'a>(&'a mut HashMap<&'static str, String>);
+
+impl Visit for FieldVisitor<'_> {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.0.insert(field.name(), value.to_owned());
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.0.insert(field.name(), format!("{:?}", value));
+    }
+}
+
+impl Capture {
+    fn spans(&self) -> Vec<(u64, SpanData)> {
+        let mut spans = self
+            .spans
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(id, span)| (*id, span.clone()))
+            .collect::<Vec<_>>();
+        spans.sort_by_key(|(id, _)| *id);
+        spans
+    }
+
+    fn named(&self, name: &str) -> Vec<(u64, SpanData)> {
+        self.spans()
+            .into_iter()
+            .filter(|(_, span)| span.name == name)
+            .collect()
+    }
+
+    fn get(&self, id: u64) -> SpanData {
+        self.spans.lock().unwrap()[&id].clone()
+    }
+}
+
+impl Subscriber for Capture {
+    fn enabled(&self, _: &Metadata<'_>) -> bool {
+        true
+    }
+
+    fn new_span(&self, attrs: &Attributes<'_>) -> Id {
+        let id = self.next_id.fetch_add(1, Ordering::SeqCst) + 1;
+
+        let parent = if let Some(parent) = attrs.parent() {
+            Some(parent.into_u64())
+        } else if attrs.is_contextual() {
+            ENTERED.with(|entered| entered.borrow().last().copied())
+        } else {
+            None
+        };
+
+        let mut fields = HashMap::new();
+        attrs.record(&mut FieldVisitor(&mut fields));
+
+        self.spans.lock().unwrap().insert(
+            id,
+            SpanData {
+                name: attrs.metadata().name(),
+                parent,
+                fields,
+            },
+        );
+
+        Id::from_u64(id)
+    }
+
+    fn record(&self, span: &Id, values: &Record<'_>) {
+        let mut spans = self.spans.lock().unwrap();
+        let span = spans.get_mut(&span.into_u64()).unwrap();
+        values.record(&mut FieldVisitor(&mut span.fields));
+    }
+
+    fn record_follows_from(&self, _: &Id, _: &Id) {}
+
+    fn event(&self, _: &Event<'_>) {}
+
+    fn enter(&self, span: &Id) {
+        ENTERED.with(|entered| entered.borrow_mut().push(span.into_u64()));
+    }
+
+    fn exit(&self, span: &Id) {
+        ENTERED.with(|entered| {
+            let mut entered = entered.borrow_mut();
+            if let Some(pos) = entered.iter().rposition(|id| *id == span.into_u64()) {
+                entered.remove(pos);
+            }
+        });
+    }
+}
+
+#[actix_rt::test]
+async fn test_tracing_spans() {
+    let capture = Capture::default();
+    tracing::subscriber::set_global_default(capture.clone()).unwrap();
+
+    let srv = test::start(|| {
+        App::new()
+            .service(
+                web::scope("/custom")
+                    .wrap(TracingLogger::new(|req| {
+                        tracing::info_span!(
+                            "custom.request",
+                            path = req.path(),
+                            status = tracing::field::Empty,
+                        )
+                    }))
+                    .route("/{id}", web::get().to(HttpResponse::NoContent)),
+            )
+            .wrap(TracingLogger::default())
+            .route("/users/{id}", web::get().to(HttpResponse::Ok))
+    });
+
+    let res = srv
+        .get("/users/1")
+        .insert_header((
+            "traceparent",
+            "00-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-01",
+        ))
+        .send()
+        .await
+        .unwrap();
+    assert!(res.status().is_success());
+
+    let requests = capture.named("http.request");
+    assert_eq!(requests.len(), 1);
+    let request = &requests[0].1;
+    assert_eq!(request.fields["method"], "GET");
+    assert_eq!(request.fields["route"], "/users/{id}");
+    assert_eq!(request.fields["status"], "200");
+    assert!(request.fields["latency_ms"].parse::<f64>().unwrap() >= 0.0);
+    assert_eq!(
+        request.fields["trace_id"],
+        "0af7651916cd43dd8448eb211c80319c"
+    );
+    assert_eq!(request.fields["parent_id"], "b7ad6b7169203331");
+
+    // request spans are children of the connection span
+    let connection = capture.get(request.parent.expect("request span has no parent"));
+    assert_eq!(connection.name, "http.connection");
+    assert_eq!(connection.fields["protocol"], "h1");
+    assert!(connection.fields.contains_key("peer_addr"));
+    assert_eq!(connection.parent, None);
+
+    let conn_id = requests[0].1.parent;
+    for phase in &["http.read", "http.write"] {
+        assert!(
+            capture
+                .named(phase)
+                .iter()
+                .any(|(_, span)| span.parent == conn_id),
+            "no {} span in connection",
+            phase
+        );
+    }
+
+    // custom spans, nested in the default span of the app
+    let res = srv.get("/custom/1").send().await.unwrap();
+    assert_eq!(res.status().as_u16(), 204);
+
+    let custom = capture.named("custom.request");
+    assert_eq!(custom.len(), 1);
+    let custom = &custom[0].1;
+    assert_eq!(custom.fields["path"], "/custom/1");
+    assert_eq!(custom.fields["status"], "204");
+    assert!(!custom.fields.contains_key("route"));
+
+    let parent = capture.get(custom.parent.unwrap());
+    assert_eq!(parent.name, "http.request");
+    assert_eq!(parent.fields["route"], "/custom/{id}");
+}
@@ -0,0 +1,114 @@
+use std::time::Instant;
+
+use actix_web::{test, web, App, Error, HttpRequest, HttpResponse};
+use awc::ws::{CloseCode, Frame, Message};
+use bytes::Bytes;
+use futures_util::{SinkExt as _, StreamExt as _};
+
+async fn echo(req: HttpRequest, body: web::Payload) -> Result<HttpResponse, Error> {
+    let (res, session, mut messages) = web::ws::handle(&req, body)?;
+
+    actix_rt::spawn(async move {
+        while let Some(Ok(msg)) = messages.next().await {
+            let res = match msg {
+                Message::Text(text) => session.text(text).await,
+                Message::Binary(bin) => session.binary(bin).await,
+                Message::Ping(bytes) => session.pong(&bytes).await,
+                Message::Close(reason) => {
+                    let _ = session.close(reason).await;
+                    break;
+                }
+                _ => Ok(()),
+            };
+
+            if res.is_err() {
+                break;
+            }
+        }
+    });
+
+    Ok(res)
+}
+
+async fn goodbye(req: HttpRequest, body: web::Payload) -> Result<HttpResponse, Error> {
+    let (res, session, mut messages) = web::ws::handle(&req, body)?;
+
+    actix_rt::spawn(async move {
+        session.text("bye").await.unwrap();
+        session.close(Some(CloseCode::Normal.into())).await.unwrap();
+
+        // drive the message stream to notice the peer's close frame
+        while messages.next().await.is_some() {}
+    });
+
+    Ok(res)
+}
+
+#[actix_rt::test]
+async fn test_echo() {
+    let mut srv = test::start(|| App::new().route("/echo", web::get().to(echo)));
+    let mut framed = srv.ws_at("/echo").await.unwrap();
+
+    framed.send(Message::Text("text".into())).await.unwrap();
+    let item = framed.next().await.unwrap().unwrap();
+    assert_eq!(item, Frame::Text(Bytes::from_static(b"text")));
+
+    framed
+        .send(Message::Binary(Bytes::from_static(b"binary")))
+        .await
+        .unwrap();
+    let item = framed.next().await.unwrap().unwrap();
+    assert_eq!(item, Frame::Binary(Bytes::from_static(b"binary")));
+
+    framed
+        .send(Message::Ping(Bytes::from_static(b"ping")))
+        .await
+        .unwrap();
+    let item = framed.next().await.unwrap().unwrap();
+    assert_eq!(item, Frame::Pong(Bytes::from_static(b"ping")));
+
+    // client initiated close is answered and the connection is closed
+    framed
+        .send(Message::Close(Some(CloseCode::Normal.into())))
+        .await
+        .unwrap();
+    let item = framed.next().await.unwrap().unwrap();
+    assert_eq!(item, Frame::Close(Some(CloseCode::Normal.into())));
+    assert!(framed.next().await.is_none());
+}
+
+#[actix_rt::test]
+async fn test_server_close() {
+    let mut srv = test::start(|| App::new().route("/goodbye", web::get().to(goodbye)));
+    let mut framed = srv.ws_at("/goodbye").await.unwrap();
+
+    let item = framed.next().await.unwrap().unwrap();
+    assert_eq!(item, Frame::Text(Bytes::from_static(b"bye")));
+    let item = framed.next().await.unwrap().unwrap();
+    assert_eq!(item, Frame::Close(Some(CloseCode::Normal.into())));
+
+    // connection is closed once the close frame is answered
+    let start = Instant::now();
+    framed
+        .send(Message::Close(Some(CloseCode::Normal.into())))
+        .await
+        .unwrap();
+    assert!(framed.next().await.is_none());
+    assert!(start.elapsed() < web::ws::CLOSE_TIMEOUT);
+}
+
+#[actix_rt::test]
+async fn test_server_close_timeout() {
+    let mut srv = test::start(|| App::new().route("/goodbye", web::get().to(goodbye)));
+    let mut framed = srv.ws_at("/goodbye").await.unwrap();
+
+    let start = Instant::now();
+    let item = framed.next().await.unwrap().unwrap();
+    assert_eq!(item, Frame::Text(Bytes::from_static(b"bye")));
+    let item = framed.next().await.unwrap().unwrap();
+    assert_eq!(item, Frame::Close(Some(CloseCode::Normal.into())));
+
+    // without an answer the connection is closed after the timeout
+    assert!(framed.next().await.is_none());
+    assert!(start.elapsed() >= web::ws::CLOSE_TIMEOUT);
+}
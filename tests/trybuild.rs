@@ -0,0 +1,16 @@
+#[test]
+fn compile_handlers() {
+    let t = trybuild::TestCases::new();
+
+    t.pass("tests/trybuild/handler-many-params-ok.rs");
+    compile_fail(&t);
+}
+
+// the messages come from `diagnostic::on_unimplemented`, which older compilers ignore
+#[rustversion::since(1.78)]
+fn compile_fail(t: &trybuild::TestCases) {
+    t.compile_fail("tests/trybuild/handler-invalid-param-fail.rs");
+}
+
+#[rustversion::before(1.78)]
+fn compile_fail(_: &trybuild::TestCases) {}
@@ -0,0 +1,12 @@
+use actix_web::{web, App, HttpResponse};
+
+struct NotAnExtractor;
+
+async fn index(_: web::Bytes, _: NotAnExtractor) -> HttpResponse {
+    HttpResponse::Ok().finish()
+}
+
+#[actix_web::main]
+async fn main() {
+    let _ = App::new().route("/", web::get().to(index));
+}
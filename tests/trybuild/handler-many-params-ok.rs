@@ -0,0 +1,31 @@
+use actix_web::{http::Method, web, App, HttpRequest, HttpResponse};
+
+struct Count;
+
+#[allow(clippy::too_many_arguments)]
+async fn index(
+    req: HttpRequest,
+    _: web::Data<Count>,
+    _: web::Path<(String, u32)>,
+    _: web::Query<Vec<(String, String)>>,
+    _: web::Bytes,
+    _: Option<web::Json<Vec<u32>>>,
+    _: Option<web::Form<Vec<(String, String)>>>,
+    _: Option<web::Data<String>>,
+    _: HttpRequest,
+    _: HttpRequest,
+    _: HttpRequest,
+    _: HttpRequest,
+    _: HttpRequest,
+    _: HttpRequest,
+) -> HttpResponse {
+    assert_eq!(req.method(), Method::GET);
+    HttpResponse::Ok().finish()
+}
+
+#[actix_web::main]
+async fn main() {
+    let _ = App::new()
+        .app_data(web::Data::new(Count))
+        .route("/{name}/{id}", web::get().to(index));
+}